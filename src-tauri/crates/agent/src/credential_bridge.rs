@@ -18,6 +18,7 @@ use proxycast_core::models::provider_pool_model::{
     CredentialData, PoolProviderType, ProviderCredential,
 };
 use proxycast_services::api_key_provider_service::ApiKeyProviderService;
+use proxycast_services::concurrency_limiter::{ConcurrencyPermit, QueueClient};
 use proxycast_services::provider_pool_service::ProviderPoolService;
 use std::sync::Arc;
 
@@ -34,6 +35,8 @@ pub enum CredentialBridgeError {
     TokenRefreshFailed(String),
     /// 数据库错误
     DatabaseError(String),
+    /// 并发排队失败（排队超时或队列已满）
+    QueueRejected(String),
 }
 
 impl std::fmt::Display for CredentialBridgeError {
@@ -44,6 +47,7 @@ impl std::fmt::Display for CredentialBridgeError {
             Self::ProviderCreationFailed(msg) => write!(f, "Provider 创建失败: {msg}"),
             Self::TokenRefreshFailed(msg) => write!(f, "Token 刷新失败: {msg}"),
             Self::DatabaseError(msg) => write!(f, "数据库错误: {msg}"),
+            Self::QueueRejected(msg) => write!(f, "并发排队失败: {msg}"),
         }
     }
 }
@@ -128,6 +132,46 @@ impl CredentialBridge {
             .await
     }
 
+    /// 从凭证池选择凭证、获取并发槽位并创建 Aster Provider 配置
+    ///
+    /// 凭证并发已满时按 `queue_client` 排队等待；返回的槽位需在任务执行期间持有。
+    pub async fn acquire_and_configure(
+        &self,
+        db: &DbConnection,
+        provider_type: &str,
+        model: &str,
+        queue_client: &QueueClient,
+    ) -> Result<(AsterProviderConfig, ConcurrencyPermit), CredentialBridgeError> {
+        let credential = self
+            .pool_service
+            .select_credential_with_fallback(
+                db,
+                &self.api_key_service,
+                provider_type,
+                Some(model),
+                Some(provider_type),
+                None,
+            )
+            .await
+            .map_err(CredentialBridgeError::DatabaseError)?
+            .ok_or_else(|| {
+                CredentialBridgeError::NoCredentials(format!(
+                    "没有找到 {provider_type} 类型的可用凭证"
+                ))
+            })?;
+
+        let permit = self
+            .pool_service
+            .acquire_slot(&credential, queue_client)
+            .await
+            .map_err(|e| CredentialBridgeError::QueueRejected(e.to_string()))?;
+
+        let config = self
+            .credential_to_config(&credential, model, provider_type, db)
+            .await?;
+        Ok((config, permit))
+    }
+
     /// 将 ProxyCast 凭证转换为 Aster Provider 配置
     async fn credential_to_config(
         &self,
@@ -10,9 +10,11 @@ pub mod credential_bridge;
 pub mod event_converter;
 pub mod hooks;
 pub mod lsp_bridge;
+pub mod lsp_client;
 pub mod mcp_bridge;
 pub mod prompt;
 pub mod session_store;
+pub mod shell_parser;
 pub mod shell_security;
 pub mod subagent_scheduler;
 pub mod tool_permissions;
@@ -29,12 +31,12 @@ pub use credential_bridge::{
     create_aster_provider, AsterProviderConfig, CredentialBridge, CredentialBridgeError,
 };
 pub use event_converter::{convert_agent_event, convert_to_tauri_message, TauriAgentEvent};
-pub use lsp_bridge::create_lsp_callback;
+pub use lsp_bridge::{create_lsp_callback, rename_preview, shutdown_lsp_servers, RenamePreview};
 pub use prompt::SystemPromptBuilder;
 pub use session_store::{
     create_session_sync, get_session_sync, list_sessions_sync, SessionDetail, SessionInfo,
 };
-pub use shell_security::ShellSecurityChecker;
+pub use shell_security::{ShellRiskFinding, ShellSecurityChecker, ShellSecurityResult};
 pub use subagent_scheduler::{
     ProxyCastScheduler, ProxyCastSubAgentExecutor, SchedulerEventEmitter, SubAgentProgressEvent,
     SubAgentRole,
@@ -1,18 +1,41 @@
 //! LSP 工具桥接
 //!
-//! 当前实现采用“本机语言服务器可执行探测 + 渐进式降级”策略：
-//! - 优先探测 rust-analyzer / typescript-language-server / pyright-langserver
-//! - 提供基础的同文件语义能力（definition/references/hover/completion/diagnostics）
-//! - 对尚未接入真实 JSON-RPC 流程的操作返回明确错误与下一步指引
+//! 按“语言服务器 + 工作区根目录”维护常驻的 LSP 进程池（通信细节见 [`crate::lsp_client`]）：
+//! - 首次请求时启动 rust-analyzer / typescript-language-server / pyright-langserver 并完成 initialize
+//! - 每次请求前以 didOpen/didChange 同步磁盘上的文件内容
+//! - definition/implementation/references/hover/completion/workspace symbol 走真实 JSON-RPC，
+//!   diagnostics 取自服务器推送的 publishDiagnostics
+//! - 工具层列号为行内 UTF-8 字节偏移，收发时按协商的 `positionEncoding` 换算
+//! - 空闲超过 [`IDLE_TIMEOUT`] 的服务器自动 shutdown
+//! - 未安装语言服务器或请求失败时，降级为同文件的文本启发式结果
+//!
+//! `LspOperation` 没有重命名操作，重命名预览通过 [`rename_preview`]（Tauri 命令
+//! `aster_lsp_rename_preview`）单独提供，不会修改文件。
 
+use crate::lsp_client::{uri_to_path, LspClient, PositionEncoding};
 use aster::tools::lsp::Location;
 use aster::tools::{
     CompletionItem, CompletionItemKind, HoverInfo, LspCallback, LspOperation, LspResult, Position,
     Range,
 };
-use std::collections::HashSet;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+/// 语言服务器空闲多久后关闭
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// 空闲检查间隔
+const REAP_INTERVAL: Duration = Duration::from_secs(60);
+/// 启动失败后的冷却时间，避免每次调用都重试
+const START_FAILURE_COOLDOWN: Duration = Duration::from_secs(60);
+/// 等待 publishDiagnostics 的最长时间
+const DIAGNOSTICS_WAIT: Duration = Duration::from_secs(5);
+/// 补全结果数量上限
+const MAX_COMPLETIONS: usize = 50;
 
 /// 创建 LSP 回调
 pub fn create_lsp_callback() -> LspCallback {
@@ -23,109 +46,858 @@ pub fn create_lsp_callback() -> LspCallback {
     )
 }
 
+/// 语言服务器配置
 #[derive(Debug, Clone)]
-struct ServerProbeResult {
+struct ServerSpec {
     command: &'static str,
+    args: &'static [&'static str],
     install_hint: &'static str,
+    /// 用于定位工作区根目录的标记文件
+    root_markers: &'static [&'static str],
 }
 
+fn detect_server(path: &Path) -> Option<ServerSpec> {
+    let ext = path.extension()?.to_string_lossy().to_lowercase();
+    match ext.as_str() {
+        "rs" => Some(ServerSpec {
+            command: "rust-analyzer",
+            args: &[],
+            install_hint: "请安装 rust-analyzer（rustup component add rust-analyzer）",
+            root_markers: &["Cargo.toml"],
+        }),
+        "ts" | "tsx" | "js" | "jsx" => Some(ServerSpec {
+            command: "typescript-language-server",
+            args: &["--stdio"],
+            install_hint:
+                "请安装 typescript-language-server（npm i -g typescript-language-server typescript）",
+            root_markers: &["tsconfig.json", "jsconfig.json", "package.json"],
+        }),
+        "py" => Some(ServerSpec {
+            command: "pyright-langserver",
+            args: &["--stdio"],
+            install_hint: "请安装 pyright（npm i -g pyright）",
+            root_markers: &[
+                "pyrightconfig.json",
+                "pyproject.toml",
+                "setup.py",
+                "setup.cfg",
+                "requirements.txt",
+            ],
+        }),
+        _ => None,
+    }
+}
+
+fn language_id(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .as_deref()
+    {
+        Some("rs") => "rust",
+        Some("ts") => "typescript",
+        Some("tsx") => "typescriptreact",
+        Some("js") => "javascript",
+        Some("jsx") => "javascriptreact",
+        Some("py") => "python",
+        _ => "plaintext",
+    }
+}
+
+/// 向上查找最近的工作区根目录；找不到标记文件时退回 git 根目录或文件所在目录
+fn workspace_root(path: &Path, markers: &[&str]) -> PathBuf {
+    let start = path.parent().unwrap_or(path);
+    start
+        .ancestors()
+        .find(|dir| markers.iter().any(|m| dir.join(m).exists()))
+        .or_else(|| start.ancestors().find(|dir| dir.join(".git").exists()))
+        .unwrap_or(start)
+        .to_path_buf()
+}
+
+// ============================================================================
+// 语言服务器池
+// ============================================================================
+
+type ServerKey = (&'static str, PathBuf);
+/// 池中的服务器槽位：启动期间为空，并发请求共同等待同一次启动
+type ServerSlot = Arc<tokio::sync::OnceCell<Arc<LspClient>>>;
+
+/// 按（语言服务器, 工作区根目录）复用的服务器池
+#[derive(Default)]
+pub struct LspServerPool {
+    servers: tokio::sync::Mutex<HashMap<ServerKey, ServerSlot>>,
+    start_failures: std::sync::Mutex<HashMap<ServerKey, (Instant, String)>>,
+    reaper_started: AtomicBool,
+}
+
+fn pool() -> &'static LspServerPool {
+    static POOL: OnceLock<LspServerPool> = OnceLock::new();
+    POOL.get_or_init(LspServerPool::default)
+}
+
+impl LspServerPool {
+    async fn client_for(
+        &'static self,
+        spec: &ServerSpec,
+        path: &Path,
+    ) -> Result<Arc<LspClient>, String> {
+        self.ensure_reaper();
+
+        let key = (spec.command, workspace_root(path, spec.root_markers));
+        if let Some(err) = self.recent_failure(&key) {
+            return Err(err);
+        }
+
+        // 只在取槽位时持锁，启动与 initialize 在锁外进行，不阻塞其他工作区的请求
+        let slot = {
+            let mut servers = self.servers.lock().await;
+            let stale = servers
+                .get(&key)
+                .and_then(|slot| slot.get())
+                .is_some_and(|client| !client.is_alive());
+            if stale {
+                servers.remove(&key);
+            }
+            servers.entry(key.clone()).or_default().clone()
+        };
+
+        let result = slot
+            .get_or_try_init(|| async {
+                // 排队等待的请求在前一次启动失败后不再重复尝试
+                if let Some(err) = self.recent_failure(&key) {
+                    return Err(err);
+                }
+                match Self::start(spec, &key.1).await {
+                    Ok(client) => {
+                        tracing::info!(
+                            "[LSP] 已启动 {}，工作区: {}",
+                            spec.command,
+                            key.1.display()
+                        );
+                        Ok(Arc::new(client))
+                    }
+                    Err(err) => {
+                        self.start_failures
+                            .lock()
+                            .unwrap_or_else(std::sync::PoisonError::into_inner)
+                            .insert(key.clone(), (Instant::now(), err.clone()));
+                        Err(err)
+                    }
+                }
+            })
+            .await;
+
+        match result {
+            Ok(client) => {
+                client.touch();
+                Ok(client.clone())
+            }
+            Err(err) => {
+                let mut servers = self.servers.lock().await;
+                if servers
+                    .get(&key)
+                    .is_some_and(|current| Arc::ptr_eq(current, &slot))
+                {
+                    servers.remove(&key);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// 冷却期内的启动失败
+    fn recent_failure(&self, key: &ServerKey) -> Option<String> {
+        self.start_failures
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(key)
+            .filter(|(at, _)| at.elapsed() < START_FAILURE_COOLDOWN)
+            .map(|(_, err)| err.clone())
+    }
+
+    async fn start(spec: &ServerSpec, root: &Path) -> Result<LspClient, String> {
+        let client = LspClient::spawn(spec.command, spec.args, root)
+            .map_err(|err| format!("{err}。{}", spec.install_hint))?;
+        if let Err(err) = client.initialize().await {
+            client.shutdown().await;
+            return Err(format!("{} 初始化失败: {err}", spec.command));
+        }
+        Ok(client)
+    }
+
+    /// 关闭空闲超过 `max_idle` 的服务器，返回关闭数量
+    pub async fn shutdown_idle(&self, max_idle: Duration) -> usize {
+        let idle: Vec<Arc<LspClient>> = {
+            let mut servers = self.servers.lock().await;
+            // 仍在启动中的槽位不处理
+            let keys: Vec<ServerKey> = servers
+                .iter()
+                .filter(|(_, slot)| {
+                    slot.get()
+                        .is_some_and(|c| !c.is_alive() || c.idle_for() >= max_idle)
+                })
+                .map(|(k, _)| k.clone())
+                .collect();
+            keys.iter()
+                .filter_map(|k| servers.remove(k))
+                .filter_map(|slot| slot.get().cloned())
+                .collect()
+        };
+
+        for client in &idle {
+            tracing::info!("[LSP] 关闭空闲语言服务器: {}", client.root().display());
+            client.shutdown().await;
+        }
+        idle.len()
+    }
+
+    /// 关闭全部服务器
+    pub async fn shutdown_all(&self) {
+        self.shutdown_idle(Duration::ZERO).await;
+    }
+
+    fn ensure_reaper(&'static self) {
+        if self.reaper_started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(REAP_INTERVAL);
+            loop {
+                interval.tick().await;
+                self.shutdown_idle(IDLE_TIMEOUT).await;
+            }
+        });
+    }
+}
+
+/// 关闭所有常驻的语言服务器（应用退出时调用）
+pub async fn shutdown_lsp_servers() {
+    pool().shutdown_all().await;
+}
+
+// ============================================================================
+// 请求分发
+// ============================================================================
+
 async fn execute_lsp(
     operation: LspOperation,
     path: PathBuf,
     position: Option<Position>,
 ) -> Result<LspResult, String> {
-    let probe = detect_server(&path).ok_or_else(|| {
+    let spec = detect_server(&path).ok_or_else(|| {
         format!(
             "lsp 不支持该文件类型: {}。目前仅支持 .rs/.ts/.tsx/.js/.jsx/.py",
             path.display()
         )
     })?;
 
-    ensure_server_available(&probe).await?;
-
     let content = tokio::fs::read_to_string(&path)
         .await
         .map_err(|err| format!("读取文件失败: {}: {}", path.display(), err))?;
 
+    let server_error = match execute_with_server(&spec, &operation, &path, &content, position).await
+    {
+        Ok(result) => return Ok(result),
+        Err(err) => {
+            tracing::debug!("[LSP] {} 请求失败，降级为文本分析: {}", spec.command, err);
+            err
+        }
+    };
+
+    execute_heuristic(&operation, &path, &content, position, &server_error)
+}
+
+fn require_position(position: Option<Position>, operation: &str) -> Result<Position, String> {
+    position.ok_or_else(|| format!("{operation} 需要 line 和 character"))
+}
+
+fn text_document_position(uri: &str, pos: Position) -> Value {
+    json!({
+        "textDocument": { "uri": uri },
+        "position": { "line": pos.line, "character": pos.character }
+    })
+}
+
+async fn execute_with_server(
+    spec: &ServerSpec,
+    operation: &LspOperation,
+    path: &Path,
+    content: &str,
+    position: Option<Position>,
+) -> Result<LspResult, String> {
+    let client = pool().client_for(spec, path).await?;
+    let uri = client
+        .sync_document(path, language_id(path), content)
+        .await?;
+    let mut positions = PositionConverter::new(client.position_encoding(), &uri, content);
+
     match operation {
         LspOperation::Definition | LspOperation::Implementation => {
-            let pos =
-                position.ok_or_else(|| "definition/implementation 需要 line 和 character".to_string())?;
-            let symbol = symbol_at(&content, pos)
+            let pos = require_position(position, "definition/implementation")?;
+            let method = if matches!(operation, LspOperation::Definition) {
+                "textDocument/definition"
+            } else {
+                "textDocument/implementation"
+            };
+            let params = positions.params(pos);
+            let result = positions.request(&client, method, params).await?;
+            Ok(LspResult::Definition {
+                locations: parse_locations(&result),
+            })
+        }
+        LspOperation::References => {
+            let pos = require_position(position, "references")?;
+            let mut params = positions.params(pos);
+            params["context"] = json!({ "includeDeclaration": true });
+            let result = positions
+                .request(&client, "textDocument/references", params)
+                .await?;
+            Ok(LspResult::References {
+                locations: parse_locations(&result),
+            })
+        }
+        LspOperation::Hover => {
+            let pos = require_position(position, "hover")?;
+            let params = positions.params(pos);
+            let result = positions
+                .request(&client, "textDocument/hover", params)
+                .await?;
+            Ok(LspResult::Hover {
+                info: parse_hover(&result),
+            })
+        }
+        LspOperation::Completion => {
+            let pos = require_position(position, "completion")?;
+            let params = positions.params(pos);
+            let result = client.request("textDocument/completion", params).await?;
+            Ok(LspResult::Completion {
+                items: parse_completions(&result),
+            })
+        }
+        LspOperation::Diagnostics => {
+            let mut raw = Value::Array(
+                client
+                    .wait_for_diagnostics(&uri, DIAGNOSTICS_WAIT)
+                    .await
+                    .unwrap_or_default(),
+            );
+            positions.convert_response(&mut raw);
+            match serde_json::from_value(raw.clone()) {
+                Ok(diagnostics) => Ok(LspResult::Diagnostics { diagnostics }),
+                Err(err) => Ok(LspResult::Hover {
+                    // 结构不兼容时以文本形式返回，避免丢失诊断信息
+                    info: Some(HoverInfo {
+                        contents: format!(
+                            "诊断结果（结构化转换失败: {err}）\n{}",
+                            format_diagnostics(path, raw.as_array().map_or(&[], Vec::as_slice))
+                        ),
+                        range: None,
+                    }),
+                }),
+            }
+        }
+        LspOperation::WorkspaceSymbol => {
+            // 回调只携带位置，以光标处的标识符作为查询词
+            let pos = require_position(position, "workspace symbol")?;
+            let query = symbol_at(content, pos)
                 .ok_or_else(|| format!("未在 {}:{} 找到可解析符号", pos.line, pos.character))?;
-            let locations = find_definition_locations(&path, &content, &symbol);
+            let result = positions
+                .request(&client, "workspace/symbol", json!({ "query": query }))
+                .await?;
+            Ok(LspResult::Definition {
+                locations: parse_symbol_locations(&result, &query),
+            })
+        }
+        LspOperation::DocumentSymbol
+        | LspOperation::PrepareCallHierarchy
+        | LspOperation::IncomingCalls
+        | LspOperation::OutgoingCalls => Err(format!(
+            "操作 {operation:?} 暂不支持，可使用 definition/references/hover/completion/diagnostics/workspace symbol"
+        )),
+    }
+}
+
+/// 语言服务器不可用时的同文件文本分析
+fn execute_heuristic(
+    operation: &LspOperation,
+    path: &Path,
+    content: &str,
+    position: Option<Position>,
+    server_error: &str,
+) -> Result<LspResult, String> {
+    match operation {
+        LspOperation::Definition | LspOperation::Implementation => {
+            let pos = require_position(position, "definition/implementation")?;
+            let symbol = symbol_at(content, pos)
+                .ok_or_else(|| format!("未在 {}:{} 找到可解析符号", pos.line, pos.character))?;
+            let locations = find_definition_locations(path, content, &symbol);
             Ok(LspResult::Definition { locations })
         }
         LspOperation::References => {
-            let pos = position.ok_or_else(|| "references 需要 line 和 character".to_string())?;
-            let symbol = symbol_at(&content, pos)
+            let pos = require_position(position, "references")?;
+            let symbol = symbol_at(content, pos)
                 .ok_or_else(|| format!("未在 {}:{} 找到可解析符号", pos.line, pos.character))?;
-            let locations = find_reference_locations(&path, &content, &symbol);
+            let locations = find_reference_locations(path, content, &symbol);
             Ok(LspResult::References { locations })
         }
         LspOperation::Hover => {
-            let pos = position.ok_or_else(|| "hover 需要 line 和 character".to_string())?;
-            let symbol = symbol_at(&content, pos)
+            let pos = require_position(position, "hover")?;
+            let symbol = symbol_at(content, pos)
                 .ok_or_else(|| format!("未在 {}:{} 找到可解析符号", pos.line, pos.character))?;
-            let hover = build_hover(&path, &content, &symbol);
+            let hover = build_hover(path, content, &symbol);
             Ok(LspResult::Hover { info: hover })
         }
         LspOperation::Completion => {
-            let pos = position.ok_or_else(|| "completion 需要 line 和 character".to_string())?;
-            let items = collect_completions(&content, pos);
+            let pos = require_position(position, "completion")?;
+            let items = collect_completions(content, pos);
             Ok(LspResult::Completion { items })
         }
         LspOperation::Diagnostics => Ok(LspResult::Diagnostics {
             diagnostics: Vec::new(),
         }),
-        LspOperation::DocumentSymbol
-        | LspOperation::WorkspaceSymbol
+        LspOperation::WorkspaceSymbol
+        | LspOperation::DocumentSymbol
         | LspOperation::PrepareCallHierarchy
         | LspOperation::IncomingCalls
-        | LspOperation::OutgoingCalls => Err(format!(
-            "操作 {:?} 尚未接入完整 JSON-RPC 流程。已探测到可执行文件 '{}', 可先使用 definition/references/hover/completion/diagnostics。",
-            operation, probe.command
-        )),
+        | LspOperation::OutgoingCalls => {
+            Err(format!("操作 {operation:?} 需要语言服务器: {server_error}"))
+        }
     }
 }
 
-fn detect_server(path: &Path) -> Option<ServerProbeResult> {
-    let ext = path.extension()?.to_string_lossy().to_lowercase();
-    match ext.as_str() {
-        "rs" => Some(ServerProbeResult {
-            command: "rust-analyzer",
-            install_hint: "请安装 rust-analyzer（rustup component add rust-analyzer）",
-        }),
-        "ts" | "tsx" | "js" | "jsx" => Some(ServerProbeResult {
-            command: "typescript-language-server",
-            install_hint:
-                "请安装 typescript-language-server（npm i -g typescript-language-server typescript）",
-        }),
-        "py" => Some(ServerProbeResult {
-            command: "pyright-langserver",
-            install_hint: "请安装 pyright（npm i -g pyright）",
-        }),
-        _ => None,
+// ============================================================================
+// 位置编码换算
+// ============================================================================
+
+/// 工具层列号（行内 UTF-8 字节偏移）与服务器位置编码之间的换算
+///
+/// 发往服务器的位置都在当前文档内；响应中的位置可能指向其他文件，按需读取其内容。
+struct PositionConverter {
+    encoding: PositionEncoding,
+    uri: String,
+    /// 按 URI 缓存的文件内容，读取失败时为 `None`（列号原样返回）
+    files: HashMap<String, Option<String>>,
+}
+
+impl PositionConverter {
+    fn new(encoding: PositionEncoding, uri: &str, content: &str) -> Self {
+        Self {
+            encoding,
+            uri: uri.to_string(),
+            files: HashMap::from([(uri.to_string(), Some(content.to_string()))]),
+        }
+    }
+
+    /// 当前文档内某位置的 `TextDocumentPositionParams`
+    fn params(&self, pos: Position) -> Value {
+        let character = self
+            .files
+            .get(&self.uri)
+            .and_then(|content| content.as_deref())
+            .and_then(|content| content.lines().nth(pos.line as usize))
+            .map_or(pos.character, |line| {
+                self.encoding.column_from_utf8(line, pos.character)
+            });
+        text_document_position(&self.uri, Position::new(pos.line, character))
+    }
+
+    /// 发送请求，并把响应中的位置换算回 UTF-8 字节列
+    async fn request(
+        &mut self,
+        client: &LspClient,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, String> {
+        let mut result = client.request(method, params).await?;
+        self.convert_response(&mut result);
+        Ok(result)
+    }
+
+    /// 就地换算响应中的所有 `{ line, character }`
+    fn convert_response(&mut self, value: &mut Value) {
+        if self.encoding != PositionEncoding::Utf8 {
+            let uri = self.uri.clone();
+            self.convert(value, &uri);
+        }
+    }
+
+    /// `uri` 为当前子树所属的文档：遇到 `uri`/`targetUri`/`textDocument.uri`
+    /// 或以 URI 为键的 `WorkspaceEdit.changes` 时切换
+    fn convert(&mut self, value: &mut Value, uri: &str) {
+        match value {
+            Value::Array(items) => {
+                for item in items {
+                    self.convert(item, uri);
+                }
+            }
+            Value::Object(map) => {
+                let line = map.get("line").and_then(Value::as_u64);
+                let character = map.get("character").and_then(Value::as_u64);
+                if let (Some(line), Some(character)) = (line, character) {
+                    let character = self.server_column_to_utf8(uri, line as u32, character as u32);
+                    map.insert("character".to_string(), json!(character));
+                    return;
+                }
+
+                let own_uri = map
+                    .get("targetUri")
+                    .or_else(|| map.get("uri"))
+                    .or_else(|| map.get("textDocument").and_then(|doc| doc.get("uri")))
+                    .and_then(Value::as_str)
+                    .map(str::to_string);
+                let inner = own_uri.as_deref().unwrap_or(uri);
+                for (key, child) in map.iter_mut() {
+                    if key.starts_with("file://") {
+                        self.convert(child, key);
+                    } else if key == "originSelectionRange" {
+                        // LocationLink 的起点范围属于发起请求的文档
+                        self.convert(child, uri);
+                    } else {
+                        self.convert(child, inner);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn server_column_to_utf8(&mut self, uri: &str, line: u32, character: u32) -> u32 {
+        let encoding = self.encoding;
+        let content = self.files.entry(uri.to_string()).or_insert_with(|| {
+            uri_to_path(uri).and_then(|path| std::fs::read_to_string(path).ok())
+        });
+        content
+            .as_deref()
+            .and_then(|content| content.lines().nth(line as usize))
+            .map_or(character, |text| encoding.column_to_utf8(text, character))
     }
 }
 
-async fn ensure_server_available(probe: &ServerProbeResult) -> Result<(), String> {
-    let status = tokio::process::Command::new(probe.command)
-        .arg("--version")
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
-        .await;
+// ============================================================================
+// 重命名预览
+// ============================================================================
 
-    match status {
-        Ok(_) => Ok(()),
-        Err(err) => Err(format!(
-            "未检测到 LSP 可执行文件 '{}': {}。{}",
-            probe.command, err, probe.install_hint
-        )),
+/// 重命名预览中的单处编辑（行列均从 0 开始）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RenameEdit {
+    pub start_line: u32,
+    pub start_character: u32,
+    pub end_line: u32,
+    pub end_character: u32,
+    pub new_text: String,
+}
+
+/// 单个文件的重命名编辑
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RenameFileEdits {
+    pub path: PathBuf,
+    pub edits: Vec<RenameEdit>,
+}
+
+/// 重命名预览结果
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RenamePreview {
+    pub files: Vec<RenameFileEdits>,
+    pub total_edits: usize,
+}
+
+/// 计算重命名会产生的编辑，不写入任何文件
+pub async fn rename_preview(
+    path: &Path,
+    position: Position,
+    new_name: &str,
+) -> Result<RenamePreview, String> {
+    let spec =
+        detect_server(path).ok_or_else(|| format!("lsp 不支持该文件类型: {}", path.display()))?;
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|err| format!("读取文件失败: {}: {}", path.display(), err))?;
+
+    let client = pool().client_for(&spec, path).await?;
+    let uri = client
+        .sync_document(path, language_id(path), &content)
+        .await?;
+    let mut positions = PositionConverter::new(client.position_encoding(), &uri, &content);
+
+    if client.supports_prepare_rename() {
+        let prepared = client
+            .request("textDocument/prepareRename", positions.params(position))
+            .await?;
+        if prepared.is_null() {
+            return Err(format!(
+                "{}:{} 处的符号不可重命名",
+                position.line, position.character
+            ));
+        }
     }
+
+    let mut params = positions.params(position);
+    params["newName"] = json!(new_name);
+    let edit = positions
+        .request(&client, "textDocument/rename", params)
+        .await?;
+    Ok(parse_workspace_edit(&edit))
 }
 
+// ============================================================================
+// 响应解析
+// ============================================================================
+
+fn parse_position(value: &Value) -> Option<Position> {
+    Some(Position::new(
+        value.get("line")?.as_u64()? as u32,
+        value.get("character")?.as_u64()? as u32,
+    ))
+}
+
+fn parse_range(value: &Value) -> Option<Range> {
+    Some(Range::new(
+        parse_position(value.get("start")?)?,
+        parse_position(value.get("end")?)?,
+    ))
+}
+
+/// 解析 `Location | Location[] | LocationLink[] | null`
+fn parse_locations(value: &Value) -> Vec<Location> {
+    let parse_one = |item: &Value| -> Option<Location> {
+        let (uri, range) = match item.get("targetUri") {
+            Some(target) => (
+                target,
+                item.get("targetSelectionRange")
+                    .or_else(|| item.get("targetRange"))?,
+            ),
+            None => (item.get("uri")?, item.get("range")?),
+        };
+        Some(Location::new(
+            uri_to_path(uri.as_str()?)?,
+            parse_range(range)?,
+        ))
+    };
+
+    match value {
+        Value::Array(items) => items.iter().filter_map(parse_one).collect(),
+        Value::Object(_) => parse_one(value).into_iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn markup_to_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(markup_to_text)
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        Value::Object(obj) => {
+            let text = obj.get("value").and_then(Value::as_str).unwrap_or_default();
+            // MarkedString { language, value } 渲染为代码块
+            match obj.get("language").and_then(Value::as_str) {
+                Some(language) => format!("```{language}\n{text}\n```"),
+                None => text.to_string(),
+            }
+        }
+        _ => String::new(),
+    }
+}
+
+fn parse_hover(value: &Value) -> Option<HoverInfo> {
+    let contents = markup_to_text(value.get("contents")?);
+    if contents.trim().is_empty() {
+        return None;
+    }
+    Some(HoverInfo {
+        contents,
+        range: value.get("range").and_then(parse_range),
+    })
+}
+
+fn completion_kind_label(kind: u64) -> Option<&'static str> {
+    Some(match kind {
+        2 => "method",
+        3 => "function",
+        4 => "constructor",
+        5 => "field",
+        6 => "variable",
+        7 => "class",
+        8 => "interface",
+        9 => "module",
+        10 => "property",
+        13 => "enum",
+        14 => "keyword",
+        15 => "snippet",
+        20 => "enum member",
+        21 => "constant",
+        22 => "struct",
+        25 => "type parameter",
+        _ => return None,
+    })
+}
+
+/// 解析 `CompletionItem[] | CompletionList | null`
+fn parse_completions(value: &Value) -> Vec<CompletionItem> {
+    let items = match value {
+        Value::Array(items) => items.as_slice(),
+        Value::Object(obj) => obj
+            .get("items")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default(),
+        _ => &[],
+    };
+
+    items
+        .iter()
+        .filter_map(|item| {
+            let label = item.get("label")?.as_str()?.to_string();
+            let kind = item.get("kind").and_then(Value::as_u64);
+            let detail = item
+                .get("detail")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .or_else(|| kind.and_then(completion_kind_label).map(str::to_string));
+            let documentation = item
+                .get("documentation")
+                .map(markup_to_text)
+                .filter(|d| !d.is_empty());
+            let insert_text = item
+                .pointer("/textEdit/newText")
+                .or_else(|| item.get("insertText"))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .or_else(|| Some(label.clone()));
+            Some(CompletionItem {
+                label,
+                kind: (kind == Some(6)).then_some(CompletionItemKind::Variable),
+                detail,
+                documentation,
+                insert_text,
+            })
+        })
+        .take(MAX_COMPLETIONS)
+        .collect()
+}
+
+/// 解析 workspace/symbol 结果，同名符号优先
+fn parse_symbol_locations(value: &Value, query: &str) -> Vec<Location> {
+    let Some(symbols) = value.as_array() else {
+        return Vec::new();
+    };
+
+    let to_location = |symbol: &Value| -> Option<Location> {
+        let location = symbol.get("location")?;
+        let path = uri_to_path(location.get("uri")?.as_str()?)?;
+        // WorkspaceSymbol 可能只带 uri，没有 range
+        let range = location
+            .get("range")
+            .and_then(parse_range)
+            .unwrap_or_else(|| Range::new(Position::new(0, 0), Position::new(0, 0)));
+        Some(Location::new(path, range))
+    };
+
+    let exact: Vec<Location> = symbols
+        .iter()
+        .filter(|s| s.get("name").and_then(Value::as_str) == Some(query))
+        .filter_map(to_location)
+        .collect();
+    if !exact.is_empty() {
+        return exact;
+    }
+    symbols.iter().filter_map(to_location).collect()
+}
+
+fn parse_text_edits(edits: &Value) -> Vec<RenameEdit> {
+    edits
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|edit| {
+            let range = edit.get("range")?;
+            let start = range.get("start")?;
+            let end = range.get("end")?;
+            Some(RenameEdit {
+                start_line: start.get("line")?.as_u64()? as u32,
+                start_character: start.get("character")?.as_u64()? as u32,
+                end_line: end.get("line")?.as_u64()? as u32,
+                end_character: end.get("character")?.as_u64()? as u32,
+                new_text: edit.get("newText")?.as_str()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// 解析 WorkspaceEdit（`changes` 或 `documentChanges`）
+fn parse_workspace_edit(value: &Value) -> RenamePreview {
+    let mut files: Vec<RenameFileEdits> = Vec::new();
+    let mut push = |uri: &str, edits: Vec<RenameEdit>| {
+        let Some(path) = uri_to_path(uri) else {
+            return;
+        };
+        if edits.is_empty() {
+            return;
+        }
+        match files.iter_mut().find(|f| f.path == path) {
+            Some(file) => file.edits.extend(edits),
+            None => files.push(RenameFileEdits { path, edits }),
+        }
+    };
+
+    if let Some(changes) = value.get("documentChanges").and_then(Value::as_array) {
+        // 跳过 create/rename/delete 等文件操作，只预览文本编辑
+        for change in changes.iter().filter(|c| c.get("kind").is_none()) {
+            if let Some(uri) = change.pointer("/textDocument/uri").and_then(Value::as_str) {
+                push(
+                    uri,
+                    parse_text_edits(change.get("edits").unwrap_or(&Value::Null)),
+                );
+            }
+        }
+    } else if let Some(changes) = value.get("changes").and_then(Value::as_object) {
+        for (uri, edits) in changes {
+            push(uri, parse_text_edits(edits));
+        }
+    }
+
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    let total_edits = files.iter().map(|f| f.edits.len()).sum();
+    RenamePreview { files, total_edits }
+}
+
+fn format_diagnostics(path: &Path, diagnostics: &[Value]) -> String {
+    diagnostics
+        .iter()
+        .map(|d| {
+            let severity = match d.get("severity").and_then(Value::as_u64) {
+                Some(1) => "error",
+                Some(2) => "warning",
+                Some(3) => "info",
+                Some(4) => "hint",
+                _ => "unknown",
+            };
+            let line = d
+                .pointer("/range/start/line")
+                .and_then(Value::as_u64)
+                .unwrap_or(0)
+                + 1;
+            let column = d
+                .pointer("/range/start/character")
+                .and_then(Value::as_u64)
+                .unwrap_or(0)
+                + 1;
+            let message = d.get("message").and_then(Value::as_str).unwrap_or_default();
+            format!("{}:{line}:{column} [{severity}] {message}", path.display())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// ============================================================================
+// 文本启发式（无语言服务器时的降级实现）
+// ============================================================================
+
 fn symbol_at(content: &str, pos: Position) -> Option<String> {
     let line = content.lines().nth(pos.line as usize)?;
     let chars: Vec<(usize, char)> = line.char_indices().collect();
@@ -360,3 +1132,202 @@ fn to_location(path: &Path, line: u32, character: u32, symbol_len: u32) -> Locat
         ),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_locations_variants() {
+        let location = json!({
+            "uri": "file:///ws/src/lib.rs",
+            "range": {
+                "start": { "line": 1, "character": 2 },
+                "end": { "line": 1, "character": 6 }
+            }
+        });
+        let single = parse_locations(&location);
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].range.start.line, 1);
+
+        let links = json!([{
+            "targetUri": "file:///ws/src/a.rs",
+            "targetRange": {
+                "start": { "line": 0, "character": 0 },
+                "end": { "line": 9, "character": 1 }
+            },
+            "targetSelectionRange": {
+                "start": { "line": 0, "character": 3 },
+                "end": { "line": 0, "character": 7 }
+            }
+        }]);
+        let parsed = parse_locations(&links);
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].range.start.character, 3);
+
+        assert!(parse_locations(&Value::Null).is_empty());
+    }
+
+    #[test]
+    fn test_parse_hover_contents() {
+        let hover = parse_hover(&json!({
+            "contents": [{ "language": "rust", "value": "fn main()" }, "入口函数"]
+        }))
+        .unwrap();
+        assert_eq!(hover.contents, "```rust\nfn main()\n```\n\n入口函数");
+
+        let markup = parse_hover(&json!({
+            "contents": { "kind": "markdown", "value": "**doc**" }
+        }))
+        .unwrap();
+        assert_eq!(markup.contents, "**doc**");
+
+        assert!(parse_hover(&json!({ "contents": "" })).is_none());
+        assert!(parse_hover(&Value::Null).is_none());
+    }
+
+    #[test]
+    fn test_parse_completions() {
+        let items = parse_completions(&json!({
+            "isIncomplete": false,
+            "items": [
+                { "label": "push", "kind": 2, "textEdit": { "newText": "push($0)" } },
+                { "label": "len", "detail": "fn len(&self) -> usize", "insertText": "len()" },
+                { "label": "value", "kind": 6 }
+            ]
+        }));
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].detail.as_deref(), Some("method"));
+        assert_eq!(items[0].insert_text.as_deref(), Some("push($0)"));
+        assert_eq!(items[1].detail.as_deref(), Some("fn len(&self) -> usize"));
+        assert_eq!(items[2].insert_text.as_deref(), Some("value"));
+    }
+
+    #[test]
+    fn test_parse_workspace_edit() {
+        let range = json!({
+            "start": { "line": 2, "character": 4 },
+            "end": { "line": 2, "character": 7 }
+        });
+        let preview = parse_workspace_edit(&json!({
+            "documentChanges": [
+                {
+                    "textDocument": { "uri": "file:///ws/b.rs", "version": 1 },
+                    "edits": [{ "range": range, "newText": "bar" }]
+                },
+                { "kind": "rename", "oldUri": "file:///ws/x.rs", "newUri": "file:///ws/y.rs" },
+                {
+                    "textDocument": { "uri": "file:///ws/a.rs", "version": 1 },
+                    "edits": [
+                        { "range": range, "newText": "bar" },
+                        { "range": range, "newText": "bar" }
+                    ]
+                }
+            ]
+        }));
+        assert_eq!(preview.total_edits, 3);
+        assert_eq!(preview.files[0].path, PathBuf::from("/ws/a.rs"));
+        assert_eq!(preview.files[0].edits[0].end_character, 7);
+
+        let legacy = parse_workspace_edit(&json!({
+            "changes": { "file:///ws/a.rs": [{ "range": range, "newText": "baz" }] }
+        }));
+        assert_eq!(legacy.total_edits, 1);
+    }
+
+    #[test]
+    fn test_position_converter_utf16() {
+        let uri = "file:///ws/src/a.rs";
+        let content = "let 中文 = helper();\n";
+        let helper = content.find("helper").unwrap() as u32;
+        let mut positions = PositionConverter::new(PositionEncoding::Utf16, uri, content);
+
+        // “中文”占 6 字节、2 个 UTF-16 单元
+        let params = positions.params(Position::new(0, helper));
+        assert_eq!(params["position"]["character"], helper - 4);
+
+        let range = json!({
+            "start": { "line": 0, "character": helper - 4 },
+            "end": { "line": 0, "character": helper + 2 }
+        });
+        let mut locations = json!([
+            { "uri": uri, "range": range },
+            { "uri": "file:///nonexistent/b.rs", "range": range }
+        ]);
+        positions.convert_response(&mut locations);
+        assert_eq!(locations[0]["range"]["start"]["character"], helper);
+        assert_eq!(locations[0]["range"]["end"]["character"], helper + 6);
+        // 读不到内容的文件保持原值
+        assert_eq!(locations[1]["range"]["start"]["character"], helper - 4);
+
+        let mut edit = json!({ "changes": { uri: [{ "range": range, "newText": "run" }] } });
+        positions.convert_response(&mut edit);
+        assert_eq!(
+            edit["changes"][uri][0]["range"]["start"]["character"],
+            helper
+        );
+    }
+
+    #[test]
+    fn test_parse_symbol_locations_prefers_exact_match() {
+        let symbols = json!([
+            {
+                "name": "FooBar",
+                "location": {
+                    "uri": "file:///ws/a.rs",
+                    "range": { "start": { "line": 1, "character": 0 }, "end": { "line": 1, "character": 6 } }
+                }
+            },
+            { "name": "Foo", "location": { "uri": "file:///ws/b.rs" } }
+        ]);
+        let locations = parse_symbol_locations(&symbols, "Foo");
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].range.start.line, 0);
+    }
+
+    #[test]
+    fn test_workspace_root_detection() {
+        let dir = tempfile::tempdir().unwrap();
+        let crate_dir = dir.path().join("crates/demo");
+        std::fs::create_dir_all(crate_dir.join("src")).unwrap();
+        std::fs::write(crate_dir.join("Cargo.toml"), "[package]").unwrap();
+
+        let file = crate_dir.join("src/lib.rs");
+        assert_eq!(workspace_root(&file, &["Cargo.toml"]), crate_dir);
+
+        let loose = dir.path().join("notes/a.py");
+        assert_eq!(
+            workspace_root(&loose, &["pyproject.toml"]),
+            dir.path().join("notes")
+        );
+    }
+
+    #[test]
+    fn test_heuristic_fallback_definition() {
+        let content = "fn helper() {}\nfn main() { helper(); }\n";
+        let result = execute_heuristic(
+            &LspOperation::Definition,
+            Path::new("/ws/main.rs"),
+            content,
+            Some(Position::new(1, 13)),
+            "未安装",
+        )
+        .unwrap();
+        match result {
+            LspResult::Definition { locations } => {
+                assert_eq!(locations.len(), 1);
+                assert_eq!(locations[0].range.start.line, 0);
+            }
+            _ => panic!("unexpected result"),
+        }
+
+        assert!(execute_heuristic(
+            &LspOperation::WorkspaceSymbol,
+            Path::new("/ws/main.rs"),
+            content,
+            None,
+            "未安装",
+        )
+        .is_err());
+    }
+}
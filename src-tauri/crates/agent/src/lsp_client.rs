@@ -0,0 +1,775 @@
+//! LSP JSON-RPC 客户端
+//!
+//! 负责与单个语言服务器进程通信：
+//! - stdio 上的 `Content-Length` 分帧读写
+//! - 请求/响应按 id 配对，超时后发送 `$/cancelRequest`
+//! - 应答服务端发起的请求（`workspace/configuration` 等）
+//! - didOpen/didChange 全量文档同步
+//! - 缓存 `textDocument/publishDiagnostics` 推送的诊断
+//! - 记录协商得到的位置编码（`positionEncoding`），供调用方换算列号
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::sync::{oneshot, Notify};
+use tokio::task::JoinHandle;
+
+/// 单个请求的超时时间
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// shutdown 请求的等待时间
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+
+type Writer = Box<dyn AsyncWrite + Send + Unpin>;
+type PendingMap = HashMap<i64, oneshot::Sender<Result<Value, String>>>;
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// 编码一条 LSP 消息（`Content-Length` 头 + JSON 正文）
+pub fn encode_message(message: &Value) -> Vec<u8> {
+    let body = message.to_string();
+    let mut out = format!("Content-Length: {}\r\n\r\n", body.len()).into_bytes();
+    out.extend_from_slice(body.as_bytes());
+    out
+}
+
+/// 读取一条 LSP 消息；流结束时返回 `None`
+pub async fn read_message<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+) -> std::io::Result<Option<Value>> {
+    let invalid = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+
+    let mut content_length: Option<usize> = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                let length = value
+                    .trim()
+                    .parse()
+                    .map_err(|e| invalid(format!("无效的 Content-Length: {e}")))?;
+                content_length = Some(length);
+            }
+        }
+    }
+
+    let mut body = vec![0u8; content_length.unwrap_or_default()];
+    reader.read_exact(&mut body).await?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| invalid(format!("无效的 JSON 消息: {e}")))
+}
+
+/// 文件路径转 `file://` URI
+pub fn path_to_uri(path: &Path) -> String {
+    let raw = path.to_string_lossy().replace('\\', "/");
+    let raw = if raw.starts_with('/') {
+        raw
+    } else {
+        // Windows 盘符路径：C:/foo -> /C:/foo
+        format!("/{raw}")
+    };
+
+    let mut uri = String::from("file://");
+    for byte in raw.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    uri
+}
+
+/// `file://` URI 转文件路径；非 file 协议返回 `None`
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    // 跳过 authority（通常为空或 localhost）
+    let rest = &rest[rest.find('/')?..];
+
+    let bytes = rest.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit()
+        {
+            if let Ok(byte) = u8::from_str_radix(&rest[i + 1..i + 3], 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    let path = String::from_utf8_lossy(&decoded).into_owned();
+
+    // /C:/foo -> C:/foo
+    let path = match path.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => path[1..].to_string(),
+        _ => path,
+    };
+    Some(PathBuf::from(path))
+}
+
+/// 位置编码：LSP `Position.character` 的计量单位
+///
+/// 服务器未在 initialize 响应中声明 `positionEncoding` 时按协议默认的 UTF-16 处理。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PositionEncoding {
+    Utf8,
+    #[default]
+    Utf16,
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// 从服务器能力中读取协商结果
+    pub fn from_capabilities(capabilities: &Value) -> Self {
+        match capabilities.get("positionEncoding").and_then(Value::as_str) {
+            Some("utf-8") => Self::Utf8,
+            Some("utf-32") => Self::Utf32,
+            _ => Self::Utf16,
+        }
+    }
+
+    fn char_units(self, ch: char) -> u32 {
+        match self {
+            Self::Utf8 => ch.len_utf8() as u32,
+            Self::Utf16 => ch.len_utf16() as u32,
+            Self::Utf32 => 1,
+        }
+    }
+
+    /// 行内 UTF-8 字节偏移转为本编码的列号（超出行尾按行尾计算）
+    pub fn column_from_utf8(self, line: &str, byte_offset: u32) -> u32 {
+        if self == Self::Utf8 {
+            return byte_offset;
+        }
+        line.char_indices()
+            .take_while(|(idx, _)| (*idx as u32) < byte_offset)
+            .map(|(_, ch)| self.char_units(ch))
+            .sum()
+    }
+
+    /// 本编码的列号转为行内 UTF-8 字节偏移（超出行尾按行尾计算）
+    pub fn column_to_utf8(self, line: &str, column: u32) -> u32 {
+        if self == Self::Utf8 {
+            return column;
+        }
+        let mut units = 0;
+        for (idx, ch) in line.char_indices() {
+            if units >= column {
+                return idx as u32;
+            }
+            units += self.char_units(ch);
+        }
+        line.len() as u32
+    }
+}
+
+/// 已打开文档的同步状态
+struct OpenDocument {
+    version: i64,
+    text: String,
+}
+
+/// 读写任务共享的连接状态
+struct Connection {
+    writer: tokio::sync::Mutex<Writer>,
+    pending: Mutex<PendingMap>,
+    diagnostics: Mutex<HashMap<String, Vec<Value>>>,
+    diagnostics_changed: Notify,
+    alive: AtomicBool,
+}
+
+impl Connection {
+    async fn send(&self, message: &Value) -> Result<(), String> {
+        let mut writer = self.writer.lock().await;
+        writer
+            .write_all(&encode_message(message))
+            .await
+            .map_err(|e| format!("写入语言服务器失败: {e}"))?;
+        writer
+            .flush()
+            .await
+            .map_err(|e| format!("写入语言服务器失败: {e}"))
+    }
+
+    async fn handle_incoming(&self, message: Value) {
+        let method = message.get("method").and_then(Value::as_str);
+        let id = message.get("id");
+
+        match (method, id) {
+            // 服务端请求：必须应答，否则部分服务器会一直等待
+            (Some(method), Some(id)) => {
+                let result = match method {
+                    "workspace/configuration" => {
+                        let count = message
+                            .pointer("/params/items")
+                            .and_then(Value::as_array)
+                            .map_or(0, Vec::len);
+                        Value::Array(vec![Value::Null; count])
+                    }
+                    _ => Value::Null,
+                };
+                let response = json!({ "jsonrpc": "2.0", "id": id, "result": result });
+                if let Err(e) = self.send(&response).await {
+                    tracing::debug!("[LSP] 应答服务端请求 {} 失败: {}", method, e);
+                }
+            }
+            (Some("textDocument/publishDiagnostics"), None) => {
+                let Some(params) = message.get("params") else {
+                    return;
+                };
+                let Some(uri) = params.get("uri").and_then(Value::as_str) else {
+                    return;
+                };
+                let diagnostics = params
+                    .get("diagnostics")
+                    .and_then(Value::as_array)
+                    .cloned()
+                    .unwrap_or_default();
+                lock(&self.diagnostics).insert(uri.to_string(), diagnostics);
+                self.diagnostics_changed.notify_waiters();
+            }
+            (Some(method), None) => {
+                tracing::trace!("[LSP] 忽略通知: {}", method);
+            }
+            (None, Some(id)) => {
+                let Some(id) = id.as_i64() else {
+                    return;
+                };
+                let Some(sender) = lock(&self.pending).remove(&id) else {
+                    return;
+                };
+                let result = match message.get("error") {
+                    Some(error) => Err(format!(
+                        "LSP 错误 {}: {}",
+                        error
+                            .get("code")
+                            .and_then(Value::as_i64)
+                            .unwrap_or_default(),
+                        error
+                            .get("message")
+                            .and_then(Value::as_str)
+                            .unwrap_or("未知错误")
+                    )),
+                    None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                };
+                let _ = sender.send(result);
+            }
+            (None, None) => {}
+        }
+    }
+
+    fn close(&self) {
+        self.alive.store(false, Ordering::SeqCst);
+        let pending: Vec<_> = lock(&self.pending).drain().collect();
+        for (_, sender) in pending {
+            let _ = sender.send(Err("语言服务器连接已关闭".to_string()));
+        }
+        self.diagnostics_changed.notify_waiters();
+    }
+}
+
+async fn reader_loop<R: AsyncRead + Unpin>(reader: R, connection: Arc<Connection>) {
+    let mut reader = BufReader::new(reader);
+    loop {
+        match read_message(&mut reader).await {
+            Ok(Some(message)) => connection.handle_incoming(message).await,
+            Ok(None) => break,
+            Err(e) => {
+                tracing::warn!("[LSP] 读取语言服务器消息失败: {}", e);
+                break;
+            }
+        }
+    }
+    connection.close();
+}
+
+/// 单个语言服务器连接
+pub struct LspClient {
+    connection: Arc<Connection>,
+    next_id: AtomicI64,
+    root: PathBuf,
+    documents: tokio::sync::Mutex<HashMap<String, OpenDocument>>,
+    capabilities: Mutex<Value>,
+    position_encoding: Mutex<PositionEncoding>,
+    last_used: Mutex<Instant>,
+    child: Mutex<Option<tokio::process::Child>>,
+    reader_task: JoinHandle<()>,
+}
+
+impl LspClient {
+    /// 在给定读写流上建立连接（不发送 initialize）
+    pub fn connect<R, W>(reader: R, writer: W, root: PathBuf) -> Self
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let connection = Arc::new(Connection {
+            writer: tokio::sync::Mutex::new(Box::new(writer)),
+            pending: Mutex::new(HashMap::new()),
+            diagnostics: Mutex::new(HashMap::new()),
+            diagnostics_changed: Notify::new(),
+            alive: AtomicBool::new(true),
+        });
+        let reader_task = tokio::spawn(reader_loop(reader, connection.clone()));
+
+        Self {
+            connection,
+            next_id: AtomicI64::new(1),
+            root,
+            documents: tokio::sync::Mutex::new(HashMap::new()),
+            capabilities: Mutex::new(Value::Null),
+            position_encoding: Mutex::new(PositionEncoding::default()),
+            last_used: Mutex::new(Instant::now()),
+            child: Mutex::new(None),
+            reader_task,
+        }
+    }
+
+    /// 启动语言服务器进程并建立 stdio 连接
+    pub fn spawn(command: &str, args: &[&str], root: &Path) -> Result<Self, String> {
+        let mut child = tokio::process::Command::new(command)
+            .args(args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("启动语言服务器 '{command}' 失败: {e}"))?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| "无法获取语言服务器 stdin".to_string())?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| "无法获取语言服务器 stdout".to_string())?;
+
+        let client = Self::connect(stdout, stdin, root.to_path_buf());
+        *lock(&client.child) = Some(child);
+        Ok(client)
+    }
+
+    /// 发送 initialize / initialized
+    pub async fn initialize(&self) -> Result<(), String> {
+        let root_uri = path_to_uri(&self.root);
+        let root_name = self
+            .root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "workspace".to_string());
+
+        let params = json!({
+            "processId": std::process::id(),
+            "clientInfo": { "name": "proxycast", "version": env!("CARGO_PKG_VERSION") },
+            "rootPath": self.root.to_string_lossy(),
+            "rootUri": root_uri,
+            "workspaceFolders": [{ "uri": root_uri, "name": root_name }],
+            "capabilities": {
+                // 优先使用 UTF-8 偏移，与工具层按字节计算的列号保持一致
+                "general": { "positionEncodings": ["utf-8", "utf-16"] },
+                "textDocument": {
+                    "synchronization": { "dynamicRegistration": false, "didSave": false },
+                    "definition": { "linkSupport": true },
+                    "implementation": { "linkSupport": true },
+                    "references": {},
+                    "hover": { "contentFormat": ["markdown", "plaintext"] },
+                    "completion": {
+                        "completionItem": {
+                            "snippetSupport": false,
+                            "documentationFormat": ["markdown", "plaintext"]
+                        }
+                    },
+                    "rename": { "prepareSupport": true },
+                    "publishDiagnostics": { "relatedInformation": false, "versionSupport": true }
+                },
+                "workspace": {
+                    "workspaceFolders": true,
+                    "configuration": true,
+                    "symbol": {}
+                },
+                "window": { "workDoneProgress": false }
+            }
+        });
+
+        let result = self.request("initialize", params).await?;
+        let capabilities = result.get("capabilities").cloned().unwrap_or_default();
+        *lock(&self.position_encoding) = PositionEncoding::from_capabilities(&capabilities);
+        *lock(&self.capabilities) = capabilities;
+        self.notify("initialized", json!({})).await
+    }
+
+    /// 发送请求并等待响应
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value, String> {
+        if !self.is_alive() {
+            return Err("语言服务器连接已关闭".to_string());
+        }
+        self.touch();
+
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = oneshot::channel();
+        lock(&self.connection.pending).insert(id, sender);
+
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        if let Err(e) = self.connection.send(&message).await {
+            lock(&self.connection.pending).remove(&id);
+            return Err(e);
+        }
+
+        match tokio::time::timeout(REQUEST_TIMEOUT, receiver).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err("语言服务器连接已关闭".to_string()),
+            Err(_) => {
+                lock(&self.connection.pending).remove(&id);
+                let _ = self.notify("$/cancelRequest", json!({ "id": id })).await;
+                Err(format!("LSP 请求超时: {method}"))
+            }
+        }
+    }
+
+    /// 发送通知
+    pub async fn notify(&self, method: &str, params: Value) -> Result<(), String> {
+        self.connection
+            .send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+            .await
+    }
+
+    /// 将文件内容同步到服务器，返回文档 URI
+    ///
+    /// 首次访问发送 didOpen，内容变化时发送全量 didChange；
+    /// 内容变化会清空该文档已缓存的诊断，等待服务器重新推送。
+    pub async fn sync_document(
+        &self,
+        path: &Path,
+        language_id: &str,
+        text: &str,
+    ) -> Result<String, String> {
+        let uri = path_to_uri(path);
+        let mut documents = self.documents.lock().await;
+
+        match documents.get_mut(&uri) {
+            Some(doc) if doc.text == text => {}
+            Some(doc) => {
+                doc.version += 1;
+                doc.text = text.to_string();
+                lock(&self.connection.diagnostics).remove(&uri);
+                self.notify(
+                    "textDocument/didChange",
+                    json!({
+                        "textDocument": { "uri": uri, "version": doc.version },
+                        "contentChanges": [{ "text": text }]
+                    }),
+                )
+                .await?;
+            }
+            None => {
+                lock(&self.connection.diagnostics).remove(&uri);
+                self.notify(
+                    "textDocument/didOpen",
+                    json!({
+                        "textDocument": {
+                            "uri": uri,
+                            "languageId": language_id,
+                            "version": 1,
+                            "text": text
+                        }
+                    }),
+                )
+                .await?;
+                documents.insert(
+                    uri.clone(),
+                    OpenDocument {
+                        version: 1,
+                        text: text.to_string(),
+                    },
+                );
+            }
+        }
+
+        Ok(uri)
+    }
+
+    /// 等待并返回文档的诊断；超时仍未收到推送时返回 `None`
+    pub async fn wait_for_diagnostics(&self, uri: &str, wait: Duration) -> Option<Vec<Value>> {
+        let deadline = tokio::time::Instant::now() + wait;
+        loop {
+            // 先注册再检查，避免错过检查与等待之间的推送
+            let notified = self.connection.diagnostics_changed.notified();
+            if let Some(diagnostics) = lock(&self.connection.diagnostics).get(uri) {
+                return Some(diagnostics.clone());
+            }
+            if !self.is_alive() {
+                return None;
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return None;
+            }
+        }
+    }
+
+    /// 服务器是否声明了某项能力（`hoverProvider` 等）
+    pub fn has_capability(&self, name: &str) -> bool {
+        match lock(&self.capabilities).get(name) {
+            None | Some(Value::Null) | Some(Value::Bool(false)) => false,
+            Some(_) => true,
+        }
+    }
+
+    /// 服务器是否支持 `textDocument/prepareRename`
+    pub fn supports_prepare_rename(&self) -> bool {
+        lock(&self.capabilities)
+            .pointer("/renameProvider/prepareProvider")
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+    }
+
+    /// 协商得到的位置编码（initialize 之前为 UTF-16）
+    pub fn position_encoding(&self) -> PositionEncoding {
+        *lock(&self.position_encoding)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.connection.alive.load(Ordering::SeqCst)
+    }
+
+    /// 刷新最近使用时间
+    pub fn touch(&self) {
+        *lock(&self.last_used) = Instant::now();
+    }
+
+    /// 距最近一次使用的时长
+    pub fn idle_for(&self) -> Duration {
+        lock(&self.last_used).elapsed()
+    }
+
+    /// 依次发送 shutdown / exit，并结束子进程
+    pub async fn shutdown(&self) {
+        if self.is_alive() {
+            let shutdown =
+                tokio::time::timeout(SHUTDOWN_TIMEOUT, self.request("shutdown", Value::Null)).await;
+            if !matches!(shutdown, Ok(Ok(_))) {
+                tracing::debug!("[LSP] shutdown 未正常响应: {}", self.root.display());
+            }
+            let _ = self.notify("exit", Value::Null).await;
+        }
+
+        let child = lock(&self.child).take();
+        if let Some(mut child) = child {
+            if tokio::time::timeout(SHUTDOWN_TIMEOUT, child.wait())
+                .await
+                .is_err()
+            {
+                let _ = child.kill().await;
+            }
+        }
+        self.connection.close();
+        self.reader_task.abort();
+    }
+}
+
+impl Drop for LspClient {
+    fn drop(&mut self) {
+        self.reader_task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 极简的语言服务器：应答 initialize/definition，并在 didOpen 后推送诊断
+    async fn fake_server<R, W>(reader: R, mut writer: W)
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut reader = BufReader::new(reader);
+        while let Ok(Some(message)) = read_message(&mut reader).await {
+            let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+            let reply = match method {
+                "initialize" => Some(json!({
+                    "jsonrpc": "2.0",
+                    "id": message["id"],
+                    "result": {
+                        "capabilities": { "definitionProvider": true, "positionEncoding": "utf-16" }
+                    }
+                })),
+                "textDocument/definition" => Some(json!({
+                    "jsonrpc": "2.0",
+                    "id": message["id"],
+                    "result": [{
+                        "uri": message["params"]["textDocument"]["uri"],
+                        "range": {
+                            "start": { "line": 3, "character": 4 },
+                            "end": { "line": 3, "character": 8 }
+                        }
+                    }]
+                })),
+                "textDocument/didOpen" => {
+                    // 先发一个服务端请求，客户端需要应答
+                    let request = json!({
+                        "jsonrpc": "2.0",
+                        "id": "cfg",
+                        "method": "workspace/configuration",
+                        "params": { "items": [{}, {}] }
+                    });
+                    writer.write_all(&encode_message(&request)).await.unwrap();
+                    Some(json!({
+                        "jsonrpc": "2.0",
+                        "method": "textDocument/publishDiagnostics",
+                        "params": {
+                            "uri": message["params"]["textDocument"]["uri"],
+                            "diagnostics": [{ "message": "unused variable", "severity": 2 }]
+                        }
+                    }))
+                }
+                "shutdown" => {
+                    Some(json!({ "jsonrpc": "2.0", "id": message["id"], "result": null }))
+                }
+                "exit" => break,
+                _ => None,
+            };
+            if let Some(reply) = reply {
+                writer.write_all(&encode_message(&reply)).await.unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_framing_roundtrip() {
+        let message = json!({ "jsonrpc": "2.0", "method": "中文", "params": [1, 2] });
+        let mut bytes = b"Content-Type: application/vscode-jsonrpc\r\n".to_vec();
+        bytes.extend(encode_message(&message));
+        bytes.extend(encode_message(&json!({ "id": 2 })));
+
+        let mut reader = BufReader::new(bytes.as_slice());
+        assert_eq!(read_message(&mut reader).await.unwrap(), Some(message));
+        assert_eq!(
+            read_message(&mut reader).await.unwrap(),
+            Some(json!({ "id": 2 }))
+        );
+        assert_eq!(read_message(&mut reader).await.unwrap(), None);
+    }
+
+    #[test]
+    fn test_uri_roundtrip() {
+        let path = Path::new("/tmp/my project/中文.rs");
+        let uri = path_to_uri(path);
+        assert_eq!(uri, "file:///tmp/my%20project/%E4%B8%AD%E6%96%87.rs");
+        assert_eq!(uri_to_path(&uri).unwrap(), path);
+        assert_eq!(
+            uri_to_path("file:///C:/work/a.ts").unwrap(),
+            PathBuf::from("C:/work/a.ts")
+        );
+        assert!(uri_to_path("untitled:foo").is_none());
+    }
+
+    #[test]
+    fn test_position_encoding_columns() {
+        assert_eq!(
+            PositionEncoding::from_capabilities(&json!({ "positionEncoding": "utf-8" })),
+            PositionEncoding::Utf8
+        );
+        assert_eq!(
+            PositionEncoding::from_capabilities(&json!({})),
+            PositionEncoding::Utf16
+        );
+
+        // “中”占 3 字节 / 1 个 UTF-16 单元，“😀”占 4 字节 / 2 个 UTF-16 单元
+        let line = "let 中😀x = 1;";
+        let x = line.find('x').unwrap() as u32;
+        assert_eq!(PositionEncoding::Utf16.column_from_utf8(line, x), 7);
+        assert_eq!(PositionEncoding::Utf32.column_from_utf8(line, x), 6);
+        assert_eq!(PositionEncoding::Utf8.column_from_utf8(line, x), x);
+        assert_eq!(PositionEncoding::Utf16.column_to_utf8(line, 7), x);
+        assert_eq!(PositionEncoding::Utf32.column_to_utf8(line, 6), x);
+        assert_eq!(
+            PositionEncoding::Utf16.column_to_utf8(line, 100),
+            line.len() as u32
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_lifecycle_against_fake_server() {
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        let (client_read, client_write) = tokio::io::split(client_io);
+        let (server_read, server_write) = tokio::io::split(server_io);
+        let server = tokio::spawn(fake_server(server_read, server_write));
+
+        let client = LspClient::connect(client_read, client_write, PathBuf::from("/ws"));
+        client.initialize().await.unwrap();
+        assert_eq!(client.position_encoding(), PositionEncoding::Utf16);
+        assert!(client.has_capability("definitionProvider"));
+        assert!(!client.has_capability("hoverProvider"));
+
+        let path = Path::new("/ws/src/main.rs");
+        let uri = client
+            .sync_document(path, "rust", "fn main() {}")
+            .await
+            .unwrap();
+        let diagnostics = client
+            .wait_for_diagnostics(&uri, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(diagnostics.len(), 1);
+
+        let result = client
+            .request(
+                "textDocument/definition",
+                json!({
+                    "textDocument": { "uri": uri },
+                    "position": { "line": 0, "character": 3 }
+                }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result[0]["range"]["start"]["line"], 3);
+
+        // 内容未变时不重复发送 didOpen，诊断缓存保留
+        client
+            .sync_document(path, "rust", "fn main() {}")
+            .await
+            .unwrap();
+        assert!(client
+            .wait_for_diagnostics(&uri, Duration::from_millis(10))
+            .await
+            .is_some());
+
+        client.shutdown().await;
+        assert!(!client.is_alive());
+        server.await.unwrap();
+    }
+}
@@ -0,0 +1,1051 @@
+//! POSIX shell 命令解析
+//!
+//! 面向安全分析的轻量解析器：不做求值，只还原命令结构——
+//! 列表与管道、子 shell、花括号组、函数定义、重定向（含 heredoc），
+//! 以及 `$(…)`、反引号、`<(…)`/`>(…)` 中嵌套的命令。
+//! 控制结构关键字（if/while/for/case 等）视为分隔符，其中的命令照常收集。
+
+/// 解析错误
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{0}")]
+pub struct ShellParseError(String);
+
+/// 命令替换的最大嵌套深度
+const MAX_DEPTH: usize = 16;
+
+/// 保留字（仅在命令开头且未加引号时生效）
+const SEPARATOR_KEYWORDS: &[&str] = &[
+    "if", "then", "elif", "else", "fi", "do", "done", "while", "until", "!", "time",
+];
+
+/// 命令序列（`;`、`&&`、`||`、换行连接的管道）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Script {
+    pub pipelines: Vec<Pipeline>,
+}
+
+/// 管道
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Pipeline {
+    pub commands: Vec<Command>,
+    /// 以 `&` 结尾，后台执行
+    pub background: bool,
+}
+
+/// 单个命令
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Simple(SimpleCommand),
+    /// `( … )`
+    Subshell {
+        body: Script,
+        redirects: Vec<Redirect>,
+    },
+    /// `{ …; }`
+    Group {
+        body: Script,
+        redirects: Vec<Redirect>,
+    },
+    /// `name() { … }` 或 `function name { … }`
+    Function {
+        name: String,
+        body: Box<Command>,
+    },
+}
+
+/// 简单命令
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimpleCommand {
+    /// 命令前的 `NAME=value`
+    pub assignments: Vec<Word>,
+    pub words: Vec<Word>,
+    pub redirects: Vec<Redirect>,
+}
+
+/// 单词
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Word {
+    /// 源码原文
+    pub raw: String,
+    /// 去除引号和转义后的值；变量与命令替换保留原文
+    pub value: String,
+    /// 是否包含引号或转义
+    pub quoted: bool,
+    /// 是否包含变量/算术展开
+    pub has_expansion: bool,
+    /// 是否包含未加引号的通配符
+    pub has_glob: bool,
+    /// 嵌套的命令替换与进程替换
+    pub substitutions: Vec<Script>,
+}
+
+impl Word {
+    /// 值在运行时才能确定
+    pub fn is_dynamic(&self) -> bool {
+        self.has_expansion || !self.substitutions.is_empty()
+    }
+}
+
+/// 重定向操作符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectOp {
+    /// `<`
+    Input,
+    /// `>`
+    Output,
+    /// `>>`
+    Append,
+    /// `>|`
+    Clobber,
+    /// `&>`
+    OutputAll,
+    /// `&>>`
+    AppendAll,
+    /// `>&`
+    DupOutput,
+    /// `<&`
+    DupInput,
+    /// `<>`
+    ReadWrite,
+    /// `<<` / `<<-`
+    HereDoc,
+    /// `<<<`
+    HereString,
+}
+
+impl RedirectOp {
+    /// 是否会写入目标文件
+    pub fn writes(&self) -> bool {
+        matches!(
+            self,
+            RedirectOp::Output
+                | RedirectOp::Append
+                | RedirectOp::Clobber
+                | RedirectOp::OutputAll
+                | RedirectOp::AppendAll
+                | RedirectOp::ReadWrite
+                | RedirectOp::DupOutput
+        )
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RedirectOp::Input => "<",
+            RedirectOp::Output => ">",
+            RedirectOp::Append => ">>",
+            RedirectOp::Clobber => ">|",
+            RedirectOp::OutputAll => "&>",
+            RedirectOp::AppendAll => "&>>",
+            RedirectOp::DupOutput => ">&",
+            RedirectOp::DupInput => "<&",
+            RedirectOp::ReadWrite => "<>",
+            RedirectOp::HereDoc => "<<",
+            RedirectOp::HereString => "<<<",
+        }
+    }
+}
+
+/// 重定向
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redirect {
+    pub fd: Option<u32>,
+    pub op: RedirectOp,
+    pub target: Word,
+    /// heredoc 正文
+    pub heredoc: Option<String>,
+}
+
+/// 解析命令
+pub fn parse(source: &str) -> Result<Script, ShellParseError> {
+    parse_with_depth(source, 0)
+}
+
+/// 列出命令实际使用的控制/重定向操作符（不含引号内的字符）
+pub fn operators(source: &str) -> Result<Vec<String>, ShellParseError> {
+    let lexer = Lexer::new(source, 0);
+    let (_, operators) = lexer.lex()?;
+    Ok(operators.into_iter().map(str::to_string).collect())
+}
+
+fn parse_with_depth(source: &str, depth: usize) -> Result<Script, ShellParseError> {
+    if depth > MAX_DEPTH {
+        return Err(ShellParseError("命令替换嵌套过深".to_string()));
+    }
+    let (tokens, _) = Lexer::new(source, depth).lex()?;
+    Parser {
+        tokens,
+        pos: 0,
+        case_depth: 0,
+    }
+    .parse_script(End::Eof)
+}
+
+// ============================================================================
+// 词法分析
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(Word),
+    Op(&'static str),
+    Redirect {
+        fd: Option<u32>,
+        op: RedirectOp,
+        heredoc: Option<String>,
+    },
+    Newline,
+}
+
+struct PendingHeredoc {
+    token_index: usize,
+    delimiter: String,
+    strip_tabs: bool,
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize,
+    tokens: Vec<Token>,
+    operators: Vec<&'static str>,
+    /// 刚读到 `<<`，下一个单词是 heredoc 结束符（token 下标, 是否去除前导 tab）
+    expect_delimiter: Option<(usize, bool)>,
+    pending_heredocs: Vec<PendingHeredoc>,
+}
+
+impl Lexer {
+    fn new(source: &str, depth: usize) -> Self {
+        Self {
+            chars: source.chars().collect(),
+            pos: 0,
+            depth,
+            tokens: Vec::new(),
+            operators: Vec::new(),
+            expect_delimiter: None,
+            pending_heredocs: Vec::new(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_at(i) == Some(c))
+    }
+
+    fn record_operator(&mut self, op: &'static str) {
+        if !self.operators.contains(&op) {
+            self.operators.push(op);
+        }
+    }
+
+    fn lex(mut self) -> Result<(Vec<Token>, Vec<&'static str>), ShellParseError> {
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\r' => self.pos += 1,
+                '\\' if self.peek_at(1) == Some('\n') => self.pos += 2,
+                '\n' => {
+                    self.pos += 1;
+                    self.tokens.push(Token::Newline);
+                    self.read_heredoc_bodies();
+                }
+                '#' => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.pos += 1;
+                    }
+                }
+                '&' | '|' | ';' | '(' | ')' => self.lex_operator(),
+                '<' | '>' if self.peek_at(1) != Some('(') => self.lex_redirect(None),
+                c if c.is_ascii_digit() && self.is_fd_redirect() => {
+                    let start = self.pos;
+                    while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                        self.pos += 1;
+                    }
+                    let fd = self.chars[start..self.pos]
+                        .iter()
+                        .collect::<String>()
+                        .parse()
+                        .ok();
+                    self.lex_redirect(fd);
+                }
+                _ => {
+                    let word = self.lex_word()?;
+                    if let Some((token_index, strip_tabs)) = self.expect_delimiter.take() {
+                        self.pending_heredocs.push(PendingHeredoc {
+                            token_index,
+                            delimiter: word.value.clone(),
+                            strip_tabs,
+                        });
+                    }
+                    self.tokens.push(Token::Word(word));
+                }
+            }
+        }
+        Ok((self.tokens, self.operators))
+    }
+
+    /// 形如 `2>`、`10<` 的文件描述符重定向
+    fn is_fd_redirect(&self) -> bool {
+        let mut i = self.pos;
+        while self.chars.get(i).is_some_and(|c| c.is_ascii_digit()) {
+            i += 1;
+        }
+        matches!(self.chars.get(i), Some('<') | Some('>')) && self.chars.get(i + 1) != Some(&'(')
+    }
+
+    fn lex_operator(&mut self) {
+        if self.starts_with("&>>") {
+            self.pos += 3;
+            return self.push_redirect(None, RedirectOp::AppendAll);
+        }
+        if self.starts_with("&>") {
+            self.pos += 2;
+            return self.push_redirect(None, RedirectOp::OutputAll);
+        }
+        for op in ["&&", "||", "|&", ";;", "&", "|", ";", "(", ")"] {
+            if self.starts_with(op) {
+                self.pos += op.chars().count();
+                self.record_operator(op);
+                self.tokens.push(Token::Op(op));
+                return;
+            }
+        }
+    }
+
+    fn lex_redirect(&mut self, fd: Option<u32>) {
+        let ops: [(&str, RedirectOp); 10] = [
+            ("<<<", RedirectOp::HereString),
+            ("<<-", RedirectOp::HereDoc),
+            ("<<", RedirectOp::HereDoc),
+            ("<&", RedirectOp::DupInput),
+            ("<>", RedirectOp::ReadWrite),
+            ("<", RedirectOp::Input),
+            (">>", RedirectOp::Append),
+            (">&", RedirectOp::DupOutput),
+            (">|", RedirectOp::Clobber),
+            (">", RedirectOp::Output),
+        ];
+        for (text, op) in ops {
+            if self.starts_with(text) {
+                self.pos += text.len();
+                if op == RedirectOp::HereDoc {
+                    self.expect_delimiter = Some((self.tokens.len(), text == "<<-"));
+                }
+                return self.push_redirect(fd, op);
+            }
+        }
+    }
+
+    fn push_redirect(&mut self, fd: Option<u32>, op: RedirectOp) {
+        self.record_operator(op.as_str());
+        self.tokens.push(Token::Redirect {
+            fd,
+            op,
+            heredoc: None,
+        });
+    }
+
+    fn read_heredoc_bodies(&mut self) {
+        for pending in std::mem::take(&mut self.pending_heredocs) {
+            let mut body = String::new();
+            while self.pos < self.chars.len() {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.pos += 1;
+                }
+                let line: String = self.chars[start..self.pos].iter().collect();
+                if self.peek() == Some('\n') {
+                    self.pos += 1;
+                }
+                let check = if pending.strip_tabs {
+                    line.trim_start_matches('\t')
+                } else {
+                    line.as_str()
+                };
+                if check == pending.delimiter {
+                    break;
+                }
+                body.push_str(&line);
+                body.push('\n');
+            }
+            if let Some(Token::Redirect { heredoc, .. }) = self.tokens.get_mut(pending.token_index)
+            {
+                *heredoc = Some(body);
+            }
+        }
+    }
+
+    fn lex_word(&mut self) -> Result<Word, ShellParseError> {
+        let start = self.pos;
+        let mut word = Word::default();
+
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\r' | '\n' | '|' | '&' | ';' | '(' | ')' => break,
+                '<' | '>' => {
+                    if self.peek_at(1) != Some('(') {
+                        break;
+                    }
+                    // 进程替换 <(…) / >(…)
+                    self.record_operator(if c == '<' { "<(" } else { ">(" });
+                    self.pos += 1;
+                    self.lex_paren_substitution(&mut word)?;
+                }
+                '\'' => {
+                    word.quoted = true;
+                    self.pos += 1;
+                    loop {
+                        match self.peek() {
+                            Some('\'') => {
+                                self.pos += 1;
+                                break;
+                            }
+                            Some(c) => {
+                                word.value.push(c);
+                                self.pos += 1;
+                            }
+                            None => return Err(ShellParseError("单引号未闭合".to_string())),
+                        }
+                    }
+                }
+                '"' => {
+                    word.quoted = true;
+                    self.pos += 1;
+                    self.lex_double_quoted(&mut word)?;
+                }
+                '\\' => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some('\n') => self.pos += 1,
+                        Some(c) => {
+                            word.quoted = true;
+                            word.value.push(c);
+                            self.pos += 1;
+                        }
+                        None => word.value.push('\\'),
+                    }
+                }
+                '$' => self.lex_dollar(&mut word)?,
+                '`' => self.lex_backtick(&mut word)?,
+                '*' | '?' | '[' => {
+                    word.has_glob = true;
+                    word.value.push(c);
+                    self.pos += 1;
+                }
+                _ => {
+                    word.value.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+
+        word.raw = self.chars[start..self.pos].iter().collect();
+        Ok(word)
+    }
+
+    fn lex_double_quoted(&mut self, word: &mut Word) -> Result<(), ShellParseError> {
+        loop {
+            match self.peek() {
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                Some('\\') => {
+                    match self.peek_at(1) {
+                        Some('\n') => {}
+                        Some(c @ ('$' | '`' | '"' | '\\')) => word.value.push(c),
+                        Some(c) => {
+                            word.value.push('\\');
+                            word.value.push(c);
+                        }
+                        None => return Err(ShellParseError("双引号未闭合".to_string())),
+                    }
+                    self.pos += 2;
+                }
+                Some('$') => self.lex_dollar(word)?,
+                Some('`') => self.lex_backtick(word)?,
+                Some(c) => {
+                    word.value.push(c);
+                    self.pos += 1;
+                }
+                None => return Err(ShellParseError("双引号未闭合".to_string())),
+            }
+        }
+    }
+
+    /// 当前位置为 `$`
+    fn lex_dollar(&mut self, word: &mut Word) -> Result<(), ShellParseError> {
+        let start = self.pos;
+        match self.peek_at(1) {
+            Some('(') if self.peek_at(2) == Some('(') => {
+                // 算术展开 $((…))
+                let close = self.find_closing_paren(self.pos + 1)?;
+                self.pos = close + 1;
+                word.has_expansion = true;
+            }
+            Some('(') => {
+                self.record_operator("$(");
+                self.pos += 1;
+                self.lex_paren_substitution(word)?;
+                return Ok(());
+            }
+            Some('{') => {
+                let mut depth = 0usize;
+                let mut i = self.pos + 1;
+                loop {
+                    match self.chars.get(i) {
+                        Some('{') => depth += 1,
+                        Some('}') => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        Some(_) => {}
+                        None => return Err(ShellParseError("${ 未闭合".to_string())),
+                    }
+                    i += 1;
+                }
+                self.pos = i + 1;
+                word.has_expansion = true;
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                self.pos += 1;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+                {
+                    self.pos += 1;
+                }
+                word.has_expansion = true;
+            }
+            Some(c) if c.is_ascii_digit() || "@*#?-$!".contains(c) => {
+                self.pos += 2;
+                word.has_expansion = true;
+            }
+            Some('\'') => {
+                // ANSI-C 字符串 $'…'
+                word.quoted = true;
+                self.pos += 2;
+                loop {
+                    match self.peek() {
+                        Some('\\') => {
+                            if let Some(c) = self.peek_at(1) {
+                                word.value.push(c);
+                            }
+                            self.pos += 2;
+                        }
+                        Some('\'') => {
+                            self.pos += 1;
+                            return Ok(());
+                        }
+                        Some(c) => {
+                            word.value.push(c);
+                            self.pos += 1;
+                        }
+                        None => return Err(ShellParseError("$' 未闭合".to_string())),
+                    }
+                }
+            }
+            _ => {
+                word.value.push('$');
+                self.pos += 1;
+                return Ok(());
+            }
+        }
+        word.value
+            .extend(self.chars[start..self.pos.min(self.chars.len())].iter());
+        Ok(())
+    }
+
+    /// 当前位置为 `(`，解析其中的命令并作为替换挂到单词上
+    fn lex_paren_substitution(&mut self, word: &mut Word) -> Result<(), ShellParseError> {
+        let open = self.pos;
+        let close = self.find_closing_paren(open)?;
+        let inner: String = self.chars[open + 1..close].iter().collect();
+        word.substitutions
+            .push(parse_with_depth(&inner, self.depth + 1)?);
+        // 值中保留替换原文（含前导 $ / < / >）
+        let prefix_start = open.saturating_sub(1);
+        word.value.extend(self.chars[prefix_start..=close].iter());
+        self.pos = close + 1;
+        Ok(())
+    }
+
+    /// 当前位置为反引号
+    fn lex_backtick(&mut self, word: &mut Word) -> Result<(), ShellParseError> {
+        self.record_operator("`");
+        let start = self.pos;
+        self.pos += 1;
+        let mut inner = String::new();
+        loop {
+            match self.peek() {
+                Some('`') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some('\\') if matches!(self.peek_at(1), Some('`' | '\\' | '$')) => {
+                    inner.push(self.peek_at(1).unwrap_or_default());
+                    self.pos += 2;
+                }
+                Some(c) => {
+                    inner.push(c);
+                    self.pos += 1;
+                }
+                None => return Err(ShellParseError("反引号未闭合".to_string())),
+            }
+        }
+        word.substitutions
+            .push(parse_with_depth(&inner, self.depth + 1)?);
+        word.value.extend(self.chars[start..self.pos].iter());
+        Ok(())
+    }
+
+    /// 从 `open`（指向 `(`）开始查找匹配的 `)`，跳过引号内容
+    fn find_closing_paren(&self, open: usize) -> Result<usize, ShellParseError> {
+        let mut depth = 0usize;
+        let mut i = open;
+        while let Some(&c) = self.chars.get(i) {
+            match c {
+                '\\' => i += 1,
+                '\'' => {
+                    i += 1;
+                    while self.chars.get(i).is_some_and(|&c| c != '\'') {
+                        i += 1;
+                    }
+                }
+                '"' => {
+                    i += 1;
+                    while let Some(&c) = self.chars.get(i) {
+                        if c == '\\' {
+                            i += 1;
+                        } else if c == '"' {
+                            break;
+                        }
+                        i += 1;
+                    }
+                }
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(i);
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        Err(ShellParseError("括号未闭合".to_string()))
+    }
+}
+
+// ============================================================================
+// 语法分析
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum End {
+    Eof,
+    RParen,
+    RBrace,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// 位于 case … esac 内部，`pattern)` 需要跳过
+    case_depth: usize,
+}
+
+fn is_keyword(token: Option<&Token>, keyword: &str) -> bool {
+    matches!(token, Some(Token::Word(w)) if !w.quoted && w.value == keyword)
+}
+
+fn is_assignment(word: &Word) -> bool {
+    let Some((name, _)) = word.raw.split_once('=') else {
+        return false;
+    };
+    let name = name.strip_suffix('+').unwrap_or(name);
+    let name = name.split('[').next().unwrap_or(name);
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    fn skip_newlines(&mut self) {
+        while matches!(self.peek(), Some(Token::Newline)) {
+            self.pos += 1;
+        }
+    }
+
+    fn parse_script(&mut self, end: End) -> Result<Script, ShellParseError> {
+        let mut script = Script::default();
+        loop {
+            let before = self.pos;
+            match self.peek() {
+                None => {
+                    if end == End::Eof {
+                        break;
+                    }
+                    return Err(ShellParseError(
+                        if end == End::RParen {
+                            "缺少 )"
+                        } else {
+                            "缺少 }"
+                        }
+                        .to_string(),
+                    ));
+                }
+                Some(Token::Newline) | Some(Token::Op(";" | ";;" | "&&" | "||")) => {
+                    self.pos += 1;
+                    continue;
+                }
+                Some(Token::Op(")")) => {
+                    if end == End::RParen {
+                        break;
+                    }
+                    return Err(ShellParseError("多余的 )".to_string()));
+                }
+                Some(token) if end == End::RBrace && is_keyword(Some(token), "}") => break,
+                _ => {}
+            }
+
+            let pipeline = self.parse_pipeline()?;
+            if !pipeline.commands.is_empty() {
+                script.pipelines.push(pipeline);
+            }
+            if matches!(self.peek(), Some(Token::Op("&"))) {
+                self.pos += 1;
+                if let Some(last) = script.pipelines.last_mut() {
+                    last.background = true;
+                }
+            }
+            if self.pos == before {
+                return Err(ShellParseError(format!(
+                    "无法解析的符号: {:?}",
+                    self.peek()
+                )));
+            }
+        }
+        Ok(script)
+    }
+
+    fn parse_pipeline(&mut self) -> Result<Pipeline, ShellParseError> {
+        let mut pipeline = Pipeline::default();
+        loop {
+            if let Some(command) = self.parse_command()? {
+                pipeline.commands.push(command);
+            }
+            if matches!(self.peek(), Some(Token::Op("|" | "|&"))) {
+                self.pos += 1;
+                self.skip_newlines();
+                continue;
+            }
+            break;
+        }
+        Ok(pipeline)
+    }
+
+    fn parse_command(&mut self) -> Result<Option<Command>, ShellParseError> {
+        if self.case_depth > 0 && self.skip_case_pattern() {
+            return Ok(None);
+        }
+
+        match self.peek() {
+            Some(Token::Op("(")) => {
+                self.pos += 1;
+                let body = self.parse_script(End::RParen)?;
+                self.pos += 1; // )
+                let redirects = self.parse_redirects()?;
+                Ok(Some(Command::Subshell { body, redirects }))
+            }
+            token if is_keyword(token, "{") => {
+                self.pos += 1;
+                let body = self.parse_script(End::RBrace)?;
+                self.pos += 1; // }
+                let redirects = self.parse_redirects()?;
+                Ok(Some(Command::Group { body, redirects }))
+            }
+            token if is_keyword(token, "function") => {
+                self.pos += 1;
+                let name = match self.peek() {
+                    Some(Token::Word(w)) => w.value.clone(),
+                    _ => return Err(ShellParseError("function 缺少函数名".to_string())),
+                };
+                self.pos += 1;
+                if matches!(self.peek(), Some(Token::Op("(")))
+                    && matches!(self.peek_at(1), Some(Token::Op(")")))
+                {
+                    self.pos += 2;
+                }
+                self.parse_function_body(name)
+            }
+            Some(Token::Word(w))
+                if matches!(self.peek_at(1), Some(Token::Op("(")))
+                    && matches!(self.peek_at(2), Some(Token::Op(")"))) =>
+            {
+                let name = w.value.clone();
+                self.pos += 3;
+                self.parse_function_body(name)
+            }
+            _ => self.parse_simple(),
+        }
+    }
+
+    fn parse_function_body(&mut self, name: String) -> Result<Option<Command>, ShellParseError> {
+        self.skip_newlines();
+        let body = self
+            .parse_command()?
+            .ok_or_else(|| ShellParseError(format!("函数 {name} 缺少函数体")))?;
+        Ok(Some(Command::Function {
+            name,
+            body: Box::new(body),
+        }))
+    }
+
+    /// case 分支的 `pattern)` / `(pattern)`
+    fn skip_case_pattern(&mut self) -> bool {
+        let mut i = self.pos;
+        if matches!(self.tokens.get(i), Some(Token::Op("("))) {
+            i += 1;
+        }
+        if !matches!(self.tokens.get(i), Some(Token::Word(_))) {
+            return false;
+        }
+        while matches!(
+            self.tokens.get(i),
+            Some(Token::Word(_)) | Some(Token::Op("|"))
+        ) {
+            i += 1;
+        }
+        if matches!(self.tokens.get(i), Some(Token::Op(")"))) {
+            self.pos = i + 1;
+            return true;
+        }
+        false
+    }
+
+    fn parse_redirects(&mut self) -> Result<Vec<Redirect>, ShellParseError> {
+        let mut redirects = Vec::new();
+        while matches!(self.peek(), Some(Token::Redirect { .. })) {
+            redirects.push(self.parse_redirect()?);
+        }
+        Ok(redirects)
+    }
+
+    fn parse_redirect(&mut self) -> Result<Redirect, ShellParseError> {
+        let Some(Token::Redirect { fd, op, heredoc }) = self.peek().cloned() else {
+            return Err(ShellParseError("期望重定向".to_string()));
+        };
+        self.pos += 1;
+        let target = match self.peek() {
+            Some(Token::Word(w)) => w.clone(),
+            _ => return Err(ShellParseError(format!("重定向 {} 缺少目标", op.as_str()))),
+        };
+        self.pos += 1;
+        Ok(Redirect {
+            fd,
+            op,
+            target,
+            heredoc,
+        })
+    }
+
+    fn parse_simple(&mut self) -> Result<Option<Command>, ShellParseError> {
+        let mut command = SimpleCommand::default();
+        loop {
+            match self.peek() {
+                Some(Token::Word(word)) => {
+                    if command.words.is_empty() && is_assignment(word) {
+                        command.assignments.push(word.clone());
+                        self.pos += 1;
+                        continue;
+                    }
+                    if command.words.is_empty() && !word.quoted {
+                        match word.value.as_str() {
+                            kw if SEPARATOR_KEYWORDS.contains(&kw) => {
+                                self.pos += 1;
+                                continue;
+                            }
+                            "case" => {
+                                // 跳过 `case word in`
+                                while let Some(token) = self.peek() {
+                                    let done = is_keyword(Some(token), "in");
+                                    self.pos += 1;
+                                    if done {
+                                        break;
+                                    }
+                                }
+                                self.case_depth += 1;
+                                return Ok(None);
+                            }
+                            "esac" => {
+                                self.pos += 1;
+                                self.case_depth = self.case_depth.saturating_sub(1);
+                                continue;
+                            }
+                            "for" | "select" => {
+                                // 跳过循环头 `for x in a b c`
+                                while matches!(self.peek(), Some(Token::Word(_))) {
+                                    self.pos += 1;
+                                }
+                                return Ok(None);
+                            }
+                            _ => {}
+                        }
+                    }
+                    command.words.push(word.clone());
+                    self.pos += 1;
+                }
+                Some(Token::Redirect { .. }) => {
+                    let redirect = self.parse_redirect()?;
+                    command.redirects.push(redirect);
+                }
+                _ => break,
+            }
+        }
+
+        if command.words.is_empty()
+            && command.assignments.is_empty()
+            && command.redirects.is_empty()
+        {
+            Ok(None)
+        } else {
+            Ok(Some(Command::Simple(command)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simple(command: &Command) -> &SimpleCommand {
+        match command {
+            Command::Simple(simple) => simple,
+            other => panic!("expected simple command, got {other:?}"),
+        }
+    }
+
+    fn values(command: &Command) -> Vec<&str> {
+        simple(command)
+            .words
+            .iter()
+            .map(|w| w.value.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_quotes_and_escapes() {
+        let script = parse(r#"echo 'a | b' "c $HOME" d\;e"#).unwrap();
+        let command = &script.pipelines[0].commands[0];
+        assert_eq!(values(command), vec!["echo", "a | b", "c $HOME", "d;e"]);
+        assert!(simple(command).words[2].has_expansion);
+        assert_eq!(script.pipelines.len(), 1);
+    }
+
+    #[test]
+    fn test_lists_pipelines_and_background() {
+        let script = parse("cd src && ls | grep rs; sleep 1 &\nmake || true").unwrap();
+        // && 与 || 连接的管道展开为并列项
+        assert_eq!(script.pipelines.len(), 5);
+        assert_eq!(script.pipelines[1].commands.len(), 2);
+        assert!(script.pipelines[2].background);
+    }
+
+    #[test]
+    fn test_nested_substitutions() {
+        let script = parse(r#"echo "$(cat `which foo` | rm -rf /)" <(curl x)"#).unwrap();
+        let words = &simple(&script.pipelines[0].commands[0]).words;
+        let outer = &words[1].substitutions[0];
+        assert_eq!(outer.pipelines[0].commands.len(), 2);
+        assert_eq!(
+            values(&outer.pipelines[0].commands[1]),
+            vec!["rm", "-rf", "/"]
+        );
+        let cat = simple(&outer.pipelines[0].commands[0]);
+        assert_eq!(cat.words[1].substitutions.len(), 1);
+        assert_eq!(words[2].substitutions.len(), 1);
+    }
+
+    #[test]
+    fn test_redirects_and_heredoc() {
+        let script = parse("cat <<'EOF' > out.txt 2>&1\nrm -rf /\nEOF\necho done").unwrap();
+        let cat = simple(&script.pipelines[0].commands[0]);
+        assert_eq!(cat.redirects.len(), 3);
+        assert_eq!(cat.redirects[0].op, RedirectOp::HereDoc);
+        assert_eq!(cat.redirects[0].heredoc.as_deref(), Some("rm -rf /\n"));
+        assert_eq!(cat.redirects[1].target.value, "out.txt");
+        assert_eq!(cat.redirects[2].fd, Some(2));
+        assert_eq!(cat.redirects[2].op, RedirectOp::DupOutput);
+        assert_eq!(script.pipelines.len(), 2);
+    }
+
+    #[test]
+    fn test_compound_commands() {
+        let script = parse(":(){ :|:& };:").unwrap();
+        match &script.pipelines[0].commands[0] {
+            Command::Function { name, body } => {
+                assert_eq!(name, ":");
+                assert!(matches!(**body, Command::Group { .. }));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+
+        let script = parse("if [ -d x ]; then (cd x && rm -r y); fi").unwrap();
+        assert_eq!(values(&script.pipelines[0].commands[0])[0], "[");
+        assert!(matches!(
+            script.pipelines[1].commands[0],
+            Command::Subshell { .. }
+        ));
+
+        let script = parse("case $1 in\n  a|b) rm x ;;\n  *) ls ;;\nesac").unwrap();
+        let names: Vec<&str> = script
+            .pipelines
+            .iter()
+            .map(|p| values(&p.commands[0])[0])
+            .collect();
+        assert_eq!(names, vec!["rm", "ls"]);
+    }
+
+    #[test]
+    fn test_assignments() {
+        let script = parse("FOO=1 BAR=\"x y\" env").unwrap();
+        let command = simple(&script.pipelines[0].commands[0]);
+        assert_eq!(command.assignments.len(), 2);
+        assert_eq!(values(&script.pipelines[0].commands[0]), vec!["env"]);
+    }
+
+    #[test]
+    fn test_operators_ignore_quoted() {
+        assert!(operators("echo 'a && b' \"|\"").unwrap().is_empty());
+        assert_eq!(operators("a && b | c > f").unwrap(), vec!["&&", "|", ">"]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("echo 'unterminated").is_err());
+        assert!(parse("echo $(ls").is_err());
+        assert!(parse("(ls").is_err());
+        assert!(parse("ls >").is_err());
+    }
+}
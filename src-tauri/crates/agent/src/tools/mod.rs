@@ -4,9 +4,11 @@
 
 pub mod browser_tool;
 pub mod heartbeat_tool;
+pub mod terminal_history_tool;
 
 pub use browser_tool::{BrowserAction, BrowserTool, BrowserToolError, BrowserToolResult};
 pub use heartbeat_tool::{
     HeartbeatCycleResult, HeartbeatExecutionRecord, HeartbeatService, HeartbeatStatus,
     HeartbeatTaskPreview, HeartbeatTool, HeartbeatToolError,
 };
+pub use terminal_history_tool::TerminalHistoryTool;
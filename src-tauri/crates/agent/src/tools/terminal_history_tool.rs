@@ -0,0 +1,248 @@
+//! Terminal History Tool
+//!
+//! 为 Aster Agent 提供终端命令历史查询能力：搜索历史命令与输出、
+//! 获取最近执行的命令，以及获取“最近一次失败命令的输出”。
+
+use aster::tools::{Tool, ToolContext, ToolError, ToolResult};
+use async_trait::async_trait;
+use proxycast_terminal::{CommandHistoryStore, CommandRecord, CommandSearchHit};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// 返回给模型的单条命令输出上限（字符数，保留末尾）
+const MAX_OUTPUT_CHARS: usize = 8000;
+
+/// Terminal History Tool 实现
+pub struct TerminalHistoryTool {
+    store: Arc<CommandHistoryStore>,
+}
+
+impl TerminalHistoryTool {
+    /// 创建新的 TerminalHistoryTool
+    pub fn new(store: Arc<CommandHistoryStore>) -> Self {
+        Self { store }
+    }
+
+    /// 截取输出末尾，避免超长输出占满上下文
+    fn tail_output(output: &str) -> String {
+        let total = output.chars().count();
+        if total <= MAX_OUTPUT_CHARS {
+            return output.to_string();
+        }
+        let tail: String = output.chars().skip(total - MAX_OUTPUT_CHARS).collect();
+        format!("…（省略前 {} 个字符）\n{}", total - MAX_OUTPUT_CHARS, tail)
+    }
+
+    /// 格式化命令摘要行
+    fn format_summary(record: &CommandRecord) -> String {
+        let exit = record
+            .exit_code
+            .map(|code| code.to_string())
+            .unwrap_or_else(|| "未知".to_string());
+        format!(
+            "[{}] {}  (退出码: {}, 耗时: {}ms, 目录: {}, 会话: {})",
+            record.id,
+            record.command,
+            exit,
+            record.duration_ms,
+            record.cwd.as_deref().unwrap_or("未知"),
+            record.block_id
+        )
+    }
+
+    /// 格式化命令详情（含输出）
+    fn format_detail(record: &CommandRecord) -> String {
+        let output = if record.output.is_empty() {
+            "（无输出）".to_string()
+        } else {
+            Self::tail_output(&record.output)
+        };
+        format!("{}\n输出:\n{}", Self::format_summary(record), output)
+    }
+
+    /// 格式化搜索结果
+    fn format_hits(query: &str, hits: &[CommandSearchHit]) -> String {
+        if hits.is_empty() {
+            return format!("没有找到匹配 \"{}\" 的终端命令", query);
+        }
+
+        let mut lines = vec![format!("搜索结果 (共 {} 条):", hits.len())];
+        for hit in hits {
+            lines.push(Self::format_summary(&hit.record));
+            lines.push(format!("  片段: {}", hit.snippet.replace('\n', " ")));
+        }
+        lines.join("\n")
+    }
+
+    /// 格式化最近命令列表
+    fn format_recent(records: &[CommandRecord]) -> String {
+        if records.is_empty() {
+            return "暂无终端命令记录".to_string();
+        }
+
+        let mut lines = vec![format!("最近命令 (共 {} 条):", records.len())];
+        lines.extend(records.iter().map(Self::format_summary));
+        lines.join("\n")
+    }
+}
+
+#[async_trait]
+impl Tool for TerminalHistoryTool {
+    fn name(&self) -> &str {
+        "terminal_history"
+    }
+
+    fn description(&self) -> &str {
+        "查询用户终端的命令历史（基于 Shell 集成记录的命令、目录、退出码和输出）。\
+         支持获取最近一次失败命令的输出、全文搜索历史命令与输出、列出最近命令。"
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "description": "终端命令历史查询工具",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "description": "要执行的操作",
+                    "enum": ["last_failed", "search", "recent", "get_detail"],
+                    "default": "last_failed"
+                },
+                "query": {
+                    "type": "string",
+                    "description": "搜索词 (用于 search，匹配命令行、输出和目录)"
+                },
+                "block_id": {
+                    "type": "string",
+                    "description": "限定终端会话 (可选，默认跨所有会话)"
+                },
+                "command_id": {
+                    "type": "number",
+                    "description": "命令记录 ID (用于 get_detail)"
+                },
+                "limit": {
+                    "type": "number",
+                    "description": "返回数量限制 (可选，用于 search / recent，默认 20)"
+                }
+            },
+            "required": ["action"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: Value,
+        _context: &ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let action = params
+            .get("action")
+            .and_then(|v| v.as_str())
+            .unwrap_or("last_failed")
+            .to_string();
+        let block_id = params
+            .get("block_id")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        let limit = params.get("limit").and_then(|v| v.as_u64()).unwrap_or(20) as usize;
+        let query = params
+            .get("query")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        let command_id = params.get("command_id").and_then(|v| v.as_i64());
+
+        if action == "search" && query.as_deref().map_or(true, |q| q.trim().is_empty()) {
+            return Err(ToolError::invalid_params("缺少 query 参数"));
+        }
+        if action == "get_detail" && command_id.is_none() {
+            return Err(ToolError::invalid_params("缺少 command_id 参数"));
+        }
+
+        let store = self.store.clone();
+        let result = tokio::task::spawn_blocking(move || -> Result<ToolResult, String> {
+            let block_id = block_id.as_deref();
+            match action.as_str() {
+                "last_failed" => {
+                    let record = store.last_failed(block_id).map_err(|e| e.to_string())?;
+                    Ok(match record {
+                        Some(record) => ToolResult::success(Self::format_detail(&record))
+                            .with_metadata("command_id", json!(record.id))
+                            .with_metadata("exit_code", json!(record.exit_code)),
+                        None => ToolResult::success("没有找到失败的终端命令".to_string()),
+                    })
+                }
+                "search" => {
+                    let query = query.unwrap_or_default();
+                    let hits = store
+                        .search(&query, block_id, limit)
+                        .map_err(|e| e.to_string())?;
+                    Ok(ToolResult::success(Self::format_hits(&query, &hits))
+                        .with_metadata("hit_count", json!(hits.len())))
+                }
+                "recent" => {
+                    let records = store.recent(block_id, limit).map_err(|e| e.to_string())?;
+                    Ok(ToolResult::success(Self::format_recent(&records))
+                        .with_metadata("command_count", json!(records.len())))
+                }
+                "get_detail" => {
+                    let id = command_id.unwrap_or_default();
+                    let record = store.get_by_id(id).map_err(|e| e.to_string())?;
+                    Ok(match record {
+                        Some(record) => ToolResult::success(Self::format_detail(&record)),
+                        None => ToolResult::success(format!("未找到命令记录 ID: {}", id)),
+                    })
+                }
+                other => Ok(ToolResult::error(format!("未知操作: {}", other))),
+            }
+        })
+        .await
+        .map_err(|e| ToolError::execution_failed(format!("查询任务失败: {}", e)))?;
+
+        result.map_err(|e| ToolError::execution_failed(format!("查询终端历史失败: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(output: &str) -> CommandRecord {
+        CommandRecord {
+            id: 7,
+            block_id: "blk".to_string(),
+            command: "cargo test".to_string(),
+            cwd: Some("/work".to_string()),
+            exit_code: Some(101),
+            started_at: 0,
+            finished_at: 1200,
+            duration_ms: 1200,
+            output_start: 0,
+            output_end: output.len() as u64,
+            output: output.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_format_detail() {
+        let output = TerminalHistoryTool::format_detail(&record("test foo ... FAILED"));
+        assert!(output.contains("[7] cargo test"));
+        assert!(output.contains("退出码: 101"));
+        assert!(output.contains("FAILED"));
+    }
+
+    #[test]
+    fn test_tail_output_keeps_end() {
+        let long = format!("{}错误在这里", "x".repeat(MAX_OUTPUT_CHARS));
+        let output = TerminalHistoryTool::tail_output(&long);
+        assert!(output.starts_with("…（省略前 5 个字符）"));
+        assert!(output.ends_with("错误在这里"));
+    }
+
+    #[test]
+    fn test_format_empty_results() {
+        assert_eq!(
+            TerminalHistoryTool::format_hits("foo", &[]),
+            "没有找到匹配 \"foo\" 的终端命令"
+        );
+        assert_eq!(TerminalHistoryTool::format_recent(&[]), "暂无终端命令记录");
+    }
+}
@@ -0,0 +1,506 @@
+//! 命令行参数解析
+//!
+//! 与 `proxycast-gateway` 一样手写解析：子命令层级浅，不值得引入额外依赖。
+
+use std::path::PathBuf;
+
+use proxycast_core::models::provider_pool_model::CredentialData;
+use proxycast_services::management_service::{CredentialInput, CredentialPatch, UsageGroupBy};
+
+/// 默认输出的日志行数
+pub const DEFAULT_LOG_LINES: usize = 50;
+
+/// 帮助信息
+pub const USAGE: &str = "\
+proxycast - ProxyCast 网关管理工具
+
+用法:
+    proxycast [全局选项] <命令> [参数]
+
+命令:
+    credentials list [--type <TYPE>]            列出凭证
+    credentials add --type <TYPE> [凭证参数]    新增凭证
+        --api-key <KEY> [--base-url <URL>]      API Key 类凭证
+        --creds-file <PATH> [--project-id <ID>] OAuth 类凭证
+        [--name <NAME>]
+    credentials update <UUID> [--name <NAME>] [--enable | --disable] [--proxy-url <URL>]
+    credentials remove <UUID>                   删除凭证
+    credentials import <FILE>                   从 JSON 文件批量导入凭证
+    credentials refresh <UUID>                  强制刷新 OAuth Token
+    routes                                      列出路由
+    pool health                                 凭证池健康状态
+    usage [--days <N>] [--by provider|model]    Token 用量与费用（需要网关在线）
+    logs [--lines <N>] [--follow]               查看日志
+
+全局选项:
+    -c, --config <PATH>     YAML 配置文件路径（默认 ~/.config/proxycast/config.yaml）
+        --db <PATH>         离线模式使用的数据库路径（默认 ~/.proxycast/proxycast.db）
+        --url <URL>         网关地址（默认根据配置文件推断）
+        --management-key <KEY>
+                            管理密钥（默认读取配置 remote_management.secret_key）
+        --offline           不连接网关，直接读写数据库
+    -o, --output <FORMAT>   输出格式：table 或 json（默认 table）
+        --json              等同于 --output json
+    -h, --help              显示帮助信息
+    -V, --version           显示版本号";
+
+/// 输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "table" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            other => Err(format!("未知的输出格式: {other}（可选 table、json）")),
+        }
+    }
+}
+
+/// 全局选项
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GlobalOptions {
+    pub config: Option<PathBuf>,
+    pub db: Option<PathBuf>,
+    pub url: Option<String>,
+    /// 管理 API 密钥（与代理接口的 API Key 分开）
+    pub management_key: Option<String>,
+    pub offline: bool,
+    pub output: OutputFormat,
+}
+
+/// `credentials add` 的参数
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AddCredentialArgs {
+    pub provider_type: String,
+    pub api_key: Option<String>,
+    pub base_url: Option<String>,
+    pub creds_file: Option<String>,
+    pub project_id: Option<String>,
+    pub name: Option<String>,
+}
+
+impl AddCredentialArgs {
+    /// 根据 Provider 类型组装凭证数据
+    pub fn into_input(self) -> Result<CredentialInput, String> {
+        let provider_type = self.provider_type.to_ascii_lowercase();
+        let api_key = || {
+            self.api_key
+                .clone()
+                .ok_or_else(|| format!("{provider_type} 类型的凭证需要 --api-key"))
+        };
+        let creds_file = || {
+            self.creds_file
+                .clone()
+                .ok_or_else(|| format!("{provider_type} 类型的凭证需要 --creds-file"))
+        };
+
+        let credential = match provider_type.as_str() {
+            "kiro" => CredentialData::KiroOAuth {
+                creds_file_path: creds_file()?,
+            },
+            "gemini" => CredentialData::GeminiOAuth {
+                creds_file_path: creds_file()?,
+                project_id: self.project_id.clone(),
+            },
+            "antigravity" => CredentialData::AntigravityOAuth {
+                creds_file_path: creds_file()?,
+                project_id: self.project_id.clone(),
+            },
+            "codex" => CredentialData::CodexOAuth {
+                creds_file_path: creds_file()?,
+                api_base_url: self.base_url.clone(),
+            },
+            "claude_oauth" => CredentialData::ClaudeOAuth {
+                creds_file_path: creds_file()?,
+            },
+            "openai" => CredentialData::OpenAIKey {
+                api_key: api_key()?,
+                base_url: self.base_url.clone(),
+            },
+            "claude" => CredentialData::ClaudeKey {
+                api_key: api_key()?,
+                base_url: self.base_url.clone(),
+            },
+            "anthropic" => CredentialData::AnthropicKey {
+                api_key: api_key()?,
+                base_url: self.base_url.clone(),
+            },
+            "vertex" => CredentialData::VertexKey {
+                api_key: api_key()?,
+                base_url: self.base_url.clone(),
+                model_aliases: Default::default(),
+            },
+            "gemini_api_key" => CredentialData::GeminiApiKey {
+                api_key: api_key()?,
+                base_url: self.base_url.clone(),
+                excluded_models: Vec::new(),
+            },
+            other => {
+                return Err(format!(
+                "不支持的凭证类型: {other}（可选 kiro、gemini、antigravity、codex、claude_oauth、\
+                     openai、claude、anthropic、vertex、gemini_api_key）"
+            ))
+            }
+        };
+
+        Ok(CredentialInput {
+            provider_type: Some(provider_type),
+            credential,
+            name: self.name,
+            check_health: None,
+            check_model_name: None,
+        })
+    }
+}
+
+/// 凭证子命令
+#[derive(Debug, Clone)]
+pub enum CredentialsCommand {
+    List {
+        provider_type: Option<String>,
+    },
+    Add(AddCredentialArgs),
+    Update {
+        uuid: String,
+        patch: CredentialPatch,
+    },
+    Remove {
+        uuid: String,
+    },
+    Import {
+        file: PathBuf,
+    },
+    Refresh {
+        uuid: String,
+    },
+}
+
+/// 顶层命令
+#[derive(Debug, Clone)]
+pub enum Command {
+    Help,
+    Version,
+    Credentials(CredentialsCommand),
+    Routes,
+    PoolHealth,
+    Usage {
+        days: Option<i64>,
+        group_by: UsageGroupBy,
+    },
+    Logs {
+        lines: usize,
+        follow: bool,
+    },
+}
+
+/// 解析结果
+#[derive(Debug, Clone)]
+pub struct CliArgs {
+    pub global: GlobalOptions,
+    pub command: Command,
+}
+
+/// 参数游标：统一处理 `--key value` 与 `--key=value`
+struct Cursor {
+    args: std::vec::IntoIter<String>,
+    /// 上一个 `--key=value` 中的取值
+    inline: Option<String>,
+}
+
+impl Cursor {
+    fn new(args: Vec<String>) -> Self {
+        Self {
+            args: args.into_iter(),
+            inline: None,
+        }
+    }
+
+    /// 取下一个参数；`--key=value` 会被拆成 `--key`，取值留给 [`Cursor::value`]
+    fn next_arg(&mut self) -> Option<String> {
+        let arg = self.args.next()?;
+        match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                self.inline = Some(value.to_string());
+                Some(flag.to_string())
+            }
+            _ => {
+                self.inline = None;
+                Some(arg)
+            }
+        }
+    }
+
+    fn value(&mut self, flag: &str) -> Result<String, String> {
+        self.inline
+            .take()
+            .or_else(|| self.args.next())
+            .ok_or_else(|| format!("参数 {flag} 缺少取值"))
+    }
+
+    fn parse_value<T: std::str::FromStr>(&mut self, flag: &str) -> Result<T, String> {
+        let raw = self.value(flag)?;
+        raw.parse::<T>()
+            .map_err(|_| format!("参数 {flag} 的取值无效: {raw}"))
+    }
+}
+
+/// 解析命令行参数（不含程序名）
+///
+/// 全局选项可以出现在任意位置。
+pub fn parse<I, S>(args: I) -> Result<CliArgs, String>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut cursor = Cursor::new(args.into_iter().map(Into::into).collect());
+    let mut global = GlobalOptions::default();
+    let mut positionals: Vec<String> = Vec::new();
+    let mut add = AddCredentialArgs::default();
+    let mut patch = CredentialPatch::default();
+    let mut provider_filter = None;
+    let mut days = None;
+    let mut group_by = UsageGroupBy::default();
+    let mut lines = DEFAULT_LOG_LINES;
+    let mut follow = false;
+
+    while let Some(arg) = cursor.next_arg() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                return Ok(CliArgs {
+                    global,
+                    command: Command::Help,
+                })
+            }
+            "-V" | "--version" => {
+                return Ok(CliArgs {
+                    global,
+                    command: Command::Version,
+                })
+            }
+            // 全局选项
+            "-c" | "--config" => global.config = Some(PathBuf::from(cursor.value(&arg)?)),
+            "--db" => global.db = Some(PathBuf::from(cursor.value(&arg)?)),
+            "--url" => global.url = Some(cursor.value(&arg)?),
+            "--offline" => global.offline = true,
+            "-o" | "--output" => global.output = cursor.value(&arg)?.parse()?,
+            "--json" => global.output = OutputFormat::Json,
+            "--management-key" => global.management_key = Some(cursor.value(&arg)?),
+            // 子命令选项
+            "--type" => {
+                let value = cursor.value(&arg)?;
+                add.provider_type = value.clone();
+                provider_filter = Some(value);
+            }
+            "--api-key" => add.api_key = Some(cursor.value(&arg)?),
+            "--base-url" => add.base_url = Some(cursor.value(&arg)?),
+            "--creds-file" => add.creds_file = Some(cursor.value(&arg)?),
+            "--project-id" => add.project_id = Some(cursor.value(&arg)?),
+            "--name" => {
+                let name = cursor.value(&arg)?;
+                add.name = Some(name.clone());
+                patch.name = Some(name);
+            }
+            "--enable" => patch.is_disabled = Some(false),
+            "--disable" => patch.is_disabled = Some(true),
+            "--proxy-url" => patch.proxy_url = Some(cursor.value(&arg)?),
+            "--days" => days = Some(cursor.parse_value::<i64>(&arg)?),
+            "--by" | "--group-by" => group_by = cursor.value(&arg)?.parse()?,
+            "-n" | "--lines" => lines = cursor.parse_value::<usize>(&arg)?,
+            "-f" | "--follow" => follow = true,
+            other if other.starts_with('-') => return Err(format!("未知参数: {other}")),
+            _ => positionals.push(arg),
+        }
+    }
+
+    let words: Vec<&str> = positionals.iter().map(String::as_str).collect();
+    let command = match words.as_slice() {
+        [] => Command::Help,
+        ["credentials" | "creds", rest @ ..] => Command::Credentials(match rest {
+            ["list"] | [] => CredentialsCommand::List {
+                provider_type: provider_filter,
+            },
+            ["add"] => {
+                if add.provider_type.is_empty() {
+                    return Err("credentials add 需要 --type".to_string());
+                }
+                CredentialsCommand::Add(add)
+            }
+            ["update", uuid] => {
+                if patch.name.is_none() && patch.is_disabled.is_none() && patch.proxy_url.is_none()
+                {
+                    return Err("credentials update 至少需要一个要修改的字段".to_string());
+                }
+                CredentialsCommand::Update {
+                    uuid: uuid.to_string(),
+                    patch,
+                }
+            }
+            ["remove" | "rm" | "delete", uuid] => CredentialsCommand::Remove {
+                uuid: uuid.to_string(),
+            },
+            ["import", file] => CredentialsCommand::Import {
+                file: PathBuf::from(file),
+            },
+            ["refresh", uuid] => CredentialsCommand::Refresh {
+                uuid: uuid.to_string(),
+            },
+            _ => return Err(format!("无效的凭证命令: {}", rest.join(" "))),
+        }),
+        ["routes"] => Command::Routes,
+        ["pool", "health"] | ["health"] => Command::PoolHealth,
+        ["usage"] => Command::Usage { days, group_by },
+        ["logs"] => Command::Logs { lines, follow },
+        _ => return Err(format!("未知命令: {}", words.join(" "))),
+    };
+
+    Ok(CliArgs { global, command })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_ok(args: &[&str]) -> CliArgs {
+        parse(args.iter().copied()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_global_options_anywhere() {
+        let args = parse_ok(&[
+            "--config",
+            "/etc/proxycast.yaml",
+            "routes",
+            "--json",
+            "--url=http://10.0.0.2:8999",
+            "--management-key",
+            "mgmt_key",
+        ]);
+        assert!(matches!(args.command, Command::Routes));
+        assert_eq!(args.global.output, OutputFormat::Json);
+        assert_eq!(
+            args.global.config,
+            Some(PathBuf::from("/etc/proxycast.yaml"))
+        );
+        assert_eq!(args.global.url.as_deref(), Some("http://10.0.0.2:8999"));
+        assert_eq!(args.global.management_key.as_deref(), Some("mgmt_key"));
+        assert!(!args.global.offline);
+    }
+
+    #[test]
+    fn test_parse_credentials_add_api_key_is_credential_key() {
+        let args = parse_ok(&[
+            "credentials",
+            "add",
+            "--type",
+            "openai",
+            "--api-key",
+            "sk-test",
+            "--base-url",
+            "https://api.example.com/v1",
+            "--name",
+            "main",
+        ]);
+        assert_eq!(args.global.management_key, None);
+        let Command::Credentials(CredentialsCommand::Add(add)) = args.command else {
+            panic!("expected credentials add");
+        };
+        let input = add.into_input().unwrap();
+        assert_eq!(input.provider_type.as_deref(), Some("openai"));
+        assert_eq!(input.name.as_deref(), Some("main"));
+        match input.credential {
+            CredentialData::OpenAIKey { api_key, base_url } => {
+                assert_eq!(api_key, "sk-test");
+                assert_eq!(base_url.as_deref(), Some("https://api.example.com/v1"));
+            }
+            other => panic!("unexpected credential: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_add_credential_requires_matching_fields() {
+        let oauth = AddCredentialArgs {
+            provider_type: "Gemini".to_string(),
+            creds_file: Some("/tmp/oauth_creds.json".to_string()),
+            project_id: Some("my-project".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            oauth.into_input().unwrap().credential,
+            CredentialData::GeminiOAuth { project_id: Some(ref p), .. } if p == "my-project"
+        ));
+
+        let missing_key = AddCredentialArgs {
+            provider_type: "claude".to_string(),
+            ..Default::default()
+        };
+        assert!(missing_key.into_input().unwrap_err().contains("--api-key"));
+
+        let unknown = AddCredentialArgs {
+            provider_type: "foo".to_string(),
+            api_key: Some("k".to_string()),
+            ..Default::default()
+        };
+        assert!(unknown
+            .into_input()
+            .unwrap_err()
+            .contains("不支持的凭证类型"));
+    }
+
+    #[test]
+    fn test_parse_update_usage_and_logs() {
+        let args = parse_ok(&["credentials", "update", "abc", "--disable", "--name=bak"]);
+        let Command::Credentials(CredentialsCommand::Update { uuid, patch }) = args.command else {
+            panic!("expected credentials update");
+        };
+        assert_eq!(uuid, "abc");
+        assert_eq!(patch.is_disabled, Some(true));
+        assert_eq!(patch.name.as_deref(), Some("bak"));
+
+        let args = parse_ok(&["usage", "--days", "7", "--by", "model"]);
+        assert!(matches!(
+            args.command,
+            Command::Usage {
+                days: Some(7),
+                group_by: UsageGroupBy::Model
+            }
+        ));
+
+        let args = parse_ok(&["logs", "-f", "--offline"]);
+        assert!(matches!(
+            args.command,
+            Command::Logs {
+                lines: DEFAULT_LOG_LINES,
+                follow: true
+            }
+        ));
+        assert!(args.global.offline);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(["credentials", "add"])
+            .unwrap_err()
+            .contains("--type"));
+        assert!(parse(["credentials", "update", "abc"])
+            .unwrap_err()
+            .contains("至少需要一个"));
+        assert!(parse(["usage", "--days", "x"])
+            .unwrap_err()
+            .contains("取值无效"));
+        assert!(parse(["--output", "xml", "routes"])
+            .unwrap_err()
+            .contains("未知的输出格式"));
+        assert!(parse(["bogus"]).unwrap_err().contains("未知命令"));
+        assert!(parse(["routes", "--verbose"])
+            .unwrap_err()
+            .contains("未知参数"));
+    }
+}
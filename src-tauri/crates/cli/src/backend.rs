@@ -0,0 +1,485 @@
+//! 管理操作的执行后端
+//!
+//! 网关在线时通过 `/api/management/*` 操作，保证与运行中的凭证池状态一致；
+//! 网关离线（或指定 `--offline`）时直接读写 SQLite 数据库。
+
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use proxycast_core::app_bootstrap::load_config_from;
+use proxycast_core::config::{Config, ConfigManager};
+use proxycast_core::database::{self, DbConnection};
+use proxycast_core::logger::LogEntry;
+use proxycast_services::management_service::{
+    CredentialInput, CredentialPatch, ManagementService, UsageGroupBy,
+};
+use proxycast_services::provider_pool_service::ProviderPoolService;
+use proxycast_services::token_cache_service::TokenCacheService;
+use reqwest::Method;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::args::GlobalOptions;
+
+/// 探测网关是否在线的超时时间
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 管理请求的超时时间（刷新 Token 需要访问上游，留足余量）
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| format!("序列化结果失败: {e}"))
+}
+
+/// 读取配置；文件不存在时使用默认配置（桌面端可能从未保存过配置）
+fn load_config(path: Option<&Path>) -> Result<Config, String> {
+    let path = path
+        .map(Path::to_path_buf)
+        .unwrap_or_else(ConfigManager::default_config_path);
+    if path.exists() {
+        load_config_from(&path).map_err(|e| e.to_string())
+    } else {
+        Ok(Config::default())
+    }
+}
+
+/// 根据监听配置推断网关地址
+fn gateway_url(config: &Config) -> String {
+    let host = match config.server.host.as_str() {
+        "0.0.0.0" | "::" | "" => "127.0.0.1",
+        host => host,
+    };
+    let scheme = if config.server.tls.enable {
+        "https"
+    } else {
+        "http"
+    };
+    if host.contains(':') {
+        format!("{scheme}://[{host}]:{}", config.server.port)
+    } else {
+        format!("{scheme}://{host}:{}", config.server.port)
+    }
+}
+
+/// 通过 HTTP 管理 API 访问运行中的网关
+pub struct RemoteBackend {
+    http: reqwest::Client,
+    base_url: String,
+    /// 管理密钥；未配置时不带认证头，由网关返回未启用管理 API 的错误
+    management_key: Option<String>,
+}
+
+impl RemoteBackend {
+    pub fn new(base_url: &str, management_key: Option<String>) -> Result<Self, String> {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| format!("创建 HTTP 客户端失败: {e}"))?;
+        Ok(Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            management_key,
+        })
+    }
+
+    /// 网关是否在线
+    pub async fn is_reachable(&self) -> bool {
+        self.http
+            .get(format!("{}/health", self.base_url))
+            .timeout(PROBE_TIMEOUT)
+            .send()
+            .await
+            .map(|resp| resp.status().is_success())
+            .unwrap_or(false)
+    }
+
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, String)],
+        body: Option<Value>,
+    ) -> Result<Value, String> {
+        let mut request = self
+            .http
+            .request(method, format!("{}/api/management{}", self.base_url, path))
+            .query(query);
+        if let Some(key) = &self.management_key {
+            request = request.bearer_auth(key);
+        }
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let resp = request
+            .send()
+            .await
+            .map_err(|e| format!("请求网关失败: {e}"))?;
+        let status = resp.status();
+        let text = resp
+            .text()
+            .await
+            .map_err(|e| format!("读取网关响应失败: {e}"))?;
+        let value: Value = serde_json::from_str(&text).unwrap_or(Value::String(text));
+
+        if status.is_success() {
+            return Ok(value);
+        }
+        let message = value
+            .pointer("/error/message")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| value.to_string());
+        Err(format!("网关返回 {status}: {message}"))
+    }
+}
+
+/// 直接读写数据库（网关离线时使用）
+pub struct LocalBackend {
+    db: DbConnection,
+    service: ManagementService,
+    default_provider: String,
+    log_file: PathBuf,
+}
+
+impl LocalBackend {
+    pub fn open(db_path: &Path, default_provider: &str) -> Result<Self, String> {
+        let db = database::init_database_at(db_path)
+            .map_err(|e| format!("打开数据库失败 {}: {e}", db_path.display()))?;
+        Ok(Self {
+            db,
+            service: ManagementService::new(
+                Arc::new(ProviderPoolService::new()),
+                Arc::new(TokenCacheService::new()),
+            ),
+            default_provider: default_provider.to_string(),
+            log_file: default_log_file(),
+        })
+    }
+}
+
+/// 桌面端与网关共用的日志文件
+fn default_log_file() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".proxycast")
+        .join("logs")
+        .join("proxycast.log")
+}
+
+/// 管理操作后端
+pub enum Backend {
+    Remote(RemoteBackend),
+    Local(LocalBackend),
+}
+
+impl Backend {
+    /// 选择后端：未指定 `--offline` 且网关可达时使用远程，否则回退到本地数据库
+    pub async fn connect(options: &GlobalOptions) -> Result<Self, String> {
+        let config = load_config(options.config.as_deref())?;
+
+        if !options.offline {
+            let url = options.url.clone().unwrap_or_else(|| gateway_url(&config));
+            // 管理 API 使用独立的管理密钥，不是代理接口的 API Key
+            let management_key = options
+                .management_key
+                .clone()
+                .or_else(|| config.remote_management.secret_key.clone())
+                .filter(|key| !key.is_empty());
+            let remote = RemoteBackend::new(&url, management_key)?;
+            if remote.is_reachable().await {
+                return Ok(Self::Remote(remote));
+            }
+            if options.url.is_some() {
+                return Err(format!("无法连接网关: {url}"));
+            }
+            eprintln!("网关 {url} 未运行，直接操作本地数据库");
+        }
+
+        let db_path = match &options.db {
+            Some(path) => path.clone(),
+            None => database::get_db_path()?,
+        };
+        Ok(Self::Local(LocalBackend::open(
+            &db_path,
+            &config.routing.default_provider,
+        )?))
+    }
+
+    pub async fn list_credentials(&self, provider_type: Option<&str>) -> Result<Value, String> {
+        match self {
+            Self::Remote(remote) => {
+                let query: Vec<(&str, String)> = provider_type
+                    .map(|pt| vec![("provider_type", pt.to_string())])
+                    .unwrap_or_default();
+                remote
+                    .request(Method::GET, "/credentials", &query, None)
+                    .await
+            }
+            Self::Local(local) => {
+                to_value(local.service.list_credentials(&local.db, provider_type)?)
+            }
+        }
+    }
+
+    pub async fn add_credential(&self, input: CredentialInput) -> Result<Value, String> {
+        match self {
+            Self::Remote(remote) => {
+                remote
+                    .request(Method::POST, "/credentials", &[], Some(to_value(input)?))
+                    .await
+            }
+            Self::Local(local) => to_value(local.service.add_credential(&local.db, input)?),
+        }
+    }
+
+    pub async fn import_credentials(&self, inputs: Vec<CredentialInput>) -> Result<Value, String> {
+        match self {
+            Self::Remote(remote) => {
+                remote
+                    .request(
+                        Method::POST,
+                        "/credentials/import",
+                        &[],
+                        Some(to_value(inputs)?),
+                    )
+                    .await
+            }
+            Self::Local(local) => to_value(local.service.import_credentials(&local.db, inputs)),
+        }
+    }
+
+    pub async fn update_credential(
+        &self,
+        uuid: &str,
+        patch: CredentialPatch,
+    ) -> Result<Value, String> {
+        match self {
+            Self::Remote(remote) => {
+                remote
+                    .request(
+                        Method::PATCH,
+                        &format!("/credentials/{uuid}"),
+                        &[],
+                        Some(to_value(patch)?),
+                    )
+                    .await
+            }
+            Self::Local(local) => {
+                to_value(local.service.update_credential(&local.db, uuid, patch)?)
+            }
+        }
+    }
+
+    pub async fn delete_credential(&self, uuid: &str) -> Result<Value, String> {
+        match self {
+            Self::Remote(remote) => {
+                remote
+                    .request(Method::DELETE, &format!("/credentials/{uuid}"), &[], None)
+                    .await
+            }
+            Self::Local(local) => {
+                local.service.delete_credential(&local.db, uuid)?;
+                Ok(serde_json::json!({ "deleted": uuid }))
+            }
+        }
+    }
+
+    pub async fn refresh_token(&self, uuid: &str) -> Result<Value, String> {
+        match self {
+            Self::Remote(remote) => {
+                remote
+                    .request(
+                        Method::POST,
+                        &format!("/credentials/{uuid}/refresh"),
+                        &[],
+                        None,
+                    )
+                    .await
+            }
+            Self::Local(local) => to_value(local.service.refresh_token(&local.db, uuid).await?),
+        }
+    }
+
+    pub async fn routes(&self) -> Result<Value, String> {
+        match self {
+            Self::Remote(remote) => remote.request(Method::GET, "/routes", &[], None).await,
+            Self::Local(local) => to_value(
+                local
+                    .service
+                    .list_routes(&local.db, &local.default_provider)?,
+            ),
+        }
+    }
+
+    pub async fn pool_health(&self) -> Result<Value, String> {
+        match self {
+            Self::Remote(remote) => remote.request(Method::GET, "/pool/health", &[], None).await,
+            Self::Local(local) => to_value(local.service.pool_health(&local.db)?),
+        }
+    }
+
+    /// 用量统计只保存在网关进程内存中，离线时不可用
+    pub async fn usage(&self, days: Option<i64>, group_by: UsageGroupBy) -> Result<Value, String> {
+        match self {
+            Self::Remote(remote) => {
+                let mut query = vec![(
+                    "group_by",
+                    to_value(group_by)?
+                        .as_str()
+                        .unwrap_or("provider")
+                        .to_string(),
+                )];
+                if let Some(days) = days {
+                    query.push(("days", days.to_string()));
+                }
+                remote.request(Method::GET, "/usage", &query, None).await
+            }
+            Self::Local(_) => {
+                Err("用量统计只保存在运行中的网关内存里，请先启动网关后再查询".to_string())
+            }
+        }
+    }
+
+    /// 拉取日志；`after` 为上次收到的最后一条日志的序号，用于 `--follow` 时的增量拉取
+    pub async fn logs(&self, after: Option<u64>, limit: usize) -> Result<Vec<LogEntry>, String> {
+        match self {
+            Self::Remote(remote) => {
+                let mut query = vec![("limit", limit.to_string())];
+                if let Some(after) = after {
+                    query.push(("after", after.to_string()));
+                }
+                let value = remote.request(Method::GET, "/logs", &query, None).await?;
+                serde_json::from_value(value).map_err(|e| format!("解析日志失败: {e}"))
+            }
+            Self::Local(local) => {
+                let lines = tail_lines(&local.log_file, limit)?;
+                Ok(lines.iter().map(|line| parse_log_line(line)).collect())
+            }
+        }
+    }
+
+    /// 本地日志文件路径（仅本地后端）
+    pub fn log_file(&self) -> Option<&Path> {
+        match self {
+            Self::Remote(_) => None,
+            Self::Local(local) => Some(&local.log_file),
+        }
+    }
+}
+
+/// 读取文件最后 `limit` 行
+fn tail_lines(path: &Path, limit: usize) -> Result<Vec<String>, String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("读取日志文件失败 {}: {e}", path.display())),
+    };
+    let lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let skip = lines.len().saturating_sub(limit);
+    Ok(lines.into_iter().skip(skip).collect())
+}
+
+/// 读取文件从 `offset` 开始新增的完整行，返回新行与新的偏移量
+///
+/// 文件被轮转（变短）时从头开始读。
+pub fn read_appended_lines(path: &Path, offset: u64) -> Result<(Vec<String>, u64), String> {
+    let mut file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+        Err(e) => return Err(format!("读取日志文件失败 {}: {e}", path.display())),
+    };
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let start = if len < offset { 0 } else { offset };
+    file.seek(SeekFrom::Start(start))
+        .map_err(|e| format!("读取日志文件失败: {e}"))?;
+
+    let mut buf = String::new();
+    file.read_to_string(&mut buf)
+        .map_err(|e| format!("读取日志文件失败: {e}"))?;
+    // 只消费到最后一个换行符，半行留到下次读取
+    let consumed = buf.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let lines = buf[..consumed].lines().map(str::to_string).collect();
+    Ok((lines, start + consumed as u64))
+}
+
+/// 解析日志文件中的一行（`2026-01-01 12:00:00.000 [INFO] message`）
+///
+/// 格式不符时整行作为消息返回。
+pub fn parse_log_line(line: &str) -> LogEntry {
+    let parsed = line.split_once(" [").and_then(|(timestamp, rest)| {
+        let (level, message) = rest.split_once("] ")?;
+        Some(LogEntry {
+            seq: 0,
+            timestamp: timestamp.to_string(),
+            level: level.to_ascii_lowercase(),
+            message: message.to_string(),
+        })
+    });
+    parsed.unwrap_or_else(|| LogEntry {
+        seq: 0,
+        timestamp: String::new(),
+        level: String::new(),
+        message: line.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_gateway_url_from_config() {
+        let mut config = Config::default();
+        config.server.host = "0.0.0.0".to_string();
+        config.server.port = 9000;
+        assert_eq!(gateway_url(&config), "http://127.0.0.1:9000");
+
+        config.server.host = "::1".to_string();
+        config.server.tls.enable = true;
+        assert_eq!(gateway_url(&config), "https://[::1]:9000");
+    }
+
+    #[test]
+    fn test_parse_log_line() {
+        let entry = parse_log_line("2026-01-01 12:00:00.000 [WARN] 凭证 [abc] 即将过期");
+        assert_eq!(entry.timestamp, "2026-01-01 12:00:00.000");
+        assert_eq!(entry.level, "warn");
+        assert_eq!(entry.message, "凭证 [abc] 即将过期");
+
+        let raw = parse_log_line("no structure here");
+        assert_eq!(raw.message, "no structure here");
+        assert!(raw.level.is_empty());
+    }
+
+    #[test]
+    fn test_tail_and_follow_log_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("proxycast.log");
+        std::fs::write(&path, "a\nb\nc\n").unwrap();
+
+        assert_eq!(tail_lines(&path, 2).unwrap(), vec!["b", "c"]);
+
+        let offset = std::fs::metadata(&path).unwrap().len();
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        write!(file, "d\npartial").unwrap();
+        let (lines, offset) = read_appended_lines(&path, offset).unwrap();
+        assert_eq!(lines, vec!["d"]);
+
+        writeln!(file, " line").unwrap();
+        let (lines, _) = read_appended_lines(&path, offset).unwrap();
+        assert_eq!(lines, vec!["partial line"]);
+
+        // 轮转后文件变短，从头读取
+        std::fs::write(&path, "new\n").unwrap();
+        let (lines, offset) = read_appended_lines(&path, offset).unwrap();
+        assert_eq!(lines, vec!["new"]);
+        assert_eq!(offset, 4);
+    }
+}
@@ -0,0 +1,201 @@
+//! ProxyCast 管理命令行工具
+//!
+//! 网关运行时通过管理 API 操作，离线时直接读写数据库：
+//! 凭证增删改查 / 导入 / 刷新、路由列表、凭证池健康、用量报表与日志查看。
+
+mod args;
+mod backend;
+mod output;
+
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use args::{CliArgs, Command, CredentialsCommand, OutputFormat};
+use backend::Backend;
+use proxycast_core::logger::LogEntry;
+use proxycast_services::management_service::CredentialInput;
+use serde_json::Value;
+
+/// `logs --follow` 的轮询间隔
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(1);
+
+fn main() -> ExitCode {
+    let args = match args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}\n\n{}", args::USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match args.command {
+        Command::Help => {
+            println!("{}", args::USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Command::Version => {
+            println!("proxycast {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        _ => {}
+    }
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("创建 tokio 运行时失败: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match runtime.block_on(run(args)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("proxycast: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(args: CliArgs) -> Result<(), String> {
+    let format = args.global.output;
+    let backend = Backend::connect(&args.global).await?;
+
+    match args.command {
+        Command::Credentials(command) => run_credentials(&backend, command, format).await,
+        Command::Routes => print(format, &backend.routes().await?, output::routes),
+        Command::PoolHealth => print(format, &backend.pool_health().await?, output::pool_health),
+        Command::Usage { days, group_by } => {
+            print(format, &backend.usage(days, group_by).await?, output::usage)
+        }
+        Command::Logs { lines, follow } => run_logs(&backend, lines, follow, format).await,
+        Command::Help | Command::Version => Ok(()),
+    }
+}
+
+async fn run_credentials(
+    backend: &Backend,
+    command: CredentialsCommand,
+    format: OutputFormat,
+) -> Result<(), String> {
+    match command {
+        CredentialsCommand::List { provider_type } => print(
+            format,
+            &backend.list_credentials(provider_type.as_deref()).await?,
+            output::credentials,
+        ),
+        CredentialsCommand::Add(add) => print(
+            format,
+            &backend.add_credential(add.into_input()?).await?,
+            output::credentials,
+        ),
+        CredentialsCommand::Update { uuid, patch } => print(
+            format,
+            &backend.update_credential(&uuid, patch).await?,
+            output::credentials,
+        ),
+        CredentialsCommand::Remove { uuid } => {
+            print(format, &backend.delete_credential(&uuid).await?, |_| {
+                format!("已删除凭证 {uuid}")
+            })
+        }
+        CredentialsCommand::Import { file } => {
+            let inputs = read_import_file(&file)?;
+            print(
+                format,
+                &backend.import_credentials(inputs).await?,
+                output::import_summary,
+            )
+        }
+        CredentialsCommand::Refresh { uuid } => print(
+            format,
+            &backend.refresh_token(&uuid).await?,
+            output::refresh_outcome,
+        ),
+    }
+}
+
+fn print(
+    format: OutputFormat,
+    value: &Value,
+    table: impl FnOnce(&Value) -> String,
+) -> Result<(), String> {
+    match format {
+        OutputFormat::Json => {
+            let json =
+                serde_json::to_string_pretty(value).map_err(|e| format!("序列化输出失败: {e}"))?;
+            println!("{json}");
+        }
+        OutputFormat::Table => println!("{}", table(value)),
+    }
+    Ok(())
+}
+
+/// 读取导入文件：凭证数组，或单个凭证对象
+fn read_import_file(path: &Path) -> Result<Vec<CredentialInput>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("读取导入文件失败 {}: {e}", path.display()))?;
+    let value: Value =
+        serde_json::from_str(&content).map_err(|e| format!("导入文件不是有效的 JSON: {e}"))?;
+    let value = match value {
+        Value::Array(_) => value,
+        single => Value::Array(vec![single]),
+    };
+    serde_json::from_value(value).map_err(|e| format!("导入文件格式错误: {e}"))
+}
+
+fn print_log(format: OutputFormat, entry: &LogEntry) {
+    match format {
+        OutputFormat::Json => {
+            if let Ok(line) = serde_json::to_string(entry) {
+                println!("{line}");
+            }
+        }
+        OutputFormat::Table => println!(
+            "{}",
+            output::log_line(&serde_json::to_value(entry).unwrap_or_default())
+        ),
+    }
+}
+
+/// 输出最近的日志；`follow` 时持续轮询新日志直到进程被中断
+async fn run_logs(
+    backend: &Backend,
+    lines: usize,
+    follow: bool,
+    format: OutputFormat,
+) -> Result<(), String> {
+    let entries = backend.logs(None, lines).await?;
+    for entry in &entries {
+        print_log(format, entry);
+    }
+    if !follow {
+        return Ok(());
+    }
+
+    // 本地模式直接追踪日志文件
+    if let Some(path) = backend.log_file() {
+        let mut offset = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        loop {
+            tokio::time::sleep(FOLLOW_INTERVAL).await;
+            let (lines, next) = backend::read_appended_lines(path, offset)?;
+            offset = next;
+            for line in lines {
+                print_log(format, &backend::parse_log_line(&line));
+            }
+        }
+    }
+
+    // 按序号游标增量拉取：同一时间戳的多条日志不会被漏掉
+    let mut after = entries.last().map(|entry| entry.seq).unwrap_or(0);
+    loop {
+        tokio::time::sleep(FOLLOW_INTERVAL).await;
+        let entries = backend.logs(Some(after), lines).await?;
+        for entry in &entries {
+            print_log(format, entry);
+        }
+        if let Some(last) = entries.last() {
+            after = last.seq;
+        }
+    }
+}
@@ -0,0 +1,325 @@
+//! 输出渲染
+//!
+//! 本地模式与远程模式的结果都先统一为 `serde_json::Value`，
+//! 这里再按命令渲染成表格；`--json` 时直接原样输出。
+
+use serde_json::Value;
+
+/// 简单的文本表格，列宽按显示宽度对齐（中文字符按 2 计）
+#[derive(Debug, Default)]
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Self {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    pub fn render(&self) -> String {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| display_width(h)).collect();
+        for row in &self.rows {
+            for (i, cell) in row.iter().enumerate().take(widths.len()) {
+                widths[i] = widths[i].max(display_width(cell));
+            }
+        }
+
+        let format_row = |cells: &[String]| {
+            let mut line = String::new();
+            for (i, width) in widths.iter().enumerate() {
+                let cell = cells.get(i).map(String::as_str).unwrap_or("");
+                line.push_str(cell);
+                if i + 1 < widths.len() {
+                    line.push_str(&" ".repeat(width - display_width(cell) + 2));
+                }
+            }
+            line.trim_end().to_string()
+        };
+
+        let mut out = format_row(&self.headers);
+        for row in &self.rows {
+            out.push('\n');
+            out.push_str(&format_row(row));
+        }
+        out
+    }
+}
+
+/// 终端显示宽度（东亚宽字符按 2 计）
+fn display_width(s: &str) -> usize {
+    s.chars()
+        .map(|c| match c as u32 {
+            0x1100..=0x115F
+            | 0x2E80..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6 => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+fn text(value: &Value, key: &str) -> String {
+    match value.get(key) {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Null) | None => "-".to_string(),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn flag(value: &Value, key: &str) -> bool {
+    value.get(key).and_then(Value::as_bool).unwrap_or(false)
+}
+
+fn items(value: &Value) -> &[Value] {
+    value.as_array().map(Vec::as_slice).unwrap_or_default()
+}
+
+fn short_uuid(uuid: &str) -> String {
+    uuid.chars().take(8).collect()
+}
+
+/// 凭证列表
+pub fn credentials(value: &Value) -> String {
+    let list: Vec<&Value> = match value {
+        Value::Array(list) => list.iter().collect(),
+        single => vec![single],
+    };
+    let mut table = Table::new(&[
+        "UUID",
+        "PROVIDER",
+        "NAME",
+        "CREDENTIAL",
+        "STATUS",
+        "USAGE",
+        "ERRORS",
+    ]);
+    for credential in list {
+        let status = if flag(credential, "is_disabled") {
+            "disabled"
+        } else if flag(credential, "is_healthy") {
+            "healthy"
+        } else {
+            "unhealthy"
+        };
+        table.push(vec![
+            text(credential, "uuid"),
+            text(credential, "provider_type"),
+            text(credential, "name"),
+            text(credential, "display_credential"),
+            status.to_string(),
+            text(credential, "usage_count"),
+            text(credential, "error_count"),
+        ]);
+    }
+    table.render()
+}
+
+/// 批量导入结果
+pub fn import_summary(value: &Value) -> String {
+    let imported = value.get("imported").cloned().unwrap_or(Value::Null);
+    let failed = value.get("failed").map(items).unwrap_or_default();
+
+    let mut out = format!("已导入 {} 个凭证", items(&imported).len());
+    if !items(&imported).is_empty() {
+        out.push_str("\n\n");
+        out.push_str(&credentials(&imported));
+    }
+    if !failed.is_empty() {
+        out.push_str(&format!("\n\n{} 个凭证导入失败:", failed.len()));
+        for failure in failed {
+            out.push_str(&format!(
+                "\n  #{}: {}",
+                text(failure, "index"),
+                text(failure, "error")
+            ));
+        }
+    }
+    out
+}
+
+/// Token 刷新结果
+pub fn refresh_outcome(value: &Value) -> String {
+    format!(
+        "已刷新 {}，新 Token 过期时间: {}",
+        text(value, "uuid"),
+        text(value, "expires_at")
+    )
+}
+
+/// 路由列表
+pub fn routes(value: &Value) -> String {
+    let mut table = Table::new(&[
+        "PATH",
+        "TYPE",
+        "PROVIDER",
+        "CREDENTIAL",
+        "ENABLED",
+        "PRIORITY",
+    ]);
+    for route in items(value) {
+        let credential = match route.get("credential_name").and_then(Value::as_str) {
+            Some(name) => name.to_string(),
+            None => route
+                .get("credential_uuid")
+                .and_then(Value::as_str)
+                .map(short_uuid)
+                .unwrap_or_else(|| "-".to_string()),
+        };
+        table.push(vec![
+            text(route, "path_pattern"),
+            text(route, "route_type"),
+            text(route, "provider_type"),
+            credential,
+            if flag(route, "enabled") { "yes" } else { "no" }.to_string(),
+            text(route, "priority"),
+        ]);
+    }
+    table.render()
+}
+
+/// 凭证池健康报告
+pub fn pool_health(value: &Value) -> String {
+    let mut providers = Table::new(&[
+        "PROVIDER", "TOTAL", "HEALTHY", "DISABLED", "USAGE", "ERRORS",
+    ]);
+    for provider in value.get("providers").map(items).unwrap_or_default() {
+        providers.push(vec![
+            text(provider, "provider_type"),
+            text(provider, "total_count"),
+            text(provider, "healthy_count"),
+            text(provider, "disabled_count"),
+            text(provider, "total_usage"),
+            text(provider, "total_errors"),
+        ]);
+    }
+
+    let mut credentials = Table::new(&[
+        "UUID",
+        "PROVIDER",
+        "NAME",
+        "HEALTHY",
+        "FAILURES",
+        "LAST ERROR",
+    ]);
+    for credential in value.get("credentials").map(items).unwrap_or_default() {
+        credentials.push(vec![
+            short_uuid(&text(credential, "uuid")),
+            text(credential, "provider_type"),
+            text(credential, "name"),
+            if flag(credential, "is_healthy") {
+                "yes"
+            } else {
+                "no"
+            }
+            .to_string(),
+            text(credential, "failure_count"),
+            text(credential, "last_error"),
+        ]);
+    }
+
+    format!("{}\n\n{}", providers.render(), credentials.render())
+}
+
+fn cost(row: &Value) -> String {
+    match row.get("cost").and_then(Value::as_f64) {
+        Some(cost) => format!(
+            "{:.4} {}",
+            cost,
+            row.get("currency").and_then(Value::as_str).unwrap_or("")
+        )
+        .trim_end()
+        .to_string(),
+        None => "-".to_string(),
+    }
+}
+
+/// 用量报表
+pub fn usage(value: &Value) -> String {
+    let key_header = match value.get("group_by").and_then(Value::as_str) {
+        Some("model") => "MODEL",
+        _ => "PROVIDER",
+    };
+    let mut table = Table::new(&[key_header, "REQUESTS", "INPUT", "OUTPUT", "TOTAL", "COST"]);
+    let mut push = |row: &Value, key: String| {
+        table.push(vec![
+            key,
+            text(row, "requests"),
+            text(row, "input_tokens"),
+            text(row, "output_tokens"),
+            text(row, "total_tokens"),
+            cost(row),
+        ]);
+    };
+    for row in value.get("rows").map(items).unwrap_or_default() {
+        push(row, text(row, "key"));
+    }
+    if let Some(total) = value.get("total") {
+        push(total, "(total)".to_string());
+    }
+    table.render()
+}
+
+/// 单条日志
+pub fn log_line(entry: &Value) -> String {
+    format!(
+        "{} [{}] {}",
+        text(entry, "timestamp"),
+        text(entry, "level").to_uppercase(),
+        text(entry, "message")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_table_aligns_wide_characters() {
+        let mut table = Table::new(&["NAME", "STATUS"]);
+        table.push(vec!["主账号".to_string(), "ok".to_string()]);
+        table.push(vec!["backup".to_string(), "disabled".to_string()]);
+        assert_eq!(
+            table.render(),
+            "NAME    STATUS\n主账号  ok\nbackup  disabled"
+        );
+    }
+
+    #[test]
+    fn test_credentials_status_and_missing_fields() {
+        let rendered = credentials(&json!([
+            {"uuid": "u1", "provider_type": "kiro", "name": null, "is_healthy": true,
+             "is_disabled": false, "usage_count": 3, "error_count": 0},
+            {"uuid": "u2", "provider_type": "openai", "is_healthy": true, "is_disabled": true}
+        ]));
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("u1") && lines[1].contains("healthy"));
+        assert!(lines[2].contains("disabled"));
+    }
+
+    #[test]
+    fn test_usage_includes_total_and_cost() {
+        let rendered = usage(&json!({
+            "group_by": "model",
+            "rows": [{"key": "gpt-4o", "requests": 2, "input_tokens": 100,
+                      "output_tokens": 50, "total_tokens": 150, "cost": 0.0015, "currency": "USD"}],
+            "total": {"key": "", "requests": 2, "input_tokens": 100,
+                      "output_tokens": 50, "total_tokens": 150, "cost": null}
+        }));
+        assert!(rendered.starts_with("MODEL"));
+        assert!(rendered.contains("0.0015 USD"));
+        assert!(rendered.lines().last().unwrap().starts_with("(total)"));
+    }
+}
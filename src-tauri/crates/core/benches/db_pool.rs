@@ -0,0 +1,156 @@
+//! 凭证热路径数据库延迟基准
+//!
+//! 模拟网关并发请求：每个请求先按类型查询候选凭证，等待上游响应，再写回使用次数。
+//! 对比两种访问方式的 p50 / p99：
+//!
+//! - legacy：在 tokio 工作线程上直接锁 `DbConnection`，读写共用一把锁；
+//! - pooled：读走 `DbPool` 只读连接（spawn_blocking），写入经后台写入队列执行并等待完成。
+//!
+//! 运行：`cargo bench -p proxycast-core --bench db_pool`
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use proxycast_core::database::dao::provider_pool::ProviderPoolDao;
+use proxycast_core::database::pool::spawn_write;
+use proxycast_core::database::{init_database_at, lock_db, DbConnection, DbPool, PoolConfig};
+use proxycast_core::models::provider_pool_model::{
+    CredentialData, PoolProviderType, ProviderCredential,
+};
+
+const CREDENTIALS: usize = 50;
+const CONCURRENCY: usize = 64;
+const REQUESTS_PER_TASK: usize = 200;
+/// 模拟上游调用耗时（选择凭证与写回统计之间）
+const UPSTREAM: Duration = Duration::from_millis(2);
+
+fn seed(db: &DbConnection) {
+    let conn = lock_db(db).unwrap();
+    for i in 0..CREDENTIALS {
+        let cred = ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: format!("sk-bench-{i}"),
+                base_url: None,
+            },
+        );
+        ProviderPoolDao::insert(&conn, &cred).unwrap();
+    }
+}
+
+fn select_and_pick(conn: &rusqlite::Connection, n: usize) -> Result<String, String> {
+    let creds =
+        ProviderPoolDao::get_by_type(conn, &PoolProviderType::OpenAI).map_err(|e| e.to_string())?;
+    creds
+        .get(n % creds.len().max(1))
+        .map(|c| c.uuid.clone())
+        .ok_or_else(|| "没有凭证".to_string())
+}
+
+fn record_usage(conn: &rusqlite::Connection, uuid: &str) -> Result<(), String> {
+    let cred = ProviderPoolDao::get_by_uuid(conn, uuid)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Credential not found: {uuid}"))?;
+    ProviderPoolDao::update_usage(conn, uuid, cred.usage_count + 1, Utc::now())
+        .map_err(|e| e.to_string())
+}
+
+async fn legacy_request(db: DbConnection, n: usize) {
+    let uuid = {
+        let conn = lock_db(&db).unwrap();
+        select_and_pick(&conn, n).unwrap()
+    };
+    tokio::time::sleep(UPSTREAM).await;
+    let conn = lock_db(&db).unwrap();
+    record_usage(&conn, &uuid).unwrap();
+}
+
+async fn pooled_request(pool: DbPool, n: usize) {
+    let uuid = pool
+        .read_async(move |conn| select_and_pick(conn, n))
+        .await
+        .unwrap();
+    tokio::time::sleep(UPSTREAM).await;
+    // 等待写入落库，延迟与 legacy 一样包含写回统计的耗时
+    spawn_write(pool.writer(), "bench", move |conn| {
+        record_usage(conn, &uuid)
+    })
+    .done()
+    .await;
+}
+
+async fn measure<F, Fut>(request: F) -> Vec<Duration>
+where
+    F: Fn(usize) -> Fut + Send + Sync + 'static,
+    Fut: std::future::Future<Output = ()> + Send,
+{
+    let request = Arc::new(request);
+    let mut tasks = Vec::with_capacity(CONCURRENCY);
+    for task in 0..CONCURRENCY {
+        let request = request.clone();
+        tasks.push(tokio::spawn(async move {
+            let mut latencies = Vec::with_capacity(REQUESTS_PER_TASK);
+            for i in 0..REQUESTS_PER_TASK {
+                let start = Instant::now();
+                request(task * REQUESTS_PER_TASK + i).await;
+                latencies.push(start.elapsed());
+                tokio::task::yield_now().await;
+            }
+            latencies
+        }));
+    }
+
+    let mut latencies = Vec::with_capacity(CONCURRENCY * REQUESTS_PER_TASK);
+    for task in tasks {
+        latencies.extend(task.await.unwrap());
+    }
+    latencies.sort();
+    latencies
+}
+
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let index = ((sorted.len() as f64 - 1.0) * p).round() as usize;
+    sorted[index]
+}
+
+fn report(label: &str, latencies: &[Duration], elapsed: Duration) {
+    println!(
+        "{label:<8} requests={} total={:>8.1?} p50={:>9.1?} p99={:>9.1?} max={:>9.1?}",
+        latencies.len(),
+        elapsed,
+        percentile(latencies, 0.50),
+        percentile(latencies, 0.99),
+        latencies.last().copied().unwrap_or_default(),
+    );
+}
+
+fn main() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = init_database_at(&dir.path().join("bench.db")).unwrap();
+    seed(&db);
+    let pool = DbPool::with_writer(db.clone(), PoolConfig::default()).unwrap();
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(4)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    println!(
+        "并发 {CONCURRENCY} 个任务，每个 {REQUESTS_PER_TASK} 次请求，{CREDENTIALS} 个凭证，{} 个只读连接",
+        pool.reader_count()
+    );
+
+    runtime.block_on(async {
+        let start = Instant::now();
+        let legacy_db = db.clone();
+        let latencies = measure(move |n| legacy_request(legacy_db.clone(), n)).await;
+        report("legacy", &latencies, start.elapsed());
+
+        let start = Instant::now();
+        let pooled = pool.clone();
+        let latencies = measure(move |n| pooled_request(pooled.clone(), n)).await;
+        report("pooled", &latencies, start.elapsed());
+    });
+}
@@ -3,7 +3,8 @@
 //! 该模块仅包含与 Tauri 无关的纯配置处理。
 
 use crate::app_utils::{generate_api_key, is_valid_bind_host};
-use crate::config::{self, Config};
+use crate::config::{self, Config, ConfigManager};
+use std::path::Path;
 
 /// 配置验证错误
 #[derive(Debug)]
@@ -56,6 +57,31 @@ pub fn load_and_validate_config() -> Result<Config, ConfigError> {
         tracing::info!("检测到默认 API key，已自动生成并保存新密钥");
     }
 
+    validate_config(&config)?;
+
+    Ok(config)
+}
+
+/// 从指定路径加载 YAML 配置（不做任何写回）
+///
+/// 与 [`config::load_config`] 不同，文件不存在时直接报错，不回退到默认配置。
+pub fn load_config_from(path: &Path) -> Result<Config, ConfigError> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| ConfigError::LoadFailed(format!("{}: {e}", path.display())))?;
+    ConfigManager::parse_yaml(&content).map_err(|e| ConfigError::LoadFailed(e.to_string()))
+}
+
+/// 校验配置中与服务启动相关的约束
+pub fn validate_config(config: &Config) -> Result<(), ConfigError> {
+    if !is_valid_bind_host(&config.server.host) {
+        return Err(ConfigError::InvalidHost);
+    }
+
+    let binds_all = config.server.host == "0.0.0.0" || config.server.host == "::";
+    if binds_all && config.server.api_key == config::DEFAULT_API_KEY {
+        return Err(ConfigError::DefaultApiKeyWithNonLocalBind);
+    }
+
     if config.server.tls.enable {
         return Err(ConfigError::TlsNotSupported);
     }
@@ -64,5 +90,41 @@ pub fn load_and_validate_config() -> Result<Config, ConfigError> {
         return Err(ConfigError::RemoteManagementNotSupported);
     }
 
-    Ok(config)
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_config_rejects_default_key_on_all_interfaces() {
+        let mut config = Config::default();
+        config.server.host = "0.0.0.0".to_string();
+        config.server.api_key = config::DEFAULT_API_KEY.to_string();
+        assert!(matches!(
+            validate_config(&config),
+            Err(ConfigError::DefaultApiKeyWithNonLocalBind)
+        ));
+
+        config.server.api_key = "pc_custom_key".to_string();
+        assert!(validate_config(&config).is_ok());
+    }
+
+    #[test]
+    fn test_validate_config_rejects_invalid_host() {
+        let mut config = Config::default();
+        config.server.host = "8.8.8.8".to_string();
+        assert!(matches!(
+            validate_config(&config),
+            Err(ConfigError::InvalidHost)
+        ));
+    }
+
+    #[test]
+    fn test_load_config_from_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = load_config_from(&dir.path().join("missing.yaml"));
+        assert!(matches!(result, Err(ConfigError::LoadFailed(_))));
+    }
 }
@@ -405,6 +405,27 @@ impl HotReloadManager {
             ));
         }
 
+        // 验证熔断器配置
+        let circuit = &config.circuit_breaker;
+        if !(circuit.failure_rate_threshold > 0.0 && circuit.failure_rate_threshold <= 1.0) {
+            return Err(HotReloadError::ValidationError(
+                "熔断失败率阈值必须在 (0, 1] 范围内".to_string(),
+            ));
+        }
+
+        if circuit.window_secs == 0 || circuit.half_open_probes == 0 {
+            return Err(HotReloadError::ValidationError(
+                "熔断统计窗口和半开探测数不能为 0".to_string(),
+            ));
+        }
+
+        // 验证并发限制配置
+        if config.concurrency.enabled && config.concurrency.queue_timeout_secs == 0 {
+            return Err(HotReloadError::ValidationError(
+                "排队超时不能为 0".to_string(),
+            ));
+        }
+
         // 验证日志保留天数
         if config.logging.retention_days == 0 {
             return Err(HotReloadError::ValidationError(
@@ -22,18 +22,21 @@ pub use path_utils::{collapse_tilde, contains_tilde, expand_tilde};
 pub use types::{
     generate_secure_api_key, AmpConfig, AmpModelMapping, ApiKeyEntry, AsrCredentialEntry,
     AsrProviderType, AssistantConfig, AssistantProfile, BaiduConfig, ChannelsConfig,
-    ChatAppearanceConfig, Config, ContentCreatorConfig, ConversationSettings, CredentialEntry,
-    CredentialPoolConfig, CustomProviderConfig, DeliveryConfig, EndpointProvidersConfig,
-    ExperimentalFeatures, GeminiApiKeyEntry, HeartbeatExecutionMode, HeartbeatSecurityConfig,
-    HeartbeatSettings, HintRouteSettingsEntry, HintRouterSettings, ImageGenConfig,
+    ChatAppearanceConfig, CircuitBreakerSettings, ConcurrencySettings, Config,
+    ContentCreatorConfig, ConversationSettings, CredentialEntry, CredentialPoolConfig,
+    CustomProviderConfig, DeliveryConfig, EndpointProvidersConfig, ExperimentalFeatures,
+    GeminiApiKeyEntry, GuardrailAction, GuardrailDetector, GuardrailRule, GuardrailSettings,
+    HeartbeatExecutionMode, HeartbeatSecurityConfig, HeartbeatSettings, HedgingRule,
+    HedgingSettings, HintRouteSettingsEntry, HintRouterSettings, ImageGenConfig,
     InjectionRuleConfig, InjectionSettings, LoggingConfig, MemoryAutoConfig, MemoryConfig,
     MemoryProfileConfig, MemoryResolveConfig, MemorySourcesConfig, ModelInfo, ModelsConfig,
     NativeAgentConfig, NavigationConfig, OpenAIAsrConfig, PairingSettings, ProviderConfig,
     ProviderModelsConfig, ProvidersConfig, QuotaExceededConfig, RateLimitSettings,
     RemoteManagementConfig, RetrySettings, RoutingConfig, ScreenshotChatConfig, SearchEngine,
-    ServerConfig, TaskSchedule, TlsConfig, UpdateCheckConfig, UserProfile, VertexApiKeyEntry,
-    VertexModelAlias, VoiceConfig, VoiceInputConfig, VoiceInstruction, VoiceOutputConfig,
-    VoiceOutputMode, VoiceProcessorConfig, WebSearchConfig, WhisperLocalConfig, WhisperModelSize,
-    WorkspaceSandboxConfig, XunfeiConfig, DEFAULT_API_KEY,
+    ServerConfig, StreamFailoverSettings, StructuredOutputSettings, TaskSchedule, TlsConfig,
+    TokenRefreshSettings, ToolEmulationRule, ToolEmulationSettings, UpdateCheckConfig, UserProfile,
+    VertexApiKeyEntry, VertexModelAlias, VoiceConfig, VoiceInputConfig, VoiceInstruction,
+    VoiceOutputConfig, VoiceOutputMode, VoiceProcessorConfig, WebSearchConfig, WhisperLocalConfig,
+    WhisperModelSize, WorkspaceSandboxConfig, XunfeiConfig, DEFAULT_API_KEY,
 };
 pub use yaml::{load_config, save_config, ConfigError, ConfigManager, YamlService};
@@ -153,6 +153,9 @@ pub struct BaiduConfig {
     pub api_key: String,
     /// Secret Key
     pub secret_key: String,
+    /// App ID（实时流式识别需要，未配置时只能录音结束后批量识别）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<u64>,
 }
 
 /// OpenAI ASR 配置
@@ -429,6 +432,30 @@ pub struct Config {
     /// 速率限制配置
     #[serde(default)]
     pub rate_limit: RateLimitSettings,
+    /// 熔断器配置
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerSettings,
+    /// 对冲请求配置
+    #[serde(default)]
+    pub hedging: HedgingSettings,
+    /// 流式响应中途故障转移配置
+    #[serde(default)]
+    pub stream_failover: StreamFailoverSettings,
+    /// 结构化输出（response_format）配置
+    #[serde(default)]
+    pub structured_output: StructuredOutputSettings,
+    /// 工具调用模拟配置
+    #[serde(default)]
+    pub tool_emulation: ToolEmulationSettings,
+    /// 内容安全（敏感信息检测）配置
+    #[serde(default)]
+    pub guardrails: GuardrailSettings,
+    /// 并发限制与排队配置
+    #[serde(default)]
+    pub concurrency: ConcurrencySettings,
+    /// OAuth Token 后台预刷新配置
+    #[serde(default)]
+    pub token_refresh: TokenRefreshSettings,
     /// 对话管理配置
     #[serde(default)]
     pub conversation: ConversationSettings,
@@ -1788,6 +1815,14 @@ impl Default for Config {
             assistant: AssistantConfig::default(),
             user_profile: UserProfile::default(),
             rate_limit: RateLimitSettings::default(),
+            circuit_breaker: CircuitBreakerSettings::default(),
+            hedging: HedgingSettings::default(),
+            stream_failover: StreamFailoverSettings::default(),
+            structured_output: StructuredOutputSettings::default(),
+            tool_emulation: ToolEmulationSettings::default(),
+            guardrails: GuardrailSettings::default(),
+            concurrency: ConcurrencySettings::default(),
+            token_refresh: TokenRefreshSettings::default(),
             conversation: ConversationSettings::default(),
             hint_router: HintRouterSettings::default(),
             pairing: PairingSettings::default(),
@@ -2709,6 +2744,389 @@ impl Default for RateLimitSettings {
     }
 }
 
+/// 熔断器配置
+///
+/// 按凭证和 Provider 分别统计失败率，失败率超过阈值后熔断，
+/// 熔断一段时间后进入半开状态放行少量探测请求。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CircuitBreakerSettings {
+    /// 是否启用熔断器
+    #[serde(default = "default_circuit_enabled")]
+    pub enabled: bool,
+    /// 失败率统计窗口（秒）
+    #[serde(default = "default_circuit_window_secs")]
+    pub window_secs: u64,
+    /// 窗口内触发熔断的最少请求数
+    #[serde(default = "default_circuit_min_requests")]
+    pub min_requests: u32,
+    /// 触发熔断的失败率阈值（0.0 - 1.0）
+    #[serde(default = "default_circuit_failure_rate")]
+    pub failure_rate_threshold: f64,
+    /// 熔断持续时间（秒），之后进入半开状态
+    #[serde(default = "default_circuit_open_secs")]
+    pub open_secs: u64,
+    /// 半开状态允许的探测请求数，全部成功后关闭熔断
+    #[serde(default = "default_circuit_half_open_probes")]
+    pub half_open_probes: u32,
+}
+
+fn default_circuit_enabled() -> bool {
+    true
+}
+fn default_circuit_window_secs() -> u64 {
+    60
+}
+fn default_circuit_min_requests() -> u32 {
+    10
+}
+fn default_circuit_failure_rate() -> f64 {
+    0.5
+}
+fn default_circuit_open_secs() -> u64 {
+    30
+}
+fn default_circuit_half_open_probes() -> u32 {
+    3
+}
+
+impl Default for CircuitBreakerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_circuit_enabled(),
+            window_secs: default_circuit_window_secs(),
+            min_requests: default_circuit_min_requests(),
+            failure_rate_threshold: default_circuit_failure_rate(),
+            open_secs: default_circuit_open_secs(),
+            half_open_probes: default_circuit_half_open_probes(),
+        }
+    }
+}
+
+/// 对冲请求配置
+///
+/// 主请求在延迟阈值内未返回时，向另一个凭证发出相同请求，
+/// 取先返回的结果并取消另一个。默认关闭，且只对匹配规则的请求生效。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct HedgingSettings {
+    /// 是否启用对冲请求
+    #[serde(default)]
+    pub enabled: bool,
+    /// 对冲规则（按顺序匹配，首个匹配的规则生效）
+    #[serde(default)]
+    pub rules: Vec<HedgingRule>,
+    /// 永不对冲的模型（支持 `*` 通配符，优先于规则）
+    #[serde(default)]
+    pub exclude_models: Vec<String>,
+}
+
+/// 对冲规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HedgingRule {
+    /// 匹配的客户端类型（如 `claude_code`、`cursor`，为空时匹配所有）
+    #[serde(default)]
+    pub client_types: Vec<String>,
+    /// 匹配的 Provider（如 `kiro`、`antigravity`，为空时匹配所有）
+    #[serde(default)]
+    pub providers: Vec<String>,
+    /// 匹配的模型（支持 `*` 通配符，为空时匹配所有）
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// 固定对冲延迟（毫秒），未设置时使用实测 P90 延迟
+    #[serde(default)]
+    pub delay_ms: Option<u64>,
+    /// 使用 P90 延迟时的下限（毫秒）
+    #[serde(default = "default_hedging_min_delay_ms")]
+    pub min_delay_ms: u64,
+    /// 实测样本不足时使用的延迟（毫秒）
+    #[serde(default = "default_hedging_fallback_delay_ms")]
+    pub fallback_delay_ms: u64,
+}
+
+fn default_hedging_min_delay_ms() -> u64 {
+    500
+}
+fn default_hedging_fallback_delay_ms() -> u64 {
+    3000
+}
+
+/// 流式响应中途故障转移配置
+///
+/// 流式响应已开始下发后上游中途断开时，以已下发的助手文本作为预填充，
+/// 向另一个凭证重新发起请求，并把续传内容拼接到同一个客户端流中。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StreamFailoverSettings {
+    /// 是否启用中途故障转移
+    #[serde(default = "default_stream_failover_enabled")]
+    pub enabled: bool,
+    /// 单个请求最多续传次数
+    #[serde(default = "default_stream_failover_max_resumes")]
+    pub max_resumes: u32,
+}
+
+fn default_stream_failover_enabled() -> bool {
+    true
+}
+fn default_stream_failover_max_resumes() -> u32 {
+    1
+}
+
+impl Default for StreamFailoverSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_stream_failover_enabled(),
+            max_resumes: default_stream_failover_max_resumes(),
+        }
+    }
+}
+
+/// 结构化输出（response_format）配置
+///
+/// 后端不支持 `response_format` 时，注入 Schema 说明并按 JSON Schema 校验模型输出，
+/// 不符合时把校验错误反馈给模型重试。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StructuredOutputSettings {
+    /// 是否启用结构化输出转换与校验（关闭后 response_format 被忽略）
+    #[serde(default = "default_structured_output_enabled")]
+    pub enabled: bool,
+    /// 输出不符合 Schema 时的最大修复重试次数
+    #[serde(default = "default_structured_output_max_repair_attempts")]
+    pub max_repair_attempts: u32,
+}
+
+fn default_structured_output_enabled() -> bool {
+    true
+}
+fn default_structured_output_max_repair_attempts() -> u32 {
+    2
+}
+
+impl Default for StructuredOutputSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_structured_output_enabled(),
+            max_repair_attempts: default_structured_output_max_repair_attempts(),
+        }
+    }
+}
+
+/// 工具调用模拟配置
+///
+/// 部分 OpenAI 兼容端点和本地模型不支持 `tools`。对匹配规则的请求，
+/// 把工具定义渲染进提示词，再从模型输出中解析出工具调用。默认关闭。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ToolEmulationSettings {
+    /// 是否启用工具调用模拟
+    #[serde(default)]
+    pub enabled: bool,
+    /// 模拟规则（任一规则匹配即模拟）
+    #[serde(default)]
+    pub rules: Vec<ToolEmulationRule>,
+}
+
+/// 工具调用模拟规则
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ToolEmulationRule {
+    /// 匹配的 Provider 类型、凭证名称或凭证 UUID（为空时匹配所有）
+    #[serde(default)]
+    pub providers: Vec<String>,
+    /// 匹配的模型（支持 `*` 通配符，为空时匹配所有）
+    #[serde(default)]
+    pub models: Vec<String>,
+}
+
+/// 内容安全（Guardrails）配置
+///
+/// 请求发往上游前按规则检测敏感信息（邮箱、手机号、身份证号、内部主机名等），
+/// 命中后拦截请求、打码或替换为可还原的令牌。每次处置都会记录审计条目。默认关闭。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GuardrailSettings {
+    /// 是否启用内容安全检查
+    #[serde(default)]
+    pub enabled: bool,
+    /// 检测规则（命中位置重叠时按起始位置靠前者处置，起始相同时按规则顺序）
+    #[serde(default)]
+    pub rules: Vec<GuardrailRule>,
+    /// 内存中保留的审计条目数
+    #[serde(default = "default_guardrail_audit_capacity")]
+    pub audit_capacity: usize,
+}
+
+fn default_guardrail_audit_capacity() -> usize {
+    1000
+}
+
+impl Default for GuardrailSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rules: Vec::new(),
+            audit_capacity: default_guardrail_audit_capacity(),
+        }
+    }
+}
+
+/// 内容安全规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GuardrailRule {
+    /// 规则名称（用于审计和令牌标签）
+    pub name: String,
+    /// 检测器
+    #[serde(flatten)]
+    pub detector: GuardrailDetector,
+    /// 命中后的处置方式
+    #[serde(default)]
+    pub action: GuardrailAction,
+}
+
+/// 内容安全检测器
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "detector", rename_all = "snake_case")]
+pub enum GuardrailDetector {
+    /// 邮箱地址
+    Email,
+    /// 中国大陆手机号
+    PhoneCn,
+    /// 中国居民身份证号（校验出生日期和校验位）
+    IdCardCn,
+    /// 以指定域名后缀结尾的主机名
+    Hostname { suffixes: Vec<String> },
+    /// 自定义正则
+    Regex { pattern: String },
+    /// 自定义词典
+    Dictionary {
+        terms: Vec<String>,
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+}
+
+/// 内容安全处置方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardrailAction {
+    /// 拒绝请求
+    Block,
+    /// 替换为打码文本，不可还原
+    #[default]
+    Redact,
+    /// 替换为令牌，响应返回前还原为原文
+    Tokenize,
+}
+
+/// 并发限制与排队配置
+///
+/// 按凭证和 Provider 限制同时进行的上游请求数，超出时进入等待队列。
+/// 队列按客户端加权公平调度，交互式请求优先于批量任务和定时任务。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConcurrencySettings {
+    /// 是否启用并发限制（关闭时仍统计并发数，但不排队）
+    #[serde(default)]
+    pub enabled: bool,
+    /// 每个凭证的最大并发数（0 表示不限制）
+    #[serde(default = "default_max_per_credential")]
+    pub max_per_credential: u32,
+    /// 每个 Provider 的最大并发数（0 表示不限制）
+    #[serde(default)]
+    pub max_per_provider: u32,
+    /// 按凭证 ID 覆盖的并发上限
+    #[serde(default)]
+    pub credential_limits: HashMap<String, u32>,
+    /// 按 Provider 覆盖的并发上限（如 `kiro: 8`）
+    #[serde(default)]
+    pub provider_limits: HashMap<String, u32>,
+    /// 排队超时（秒）
+    #[serde(default = "default_queue_timeout_secs")]
+    pub queue_timeout_secs: u64,
+    /// 最大排队请求数，超出时直接拒绝
+    #[serde(default = "default_max_queue_depth")]
+    pub max_queue_depth: usize,
+    /// 客户端权重（键为客户端类型，如 `claude_code`、`cursor`、`batch`；默认权重 1）
+    #[serde(default)]
+    pub client_weights: HashMap<String, u32>,
+}
+
+fn default_max_per_credential() -> u32 {
+    4
+}
+fn default_queue_timeout_secs() -> u64 {
+    30
+}
+fn default_max_queue_depth() -> usize {
+    256
+}
+
+impl Default for ConcurrencySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_per_credential: default_max_per_credential(),
+            max_per_provider: 0,
+            credential_limits: HashMap::new(),
+            provider_limits: HashMap::new(),
+            queue_timeout_secs: default_queue_timeout_secs(),
+            max_queue_depth: default_max_queue_depth(),
+            client_weights: HashMap::new(),
+        }
+    }
+}
+
+/// OAuth Token 后台预刷新配置
+///
+/// 在 Token 过期前主动刷新（带随机抖动），避免过期后的首个请求承担刷新延迟。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TokenRefreshSettings {
+    /// 是否启用后台预刷新
+    #[serde(default = "default_token_refresh_enabled")]
+    pub enabled: bool,
+    /// 提前刷新的时间（秒）
+    #[serde(default = "default_refresh_ahead_secs")]
+    pub refresh_ahead_secs: u64,
+    /// 额外的随机抖动上限（秒），避免多个凭证同时刷新
+    #[serde(default = "default_refresh_jitter_secs")]
+    pub jitter_secs: u64,
+    /// 检查间隔（秒）
+    #[serde(default = "default_refresh_check_interval_secs")]
+    pub check_interval_secs: u64,
+    /// 刷新失败后的首次重试延迟（秒），之后按指数退避
+    #[serde(default = "default_refresh_initial_backoff_secs")]
+    pub initial_backoff_secs: u64,
+    /// 最大退避时间（秒）
+    #[serde(default = "default_refresh_max_backoff_secs")]
+    pub max_backoff_secs: u64,
+}
+
+fn default_token_refresh_enabled() -> bool {
+    true
+}
+fn default_refresh_ahead_secs() -> u64 {
+    600
+}
+fn default_refresh_jitter_secs() -> u64 {
+    120
+}
+fn default_refresh_check_interval_secs() -> u64 {
+    30
+}
+fn default_refresh_initial_backoff_secs() -> u64 {
+    30
+}
+fn default_refresh_max_backoff_secs() -> u64 {
+    1800
+}
+
+impl Default for TokenRefreshSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_token_refresh_enabled(),
+            refresh_ahead_secs: default_refresh_ahead_secs(),
+            jitter_secs: default_refresh_jitter_secs(),
+            check_interval_secs: default_refresh_check_interval_secs(),
+            initial_backoff_secs: default_refresh_initial_backoff_secs(),
+            max_backoff_secs: default_refresh_max_backoff_secs(),
+        }
+    }
+}
+
 /// 对话管理配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ConversationSettings {
@@ -24,6 +24,27 @@ pub enum HealthStatus {
     Unknown,
 }
 
+/// 熔断器视角的凭证状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CircuitHealth {
+    /// 熔断打开
+    Open {
+        /// 距离进入半开探测的剩余时间（毫秒）
+        retry_after_ms: u64,
+    },
+    /// 半开探测中
+    HalfOpen,
+}
+
+/// 熔断状态来源
+///
+/// 由 `proxycast_infra::resilience::CircuitBreaker` 实现，
+/// 凭证池据此跳过熔断打开的凭证，并在健康状态中反映熔断器的状态。
+pub trait CircuitHealthSource: Send + Sync {
+    /// 获取凭证的熔断状态（熔断器关闭时返回 None）
+    fn credential_circuit(&self, credential_id: &str) -> Option<CircuitHealth>;
+}
+
 /// 健康检查结果
 #[derive(Debug, Clone)]
 pub struct HealthCheckResult {
@@ -9,7 +9,10 @@ pub mod pool;
 pub mod risk;
 pub mod types;
 
-pub use health::{HealthCheckConfig, HealthCheckResult, HealthChecker, HealthStatus};
+pub use health::{
+    CircuitHealth, CircuitHealthSource, HealthCheckConfig, HealthCheckResult, HealthChecker,
+    HealthStatus,
+};
 pub use pool::{CredentialPool, PoolError, PoolStatus};
 pub use risk::{CooldownConfig, RateLimitEvent, RateLimitStats, RiskController, RiskLevel};
 pub use types::{Credential, CredentialData, CredentialStats, CredentialStatus};
@@ -37,8 +37,8 @@ pub fn migrate_unified_content_system(conn: &Connection) -> Result<MigrationResu
 
     tracing::info!("[迁移] 开始执行统一内容系统迁移");
 
-    // 开始事务
-    conn.execute("BEGIN TRANSACTION", [])
+    // 使用 SAVEPOINT：单独调用时等同于事务，在迁移注册表的事务内调用时可以嵌套
+    conn.execute("SAVEPOINT unified_content_system", [])
         .map_err(|e| format!("开始事务失败: {e}"))?;
 
     // 执行迁移
@@ -50,7 +50,7 @@ pub fn migrate_unified_content_system(conn: &Connection) -> Result<MigrationResu
             mark_migration_completed(conn, MIGRATION_KEY_UNIFIED_CONTENT)?;
 
             // 提交事务
-            conn.execute("COMMIT", [])
+            conn.execute("RELEASE unified_content_system", [])
                 .map_err(|e| format!("提交事务失败: {e}"))?;
 
             tracing::info!(
@@ -64,7 +64,9 @@ pub fn migrate_unified_content_system(conn: &Connection) -> Result<MigrationResu
         Err(e) => {
             // 回滚事务
             // _Requirements: 2.4_
-            let _ = conn.execute("ROLLBACK", []);
+            let _ = conn.execute_batch(
+                "ROLLBACK TO unified_content_system; RELEASE unified_content_system;",
+            );
             tracing::error!("[迁移] 统一内容系统迁移失败，已回滚: {}", e);
             Err(e)
         }
@@ -51,8 +51,8 @@ pub fn migrate_playwright_mcp_server(conn: &Connection) -> Result<MigrationResul
         });
     }
 
-    // 开始事务
-    conn.execute("BEGIN TRANSACTION", [])
+    // 使用 SAVEPOINT：单独调用时等同于事务，在迁移注册表的事务内调用时可以嵌套
+    conn.execute("SAVEPOINT playwright_mcp_server", [])
         .map_err(|e| format!("开始事务失败: {e}"))?;
 
     // 执行迁移
@@ -64,7 +64,7 @@ pub fn migrate_playwright_mcp_server(conn: &Connection) -> Result<MigrationResul
             mark_migration_completed(conn, MIGRATION_KEY_PLAYWRIGHT_SERVER)?;
 
             // 提交事务
-            conn.execute("COMMIT", [])
+            conn.execute("RELEASE playwright_mcp_server", [])
                 .map_err(|e| format!("提交事务失败: {e}"))?;
 
             tracing::info!(
@@ -79,7 +79,8 @@ pub fn migrate_playwright_mcp_server(conn: &Connection) -> Result<MigrationResul
         }
         Err(e) => {
             // 回滚事务
-            let _ = conn.execute("ROLLBACK", []);
+            let _ = conn
+                .execute_batch("ROLLBACK TO playwright_mcp_server; RELEASE playwright_mcp_server;");
             tracing::error!("[迁移] Playwright MCP Server 迁移失败，已回滚: {}", e);
             Err(e)
         }
@@ -50,8 +50,8 @@ pub fn migrate_fix_promise_paths(conn: &Connection) -> Result<MigrationResult, S
         .unwrap_or_else(|| "/Users/unknown".to_string());
     let default_path = format!("{}/{}", home, DEFAULT_PROJECTS_DIR);
 
-    // 开始事务
-    conn.execute("BEGIN TRANSACTION", [])
+    // 使用 SAVEPOINT：单独调用时等同于事务，在迁移注册表的事务内调用时可以嵌套
+    conn.execute("SAVEPOINT fix_promise_paths", [])
         .map_err(|e| format!("开始事务失败: {e}"))?;
 
     let result = execute_migration(conn, &default_path, promise_done, unify_done);
@@ -65,7 +65,7 @@ pub fn migrate_fix_promise_paths(conn: &Connection) -> Result<MigrationResult, S
                 mark_migration_completed(conn, MIGRATION_KEY_UNIFY_SESSION_DIRS)?;
             }
 
-            conn.execute("COMMIT", [])
+            conn.execute("RELEASE fix_promise_paths", [])
                 .map_err(|e| format!("提交事务失败: {e}"))?;
 
             if fixed_ws > 0 || fixed_sess > 0 {
@@ -90,7 +90,7 @@ pub fn migrate_fix_promise_paths(conn: &Connection) -> Result<MigrationResult, S
             })
         }
         Err(e) => {
-            let _ = conn.execute("ROLLBACK", []);
+            let _ = conn.execute_batch("ROLLBACK TO fix_promise_paths; RELEASE fix_promise_paths;");
             tracing::error!("[迁移] 路径修复和会话统一失败，已回滚: {}", e);
             Err(e)
         }
@@ -0,0 +1,656 @@
+//! 版本化迁移注册表
+//!
+//! 各 crate 把自己的迁移注册到 [`MigrationRegistry`]，打开数据库时按版本顺序执行：
+//!
+//! - 每个组件（`core`、`scheduler`、`terminal` …）有独立递增的版本号，
+//!   已执行的迁移记录在 `schema_migrations` 表；`core` 的版本同时写入 `PRAGMA user_version`；
+//! - 每个迁移在单独的事务中执行，失败时回滚，后续迁移不再执行；
+//! - 有待执行迁移时先备份数据库文件；
+//! - 数据库版本高于当前程序已知的最新版本时拒绝打开，避免旧版本程序写坏新结构；
+//! - [`MigrationRegistry::plan`] 只计算待执行迁移（dry-run），不修改数据库。
+
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, DatabaseName, OpenFlags, OptionalExtension};
+use serde::Serialize;
+
+use super::{migration, migration_v2, migration_v3, migration_v4};
+
+/// 核心组件名，其版本号同步到 `PRAGMA user_version`
+pub const CORE_COMPONENT: &str = "core";
+
+/// 自动备份保留数量
+const MAX_BACKUPS: usize = 5;
+
+/// 迁移函数，在注册表开启的事务内执行
+pub type MigrationFn = fn(&Connection) -> Result<(), String>;
+
+/// 单个迁移
+#[derive(Debug, Clone)]
+pub struct Migration {
+    /// 所属组件
+    pub component: &'static str,
+    /// 组件内版本号，从 1 开始递增
+    pub version: u32,
+    /// 迁移名称（用于日志与记录）
+    pub name: &'static str,
+    /// 迁移逻辑
+    pub up: MigrationFn,
+}
+
+impl Migration {
+    pub const fn new(
+        component: &'static str,
+        version: u32,
+        name: &'static str,
+        up: MigrationFn,
+    ) -> Self {
+        Self {
+            component,
+            version,
+            name,
+            up,
+        }
+    }
+}
+
+/// 迁移标识（计划与报告中使用）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationId {
+    pub component: String,
+    pub version: u32,
+    pub name: String,
+}
+
+impl From<&Migration> for MigrationId {
+    fn from(m: &Migration) -> Self {
+        Self {
+            component: m.component.to_string(),
+            version: m.version,
+            name: m.name.to_string(),
+        }
+    }
+}
+
+/// 组件版本
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComponentVersion {
+    pub component: String,
+    /// 数据库当前版本
+    pub current: u32,
+    /// 程序已知的最新版本
+    pub latest: u32,
+}
+
+/// 迁移计划（dry-run 结果）
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationPlan {
+    pub components: Vec<ComponentVersion>,
+    pub pending: Vec<MigrationId>,
+}
+
+/// 迁移执行报告
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MigrationReport {
+    pub applied: Vec<MigrationId>,
+    /// 迁移前的备份文件
+    pub backup: Option<PathBuf>,
+}
+
+/// 迁移注册表
+#[derive(Debug, Clone, Default)]
+pub struct MigrationRegistry {
+    migrations: Vec<Migration>,
+}
+
+impl MigrationRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 包含 core 迁移的注册表
+    pub fn core() -> Self {
+        let mut registry = Self::new();
+        registry
+            .register_all(core_migrations())
+            .expect("core 迁移版本号冲突");
+        registry
+    }
+
+    /// 注册迁移，同一组件内版本号不能重复且必须大于 0
+    pub fn register(&mut self, migration: Migration) -> Result<(), String> {
+        if migration.version == 0 {
+            return Err(format!(
+                "迁移 {}:{} 的版本号必须大于 0",
+                migration.component, migration.name
+            ));
+        }
+        if let Some(existing) = self
+            .migrations
+            .iter()
+            .find(|m| m.component == migration.component && m.version == migration.version)
+        {
+            return Err(format!(
+                "迁移版本冲突: {} v{} 已注册为 {}，不能再注册 {}",
+                migration.component, migration.version, existing.name, migration.name
+            ));
+        }
+        self.migrations.push(migration);
+        Ok(())
+    }
+
+    /// 批量注册
+    pub fn register_all(
+        &mut self,
+        migrations: impl IntoIterator<Item = Migration>,
+    ) -> Result<(), String> {
+        migrations.into_iter().try_for_each(|m| self.register(m))
+    }
+
+    /// 已注册的组件（core 在前，其余按注册顺序）
+    pub fn components(&self) -> Vec<&'static str> {
+        let mut components = vec![CORE_COMPONENT];
+        for m in &self.migrations {
+            if !components.contains(&m.component) {
+                components.push(m.component);
+            }
+        }
+        components
+    }
+
+    /// 组件的最新版本，未注册时为 0
+    pub fn latest_version(&self, component: &str) -> u32 {
+        self.migrations
+            .iter()
+            .filter(|m| m.component == component)
+            .map(|m| m.version)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// 计算待执行的迁移，不修改数据库
+    pub fn plan(&self, conn: &Connection) -> Result<MigrationPlan, String> {
+        let mut components = Vec::new();
+        let mut pending = Vec::new();
+
+        for component in self.components() {
+            let current = current_version(conn, component)?;
+            components.push(ComponentVersion {
+                component: component.to_string(),
+                current,
+                latest: self.latest_version(component),
+            });
+
+            let mut migrations: Vec<&Migration> = self
+                .migrations
+                .iter()
+                .filter(|m| m.component == component && m.version > current)
+                .collect();
+            migrations.sort_by_key(|m| m.version);
+            pending.extend(migrations.into_iter().map(MigrationId::from));
+        }
+
+        Ok(MigrationPlan {
+            components,
+            pending,
+        })
+    }
+
+    /// 以只读方式打开数据库文件并计算待执行的迁移（dry-run）
+    ///
+    /// 数据库文件不存在时视为空库，不会创建文件。
+    pub fn plan_at(&self, db_path: &Path) -> Result<MigrationPlan, String> {
+        let conn = if db_path.exists() {
+            Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+                .map_err(|e| format!("打开数据库失败 {db_path:?}: {e}"))?
+        } else {
+            Connection::open_in_memory().map_err(|e| e.to_string())?
+        };
+        self.plan(&conn)
+    }
+
+    /// 检查数据库版本是否被当前程序支持
+    pub fn ensure_supported(&self, conn: &Connection) -> Result<(), String> {
+        for component in self.components() {
+            let current = current_version(conn, component)?;
+            let latest = self.latest_version(component);
+            if current > latest {
+                return Err(format!(
+                    "数据库 {component} 版本 v{current} 高于当前程序支持的 v{latest}，请升级 ProxyCast 后再打开"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// 执行所有待执行的迁移
+    ///
+    /// `backup_dir` 不为空且存在待执行迁移时，先把数据库备份到该目录。
+    /// 某个迁移失败时回滚该迁移并返回错误，之前已提交的迁移保留。
+    pub fn migrate(
+        &self,
+        conn: &Connection,
+        backup_dir: Option<&Path>,
+    ) -> Result<MigrationReport, String> {
+        self.ensure_supported(conn)?;
+        ensure_migrations_table(conn)?;
+
+        let plan = self.plan(conn)?;
+        let mut report = MigrationReport::default();
+        if plan.pending.is_empty() {
+            return Ok(report);
+        }
+
+        if let Some(dir) = backup_dir {
+            let core_version = current_version(conn, CORE_COMPONENT)?;
+            report.backup = Some(backup_database(conn, dir, core_version)?);
+        }
+
+        for id in plan.pending {
+            let migration = self
+                .migrations
+                .iter()
+                .find(|m| m.component == id.component && m.version == id.version)
+                .ok_or_else(|| format!("迁移 {} v{} 未注册", id.component, id.version))?;
+            apply(conn, migration)?;
+            tracing::info!(
+                "[数据库] 已执行迁移 {} v{}: {}",
+                id.component,
+                id.version,
+                id.name
+            );
+            report.applied.push(id);
+        }
+
+        Ok(report)
+    }
+}
+
+fn ensure_migrations_table(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            component TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            applied_at INTEGER NOT NULL,
+            PRIMARY KEY (component, version)
+        )",
+        [],
+    )
+    .map_err(|e| format!("创建 schema_migrations 表失败: {e}"))?;
+    Ok(())
+}
+
+fn migrations_table_exists(conn: &Connection) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_migrations'",
+        [],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|row| row.is_some())
+    .map_err(|e| format!("读取迁移记录失败: {e}"))
+}
+
+/// 组件当前版本
+///
+/// core 取 `user_version` 与迁移记录中较大者（`user_version` 可能由旧工具直接设置）。
+pub fn current_version(conn: &Connection, component: &str) -> Result<u32, String> {
+    let recorded: u32 = if migrations_table_exists(conn)? {
+        conn.query_row(
+            "SELECT COALESCE(MAX(version), 0) FROM schema_migrations WHERE component = ?1",
+            [component],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("读取迁移记录失败: {e}"))?
+    } else {
+        0
+    };
+
+    if component != CORE_COMPONENT {
+        return Ok(recorded);
+    }
+    let user_version: u32 = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| format!("读取 user_version 失败: {e}"))?;
+    Ok(recorded.max(user_version))
+}
+
+fn apply(conn: &Connection, migration: &Migration) -> Result<(), String> {
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| format!("开始迁移事务失败: {e}"))?;
+
+    (migration.up)(&tx).map_err(|e| {
+        format!(
+            "迁移 {} v{} ({}) 失败，已回滚: {e}",
+            migration.component, migration.version, migration.name
+        )
+    })?;
+
+    tx.execute(
+        "INSERT INTO schema_migrations (component, version, name, applied_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![
+            migration.component,
+            migration.version,
+            migration.name,
+            chrono::Utc::now().timestamp()
+        ],
+    )
+    .map_err(|e| format!("记录迁移失败: {e}"))?;
+
+    if migration.component == CORE_COMPONENT {
+        tx.execute_batch(&format!("PRAGMA user_version = {}", migration.version))
+            .map_err(|e| format!("更新 user_version 失败: {e}"))?;
+    }
+
+    tx.commit().map_err(|e| format!("提交迁移事务失败: {e}"))
+}
+
+/// 迁移前备份数据库，返回备份文件路径；只保留最近 [`MAX_BACKUPS`] 个备份
+pub fn backup_database(conn: &Connection, dir: &Path, version: u32) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("创建备份目录失败 {dir:?}: {e}"))?;
+    let path = dir.join(format!(
+        "pre-migration-v{version}-{}.db",
+        chrono::Utc::now().format("%Y%m%d%H%M%S%3f")
+    ));
+    let progress: Option<fn(rusqlite::backup::Progress)> = None;
+    conn.backup(DatabaseName::Main, &path, progress)
+        .map_err(|e| format!("备份数据库失败: {e}"))?;
+    tracing::info!("[数据库] 迁移前已备份到 {}", path.display());
+
+    prune_backups(dir);
+    Ok(path)
+}
+
+fn prune_backups(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut backups: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("pre-migration-") && n.ends_with(".db"))
+        })
+        .collect();
+    if backups.len() <= MAX_BACKUPS {
+        return;
+    }
+    backups.sort_by_key(|p| {
+        std::fs::metadata(p)
+            .and_then(|m| m.modified())
+            .unwrap_or(std::time::UNIX_EPOCH)
+    });
+    for old in &backups[..backups.len() - MAX_BACKUPS] {
+        if let Err(e) = std::fs::remove_file(old) {
+            tracing::warn!("[数据库] 删除旧备份失败 {}: {}", old.display(), e);
+        }
+    }
+}
+
+// ============ core 迁移 ============
+//
+// 以下迁移原先在每次启动时依次调用，依靠 settings 表中的标记保证只执行一次；
+// 标记检查保留，已迁移过的数据库首次升级时这些迁移都是空操作。
+
+fn core_migrations() -> Vec<Migration> {
+    vec![
+        Migration::new(CORE_COMPONENT, 1, "provider_ids", |conn| {
+            let count = migration::migrate_provider_ids(conn)?;
+            if count > 0 {
+                tracing::info!("[数据库] 已迁移 {} 个 Provider ID", count);
+                // 标记需要刷新模型注册表
+                migration::mark_model_registry_refresh_needed(conn);
+            }
+            Ok(())
+        }),
+        Migration::new(CORE_COMPONENT, 2, "api_keys_to_pool", |conn| {
+            let count = migration::migrate_api_keys_to_pool(conn)?;
+            if count > 0 {
+                tracing::info!("[数据库] 已将 {} 条 API Key 迁移到凭证池", count);
+            }
+            Ok(())
+        }),
+        Migration::new(
+            CORE_COMPONENT,
+            3,
+            "cleanup_legacy_api_key_credentials",
+            |conn| {
+                let count = migration::cleanup_legacy_api_key_credentials(conn)?;
+                if count > 0 {
+                    tracing::info!("[数据库] 已清理 {} 条旧 API Key 凭证", count);
+                }
+                Ok(())
+            },
+        ),
+        Migration::new(CORE_COMPONENT, 4, "mcp_proxycast_enabled", |conn| {
+            let count = migration::migrate_mcp_proxycast_enabled(conn)?;
+            if count > 0 {
+                tracing::info!("[数据库] 已修复 {} 条 MCP ProxyCast 启用状态", count);
+            }
+            Ok(())
+        }),
+        Migration::new(CORE_COMPONENT, 5, "unified_content_system", |conn| {
+            migration_v2::migrate_unified_content_system(conn).map(|_| ())
+        }),
+        Migration::new(CORE_COMPONENT, 6, "playwright_mcp_server", |conn| {
+            migration_v3::migrate_playwright_mcp_server(conn).map(|_| ())
+        }),
+        Migration::new(CORE_COMPONENT, 7, "fix_promise_paths", |conn| {
+            migration_v4::migrate_fix_promise_paths(conn).map(|_| ())
+        }),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_items(conn: &Connection) -> Result<(), String> {
+        conn.execute_batch("CREATE TABLE items (id INTEGER PRIMARY KEY);")
+            .map_err(|e| e.to_string())
+    }
+
+    fn add_name(conn: &Connection) -> Result<(), String> {
+        conn.execute_batch("ALTER TABLE items ADD COLUMN name TEXT;")
+            .map_err(|e| e.to_string())
+    }
+
+    fn fail_after_write(conn: &Connection) -> Result<(), String> {
+        conn.execute_batch("INSERT INTO items (id) VALUES (1);")
+            .map_err(|e| e.to_string())?;
+        Err("boom".to_string())
+    }
+
+    fn registry(migrations: Vec<Migration>) -> MigrationRegistry {
+        let mut registry = MigrationRegistry::new();
+        registry.register_all(migrations).unwrap();
+        registry
+    }
+
+    fn user_version(conn: &Connection) -> u32 {
+        conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_applies_in_order_and_records_versions() {
+        let conn = Connection::open_in_memory().unwrap();
+        // 注册顺序与版本顺序不一致时按版本执行
+        let registry = registry(vec![
+            Migration::new(CORE_COMPONENT, 2, "add_name", add_name),
+            Migration::new(CORE_COMPONENT, 1, "create_items", create_items),
+            Migration::new("plugin", 1, "plugin_items", |conn| {
+                conn.execute_batch("CREATE TABLE plugin_items (id INTEGER);")
+                    .map_err(|e| e.to_string())
+            }),
+        ]);
+
+        let report = registry.migrate(&conn, None).unwrap();
+        let applied: Vec<_> = report
+            .applied
+            .iter()
+            .map(|id| (id.component.as_str(), id.version))
+            .collect();
+        assert_eq!(applied, vec![("core", 1), ("core", 2), ("plugin", 1)]);
+        assert_eq!(user_version(&conn), 2);
+        assert_eq!(current_version(&conn, "plugin").unwrap(), 1);
+
+        // 再次执行无待执行迁移
+        assert!(registry.migrate(&conn, None).unwrap().applied.is_empty());
+    }
+
+    #[test]
+    fn test_plan_is_dry_run() {
+        let conn = Connection::open_in_memory().unwrap();
+        let registry = registry(vec![Migration::new(
+            CORE_COMPONENT,
+            1,
+            "create_items",
+            create_items,
+        )]);
+
+        let plan = registry.plan(&conn).unwrap();
+        assert_eq!(plan.pending.len(), 1);
+        assert_eq!(plan.components[0].current, 0);
+        assert_eq!(plan.components[0].latest, 1);
+        assert!(!migrations_table_exists(&conn).unwrap());
+        assert_eq!(user_version(&conn), 0);
+    }
+
+    #[test]
+    fn test_plan_at_opens_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("plan.db");
+        let full = registry(vec![
+            Migration::new(CORE_COMPONENT, 1, "create_items", create_items),
+            Migration::new(CORE_COMPONENT, 2, "add_name", add_name),
+        ]);
+
+        // 不存在的数据库视为空库，且不创建文件
+        assert_eq!(full.plan_at(&db_path).unwrap().pending.len(), 2);
+        assert!(!db_path.exists());
+
+        let conn = Connection::open(&db_path).unwrap();
+        registry(vec![Migration::new(
+            CORE_COMPONENT,
+            1,
+            "create_items",
+            create_items,
+        )])
+        .migrate(&conn, None)
+        .unwrap();
+        drop(conn);
+
+        let plan = full.plan_at(&db_path).unwrap();
+        assert_eq!(plan.components[0].current, 1);
+        assert_eq!(plan.pending.len(), 1);
+        assert_eq!(plan.pending[0].name, "add_name");
+    }
+
+    #[test]
+    fn test_init_database_fails_on_migration_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = registry(vec![Migration::new(
+            "plugin",
+            1,
+            "broken",
+            fail_after_write,
+        )]);
+
+        let err = crate::database::init_database_with(&dir.path().join("fail.db"), &registry)
+            .unwrap_err();
+        assert!(err.contains("数据库迁移失败"));
+        assert!(err.contains("broken"));
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        let conn = Connection::open_in_memory().unwrap();
+        let registry = registry(vec![
+            Migration::new(CORE_COMPONENT, 1, "create_items", create_items),
+            Migration::new(CORE_COMPONENT, 2, "broken", fail_after_write),
+            Migration::new(CORE_COMPONENT, 3, "add_name", add_name),
+        ]);
+
+        let err = registry.migrate(&conn, None).unwrap_err();
+        assert!(err.contains("broken"));
+        assert_eq!(user_version(&conn), 1);
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM items", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 0);
+        assert_eq!(registry.plan(&conn).unwrap().pending.len(), 2);
+    }
+
+    #[test]
+    fn test_refuses_newer_database() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA user_version = 9;").unwrap();
+        let registry = registry(vec![Migration::new(
+            CORE_COMPONENT,
+            1,
+            "create_items",
+            create_items,
+        )]);
+
+        let err = registry.migrate(&conn, None).unwrap_err();
+        assert!(err.contains("v9"));
+    }
+
+    #[test]
+    fn test_duplicate_version_rejected() {
+        let mut registry = MigrationRegistry::new();
+        registry
+            .register(Migration::new("plugin", 1, "a", create_items))
+            .unwrap();
+        assert!(registry
+            .register(Migration::new("plugin", 1, "b", add_name))
+            .is_err());
+        assert!(registry
+            .register(Migration::new("plugin", 0, "c", add_name))
+            .is_err());
+        // 不同组件可以使用相同版本号
+        assert!(registry
+            .register(Migration::new("other", 1, "d", add_name))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_backup_before_migration() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = Connection::open(dir.path().join("app.db")).unwrap();
+        create_items(&conn).unwrap();
+        conn.execute_batch("INSERT INTO items (id) VALUES (1);")
+            .unwrap();
+
+        let registry = registry(vec![Migration::new(
+            CORE_COMPONENT,
+            1,
+            "add_name",
+            add_name,
+        )]);
+        let backup_dir = dir.path().join("backups");
+        let report = registry.migrate(&conn, Some(&backup_dir)).unwrap();
+
+        let backup = Connection::open(report.backup.unwrap()).unwrap();
+        let columns: i64 = backup
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('items')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(columns, 1, "备份应为迁移前的结构");
+
+        // 没有待执行迁移时不再备份
+        assert!(registry
+            .migrate(&conn, Some(&backup_dir))
+            .unwrap()
+            .backup
+            .is_none());
+    }
+}
@@ -3,15 +3,20 @@ pub mod migration;
 pub mod migration_v2;
 pub mod migration_v3;
 pub mod migration_v4;
+pub mod migrator;
+pub mod pool;
 pub mod schema;
 pub mod system_providers;
 
 use rusqlite::Connection;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 pub type DbConnection = Arc<Mutex<Connection>>;
 
+pub use migrator::{Migration, MigrationRegistry};
+pub use pool::{DbPool, PoolConfig};
+
 /// 获取数据库连接锁（自动处理 poisoned lock）
 pub fn lock_db(db: &DbConnection) -> Result<std::sync::MutexGuard<'_, Connection>, String> {
     match db.lock() {
@@ -32,10 +37,41 @@ pub fn get_db_path() -> Result<PathBuf, String> {
     Ok(db_dir.join("proxycast.db"))
 }
 
+/// 迁移前自动备份的目录（数据库所在目录下的 `backups/`）
+pub fn backup_dir_for(db_path: &Path) -> PathBuf {
+    db_path
+        .parent()
+        .map(|p| p.join("backups"))
+        .unwrap_or_else(|| PathBuf::from("backups"))
+}
+
 /// 初始化数据库连接
 pub fn init_database() -> Result<DbConnection, String> {
-    let db_path = get_db_path()?;
-    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+    init_database_at(&get_db_path()?)
+}
+
+/// 在指定路径初始化数据库连接（建表并执行迁移）
+pub fn init_database_at(db_path: &Path) -> Result<DbConnection, String> {
+    init_database_with(db_path, &MigrationRegistry::core())
+}
+
+/// 使用指定的迁移注册表初始化数据库连接
+///
+/// 注册表应包含当前程序用到的所有组件的迁移；
+/// 数据库版本高于注册表已知版本时返回错误。
+pub fn init_database_with(
+    db_path: &Path,
+    registry: &MigrationRegistry,
+) -> Result<DbConnection, String> {
+    if let Some(parent) = db_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("无法创建数据库目录 {parent:?}: {e}"))?;
+    }
+    let is_new = !db_path.exists();
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    // 旧程序不能打开被新版本迁移过的数据库
+    registry.ensure_supported(&conn)?;
 
     // 设置 busy_timeout 为 5 秒，避免 "database is locked" 错误
     conn.busy_timeout(std::time::Duration::from_secs(5))
@@ -56,111 +92,18 @@ pub fn init_database() -> Result<DbConnection, String> {
     schema::create_tables(&conn).map_err(|e| e.to_string())?;
     migration::migrate_from_json(&conn)?;
 
-    // 执行 Provider ID 迁移（修复旧 ID 与模型注册表不匹配的问题）
-    match migration::migrate_provider_ids(&conn) {
-        Ok(count) => {
-            if count > 0 {
-                tracing::info!("[数据库] 已迁移 {} 个 Provider ID", count);
-                // 标记需要刷新模型注册表
-                migration::mark_model_registry_refresh_needed(&conn);
-            }
-        }
-        Err(e) => {
-            tracing::warn!("[数据库] Provider ID 迁移失败（非致命）: {}", e);
-        }
+    // 按版本执行已注册的迁移（每个迁移单独事务，执行前自动备份）
+    // 失败的迁移已回滚，但依赖它的表结构不存在，继续启动只会在运行时出错
+    let backup_dir = (!is_new).then(|| backup_dir_for(db_path));
+    let report = registry
+        .migrate(&conn, backup_dir.as_deref())
+        .map_err(|e| format!("数据库迁移失败: {e}"))?;
+    if !report.applied.is_empty() {
+        tracing::info!("[数据库] 本次执行了 {} 个迁移", report.applied.len());
     }
 
     // 检查是否需要刷新模型注册表（版本升级时）
     migration::check_model_registry_version(&conn);
 
-    // 执行 API Keys 到 Provider Pool 的迁移
-    match migration::migrate_api_keys_to_pool(&conn) {
-        Ok(count) => {
-            if count > 0 {
-                tracing::info!("[数据库] 已将 {} 条 API Key 迁移到凭证池", count);
-            }
-        }
-        Err(e) => {
-            tracing::warn!("[数据库] API Key 迁移失败（非致命）: {}", e);
-        }
-    }
-
-    // 清理旧的 API Key 凭证（openai_key, claude_key 类型）
-    match migration::cleanup_legacy_api_key_credentials(&conn) {
-        Ok(count) => {
-            if count > 0 {
-                tracing::info!("[数据库] 已清理 {} 条旧 API Key 凭证", count);
-            }
-        }
-        Err(e) => {
-            tracing::warn!("[数据库] 旧 API Key 凭证清理失败（非致命）: {}", e);
-        }
-    }
-
-    // 修复历史 MCP 导入数据（补齐 enabled_proxycast）
-    match migration::migrate_mcp_proxycast_enabled(&conn) {
-        Ok(count) => {
-            if count > 0 {
-                tracing::info!("[数据库] 已修复 {} 条 MCP ProxyCast 启用状态", count);
-            }
-        }
-        Err(e) => {
-            tracing::warn!("[数据库] MCP ProxyCast 启用状态修复失败（非致命）: {}", e);
-        }
-    }
-
-    // 执行统一内容系统迁移（创建默认项目，迁移话题）
-    // _Requirements: 2.1, 2.2, 2.3, 2.4_
-    match migration_v2::migrate_unified_content_system(&conn) {
-        Ok(result) => {
-            if result.executed {
-                if let Some(stats) = result.stats {
-                    tracing::info!(
-                        "[数据库] 统一内容系统迁移完成: 默认项目={}, 迁移内容数={}",
-                        stats.default_project_id,
-                        stats.migrated_contents_count
-                    );
-                }
-            }
-        }
-        Err(e) => {
-            tracing::warn!("[数据库] 统一内容系统迁移失败（非致命）: {}", e);
-        }
-    }
-
-    // 执行 Playwright MCP Server 迁移
-    match migration_v3::migrate_playwright_mcp_server(&conn) {
-        Ok(result) => {
-            if result.executed {
-                if let Some(server_id) = result.server_id {
-                    tracing::info!(
-                        "[数据库] Playwright MCP Server 迁移完成: server_id={}",
-                        server_id
-                    );
-                }
-            }
-        }
-        Err(e) => {
-            tracing::warn!("[数据库] Playwright MCP Server 迁移失败（非致命）: {}", e);
-        }
-    }
-
-    // 修复 [object Promise] 路径污染问题（历史 bug 遗留数据）
-    match migration_v4::migrate_fix_promise_paths(&conn) {
-        Ok(result) => {
-            if result.executed {
-                tracing::info!(
-                    "[数据库] 路径修复和会话统一完成: workspaces={}, sessions={}, unified={}",
-                    result.fixed_workspaces,
-                    result.fixed_sessions,
-                    result.unified_sessions
-                );
-            }
-        }
-        Err(e) => {
-            tracing::warn!("[数据库] 路径修复和会话统一失败（非致命）: {}", e);
-        }
-    }
-
     Ok(Arc::new(Mutex::new(conn)))
 }
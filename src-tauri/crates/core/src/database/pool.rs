@@ -0,0 +1,417 @@
+//! SQLite 连接池
+//!
+//! `DbConnection` 是单个 `Arc<Mutex<Connection>>`，所有读写都在同一把锁上串行，
+//! 且在 tokio 工作线程里同步阻塞。连接池在 WAL 模式下提供：
+//!
+//! - 一个写连接：就是原有的 `DbConnection`，旧代码与连接池共用，保证全局只有一个写者；
+//! - N 个只读连接：WAL 下读不阻塞写，热路径查询（凭证选择等）不再与写入抢锁；
+//! - `*_async`：通过 `spawn_blocking` 把 SQLite 调用移出 tokio 工作线程；
+//! - [`spawn_write`]：后台写入进入单个写入队列，由专用线程按提交顺序执行，
+//!   同一凭证先后提交的“标记不健康 / 标记健康”不会乱序落库。
+//!
+//! 内存数据库无法在多个连接间共享，此时不创建只读连接，读操作回退到写连接。
+
+use std::path::Path;
+use std::sync::mpsc;
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::time::Duration;
+
+use rusqlite::{Connection, OpenFlags};
+use tokio::sync::oneshot;
+
+use super::{lock_db, DbConnection};
+
+/// 默认只读连接数
+pub const DEFAULT_READERS: usize = 4;
+
+/// 连接池配置
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// 只读连接数量，为 0 时所有读操作走写连接
+    pub readers: usize,
+    /// 单个连接的 busy_timeout
+    pub busy_timeout: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            readers: DEFAULT_READERS,
+            busy_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+struct ReaderSlots {
+    size: usize,
+    idle: Mutex<Vec<Connection>>,
+    available: Condvar,
+}
+
+/// 一写多读的 SQLite 连接池
+#[derive(Clone)]
+pub struct DbPool {
+    writer: DbConnection,
+    readers: Option<Arc<ReaderSlots>>,
+}
+
+impl std::fmt::Debug for DbPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DbPool")
+            .field("readers", &self.reader_count())
+            .finish()
+    }
+}
+
+impl DbPool {
+    /// 打开数据库（建表并执行迁移）并创建连接池
+    pub fn open(db_path: &Path, config: PoolConfig) -> Result<Self, String> {
+        let writer = super::init_database_at(db_path)?;
+        Self::with_writer(writer, config)
+    }
+
+    /// 基于已有的写连接创建连接池
+    ///
+    /// 只读连接打开写连接所在的同一个数据库文件；写连接是内存数据库时不创建只读连接。
+    pub fn with_writer(writer: DbConnection, config: PoolConfig) -> Result<Self, String> {
+        let path = {
+            let conn = lock_db(&writer)?;
+            conn.path()
+                .filter(|p| !p.is_empty() && *p != ":memory:")
+                .map(str::to_string)
+        };
+
+        let readers = match path {
+            Some(path) if config.readers > 0 => {
+                let mut idle = Vec::with_capacity(config.readers);
+                for _ in 0..config.readers {
+                    idle.push(open_reader(Path::new(&path), config.busy_timeout)?);
+                }
+                tracing::info!(
+                    "[数据库] 连接池已就绪: 1 个写连接, {} 个只读连接",
+                    idle.len()
+                );
+                Some(Arc::new(ReaderSlots {
+                    size: idle.len(),
+                    idle: Mutex::new(idle),
+                    available: Condvar::new(),
+                }))
+            }
+            _ => None,
+        };
+
+        Ok(Self { writer, readers })
+    }
+
+    /// 只有写连接的连接池，读操作回退到写连接
+    pub fn single(writer: DbConnection) -> Self {
+        Self {
+            writer,
+            readers: None,
+        }
+    }
+
+    /// 写连接（与旧代码共用）
+    pub fn writer(&self) -> &DbConnection {
+        &self.writer
+    }
+
+    /// 只读连接数量
+    pub fn reader_count(&self) -> usize {
+        self.readers.as_ref().map(|slots| slots.size).unwrap_or(0)
+    }
+
+    /// 在只读连接上同步执行查询（会阻塞当前线程，异步上下文请用 [`DbPool::read_async`]）
+    pub fn read<T>(&self, f: impl FnOnce(&Connection) -> Result<T, String>) -> Result<T, String> {
+        let Some(slots) = &self.readers else {
+            let conn = lock_db(&self.writer)?;
+            return f(&conn);
+        };
+
+        let conn = {
+            let mut idle = slots.idle.lock().unwrap_or_else(|e| e.into_inner());
+            loop {
+                if let Some(conn) = idle.pop() {
+                    break conn;
+                }
+                idle = slots
+                    .available
+                    .wait(idle)
+                    .unwrap_or_else(|e| e.into_inner());
+            }
+        };
+
+        // 查询 panic 时连接同样要归还，避免连接池逐渐耗尽
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(&conn)));
+        slots
+            .idle
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(conn);
+        slots.available.notify_one();
+
+        match result {
+            Ok(result) => result,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+
+    /// 在写连接上同步执行（会阻塞当前线程，异步上下文请用 [`DbPool::write_async`]）
+    pub fn write<T>(&self, f: impl FnOnce(&Connection) -> Result<T, String>) -> Result<T, String> {
+        let conn = lock_db(&self.writer)?;
+        f(&conn)
+    }
+
+    /// 在阻塞线程池中执行只读查询
+    pub async fn read_async<T, F>(&self, f: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, String> + Send + 'static,
+    {
+        let pool = self.clone();
+        tokio::task::spawn_blocking(move || pool.read(f))
+            .await
+            .map_err(|e| format!("数据库读取任务失败: {e}"))?
+    }
+
+    /// 在阻塞线程池中执行写操作
+    pub async fn write_async<T, F>(&self, f: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, String> + Send + 'static,
+    {
+        run_blocking(&self.writer, f).await
+    }
+}
+
+fn open_reader(path: &Path, busy_timeout: Duration) -> Result<Connection, String> {
+    let conn = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX
+            | OpenFlags::SQLITE_OPEN_URI,
+    )
+    .map_err(|e| format!("打开只读连接失败: {e}"))?;
+    conn.busy_timeout(busy_timeout)
+        .map_err(|e| format!("设置 busy_timeout 失败: {e}"))?;
+    conn.execute_batch(
+        "PRAGMA query_only = ON;
+         PRAGMA cache_size = -16000;
+         PRAGMA temp_store = MEMORY;",
+    )
+    .map_err(|e| format!("设置只读连接参数失败: {e}"))?;
+    Ok(conn)
+}
+
+/// 在阻塞线程池中使用 `DbConnection` 执行操作
+pub async fn run_blocking<T, F>(db: &DbConnection, f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce(&Connection) -> Result<T, String> + Send + 'static,
+{
+    let db = db.clone();
+    tokio::task::spawn_blocking(move || {
+        let conn = lock_db(&db)?;
+        f(&conn)
+    })
+    .await
+    .map_err(|e| format!("数据库任务失败: {e}"))?
+}
+
+/// 后台写入任务
+type WriteJob = Box<dyn FnOnce() + Send>;
+
+/// 全局后台写入队列（专用线程，按提交顺序逐个执行）
+static WRITE_QUEUE: OnceLock<mpsc::Sender<WriteJob>> = OnceLock::new();
+
+fn write_queue() -> &'static mpsc::Sender<WriteJob> {
+    WRITE_QUEUE.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<WriteJob>();
+        std::thread::Builder::new()
+            .name("db-writer".to_string())
+            .spawn(move || {
+                for job in receiver {
+                    job();
+                }
+            })
+            .expect("启动数据库写入线程失败");
+        sender
+    })
+}
+
+/// 后台写入的完成通知
+#[derive(Debug)]
+pub struct WriteHandle(oneshot::Receiver<()>);
+
+impl WriteHandle {
+    /// 等待写入执行完毕（失败已记录日志，不再返回）
+    pub async fn done(self) {
+        let _ = self.0.await;
+    }
+}
+
+/// 后台执行写操作，不阻塞调用方
+///
+/// 用于请求热路径上的统计类写入（使用次数、健康状态），失败只记录日志。
+/// 所有后台写入进入同一个队列按提交顺序执行，调用方不关心结果时直接丢弃返回的 [`WriteHandle`]。
+pub fn spawn_write<F>(db: &DbConnection, label: &'static str, f: F) -> WriteHandle
+where
+    F: FnOnce(&Connection) -> Result<(), String> + Send + 'static,
+{
+    let db = db.clone();
+    let (done, handle) = oneshot::channel();
+    let job: WriteJob = Box::new(move || {
+        if let Err(e) = lock_db(&db).and_then(|conn| f(&conn)) {
+            tracing::warn!("[数据库] 后台写入失败 ({}): {}", label, e);
+        }
+        let _ = done.send(());
+    });
+    if let Err(mpsc::SendError(job)) = write_queue().send(job) {
+        // 写入线程已退出（仅在其 panic 后发生），退化为同步执行
+        job();
+    }
+    WriteHandle(handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_pool(dir: &tempfile::TempDir, readers: usize) -> DbPool {
+        let conn = Connection::open(dir.path().join("pool.db")).unwrap();
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL);",
+        )
+        .unwrap();
+        DbPool::with_writer(
+            Arc::new(Mutex::new(conn)),
+            PoolConfig {
+                readers,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    }
+
+    fn count(conn: &Connection) -> Result<i64, String> {
+        conn.query_row("SELECT COUNT(*) FROM items", [], |row| row.get(0))
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_readers_see_committed_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = file_pool(&dir, 2);
+        assert_eq!(pool.reader_count(), 2);
+
+        pool.write(|conn| {
+            conn.execute("INSERT INTO items (name) VALUES ('a')", [])
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+        .unwrap();
+        assert_eq!(pool.read(count).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_readers_are_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = file_pool(&dir, 1);
+        let result = pool.read(|conn| {
+            conn.execute("INSERT INTO items (name) VALUES ('a')", [])
+                .map_err(|e| e.to_string())
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_read_does_not_wait_for_writer_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = file_pool(&dir, 1);
+
+        // 持有写连接的锁时，只读连接仍然可以查询
+        let _writer = lock_db(pool.writer()).unwrap();
+        assert_eq!(pool.read(count).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_in_memory_falls_back_to_writer() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL);")
+            .unwrap();
+        let pool = DbPool::with_writer(Arc::new(Mutex::new(conn)), PoolConfig::default()).unwrap();
+        assert_eq!(pool.reader_count(), 0);
+        assert_eq!(pool.read(count).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_reader_returned_after_panic() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = file_pool(&dir, 1);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _ = pool.read(|_| -> Result<(), String> { panic!("boom") });
+        }));
+        assert!(result.is_err());
+        let idle = pool.readers.as_ref().unwrap().idle.lock().unwrap().len();
+        assert_eq!(idle, 1);
+    }
+
+    #[tokio::test]
+    async fn test_async_offload_and_spawn_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = file_pool(&dir, 2);
+
+        pool.write_async(|conn| {
+            conn.execute("INSERT INTO items (name) VALUES ('a')", [])
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+        .await
+        .unwrap();
+
+        spawn_write(pool.writer(), "test", |conn| {
+            conn.execute("INSERT INTO items (name) VALUES ('b')", [])
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+        .done()
+        .await;
+        assert_eq!(pool.read_async(count).await.unwrap(), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_spawn_write_preserves_submission_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = file_pool(&dir, 1);
+        pool.write(|conn| {
+            conn.execute("INSERT INTO items (id, name) VALUES (1, 'v0')", [])
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+        .unwrap();
+
+        // 模拟同一凭证交替标记健康 / 不健康，最后提交的状态必须最后落库
+        let mut last = None;
+        for i in 1..=200 {
+            let name = format!("v{i}");
+            last = Some(spawn_write(pool.writer(), "order", move |conn| {
+                if i % 7 == 0 {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                conn.execute("UPDATE items SET name = ?1 WHERE id = 1", [name])
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }));
+        }
+        last.unwrap().done().await;
+
+        let name: String = pool
+            .read(|conn| {
+                conn.query_row("SELECT name FROM items WHERE id = 1", [], |row| row.get(0))
+                    .map_err(|e| e.to_string())
+            })
+            .unwrap();
+        assert_eq!(name, "v200");
+    }
+}
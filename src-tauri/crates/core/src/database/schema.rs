@@ -1088,6 +1088,25 @@ pub fn create_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
         [],
     )?;
 
+    // 小说设定知识库（Canon）快照表
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS novel_canon_snapshots (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            canon_json TEXT NOT NULL DEFAULT '{}',
+            source_chapter_no INTEGER NOT NULL DEFAULT 0,
+            version INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL,
+            FOREIGN KEY (project_id) REFERENCES novel_projects(id) ON DELETE CASCADE,
+            UNIQUE(project_id, version)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_novel_canon_project_version ON novel_canon_snapshots(project_id, version DESC)",
+        [],
+    )?;
+
     // ============================================================================
     // A2UI 表单数据表
     // 存储 AI 生成的交互式表单及用户填写的数据
@@ -16,6 +16,10 @@ pub enum GatewayErrorCode {
     UpstreamTimeout,
     UpstreamUnavailable,
     UpstreamError,
+    /// 模型输出不符合 `response_format` 要求的 JSON Schema
+    StructuredOutputInvalid,
+    /// 请求内容被内容安全策略拦截
+    ContentBlocked,
     InternalError,
 }
 
@@ -65,6 +69,8 @@ impl GatewayErrorCode {
             Self::UpstreamTimeout => "上游请求超时",
             Self::UpstreamUnavailable => "上游服务暂不可用",
             Self::UpstreamError => "上游服务返回错误",
+            Self::StructuredOutputInvalid => "模型输出不符合要求的 JSON 格式",
+            Self::ContentBlocked => "请求内容违反内容安全策略",
             Self::InternalError => "服务内部错误",
         }
     }
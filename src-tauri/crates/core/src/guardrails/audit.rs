@@ -0,0 +1,150 @@
+//! 内容安全审计记录
+//!
+//! 每次处置（拦截、打码、令牌化）按规则记录一条审计条目，只记录命中次数，不记录原文。
+//! 条目保存在内存环形缓冲区中，同时写入 tracing 日志。
+
+use super::GuardrailReport;
+use crate::config::GuardrailAction;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 默认保留的审计条目数
+const DEFAULT_CAPACITY: usize = 1000;
+
+/// 审计条目
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GuardrailAuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub request_id: String,
+    /// 请求入口（如 `/v1/chat/completions`）
+    pub endpoint: String,
+    pub model: String,
+    pub rule: String,
+    pub action: GuardrailAction,
+    /// 命中次数
+    pub count: usize,
+}
+
+/// 审计记录
+pub struct GuardrailAuditLog {
+    entries: Mutex<VecDeque<GuardrailAuditEntry>>,
+    capacity: AtomicUsize,
+}
+
+impl Default for GuardrailAuditLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl GuardrailAuditLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::new()),
+            capacity: AtomicUsize::new(capacity.max(1)),
+        }
+    }
+
+    /// 调整保留条目数
+    pub fn set_capacity(&self, capacity: usize) {
+        let capacity = capacity.max(1);
+        self.capacity.store(capacity, Ordering::Relaxed);
+        let mut entries = self.entries.lock();
+        while entries.len() > capacity {
+            entries.pop_front();
+        }
+    }
+
+    /// 记录一次请求的全部处置，返回新增的条目
+    pub fn record(
+        &self,
+        request_id: &str,
+        endpoint: &str,
+        model: &str,
+        report: &GuardrailReport,
+    ) -> Vec<GuardrailAuditEntry> {
+        let timestamp = Utc::now();
+        let recorded: Vec<GuardrailAuditEntry> = report
+            .hits
+            .iter()
+            .map(|hit| GuardrailAuditEntry {
+                timestamp,
+                request_id: request_id.to_string(),
+                endpoint: endpoint.to_string(),
+                model: model.to_string(),
+                rule: hit.rule.clone(),
+                action: hit.action,
+                count: hit.count,
+            })
+            .collect();
+        for entry in &recorded {
+            tracing::info!(
+                "[GUARDRAIL] request_id={} endpoint={} model={} rule={} action={:?} count={}",
+                entry.request_id,
+                entry.endpoint,
+                entry.model,
+                entry.rule,
+                entry.action,
+                entry.count
+            );
+        }
+
+        let capacity = self.capacity.load(Ordering::Relaxed);
+        let mut entries = self.entries.lock();
+        entries.extend(recorded.iter().cloned());
+        while entries.len() > capacity {
+            entries.pop_front();
+        }
+        recorded
+    }
+
+    /// 最近的审计条目（按时间升序，最多 `limit` 条）
+    pub fn entries(&self, limit: Option<usize>) -> Vec<GuardrailAuditEntry> {
+        let entries = self.entries.lock();
+        let skip = limit.map_or(0, |limit| entries.len().saturating_sub(limit));
+        entries.iter().skip(skip).cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guardrails::GuardrailHit;
+
+    fn report(rules: &[&str]) -> GuardrailReport {
+        GuardrailReport {
+            hits: rules
+                .iter()
+                .map(|rule| GuardrailHit {
+                    rule: rule.to_string(),
+                    action: GuardrailAction::Redact,
+                    count: 1,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_records_one_entry_per_rule_and_keeps_capacity() {
+        let log = GuardrailAuditLog::new(3);
+        assert_eq!(
+            log.record("req-1", "/v1/messages", "m", &report(&["email", "phone"]))
+                .len(),
+            2
+        );
+        log.record("req-2", "/v1/messages", "m", &report(&["email", "host"]));
+
+        let entries = log.entries(None);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].rule, "phone");
+        assert_eq!(entries[2].request_id, "req-2");
+        assert_eq!(log.entries(Some(1))[0].rule, "host");
+
+        log.set_capacity(1);
+        assert_eq!(log.entries(None).len(), 1);
+    }
+}
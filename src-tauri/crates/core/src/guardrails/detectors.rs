@@ -0,0 +1,261 @@
+//! 敏感信息检测器
+//!
+//! 内置邮箱、中国大陆手机号、居民身份证号（含校验位）、内部主机名、自定义正则和词典检测。
+//! 其他检测方式实现 [`Detector`] 后通过 `GuardrailEngine::add_detector` 注册。
+
+use regex::Regex;
+use std::ops::Range;
+
+/// 敏感信息检测器
+pub trait Detector: Send + Sync {
+    /// 返回文本中命中的字节区间（按起始位置升序，互不重叠）
+    fn detect(&self, text: &str) -> Vec<Range<usize>>;
+}
+
+/// 基于正则的检测器
+///
+/// 可选地要求命中片段两侧不是指定字符（避免截取更长数字串的一部分），
+/// 并对命中片段做二次校验。
+pub struct PatternDetector {
+    regex: Regex,
+    boundary: Option<fn(char) -> bool>,
+    validator: Option<fn(&str) -> bool>,
+}
+
+impl PatternDetector {
+    /// 自定义正则
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let regex = Regex::new(pattern).map_err(|e| format!("无效的正则表达式 {pattern}: {e}"))?;
+        Ok(Self {
+            regex,
+            boundary: None,
+            validator: None,
+        })
+    }
+
+    /// 要求命中片段两侧的字符不满足 `boundary`
+    pub fn with_boundary(mut self, boundary: fn(char) -> bool) -> Self {
+        self.boundary = Some(boundary);
+        self
+    }
+
+    /// 对命中片段做二次校验
+    pub fn with_validator(mut self, validator: fn(&str) -> bool) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    /// 邮箱地址
+    pub fn email() -> Self {
+        Self::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}")
+            .expect("内置正则有效")
+            .with_boundary(|c| c.is_ascii_alphanumeric() || "._%+-".contains(c))
+    }
+
+    /// 中国大陆手机号（可带 +86 前缀，允许空格或连字符分隔）
+    pub fn phone_cn() -> Self {
+        Self::new(r"(?:\+?86[ -]?)?1[3-9]\d(?:[ -]?\d{4}){2}")
+            .expect("内置正则有效")
+            .with_boundary(|c| c.is_ascii_digit())
+    }
+
+    /// 中国居民身份证号（18 位，校验出生日期和校验位）
+    pub fn id_card_cn() -> Self {
+        Self::new(r"\d{17}[\dXx]")
+            .expect("内置正则有效")
+            .with_boundary(|c| c.is_ascii_alphanumeric())
+            .with_validator(is_valid_id_card_cn)
+    }
+
+    /// 以指定后缀结尾的主机名（如 `corp.example.com` 匹配 `git.corp.example.com`）
+    pub fn hostname(suffixes: &[String]) -> Result<Self, String> {
+        let suffixes: Vec<String> = suffixes
+            .iter()
+            .map(|s| s.trim().trim_start_matches("*.").trim_matches('.'))
+            .filter(|s| !s.is_empty())
+            .map(regex::escape)
+            .collect();
+        if suffixes.is_empty() {
+            return Err("主机名检测器至少需要一个域名后缀".to_string());
+        }
+        let pattern = format!(
+            r"(?i)(?:[a-z0-9](?:[a-z0-9-]*[a-z0-9])?\.)*(?:{})",
+            suffixes.join("|")
+        );
+        Ok(Self::new(&pattern)?
+            .with_boundary(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+    }
+}
+
+impl Detector for PatternDetector {
+    fn detect(&self, text: &str) -> Vec<Range<usize>> {
+        self.regex
+            .find_iter(text)
+            .filter(|m| {
+                self.boundary.is_none_or(|is_word| {
+                    !text[..m.start()].chars().next_back().is_some_and(is_word)
+                        && !text[m.end()..].chars().next().is_some_and(is_word)
+                })
+            })
+            .filter(|m| self.validator.is_none_or(|valid| valid(m.as_str())))
+            .map(|m| m.range())
+            .collect()
+    }
+}
+
+/// 词典检测器
+///
+/// 默认不区分大小写。以字母或数字开头/结尾的词条要求两侧不是字母或数字，
+/// 中文等其他词条按子串匹配。
+pub struct DictionaryDetector {
+    regex: Regex,
+}
+
+impl DictionaryDetector {
+    pub fn new(terms: &[String], case_sensitive: bool) -> Result<Self, String> {
+        let mut terms: Vec<&str> = terms
+            .iter()
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .collect();
+        if terms.is_empty() {
+            return Err("词典检测器至少需要一个词条".to_string());
+        }
+        // 长词条优先，避免被其前缀抢先命中
+        terms.sort_by_key(|t| std::cmp::Reverse(t.len()));
+        let alternation = terms
+            .iter()
+            .map(|t| regex::escape(t))
+            .collect::<Vec<_>>()
+            .join("|");
+        let flags = if case_sensitive { "" } else { "(?i)" };
+        let regex = Regex::new(&format!("{flags}(?:{alternation})"))
+            .map_err(|e| format!("词典编译失败: {e}"))?;
+        Ok(Self { regex })
+    }
+}
+
+impl Detector for DictionaryDetector {
+    fn detect(&self, text: &str) -> Vec<Range<usize>> {
+        let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+        self.regex
+            .find_iter(text)
+            .filter(|m| {
+                let matched = m.as_str();
+                let starts_word = matched.chars().next().is_some_and(is_word);
+                let ends_word = matched.chars().next_back().is_some_and(is_word);
+                let before = text[..m.start()].chars().next_back().is_some_and(is_word);
+                let after = text[m.end()..].chars().next().is_some_and(is_word);
+                !(starts_word && before || ends_word && after)
+            })
+            .map(|m| m.range())
+            .collect()
+    }
+}
+
+/// 校验 18 位居民身份证号的出生日期和校验位（GB 11643-1999）
+pub fn is_valid_id_card_cn(id: &str) -> bool {
+    const WEIGHTS: [u32; 17] = [7, 9, 10, 5, 8, 4, 2, 1, 6, 3, 7, 9, 10, 5, 8, 4, 2];
+    const CHECK_CODES: [char; 11] = ['1', '0', 'X', '9', '8', '7', '6', '5', '4', '3', '2'];
+
+    let chars: Vec<char> = id.chars().collect();
+    if chars.len() != 18 {
+        return false;
+    }
+    let Some(digits) = chars[..17]
+        .iter()
+        .map(|c| c.to_digit(10))
+        .collect::<Option<Vec<u32>>>()
+    else {
+        return false;
+    };
+
+    let number = |range: Range<usize>| digits[range].iter().fold(0, |acc, d| acc * 10 + d);
+    let (year, month, day) = (number(6..10), number(10..12), number(12..14));
+    if !(1900..=2100).contains(&year) || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return false;
+    }
+
+    let sum: u32 = digits.iter().zip(WEIGHTS).map(|(d, w)| d * w).sum();
+    CHECK_CODES[(sum % 11) as usize] == chars[17].to_ascii_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches<'a>(detector: &dyn Detector, text: &'a str) -> Vec<&'a str> {
+        detector
+            .detect(text)
+            .into_iter()
+            .map(|r| &text[r])
+            .collect()
+    }
+
+    #[test]
+    fn test_email_and_phone() {
+        let text = "联系 zhang.san@example.com 或 138-1234-5678，工号 213800138000";
+        assert_eq!(
+            matches(&PatternDetector::email(), text),
+            vec!["zhang.san@example.com"]
+        );
+        // 更长数字串中的片段不算手机号
+        assert_eq!(
+            matches(&PatternDetector::phone_cn(), text),
+            vec!["138-1234-5678"]
+        );
+        assert_eq!(
+            matches(&PatternDetector::phone_cn(), "tel:+86 13912345678."),
+            vec!["+86 13912345678"]
+        );
+    }
+
+    #[test]
+    fn test_id_card_checksum() {
+        assert!(is_valid_id_card_cn("11010519491231002X"));
+        assert!(is_valid_id_card_cn("11010519491231002x"));
+        assert!(!is_valid_id_card_cn("110105194912310021"));
+        assert!(!is_valid_id_card_cn("110105194913310028"));
+
+        let detector = PatternDetector::id_card_cn();
+        assert_eq!(
+            matches(
+                &detector,
+                "身份证11010519491231002X，另一个110105194912310021"
+            ),
+            vec!["11010519491231002X"]
+        );
+    }
+
+    #[test]
+    fn test_hostname_suffixes() {
+        let detector =
+            PatternDetector::hostname(&["*.corp.example.com".to_string(), "intra".to_string()])
+                .unwrap();
+        assert_eq!(
+            matches(
+                &detector,
+                "see https://git.corp.example.com/x and db01.intra, not corp.example.community"
+            ),
+            vec!["git.corp.example.com", "db01.intra"]
+        );
+        assert!(PatternDetector::hostname(&[]).is_err());
+    }
+
+    #[test]
+    fn test_dictionary_terms() {
+        let detector = DictionaryDetector::new(
+            &["Project Phoenix".to_string(), "凤凰计划".to_string()],
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            matches(
+                &detector,
+                "project phoenix 即凤凰计划；phoenixes 不算 Project Phoenixes"
+            ),
+            vec!["project phoenix", "凤凰计划"]
+        );
+        assert!(PatternDetector::new("(").is_err());
+    }
+}
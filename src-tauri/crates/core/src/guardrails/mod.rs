@@ -0,0 +1,507 @@
+//! 内容安全（Guardrails）
+//!
+//! 请求发往上游前检测消息中的敏感信息，按规则处置：
+//!
+//! - `block`：拒绝请求
+//! - `redact`：替换为 `[REDACTED_<LABEL>]`
+//! - `tokenize`：替换为 `[PII_<LABEL>_<N>]` 令牌，响应返回前还原（见 [`TokenVault`]）
+//!
+//! 检测器见 [`detectors`]，每次处置由调用方写入 [`GuardrailAuditLog`]。
+//! 与 [`crate::sanitizer`] 不同，这里处理的是发往上游的请求内容，而不是日志。
+
+pub mod audit;
+pub mod detectors;
+pub mod tokens;
+
+pub use audit::{GuardrailAuditEntry, GuardrailAuditLog};
+pub use detectors::{Detector, DictionaryDetector, PatternDetector};
+pub use tokens::TokenVault;
+
+use crate::config::{GuardrailAction, GuardrailDetector, GuardrailSettings};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::ops::Range;
+
+/// 请求体中检查的顶层字段
+const INSPECTED_FIELDS: &[&str] = &["system", "messages", "prompt", "input"];
+
+/// 不检查的结构字段（角色、ID、工具名、图片数据等）
+const SKIPPED_KEYS: &[&str] = &[
+    "role",
+    "type",
+    "id",
+    "tool_call_id",
+    "tool_use_id",
+    "name",
+    "signature",
+    "media_type",
+    "data",
+    "url",
+    "cache_control",
+];
+
+struct CompiledRule {
+    name: String,
+    label: String,
+    action: GuardrailAction,
+    detector: Box<dyn Detector>,
+}
+
+/// 内容安全检查引擎
+pub struct GuardrailEngine {
+    enabled: bool,
+    rules: Vec<CompiledRule>,
+}
+
+impl Default for GuardrailEngine {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+impl GuardrailEngine {
+    /// 未启用的引擎
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            rules: Vec::new(),
+        }
+    }
+
+    /// 按配置编译规则，任一规则无效时返回错误
+    pub fn from_settings(settings: &GuardrailSettings) -> Result<Self, String> {
+        let mut engine = Self {
+            enabled: settings.enabled,
+            rules: Vec::with_capacity(settings.rules.len()),
+        };
+        for rule in &settings.rules {
+            let detector: Box<dyn Detector> = match &rule.detector {
+                GuardrailDetector::Email => Box::new(PatternDetector::email()),
+                GuardrailDetector::PhoneCn => Box::new(PatternDetector::phone_cn()),
+                GuardrailDetector::IdCardCn => Box::new(PatternDetector::id_card_cn()),
+                GuardrailDetector::Hostname { suffixes } => Box::new(
+                    PatternDetector::hostname(suffixes)
+                        .map_err(|e| format!("规则 {}: {e}", rule.name))?,
+                ),
+                GuardrailDetector::Regex { pattern } => Box::new(
+                    PatternDetector::new(pattern)
+                        .map_err(|e| format!("规则 {}: {e}", rule.name))?,
+                ),
+                GuardrailDetector::Dictionary {
+                    terms,
+                    case_sensitive,
+                } => Box::new(
+                    DictionaryDetector::new(terms, *case_sensitive)
+                        .map_err(|e| format!("规则 {}: {e}", rule.name))?,
+                ),
+            };
+            engine.add_detector(&rule.name, rule.action, detector);
+        }
+        Ok(engine)
+    }
+
+    /// 注册检测器（内置检测器之外的扩展）
+    pub fn add_detector(
+        &mut self,
+        name: &str,
+        action: GuardrailAction,
+        detector: Box<dyn Detector>,
+    ) {
+        self.rules.push(CompiledRule {
+            name: name.to_string(),
+            label: token_label(name),
+            action,
+            detector,
+        });
+    }
+
+    /// 是否需要检查请求
+    pub fn is_active(&self) -> bool {
+        self.enabled && !self.rules.is_empty()
+    }
+
+    /// 检查请求体中的消息内容，就地替换命中的文本
+    ///
+    /// 支持 OpenAI 和 Anthropic 格式；`block` 规则命中时不修改对应文本，
+    /// 由调用方根据 [`GuardrailReport::is_blocked`] 拒绝请求。
+    pub fn apply_to_payload(&self, payload: &mut Value) -> GuardrailReport {
+        self.apply_payload(payload, true)
+    }
+
+    /// 检查输出无法还原令牌的请求（如语音合成），`tokenize` 规则按 `redact` 处置
+    ///
+    /// 返回的检查结果中令牌表为空，命中统计记录实际执行的 `redact`。
+    pub fn apply_to_payload_redacting(&self, payload: &mut Value) -> GuardrailReport {
+        self.apply_payload(payload, false)
+    }
+
+    /// 检查单段文本，返回处置后的文本（未命中时返回 `None`）
+    pub fn apply_to_text(&self, text: &str, report: &mut GuardrailReport) -> Option<String> {
+        self.replace_text(text, report, true)
+    }
+
+    fn apply_payload(&self, payload: &mut Value, restorable: bool) -> GuardrailReport {
+        let mut report = GuardrailReport::default();
+        if !self.is_active() {
+            return report;
+        }
+        for field in INSPECTED_FIELDS {
+            if let Some(value) = payload.get_mut(*field) {
+                self.walk(value, &mut report, restorable);
+            }
+        }
+        report
+    }
+
+    fn replace_text(
+        &self,
+        text: &str,
+        report: &mut GuardrailReport,
+        restorable: bool,
+    ) -> Option<String> {
+        let hits = self.find(text);
+        if hits.is_empty() {
+            return None;
+        }
+        let mut output = String::with_capacity(text.len());
+        let mut last = 0;
+        for (range, index) in hits {
+            let rule = &self.rules[index];
+            let action = match rule.action {
+                GuardrailAction::Tokenize if !restorable => GuardrailAction::Redact,
+                action => action,
+            };
+            report.record(&rule.name, action);
+            let replacement = match action {
+                GuardrailAction::Block => continue,
+                GuardrailAction::Redact => format!("[REDACTED_{}]", rule.label),
+                GuardrailAction::Tokenize => {
+                    report.vault.tokenize(&rule.label, &text[range.clone()])
+                }
+            };
+            output.push_str(&text[last..range.start]);
+            output.push_str(&replacement);
+            last = range.end;
+        }
+        output.push_str(&text[last..]);
+        Some(output)
+    }
+
+    /// 所有规则的命中位置，重叠时保留起始位置靠前的（相同时按规则顺序）
+    fn find(&self, text: &str) -> Vec<(Range<usize>, usize)> {
+        let mut hits: Vec<(Range<usize>, usize)> = self
+            .rules
+            .iter()
+            .enumerate()
+            .flat_map(|(index, rule)| {
+                rule.detector
+                    .detect(text)
+                    .into_iter()
+                    .map(move |range| (range, index))
+            })
+            .collect();
+        hits.sort_by_key(|(range, index)| (range.start, *index));
+
+        let mut end = 0;
+        hits.retain(|(range, _)| {
+            let keep = range.start >= end && !range.is_empty();
+            if keep {
+                end = range.end;
+            }
+            keep
+        });
+        hits
+    }
+
+    fn walk(&self, value: &mut Value, report: &mut GuardrailReport, restorable: bool) {
+        match value {
+            Value::String(text) => {
+                if let Some(replaced) = self.replace_text(text, report, restorable) {
+                    *text = replaced;
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.walk(item, report, restorable);
+                }
+            }
+            Value::Object(map) => {
+                for (key, item) in map.iter_mut() {
+                    if !SKIPPED_KEYS.contains(&key.as_str()) {
+                        self.walk(item, report, restorable);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 规则名转换为令牌标签（大写字母、数字和下划线）
+fn token_label(name: &str) -> String {
+    let label: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let label = label.trim_matches('_');
+    if label.is_empty() {
+        "PII".to_string()
+    } else {
+        label.to_string()
+    }
+}
+
+/// 单条规则的命中统计
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GuardrailHit {
+    pub rule: String,
+    pub action: GuardrailAction,
+    pub count: usize,
+}
+
+/// 一次请求的检查结果
+#[derive(Debug, Clone, Default)]
+pub struct GuardrailReport {
+    /// 令牌化的原文（只在本次请求内使用，不写入日志和审计）
+    pub vault: TokenVault,
+    /// 各规则命中次数
+    pub hits: Vec<GuardrailHit>,
+}
+
+impl GuardrailReport {
+    fn record(&mut self, rule: &str, action: GuardrailAction) {
+        match self.hits.iter_mut().find(|hit| hit.rule == rule) {
+            Some(hit) => hit.count += 1,
+            None => self.hits.push(GuardrailHit {
+                rule: rule.to_string(),
+                action,
+                count: 1,
+            }),
+        }
+    }
+
+    /// 是否有命中
+    pub fn has_hits(&self) -> bool {
+        !self.hits.is_empty()
+    }
+
+    /// 是否需要拒绝请求
+    pub fn is_blocked(&self) -> bool {
+        self.hits
+            .iter()
+            .any(|hit| hit.action == GuardrailAction::Block)
+    }
+
+    /// 触发拒绝的规则名
+    pub fn blocked_rules(&self) -> Vec<&str> {
+        self.hits
+            .iter()
+            .filter(|hit| hit.action == GuardrailAction::Block)
+            .map(|hit| hit.rule.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GuardrailRule;
+    use serde_json::json;
+
+    fn settings(rules: Vec<GuardrailRule>) -> GuardrailSettings {
+        GuardrailSettings {
+            enabled: true,
+            rules,
+            ..Default::default()
+        }
+    }
+
+    fn rule(name: &str, detector: GuardrailDetector, action: GuardrailAction) -> GuardrailRule {
+        GuardrailRule {
+            name: name.to_string(),
+            detector,
+            action,
+        }
+    }
+
+    #[test]
+    fn test_settings_from_yaml() {
+        let settings: GuardrailSettings = serde_yaml::from_str(
+            r#"
+enabled: true
+rules:
+  - name: email
+    detector: email
+    action: tokenize
+  - name: internal-host
+    detector: hostname
+    suffixes: ["corp.example.com"]
+  - name: codename
+    detector: dictionary
+    terms: ["Phoenix"]
+    action: block
+"#,
+        )
+        .unwrap();
+        assert_eq!(settings.rules.len(), 3);
+        assert_eq!(settings.rules[1].action, GuardrailAction::Redact);
+        assert_eq!(
+            settings.rules[2].detector,
+            GuardrailDetector::Dictionary {
+                terms: vec!["Phoenix".to_string()],
+                case_sensitive: false
+            }
+        );
+        assert!(GuardrailEngine::from_settings(&settings)
+            .unwrap()
+            .is_active());
+
+        let invalid = settings_with_pattern("(");
+        assert!(GuardrailEngine::from_settings(&invalid)
+            .err()
+            .unwrap()
+            .starts_with("规则 custom: "));
+    }
+
+    fn settings_with_pattern(pattern: &str) -> GuardrailSettings {
+        settings(vec![rule(
+            "custom",
+            GuardrailDetector::Regex {
+                pattern: pattern.to_string(),
+            },
+            GuardrailAction::Redact,
+        )])
+    }
+
+    #[test]
+    fn test_apply_to_openai_and_anthropic_payloads() {
+        let engine = GuardrailEngine::from_settings(&settings(vec![
+            rule("email", GuardrailDetector::Email, GuardrailAction::Tokenize),
+            rule("phone", GuardrailDetector::PhoneCn, GuardrailAction::Redact),
+            rule(
+                "internal host",
+                GuardrailDetector::Hostname {
+                    suffixes: vec!["corp.example.com".to_string()],
+                },
+                GuardrailAction::Tokenize,
+            ),
+        ]))
+        .unwrap();
+
+        let mut openai = json!({
+            "model": "gpt-4o",
+            "messages": [
+                {"role": "system", "content": "Reply to ops@corp.example.com"},
+                {"role": "user", "content": [
+                    {"type": "text", "text": "Call 13812345678 or mail ops@corp.example.com about git.corp.example.com"},
+                    {"type": "image_url", "image_url": {"url": "data:image/png;base64,ops@corp.example.com"}}
+                ]}
+            ]
+        });
+        let report = engine.apply_to_payload(&mut openai);
+        assert_eq!(openai["messages"][0]["content"], "Reply to [PII_EMAIL_1]");
+        assert_eq!(
+            openai["messages"][1]["content"][0]["text"],
+            "Call [REDACTED_PHONE] or mail [PII_EMAIL_1] about [PII_INTERNAL_HOST_1]"
+        );
+        // 图片数据不检查
+        assert_eq!(
+            openai["messages"][1]["content"][1]["image_url"]["url"],
+            "data:image/png;base64,ops@corp.example.com"
+        );
+        assert_eq!(
+            report.hits,
+            vec![
+                GuardrailHit {
+                    rule: "email".to_string(),
+                    action: GuardrailAction::Tokenize,
+                    count: 2
+                },
+                GuardrailHit {
+                    rule: "phone".to_string(),
+                    action: GuardrailAction::Redact,
+                    count: 1
+                },
+                GuardrailHit {
+                    rule: "internal host".to_string(),
+                    action: GuardrailAction::Tokenize,
+                    count: 1
+                },
+            ]
+        );
+        assert_eq!(
+            report.vault.restore("[PII_EMAIL_1]"),
+            "ops@corp.example.com"
+        );
+        assert!(!report.is_blocked());
+
+        let mut anthropic = json!({
+            "model": "claude-sonnet-4-5",
+            "system": [{"type": "text", "text": "Escalate to boss@example.com"}],
+            "messages": [{"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "toolu_1", "content": "owner: a@example.com"}
+            ]}]
+        });
+        let report = engine.apply_to_payload(&mut anthropic);
+        assert_eq!(anthropic["system"][0]["text"], "Escalate to [PII_EMAIL_1]");
+        assert_eq!(
+            anthropic["messages"][0]["content"][0]["content"],
+            "owner: [PII_EMAIL_2]"
+        );
+        assert_eq!(report.vault.len(), 2);
+    }
+
+    #[test]
+    fn test_redacting_mode_does_not_tokenize() {
+        let engine = GuardrailEngine::from_settings(&settings(vec![
+            rule("email", GuardrailDetector::Email, GuardrailAction::Tokenize),
+            rule(
+                "id card",
+                GuardrailDetector::IdCardCn,
+                GuardrailAction::Block,
+            ),
+        ]))
+        .unwrap();
+
+        let mut speech = json!({"model": "tts-1", "input": "请联系 ops@example.com"});
+        let report = engine.apply_to_payload_redacting(&mut speech);
+        assert_eq!(speech["input"], "请联系 [REDACTED_EMAIL]");
+        assert!(report.vault.is_empty());
+        assert_eq!(report.hits[0].action, GuardrailAction::Redact);
+
+        // 拦截规则不受影响
+        let mut speech = json!({"input": "身份证 11010519491231002X"});
+        assert!(engine.apply_to_payload_redacting(&mut speech).is_blocked());
+    }
+
+    #[test]
+    fn test_block_rules_and_disabled_engine() {
+        let mut settings = settings(vec![rule(
+            "id card",
+            GuardrailDetector::IdCardCn,
+            GuardrailAction::Block,
+        )]);
+        let mut payload =
+            json!({"messages": [{"role": "user", "content": "我的身份证 11010519491231002X"}]});
+        let original = payload.clone();
+
+        let report = GuardrailEngine::from_settings(&settings)
+            .unwrap()
+            .apply_to_payload(&mut payload);
+        assert!(report.is_blocked());
+        assert_eq!(report.blocked_rules(), vec!["id card"]);
+        assert_eq!(payload, original);
+
+        settings.enabled = false;
+        let report = GuardrailEngine::from_settings(&settings)
+            .unwrap()
+            .apply_to_payload(&mut payload);
+        assert!(!report.has_hits());
+    }
+}
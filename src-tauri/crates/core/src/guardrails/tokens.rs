@@ -0,0 +1,146 @@
+//! 可逆令牌化
+//!
+//! 发往上游前把敏感信息替换为 `[PII_<LABEL>_<N>]` 形式的令牌，原文只保存在本次请求的
+//! [`TokenVault`] 中；响应返回客户端前再把令牌还原。同一原文在一次请求中始终对应同一令牌。
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// 令牌前缀
+const TOKEN_PREFIX: &str = "[PII_";
+
+fn token_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"\[PII_[A-Z0-9_]+_\d+\]").expect("内置正则有效"))
+}
+
+/// 单次请求的令牌表
+#[derive(Debug, Clone, Default)]
+pub struct TokenVault {
+    /// 令牌 -> 原文
+    originals: HashMap<String, String>,
+    /// (标签, 原文) -> 令牌
+    tokens: HashMap<(String, String), String>,
+    /// 各标签已分配的令牌数
+    counters: HashMap<String, usize>,
+    /// 最长令牌的字节数（用于流式还原时判断是否需要等待后续内容）
+    max_token_len: usize,
+}
+
+impl TokenVault {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.originals.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.originals.len()
+    }
+
+    /// 为原文分配令牌
+    pub fn tokenize(&mut self, label: &str, original: &str) -> String {
+        let key = (label.to_string(), original.to_string());
+        if let Some(token) = self.tokens.get(&key) {
+            return token.clone();
+        }
+        let counter = self.counters.entry(label.to_string()).or_default();
+        *counter += 1;
+        let token = format!("{TOKEN_PREFIX}{label}_{counter}]");
+        self.max_token_len = self.max_token_len.max(token.len());
+        self.originals.insert(token.clone(), original.to_string());
+        self.tokens.insert(key, token.clone());
+        token
+    }
+
+    /// 还原文本中的令牌（未知令牌保持原样）
+    pub fn restore(&self, text: &str) -> String {
+        self.restore_with(text, false)
+    }
+
+    /// 还原 JSON 字符串片段中的令牌（原文按 JSON 字符串转义）
+    pub fn restore_json_fragment(&self, text: &str) -> String {
+        self.restore_with(text, true)
+    }
+
+    fn restore_with(&self, text: &str, json_escape: bool) -> String {
+        if self.is_empty() || !text.contains(TOKEN_PREFIX) {
+            return text.to_string();
+        }
+        token_regex()
+            .replace_all(text, |caps: &regex::Captures| {
+                let token = &caps[0];
+                match self.originals.get(token) {
+                    Some(original) if json_escape => {
+                        let quoted = serde_json::to_string(original).unwrap_or_default();
+                        quoted[1..quoted.len() - 1].to_string()
+                    }
+                    Some(original) => original.clone(),
+                    None => token.to_string(),
+                }
+            })
+            .into_owned()
+    }
+
+    /// 文本结尾可能是未完整令牌的部分的起始位置
+    ///
+    /// 流式响应中令牌可能被拆到多个增量里，调用方应保留该位置之后的内容，
+    /// 与下一个增量拼接后再还原。
+    pub fn pending_start(&self, text: &str) -> Option<usize> {
+        if self.is_empty() {
+            return None;
+        }
+        let start = text.rfind('[')?;
+        let tail = &text[start..];
+        let prefix_len = tail.len().min(TOKEN_PREFIX.len());
+        let possible = tail.len() < self.max_token_len
+            && !tail.contains(']')
+            && tail.as_bytes()[..prefix_len] == TOKEN_PREFIX.as_bytes()[..prefix_len]
+            && tail
+                .chars()
+                .skip(TOKEN_PREFIX.len())
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+        possible.then_some(start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_and_restore() {
+        let mut vault = TokenVault::new();
+        let a = vault.tokenize("EMAIL", "a@example.com");
+        let b = vault.tokenize("EMAIL", "b\"q@example.com");
+        assert_eq!(a, "[PII_EMAIL_1]");
+        assert_eq!(b, "[PII_EMAIL_2]");
+        assert_eq!(vault.tokenize("EMAIL", "a@example.com"), a);
+
+        assert_eq!(
+            vault.restore("to [PII_EMAIL_1], cc [PII_EMAIL_9]"),
+            "to a@example.com, cc [PII_EMAIL_9]"
+        );
+        assert_eq!(
+            vault.restore_json_fragment("{\"to\": \"[PII_EMAIL_2]\"}"),
+            "{\"to\": \"b\\\"q@example.com\"}"
+        );
+    }
+
+    #[test]
+    fn test_pending_start() {
+        let mut vault = TokenVault::new();
+        assert_eq!(vault.pending_start("send to [PII_"), None);
+
+        vault.tokenize("PHONE_CN", "13812345678");
+        assert_eq!(vault.pending_start("send to ["), Some(8));
+        assert_eq!(vault.pending_start("send to [PII_PHO"), Some(8));
+        assert_eq!(vault.pending_start("send to [PII_PHONE_CN_1]"), None);
+        assert_eq!(vault.pending_start("array[0"), None);
+        assert_eq!(vault.pending_start("数组[é"), None);
+        assert_eq!(vault.pending_start("[PII_phone"), None);
+    }
+}
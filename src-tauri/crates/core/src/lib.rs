@@ -62,6 +62,9 @@ pub mod network;
 // 凭证清理（敏感信息过滤）
 pub mod sanitizer;
 
+// 内容安全（请求敏感信息检测、打码与令牌化）
+pub mod guardrails;
+
 // 数据层
 pub mod content;
 pub mod database;
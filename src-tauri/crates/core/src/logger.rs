@@ -30,6 +30,9 @@ impl Default for LogStoreConfig {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogEntry {
+    /// 单调递增的序号（从 1 开始，进程内唯一），用作增量拉取的游标；日志文件解析出的条目为 0
+    #[serde(default)]
+    pub seq: u64,
     pub timestamp: String,
     pub level: String,
     pub message: String,
@@ -38,6 +41,8 @@ pub struct LogEntry {
 pub struct LogStore {
     logs: VecDeque<LogEntry>,
     max_logs: usize,
+    /// 最近一条日志的序号
+    last_seq: u64,
     config: LogStoreConfig,
     log_file_path: Option<PathBuf>,
 }
@@ -54,6 +59,7 @@ impl Default for LogStore {
         Self {
             logs: VecDeque::new(),
             max_logs: config.max_logs,
+            last_seq: 0,
             config,
             log_file_path: Some(log_file),
         }
@@ -77,7 +83,9 @@ impl LogStore {
     pub fn add(&mut self, level: &str, message: &str) {
         let sanitized = sanitize_log_message(message);
         let now = Utc::now();
+        self.last_seq += 1;
         let entry = LogEntry {
+            seq: self.last_seq,
             timestamp: now.to_rfc3339(),
             level: level.to_string(),
             message: sanitized.clone(),
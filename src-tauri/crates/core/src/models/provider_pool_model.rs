@@ -151,6 +151,18 @@ impl CredentialData {
             CredentialData::AnthropicKey { .. } => PoolProviderType::Anthropic,
         }
     }
+
+    /// 获取自定义端点地址（仅 API Key 类型可配置）
+    pub fn base_url(&self) -> Option<&str> {
+        match self {
+            CredentialData::OpenAIKey { base_url, .. }
+            | CredentialData::ClaudeKey { base_url, .. }
+            | CredentialData::VertexKey { base_url, .. }
+            | CredentialData::GeminiApiKey { base_url, .. }
+            | CredentialData::AnthropicKey { base_url, .. } => base_url.as_deref(),
+            _ => None,
+        }
+    }
 }
 
 /// 通配符模式匹配
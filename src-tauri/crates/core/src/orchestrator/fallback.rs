@@ -247,6 +247,7 @@ mod tests {
                     output_cost_per_million: None,
                     is_healthy: true,
                     current_load: None,
+                    performance: None,
                 }],
             ),
             (
@@ -264,6 +265,7 @@ mod tests {
                     output_cost_per_million: None,
                     is_healthy: true,
                     current_load: None,
+                    performance: None,
                 }],
             ),
         ]
@@ -10,6 +10,7 @@
 //! - `selector` - 模型选择器
 //! - `fallback` - 降级处理器
 //! - `pool_builder` - 动态模型池构建
+//! - `performance` - 实测性能统计（TTFT、输出速度、P95 延迟、错误率）
 //! - `orchestrator` - 统一编排接口
 //!
 //! ## 使用模式
@@ -19,6 +20,7 @@
 
 mod fallback;
 mod model_orchestrator;
+mod performance;
 mod pool_builder;
 mod selector;
 pub mod strategies;
@@ -30,6 +32,10 @@ pub use model_orchestrator::{
     get_global_orchestrator, init_global_orchestrator, ModelOrchestrator, OrchestratorConfig,
     PoolStats,
 };
+pub use performance::{
+    ModelPerformance, ModelPerformanceEntry, PerformanceSample, PerformanceTracker,
+    MIN_RELIABLE_SAMPLES,
+};
 pub use pool_builder::{
     builtin_model_metadata, builtin_provider_definitions, CredentialInfo, DynamicPoolBuilder,
     ModelFamily, ModelMetadata, ProviderDefinition, ProviderType,
@@ -3,6 +3,7 @@
 //! 统一的模型编排接口，整合模型池构建、策略选择和降级处理。
 
 use super::fallback::{FallbackHandler, FallbackPolicy};
+use super::performance::{ModelPerformanceEntry, PerformanceSample, PerformanceTracker};
 use super::pool_builder::{CredentialInfo, DynamicPoolBuilder};
 use super::selector::{ModelSelector, SelectionResult};
 use super::strategies::create_default_registry;
@@ -55,6 +56,8 @@ pub struct ModelOrchestrator {
     fallback_handler: FallbackHandler,
     /// 当前凭证列表
     credentials: RwLock<Vec<CredentialInfo>>,
+    /// 实测性能统计
+    performance: PerformanceTracker,
 }
 
 impl ModelOrchestrator {
@@ -69,6 +72,7 @@ impl ModelOrchestrator {
             selector: ModelSelector::new(registry),
             pool_builder: DynamicPoolBuilder::new(),
             credentials: RwLock::new(Vec::new()),
+            performance: PerformanceTracker::new(),
         }
     }
 
@@ -82,6 +86,7 @@ impl ModelOrchestrator {
             selector: ModelSelector::new(registry),
             pool_builder: DynamicPoolBuilder::new(),
             credentials: RwLock::new(Vec::new()),
+            performance: PerformanceTracker::new(),
         }
     }
 
@@ -112,7 +117,7 @@ impl ModelOrchestrator {
         );
 
         // 更新选择器的模型池
-        self.selector.update_pool(pool).await;
+        self.install_pool(pool).await;
 
         // 保存凭证列表
         let mut creds = self.credentials.write().await;
@@ -128,25 +133,33 @@ impl ModelOrchestrator {
         let pool = self.pool_builder.build_pool(&creds);
         drop(creds);
 
-        self.selector.update_pool(pool).await;
+        self.install_pool(pool).await;
     }
 
     /// 移除凭证
     pub async fn remove_credential(&self, credential_id: &str) {
         let mut creds = self.credentials.write().await;
         creds.retain(|c| c.id != credential_id);
+        self.performance.remove_credential(credential_id);
 
         // 重新构建模型池
         let pool = self.pool_builder.build_pool(&creds);
         drop(creds);
 
+        self.install_pool(pool).await;
+    }
+
+    /// 安装新的模型池并填充实测性能统计
+    async fn install_pool(&self, pool: TierPool) {
         self.selector.update_pool(pool).await;
+        self.selector.apply_performance(&self.performance).await;
     }
 
     /// 选择模型
     pub async fn select(&self, ctx: &SelectionContext) -> StrategyResult<SelectionResult> {
         debug!("选择模型: 等级={}, 任务={:?}", ctx.tier, ctx.task_hint);
 
+        self.selector.apply_performance(&self.performance).await;
         self.selector.select(ctx).await
     }
 
@@ -156,9 +169,40 @@ impl ModelOrchestrator {
         strategy_id: &str,
         ctx: &SelectionContext,
     ) -> StrategyResult<SelectionResult> {
+        self.selector.apply_performance(&self.performance).await;
         self.selector.select_with_strategy(strategy_id, ctx).await
     }
 
+    /// 记录一次请求的性能样本
+    ///
+    /// 同步方法，可在请求处理路径中直接调用；统计在下次选择时生效。
+    pub fn record_performance(&self, sample: PerformanceSample) {
+        self.performance.record(sample);
+    }
+
+    /// 记录一次请求的输出 Token 数（用于计算输出速度）
+    pub fn record_output_tokens(
+        &self,
+        credential_id: &str,
+        model: &str,
+        output_tokens: u32,
+        generation_ms: u64,
+    ) {
+        self.performance
+            .record_tokens(credential_id, model, output_tokens, generation_ms);
+    }
+
+    /// 获取所有凭证/模型的实测性能统计
+    pub fn get_performance_stats(&self) -> Vec<ModelPerformanceEntry> {
+        self.performance.snapshot()
+    }
+
+    /// 清除实测性能统计
+    pub async fn reset_performance_stats(&self) {
+        self.performance.clear();
+        self.selector.apply_performance(&self.performance).await;
+    }
+
     /// 快速选择（使用默认等级和策略）
     pub async fn quick_select(&self) -> StrategyResult<SelectionResult> {
         let config = self.config.read().await;
@@ -180,18 +224,19 @@ impl ModelOrchestrator {
 
     /// 获取当前模型池
     pub async fn get_pool(&self) -> TierPool {
+        self.selector.apply_performance(&self.performance).await;
         self.selector.get_pool().await
     }
 
     /// 获取指定等级的可用模型
     pub async fn get_models(&self, tier: ServiceTier) -> Vec<AvailableModel> {
-        let pool = self.selector.get_pool().await;
+        let pool = self.get_pool().await;
         pool.get(tier).to_vec()
     }
 
     /// 获取所有可用模型
     pub async fn get_all_models(&self) -> Vec<AvailableModel> {
-        let pool = self.selector.get_pool().await;
+        let pool = self.get_pool().await;
         let mut all = Vec::new();
         all.extend(pool.get(ServiceTier::Mini).iter().cloned());
         all.extend(pool.get(ServiceTier::Pro).iter().cloned());
@@ -241,7 +286,7 @@ impl ModelOrchestrator {
         let pool = self.pool_builder.build_pool(&creds);
         drop(creds);
 
-        self.selector.update_pool(pool).await;
+        self.install_pool(pool).await;
     }
 
     /// 标记模型为健康
@@ -257,7 +302,7 @@ impl ModelOrchestrator {
         let pool = self.pool_builder.build_pool(&creds);
         drop(creds);
 
-        self.selector.update_pool(pool).await;
+        self.install_pool(pool).await;
     }
 
     /// 更新凭证负载
@@ -271,7 +316,7 @@ impl ModelOrchestrator {
         let pool = self.pool_builder.build_pool(&creds);
         drop(creds);
 
-        self.selector.update_pool(pool).await;
+        self.install_pool(pool).await;
     }
 }
 
@@ -370,4 +415,41 @@ mod tests {
             .await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_orchestrator_ranks_on_observed_performance() {
+        let orchestrator = ModelOrchestrator::new();
+        let credential = |id: &str| CredentialInfo {
+            id: id.to_string(),
+            provider_type: ProviderType::Anthropic,
+            original_provider_type: None,
+            supported_models: vec!["claude-3-5-haiku-20241022".to_string()],
+            is_healthy: true,
+            current_load: None,
+        };
+        orchestrator
+            .update_credentials(vec![credential("cred-slow"), credential("cred-fast")])
+            .await;
+
+        for _ in 0..10 {
+            for (cred, ttft) in [("cred-slow", 3000), ("cred-fast", 200)] {
+                orchestrator.record_performance(PerformanceSample {
+                    credential_id: cred.to_string(),
+                    model: "claude-3-5-haiku-20241022".to_string(),
+                    latency_ms: ttft * 4,
+                    ttft_ms: Some(ttft),
+                    success: true,
+                });
+            }
+        }
+
+        let ctx = SelectionContext::new(ServiceTier::Mini);
+        let result = orchestrator
+            .select_with_strategy("speed_optimized", &ctx)
+            .await
+            .unwrap();
+        assert_eq!(result.model.credential_id, "cred-fast");
+        assert!(result.model.performance.is_some());
+        assert_eq!(orchestrator.get_performance_stats().len(), 2);
+    }
 }
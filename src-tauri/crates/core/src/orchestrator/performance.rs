@@ -0,0 +1,345 @@
+//! 模型性能统计
+//!
+//! 按 (凭证, 模型) 维护滚动性能统计，供选择策略按实测数据排序：
+//!
+//! - 首 Token 延迟（TTFT）的 EWMA
+//! - 输出速度（tokens/s）的 EWMA
+//! - 最近 N 次流式请求的 P95 首 Token 延迟
+//! - 最近 N 次非流式请求的 P95 延迟
+//! - 最近 N 次请求的错误率
+//!
+//! 流式请求的遥测在收到响应头时记录，此时的耗时只是首 Token 延迟，
+//! 与非流式请求的完整耗时不可比，因此两者分别放在独立的滚动窗口中。
+//!
+//! 样本数不足 [`MIN_RELIABLE_SAMPLES`] 时，策略仍使用基于模型家族的估算值。
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// EWMA 平滑系数（越大越偏向最近的样本）
+const EWMA_ALPHA: f64 = 0.2;
+
+/// 计算 P95 延迟和错误率的滚动窗口大小
+const WINDOW_SIZE: usize = 100;
+
+/// 统计可信所需的最少样本数
+pub const MIN_RELIABLE_SAMPLES: u64 = 5;
+
+/// 一次请求的性能样本
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerformanceSample {
+    /// 凭证 ID
+    pub credential_id: String,
+    /// 模型 ID
+    pub model: String,
+    /// 请求耗时（毫秒）
+    pub latency_ms: u64,
+    /// 首 Token 延迟（毫秒），非流式请求为 None（用于区分流式与非流式样本）
+    pub ttft_ms: Option<u64>,
+    /// 是否成功
+    pub success: bool,
+}
+
+/// 模型性能统计（按凭证 + 模型）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelPerformance {
+    /// 累计样本数
+    pub sample_count: u64,
+    /// 首 Token 延迟 EWMA（毫秒）
+    pub ewma_ttft_ms: Option<f64>,
+    /// 输出速度 EWMA（tokens/s）
+    pub ewma_tokens_per_sec: Option<f64>,
+    /// 非流式请求耗时 EWMA（毫秒）
+    pub ewma_latency_ms: f64,
+    /// 滚动窗口内非流式请求的 P95 延迟（毫秒）
+    pub p95_latency_ms: u64,
+    /// 滚动窗口内流式请求的 P95 首 Token 延迟（毫秒）
+    #[serde(default)]
+    pub p95_ttft_ms: u64,
+    /// 滚动窗口内的错误率（0.0 - 1.0）
+    pub error_rate: f64,
+}
+
+impl ModelPerformance {
+    /// 样本数是否足以用于排序
+    pub fn is_reliable(&self) -> bool {
+        self.sample_count >= MIN_RELIABLE_SAMPLES
+    }
+
+    /// 估算的首 Token 延迟（无 TTFT 样本时使用请求耗时）
+    pub fn effective_ttft_ms(&self) -> f64 {
+        self.ewma_ttft_ms.unwrap_or(self.ewma_latency_ms)
+    }
+
+    /// 长尾延迟（取非流式 P95 延迟与流式 P95 首 Token 延迟中的较大者）
+    pub fn tail_latency_ms(&self) -> u64 {
+        self.p95_latency_ms.max(self.p95_ttft_ms)
+    }
+}
+
+/// 带凭证和模型标识的性能统计（用于 API 输出）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelPerformanceEntry {
+    /// 凭证 ID
+    pub credential_id: String,
+    /// 模型 ID
+    pub model: String,
+    /// 性能统计
+    #[serde(flatten)]
+    pub performance: ModelPerformance,
+}
+
+/// 单个 (凭证, 模型) 的滚动统计状态
+#[derive(Debug, Default)]
+struct PerformanceWindow {
+    stats: ModelPerformance,
+    /// 最近请求是否成功（流式与非流式合计）
+    outcomes: VecDeque<bool>,
+    /// 最近非流式请求的耗时
+    latencies: VecDeque<u64>,
+    /// 最近成功流式请求的首 Token 延迟
+    ttfts: VecDeque<u64>,
+}
+
+/// 推入滚动窗口，超出容量时丢弃最旧的样本
+fn push_window<T>(window: &mut VecDeque<T>, value: T) {
+    if window.len() == WINDOW_SIZE {
+        window.pop_front();
+    }
+    window.push_back(value);
+}
+
+fn ewma(current: Option<f64>, value: f64) -> f64 {
+    match current {
+        Some(current) => current + EWMA_ALPHA * (value - current),
+        None => value,
+    }
+}
+
+impl PerformanceWindow {
+    fn record(&mut self, sample: &PerformanceSample) {
+        let stats = &mut self.stats;
+        stats.sample_count += 1;
+        match sample.ttft_ms {
+            Some(ttft) => {
+                if sample.success {
+                    stats.ewma_ttft_ms = Some(ewma(stats.ewma_ttft_ms, ttft as f64));
+                    push_window(&mut self.ttfts, ttft);
+                    stats.p95_ttft_ms = percentile(self.ttfts.iter().copied(), 0.95);
+                }
+            }
+            None => {
+                stats.ewma_latency_ms = ewma(
+                    (!self.latencies.is_empty()).then_some(stats.ewma_latency_ms),
+                    sample.latency_ms as f64,
+                );
+                push_window(&mut self.latencies, sample.latency_ms);
+                stats.p95_latency_ms = percentile(self.latencies.iter().copied(), 0.95);
+            }
+        }
+
+        push_window(&mut self.outcomes, sample.success);
+        let failures = self.outcomes.iter().filter(|ok| !**ok).count();
+        stats.error_rate = failures as f64 / self.outcomes.len() as f64;
+    }
+
+    fn record_tokens(&mut self, output_tokens: u32, generation_ms: u64) {
+        if output_tokens == 0 || generation_ms == 0 {
+            return;
+        }
+        let tokens_per_sec = output_tokens as f64 * 1000.0 / generation_ms as f64;
+        self.stats.ewma_tokens_per_sec = Some(ewma(self.stats.ewma_tokens_per_sec, tokens_per_sec));
+    }
+}
+
+/// 最近邻法计算百分位
+fn percentile(values: impl Iterator<Item = u64>, p: f64) -> u64 {
+    let mut sorted: Vec<u64> = values.collect();
+    if sorted.is_empty() {
+        return 0;
+    }
+    sorted.sort_unstable();
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// 模型性能追踪器
+#[derive(Debug, Default)]
+pub struct PerformanceTracker {
+    windows: RwLock<HashMap<(String, String), PerformanceWindow>>,
+}
+
+impl PerformanceTracker {
+    /// 创建新的追踪器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次请求
+    pub fn record(&self, sample: PerformanceSample) {
+        let mut windows = self.windows.write();
+        windows
+            .entry((sample.credential_id.clone(), sample.model.clone()))
+            .or_default()
+            .record(&sample);
+    }
+
+    /// 记录一次请求的输出 Token 数，用于计算输出速度
+    ///
+    /// `generation_ms` 为生成这些 Token 的耗时：非流式请求为请求总耗时，
+    /// 流式请求为首个数据块到流结束的耗时（不含首 Token 延迟）。
+    pub fn record_tokens(
+        &self,
+        credential_id: &str,
+        model: &str,
+        output_tokens: u32,
+        generation_ms: u64,
+    ) {
+        let mut windows = self.windows.write();
+        windows
+            .entry((credential_id.to_string(), model.to_string()))
+            .or_default()
+            .record_tokens(output_tokens, generation_ms);
+    }
+
+    /// 获取指定凭证和模型的统计
+    pub fn get(&self, credential_id: &str, model: &str) -> Option<ModelPerformance> {
+        self.windows
+            .read()
+            .get(&(credential_id.to_string(), model.to_string()))
+            .filter(|window| window.stats.sample_count > 0)
+            .map(|window| window.stats.clone())
+    }
+
+    /// 获取所有统计（按凭证、模型排序）
+    pub fn snapshot(&self) -> Vec<ModelPerformanceEntry> {
+        let mut entries: Vec<_> = self
+            .windows
+            .read()
+            .iter()
+            .filter(|(_, window)| window.stats.sample_count > 0)
+            .map(|((credential_id, model), window)| ModelPerformanceEntry {
+                credential_id: credential_id.clone(),
+                model: model.clone(),
+                performance: window.stats.clone(),
+            })
+            .collect();
+        entries.sort_by(|a, b| (&a.credential_id, &a.model).cmp(&(&b.credential_id, &b.model)));
+        entries
+    }
+
+    /// 清除凭证的统计（凭证移除时调用）
+    pub fn remove_credential(&self, credential_id: &str) {
+        self.windows
+            .write()
+            .retain(|(cred, _), _| cred != credential_id);
+    }
+
+    /// 清除所有统计
+    pub fn clear(&self) {
+        self.windows.write().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(latency_ms: u64, ttft_ms: Option<u64>, success: bool) -> PerformanceSample {
+        PerformanceSample {
+            credential_id: "cred-1".to_string(),
+            model: "claude-haiku".to_string(),
+            latency_ms,
+            ttft_ms,
+            success,
+        }
+    }
+
+    #[test]
+    fn test_ewma_and_error_rate() {
+        let tracker = PerformanceTracker::new();
+        tracker.record(sample(1000, Some(200), true));
+        tracker.record(sample(2000, Some(400), true));
+        tracker.record(sample(3000, None, false));
+        tracker.record(sample(1000, Some(200), true));
+
+        let stats = tracker.get("cred-1", "claude-haiku").unwrap();
+        assert_eq!(stats.sample_count, 4);
+        assert!(!stats.is_reliable());
+        assert!((stats.error_rate - 0.25).abs() < 1e-9);
+        // 200 -> 240 -> 232（失败样本不计入 TTFT）
+        assert!((stats.ewma_ttft_ms.unwrap() - 232.0).abs() < 1e-9);
+        assert!(tracker.get("cred-1", "other").is_none());
+    }
+
+    #[test]
+    fn test_p95_uses_rolling_window() {
+        let tracker = PerformanceTracker::new();
+        for ms in 1..=100 {
+            tracker.record(sample(ms * 10, None, true));
+        }
+        assert_eq!(
+            tracker
+                .get("cred-1", "claude-haiku")
+                .unwrap()
+                .p95_latency_ms,
+            950
+        );
+
+        // 旧样本滑出窗口后 P95 随之下降
+        for _ in 0..WINDOW_SIZE {
+            tracker.record(sample(100, None, true));
+        }
+        let stats = tracker.get("cred-1", "claude-haiku").unwrap();
+        assert_eq!(stats.p95_latency_ms, 100);
+        assert_eq!(stats.sample_count, 200);
+    }
+
+    #[test]
+    fn test_stream_and_non_stream_windows_are_separate() {
+        let tracker = PerformanceTracker::new();
+        for _ in 0..10 {
+            tracker.record(sample(300, Some(300), true));
+        }
+        tracker.record(sample(8000, None, true));
+        tracker.record(sample(9000, Some(9000), false));
+
+        let stats = tracker.get("cred-1", "claude-haiku").unwrap();
+        // 流式首 Token 延迟不会拉低非流式的完整耗时统计
+        assert_eq!(stats.p95_latency_ms, 8000);
+        assert!((stats.ewma_latency_ms - 8000.0).abs() < 1e-9);
+        // 失败的流式请求不计入首 Token 延迟
+        assert_eq!(stats.p95_ttft_ms, 300);
+        assert_eq!(stats.tail_latency_ms(), 8000);
+        assert!((stats.error_rate - 1.0 / 12.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_tokens_per_sec() {
+        let tracker = PerformanceTracker::new();
+        tracker.record(sample(0, Some(500), true));
+        tracker.record_tokens("cred-1", "claude-haiku", 100, 1000);
+        tracker.record_tokens("cred-1", "claude-haiku", 0, 1000);
+
+        let stats = tracker.get("cred-1", "claude-haiku").unwrap();
+        assert!((stats.ewma_tokens_per_sec.unwrap() - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_snapshot_and_remove_credential() {
+        let tracker = PerformanceTracker::new();
+        tracker.record(sample(100, None, true));
+        tracker.record(PerformanceSample {
+            credential_id: "cred-2".to_string(),
+            ..sample(100, None, true)
+        });
+
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].credential_id, "cred-1");
+
+        tracker.remove_credential("cred-1");
+        assert_eq!(tracker.snapshot().len(), 1);
+    }
+}
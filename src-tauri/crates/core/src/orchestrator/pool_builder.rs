@@ -570,6 +570,7 @@ impl DynamicPoolBuilder {
                         .and_then(|m| m.output_cost_per_million),
                     is_healthy: credential.is_healthy,
                     current_load: credential.current_load,
+                    performance: None,
                 };
 
                 pool.add(tier, available_model);
@@ -2,6 +2,7 @@
 //!
 //! 提供统一的模型选择接口，整合策略和模型池。
 
+use super::performance::PerformanceTracker;
 use super::strategy::{SelectionContext, StrategyError, StrategyRegistry, StrategyResult};
 use super::tier::{AvailableModel, ServiceTier, TierConfig, TierPool};
 use serde::{Deserialize, Serialize};
@@ -73,6 +74,16 @@ impl ModelSelector {
         );
     }
 
+    /// 将实测性能统计填充到模型池
+    pub async fn apply_performance(&self, tracker: &PerformanceTracker) {
+        let mut pool = self.pool.write().await;
+        for tier in ServiceTier::all() {
+            for model in pool.get_mut(*tier) {
+                model.performance = tracker.get(&model.credential_id, &model.id);
+            }
+        }
+    }
+
     /// 获取模型池
     pub async fn get_pool(&self) -> TierPool {
         self.pool.read().await.clone()
@@ -250,6 +261,7 @@ mod tests {
                 output_cost_per_million: None,
                 is_healthy: true,
                 current_load: Some(20),
+                performance: None,
             },
         );
 
@@ -268,6 +280,7 @@ mod tests {
                 output_cost_per_million: None,
                 is_healthy: true,
                 current_load: Some(30),
+                performance: None,
             },
         );
 
@@ -310,6 +323,7 @@ mod tests {
                 output_cost_per_million: None,
                 is_healthy: true,
                 current_load: None,
+                performance: None,
             },
         );
         selector.update_pool(pool).await;
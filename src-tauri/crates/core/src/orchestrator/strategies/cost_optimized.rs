@@ -18,7 +18,19 @@ impl CostOptimizedStrategy {
     }
 
     /// 计算模型的成本得分（越低越好）
+    ///
+    /// 有实测错误率时按每次成功请求的期望成本计算（失败请求需要重试）。
     fn cost_score(model: &AvailableModel) -> f64 {
+        let base = Self::base_cost(model);
+
+        match model.observed_performance() {
+            Some(perf) => base / (1.0 - perf.error_rate).max(0.05),
+            None => base,
+        }
+    }
+
+    /// 单次请求的估算成本
+    fn base_cost(model: &AvailableModel) -> f64 {
         // 如果有价格信息，使用价格
         if let (Some(input), Some(output)) =
             (model.input_cost_per_million, model.output_cost_per_million)
@@ -126,6 +138,7 @@ mod tests {
                 output_cost_per_million: Some(75.0),
                 is_healthy: true,
                 current_load: None,
+                performance: None,
             },
             AvailableModel {
                 id: "claude-haiku".to_string(),
@@ -140,6 +153,7 @@ mod tests {
                 output_cost_per_million: Some(1.25),
                 is_healthy: true,
                 current_load: None,
+                performance: None,
             },
         ]
     }
@@ -154,4 +168,29 @@ mod tests {
         // 应该选择最便宜的 Haiku
         assert_eq!(result.model.id, "claude-haiku");
     }
+
+    #[tokio::test]
+    async fn test_error_rate_increases_effective_cost() {
+        use crate::orchestrator::performance::ModelPerformance;
+
+        let models = create_test_models();
+        // 同一模型的两个凭证，其中一个实测一半请求失败
+        let mut flaky = models[1].clone();
+        flaky.credential_id = "cred-flaky".to_string();
+        flaky.performance = Some(ModelPerformance {
+            sample_count: 20,
+            error_rate: 0.5,
+            ..Default::default()
+        });
+        let mut stable = models[1].clone();
+        stable.performance = Some(ModelPerformance {
+            sample_count: 20,
+            ..Default::default()
+        });
+
+        let strategy = CostOptimizedStrategy::new();
+        let ctx = SelectionContext::new(ServiceTier::Mini);
+        let result = strategy.select(&[flaky, stable], &ctx).await.unwrap();
+        assert_eq!(result.model.credential_id, "cred-2");
+    }
 }
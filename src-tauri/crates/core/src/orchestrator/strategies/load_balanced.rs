@@ -20,13 +20,19 @@ impl LoadBalancedStrategy {
     /// 计算模型的负载得分（越低越好）
     fn load_score(model: &AvailableModel) -> f64 {
         // 基础负载
-        let load = model.current_load.unwrap_or(50) as f64;
+        let mut load = model.current_load.unwrap_or(50) as f64;
 
         // 如果不健康，给予最高负载
         if !model.is_healthy {
             return 1000.0;
         }
 
+        // 实测错误率和长尾延迟反映上游压力
+        if let Some(perf) = model.observed_performance() {
+            load += perf.error_rate * 100.0;
+            load += (perf.tail_latency_ms() as f64 / 1000.0).min(50.0);
+        }
+
         load
     }
 }
@@ -83,10 +89,18 @@ impl SelectionStrategy for LoadBalancedStrategy {
 
         let selected = available.remove(0);
         let load = selected.current_load.unwrap_or(50);
+        let reason = match selected.observed_performance() {
+            Some(perf) => format!(
+                "负载均衡选择 (当前负载: {load}%, 实测错误率: {:.1}%, P95: {}ms)",
+                perf.error_rate * 100.0,
+                perf.tail_latency_ms()
+            ),
+            None => format!("负载均衡选择 (当前负载: {load}%)"),
+        };
 
         Ok(ModelSelection {
             model: selected,
-            reason: format!("负载均衡选择 (当前负载: {load}%)"),
+            reason,
             confidence: 80,
             alternatives: available,
         })
@@ -113,6 +127,7 @@ mod tests {
                 output_cost_per_million: None,
                 is_healthy: true,
                 current_load: Some(80),
+                performance: None,
             },
             AvailableModel {
                 id: "model-low-load".to_string(),
@@ -127,6 +142,7 @@ mod tests {
                 output_cost_per_million: None,
                 is_healthy: true,
                 current_load: Some(20),
+                performance: None,
             },
             AvailableModel {
                 id: "model-medium-load".to_string(),
@@ -141,6 +157,7 @@ mod tests {
                 output_cost_per_million: None,
                 is_healthy: true,
                 current_load: Some(50),
+                performance: None,
             },
         ]
     }
@@ -155,4 +172,25 @@ mod tests {
         // 应该选择负载最低的模型
         assert_eq!(result.model.id, "model-low-load");
     }
+
+    #[tokio::test]
+    async fn test_observed_errors_raise_load() {
+        use crate::orchestrator::performance::ModelPerformance;
+
+        let mut models = create_test_models();
+        // 低负载凭证实测错误率很高
+        models[1].performance = Some(ModelPerformance {
+            sample_count: 50,
+            ewma_latency_ms: 1000.0,
+            p95_latency_ms: 2000,
+            error_rate: 0.5,
+            ..Default::default()
+        });
+
+        let strategy = LoadBalancedStrategy::new();
+        let ctx = SelectionContext::new(ServiceTier::Pro);
+        let result = strategy.select(&models, &ctx).await.unwrap();
+        assert_eq!(result.model.id, "model-medium-load");
+        assert_eq!(result.alternatives[0].id, "model-low-load");
+    }
 }
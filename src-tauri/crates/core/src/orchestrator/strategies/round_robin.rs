@@ -105,6 +105,7 @@ mod tests {
                 output_cost_per_million: None,
                 is_healthy: true,
                 current_load: None,
+                performance: None,
             },
             AvailableModel {
                 id: "model-2".to_string(),
@@ -119,6 +120,7 @@ mod tests {
                 output_cost_per_million: None,
                 is_healthy: true,
                 current_load: None,
+                performance: None,
             },
             AvailableModel {
                 id: "model-3".to_string(),
@@ -133,6 +135,7 @@ mod tests {
                 output_cost_per_million: None,
                 is_healthy: true,
                 current_load: None,
+                performance: None,
             },
         ]
     }
@@ -18,22 +18,38 @@ impl SpeedOptimizedStrategy {
     }
 
     /// 计算模型的速度得分（越高越好）
+    ///
+    /// 有足够实测样本时按首 Token 延迟、输出速度、P95 延迟和错误率评分，
+    /// 否则根据模型家族估算。
     fn speed_score(model: &AvailableModel) -> f64 {
         let mut score = 100.0;
 
-        // 根据家族估算速度
-        let family = model.family.as_deref().unwrap_or("").to_lowercase();
-
-        if family.contains("haiku") || family.contains("flash") {
-            score += 50.0; // 最快
-        } else if family.contains("gpt-3.5") {
-            score += 40.0;
-        } else if family.contains("sonnet") || family.contains("pro") {
-            score += 20.0; // 中等
-        } else if family.contains("gpt-4") {
-            score += 10.0;
-        } else if family.contains("opus") || family.contains("ultra") || family.contains("o1") {
-            score += 0.0; // 最慢
+        if let Some(perf) = model.observed_performance() {
+            // 首 Token 延迟：0ms 得 50 分，1s 得 25 分
+            score += 50.0 * 1000.0 / (1000.0 + perf.effective_ttft_ms());
+            // 输出速度：每 10 tokens/s 加 2 分，最多 20 分
+            if let Some(tps) = perf.ewma_tokens_per_sec {
+                score += (tps * 0.2).min(20.0);
+            }
+            // 长尾延迟惩罚：每秒 1 分，最多 30 分
+            score -= (perf.tail_latency_ms() as f64 / 1000.0).min(30.0);
+            // 失败的请求需要重试，按错误率惩罚
+            score -= perf.error_rate * 100.0;
+        } else {
+            // 根据家族估算速度
+            let family = model.family.as_deref().unwrap_or("").to_lowercase();
+
+            if family.contains("haiku") || family.contains("flash") {
+                score += 50.0; // 最快
+            } else if family.contains("gpt-3.5") {
+                score += 40.0;
+            } else if family.contains("sonnet") || family.contains("pro") {
+                score += 20.0; // 中等
+            } else if family.contains("gpt-4") {
+                score += 10.0;
+            } else if family.contains("opus") || family.contains("ultra") || family.contains("o1") {
+                score += 0.0; // 最慢
+            }
         }
 
         // 负载惩罚（负载越高，速度越慢）
@@ -96,10 +112,18 @@ impl SelectionStrategy for SpeedOptimizedStrategy {
         });
 
         let selected = available.remove(0);
+        let reason = match selected.observed_performance() {
+            Some(perf) => format!(
+                "速度优先选择 (实测首 Token: {:.0}ms, P95: {}ms)",
+                perf.effective_ttft_ms(),
+                perf.tail_latency_ms()
+            ),
+            None => "速度优先选择".to_string(),
+        };
 
         Ok(ModelSelection {
             model: selected,
-            reason: "速度优先选择".to_string(),
+            reason,
             confidence: 85,
             alternatives: available,
         })
@@ -126,6 +150,7 @@ mod tests {
                 output_cost_per_million: None,
                 is_healthy: true,
                 current_load: Some(20),
+                performance: None,
             },
             AvailableModel {
                 id: "claude-haiku".to_string(),
@@ -140,6 +165,7 @@ mod tests {
                 output_cost_per_million: None,
                 is_healthy: true,
                 current_load: Some(10),
+                performance: None,
             },
         ]
     }
@@ -154,4 +180,35 @@ mod tests {
         // 应该选择最快的 Haiku
         assert_eq!(result.model.id, "claude-haiku");
     }
+
+    #[tokio::test]
+    async fn test_observed_latency_overrides_family_estimate() {
+        use crate::orchestrator::performance::ModelPerformance;
+
+        let mut models = create_test_models();
+        // 实测 Haiku 凭证很慢且经常失败，Opus 凭证很快
+        models[0].performance = Some(ModelPerformance {
+            sample_count: 20,
+            ewma_ttft_ms: Some(300.0),
+            ewma_tokens_per_sec: Some(80.0),
+            ewma_latency_ms: 2000.0,
+            p95_latency_ms: 3000,
+            p95_ttft_ms: 500,
+            error_rate: 0.0,
+        });
+        models[1].performance = Some(ModelPerformance {
+            sample_count: 20,
+            ewma_ttft_ms: Some(4000.0),
+            ewma_tokens_per_sec: Some(20.0),
+            ewma_latency_ms: 15000.0,
+            p95_latency_ms: 20000,
+            p95_ttft_ms: 6000,
+            error_rate: 0.3,
+        });
+
+        let strategy = SpeedOptimizedStrategy::new();
+        let ctx = SelectionContext::new(ServiceTier::Mini);
+        let result = strategy.select(&models, &ctx).await.unwrap();
+        assert_eq!(result.model.id, "claude-opus");
+    }
 }
@@ -197,6 +197,7 @@ mod tests {
                 output_cost_per_million: None,
                 is_healthy: true,
                 current_load: Some(20),
+                performance: None,
             },
             AvailableModel {
                 id: "claude-sonnet".to_string(),
@@ -211,6 +212,7 @@ mod tests {
                 output_cost_per_million: None,
                 is_healthy: true,
                 current_load: Some(30),
+                performance: None,
             },
             AvailableModel {
                 id: "claude-haiku".to_string(),
@@ -225,6 +227,7 @@ mod tests {
                 output_cost_per_million: None,
                 is_healthy: true,
                 current_load: Some(10),
+                performance: None,
             },
         ]
     }
@@ -2,6 +2,7 @@
 //!
 //! 定义 Mini/Pro/Max 三个服务等级及其配置。
 
+use super::performance::ModelPerformance;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -159,9 +160,17 @@ pub struct AvailableModel {
     pub is_healthy: bool,
     /// 当前负载（0-100）
     pub current_load: Option<u8>,
+    /// 实测性能统计（由编排器在选择前填充）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub performance: Option<ModelPerformance>,
 }
 
 impl AvailableModel {
+    /// 获取样本数足够的实测性能统计
+    pub fn observed_performance(&self) -> Option<&ModelPerformance> {
+        self.performance.as_ref().filter(|p| p.is_reliable())
+    }
+
     /// 计算模型的综合评分
     pub fn score(&self, tier: ServiceTier) -> f64 {
         let mut score = 0.0;
@@ -320,6 +329,7 @@ mod tests {
             output_cost_per_million: None,
             is_healthy: true,
             current_load: Some(30),
+            performance: None,
         };
 
         // Haiku 模型在 Mini 等级应该得分最高
@@ -349,6 +359,7 @@ mod tests {
                 output_cost_per_million: None,
                 is_healthy: true,
                 current_load: None,
+                performance: None,
             },
         );
 
@@ -24,4 +24,5 @@ mod rules;
 pub use amp_router::AmpRouter;
 pub use hint_router::{HintMatch, HintRoute, HintRouteEntry, HintRouter, HintRouterConfig};
 pub use mapper::ModelMapper;
+pub use route_registry::{RegisteredRoute, RouteRegistry, RouteType};
 pub use rules::Router;
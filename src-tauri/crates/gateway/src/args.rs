@@ -0,0 +1,348 @@
+//! 命令行参数解析
+//!
+//! 参数很少，手写解析即可，避免为守护进程引入额外依赖。
+
+use proxycast_server::traffic::UpstreamKind;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// 默认优雅停机等待时间（秒）
+pub const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+
+/// 录制代理默认监听地址
+pub const DEFAULT_RECORD_LISTEN: &str = "127.0.0.1:8998";
+
+/// 帮助信息
+pub const USAGE: &str = "\
+proxycast-gateway - ProxyCast 无界面网关守护进程
+
+用法:
+    proxycast-gateway [选项]
+    proxycast-gateway record --upstream <URL> --kind <KIND> --out <DIR> [录制选项]
+
+选项:
+    -c, --config <PATH>          YAML 配置文件路径（默认 ~/.config/proxycast/config.yaml）
+        --db <PATH>              SQLite 数据库路径（默认 ~/.proxycast/proxycast.db）
+        --host <HOST>            覆盖配置中的监听地址
+        --port <PORT>            覆盖配置中的监听端口
+        --log-format <FORMAT>    日志格式：text 或 json（默认 text）
+        --shutdown-timeout <SECS>
+                                 收到 SIGTERM 后等待进行中请求结束的最长时间（默认 30）
+        --check-config           仅校验配置后退出，不启动服务
+        --migrate-dry-run        列出待执行的数据库迁移后退出，不修改数据库
+    -h, --help                   显示帮助信息
+    -V, --version                显示版本号
+
+录制选项（record 子命令：转发到真实上游并把脱敏后的流量写成回放测试用的录制文件）:
+        --upstream <URL>         真实上游基础 URL（如 https://api.openai.com）
+        --kind <KIND>            上游协议：openai、claude、kiro 或 antigravity
+        --out <DIR>              录制文件输出目录
+        --listen <ADDR>          录制代理监听地址（默认 127.0.0.1:8998）
+        --prefix <PREFIX>        录制文件名前缀（默认为空）
+        --log-format <FORMAT>    日志格式：text 或 json（默认 text）";
+
+/// 日志输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// 人类可读的文本格式
+    #[default]
+    Text,
+    /// 每行一个 JSON 对象，便于日志采集
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" | "pretty" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => Err(format!("未知的日志格式: {other}（可选 text、json）")),
+        }
+    }
+}
+
+/// 网关启动参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GatewayArgs {
+    /// 配置文件路径
+    pub config: Option<PathBuf>,
+    /// 数据库路径
+    pub db: Option<PathBuf>,
+    /// 监听地址覆盖
+    pub host: Option<String>,
+    /// 监听端口覆盖
+    pub port: Option<u16>,
+    /// 日志格式
+    pub log_format: LogFormat,
+    /// 优雅停机超时
+    pub shutdown_timeout: Duration,
+    /// 仅校验配置
+    pub check_config: bool,
+    /// 仅列出待执行的数据库迁移
+    pub migrate_dry_run: bool,
+}
+
+impl Default for GatewayArgs {
+    fn default() -> Self {
+        Self {
+            config: None,
+            db: None,
+            host: None,
+            port: None,
+            log_format: LogFormat::default(),
+            shutdown_timeout: Duration::from_secs(DEFAULT_SHUTDOWN_TIMEOUT_SECS),
+            check_config: false,
+            migrate_dry_run: false,
+        }
+    }
+}
+
+/// 录制代理参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordArgs {
+    /// 真实上游基础 URL
+    pub upstream: String,
+    /// 上游协议
+    pub kind: UpstreamKind,
+    /// 监听地址
+    pub listen: SocketAddr,
+    /// 录制文件输出目录
+    pub out: PathBuf,
+    /// 录制文件名前缀
+    pub prefix: String,
+    /// 日志格式
+    pub log_format: LogFormat,
+}
+
+/// 解析结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// 启动（或校验）网关
+    Run(GatewayArgs),
+    /// 启动流量录制代理
+    Record(RecordArgs),
+    /// 打印帮助
+    Help,
+    /// 打印版本
+    Version,
+}
+
+/// 解析命令行参数（不含程序名）
+///
+/// 同时支持 `--key value` 与 `--key=value` 两种写法。
+pub fn parse<I, S>(args: I) -> Result<Command, String>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut iter = args.into_iter().map(Into::into).peekable();
+    if iter.peek().map(String::as_str) == Some("record") {
+        iter.next();
+        return parse_record(iter);
+    }
+
+    let mut parsed = GatewayArgs::default();
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &str| -> Result<String, String> {
+            match inline {
+                Some(v) => Ok(v.to_string()),
+                None => iter.next().ok_or_else(|| format!("参数 {name} 缺少取值")),
+            }
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "--check-config" => parsed.check_config = true,
+            "--migrate-dry-run" => parsed.migrate_dry_run = true,
+            "-c" | "--config" => parsed.config = Some(PathBuf::from(value(&flag)?)),
+            "--db" => parsed.db = Some(PathBuf::from(value(&flag)?)),
+            "--host" => parsed.host = Some(value(&flag)?),
+            "--port" => {
+                let raw = value(&flag)?;
+                let port = raw
+                    .parse::<u16>()
+                    .map_err(|_| format!("无效的端口: {raw}"))?;
+                parsed.port = Some(port);
+            }
+            "--log-format" => parsed.log_format = value(&flag)?.parse()?,
+            "--shutdown-timeout" => {
+                let raw = value(&flag)?;
+                let secs = raw
+                    .parse::<u64>()
+                    .map_err(|_| format!("无效的停机超时: {raw}"))?;
+                parsed.shutdown_timeout = Duration::from_secs(secs);
+            }
+            _ => return Err(format!("未知参数: {arg}")),
+        }
+    }
+
+    Ok(Command::Run(parsed))
+}
+
+/// 解析 `record` 子命令参数
+fn parse_record(mut iter: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut upstream = None;
+    let mut kind = None;
+    let mut out = None;
+    let mut listen: SocketAddr = DEFAULT_RECORD_LISTEN
+        .parse()
+        .map_err(|e| format!("无效的默认监听地址: {e}"))?;
+    let mut prefix = String::new();
+    let mut log_format = LogFormat::default();
+
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &str| -> Result<String, String> {
+            match inline {
+                Some(v) => Ok(v.to_string()),
+                None => iter.next().ok_or_else(|| format!("参数 {name} 缺少取值")),
+            }
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--upstream" => upstream = Some(value(&flag)?),
+            "--kind" => kind = Some(value(&flag)?.parse::<UpstreamKind>()?),
+            "--out" => out = Some(PathBuf::from(value(&flag)?)),
+            "--listen" => {
+                let raw = value(&flag)?;
+                listen = raw.parse().map_err(|_| format!("无效的监听地址: {raw}"))?;
+            }
+            "--prefix" => prefix = value(&flag)?,
+            "--log-format" => log_format = value(&flag)?.parse()?,
+            _ => return Err(format!("未知参数: {arg}")),
+        }
+    }
+
+    Ok(Command::Record(RecordArgs {
+        upstream: upstream.ok_or("record 需要 --upstream")?,
+        kind: kind.ok_or("record 需要 --kind")?,
+        listen,
+        out: out.ok_or("record 需要 --out")?,
+        prefix,
+        log_format,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &[&str]) -> GatewayArgs {
+        match parse(args.iter().copied()).unwrap() {
+            Command::Run(args) => args,
+            other => panic!("unexpected command: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_defaults() {
+        let args = run(&[]);
+        assert_eq!(args, GatewayArgs::default());
+        assert_eq!(args.shutdown_timeout, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_parse_all_options() {
+        let args = run(&[
+            "--config",
+            "/etc/proxycast.yaml",
+            "--db=/var/lib/proxycast.db",
+            "--host",
+            "0.0.0.0",
+            "--port=9000",
+            "--log-format",
+            "JSON",
+            "--shutdown-timeout",
+            "5",
+            "--check-config",
+            "--migrate-dry-run",
+        ]);
+        assert_eq!(args.config, Some(PathBuf::from("/etc/proxycast.yaml")));
+        assert_eq!(args.db, Some(PathBuf::from("/var/lib/proxycast.db")));
+        assert_eq!(args.host.as_deref(), Some("0.0.0.0"));
+        assert_eq!(args.port, Some(9000));
+        assert_eq!(args.log_format, LogFormat::Json);
+        assert_eq!(args.shutdown_timeout, Duration::from_secs(5));
+        assert!(args.check_config);
+        assert!(args.migrate_dry_run);
+    }
+
+    #[test]
+    fn test_parse_help_and_version() {
+        assert_eq!(parse(["--check-config", "-h"]).unwrap(), Command::Help);
+        assert_eq!(parse(["-V"]).unwrap(), Command::Version);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(["--port", "abc"]).unwrap_err().contains("无效的端口"));
+        assert!(parse(["--config"]).unwrap_err().contains("缺少取值"));
+        assert!(parse(["--log-format", "xml"])
+            .unwrap_err()
+            .contains("未知的日志格式"));
+        assert!(parse(["--verbose"]).unwrap_err().contains("未知参数"));
+    }
+
+    #[test]
+    fn test_parse_record() {
+        let command = parse([
+            "record",
+            "--upstream",
+            "https://api.openai.com",
+            "--kind=openai",
+            "--out",
+            "fixtures",
+            "--listen",
+            "127.0.0.1:9001",
+            "--prefix",
+            "drift-",
+        ])
+        .unwrap();
+        assert_eq!(
+            command,
+            Command::Record(RecordArgs {
+                upstream: "https://api.openai.com".to_string(),
+                kind: UpstreamKind::OpenAI,
+                listen: "127.0.0.1:9001".parse().unwrap(),
+                out: PathBuf::from("fixtures"),
+                prefix: "drift-".to_string(),
+                log_format: LogFormat::Text,
+            })
+        );
+
+        assert!(parse(["record", "--kind", "kiro", "--out", "x"])
+            .unwrap_err()
+            .contains("--upstream"));
+        assert!(matches!(
+            parse([
+                "record",
+                "--upstream",
+                "http://x",
+                "--kind",
+                "antigravity",
+                "--out",
+                "x"
+            ]),
+            Ok(Command::Record(RecordArgs {
+                kind: UpstreamKind::Antigravity,
+                ..
+            }))
+        ));
+        assert!(parse(["record", "--kind", "gemini"]).is_err());
+        assert!(parse(["record", "--port", "1"])
+            .unwrap_err()
+            .contains("未知参数"));
+    }
+}
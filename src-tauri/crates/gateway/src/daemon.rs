@@ -0,0 +1,420 @@
+//! 网关守护进程主流程
+//!
+//! 按桌面端 setup 的顺序组装各组件（去掉 Tauri 相关部分）：
+//! 配置 → 数据库 → 凭证池 / Token 缓存 → HTTP 服务（含配置热重载）→ 调度器 → MCP。
+//! 收到 SIGTERM / Ctrl+C 后停止接受新连接，等待进行中的流式响应结束再退出。
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use proxycast_core::app_bootstrap::{load_config_from, validate_config};
+use proxycast_core::app_utils::generate_api_key;
+use proxycast_core::config::{Config, ConfigManager, DEFAULT_API_KEY};
+use proxycast_core::database::{self, DbConnection, MigrationRegistry};
+use proxycast_core::logger;
+use proxycast_mcp::{McpClientManager, McpServerConfig};
+use proxycast_scheduler::{SchedulerService, SchedulerServiceConfig};
+use proxycast_server::traffic::{RecorderConfig, RecordingProxy};
+use proxycast_server::ServerState;
+use proxycast_services::mcp_service::McpService;
+use proxycast_services::provider_pool_service::ProviderPoolService;
+use proxycast_services::token_cache_service::TokenCacheService;
+use proxycast_services::token_refresh_scheduler::TokenRefreshScheduler;
+use tokio::sync::RwLock;
+
+use crate::args::{GatewayArgs, RecordArgs};
+use crate::logging;
+
+/// 服务存活检查间隔
+const SERVER_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 加载配置并应用命令行覆盖
+///
+/// `persist_generated_key` 为 true 时，检测到默认 API Key 会生成新密钥并写回配置文件；
+/// `--check-config` 模式下不做任何写入。
+fn prepare_config(
+    args: &GatewayArgs,
+    config_path: &Path,
+    persist_generated_key: bool,
+) -> Result<Config, String> {
+    let mut config = load_config_from(config_path).map_err(|e| e.to_string())?;
+
+    if persist_generated_key && config.server.api_key == DEFAULT_API_KEY {
+        config.server.api_key = generate_api_key();
+        ConfigManager::with_config(config.clone(), config_path.to_path_buf())
+            .save()
+            .map_err(|e| format!("保存自动生成的 API Key 失败: {e}"))?;
+        tracing::info!("[Gateway] 检测到默认 API Key，已自动生成并写回配置文件");
+    }
+
+    if let Some(host) = &args.host {
+        config.server.host = host.clone();
+    }
+    if let Some(port) = args.port {
+        config.server.port = port;
+    }
+
+    validate_config(&config).map_err(|e| e.to_string())?;
+    Ok(config)
+}
+
+/// 校验配置后退出
+pub fn check_config(args: &GatewayArgs) -> Result<(), String> {
+    let config_path = resolve_config_path(args);
+    let config = prepare_config(args, &config_path, false)?;
+
+    println!("配置有效: {}", config_path.display());
+    println!("监听地址: {}:{}", config.server.host, config.server.port);
+    println!("默认 Provider: {}", config.routing.default_provider);
+    if config.server.api_key == DEFAULT_API_KEY {
+        println!("提示: 当前使用默认 API Key，首次启动时将自动生成新密钥");
+    }
+    Ok(())
+}
+
+/// 列出待执行的数据库迁移后退出（dry-run，不修改数据库）
+pub fn plan_migrations(args: &GatewayArgs) -> Result<(), String> {
+    let db_path = resolve_db_path(args)?;
+    let plan = migration_registry()?.plan_at(&db_path)?;
+
+    println!("数据库: {}", db_path.display());
+    for component in &plan.components {
+        let note = if component.current > component.latest {
+            "（高于程序已知版本，无法打开）"
+        } else {
+            ""
+        };
+        println!(
+            "{}: 当前 v{}，最新 v{}{}",
+            component.component, component.current, component.latest, note
+        );
+    }
+    if plan.pending.is_empty() {
+        println!("没有待执行的迁移");
+    } else {
+        println!("待执行的迁移:");
+        for migration in &plan.pending {
+            println!(
+                "  {} v{} {}",
+                migration.component, migration.version, migration.name
+            );
+        }
+    }
+    Ok(())
+}
+
+/// 启动网关并阻塞直到收到停机信号
+pub async fn run(args: GatewayArgs) -> Result<(), String> {
+    let config_path = resolve_config_path(&args);
+    let config = prepare_config(&args, &config_path, true)?;
+
+    logging::init(args.log_format, &config.logging.level);
+    tracing::info!(
+        config = %config_path.display(),
+        version = env!("CARGO_PKG_VERSION"),
+        "[Gateway] 正在启动"
+    );
+
+    // 数据库
+    let db_path = resolve_db_path(&args)?;
+    let db = database::init_database_with(&db_path, &migration_registry()?)
+        .map_err(|e| format!("数据库初始化失败: {e}"))?;
+    tracing::info!(db = %db_path.display(), "[Gateway] 数据库已就绪");
+
+    // 凭证池与 Token 缓存
+    let pool_service = Arc::new(ProviderPoolService::new());
+    let token_cache = Arc::new(TokenCacheService::new());
+    log_pool_overview(&pool_service, &db);
+
+    // HTTP 服务（内置配置热重载）
+    let logs = Arc::new(RwLock::new(logger::create_log_store_from_config(
+        &config.logging,
+    )));
+    let token_refresh_settings = config.token_refresh.clone();
+    let mut server = ServerState::new(config);
+    server.set_config_path(config_path);
+    server
+        .start(logs, pool_service, token_cache.clone(), Some(db.clone()))
+        .await
+        .map_err(|e| format!("启动服务器失败: {e}"))?;
+
+    // 后台任务：OAuth Token 预刷新（与请求路径共用 Token 缓存及其凭证锁）
+    let token_refresh = Arc::new(TokenRefreshScheduler::new(
+        token_cache,
+        token_refresh_settings,
+    ));
+    let token_refresh_task = token_refresh.start(db.clone());
+
+    let scheduler = SchedulerService::new(db.clone(), SchedulerServiceConfig::default());
+    scheduler.start(db.clone());
+
+    let mcp_manager = McpClientManager::new(None);
+    start_mcp_servers(&db, &mcp_manager).await;
+
+    let status = server.status();
+    tracing::info!(
+        host = %status.host,
+        port = status.port,
+        "[Gateway] 已就绪"
+    );
+
+    // 等待停机信号或服务意外退出
+    let server_failed = tokio::select! {
+        signal = shutdown_signal() => {
+            tracing::info!(signal, "[Gateway] 收到停机信号，开始优雅停机");
+            false
+        }
+        _ = watch_server(&server) => {
+            tracing::error!("[Gateway] HTTP 服务意外退出（端口被占用或绑定失败？）");
+            true
+        }
+    };
+
+    // 先停止接受新请求并排空进行中的流，再停止后台组件
+    let drained = server.shutdown_gracefully(args.shutdown_timeout).await;
+    if drained {
+        tracing::info!("[Gateway] 进行中的请求已全部结束");
+    }
+
+    scheduler.stop();
+    token_refresh.stop();
+    if let Some(task) = token_refresh_task {
+        let _ = task.await;
+    }
+    stop_mcp_servers(&mcp_manager).await;
+
+    tracing::info!("[Gateway] 已停止");
+
+    if server_failed {
+        Err("HTTP 服务意外退出".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+fn resolve_db_path(args: &GatewayArgs) -> Result<PathBuf, String> {
+    match &args.db {
+        Some(path) => Ok(path.clone()),
+        None => database::get_db_path(),
+    }
+}
+
+/// 网关打开数据库时执行的迁移
+fn migration_registry() -> Result<MigrationRegistry, String> {
+    let mut registry = MigrationRegistry::core();
+    registry.register_all(proxycast_scheduler::migrations())?;
+    Ok(registry)
+}
+
+fn resolve_config_path(args: &GatewayArgs) -> PathBuf {
+    args.config
+        .clone()
+        .unwrap_or_else(ConfigManager::default_config_path)
+}
+
+fn log_pool_overview(pool_service: &ProviderPoolService, db: &DbConnection) {
+    match pool_service.get_overview(db) {
+        Ok(overview) => {
+            let total: usize = overview.iter().map(|p| p.stats.total_count).sum();
+            if total == 0 {
+                tracing::warn!("[Gateway] 凭证池为空，请求将无可用凭证");
+            }
+            for provider in overview.iter().filter(|p| p.stats.total_count > 0) {
+                tracing::info!(
+                    provider = %provider.provider_type,
+                    count = provider.stats.total_count,
+                    "[Gateway] 凭证已加载"
+                );
+            }
+        }
+        Err(e) => tracing::warn!("[Gateway] 获取凭证池信息失败: {}", e),
+    }
+}
+
+/// 启动启用了 `enabled_proxycast` 的 MCP 服务器
+async fn start_mcp_servers(db: &DbConnection, manager: &McpClientManager) {
+    let servers = match McpService::get_all(db) {
+        Ok(servers) => servers,
+        Err(e) => {
+            tracing::warn!("[Gateway] 读取 MCP 配置失败，跳过自动启动: {}", e);
+            return;
+        }
+    };
+
+    for server in servers.iter().filter(|s| s.enabled_proxycast) {
+        let parsed = server.parse_config();
+        let config = McpServerConfig {
+            command: parsed.command,
+            args: parsed.args,
+            env: parsed.env,
+            cwd: parsed.cwd,
+            timeout: parsed.timeout,
+        };
+
+        match manager.start_server(&server.name, &config).await {
+            Ok(_) => tracing::info!("[Gateway] MCP server 已启动: {}", server.name),
+            Err(e) => tracing::error!("[Gateway] MCP server 启动失败: {} => {}", server.name, e),
+        }
+    }
+}
+
+async fn stop_mcp_servers(manager: &McpClientManager) {
+    for name in manager.get_running_servers().await {
+        if let Err(e) = manager.stop_server(&name).await {
+            tracing::warn!("[Gateway] 停止 MCP server 失败: {} => {}", name, e);
+        }
+    }
+}
+
+/// 服务后台任务结束时返回
+async fn watch_server(server: &ServerState) {
+    let mut ticker = tokio::time::interval(SERVER_WATCH_INTERVAL);
+    loop {
+        ticker.tick().await;
+        if !server.is_serving() {
+            return;
+        }
+    }
+}
+
+/// 启动流量录制代理并阻塞直到收到停机信号
+///
+/// 把凭证的 `base_url`（Kiro 凭证文件的 `apiEndpoint`）指向监听地址即可录制。
+pub async fn record(args: RecordArgs) -> Result<(), String> {
+    logging::init(args.log_format, "info");
+    std::fs::create_dir_all(&args.out)
+        .map_err(|e| format!("创建录制目录 {} 失败: {e}", args.out.display()))?;
+
+    let proxy = RecordingProxy::start(
+        args.listen,
+        RecorderConfig {
+            upstream: args.upstream.clone(),
+            upstream_kind: args.kind,
+            output_dir: args.out.clone(),
+            name_prefix: args.prefix,
+        },
+    )
+    .await?;
+    tracing::info!(
+        listen = %proxy.addr(),
+        upstream = %args.upstream,
+        kind = %args.kind,
+        out = %args.out.display(),
+        "[Gateway] 录制代理已就绪"
+    );
+
+    let signal = shutdown_signal().await;
+    tracing::info!(signal, "[Gateway] 收到停机信号，停止录制");
+    proxy.shutdown().await;
+    Ok(())
+}
+
+/// 等待 SIGTERM / SIGINT（Windows 上仅 Ctrl+C），返回信号名
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = sigterm.recv() => "SIGTERM",
+                    _ = tokio::signal::ctrl_c() => "SIGINT",
+                }
+            }
+            Err(e) => {
+                tracing::warn!("[Gateway] 注册 SIGTERM 处理失败: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                "SIGINT"
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "CTRL_C"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_config(dir: &Path, yaml: &str) -> PathBuf {
+        let path = dir.join("config.yaml");
+        std::fs::write(&path, yaml).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_prepare_config_applies_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(
+            dir.path(),
+            "server:\n  host: 127.0.0.1\n  port: 8999\n  api_key: pc_test_key\n",
+        );
+        let args = GatewayArgs {
+            port: Some(9100),
+            ..GatewayArgs::default()
+        };
+
+        let config = prepare_config(&args, &path, false).unwrap();
+        assert_eq!(config.server.port, 9100);
+        assert_eq!(config.server.api_key, "pc_test_key");
+    }
+
+    #[test]
+    fn test_prepare_config_rejects_default_key_on_public_bind() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(
+            dir.path(),
+            &format!("server:\n  host: 127.0.0.1\n  api_key: {DEFAULT_API_KEY}\n"),
+        );
+        let args = GatewayArgs {
+            host: Some("0.0.0.0".to_string()),
+            ..GatewayArgs::default()
+        };
+
+        let err = prepare_config(&args, &path, false).unwrap_err();
+        assert!(err.contains("API Key"));
+    }
+
+    #[test]
+    fn test_prepare_config_persists_generated_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(
+            dir.path(),
+            &format!("server:\n  host: 127.0.0.1\n  api_key: {DEFAULT_API_KEY}\n"),
+        );
+
+        let config = prepare_config(&GatewayArgs::default(), &path, true).unwrap();
+        assert_ne!(config.server.api_key, DEFAULT_API_KEY);
+
+        let saved = load_config_from(&path).unwrap();
+        assert_eq!(saved.server.api_key, config.server.api_key);
+    }
+
+    #[test]
+    fn test_plan_migrations_does_not_create_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("proxycast.db");
+        let args = GatewayArgs {
+            db: Some(db_path.clone()),
+            ..GatewayArgs::default()
+        };
+        plan_migrations(&args).unwrap();
+        assert!(!db_path.exists());
+    }
+
+    #[test]
+    fn test_check_config_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let args = GatewayArgs {
+            config: Some(dir.path().join("missing.yaml")),
+            ..GatewayArgs::default()
+        };
+        assert!(check_config(&args).is_err());
+    }
+}
@@ -0,0 +1,26 @@
+//! 日志初始化
+//!
+//! 优先使用 `RUST_LOG` 环境变量，未设置时回退到配置文件中的 `logging.level`。
+
+use crate::args::LogFormat;
+use tracing_subscriber::EnvFilter;
+
+/// 初始化全局 tracing subscriber
+pub fn init(format: LogFormat, default_level: &str) {
+    let filter = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(default_level))
+        .unwrap_or_else(|_| EnvFilter::new("info"));
+
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(true);
+
+    match format {
+        LogFormat::Json => builder
+            .json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .init(),
+        LogFormat::Text => builder.init(),
+    }
+}
@@ -0,0 +1,73 @@
+//! ProxyCast 无界面网关
+//!
+//! 不依赖 Tauri 的独立守护进程，适合在服务器或容器中运行：
+//! 复用桌面端的配置文件与 SQLite 数据库，启动同一套 HTTP 代理服务。
+
+mod args;
+mod daemon;
+mod logging;
+
+use std::process::ExitCode;
+
+use args::Command;
+
+fn main() -> ExitCode {
+    let args = match args::parse(std::env::args().skip(1)) {
+        Ok(Command::Run(args)) => args,
+        Ok(Command::Record(args)) => return run_async(daemon::record(args)),
+        Ok(Command::Help) => {
+            println!("{}", args::USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Version) => {
+            println!("proxycast-gateway {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("{e}\n\n{}", args::USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    if args.check_config {
+        return match daemon::check_config(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("配置无效: {e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if args.migrate_dry_run {
+        return match daemon::plan_migrations(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("计算迁移计划失败: {e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    run_async(daemon::run(args))
+}
+
+/// 在新建的 tokio 运行时中执行并转换为退出码
+fn run_async(task: impl std::future::Future<Output = Result<(), String>>) -> ExitCode {
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("创建 tokio 运行时失败: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match runtime.block_on(task) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            tracing::error!("[Gateway] {}", e);
+            eprintln!("proxycast-gateway: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -17,7 +17,8 @@ pub mod telemetry;
 pub use injection::{InjectionConfig, InjectionMode, InjectionResult, InjectionRule, Injector};
 pub use proxy::{ProxyClientFactory, ProxyError, ProxyProtocol};
 pub use resilience::{
-    Failover, FailoverConfig, Retrier, RetryConfig, TimeoutConfig, TimeoutController,
+    CircuitBreaker, CircuitBreakerConfig, CircuitKey, Failover, FailoverConfig, HedgePolicy,
+    Retrier, RetryConfig, TimeoutConfig, TimeoutController,
 };
 pub use telemetry::{
     LogRotationConfig, LoggerError, ModelStats, ModelTokenStats, PeriodTokenStats, ProviderStats,
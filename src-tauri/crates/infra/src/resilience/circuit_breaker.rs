@@ -0,0 +1,817 @@
+//! 熔断器实现
+//!
+//! 按凭证和 Provider 分别维护熔断状态（关闭 / 打开 / 半开）：
+//!
+//! - 关闭：正常放行，在滑动时间窗口内统计失败率
+//! - 打开：窗口内请求数达到下限且失败率超过阈值后打开，直接拒绝请求
+//! - 半开：打开持续一段时间后进入半开，只放行有限数量的探测请求；
+//!   探测全部成功则关闭，任一失败则重新打开
+
+use crate::telemetry::RequestLogger;
+use chrono::{DateTime, Utc};
+use parking_lot::{Mutex, RwLock};
+use proxycast_core::config::CircuitBreakerSettings;
+use proxycast_core::credential::{CircuitHealth, CircuitHealthSource};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// 保留的状态转换事件数量
+const MAX_TRANSITIONS: usize = 100;
+
+/// 半开探测名额已满时建议的重试间隔（毫秒）
+const PROBE_RETRY_AFTER_MS: u64 = 1000;
+
+/// 熔断器配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CircuitBreakerConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 失败率统计窗口（毫秒）
+    pub window_ms: u64,
+    /// 窗口内触发熔断的最少请求数
+    pub min_requests: u32,
+    /// 触发熔断的失败率阈值（0.0 - 1.0）
+    pub failure_rate_threshold: f64,
+    /// 熔断持续时间（毫秒），之后进入半开状态
+    pub open_duration_ms: u64,
+    /// 半开状态允许的探测请求数，全部成功后关闭熔断
+    pub half_open_max_probes: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self::from(&CircuitBreakerSettings::default())
+    }
+}
+
+impl From<&CircuitBreakerSettings> for CircuitBreakerConfig {
+    fn from(settings: &CircuitBreakerSettings) -> Self {
+        Self {
+            enabled: settings.enabled,
+            window_ms: settings.window_secs * 1000,
+            min_requests: settings.min_requests,
+            failure_rate_threshold: settings.failure_rate_threshold,
+            open_duration_ms: settings.open_secs * 1000,
+            half_open_max_probes: settings.half_open_probes.max(1),
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    fn window(&self) -> Duration {
+        Duration::from_millis(self.window_ms)
+    }
+
+    fn open_duration(&self) -> Duration {
+        Duration::from_millis(self.open_duration_ms)
+    }
+}
+
+/// 熔断维度
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "scope", content = "id", rename_all = "snake_case")]
+pub enum CircuitKey {
+    /// 单个凭证
+    Credential(String),
+    /// Provider 端点（同一端点的所有凭证共享）
+    Provider(String),
+}
+
+impl CircuitKey {
+    /// 凭证维度
+    pub fn credential(id: impl Into<String>) -> Self {
+        Self::Credential(id.into())
+    }
+
+    /// Provider 维度
+    pub fn provider(name: impl Into<String>) -> Self {
+        Self::Provider(name.into().to_lowercase())
+    }
+
+    /// Provider 端点维度
+    ///
+    /// 同类型的自定义端点按 `base_url` 各自熔断，未配置 `base_url` 时与 [`CircuitKey::provider`] 相同。
+    pub fn provider_endpoint(name: impl Into<String>, base_url: Option<&str>) -> Self {
+        let name = name.into().to_lowercase();
+        match base_url
+            .map(|url| url.trim().trim_end_matches('/'))
+            .filter(|url| !url.is_empty())
+        {
+            Some(url) => Self::Provider(format!("{name}@{}", url.to_lowercase())),
+            None => Self::Provider(name),
+        }
+    }
+}
+
+impl std::fmt::Display for CircuitKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CircuitKey::Credential(id) => write!(f, "credential:{id}"),
+            CircuitKey::Provider(name) => write!(f, "provider:{name}"),
+        }
+    }
+}
+
+/// 熔断状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// 关闭（正常放行）
+    Closed,
+    /// 打开（拒绝请求）
+    Open,
+    /// 半开（放行探测请求）
+    HalfOpen,
+}
+
+/// 熔断状态转换事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitTransition {
+    /// 熔断维度
+    pub key: CircuitKey,
+    /// 原状态
+    pub from: CircuitState,
+    /// 新状态
+    pub to: CircuitState,
+    /// 原因
+    pub reason: String,
+    /// 时间戳
+    pub timestamp: DateTime<Utc>,
+}
+
+/// 熔断状态快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitStatus {
+    /// 熔断维度
+    pub key: CircuitKey,
+    /// 当前状态
+    pub state: CircuitState,
+    /// 窗口内请求数
+    pub request_count: usize,
+    /// 窗口内失败率
+    pub failure_rate: f64,
+    /// 距离进入半开的剩余时间（毫秒，仅打开状态）
+    pub retry_after_ms: Option<u64>,
+}
+
+/// 熔断拒绝错误
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitOpenError {
+    /// 被熔断的维度
+    pub key: CircuitKey,
+    /// 建议的重试间隔（毫秒）
+    pub retry_after_ms: u64,
+}
+
+impl std::fmt::Display for CircuitOpenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "熔断器已打开: {}，{}ms 后重试",
+            self.key, self.retry_after_ms
+        )
+    }
+}
+
+impl std::error::Error for CircuitOpenError {}
+
+/// 单个维度的熔断状态
+#[derive(Debug)]
+struct Circuit {
+    state: CircuitState,
+    /// 窗口内的 (时间, 是否成功)
+    outcomes: VecDeque<(Instant, bool)>,
+    /// 进入当前状态的时间
+    since: Instant,
+    /// 半开状态下已放行但未返回的探测数
+    probes_in_flight: u32,
+    /// 半开状态下成功的探测数
+    probe_successes: u32,
+}
+
+impl Circuit {
+    fn new(now: Instant) -> Self {
+        Self {
+            state: CircuitState::Closed,
+            outcomes: VecDeque::new(),
+            since: now,
+            probes_in_flight: 0,
+            probe_successes: 0,
+        }
+    }
+
+    fn prune(&mut self, window: Duration, now: Instant) {
+        while let Some((at, _)) = self.outcomes.front() {
+            if now.duration_since(*at) <= window {
+                break;
+            }
+            self.outcomes.pop_front();
+        }
+    }
+
+    fn failure_rate(&self) -> f64 {
+        if self.outcomes.is_empty() {
+            return 0.0;
+        }
+        let failures = self.outcomes.iter().filter(|(_, ok)| !ok).count();
+        failures as f64 / self.outcomes.len() as f64
+    }
+
+    fn retry_after_ms(&self, config: &CircuitBreakerConfig, now: Instant) -> u64 {
+        config
+            .open_duration()
+            .saturating_sub(now.duration_since(self.since))
+            .as_millis() as u64
+    }
+}
+
+/// 熔断器
+///
+/// 线程安全，可在多个请求间共享（通常包装在 `Arc` 中）。
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: RwLock<CircuitBreakerConfig>,
+    circuits: Mutex<HashMap<CircuitKey, Circuit>>,
+    transitions: Mutex<VecDeque<CircuitTransition>>,
+    /// 状态转换事件写入的遥测日志
+    telemetry: RwLock<Option<Arc<RequestLogger>>>,
+}
+
+impl CircuitBreaker {
+    /// 创建新的熔断器
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            circuits: Mutex::new(HashMap::new()),
+            transitions: Mutex::new(VecDeque::new()),
+            telemetry: RwLock::new(None),
+        }
+    }
+
+    /// 设置遥测日志，之后的状态转换事件同时写入遥测
+    pub fn set_telemetry(&self, logger: Arc<RequestLogger>) {
+        *self.telemetry.write() = Some(logger);
+    }
+
+    /// 使用默认配置创建熔断器
+    pub fn with_defaults() -> Self {
+        Self::new(CircuitBreakerConfig::default())
+    }
+
+    /// 获取配置
+    pub fn config(&self) -> CircuitBreakerConfig {
+        self.config.read().clone()
+    }
+
+    /// 更新配置（支持热重载，已有的熔断状态保留）
+    pub fn update_config(&self, config: CircuitBreakerConfig) {
+        *self.config.write() = config;
+    }
+
+    /// 检查状态码是否应计为熔断失败
+    ///
+    /// 只统计反映上游故障的状态码；4xx 请求错误不计入。
+    pub fn is_failure_status(status_code: u16) -> bool {
+        matches!(status_code, 408 | 429 | 500..=599)
+    }
+
+    /// 请求放行检查
+    ///
+    /// 所有维度都允许时才放行；放行后必须调用 [`record_success`] 或
+    /// [`record_failure`] 报告结果，以释放半开探测名额。
+    ///
+    /// [`record_success`]: CircuitBreaker::record_success
+    /// [`record_failure`]: CircuitBreaker::record_failure
+    pub fn acquire(&self, keys: &[CircuitKey]) -> Result<(), CircuitOpenError> {
+        self.acquire_at(keys, Instant::now())
+    }
+
+    /// 报告请求成功，返回产生的状态转换
+    pub fn record_success(&self, keys: &[CircuitKey]) -> Vec<CircuitTransition> {
+        self.record_at(keys, true, Instant::now())
+    }
+
+    /// 报告请求失败，返回产生的状态转换
+    pub fn record_failure(&self, keys: &[CircuitKey]) -> Vec<CircuitTransition> {
+        self.record_at(keys, false, Instant::now())
+    }
+
+    fn acquire_at(&self, keys: &[CircuitKey], now: Instant) -> Result<(), CircuitOpenError> {
+        let config = self.config.read().clone();
+        if !config.enabled {
+            return Ok(());
+        }
+
+        let mut circuits = self.circuits.lock();
+        let mut transitions = Vec::new();
+
+        // 先检查所有维度，全部允许后再占用半开探测名额
+        let mut rejection = None;
+        for key in keys {
+            let Some(circuit) = circuits.get_mut(key) else {
+                continue;
+            };
+            match circuit.state {
+                CircuitState::Closed => {}
+                CircuitState::Open => {
+                    if now.duration_since(circuit.since) >= config.open_duration() {
+                        transitions.push(self.transition(
+                            key,
+                            circuit,
+                            CircuitState::HalfOpen,
+                            "熔断时间已到，开始探测".to_string(),
+                            now,
+                        ));
+                    } else {
+                        rejection = Some(CircuitOpenError {
+                            key: key.clone(),
+                            retry_after_ms: circuit.retry_after_ms(&config, now),
+                        });
+                        break;
+                    }
+                }
+                CircuitState::HalfOpen => {}
+            }
+
+            if circuit.state == CircuitState::HalfOpen {
+                // 探测请求长时间未返回（如客户端断开），视为丢失并释放名额
+                if now.duration_since(circuit.since) >= config.open_duration() {
+                    circuit.since = now;
+                    circuit.probes_in_flight = 0;
+                }
+                if circuit.probes_in_flight + circuit.probe_successes >= config.half_open_max_probes
+                {
+                    rejection = Some(CircuitOpenError {
+                        key: key.clone(),
+                        retry_after_ms: PROBE_RETRY_AFTER_MS,
+                    });
+                    break;
+                }
+            }
+        }
+
+        if rejection.is_none() {
+            for key in keys {
+                if let Some(circuit) = circuits.get_mut(key) {
+                    if circuit.state == CircuitState::HalfOpen {
+                        circuit.probes_in_flight += 1;
+                    }
+                }
+            }
+        }
+        drop(circuits);
+
+        self.publish(transitions);
+        match rejection {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    fn record_at(
+        &self,
+        keys: &[CircuitKey],
+        success: bool,
+        now: Instant,
+    ) -> Vec<CircuitTransition> {
+        let config = self.config.read().clone();
+        if !config.enabled {
+            return Vec::new();
+        }
+
+        let mut circuits = self.circuits.lock();
+        let mut transitions = Vec::new();
+
+        for key in keys {
+            let circuit = circuits
+                .entry(key.clone())
+                .or_insert_with(|| Circuit::new(now));
+
+            match circuit.state {
+                CircuitState::Closed => {
+                    circuit.outcomes.push_back((now, success));
+                    circuit.prune(config.window(), now);
+
+                    let failure_rate = circuit.failure_rate();
+                    if !success
+                        && circuit.outcomes.len() >= config.min_requests as usize
+                        && failure_rate >= config.failure_rate_threshold
+                    {
+                        let reason = format!(
+                            "失败率 {:.0}% 超过阈值 {:.0}%（窗口内 {} 次请求）",
+                            failure_rate * 100.0,
+                            config.failure_rate_threshold * 100.0,
+                            circuit.outcomes.len()
+                        );
+                        transitions.push(self.transition(
+                            key,
+                            circuit,
+                            CircuitState::Open,
+                            reason,
+                            now,
+                        ));
+                    }
+                }
+                CircuitState::HalfOpen => {
+                    circuit.probes_in_flight = circuit.probes_in_flight.saturating_sub(1);
+                    if success {
+                        circuit.probe_successes += 1;
+                        if circuit.probe_successes >= config.half_open_max_probes {
+                            let reason = format!("{} 次探测全部成功", circuit.probe_successes);
+                            transitions.push(self.transition(
+                                key,
+                                circuit,
+                                CircuitState::Closed,
+                                reason,
+                                now,
+                            ));
+                        }
+                    } else {
+                        transitions.push(self.transition(
+                            key,
+                            circuit,
+                            CircuitState::Open,
+                            "探测请求失败".to_string(),
+                            now,
+                        ));
+                    }
+                }
+                // 熔断前已发出的请求返回，不影响状态
+                CircuitState::Open => {}
+            }
+        }
+        drop(circuits);
+
+        self.publish(transitions.clone());
+        transitions
+    }
+
+    /// 切换状态并重置对应的计数
+    fn transition(
+        &self,
+        key: &CircuitKey,
+        circuit: &mut Circuit,
+        to: CircuitState,
+        reason: String,
+        now: Instant,
+    ) -> CircuitTransition {
+        let from = circuit.state;
+        circuit.state = to;
+        circuit.since = now;
+        circuit.probes_in_flight = 0;
+        circuit.probe_successes = 0;
+        if to == CircuitState::Closed {
+            circuit.outcomes.clear();
+        }
+
+        CircuitTransition {
+            key: key.clone(),
+            from,
+            to,
+            reason,
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// 记录状态转换事件
+    fn publish(&self, transitions: Vec<CircuitTransition>) {
+        if transitions.is_empty() {
+            return;
+        }
+
+        let telemetry = self.telemetry.read().clone();
+        let mut log = self.transitions.lock();
+        for event in transitions {
+            if let Some(logger) = &telemetry {
+                if let Err(e) = logger.record_circuit_transition(&event) {
+                    tracing::warn!("[CIRCUIT] 写入遥测失败: {}", e);
+                }
+            }
+            match event.to {
+                CircuitState::Open => tracing::warn!(
+                    "[CIRCUIT] {} {:?} -> {:?}: {}",
+                    event.key,
+                    event.from,
+                    event.to,
+                    event.reason
+                ),
+                _ => tracing::info!(
+                    "[CIRCUIT] {} {:?} -> {:?}: {}",
+                    event.key,
+                    event.from,
+                    event.to,
+                    event.reason
+                ),
+            }
+            if log.len() == MAX_TRANSITIONS {
+                log.pop_front();
+            }
+            log.push_back(event);
+        }
+    }
+
+    /// 获取指定维度的状态
+    pub fn state(&self, key: &CircuitKey) -> CircuitState {
+        self.circuits
+            .lock()
+            .get(key)
+            .map(|circuit| circuit.state)
+            .unwrap_or(CircuitState::Closed)
+    }
+
+    /// 获取所有维度的状态快照
+    pub fn snapshot(&self) -> Vec<CircuitStatus> {
+        let config = self.config.read().clone();
+        let now = Instant::now();
+        let mut circuits = self.circuits.lock();
+
+        let mut statuses: Vec<_> = circuits
+            .iter_mut()
+            .map(|(key, circuit)| {
+                circuit.prune(config.window(), now);
+                CircuitStatus {
+                    key: key.clone(),
+                    state: circuit.state,
+                    request_count: circuit.outcomes.len(),
+                    failure_rate: circuit.failure_rate(),
+                    retry_after_ms: (circuit.state == CircuitState::Open)
+                        .then(|| circuit.retry_after_ms(&config, now)),
+                }
+            })
+            .collect();
+        statuses.sort_by_key(|status| status.key.to_string());
+        statuses
+    }
+
+    /// 当前未关闭（打开或半开）的维度数
+    pub fn open_count(&self) -> usize {
+        self.circuits
+            .lock()
+            .values()
+            .filter(|circuit| circuit.state != CircuitState::Closed)
+            .count()
+    }
+
+    /// 获取最近的状态转换事件（按时间顺序）
+    pub fn recent_transitions(&self) -> Vec<CircuitTransition> {
+        self.transitions.lock().iter().cloned().collect()
+    }
+
+    /// 手动关闭指定维度的熔断
+    pub fn reset(&self, key: &CircuitKey) {
+        let now = Instant::now();
+        let transition = {
+            let mut circuits = self.circuits.lock();
+            circuits
+                .get_mut(key)
+                .filter(|circuit| circuit.state != CircuitState::Closed)
+                .map(|circuit| {
+                    self.transition(
+                        key,
+                        circuit,
+                        CircuitState::Closed,
+                        "手动重置".to_string(),
+                        now,
+                    )
+                })
+        };
+        self.publish(transition.into_iter().collect());
+    }
+
+    /// 清除所有熔断状态
+    pub fn reset_all(&self) {
+        self.circuits.lock().clear();
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
+
+impl CircuitHealthSource for CircuitBreaker {
+    fn credential_circuit(&self, credential_id: &str) -> Option<CircuitHealth> {
+        let config = self.config.read().clone();
+        let circuits = self.circuits.lock();
+        let circuit = circuits.get(&CircuitKey::credential(credential_id))?;
+        match circuit.state {
+            CircuitState::Closed => None,
+            CircuitState::Open => Some(CircuitHealth::Open {
+                retry_after_ms: circuit.retry_after_ms(&config, Instant::now()),
+            }),
+            CircuitState::HalfOpen => Some(CircuitHealth::HalfOpen),
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    fn test_config() -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            enabled: true,
+            window_ms: 10_000,
+            min_requests: 4,
+            failure_rate_threshold: 0.5,
+            open_duration_ms: 1_000,
+            half_open_max_probes: 2,
+        }
+    }
+
+    fn keys() -> Vec<CircuitKey> {
+        vec![
+            CircuitKey::credential("cred-1"),
+            CircuitKey::provider("OpenAI"),
+        ]
+    }
+
+    /// 在 `start` 基础上产生足以打开熔断的失败
+    fn trip(breaker: &CircuitBreaker, keys: &[CircuitKey], start: Instant) {
+        breaker.record_at(keys, true, start);
+        for i in 1..=3 {
+            breaker.record_at(keys, false, start + Duration::from_millis(i));
+        }
+    }
+
+    #[test]
+    fn test_opens_when_failure_rate_exceeds_threshold() {
+        let breaker = CircuitBreaker::new(test_config());
+        let keys = keys();
+        let start = Instant::now();
+
+        breaker.record_at(&keys, false, start);
+        breaker.record_at(&keys, false, start);
+        // 请求数不足时不熔断
+        assert_eq!(breaker.state(&keys[0]), CircuitState::Closed);
+
+        breaker.record_at(&keys, true, start);
+        let transitions = breaker.record_at(&keys, false, start);
+        assert_eq!(transitions.len(), 2);
+        assert!(transitions.iter().all(|t| t.to == CircuitState::Open));
+        assert_eq!(breaker.open_count(), 2);
+
+        let err = breaker
+            .acquire_at(&keys, start + Duration::from_millis(400))
+            .unwrap_err();
+        assert_eq!(err.key, keys[0]);
+        assert_eq!(err.retry_after_ms, 600);
+    }
+
+    #[test]
+    fn test_failures_outside_window_are_ignored() {
+        let breaker = CircuitBreaker::new(test_config());
+        let keys = keys();
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            breaker.record_at(&keys, false, start);
+        }
+        let later = start + Duration::from_secs(11);
+        breaker.record_at(&keys, true, later);
+        breaker.record_at(&keys, false, later);
+        assert_eq!(breaker.state(&keys[0]), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_half_open_limits_probes_and_closes_on_success() {
+        let breaker = CircuitBreaker::new(test_config());
+        let keys = keys();
+        let start = Instant::now();
+        trip(&breaker, &keys, start);
+
+        let probe_time = start + Duration::from_millis(1_100);
+        assert!(breaker.acquire_at(&keys, probe_time).is_ok());
+        assert!(breaker.acquire_at(&keys, probe_time).is_ok());
+        assert_eq!(breaker.state(&keys[0]), CircuitState::HalfOpen);
+        // 探测名额已满
+        let err = breaker.acquire_at(&keys, probe_time).unwrap_err();
+        assert_eq!(err.retry_after_ms, PROBE_RETRY_AFTER_MS);
+
+        breaker.record_at(&keys, true, probe_time);
+        assert_eq!(breaker.state(&keys[0]), CircuitState::HalfOpen);
+        breaker.record_at(&keys, true, probe_time);
+        assert_eq!(breaker.state(&keys[0]), CircuitState::Closed);
+        assert_eq!(breaker.open_count(), 0);
+
+        let history: Vec<_> = breaker
+            .recent_transitions()
+            .into_iter()
+            .filter(|t| t.key == keys[0])
+            .map(|t| t.to)
+            .collect();
+        assert_eq!(
+            history,
+            vec![
+                CircuitState::Open,
+                CircuitState::HalfOpen,
+                CircuitState::Closed
+            ]
+        );
+    }
+
+    #[test]
+    fn test_probe_failure_reopens() {
+        let breaker = CircuitBreaker::new(test_config());
+        let keys = keys();
+        let start = Instant::now();
+        trip(&breaker, &keys, start);
+
+        let probe_time = start + Duration::from_millis(1_100);
+        breaker.acquire_at(&keys, probe_time).unwrap();
+        breaker.record_at(&keys, false, probe_time);
+        assert_eq!(breaker.state(&keys[0]), CircuitState::Open);
+        assert!(breaker
+            .acquire_at(&keys, probe_time + Duration::from_millis(500))
+            .is_err());
+    }
+
+    #[test]
+    fn test_provider_circuit_blocks_other_credentials() {
+        let breaker = CircuitBreaker::new(test_config());
+        let start = Instant::now();
+        trip(&breaker, &[CircuitKey::provider("openai")], start);
+
+        let other = [
+            CircuitKey::credential("cred-2"),
+            CircuitKey::provider("openai"),
+        ];
+        let err = breaker.acquire_at(&other, start).unwrap_err();
+        assert_eq!(err.key, CircuitKey::provider("openai"));
+    }
+
+    #[test]
+    fn test_same_provider_type_endpoints_trip_independently() {
+        let breaker = CircuitBreaker::new(test_config());
+        let start = Instant::now();
+        let broken = CircuitKey::provider_endpoint("openai", Some("https://api.deepseek.com/v1/"));
+        let healthy = CircuitKey::provider_endpoint("openai", Some("https://api.moonshot.cn/v1"));
+        assert_ne!(broken, healthy);
+        assert_eq!(
+            broken,
+            CircuitKey::provider_endpoint("OpenAI", Some("https://API.deepseek.com/v1"))
+        );
+        assert_eq!(
+            CircuitKey::provider_endpoint("openai", Some("  ")),
+            CircuitKey::provider("openai")
+        );
+
+        trip(&breaker, std::slice::from_ref(&broken), start);
+        let err = breaker
+            .acquire_at(&[CircuitKey::credential("cred-1"), broken.clone()], start)
+            .unwrap_err();
+        assert_eq!(err.key, broken);
+        assert!(breaker
+            .acquire_at(&[CircuitKey::credential("cred-2"), healthy], start)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_disabled_breaker_always_allows() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            enabled: false,
+            ..test_config()
+        });
+        let keys = keys();
+        let start = Instant::now();
+        trip(&breaker, &keys, start);
+        assert!(breaker.acquire_at(&keys, start).is_ok());
+        assert_eq!(breaker.open_count(), 0);
+    }
+
+    #[test]
+    fn test_health_source_reports_credential_state() {
+        let breaker = CircuitBreaker::new(test_config());
+        assert_eq!(breaker.credential_circuit("cred-1"), None);
+
+        trip(&breaker, &keys(), Instant::now());
+        assert!(matches!(
+            breaker.credential_circuit("cred-1"),
+            Some(CircuitHealth::Open { .. })
+        ));
+
+        breaker.reset(&CircuitKey::credential("cred-1"));
+        assert_eq!(breaker.credential_circuit("cred-1"), None);
+    }
+
+    #[test]
+    fn test_transitions_recorded_in_telemetry() {
+        let logger = Arc::new(
+            RequestLogger::new(crate::telemetry::LogRotationConfig {
+                enable_file_logging: false,
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+        let breaker = CircuitBreaker::new(test_config());
+        breaker.set_telemetry(logger.clone());
+
+        trip(&breaker, &keys(), Instant::now());
+        let recorded = logger.circuit_transitions();
+        assert_eq!(recorded.len(), 2);
+        assert!(recorded.iter().all(|t| t.to == CircuitState::Open));
+    }
+}
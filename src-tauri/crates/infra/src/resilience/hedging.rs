@@ -0,0 +1,413 @@
+//! 对冲请求实现
+//!
+//! 主请求在延迟阈值内未返回时，向另一个凭证发出相同请求，
+//! 取先返回可用结果的一方，另一方的 Future 被直接 drop（底层 HTTP 连接随之取消）。
+//!
+//! 对冲只对匹配 [`HedgingSettings`] 规则的请求生效，`exclude_models` 优先于规则，
+//! 避免对昂贵模型意外地产生双倍开销。
+
+use parking_lot::RwLock;
+use proxycast_core::config::{HedgingRule, HedgingSettings};
+use proxycast_core::models::injection_types::pattern_matches;
+use std::future::Future;
+use std::time::Duration;
+
+/// 计算 P90 延迟所需的最少样本数
+pub const MIN_P90_SAMPLES: usize = 10;
+
+/// 对冲计划（规则匹配后确定）
+#[derive(Debug, Clone, PartialEq)]
+pub struct HedgePlan {
+    /// 固定对冲延迟（None 表示使用实测 P90）
+    pub fixed_delay: Option<Duration>,
+    /// 使用 P90 时的延迟下限
+    pub min_delay: Duration,
+    /// 样本不足时使用的延迟
+    pub fallback_delay: Duration,
+}
+
+impl HedgePlan {
+    fn from_rule(rule: &HedgingRule) -> Self {
+        Self {
+            fixed_delay: rule.delay_ms.map(Duration::from_millis),
+            min_delay: Duration::from_millis(rule.min_delay_ms),
+            fallback_delay: Duration::from_millis(rule.fallback_delay_ms),
+        }
+    }
+
+    /// 是否需要实测 P90 才能确定延迟
+    pub fn needs_p90(&self) -> bool {
+        self.fixed_delay.is_none()
+    }
+
+    /// 确定对冲延迟
+    pub fn delay(&self, observed_p90: Option<Duration>) -> Duration {
+        match (self.fixed_delay, observed_p90) {
+            (Some(delay), _) => delay,
+            (None, Some(p90)) => p90.max(self.min_delay),
+            (None, None) => self.fallback_delay,
+        }
+    }
+}
+
+/// 对冲策略
+///
+/// 持有对冲配置，支持热重载
+#[derive(Debug, Default)]
+pub struct HedgePolicy {
+    settings: RwLock<HedgingSettings>,
+}
+
+impl HedgePolicy {
+    /// 创建新的对冲策略
+    pub fn new(settings: HedgingSettings) -> Self {
+        Self {
+            settings: RwLock::new(settings),
+        }
+    }
+
+    /// 获取配置
+    pub fn settings(&self) -> HedgingSettings {
+        self.settings.read().clone()
+    }
+
+    /// 更新配置
+    pub fn update_settings(&self, settings: HedgingSettings) {
+        *self.settings.write() = settings;
+    }
+
+    /// 匹配对冲规则
+    ///
+    /// 返回 None 表示该请求不对冲（未启用、模型被排除或没有匹配的规则）
+    pub fn plan(&self, client_type: &str, provider: &str, model: &str) -> Option<HedgePlan> {
+        let settings = self.settings.read();
+        if !settings.enabled {
+            return None;
+        }
+        if settings
+            .exclude_models
+            .iter()
+            .any(|pattern| pattern_matches(pattern, model))
+        {
+            return None;
+        }
+
+        settings
+            .rules
+            .iter()
+            .find(|rule| {
+                (rule.client_types.is_empty()
+                    || rule
+                        .client_types
+                        .iter()
+                        .any(|c| c.eq_ignore_ascii_case(client_type)))
+                    && (rule.providers.is_empty()
+                        || rule
+                            .providers
+                            .iter()
+                            .any(|p| p.eq_ignore_ascii_case(provider)))
+                    && (rule.models.is_empty()
+                        || rule
+                            .models
+                            .iter()
+                            .any(|pattern| pattern_matches(pattern, model)))
+            })
+            .map(HedgePlan::from_rule)
+    }
+}
+
+/// 计算 P90 延迟（样本不足 [`MIN_P90_SAMPLES`] 时返回 None）
+pub fn p90_latency(samples_ms: impl IntoIterator<Item = u64>) -> Option<Duration> {
+    let mut sorted: Vec<u64> = samples_ms.into_iter().collect();
+    if sorted.len() < MIN_P90_SAMPLES {
+        return None;
+    }
+    sorted.sort_unstable();
+    let rank = (sorted.len() as f64 * 0.9).ceil() as usize;
+    Some(Duration::from_millis(
+        sorted[rank.clamp(1, sorted.len()) - 1],
+    ))
+}
+
+/// 对冲结果中的获胜方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HedgeWinner {
+    /// 主请求
+    Primary,
+    /// 对冲请求
+    Hedge,
+}
+
+/// 对冲中落败一方的结局
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HedgeLoser {
+    /// 获胜方先返回，落败方被取消
+    Cancelled,
+    /// 落败方已返回不可用的结果
+    Failed,
+}
+
+/// 对冲执行结果
+#[derive(Debug)]
+pub struct HedgeOutcome<T> {
+    /// 最终使用的结果
+    pub value: T,
+    /// 获胜方
+    pub winner: HedgeWinner,
+    /// 落败方结局（未发出对冲请求时为 None）
+    pub loser: Option<HedgeLoser>,
+}
+
+impl<T> HedgeOutcome<T> {
+    /// 是否发出了对冲请求
+    pub fn hedged(&self) -> bool {
+        self.loser.is_some()
+    }
+}
+
+/// 执行对冲请求
+///
+/// - 主请求在 `delay` 内返回时直接使用其结果，不发出对冲请求
+/// - 否则调用 `start_hedge` 发出对冲请求，两者中先返回可用结果（`accept` 为 true）的一方获胜；
+///   `start_hedge` 返回 None 表示此刻无法发出对冲（如备用凭证没有空闲槽位），继续等待主请求
+/// - 先返回的一方不可用时继续等待另一方；两者都不可用时返回主请求的结果
+pub async fn race<T, P, F, H, A>(
+    primary: P,
+    delay: Duration,
+    start_hedge: F,
+    accept: A,
+) -> HedgeOutcome<T>
+where
+    P: Future<Output = T>,
+    F: FnOnce() -> Option<H>,
+    H: Future<Output = T>,
+    A: Fn(&T) -> bool,
+{
+    tokio::pin!(primary);
+
+    tokio::select! {
+        value = &mut primary => {
+            return HedgeOutcome {
+                value,
+                winner: HedgeWinner::Primary,
+                loser: None,
+            };
+        }
+        _ = tokio::time::sleep(delay) => {}
+    }
+
+    let Some(hedge) = start_hedge() else {
+        return HedgeOutcome {
+            value: primary.await,
+            winner: HedgeWinner::Primary,
+            loser: None,
+        };
+    };
+    tokio::pin!(hedge);
+
+    tokio::select! {
+        value = &mut primary => {
+            if accept(&value) {
+                return HedgeOutcome {
+                    value,
+                    winner: HedgeWinner::Primary,
+                    loser: Some(HedgeLoser::Cancelled),
+                };
+            }
+            let hedge_value = hedge.await;
+            if accept(&hedge_value) {
+                HedgeOutcome {
+                    value: hedge_value,
+                    winner: HedgeWinner::Hedge,
+                    loser: Some(HedgeLoser::Failed),
+                }
+            } else {
+                HedgeOutcome {
+                    value,
+                    winner: HedgeWinner::Primary,
+                    loser: Some(HedgeLoser::Failed),
+                }
+            }
+        }
+        hedge_value = &mut hedge => {
+            if accept(&hedge_value) {
+                return HedgeOutcome {
+                    value: hedge_value,
+                    winner: HedgeWinner::Hedge,
+                    loser: Some(HedgeLoser::Cancelled),
+                };
+            }
+            HedgeOutcome {
+                value: primary.await,
+                winner: HedgeWinner::Primary,
+                loser: Some(HedgeLoser::Failed),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    fn rule() -> HedgingRule {
+        HedgingRule {
+            client_types: vec!["claude_code".to_string()],
+            providers: vec!["kiro".to_string()],
+            models: vec!["*haiku*".to_string(), "*sonnet*".to_string()],
+            delay_ms: None,
+            min_delay_ms: 500,
+            fallback_delay_ms: 3000,
+        }
+    }
+
+    fn policy() -> HedgePolicy {
+        HedgePolicy::new(HedgingSettings {
+            enabled: true,
+            rules: vec![rule()],
+            exclude_models: vec!["*opus*".to_string()],
+        })
+    }
+
+    async fn after(ms: u64, value: &'static str) -> &'static str {
+        tokio::time::sleep(Duration::from_millis(ms)).await;
+        value
+    }
+
+    #[test]
+    fn test_plan_matches_rule_dimensions() {
+        let policy = policy();
+        assert!(policy
+            .plan("claude_code", "Kiro", "claude-sonnet-4-5")
+            .is_some());
+        assert!(policy.plan("cursor", "kiro", "claude-sonnet-4-5").is_none());
+        assert!(policy
+            .plan("claude_code", "openai", "claude-sonnet-4-5")
+            .is_none());
+        assert!(policy.plan("claude_code", "kiro", "gpt-4o").is_none());
+    }
+
+    #[test]
+    fn test_excluded_models_are_never_hedged() {
+        let policy = HedgePolicy::new(HedgingSettings {
+            enabled: true,
+            rules: vec![HedgingRule {
+                models: Vec::new(),
+                ..rule()
+            }],
+            exclude_models: vec!["*opus*".to_string()],
+        });
+        assert!(policy
+            .plan("claude_code", "kiro", "claude-opus-4")
+            .is_none());
+        assert!(policy.plan("claude_code", "kiro", "claude-haiku").is_some());
+
+        policy.update_settings(HedgingSettings::default());
+        assert!(policy.plan("claude_code", "kiro", "claude-haiku").is_none());
+    }
+
+    #[test]
+    fn test_delay_uses_p90_with_floor() {
+        let plan = HedgePlan::from_rule(&rule());
+        assert_eq!(plan.delay(None), Duration::from_millis(3000));
+        assert_eq!(
+            plan.delay(Some(Duration::from_millis(100))),
+            Duration::from_millis(500)
+        );
+
+        let p90 = p90_latency((1..=20).map(|i| i * 100)).unwrap();
+        assert_eq!(p90, Duration::from_millis(1800));
+        assert_eq!(plan.delay(Some(p90)), p90);
+        assert!(p90_latency([100, 200]).is_none());
+
+        let fixed = HedgePlan::from_rule(&HedgingRule {
+            delay_ms: Some(800),
+            ..rule()
+        });
+        assert!(!fixed.needs_p90());
+        assert_eq!(fixed.delay(Some(p90)), Duration::from_millis(800));
+    }
+
+    #[tokio::test]
+    async fn test_fast_primary_skips_hedge() {
+        let started = Arc::new(AtomicBool::new(false));
+        let flag = started.clone();
+        let outcome = race(
+            after(10, "primary"),
+            Duration::from_millis(500),
+            move || {
+                flag.store(true, Ordering::SeqCst);
+                Some(after(10, "hedge"))
+            },
+            |_| true,
+        )
+        .await;
+
+        assert_eq!(outcome.value, "primary");
+        assert!(!outcome.hedged());
+        assert!(!started.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_slow_primary_loses_to_hedge() {
+        let outcome = race(
+            after(5000, "primary"),
+            Duration::from_millis(50),
+            || Some(after(10, "hedge")),
+            |_| true,
+        )
+        .await;
+
+        assert_eq!(outcome.value, "hedge");
+        assert_eq!(outcome.winner, HedgeWinner::Hedge);
+        assert_eq!(outcome.loser, Some(HedgeLoser::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn test_failed_first_result_waits_for_other() {
+        let outcome = race(
+            after(300, "primary"),
+            Duration::from_millis(50),
+            || Some(after(10, "error")),
+            |value| *value != "error",
+        )
+        .await;
+        assert_eq!(outcome.value, "primary");
+        assert_eq!(outcome.winner, HedgeWinner::Primary);
+        assert_eq!(outcome.loser, Some(HedgeLoser::Failed));
+
+        let both_failed = race(
+            after(300, "error"),
+            Duration::from_millis(50),
+            || Some(after(10, "error")),
+            |value| *value != "error",
+        )
+        .await;
+        assert_eq!(both_failed.winner, HedgeWinner::Primary);
+        assert_eq!(both_failed.loser, Some(HedgeLoser::Failed));
+    }
+
+    #[tokio::test]
+    async fn test_hedge_not_sent_waits_for_primary() {
+        let started = Arc::new(AtomicBool::new(false));
+        let flag = started.clone();
+        let outcome = race(
+            after(200, "primary"),
+            Duration::from_millis(20),
+            move || {
+                // 延迟到期时才尝试获取备用凭证槽位，获取失败则不对冲
+                flag.store(true, Ordering::SeqCst);
+                None::<std::future::Ready<&'static str>>
+            },
+            |_| true,
+        )
+        .await;
+
+        assert!(started.load(Ordering::SeqCst));
+        assert_eq!(outcome.value, "primary");
+        assert_eq!(outcome.winner, HedgeWinner::Primary);
+        assert!(!outcome.hedged());
+    }
+}
@@ -1,15 +1,25 @@
 //! 容错机制模块
 //!
-//! 提供重试、故障转移和超时控制功能
+//! 提供重试、熔断、对冲、故障转移和超时控制功能
 
+mod circuit_breaker;
 mod failover;
+mod hedging;
 mod retry;
 mod timeout;
 
+pub use circuit_breaker::{
+    CircuitBreaker, CircuitBreakerConfig, CircuitKey, CircuitOpenError, CircuitState,
+    CircuitStatus, CircuitTransition,
+};
 pub use failover::{
     Failover, FailoverConfig, FailoverManager, FailoverResult, FailureType, SwitchEvent,
     QUOTA_EXCEEDED_KEYWORDS, QUOTA_EXCEEDED_STATUS_CODES,
 };
+pub use hedging::{
+    p90_latency, race as hedge_race, HedgeLoser, HedgeOutcome, HedgePlan, HedgePolicy, HedgeWinner,
+    MIN_P90_SAMPLES,
+};
 pub use retry::{Retrier, RetryConfig, RetryError};
 pub use timeout::{
     CancellationToken, StreamIdleDetector, StreamWithIdleTimeout, TimeoutConfig, TimeoutController,
@@ -3,6 +3,7 @@
 //! 提供请求日志记录、查询和轮转功能
 
 use super::types::{ModelStats, ProviderStats, RequestLog, RequestStatus, StatsSummary, TimeRange};
+use crate::resilience::CircuitTransition;
 use chrono::{DateTime, Duration, Utc};
 use parking_lot::RwLock;
 use proxycast_core::ProviderType;
@@ -59,6 +60,9 @@ impl From<serde_json::Error> for LoggerError {
     }
 }
 
+/// 内存中保留的熔断状态转换事件数量
+const MAX_CIRCUIT_EVENTS: usize = 1000;
+
 /// 日志轮转配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogRotationConfig {
@@ -86,6 +90,7 @@ impl Default for LogRotationConfig {
 /// 请求日志记录器
 ///
 /// 管理请求日志的记录、存储和查询
+#[derive(Debug)]
 pub struct RequestLogger {
     /// 内存中的日志队列
     logs: RwLock<VecDeque<RequestLog>>,
@@ -95,6 +100,8 @@ pub struct RequestLogger {
     log_dir: PathBuf,
     /// 当前日志文件路径
     current_log_file: RwLock<Option<PathBuf>>,
+    /// 熔断状态转换事件
+    circuit_events: RwLock<VecDeque<CircuitTransition>>,
 }
 
 impl RequestLogger {
@@ -115,6 +122,7 @@ impl RequestLogger {
             config,
             log_dir,
             current_log_file: RwLock::new(None),
+            circuit_events: RwLock::new(VecDeque::new()),
         };
 
         // 初始化日志文件
@@ -151,6 +159,37 @@ impl RequestLogger {
         Ok(())
     }
 
+    /// 记录熔断状态转换事件
+    ///
+    /// 事件写入 `circuit_YYYY-MM-DD.jsonl`，与请求日志一起按保留天数清理。
+    pub fn record_circuit_transition(
+        &self,
+        transition: &CircuitTransition,
+    ) -> Result<(), LoggerError> {
+        {
+            let mut events = self.circuit_events.write();
+            events.push_back(transition.clone());
+            while events.len() > MAX_CIRCUIT_EVENTS {
+                events.pop_front();
+            }
+        }
+
+        if self.config.enable_file_logging {
+            let today = Utc::now().format("%Y-%m-%d").to_string();
+            let path = self.log_dir.join(format!("circuit_{today}.jsonl"));
+            let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+            let json = serde_json::to_string(transition)?;
+            writeln!(file, "{json}")?;
+        }
+
+        Ok(())
+    }
+
+    /// 获取内存中的熔断状态转换事件（按时间升序）
+    pub fn circuit_transitions(&self) -> Vec<CircuitTransition> {
+        self.circuit_events.read().iter().cloned().collect()
+    }
+
     /// 获取所有内存中的日志
     pub fn get_all(&self) -> Vec<RequestLog> {
         self.logs.read().iter().cloned().collect()
@@ -392,8 +431,10 @@ impl RequestLogger {
     /// 从日志文件名解析日期
     fn parse_log_file_date(&self, path: &Path) -> Option<DateTime<Utc>> {
         let file_name = path.file_stem()?.to_str()?;
-        // 文件名格式: requests_YYYY-MM-DD 或 requests_YYYY-MM-DD_N
-        let date_part = file_name.strip_prefix("requests_")?;
+        // 文件名格式: requests_YYYY-MM-DD、requests_YYYY-MM-DD_N 或 circuit_YYYY-MM-DD
+        let date_part = file_name
+            .strip_prefix("requests_")
+            .or_else(|| file_name.strip_prefix("circuit_"))?;
         let date_str = if date_part.len() >= 10 {
             &date_part[..10]
         } else {
@@ -438,7 +479,14 @@ impl RequestLogger {
             let entry = entry?;
             let path = entry.path();
 
-            if path.is_file() && path.extension().is_some_and(|ext| ext == "jsonl") {
+            let is_request_log = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("requests_"));
+            if is_request_log
+                && path.is_file()
+                && path.extension().is_some_and(|ext| ext == "jsonl")
+            {
                 if let Some(file_date) = self.parse_log_file_date(&path) {
                     if file_date >= cutoff {
                         log_files.push(path);
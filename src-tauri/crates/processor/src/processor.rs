@@ -16,11 +16,16 @@
 pub use proxycast_core::processor::RequestContext;
 
 use parking_lot::RwLock as ParkingLotRwLock;
+use proxycast_core::config::{
+    StreamFailoverSettings, StructuredOutputSettings, ToolEmulationSettings,
+};
+use proxycast_core::guardrails::{GuardrailAuditLog, GuardrailEngine};
 use proxycast_core::plugin::PluginManager;
 use proxycast_core::router::{ModelMapper, Router};
 use proxycast_core::ProviderType;
 use proxycast_infra::{
-    Failover, Injector, Retrier, StatsAggregator, TimeoutController, TokenTracker,
+    CircuitBreaker, Failover, HedgePolicy, Injector, Retrier, StatsAggregator, TimeoutController,
+    TokenTracker,
 };
 use proxycast_services::provider_pool_service::ProviderPoolService;
 use std::sync::Arc;
@@ -42,6 +47,20 @@ pub struct RequestProcessor {
     pub failover: Arc<Failover>,
     /// 超时控制器
     pub timeout: Arc<TimeoutController>,
+    /// 熔断器（按凭证和 Provider 维度）
+    pub circuit_breaker: Arc<CircuitBreaker>,
+    /// 对冲请求策略
+    pub hedging: Arc<HedgePolicy>,
+    /// 流式响应中途故障转移配置
+    pub stream_failover: Arc<ParkingLotRwLock<StreamFailoverSettings>>,
+    /// 结构化输出配置
+    pub structured_output: Arc<ParkingLotRwLock<StructuredOutputSettings>>,
+    /// 工具调用模拟配置
+    pub tool_emulation: Arc<ParkingLotRwLock<ToolEmulationSettings>>,
+    /// 内容安全检查引擎（配置更新时整体替换）
+    pub guardrails: Arc<ParkingLotRwLock<Arc<GuardrailEngine>>>,
+    /// 内容安全审计记录
+    pub guardrail_audit: Arc<GuardrailAuditLog>,
     /// 插件管理器
     pub plugins: Arc<PluginManager>,
     /// 统计聚合器（使用 parking_lot::RwLock 以支持与 TelemetryState 共享）
@@ -79,6 +98,13 @@ impl RequestProcessor {
             retrier,
             failover,
             timeout,
+            circuit_breaker: Arc::new(CircuitBreaker::with_defaults()),
+            hedging: Arc::new(HedgePolicy::default()),
+            stream_failover: Arc::new(ParkingLotRwLock::new(StreamFailoverSettings::default())),
+            structured_output: Arc::new(ParkingLotRwLock::new(StructuredOutputSettings::default())),
+            tool_emulation: Arc::new(ParkingLotRwLock::new(ToolEmulationSettings::default())),
+            guardrails: Arc::new(ParkingLotRwLock::new(Arc::new(GuardrailEngine::disabled()))),
+            guardrail_audit: Arc::new(GuardrailAuditLog::default()),
             plugins,
             stats,
             tokens,
@@ -100,6 +126,13 @@ impl RequestProcessor {
             retrier: Arc::new(Retrier::with_defaults()),
             failover: Arc::new(Failover::with_defaults()),
             timeout: Arc::new(TimeoutController::with_defaults()),
+            circuit_breaker: Arc::new(CircuitBreaker::with_defaults()),
+            hedging: Arc::new(HedgePolicy::default()),
+            stream_failover: Arc::new(ParkingLotRwLock::new(StreamFailoverSettings::default())),
+            structured_output: Arc::new(ParkingLotRwLock::new(StructuredOutputSettings::default())),
+            tool_emulation: Arc::new(ParkingLotRwLock::new(ToolEmulationSettings::default())),
+            guardrails: Arc::new(ParkingLotRwLock::new(Arc::new(GuardrailEngine::disabled()))),
+            guardrail_audit: Arc::new(GuardrailAuditLog::default()),
             plugins: Arc::new(PluginManager::with_defaults()),
             stats: Arc::new(ParkingLotRwLock::new(StatsAggregator::with_defaults())),
             tokens: Arc::new(ParkingLotRwLock::new(TokenTracker::with_defaults())),
@@ -134,6 +167,13 @@ impl RequestProcessor {
             retrier: Arc::new(Retrier::with_defaults()),
             failover: Arc::new(Failover::with_defaults()),
             timeout: Arc::new(TimeoutController::with_defaults()),
+            circuit_breaker: Arc::new(CircuitBreaker::with_defaults()),
+            hedging: Arc::new(HedgePolicy::default()),
+            stream_failover: Arc::new(ParkingLotRwLock::new(StreamFailoverSettings::default())),
+            structured_output: Arc::new(ParkingLotRwLock::new(StructuredOutputSettings::default())),
+            tool_emulation: Arc::new(ParkingLotRwLock::new(ToolEmulationSettings::default())),
+            guardrails: Arc::new(ParkingLotRwLock::new(Arc::new(GuardrailEngine::disabled()))),
+            guardrail_audit: Arc::new(GuardrailAuditLog::default()),
             plugins: Arc::new(PluginManager::with_defaults()),
             stats,
             tokens,
@@ -9,10 +9,14 @@
 //! - `translator`: 请求/响应翻译层
 //! - `stream`: 流事件解析和生成
 //! - `session`: 会话管理（签名存储、会话 ID 生成）
+//! - `structured_output`: 结构化输出（`response_format`）映射、校验与修复
+//! - `tool_emulation`: 为不支持原生工具调用的上游模拟 function calling
 
 pub mod converter;
 pub mod providers;
 pub mod session;
 pub mod stream;
 pub mod streaming;
+pub mod structured_output;
+pub mod tool_emulation;
 pub mod translator;
@@ -332,6 +332,9 @@ pub struct AntigravityCredentials {
     /// 用户邮箱
     #[serde(skip_serializing_if = "Option::is_none")]
     pub email: Option<String>,
+    /// 覆盖 API 基础地址（用于流量录制代理或本地模拟上游）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_endpoint: Option<String>,
 }
 
 fn default_antigravity_type() -> String {
@@ -354,6 +357,7 @@ impl Default for AntigravityCredentials {
             enable: None,
             project_id: None,
             email: None,
+            api_endpoint: None,
         }
     }
 }
@@ -422,11 +426,7 @@ impl AntigravityProvider {
 
         // 尝试解析为单个凭证对象
         if let Ok(creds) = serde_json::from_str::<AntigravityCredentials>(&content) {
-            self.credentials = creds;
-            // 如果凭证中有 project_id，设置到 provider
-            if let Some(ref pid) = self.credentials.project_id {
-                self.project_id = Some(pid.clone());
-            }
+            self.apply_credentials(creds);
             return Ok(());
         }
 
@@ -434,11 +434,7 @@ impl AntigravityProvider {
         if let Ok(creds_array) = serde_json::from_str::<Vec<AntigravityCredentials>>(&content) {
             // 找到第一个启用的凭证
             if let Some(creds) = creds_array.into_iter().find(|c| c.enable != Some(false)) {
-                self.credentials = creds;
-                // 如果凭证中有 project_id，设置到 provider
-                if let Some(ref pid) = self.credentials.project_id {
-                    self.project_id = Some(pid.clone());
-                }
+                self.apply_credentials(creds);
                 return Ok(());
             }
             return Err("凭证文件中没有可用的账号（所有账号都被禁用）".into());
@@ -447,6 +443,18 @@ impl AntigravityProvider {
         Err("无法解析凭证文件，请确保是有效的 JSON 格式".into())
     }
 
+    /// 使用加载到的凭证，同步 project_id 和 API 基础地址覆盖
+    fn apply_credentials(&mut self, creds: AntigravityCredentials) {
+        // 如果凭证中有 project_id，设置到 provider
+        if let Some(ref pid) = creds.project_id {
+            self.project_id = Some(pid.clone());
+        }
+        if let Some(endpoint) = creds.api_endpoint.as_deref().filter(|e| !e.is_empty()) {
+            self.base_urls = vec![endpoint.trim_end_matches('/').to_string()];
+        }
+        self.credentials = creds;
+    }
+
     pub async fn save_credentials(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
         let path = Self::default_creds_path();
         if let Some(parent) = path.parent() {
@@ -1524,6 +1532,7 @@ pub async fn start_oauth_server_and_get_url(
                     enable: Some(true),
                     project_id,
                     email: email.clone(),
+                    api_endpoint: None,
                 };
 
                 // 保存凭证到应用数据目录
@@ -1745,6 +1754,7 @@ pub async fn start_oauth_login_with_port(
                     enable: Some(true),
                     project_id,
                     email: email.clone(),
+                    api_endpoint: None,
                 };
 
                 // 保存凭证到应用数据目录
@@ -1971,6 +1981,7 @@ pub async fn start_oauth_login(
                     enable: Some(true),
                     project_id,
                     email: email.clone(),
+                    api_endpoint: None,
                 };
 
                 // 保存凭证到应用数据目录
@@ -131,6 +131,56 @@ impl ClaudeCustomProvider {
         None
     }
 
+    /// 将 OpenAI tools 转换为 Anthropic 格式（WebSearch 等其他工具类型暂不处理）
+    fn convert_tools_to_claude(request: &ChatCompletionRequest) -> Option<Vec<serde_json::Value>> {
+        let tools: Vec<serde_json::Value> = request
+            .tools
+            .as_ref()?
+            .iter()
+            .filter_map(|tool| match tool {
+                proxycast_core::models::openai::Tool::Function { function } => {
+                    Some(serde_json::json!({
+                        "name": function.name,
+                        "description": function.description.clone().unwrap_or_default(),
+                        "input_schema": function.parameters.clone().unwrap_or_else(|| serde_json::json!({"type": "object", "properties": {}}))
+                    }))
+                }
+                _ => None,
+            })
+            .collect();
+        (!tools.is_empty()).then_some(tools)
+    }
+
+    /// 将 OpenAI tool_choice 转换为 Anthropic 格式，未知值返回 None
+    fn convert_tool_choice_to_claude(request: &ChatCompletionRequest) -> Option<serde_json::Value> {
+        match request.tool_choice.as_ref()? {
+            serde_json::Value::String(s) => match s.as_str() {
+                "none" => Some(serde_json::json!({"type": "none"})),
+                "auto" => Some(serde_json::json!({"type": "auto"})),
+                "required" | "any" => Some(serde_json::json!({"type": "any"})),
+                _ => None,
+            },
+            serde_json::Value::Object(obj) => {
+                // 处理 {"type": "function", "function": {"name": "xxx"}} 格式
+                if let Some(func) = obj.get("function") {
+                    func.get("name")
+                        .and_then(|n| n.as_str())
+                        .map(|name| serde_json::json!({"type": "tool", "name": name}))
+                } else if let Some(t) = obj.get("type").and_then(|t| t.as_str()) {
+                    match t {
+                        "any" | "tool" => Some(serde_json::json!({"type": "any"})),
+                        "auto" => Some(serde_json::json!({"type": "auto"})),
+                        "none" => Some(serde_json::json!({"type": "none"})),
+                        _ => None,
+                    }
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
     /// 调用 Anthropic API（原生格式）
     pub async fn call_api(
         &self,
@@ -244,6 +294,12 @@ impl ClaudeCustomProvider {
         if let Some(sys) = system_content {
             anthropic_body["system"] = serde_json::json!(sys);
         }
+        if let Some(anthropic_tools) = Self::convert_tools_to_claude(request) {
+            anthropic_body["tools"] = serde_json::json!(anthropic_tools);
+        }
+        if let Some(tc) = Self::convert_tool_choice_to_claude(request) {
+            anthropic_body["tool_choice"] = tc;
+        }
 
         let api_key = self
             .config
@@ -297,12 +353,41 @@ impl ClaudeCustomProvider {
 
         let anthropic_resp: serde_json::Value = resp.json().await?;
 
-        // 转换回 OpenAI 格式
-        let content = anthropic_resp["content"]
+        // 转换回 OpenAI 格式：拼接文本块，tool_use 块转换为 tool_calls
+        let blocks = anthropic_resp["content"]
             .as_array()
-            .and_then(|arr| arr.first())
-            .and_then(|block| block["text"].as_str())
-            .unwrap_or("");
+            .cloned()
+            .unwrap_or_default();
+        let content = blocks
+            .iter()
+            .filter(|block| block["type"] == "text")
+            .filter_map(|block| block["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("");
+        let tool_calls: Vec<serde_json::Value> = blocks
+            .iter()
+            .filter(|block| block["type"] == "tool_use")
+            .map(|block| {
+                serde_json::json!({
+                    "id": block["id"],
+                    "type": "function",
+                    "function": {
+                        "name": block["name"],
+                        "arguments": block["input"].to_string()
+                    }
+                })
+            })
+            .collect();
+        let mut message = serde_json::json!({
+            "role": "assistant",
+            "content": content
+        });
+        let finish_reason = if tool_calls.is_empty() {
+            "stop"
+        } else {
+            message["tool_calls"] = serde_json::json!(tool_calls);
+            "tool_calls"
+        };
 
         Ok(serde_json::json!({
             "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
@@ -314,11 +399,8 @@ impl ClaudeCustomProvider {
             "model": request.model,
             "choices": [{
                 "index": 0,
-                "message": {
-                    "role": "assistant",
-                    "content": content
-                },
-                "finish_reason": "stop"
+                "message": message,
+                "finish_reason": finish_reason
             }],
             "usage": {
                 "prompt_tokens": anthropic_resp["usage"]["input_tokens"].as_u64().unwrap_or(0),
@@ -554,72 +636,17 @@ impl StreamingProvider for ClaudeCustomProvider {
             anthropic_body["system"] = serde_json::json!(sys);
         }
 
-        // 转换 tools: OpenAI 格式 -> Anthropic 格式
-        if let Some(ref tools) = request.tools {
-            let anthropic_tools: Vec<serde_json::Value> = tools
-                .iter()
-                .filter_map(|tool| {
-                    match tool {
-                        proxycast_core::models::openai::Tool::Function { function } => {
-                            Some(serde_json::json!({
-                                "name": function.name,
-                                "description": function.description.clone().unwrap_or_default(),
-                                "input_schema": function.parameters.clone().unwrap_or_else(|| serde_json::json!({"type": "object", "properties": {}}))
-                            }))
-                        }
-                        // WebSearch 等其他工具类型暂不处理
-                        _ => None,
-                    }
-                })
-                .collect();
-
-            if !anthropic_tools.is_empty() {
-                anthropic_body["tools"] = serde_json::json!(anthropic_tools);
-                tracing::info!(
-                    "[CLAUDE_STREAM] 添加 {} 个工具到请求",
-                    anthropic_tools.len()
-                );
-            }
+        // 转换 tools / tool_choice: OpenAI 格式 -> Anthropic 格式
+        if let Some(anthropic_tools) = Self::convert_tools_to_claude(request) {
+            tracing::info!(
+                "[CLAUDE_STREAM] 添加 {} 个工具到请求",
+                anthropic_tools.len()
+            );
+            anthropic_body["tools"] = serde_json::json!(anthropic_tools);
         }
-
-        // 转换 tool_choice: OpenAI 格式 -> Anthropic 格式
-        if let Some(ref tool_choice) = request.tool_choice {
-            let anthropic_tool_choice = match tool_choice {
-                serde_json::Value::String(s) => {
-                    match s.as_str() {
-                        "none" => Some(serde_json::json!({"type": "none"})),
-                        "auto" => Some(serde_json::json!({"type": "auto"})),
-                        "required" | "any" => Some(serde_json::json!({"type": "any"})),
-                        _ => None, // 未知值，不设置
-                    }
-                }
-                serde_json::Value::Object(obj) => {
-                    // 处理 {"type": "function", "function": {"name": "xxx"}} 格式
-                    if let Some(func) = obj.get("function") {
-                        func.get("name")
-                            .and_then(|n| n.as_str())
-                            .map(|name| serde_json::json!({"type": "tool", "name": name}))
-                    } else if let Some(t) = obj.get("type").and_then(|t| t.as_str()) {
-                        match t {
-                            "any" | "tool" => Some(serde_json::json!({"type": "any"})),
-                            "auto" => Some(serde_json::json!({"type": "auto"})),
-                            "none" => Some(serde_json::json!({"type": "none"})),
-                            _ => None,
-                        }
-                    } else {
-                        None
-                    }
-                }
-                _ => None,
-            };
-
-            if let Some(tc) = anthropic_tool_choice {
-                anthropic_body["tool_choice"] = tc;
-                tracing::info!(
-                    "[CLAUDE_STREAM] 设置 tool_choice: {:?}",
-                    anthropic_body["tool_choice"]
-                );
-            }
+        if let Some(tc) = Self::convert_tool_choice_to_claude(request) {
+            tracing::info!("[CLAUDE_STREAM] 设置 tool_choice: {:?}", tc);
+            anthropic_body["tool_choice"] = tc;
         }
 
         let url = self.build_url("messages");
@@ -255,6 +255,9 @@ pub struct KiroCredentials {
     /// 凭证类型标识
     #[serde(default = "default_kiro_type", rename = "type")]
     pub cred_type: String,
+    /// 覆盖 CodeWhisperer API 端点（用于流量录制代理或本地模拟上游）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_endpoint: Option<String>,
 }
 
 fn default_kiro_type() -> String {
@@ -276,6 +279,7 @@ impl Default for KiroCredentials {
             client_id_hash: None,
             last_refresh: None,
             cred_type: default_kiro_type(),
+            api_endpoint: None,
         }
     }
 }
@@ -585,6 +589,9 @@ impl KiroProvider {
     }
 
     pub fn get_base_url(&self) -> String {
+        if let Some(endpoint) = &self.credentials.api_endpoint {
+            return endpoint.clone();
+        }
         let region = self.credentials.region.as_deref().unwrap_or("us-east-1");
         format!("https://codewhisperer.{region}.amazonaws.com/generateAssistantResponse")
     }
@@ -1125,6 +1132,9 @@ fn merge_credentials(target: &mut KiroCredentials, source: &KiroCredentials) {
     if source.last_refresh.is_some() {
         target.last_refresh = source.last_refresh.clone();
     }
+    if source.api_endpoint.is_some() {
+        target.api_endpoint = source.api_endpoint.clone();
+    }
     // cred_type 使用默认值，不需要合并
 }
 
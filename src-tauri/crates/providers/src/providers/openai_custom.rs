@@ -292,6 +292,43 @@ impl OpenAICustomProvider {
         Ok(resp)
     }
 
+    /// 调用 OpenAI 兼容的语音合成 API（`/v1/audio/speech`）
+    ///
+    /// 返回原始响应，响应体为音频数据
+    pub async fn audio_speech(
+        &self,
+        request: &serde_json::Value,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let api_key = self
+            .config
+            .api_key
+            .as_ref()
+            .ok_or("OpenAI API key not configured")?;
+
+        let urls = self.build_urls_with_fallbacks("audio/speech");
+        let mut last_resp: Option<reqwest::Response> = None;
+
+        for url in &urls {
+            let resp = self
+                .client
+                .post(url)
+                .header("Authorization", format!("Bearer {api_key}"))
+                .header("Content-Type", "application/json")
+                .json(request)
+                .send()
+                .await?;
+
+            Self::maybe_log_protocol_mismatch_hint(url, resp.status());
+
+            if resp.status() != StatusCode::NOT_FOUND {
+                return Ok(resp);
+            }
+            last_resp = Some(resp);
+        }
+
+        Ok(last_resp.ok_or("Request failed")?)
+    }
+
     pub async fn list_models(&self) -> Result<serde_json::Value, Box<dyn Error + Send + Sync>> {
         let api_key = self
             .config
@@ -19,6 +19,7 @@
 use crate::streaming::converter::{StreamConverter, StreamFormat};
 use crate::streaming::error::StreamError;
 use crate::streaming::metrics::StreamMetrics;
+use crate::streaming::resume::StreamTranscript;
 use crate::streaming::traits::StreamResponse;
 use bytes::Bytes;
 use futures::{Stream, StreamExt};
@@ -218,6 +219,13 @@ impl StreamManager {
         self.config = config;
     }
 
+    /// 创建已下发内容记录
+    ///
+    /// 用于流中断续传：记录下发给客户端的助手内容，已下发文本超过 `buffer_size` 时不再续传。
+    pub fn transcript(&self, format: StreamFormat) -> StreamTranscript {
+        StreamTranscript::new(format, self.config.buffer_size)
+    }
+
     /// 处理流式请求
     ///
     /// 将源流转换为目标格式的 SSE 事件流。
@@ -12,6 +12,7 @@
 //! - `converter`: 流式格式转换器
 //! - `traits`: StreamingProvider trait 定义
 //! - `manager`: 流式管理器
+//! - `resume`: 流中断续传（已下发内容记录与续传流拼接）
 
 pub mod anthropic_sse;
 pub mod aws_parser;
@@ -19,6 +20,7 @@ pub mod converter;
 pub mod error;
 pub mod manager;
 pub mod metrics;
+pub mod resume;
 pub mod traits;
 
 // 重新导出核心类型
@@ -26,4 +28,5 @@ pub use converter::StreamFormat;
 pub use error::StreamError;
 pub use manager::{with_timeout, StreamConfig, StreamContext, StreamManager};
 pub use metrics::StreamMetrics;
+pub use resume::{ResumeSplicer, SseFramer, StreamTranscript};
 pub use traits::{reqwest_stream_to_stream_response, StreamResponse};
@@ -0,0 +1,942 @@
+//! 流中断续传
+//!
+//! 上游流在中途断开（网络重置、空闲超时、流内错误事件）时，以已发送给客户端的助手文本作为
+//! 预填充（prefill）向其他凭证重新发起请求，并把续传流拼接回同一个客户端流：
+//!
+//! - [`StreamTranscript`] 跟踪已下发的客户端格式 SSE 事件（Anthropic / OpenAI），累积助手文本。
+//!   工具调用事件在完整结束前暂不下发，断流时直接丢弃未完成的部分，由续传重新生成，
+//!   避免客户端收到残缺的工具调用 JSON
+//! - [`ResumeSplicer`] 改写续传流：去掉重复的开头事件，重排内容块索引，
+//!   并去掉续传开头与已下发文本重复的部分
+
+use crate::streaming::converter::StreamFormat;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+// ============================================================================
+// SSE 事件解析
+// ============================================================================
+
+/// 解析后的 SSE 事件
+#[derive(Debug, Clone, PartialEq)]
+pub struct SseEvent {
+    /// `event:` 字段
+    pub event: Option<String>,
+    /// `data:` 字段（多行已合并）
+    pub data: String,
+}
+
+impl SseEvent {
+    /// 解析单个完整事件，注释和空事件返回 None
+    pub fn parse(raw: &str) -> Option<Self> {
+        let mut event = None;
+        let mut data = Vec::new();
+        for line in raw.lines() {
+            if let Some(value) = line.strip_prefix("event:") {
+                event = Some(value.trim().to_string());
+            } else if let Some(value) = line.strip_prefix("data:") {
+                data.push(value.strip_prefix(' ').unwrap_or(value));
+            }
+        }
+        if event.is_none() && data.is_empty() {
+            return None;
+        }
+        Some(Self {
+            event,
+            data: data.join("\n"),
+        })
+    }
+
+    /// 解析 data 中的 JSON
+    pub fn json(&self) -> Option<Value> {
+        serde_json::from_str(&self.data).ok()
+    }
+
+    /// 是否为 OpenAI 流结束标记 `[DONE]`
+    pub fn is_done(&self) -> bool {
+        self.data.trim() == "[DONE]"
+    }
+
+    /// 流内错误事件的错误信息
+    ///
+    /// 识别 `event: error`、Anthropic 的 `{"type":"error"}` 和 OpenAI 兼容上游的 `{"error":{...}}`。
+    pub fn error_message(&self) -> Option<String> {
+        let json = self.json();
+        let is_error = self.event.as_deref() == Some("error")
+            || json.as_ref().is_some_and(|j| {
+                j.get("type").and_then(Value::as_str) == Some("error")
+                    || j.get("error").is_some_and(Value::is_object)
+            });
+        if !is_error {
+            return None;
+        }
+        Some(
+            json.as_ref()
+                .and_then(|j| j.pointer("/error/message"))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .unwrap_or_else(|| self.data.clone()),
+        )
+    }
+
+    /// 事件类型：优先取 data 中的 `type`，其次取 `event:` 字段
+    fn kind(&self, json: Option<&Value>) -> String {
+        json.and_then(|j| j.get("type"))
+            .and_then(Value::as_str)
+            .or(self.event.as_deref())
+            .unwrap_or_default()
+            .to_string()
+    }
+}
+
+/// 将字节流切分为完整的 SSE 事件（保留结尾空行）
+#[derive(Debug, Default)]
+pub struct SseFramer {
+    buffer: Vec<u8>,
+}
+
+impl SseFramer {
+    /// 追加字节，返回已完整的事件
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        // JSON 中的 \r 已转义，直接去掉裸 \r 以统一换行
+        self.buffer
+            .extend(bytes.iter().copied().filter(|b| *b != b'\r'));
+        let mut events = Vec::new();
+        while let Some(pos) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let rest = self.buffer.split_off(pos + 2);
+            let event = std::mem::replace(&mut self.buffer, rest);
+            events.push(String::from_utf8_lossy(&event).into_owned());
+        }
+        events
+    }
+
+    /// 取出未以空行结束的剩余数据
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.buffer);
+        let rest = String::from_utf8_lossy(&rest);
+        if rest.trim().is_empty() {
+            None
+        } else {
+            Some(format!("{}\n\n", rest.trim_end()))
+        }
+    }
+}
+
+fn anthropic_event(name: &str, data: &Value) -> String {
+    format!("event: {name}\ndata: {data}\n\n")
+}
+
+/// Anthropic 内容块类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Text,
+    Thinking,
+    /// 工具调用等需要完整下发的内容块
+    Tool,
+}
+
+impl BlockKind {
+    fn from_type(block_type: &str) -> Self {
+        match block_type {
+            "text" => Self::Text,
+            "thinking" | "redacted_thinking" => Self::Thinking,
+            _ => Self::Tool,
+        }
+    }
+}
+
+// ============================================================================
+// 已下发内容记录
+// ============================================================================
+
+/// 已下发给客户端的流内容记录
+#[derive(Debug)]
+pub struct StreamTranscript {
+    format: StreamFormat,
+    /// 预填充文本上限（字节），超出后不再续传
+    max_bytes: usize,
+    /// 已下发的助手文本
+    text: String,
+    /// 是否已下发过开头事件（Anthropic `message_start`）
+    started: bool,
+    /// 当前未结束的内容块（Anthropic：索引和类型）
+    open_block: Option<(u64, BlockKind)>,
+    /// 下一个内容块索引（Anthropic）
+    next_index: u64,
+    /// 暂存的工具调用事件
+    held: Vec<String>,
+    /// 暂存事件中的文本（随工具调用一起下发）
+    held_text: String,
+    /// 是否已下发过工具调用
+    tool_emitted: bool,
+    /// 是否已收到结束事件
+    finished: bool,
+    /// 已下发文本是否超过预填充上限
+    overflow: bool,
+    /// 响应 ID（OpenAI chunk id）
+    response_id: Option<String>,
+    /// 最近一个 OpenAI chunk（用于构造补发的文本 chunk）
+    last_chunk: Option<Value>,
+}
+
+impl StreamTranscript {
+    /// 创建记录（`max_bytes` 为可续传的已下发文本上限）
+    pub fn new(format: StreamFormat, max_bytes: usize) -> Self {
+        Self {
+            format,
+            max_bytes,
+            text: String::new(),
+            started: false,
+            open_block: None,
+            next_index: 0,
+            held: Vec::new(),
+            held_text: String::new(),
+            tool_emitted: false,
+            finished: false,
+            overflow: false,
+            response_id: None,
+            last_chunk: None,
+        }
+    }
+
+    /// 记录一个即将下发的事件，返回现在应当下发的事件
+    ///
+    /// 工具调用事件会被暂存，直到调用完整结束后一起返回；遇到流内错误事件返回 `Err`（不下发）。
+    pub fn record(&mut self, raw: String) -> Result<Vec<String>, String> {
+        let Some(event) = SseEvent::parse(&raw) else {
+            return Ok(vec![raw]);
+        };
+        if let Some(message) = event.error_message() {
+            return Err(message);
+        }
+        self.started = true;
+        Ok(match self.format {
+            StreamFormat::OpenAiSse => self.record_openai(raw, &event),
+            _ => self.record_anthropic(raw, &event),
+        })
+    }
+
+    fn record_anthropic(&mut self, raw: String, event: &SseEvent) -> Vec<String> {
+        let json = event.json();
+        let index = json
+            .as_ref()
+            .and_then(|j| j.get("index"))
+            .and_then(Value::as_u64);
+        match event.kind(json.as_ref()).as_str() {
+            "content_block_start" => {
+                let block = BlockKind::from_type(
+                    json.as_ref()
+                        .and_then(|j| j.pointer("/content_block/type"))
+                        .and_then(Value::as_str)
+                        .unwrap_or("text"),
+                );
+                if let Some(index) = index {
+                    self.open_block = Some((index, block));
+                }
+                if block == BlockKind::Tool {
+                    self.held.push(raw);
+                    return Vec::new();
+                }
+                if let Some(index) = index {
+                    self.next_index = self.next_index.max(index + 1);
+                }
+            }
+            "content_block_delta" => {
+                if self.holding_tool() {
+                    self.held.push(raw);
+                    return Vec::new();
+                }
+                if let Some(text) = json
+                    .as_ref()
+                    .and_then(|j| j.pointer("/delta/text"))
+                    .and_then(Value::as_str)
+                {
+                    self.push_text(text);
+                }
+            }
+            "content_block_stop" => {
+                let tool = self.holding_tool();
+                if index.is_none() || self.open_block.map(|(i, _)| i) == index {
+                    self.open_block = None;
+                }
+                if tool {
+                    if let Some(index) = index {
+                        self.next_index = self.next_index.max(index + 1);
+                    }
+                    self.held.push(raw);
+                    return self.flush_held();
+                }
+            }
+            "message_delta"
+                if json
+                    .as_ref()
+                    .and_then(|j| j.pointer("/delta/stop_reason"))
+                    .is_some_and(|v| !v.is_null()) =>
+            {
+                self.finished = true;
+            }
+            "message_stop" => self.finished = true,
+            _ => {}
+        }
+        vec![raw]
+    }
+
+    fn record_openai(&mut self, raw: String, event: &SseEvent) -> Vec<String> {
+        if event.is_done() {
+            self.finished = true;
+            let mut events = self.flush_held();
+            events.push(raw);
+            return events;
+        }
+        let Some(chunk) = event.json() else {
+            return vec![raw];
+        };
+        if self.response_id.is_none() {
+            self.response_id = chunk.get("id").and_then(Value::as_str).map(str::to_string);
+        }
+        let choice = chunk.pointer("/choices/0");
+        let has_tool_calls = choice
+            .and_then(|c| c.pointer("/delta/tool_calls"))
+            .is_some_and(|v| !v.is_null());
+        let finish = choice
+            .and_then(|c| c.get("finish_reason"))
+            .is_some_and(|v| !v.is_null());
+        let text = choice
+            .and_then(|c| c.pointer("/delta/content"))
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        self.last_chunk = Some(chunk);
+
+        // 工具调用开始后保持事件顺序，之后的 chunk 一并暂存到结束
+        if has_tool_calls || !self.held.is_empty() {
+            if let Some(text) = text {
+                self.held_text.push_str(&text);
+            }
+            self.held.push(raw);
+            if finish {
+                self.finished = true;
+                return self.flush_held();
+            }
+            return Vec::new();
+        }
+
+        if let Some(text) = text {
+            self.push_text(&text);
+        }
+        if finish {
+            self.finished = true;
+        }
+        vec![raw]
+    }
+
+    fn holding_tool(&self) -> bool {
+        matches!(self.open_block, Some((_, BlockKind::Tool)))
+    }
+
+    fn flush_held(&mut self) -> Vec<String> {
+        if self.held.is_empty() {
+            return Vec::new();
+        }
+        self.tool_emitted = true;
+        let text = std::mem::take(&mut self.held_text);
+        self.push_text(&text);
+        std::mem::take(&mut self.held)
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if self.overflow {
+            return;
+        }
+        self.text.push_str(text);
+        if self.text.len() > self.max_bytes {
+            self.overflow = true;
+            self.text = String::new();
+        }
+    }
+
+    /// 流是否已正常结束
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// 断流后能否续传：未正常结束、未下发过工具调用、已下发文本未超过上限
+    pub fn can_resume(&self) -> bool {
+        !self.finished && !self.tool_emitted && !self.overflow
+    }
+
+    /// 已下发的助手文本
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// 续传请求使用的助手预填充
+    ///
+    /// 去掉结尾空白（Anthropic 不接受以空白结尾的预填充），续传开头补回的空白会被去重。
+    pub fn prefill(&self) -> &str {
+        self.text.trim_end()
+    }
+
+    /// 断流：丢弃未完成的工具调用事件，返回续传流的拼接器
+    pub fn resume(&mut self) -> ResumeSplicer {
+        self.held.clear();
+        self.held_text.clear();
+        if self.holding_tool() {
+            // 工具调用的开始事件尚未下发，客户端看不到这个内容块
+            self.open_block = None;
+        }
+        ResumeSplicer {
+            format: self.format,
+            started: self.started,
+            open_block: self.open_block,
+            next_index: self.next_index,
+            index_map: HashMap::new(),
+            block_seen: false,
+            text_seen: false,
+            dedup_index: None,
+            dedup: TextDeduper::new(&self.text),
+            response_id: self.response_id.clone(),
+            template: self.last_chunk.clone(),
+        }
+    }
+}
+
+// ============================================================================
+// 续传流拼接
+// ============================================================================
+
+/// 续传流拼接器
+///
+/// 将续传请求的客户端格式 SSE 事件改写为原响应的延续，输出的事件仍需交给
+/// [`StreamTranscript::record`] 记录，以便再次断流时继续续传。
+#[derive(Debug)]
+pub struct ResumeSplicer {
+    format: StreamFormat,
+    /// 客户端是否已收到开头事件
+    started: bool,
+    /// 断流时未结束的内容块
+    open_block: Option<(u64, BlockKind)>,
+    /// 续传内容块的起始索引
+    next_index: u64,
+    /// 续传索引 → 客户端索引
+    index_map: HashMap<u64, u64>,
+    /// 是否已收到续传的第一个内容块
+    block_seen: bool,
+    /// 是否已收到续传的文本块
+    text_seen: bool,
+    /// 需要去重的续传文本块索引
+    dedup_index: Option<u64>,
+    dedup: TextDeduper,
+    /// 原响应 ID（OpenAI）
+    response_id: Option<String>,
+    /// 最近一个 OpenAI chunk（用于构造补发的文本 chunk）
+    template: Option<Value>,
+}
+
+impl ResumeSplicer {
+    /// 改写一个续传事件，返回应当下发的事件；遇到流内错误事件返回 `Err`
+    pub fn splice(&mut self, raw: String) -> Result<Vec<String>, String> {
+        let Some(event) = SseEvent::parse(&raw) else {
+            return Ok(vec![raw]);
+        };
+        if let Some(message) = event.error_message() {
+            return Err(message);
+        }
+        Ok(match self.format {
+            StreamFormat::OpenAiSse => self.splice_openai(raw, &event),
+            _ => self.splice_anthropic(raw, &event),
+        })
+    }
+
+    fn splice_anthropic(&mut self, raw: String, event: &SseEvent) -> Vec<String> {
+        let Some(mut data) = event.json() else {
+            return vec![raw];
+        };
+        let kind = event.kind(Some(&data));
+        let name = event.event.clone().unwrap_or_else(|| kind.clone());
+        let index = data.get("index").and_then(Value::as_u64).unwrap_or(0);
+        let mut events = Vec::new();
+
+        match kind.as_str() {
+            "message_start" if self.started => {}
+            "ping" => {}
+            "content_block_start" => {
+                let block = BlockKind::from_type(
+                    data.pointer("/content_block/type")
+                        .and_then(Value::as_str)
+                        .unwrap_or("text"),
+                );
+                let first = !self.block_seen;
+                self.block_seen = true;
+                match self.open_block {
+                    // 续传的第一个文本块接续断流时未结束的文本块
+                    Some((open_index, BlockKind::Text)) if first && block == BlockKind::Text => {
+                        self.open_block = None;
+                        self.index_map.insert(index, open_index);
+                        self.dedup_index = Some(index);
+                    }
+                    _ => {
+                        events.extend(self.close_open_block());
+                        let mapped = self.next_index;
+                        self.next_index += 1;
+                        self.index_map.insert(index, mapped);
+                        if block == BlockKind::Text && !self.text_seen {
+                            self.dedup_index = Some(index);
+                        }
+                        data["index"] = mapped.into();
+                        events.push(anthropic_event(&name, &data));
+                    }
+                }
+                if block == BlockKind::Text {
+                    self.text_seen = true;
+                }
+            }
+            "content_block_delta" => {
+                let mapped = self.mapped_index(index);
+                data["index"] = mapped.into();
+                if self.dedup_index == Some(index) {
+                    if let Some(text) = data.pointer("/delta/text").and_then(Value::as_str) {
+                        match self.dedup.push(text) {
+                            Some(text) if !text.is_empty() => data["delta"]["text"] = text.into(),
+                            _ => return events,
+                        }
+                    }
+                }
+                events.push(anthropic_event(&name, &data));
+            }
+            "content_block_stop" => {
+                let mapped = self.mapped_index(index);
+                if self.dedup_index == Some(index) {
+                    self.dedup_index = None;
+                    let rest = self.dedup.finish();
+                    if !rest.is_empty() {
+                        events.push(anthropic_event(
+                            "content_block_delta",
+                            &json!({
+                                "type": "content_block_delta",
+                                "index": mapped,
+                                "delta": {"type": "text_delta", "text": rest}
+                            }),
+                        ));
+                    }
+                }
+                data["index"] = mapped.into();
+                events.push(anthropic_event(&name, &data));
+            }
+            "message_delta" | "message_stop" => {
+                events.extend(self.close_open_block());
+                events.push(raw);
+            }
+            _ => events.push(raw),
+        }
+        events
+    }
+
+    fn mapped_index(&self, index: u64) -> u64 {
+        self.index_map.get(&index).copied().unwrap_or(index)
+    }
+
+    /// 续传没有接续断流时未结束的内容块时，先补发它的结束事件
+    fn close_open_block(&mut self) -> Vec<String> {
+        match self.open_block.take() {
+            Some((index, _)) => vec![anthropic_event(
+                "content_block_stop",
+                &json!({"type": "content_block_stop", "index": index}),
+            )],
+            None => Vec::new(),
+        }
+    }
+
+    fn splice_openai(&mut self, raw: String, event: &SseEvent) -> Vec<String> {
+        if event.is_done() {
+            let mut events = Vec::new();
+            let rest = self.dedup.finish();
+            if !rest.is_empty() {
+                events.extend(self.text_chunk(rest));
+            }
+            events.push(raw);
+            return events;
+        }
+        let Some(mut chunk) = event.json() else {
+            return vec![raw];
+        };
+        if let Some(id) = &self.response_id {
+            chunk["id"] = id.clone().into();
+        }
+        let finish = chunk
+            .pointer("/choices/0/finish_reason")
+            .is_some_and(|v| !v.is_null());
+        let has_usage = chunk.get("usage").is_some_and(|v| !v.is_null());
+
+        if let Some(delta) = chunk
+            .pointer_mut("/choices/0/delta")
+            .and_then(Value::as_object_mut)
+        {
+            delta.remove("role");
+            let mut text = match delta.remove("content") {
+                Some(Value::String(text)) => self.dedup.push(&text).unwrap_or_default(),
+                _ => String::new(),
+            };
+            if finish {
+                text.push_str(&self.dedup.finish());
+            }
+            if !text.is_empty() {
+                delta.insert("content".to_string(), text.into());
+            }
+            if delta.is_empty() && !finish && !has_usage {
+                return Vec::new();
+            }
+        }
+
+        let spliced = format!("data: {chunk}\n\n");
+        self.template = Some(chunk);
+        vec![spliced]
+    }
+
+    /// 构造只包含文本增量的 OpenAI chunk
+    fn text_chunk(&self, text: String) -> Option<String> {
+        let mut chunk = self.template.clone()?;
+        chunk["choices"] = json!([{
+            "index": 0,
+            "delta": {"content": text},
+            "finish_reason": null
+        }]);
+        if let Some(object) = chunk.as_object_mut() {
+            object.remove("usage");
+        }
+        Some(format!("data: {chunk}\n\n"))
+    }
+}
+
+// ============================================================================
+// 续传文本去重
+// ============================================================================
+
+/// 续传文本去重
+///
+/// 续传开头可能补回预填充时去掉的结尾空白，也可能忽略预填充从头重新生成；
+/// 确定之前暂存续传文本，确定后去掉与已下发文本重复的部分。
+#[derive(Debug)]
+struct TextDeduper {
+    /// 已下发的文本
+    emitted: String,
+    /// 预填充时去掉的结尾空白
+    trimmed: String,
+    pending: String,
+    resolved: bool,
+}
+
+impl TextDeduper {
+    fn new(emitted: &str) -> Self {
+        let prefill_len = emitted.trim_end().len();
+        Self {
+            emitted: emitted.to_string(),
+            trimmed: emitted[prefill_len..].to_string(),
+            pending: String::new(),
+            resolved: emitted.is_empty(),
+        }
+    }
+
+    /// 追加续传文本，返回可以下发的部分（仍需暂存时返回 None）
+    fn push(&mut self, text: &str) -> Option<String> {
+        if self.resolved {
+            return Some(text.to_string());
+        }
+        self.pending.push_str(text);
+        let undecided = |target: &str, pending: &str| {
+            pending.len() < target.len() && target.starts_with(pending)
+        };
+        if undecided(&self.emitted, &self.pending) || undecided(&self.trimmed, &self.pending) {
+            return None;
+        }
+        self.resolved = true;
+        let pending = std::mem::take(&mut self.pending);
+        let rest = pending
+            .strip_prefix(self.emitted.as_str())
+            .or_else(|| pending.strip_prefix(self.trimmed.as_str()))
+            .unwrap_or(&pending);
+        Some(rest.to_string())
+    }
+
+    /// 续传文本结束，返回仍在暂存的部分
+    fn finish(&mut self) -> String {
+        if self.resolved {
+            return String::new();
+        }
+        self.resolved = true;
+        let pending = std::mem::take(&mut self.pending);
+        // 暂存的内容是已下发文本的开头（重新生成到一半）或补回的空白，都不需要再下发
+        if self.emitted.starts_with(&pending) || self.trimmed.starts_with(&pending) {
+            String::new()
+        } else {
+            pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anthropic(data: Value) -> String {
+        let name = data["type"].as_str().unwrap().to_string();
+        anthropic_event(&name, &data)
+    }
+
+    fn text_delta(index: u64, text: &str) -> String {
+        anthropic(json!({
+            "type": "content_block_delta",
+            "index": index,
+            "delta": {"type": "text_delta", "text": text}
+        }))
+    }
+
+    fn openai_chunk(id: &str, delta: Value, finish: Option<&str>) -> String {
+        let chunk = json!({
+            "id": id,
+            "object": "chat.completion.chunk",
+            "model": "m",
+            "choices": [{"index": 0, "delta": delta, "finish_reason": finish}]
+        });
+        format!("data: {chunk}\n\n")
+    }
+
+    /// 依次经过拼接器和记录，返回最终下发给客户端的事件
+    fn feed(
+        transcript: &mut StreamTranscript,
+        splicer: &mut ResumeSplicer,
+        raw: String,
+    ) -> Vec<SseEvent> {
+        splicer
+            .splice(raw)
+            .unwrap()
+            .into_iter()
+            .flat_map(|raw| transcript.record(raw).unwrap())
+            .filter_map(|raw| SseEvent::parse(&raw))
+            .collect()
+    }
+
+    fn texts(events: &[SseEvent]) -> String {
+        events
+            .iter()
+            .filter_map(|e| e.json())
+            .filter_map(|j| {
+                j.pointer("/delta/text")
+                    .or_else(|| j.pointer("/choices/0/delta/content"))
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_framer_handles_split_events() {
+        let mut framer = SseFramer::default();
+        assert!(framer.push(b"event: ping\r\ndata: {}").is_empty());
+        let events = framer.push("\r\n\r\ndata: 你".as_bytes());
+        assert_eq!(events, vec!["event: ping\ndata: {}\n\n".to_string()]);
+        let tail = "好\n\n".as_bytes();
+        assert!(framer.push(&tail[..2]).is_empty());
+        assert_eq!(framer.push(&tail[2..]), vec!["data: 你好\n\n".to_string()]);
+        assert_eq!(framer.finish(), None);
+    }
+
+    #[test]
+    fn test_anthropic_resume_continues_open_text_block() {
+        let mut transcript = StreamTranscript::new(StreamFormat::AnthropicSse, 1024);
+        for raw in [
+            anthropic(json!({"type": "message_start", "message": {"id": "msg_1"}})),
+            anthropic(json!({
+                "type": "content_block_start",
+                "index": 0,
+                "content_block": {"type": "text", "text": ""}
+            })),
+            text_delta(0, "Hello "),
+        ] {
+            assert_eq!(transcript.record(raw).unwrap().len(), 1);
+        }
+        assert!(transcript.can_resume());
+        assert_eq!(transcript.prefill(), "Hello");
+
+        let mut splicer = transcript.resume();
+        let mut out = Vec::new();
+        for raw in [
+            anthropic(json!({"type": "message_start", "message": {"id": "msg_2"}})),
+            anthropic(json!({
+                "type": "content_block_start",
+                "index": 0,
+                "content_block": {"type": "text", "text": ""}
+            })),
+            text_delta(0, " wor"),
+            text_delta(0, "ld"),
+            anthropic(json!({"type": "content_block_stop", "index": 0})),
+            anthropic(json!({
+                "type": "content_block_start",
+                "index": 1,
+                "content_block": {"type": "tool_use", "id": "t1", "name": "f", "input": {}}
+            })),
+            anthropic(json!({
+                "type": "content_block_delta",
+                "index": 1,
+                "delta": {"type": "input_json_delta", "partial_json": "{}"}
+            })),
+            anthropic(json!({"type": "content_block_stop", "index": 1})),
+            anthropic(json!({"type": "message_delta", "delta": {"stop_reason": "tool_use"}})),
+            anthropic(json!({"type": "message_stop"})),
+        ] {
+            out.extend(feed(&mut transcript, &mut splicer, raw));
+        }
+
+        let kinds: Vec<_> = out
+            .iter()
+            .map(|e| e.event.clone().unwrap_or_default())
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "content_block_delta",
+                "content_block_delta",
+                "content_block_stop",
+                "content_block_start",
+                "content_block_delta",
+                "content_block_stop",
+                "message_delta",
+                "message_stop"
+            ]
+        );
+        assert_eq!(texts(&out), "world");
+        assert_eq!(transcript.text(), "Hello world");
+        assert!(transcript.is_finished());
+        let indices: Vec<_> = out
+            .iter()
+            .filter_map(|e| e.json()?.get("index")?.as_u64())
+            .collect();
+        assert_eq!(indices, [0, 0, 0, 1, 1, 1]);
+    }
+
+    #[test]
+    fn test_anthropic_partial_tool_call_is_dropped_on_resume() {
+        let mut transcript = StreamTranscript::new(StreamFormat::AnthropicSse, 1024);
+        let mut sent = Vec::new();
+        for raw in [
+            anthropic(json!({"type": "message_start", "message": {}})),
+            anthropic(json!({
+                "type": "content_block_start",
+                "index": 0,
+                "content_block": {"type": "text", "text": ""}
+            })),
+            text_delta(0, "Let me check."),
+            anthropic(json!({"type": "content_block_stop", "index": 0})),
+            anthropic(json!({
+                "type": "content_block_start",
+                "index": 1,
+                "content_block": {"type": "tool_use", "id": "t1", "name": "f", "input": {}}
+            })),
+            anthropic(json!({
+                "type": "content_block_delta",
+                "index": 1,
+                "delta": {"type": "input_json_delta", "partial_json": "{\"pa"}
+            })),
+        ] {
+            sent.extend(transcript.record(raw).unwrap());
+        }
+        // 未完成的工具调用没有下发
+        assert_eq!(sent.len(), 4);
+        assert!(transcript.can_resume());
+
+        let mut splicer = transcript.resume();
+        let out = feed(
+            &mut transcript,
+            &mut splicer,
+            anthropic(json!({
+                "type": "content_block_start",
+                "index": 0,
+                "content_block": {"type": "tool_use", "id": "t2", "name": "f", "input": {}}
+            })),
+        );
+        assert!(out.is_empty());
+        let out = feed(
+            &mut transcript,
+            &mut splicer,
+            anthropic(json!({"type": "content_block_stop", "index": 0})),
+        );
+        let indices: Vec<_> = out
+            .iter()
+            .filter_map(|e| e.json()?.get("index")?.as_u64())
+            .collect();
+        assert_eq!(indices, [1, 1]);
+        assert!(!transcript.can_resume());
+    }
+
+    #[test]
+    fn test_openai_resume_strips_restarted_text_and_keeps_id() {
+        let mut transcript = StreamTranscript::new(StreamFormat::OpenAiSse, 1024);
+        for raw in [
+            openai_chunk("a", json!({"role": "assistant", "content": ""}), None),
+            openai_chunk("a", json!({"content": "The answer"}), None),
+        ] {
+            transcript.record(raw).unwrap();
+        }
+        let mut splicer = transcript.resume();
+        let mut out = Vec::new();
+        for raw in [
+            openai_chunk("b", json!({"role": "assistant", "content": ""}), None),
+            openai_chunk("b", json!({"content": "The ans"}), None),
+            openai_chunk("b", json!({"content": "wer is 42"}), None),
+            openai_chunk("b", json!({}), Some("stop")),
+            "data: [DONE]\n\n".to_string(),
+        ] {
+            out.extend(feed(&mut transcript, &mut splicer, raw));
+        }
+        assert_eq!(texts(&out), " is 42");
+        assert!(out.iter().filter_map(|e| e.json()).all(|j| j["id"] == "a"));
+        assert_eq!(transcript.text(), "The answer is 42");
+        assert!(out.last().unwrap().is_done());
+    }
+
+    #[test]
+    fn test_openai_tool_calls_are_held_until_finish() {
+        let mut transcript = StreamTranscript::new(StreamFormat::OpenAiSse, 1024);
+        let tool = json!({"tool_calls": [{"index": 0, "function": {"arguments": "{\"a\""}}]});
+        assert!(transcript
+            .record(openai_chunk("a", tool.clone(), None))
+            .unwrap()
+            .is_empty());
+        assert!(transcript.can_resume());
+        assert!(transcript
+            .record(openai_chunk("a", tool, None))
+            .unwrap()
+            .is_empty());
+        let flushed = transcript
+            .record(openai_chunk("a", json!({}), Some("tool_calls")))
+            .unwrap();
+        assert_eq!(flushed.len(), 3);
+        assert!(!transcript.can_resume());
+    }
+
+    #[test]
+    fn test_error_event_and_overflow() {
+        let mut transcript = StreamTranscript::new(StreamFormat::AnthropicSse, 4);
+        let error = "event: error\ndata: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n";
+        assert_eq!(
+            transcript.record(error.to_string()),
+            Err("Overloaded".to_string())
+        );
+        transcript.record(text_delta(0, "12345")).unwrap();
+        assert!(!transcript.can_resume());
+    }
+
+    #[test]
+    fn test_deduper_restores_trimmed_whitespace_once() {
+        let mut dedup = TextDeduper::new("Hello ");
+        assert_eq!(dedup.push(" "), Some(String::new()));
+        assert_eq!(dedup.push("world"), Some("world".to_string()));
+
+        let mut dedup = TextDeduper::new("Hello\n\n");
+        assert_eq!(dedup.push("\n"), None);
+        assert_eq!(dedup.push("\nNext"), Some("Next".to_string()));
+
+        let mut dedup = TextDeduper::new("Hello");
+        assert_eq!(dedup.push("He"), None);
+        assert_eq!(dedup.finish(), "");
+    }
+}
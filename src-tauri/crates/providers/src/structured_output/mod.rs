@@ -0,0 +1,594 @@
+//! 结构化输出（`response_format`）
+//!
+//! Chat Completions 的 `response_format` 支持 `json_object` 和 `json_schema` 两种要求，
+//! 各上游的支持情况不同，按凭证类型选择实现方式（见 [`StructuredOutputMode`]）：
+//!
+//! - OpenAI 兼容上游：原样透传 `response_format`
+//! - Antigravity（非流式）：映射为 `generationConfig.responseMimeType` / `responseSchema`
+//! - Claude API Key（非流式）：强制调用一个以 schema 为参数的工具，再把工具参数还原为消息内容
+//! - 其他上游（Kiro、Vertex、Codex 及流式的 Claude / Antigravity 等）：在 system 消息中注入 schema 说明
+//!
+//! 网关按 schema 校验非流式响应，不符合时把错误反馈给模型重试（次数受配置限制），
+//! 仍不符合则返回 `STRUCTURED_OUTPUT_INVALID`。流式响应只做请求侧的映射和说明注入，不校验输出。
+
+pub mod schema;
+
+use proxycast_core::models::openai::{
+    ChatCompletionRequest, ChatMessage, FunctionDef, MessageContent, Tool,
+};
+use proxycast_core::models::provider_pool_model::CredentialData;
+use serde_json::{json, Value};
+
+/// `json_schema` 未指定名称时使用的名称（也是工具强制模式下的工具名）
+pub const DEFAULT_SCHEMA_NAME: &str = "structured_output";
+
+/// `response_format.json_schema`
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonSchemaFormat {
+    pub name: String,
+    pub description: Option<String>,
+    pub schema: Value,
+    pub strict: bool,
+}
+
+/// 客户端要求的输出格式
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResponseFormat {
+    /// 任意 JSON 对象
+    JsonObject,
+    /// 符合 JSON Schema 的 JSON
+    JsonSchema(JsonSchemaFormat),
+}
+
+impl ResponseFormat {
+    /// 从原始请求体解析 `response_format`
+    ///
+    /// 未设置或 `type` 为 `text` 时返回 `None`。
+    pub fn from_request_body(body: &Value) -> Result<Option<Self>, String> {
+        let format = match body.get("response_format") {
+            None | Some(Value::Null) => return Ok(None),
+            Some(format) => format,
+        };
+        let format_type = format
+            .get("type")
+            .and_then(Value::as_str)
+            .ok_or_else(|| "response_format.type 缺失或不是字符串".to_string())?;
+        match format_type {
+            "text" => Ok(None),
+            "json_object" => Ok(Some(Self::JsonObject)),
+            "json_schema" => {
+                let spec = format
+                    .get("json_schema")
+                    .and_then(Value::as_object)
+                    .ok_or_else(|| "response_format.json_schema 缺失或不是对象".to_string())?;
+                let schema = match spec.get("schema") {
+                    Some(schema @ (Value::Object(_) | Value::Bool(_))) => schema.clone(),
+                    Some(_) => {
+                        return Err("response_format.json_schema.schema 必须是对象".to_string())
+                    }
+                    None => return Err("response_format.json_schema.schema 缺失".to_string()),
+                };
+                Ok(Some(Self::JsonSchema(JsonSchemaFormat {
+                    name: spec
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .filter(|name| !name.is_empty())
+                        .unwrap_or(DEFAULT_SCHEMA_NAME)
+                        .to_string(),
+                    description: spec
+                        .get("description")
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
+                    schema,
+                    strict: spec.get("strict").and_then(Value::as_bool).unwrap_or(false),
+                })))
+            }
+            other => Err(format!("不支持的 response_format.type: {other}")),
+        }
+    }
+
+    /// 转换回 OpenAI 请求中的 `response_format`
+    pub fn to_openai_value(&self) -> Value {
+        match self {
+            Self::JsonObject => json!({"type": "json_object"}),
+            Self::JsonSchema(format) => {
+                let mut spec = json!({
+                    "name": format.name,
+                    "schema": format.schema,
+                    "strict": format.strict,
+                });
+                if let Some(description) = &format.description {
+                    spec["description"] = json!(description);
+                }
+                json!({"type": "json_schema", "json_schema": spec})
+            }
+        }
+    }
+
+    /// 格式名称
+    pub fn name(&self) -> &str {
+        match self {
+            Self::JsonObject => DEFAULT_SCHEMA_NAME,
+            Self::JsonSchema(format) => &format.name,
+        }
+    }
+
+    /// 要求的 JSON Schema（`json_object` 没有 schema）
+    pub fn schema(&self) -> Option<&Value> {
+        match self {
+            Self::JsonObject => None,
+            Self::JsonSchema(format) => Some(&format.schema),
+        }
+    }
+
+    /// 校验模型输出
+    ///
+    /// 成功时返回去掉代码块和前后说明文字后的 JSON 文本，失败时返回错误列表。
+    pub fn check_output(&self, text: &str) -> Result<String, Vec<String>> {
+        let Some(json_text) = extract_json(text) else {
+            return Err(vec!["输出不是合法的 JSON".to_string()]);
+        };
+        let instance: Value = serde_json::from_str(json_text)
+            .map_err(|e| vec![format!("输出不是合法的 JSON: {e}")])?;
+        let errors = match self {
+            Self::JsonObject if !instance.is_object() => {
+                vec!["/: 应为 JSON 对象".to_string()]
+            }
+            Self::JsonObject => Vec::new(),
+            Self::JsonSchema(format) => schema::validate(&format.schema, &instance),
+        };
+        if errors.is_empty() {
+            Ok(json_text.to_string())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// 注入给不支持结构化输出的模型的说明
+    pub fn instructions(&self) -> String {
+        const ONLY_JSON: &str =
+            "Do not include any explanation, Markdown code fences or other text outside the JSON.";
+        match self {
+            Self::JsonObject => {
+                format!("Respond only with a single valid JSON object. {ONLY_JSON}")
+            }
+            Self::JsonSchema(format) => {
+                let description = format
+                    .description
+                    .as_ref()
+                    .map(|d| format!(" ({d})"))
+                    .unwrap_or_default();
+                let schema =
+                    serde_json::to_string_pretty(&format.schema).unwrap_or_else(|_| "{}".into());
+                format!(
+                    "Respond only with JSON that conforms to the JSON Schema \"{}\"{description} below. {ONLY_JSON}\n\n{schema}",
+                    format.name
+                )
+            }
+        }
+    }
+
+    /// 工具强制模式下的工具参数 schema（Anthropic 要求 `input_schema` 为 object 类型）
+    fn tool_schema(&self) -> Option<Value> {
+        match self {
+            Self::JsonObject => Some(json!({"type": "object"})),
+            Self::JsonSchema(format) => {
+                (format.schema.get("type") == Some(&json!("object"))).then(|| format.schema.clone())
+            }
+        }
+    }
+}
+
+/// 从模型输出中提取 JSON 文本
+///
+/// 依次尝试：整体、Markdown 代码块、第一个 `{`/`[` 到最后一个 `}`/`]` 之间的内容。
+pub fn extract_json(text: &str) -> Option<&str> {
+    let is_json = |s: &str| serde_json::from_str::<Value>(s).is_ok();
+    let trimmed = text.trim();
+    if is_json(trimmed) {
+        return Some(trimmed);
+    }
+
+    let mut rest = text;
+    while let Some(start) = rest.find("```") {
+        let after = &rest[start + 3..];
+        // 跳过语言标记（```json）
+        let body_start = after.find('\n').map(|i| i + 1).unwrap_or(after.len());
+        let body = &after[body_start..];
+        let Some(end) = body.find("```") else {
+            break;
+        };
+        let candidate = body[..end].trim();
+        if is_json(candidate) {
+            return Some(candidate);
+        }
+        rest = &body[end + 3..];
+    }
+
+    let mut spans: Vec<(usize, usize)> = [('{', '}'), ('[', ']')]
+        .iter()
+        .filter_map(|&(open, close)| Some((trimmed.find(open)?, trimmed.rfind(close)?)))
+        .filter(|(start, end)| start < end)
+        .collect();
+    spans.sort();
+    spans
+        .into_iter()
+        .map(|(start, end)| &trimmed[start..=end])
+        .find(|candidate| is_json(candidate))
+}
+
+/// 结构化输出的实现方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructuredOutputMode {
+    /// 上游原生支持，透传 `response_format`
+    Passthrough,
+    /// 映射为 Gemini `responseSchema`
+    GeminiSchema,
+    /// 强制调用以 schema 为参数的工具
+    ToolForcing,
+    /// 注入 schema 说明
+    Emulated,
+}
+
+impl StructuredOutputMode {
+    /// 按凭证类型和请求选择实现方式
+    ///
+    /// 请求自带工具时，Gemini（JSON 输出与函数调用互斥）和工具强制都会抢占客户端的工具，
+    /// 这两种情况退回注入说明。
+    pub fn select(
+        credential: &CredentialData,
+        request: &ChatCompletionRequest,
+        format: &ResponseFormat,
+    ) -> Self {
+        let has_tools = request
+            .tools
+            .as_ref()
+            .is_some_and(|tools| !tools.is_empty());
+        match credential {
+            CredentialData::OpenAIKey { .. } => Self::Passthrough,
+            CredentialData::AnthropicKey {
+                base_url: Some(_), ..
+            } => Self::Passthrough,
+            CredentialData::AntigravityOAuth { .. } if !request.stream && !has_tools => {
+                Self::GeminiSchema
+            }
+            CredentialData::ClaudeKey { .. }
+                if !request.stream && !has_tools && format.tool_schema().is_some() =>
+            {
+                Self::ToolForcing
+            }
+            _ => Self::Emulated,
+        }
+    }
+}
+
+/// 按实现方式改写请求
+///
+/// `Passthrough` 和 `GeminiSchema` 在发送时处理（见 [`openai_request_body`]、
+/// [`apply_to_antigravity_request`]），这里原样返回。
+pub fn prepare_request(
+    request: &ChatCompletionRequest,
+    format: &ResponseFormat,
+    mode: StructuredOutputMode,
+) -> ChatCompletionRequest {
+    let mut request = request.clone();
+    match mode {
+        StructuredOutputMode::Passthrough | StructuredOutputMode::GeminiSchema => {}
+        StructuredOutputMode::ToolForcing => {
+            let parameters = format
+                .tool_schema()
+                .unwrap_or_else(|| json!({"type": "object"}));
+            request.tools = Some(vec![Tool::Function {
+                function: FunctionDef {
+                    name: format.name().to_string(),
+                    description: Some(
+                        "Return the final answer by calling this tool with arguments that conform to its schema."
+                            .to_string(),
+                    ),
+                    parameters: Some(parameters),
+                },
+            }]);
+            request.tool_choice = Some(json!({
+                "type": "function",
+                "function": {"name": format.name()}
+            }));
+        }
+        StructuredOutputMode::Emulated => {
+            let instructions = format.instructions();
+            match request.messages.iter_mut().find(|m| m.role == "system") {
+                Some(system) => {
+                    let text = system.get_content_text();
+                    system.content = Some(MessageContent::Text(if text.is_empty() {
+                        instructions
+                    } else {
+                        format!("{text}\n\n{instructions}")
+                    }));
+                }
+                None => request
+                    .messages
+                    .insert(0, text_message("system", instructions)),
+            }
+        }
+    }
+    request
+}
+
+/// 构建带 `response_format` 的 OpenAI 请求体（`ChatCompletionRequest` 不含该字段）
+pub fn openai_request_body(request: &ChatCompletionRequest, format: &ResponseFormat) -> Value {
+    let mut body = serde_json::to_value(request).unwrap_or_else(|_| json!({}));
+    body["response_format"] = format.to_openai_value();
+    body
+}
+
+/// 在 Antigravity 请求的 `generationConfig` 中设置 JSON 输出和 schema
+pub fn apply_to_antigravity_request(body: &mut Value, format: &ResponseFormat) {
+    let config = &mut body["request"]["generationConfig"];
+    config["responseMimeType"] = json!("application/json");
+    if let Some(schema) = format.schema() {
+        config["responseSchema"] = schema::to_gemini_schema(schema);
+    }
+}
+
+/// 把工具强制模式下的工具调用还原为消息内容
+///
+/// 只处理对指定工具的调用，返回是否还原成功。
+pub fn unwrap_forced_tool_call(response: &mut Value, format: &ResponseFormat) -> bool {
+    let Some(message) = response.pointer_mut("/choices/0/message") else {
+        return false;
+    };
+    let arguments = message
+        .get("tool_calls")
+        .and_then(Value::as_array)
+        .and_then(|calls| {
+            calls
+                .iter()
+                .find(|call| call["function"]["name"] == format.name())
+        })
+        .and_then(|call| call["function"]["arguments"].as_str())
+        .map(str::to_string);
+    let Some(arguments) = arguments else {
+        return false;
+    };
+    if let Some(message) = message.as_object_mut() {
+        message.remove("tool_calls");
+        message.insert("content".to_string(), json!(arguments));
+    }
+    response["choices"][0]["finish_reason"] = json!("stop");
+    true
+}
+
+/// 读取 OpenAI 非流式响应的消息文本
+pub fn response_text(response: &Value) -> Option<&str> {
+    response.pointer("/choices/0/message/content")?.as_str()
+}
+
+/// 替换 OpenAI 非流式响应的消息文本
+pub fn set_response_text(response: &mut Value, text: String) {
+    if let Some(message) = response.pointer_mut("/choices/0/message") {
+        message["content"] = json!(text);
+    }
+}
+
+/// 响应是否包含工具调用（此时不按结构化输出校验）
+pub fn has_tool_calls(response: &Value) -> bool {
+    response
+        .pointer("/choices/0/message/tool_calls")
+        .and_then(Value::as_array)
+        .is_some_and(|calls| !calls.is_empty())
+}
+
+/// 构建修复请求：附上不符合要求的输出和校验错误，要求模型重新输出
+pub fn repair_request(
+    request: &ChatCompletionRequest,
+    previous_output: &str,
+    errors: &[String],
+) -> ChatCompletionRequest {
+    let mut request = request.clone();
+    request
+        .messages
+        .push(text_message("assistant", previous_output.to_string()));
+    request.messages.push(text_message(
+        "user",
+        format!(
+            "Your previous response did not match the required JSON format:\n- {}\n\nRespond again with only the corrected JSON.",
+            errors.join("\n- ")
+        ),
+    ));
+    request
+}
+
+fn text_message(role: &str, text: String) -> ChatMessage {
+    ChatMessage {
+        role: role.to_string(),
+        content: Some(MessageContent::Text(text)),
+        tool_calls: None,
+        tool_call_id: None,
+        reasoning_content: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn person_format() -> ResponseFormat {
+        ResponseFormat::from_request_body(&json!({
+            "response_format": {
+                "type": "json_schema",
+                "json_schema": {
+                    "name": "person",
+                    "strict": true,
+                    "schema": {
+                        "type": "object",
+                        "properties": {"name": {"type": "string"}, "age": {"type": "integer"}},
+                        "required": ["name", "age"],
+                        "additionalProperties": false
+                    }
+                }
+            }
+        }))
+        .unwrap()
+        .unwrap()
+    }
+
+    fn request(stream: bool) -> ChatCompletionRequest {
+        serde_json::from_value(json!({
+            "model": "m",
+            "stream": stream,
+            "messages": [{"role": "user", "content": "hi"}]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_response_format() {
+        assert_eq!(ResponseFormat::from_request_body(&json!({})), Ok(None));
+        assert_eq!(
+            ResponseFormat::from_request_body(&json!({"response_format": {"type": "text"}})),
+            Ok(None)
+        );
+        assert_eq!(
+            ResponseFormat::from_request_body(&json!({"response_format": {"type": "json_object"}})),
+            Ok(Some(ResponseFormat::JsonObject))
+        );
+        assert!(
+            ResponseFormat::from_request_body(&json!({"response_format": {"type": "xml"}}))
+                .is_err()
+        );
+        assert!(ResponseFormat::from_request_body(
+            &json!({"response_format": {"type": "json_schema", "json_schema": {"name": "x"}}})
+        )
+        .is_err());
+
+        let format = person_format();
+        assert_eq!(format.name(), "person");
+        assert_eq!(
+            format.to_openai_value()["json_schema"]["schema"]["required"],
+            json!(["name", "age"])
+        );
+    }
+
+    #[test]
+    fn test_extract_and_check_output() {
+        assert_eq!(extract_json(" {\"a\":1} "), Some("{\"a\":1}"));
+        assert_eq!(
+            extract_json("Sure:\n```json\n{\"a\": [1, 2]}\n```\nDone."),
+            Some("{\"a\": [1, 2]}")
+        );
+        assert_eq!(
+            extract_json("Result: {\"a\": {\"b\": 1}} hope it helps"),
+            Some("{\"a\": {\"b\": 1}}")
+        );
+        assert_eq!(extract_json("no json here"), None);
+
+        let format = person_format();
+        assert_eq!(
+            format.check_output("```json\n{\"name\": \"Ann\", \"age\": 30}\n```"),
+            Ok("{\"name\": \"Ann\", \"age\": 30}".to_string())
+        );
+        let errors = format
+            .check_output("{\"name\": \"Ann\", \"age\": \"30\"}")
+            .unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("/age: "));
+        assert!(ResponseFormat::JsonObject.check_output("[1]").is_err());
+        assert!(ResponseFormat::JsonObject.check_output("oops").is_err());
+    }
+
+    #[test]
+    fn test_select_mode() {
+        let format = person_format();
+        let claude = CredentialData::ClaudeKey {
+            api_key: "k".to_string(),
+            base_url: None,
+        };
+        assert_eq!(
+            StructuredOutputMode::select(&claude, &request(false), &format),
+            StructuredOutputMode::ToolForcing
+        );
+        assert_eq!(
+            StructuredOutputMode::select(&claude, &request(true), &format),
+            StructuredOutputMode::Emulated
+        );
+        // 根类型不是 object 的 schema 不能作为工具参数
+        let array_format = ResponseFormat::JsonSchema(JsonSchemaFormat {
+            name: "list".to_string(),
+            description: None,
+            schema: json!({"type": "array"}),
+            strict: false,
+        });
+        assert_eq!(
+            StructuredOutputMode::select(&claude, &request(false), &array_format),
+            StructuredOutputMode::Emulated
+        );
+        let openai = CredentialData::OpenAIKey {
+            api_key: "k".to_string(),
+            base_url: None,
+        };
+        assert_eq!(
+            StructuredOutputMode::select(&openai, &request(true), &format),
+            StructuredOutputMode::Passthrough
+        );
+    }
+
+    #[test]
+    fn test_prepare_request() {
+        let format = person_format();
+        let forced = prepare_request(&request(false), &format, StructuredOutputMode::ToolForcing);
+        assert_eq!(forced.tools.as_ref().map(Vec::len), Some(1));
+        assert_eq!(forced.tool_choice.unwrap()["function"]["name"], "person");
+
+        let emulated = prepare_request(&request(false), &format, StructuredOutputMode::Emulated);
+        assert_eq!(emulated.messages.len(), 2);
+        assert_eq!(emulated.messages[0].role, "system");
+        assert!(emulated.messages[0]
+            .get_content_text()
+            .contains("\"additionalProperties\": false"));
+
+        let body = openai_request_body(&request(false), &format);
+        assert_eq!(body["response_format"]["type"], "json_schema");
+
+        let mut antigravity = json!({"request": {"contents": []}});
+        apply_to_antigravity_request(&mut antigravity, &format);
+        let config = &antigravity["request"]["generationConfig"];
+        assert_eq!(config["responseMimeType"], "application/json");
+        assert_eq!(config["responseSchema"]["required"], json!(["name", "age"]));
+        assert!(config["responseSchema"]
+            .get("additionalProperties")
+            .is_none());
+    }
+
+    #[test]
+    fn test_unwrap_forced_tool_call_and_repair() {
+        let format = person_format();
+        let mut response = json!({
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": "",
+                    "tool_calls": [{
+                        "id": "toolu_1",
+                        "type": "function",
+                        "function": {"name": "person", "arguments": "{\"name\":\"Ann\",\"age\":30}"}
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }]
+        });
+        assert!(unwrap_forced_tool_call(&mut response, &format));
+        assert!(!has_tool_calls(&response));
+        assert_eq!(
+            response_text(&response),
+            Some("{\"name\":\"Ann\",\"age\":30}")
+        );
+        assert_eq!(response["choices"][0]["finish_reason"], "stop");
+
+        let repaired = repair_request(&request(false), "{}", &["/name: 缺少必需字段".into()]);
+        assert_eq!(repaired.messages.len(), 3);
+        assert_eq!(repaired.messages[1].role, "assistant");
+        assert!(repaired.messages[2]
+            .get_content_text()
+            .contains("/name: 缺少必需字段"));
+    }
+}
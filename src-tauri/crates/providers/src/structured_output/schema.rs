@@ -0,0 +1,580 @@
+//! JSON Schema 校验与转换
+//!
+//! 只实现结构化输出常用的关键字（Draft 2020-12 / OpenAI Structured Outputs 子集）：
+//! `type`、`enum`、`const`、`properties`、`required`、`additionalProperties`、`items`、
+//! `prefixItems`、长度/数量/数值范围、`pattern`、`anyOf`/`oneOf`/`allOf`/`not`、
+//! 本地 `$ref`（`#/$defs/...`、`#/definitions/...`）以及 OpenAPI 的 `nullable`。
+//! 未识别的关键字忽略，不视为错误。
+
+use regex::Regex;
+use serde_json::{Map, Value};
+
+/// 单次校验最多报告的错误数
+const MAX_ERRORS: usize = 20;
+
+/// `$ref` 展开的最大深度（防止递归 Schema 死循环）
+const MAX_DEPTH: usize = 64;
+
+/// 按 JSON Schema 校验实例，返回错误列表（为空表示通过）
+///
+/// 错误信息形如 `/items/0/name: 缺少必需字段`，路径为 JSON Pointer。
+pub fn validate(schema: &Value, instance: &Value) -> Vec<String> {
+    let mut validator = Validator {
+        root: schema,
+        errors: Vec::new(),
+    };
+    validator.check(schema, instance, "", 0);
+    validator.errors
+}
+
+struct Validator<'a> {
+    root: &'a Value,
+    errors: Vec<String>,
+}
+
+impl<'a> Validator<'a> {
+    fn error(&mut self, path: &str, message: impl Into<String>) {
+        if self.errors.len() < MAX_ERRORS {
+            let path = if path.is_empty() { "/" } else { path };
+            self.errors.push(format!("{path}: {}", message.into()));
+        }
+    }
+
+    /// 在不影响当前错误列表的情况下校验子 Schema
+    fn probe(&self, schema: &'a Value, instance: &Value, path: &str, depth: usize) -> Vec<String> {
+        let mut nested = Validator {
+            root: self.root,
+            errors: Vec::new(),
+        };
+        nested.check(schema, instance, path, depth);
+        nested.errors
+    }
+
+    fn check(&mut self, schema: &'a Value, instance: &Value, path: &str, depth: usize) {
+        if depth > MAX_DEPTH {
+            self.error(path, "Schema 嵌套过深");
+            return;
+        }
+        let schema = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => {
+                self.error(path, "Schema 不允许任何值");
+                return;
+            }
+            Value::Object(schema) => schema,
+            _ => return,
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            match resolve_ref(self.root, reference) {
+                Some(target) => self.check(target, instance, path, depth + 1),
+                None => self.error(path, format!("无法解析 $ref: {reference}")),
+            }
+        }
+
+        if instance.is_null() && schema.get("nullable").and_then(Value::as_bool) == Some(true) {
+            return;
+        }
+
+        if let Some(expected) = schema.get("type") {
+            let allowed: Vec<&str> = match expected {
+                Value::String(t) => vec![t.as_str()],
+                Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            if !allowed.is_empty() && !allowed.iter().any(|t| type_matches(t, instance)) {
+                self.error(
+                    path,
+                    format!(
+                        "类型应为 {}，实际为 {}",
+                        allowed.join(" | "),
+                        type_name(instance)
+                    ),
+                );
+                return;
+            }
+        }
+
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            if !values.iter().any(|v| json_eq(v, instance)) {
+                self.error(
+                    path,
+                    format!("取值应为 {} 之一", Value::Array(values.clone())),
+                );
+            }
+        }
+        if let Some(expected) = schema.get("const") {
+            if !json_eq(expected, instance) {
+                self.error(path, format!("取值应为 {expected}"));
+            }
+        }
+
+        match instance {
+            Value::Object(object) => self.check_object(schema, object, path, depth),
+            Value::Array(items) => self.check_array(schema, items, path, depth),
+            Value::String(text) => self.check_string(schema, text, path),
+            Value::Number(_) => self.check_number(schema, instance, path),
+            _ => {}
+        }
+
+        if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+            for sub in all {
+                self.check(sub, instance, path, depth + 1);
+            }
+        }
+        if let Some(any) = schema.get("anyOf").and_then(Value::as_array) {
+            let branches: Vec<Vec<String>> = any
+                .iter()
+                .map(|sub| self.probe(sub, instance, path, depth + 1))
+                .collect();
+            if !branches.iter().any(Vec::is_empty) {
+                self.report_best_branch(path, "anyOf", branches);
+            }
+        }
+        if let Some(one) = schema.get("oneOf").and_then(Value::as_array) {
+            let branches: Vec<Vec<String>> = one
+                .iter()
+                .map(|sub| self.probe(sub, instance, path, depth + 1))
+                .collect();
+            match branches.iter().filter(|errors| errors.is_empty()).count() {
+                1 => {}
+                0 => self.report_best_branch(path, "oneOf", branches),
+                n => self.error(path, format!("同时匹配 oneOf 的 {n} 个分支")),
+            }
+        }
+        if let Some(not) = schema.get("not") {
+            if self.probe(not, instance, path, depth + 1).is_empty() {
+                self.error(path, "不应匹配 not 中的 Schema");
+            }
+        }
+    }
+
+    /// 所有分支都不匹配时，报告错误最少的分支（通常最接近模型的意图）
+    fn report_best_branch(&mut self, path: &str, keyword: &str, branches: Vec<Vec<String>>) {
+        match branches.into_iter().min_by_key(Vec::len) {
+            Some(best) if !best.is_empty() => {
+                for error in best {
+                    if self.errors.len() < MAX_ERRORS {
+                        self.errors.push(error);
+                    }
+                }
+            }
+            _ => self.error(path, format!("不匹配 {keyword} 的任何分支")),
+        }
+    }
+
+    fn check_object(
+        &mut self,
+        schema: &'a Map<String, Value>,
+        object: &Map<String, Value>,
+        path: &str,
+        depth: usize,
+    ) {
+        let properties = schema.get("properties").and_then(Value::as_object);
+
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    self.error(&child_path(path, name), "缺少必需字段");
+                }
+            }
+        }
+
+        for (name, value) in object {
+            let child = child_path(path, name);
+            match properties.and_then(|p| p.get(name)) {
+                Some(sub) => self.check(sub, value, &child, depth + 1),
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => self.error(&child, "不允许的字段"),
+                    Some(sub @ Value::Object(_)) => self.check(sub, value, &child, depth + 1),
+                    _ => {}
+                },
+            }
+        }
+
+        if let Some(min) = schema.get("minProperties").and_then(Value::as_u64) {
+            if (object.len() as u64) < min {
+                self.error(path, format!("字段数应不少于 {min}"));
+            }
+        }
+        if let Some(max) = schema.get("maxProperties").and_then(Value::as_u64) {
+            if (object.len() as u64) > max {
+                self.error(path, format!("字段数应不多于 {max}"));
+            }
+        }
+    }
+
+    fn check_array(
+        &mut self,
+        schema: &'a Map<String, Value>,
+        items: &[Value],
+        path: &str,
+        depth: usize,
+    ) {
+        let prefix = schema
+            .get("prefixItems")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        for (index, item) in items.iter().enumerate() {
+            let child = child_path(path, &index.to_string());
+            if let Some(sub) = prefix.get(index) {
+                self.check(sub, item, &child, depth + 1);
+            } else if let Some(sub) = schema.get("items") {
+                self.check(sub, item, &child, depth + 1);
+            }
+        }
+
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if (items.len() as u64) < min {
+                self.error(path, format!("元素数应不少于 {min}"));
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if (items.len() as u64) > max {
+                self.error(path, format!("元素数应不多于 {max}"));
+            }
+        }
+        if schema.get("uniqueItems").and_then(Value::as_bool) == Some(true) {
+            let duplicated = items
+                .iter()
+                .enumerate()
+                .any(|(i, a)| items[..i].iter().any(|b| json_eq(a, b)));
+            if duplicated {
+                self.error(path, "元素不能重复");
+            }
+        }
+    }
+
+    fn check_string(&mut self, schema: &Map<String, Value>, text: &str, path: &str) {
+        let length = text.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+            if length < min {
+                self.error(path, format!("长度应不少于 {min}"));
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+            if length > max {
+                self.error(path, format!("长度应不超过 {max}"));
+            }
+        }
+        if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+            // 无法编译的正则不作为模型输出的错误
+            if let Ok(re) = Regex::new(pattern) {
+                if !re.is_match(text) {
+                    self.error(path, format!("不匹配正则 {pattern}"));
+                }
+            }
+        }
+    }
+
+    fn check_number(&mut self, schema: &Map<String, Value>, instance: &Value, path: &str) {
+        let Some(value) = instance.as_f64() else {
+            return;
+        };
+        let bound = |key: &str| schema.get(key).and_then(Value::as_f64);
+        if let Some(min) = bound("minimum") {
+            if value < min {
+                self.error(path, format!("应不小于 {min}"));
+            }
+        }
+        if let Some(max) = bound("maximum") {
+            if value > max {
+                self.error(path, format!("应不大于 {max}"));
+            }
+        }
+        if let Some(min) = bound("exclusiveMinimum") {
+            if value <= min {
+                self.error(path, format!("应大于 {min}"));
+            }
+        }
+        if let Some(max) = bound("exclusiveMaximum") {
+            if value >= max {
+                self.error(path, format!("应小于 {max}"));
+            }
+        }
+        if let Some(step) = bound("multipleOf").filter(|s| *s > 0.0) {
+            let quotient = value / step;
+            if (quotient - quotient.round()).abs() > 1e-9 {
+                self.error(path, format!("应为 {step} 的倍数"));
+            }
+        }
+    }
+}
+
+fn type_matches(expected: &str, instance: &Value) -> bool {
+    match expected {
+        "object" => instance.is_object(),
+        "array" => instance.is_array(),
+        "string" => instance.is_string(),
+        "boolean" => instance.is_boolean(),
+        "null" => instance.is_null(),
+        "number" => instance.is_number(),
+        "integer" => {
+            instance.is_i64()
+                || instance.is_u64()
+                || instance.as_f64().is_some_and(|f| f.fract() == 0.0)
+        }
+        _ => true,
+    }
+}
+
+fn type_name(instance: &Value) -> &'static str {
+    match instance {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// 比较两个 JSON 值（数字按数值比较，`1` 与 `1.0` 相等）
+fn json_eq(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64() == y.as_f64(),
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(a, b)| json_eq(a, b))
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(k, v)| y.get(k).is_some_and(|other| json_eq(v, other)))
+        }
+        _ => a == b,
+    }
+}
+
+fn child_path(path: &str, segment: &str) -> String {
+    format!("{path}/{}", segment.replace('~', "~0").replace('/', "~1"))
+}
+
+/// 解析本地 `$ref`（仅支持 `#` 开头的 JSON Pointer）
+fn resolve_ref<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    let pointer = reference.strip_prefix('#')?;
+    if pointer.is_empty() {
+        return Some(root);
+    }
+    root.pointer(pointer)
+}
+
+/// Gemini `responseSchema` 支持的关键字
+const GEMINI_SCHEMA_KEYS: &[&str] = &[
+    "type",
+    "format",
+    "description",
+    "nullable",
+    "enum",
+    "properties",
+    "required",
+    "items",
+    "minItems",
+    "maxItems",
+    "minimum",
+    "maximum",
+    "anyOf",
+    "propertyOrdering",
+];
+
+/// 把 JSON Schema 转换为 Gemini `responseSchema`（OpenAPI 3.0 子集）
+///
+/// 展开本地 `$ref`，`const` 转为单值 `enum`，`type: [T, "null"]` 转为 `nullable`，
+/// `oneOf` 按 `anyOf` 处理，并去掉 Gemini 不支持的关键字。
+pub fn to_gemini_schema(schema: &Value) -> Value {
+    convert_gemini(schema, schema, 0)
+}
+
+fn convert_gemini(root: &Value, schema: &Value, depth: usize) -> Value {
+    let Some(object) = schema.as_object() else {
+        return Value::Object(Map::new());
+    };
+    if let Some(target) = object
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|r| resolve_ref(root, r))
+    {
+        if depth < MAX_DEPTH {
+            return convert_gemini(root, target, depth + 1);
+        }
+        // 递归 Schema 超过深度后退化为不受约束的对象
+        return serde_json::json!({"type": "object"});
+    }
+
+    let mut result = Map::new();
+    for (key, value) in object {
+        match key.as_str() {
+            "type" => match value {
+                Value::Array(types) => {
+                    let non_null: Vec<&Value> = types.iter().filter(|t| *t != "null").collect();
+                    if non_null.len() < types.len() {
+                        result.insert("nullable".to_string(), Value::Bool(true));
+                    }
+                    if let Some(first) = non_null.first() {
+                        result.insert("type".to_string(), (*first).clone());
+                    }
+                }
+                other => {
+                    result.insert("type".to_string(), other.clone());
+                }
+            },
+            "const" => {
+                result.insert("enum".to_string(), Value::Array(vec![value.clone()]));
+            }
+            "properties" => {
+                if let Some(properties) = value.as_object() {
+                    let converted: Map<String, Value> = properties
+                        .iter()
+                        .map(|(name, sub)| (name.clone(), convert_gemini(root, sub, depth + 1)))
+                        .collect();
+                    result.insert("properties".to_string(), Value::Object(converted));
+                }
+            }
+            "items" => {
+                result.insert("items".to_string(), convert_gemini(root, value, depth + 1));
+            }
+            "anyOf" | "oneOf" => {
+                if let Some(branches) = value.as_array() {
+                    let converted = branches
+                        .iter()
+                        .map(|sub| convert_gemini(root, sub, depth + 1))
+                        .collect();
+                    result.insert("anyOf".to_string(), Value::Array(converted));
+                }
+            }
+            key if GEMINI_SCHEMA_KEYS.contains(&key) => {
+                result.insert(key.to_string(), value.clone());
+            }
+            _ => {}
+        }
+    }
+    Value::Object(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn person_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "minLength": 1},
+                "age": {"type": "integer", "minimum": 0},
+                "tags": {"type": "array", "items": {"type": "string"}, "maxItems": 2},
+                "address": {"$ref": "#/$defs/address"},
+                "role": {"enum": ["admin", "user"]},
+                "nickname": {"type": ["string", "null"]}
+            },
+            "required": ["name", "age"],
+            "additionalProperties": false,
+            "$defs": {
+                "address": {
+                    "type": "object",
+                    "properties": {"city": {"type": "string"}},
+                    "required": ["city"]
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_valid_instance_passes() {
+        let instance = json!({
+            "name": "Ada",
+            "age": 36,
+            "tags": ["math"],
+            "address": {"city": "London"},
+            "role": "admin",
+            "nickname": null
+        });
+        assert!(validate(&person_schema(), &instance).is_empty());
+        // 整数值的浮点数视为 integer
+        assert!(validate(&json!({"type": "integer"}), &json!(3.0)).is_empty());
+    }
+
+    #[test]
+    fn test_reports_errors_with_paths() {
+        let instance = json!({
+            "name": "",
+            "age": -1.5,
+            "tags": ["a", "b", 3],
+            "address": {},
+            "role": "root",
+            "extra": true
+        });
+        let errors = validate(&person_schema(), &instance);
+        let expected = [
+            "/name: 长度应不少于 1",
+            "/age: 类型应为 integer，实际为 number",
+            "/tags/2: 类型应为 string，实际为 integer",
+            "/tags: 元素数应不多于 2",
+            "/address/city: 缺少必需字段",
+            "/role: 取值应为",
+            "/extra: 不允许的字段",
+        ];
+        for message in expected {
+            assert!(
+                errors.iter().any(|e| e.starts_with(message)),
+                "缺少错误 {message:?}: {errors:?}"
+            );
+        }
+        assert_eq!(errors.len(), expected.len());
+    }
+
+    #[test]
+    fn test_combinators_and_nullable() {
+        let schema = json!({
+            "anyOf": [
+                {"type": "object", "properties": {"kind": {"const": "circle"}, "r": {"type": "number"}}, "required": ["kind", "r"]},
+                {"type": "object", "properties": {"kind": {"const": "square"}, "side": {"type": "number"}}, "required": ["kind", "side"]}
+            ]
+        });
+        assert!(validate(&schema, &json!({"kind": "square", "side": 2})).is_empty());
+        let errors = validate(&schema, &json!({"kind": "circle"}));
+        assert_eq!(errors, vec!["/r: 缺少必需字段"]);
+
+        let one_of = json!({"oneOf": [{"type": "integer"}, {"type": "number"}]});
+        assert!(validate(&one_of, &json!(1.5)).is_empty());
+        assert!(validate(&one_of, &json!(1))[0].contains("oneOf"));
+
+        let nullable = json!({"type": "string", "nullable": true, "pattern": "^[a-z]+$"});
+        assert!(validate(&nullable, &Value::Null).is_empty());
+        assert!(validate(&nullable, &json!("ABC"))[0].contains("正则"));
+        assert!(validate(&json!(false), &json!(1))[0].contains("不允许"));
+    }
+
+    #[test]
+    fn test_to_gemini_schema() {
+        let converted = to_gemini_schema(&person_schema());
+        assert_eq!(
+            converted,
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "age": {"type": "integer", "minimum": 0},
+                    "tags": {"type": "array", "items": {"type": "string"}, "maxItems": 2},
+                    "address": {
+                        "type": "object",
+                        "properties": {"city": {"type": "string"}},
+                        "required": ["city"]
+                    },
+                    "role": {"enum": ["admin", "user"]},
+                    "nickname": {"type": "string", "nullable": true}
+                },
+                "required": ["name", "age"]
+            })
+        );
+
+        // 递归 Schema 不会无限展开
+        let recursive = json!({
+            "type": "object",
+            "properties": {"child": {"$ref": "#"}}
+        });
+        let converted = to_gemini_schema(&recursive);
+        assert_eq!(converted["properties"]["child"]["type"], "object");
+    }
+}
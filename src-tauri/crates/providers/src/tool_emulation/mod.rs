@@ -0,0 +1,408 @@
+//! 工具调用模拟
+//!
+//! 部分上游（或部分模型）不支持原生 function calling。对配置规则匹配的凭证和模型，
+//! 网关把工具定义渲染进 system 提示词，让模型按 `<tool_call>` 文本协议输出调用
+//! （见 [`parser`]），再把输出解析为标准的 `tool_calls` 返回给客户端：
+//!
+//! - 请求侧：去掉 `tools` / `tool_choice`，历史中的工具调用和工具结果改写为文本
+//! - 非流式响应：见 [`rewrite_response`]
+//! - 流式响应：见 [`stream::EmulatedToolStream`]
+//!
+//! Anthropic 格式的请求先转换为 OpenAI 格式，因此两种前端共用同一套改写。
+
+pub mod parser;
+pub mod stream;
+
+pub use parser::{parse_output, EmulatedToolCall, ParsedSegment, ToolCallParser};
+pub use stream::EmulatedToolStream;
+
+use parser::{TOOL_CALL_CLOSE, TOOL_CALL_OPEN};
+use proxycast_core::config::ToolEmulationSettings;
+use proxycast_core::models::injection_types::pattern_matches;
+use proxycast_core::models::openai::{ChatCompletionRequest, ChatMessage, MessageContent, Tool};
+use proxycast_core::models::provider_pool_model::ProviderCredential;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// 请求是否需要模拟工具调用
+///
+/// 需要功能开启、请求声明了函数工具，且有规则同时匹配凭证和模型。
+pub fn should_emulate(
+    settings: &ToolEmulationSettings,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+) -> bool {
+    if !settings.enabled || tool_names(request).is_empty() {
+        return false;
+    }
+    let provider_type = credential.provider_type.to_string();
+    settings.rules.iter().any(|rule| {
+        let provider_matches = rule.providers.is_empty()
+            || rule.providers.iter().any(|p| {
+                p.eq_ignore_ascii_case(&provider_type)
+                    || p.eq_ignore_ascii_case(&credential.uuid)
+                    || credential
+                        .name
+                        .as_deref()
+                        .is_some_and(|name| p.eq_ignore_ascii_case(name))
+            });
+        let model_matches = rule.models.is_empty()
+            || rule
+                .models
+                .iter()
+                .any(|pattern| pattern_matches(pattern, &request.model));
+        provider_matches && model_matches
+    })
+}
+
+/// 请求中声明的函数工具名称
+pub fn tool_names(request: &ChatCompletionRequest) -> Vec<String> {
+    request
+        .tools
+        .iter()
+        .flatten()
+        .filter_map(|tool| match tool {
+            Tool::Function { function } => Some(function.name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 改写请求：工具定义注入 system 提示词，历史中的工具调用和结果改写为文本
+pub fn prepare_request(request: &ChatCompletionRequest) -> ChatCompletionRequest {
+    let mut prepared = request.clone();
+    let prompt = render_tools_prompt(request);
+    prepared.tools = None;
+    prepared.tool_choice = None;
+
+    // tool 消息只带 tool_call_id，从之前的 assistant 消息中查找工具名
+    let mut call_names = HashMap::new();
+    let mut messages: Vec<ChatMessage> = Vec::with_capacity(request.messages.len() + 1);
+    for message in &request.messages {
+        match message.role.as_str() {
+            "assistant" if message.tool_calls.is_some() => {
+                let mut text = message.get_content_text();
+                for call in message.tool_calls.iter().flatten() {
+                    call_names.insert(call.id.clone(), call.function.name.clone());
+                    let arguments = serde_json::from_str::<Value>(&call.function.arguments)
+                        .unwrap_or_else(|_| json!({}));
+                    let block = json!({"name": call.function.name, "arguments": arguments});
+                    if !text.is_empty() {
+                        text.push('\n');
+                    }
+                    text.push_str(&format!("{TOOL_CALL_OPEN}\n{block}\n{TOOL_CALL_CLOSE}"));
+                }
+                messages.push(text_message("assistant", text));
+            }
+            "tool" => {
+                let id = message.tool_call_id.clone().unwrap_or_default();
+                let name = call_names.get(&id).map(String::as_str).unwrap_or_default();
+                let result = format!(
+                    "<tool_result name=\"{name}\" id=\"{id}\">\n{}\n</tool_result>",
+                    message.get_content_text()
+                );
+                // 连续的工具结果合并为一条 user 消息
+                match messages.last_mut() {
+                    Some(last) if last.role == "user" && last.tool_call_id.is_some() => {
+                        let text = last.get_content_text();
+                        last.content = Some(MessageContent::Text(format!("{text}\n{result}")));
+                    }
+                    _ => {
+                        let mut user = text_message("user", result);
+                        // 仅作合并标记，发送前清除
+                        user.tool_call_id = Some(id);
+                        messages.push(user);
+                    }
+                }
+            }
+            _ => messages.push(message.clone()),
+        }
+    }
+    for message in &mut messages {
+        if message.role == "user" {
+            message.tool_call_id = None;
+        }
+    }
+
+    if let Some(prompt) = prompt {
+        match messages.iter_mut().find(|m| m.role == "system") {
+            Some(system) => {
+                let text = system.get_content_text();
+                system.content = Some(MessageContent::Text(if text.is_empty() {
+                    prompt
+                } else {
+                    format!("{text}\n\n{prompt}")
+                }));
+            }
+            None => messages.insert(0, text_message("system", prompt)),
+        }
+    }
+    prepared.messages = messages;
+    prepared
+}
+
+/// 渲染工具说明提示词，`tool_choice` 为 `none` 时返回 `None`
+pub fn render_tools_prompt(request: &ChatCompletionRequest) -> Option<String> {
+    // OpenAI 为字符串或 {"type": "function", ...}，Anthropic 为 {"type": "auto" | "any" | "none" | "tool"}
+    let choice = request.tool_choice.as_ref();
+    let choice_type = choice.and_then(|c| c.as_str().or_else(|| c.get("type")?.as_str()));
+    if choice_type == Some("none") {
+        return None;
+    }
+    let tools: Vec<Value> = request
+        .tools
+        .iter()
+        .flatten()
+        .filter_map(|tool| match tool {
+            Tool::Function { function } => Some(json!({
+                "name": function.name,
+                "description": function.description.clone().unwrap_or_default(),
+                "parameters": function.parameters.clone().unwrap_or_else(|| json!({"type": "object"})),
+            })),
+            _ => None,
+        })
+        .collect();
+    if tools.is_empty() {
+        return None;
+    }
+
+    let mut prompt = String::from(
+        "You have access to the following tools. Each tool is described as JSON with its name, description and JSON Schema parameters:\n\n",
+    );
+    for tool in &tools {
+        prompt.push_str(&tool.to_string());
+        prompt.push('\n');
+    }
+    prompt.push_str(&format!(
+        "\nTo call a tool, output a block in exactly this format, with a single JSON object whose arguments conform to the tool's parameters:\n\n{TOOL_CALL_OPEN}\n{{\"name\": \"<tool name>\", \"arguments\": {{...}}}}\n{TOOL_CALL_CLOSE}\n\n\
+You may output several blocks to call several tools. After calling tools, stop and wait: the results will be sent back as <tool_result> blocks. \
+Do not invent tool results, and do not use this format for anything other than calling a tool."
+    ));
+    let forced = match choice_type {
+        Some("required" | "any") => {
+            Some("You must call at least one tool in your response.".to_string())
+        }
+        Some("function" | "tool") => choice
+            .and_then(|c| c.pointer("/function/name").or_else(|| c.get("name")))
+            .and_then(Value::as_str)
+            .map(|name| format!("You must call the `{name}` tool in your response.")),
+        _ => None,
+    };
+    if let Some(forced) = forced {
+        prompt.push_str("\n\n");
+        prompt.push_str(&forced);
+    }
+    Some(prompt)
+}
+
+/// 生成工具调用 ID
+pub fn new_call_id() -> String {
+    format!("call_{}", uuid::Uuid::new_v4().simple())
+}
+
+/// 改写 OpenAI 非流式响应：把消息文本中的工具调用块解析为 `tool_calls`
+///
+/// 返回解析出的工具调用数量；没有调用时只清理文本。
+pub fn rewrite_response(response: &mut Value, tool_names: &[String]) -> usize {
+    let Some(message) = response.pointer_mut("/choices/0/message") else {
+        return 0;
+    };
+    let Some(text) = message.get("content").and_then(Value::as_str) else {
+        return 0;
+    };
+    let (content, calls) = parse_output(text, tool_names);
+    if calls.is_empty() {
+        return 0;
+    }
+    message["content"] = if content.is_empty() {
+        Value::Null
+    } else {
+        json!(content)
+    };
+    message["tool_calls"] = calls
+        .iter()
+        .map(|call| {
+            json!({
+                "id": new_call_id(),
+                "type": "function",
+                "function": {"name": call.name, "arguments": call.arguments}
+            })
+        })
+        .collect();
+    response["choices"][0]["finish_reason"] = json!("tool_calls");
+    calls.len()
+}
+
+fn text_message(role: &str, text: String) -> ChatMessage {
+    ChatMessage {
+        role: role.to_string(),
+        content: Some(MessageContent::Text(text)),
+        tool_calls: None,
+        tool_call_id: None,
+        reasoning_content: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
+    use proxycast_core::config::ToolEmulationRule;
+    use proxycast_core::models::anthropic::AnthropicMessagesRequest;
+    use proxycast_core::models::provider_pool_model::{CredentialData, PoolProviderType};
+
+    fn tools() -> Value {
+        json!([{
+            "type": "function",
+            "function": {
+                "name": "get_weather",
+                "description": "Get the weather",
+                "parameters": {"type": "object", "properties": {"city": {"type": "string"}}}
+            }
+        }])
+    }
+
+    fn credential() -> ProviderCredential {
+        let mut credential = ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "sk-test".to_string(),
+                base_url: Some("http://localhost:8080/v1".to_string()),
+            },
+        );
+        credential.name = Some("local-llama".to_string());
+        credential
+    }
+
+    #[test]
+    fn test_should_emulate_matches_rules() {
+        let request: ChatCompletionRequest = serde_json::from_value(json!({
+            "model": "llama-3-8b",
+            "messages": [{"role": "user", "content": "hi"}],
+            "tools": tools()
+        }))
+        .unwrap();
+        let mut settings = ToolEmulationSettings {
+            enabled: true,
+            rules: vec![ToolEmulationRule {
+                providers: vec!["Local-Llama".to_string()],
+                models: vec!["llama-*".to_string()],
+            }],
+        };
+        assert!(should_emulate(&settings, &credential(), &request));
+
+        settings.rules[0].models = vec!["gpt-*".to_string()];
+        assert!(!should_emulate(&settings, &credential(), &request));
+
+        settings.rules[0].models.clear();
+        settings.enabled = false;
+        assert!(!should_emulate(&settings, &credential(), &request));
+    }
+
+    #[test]
+    fn test_openai_conformance_non_stream() {
+        let request: ChatCompletionRequest = serde_json::from_value(json!({
+            "model": "m",
+            "messages": [
+                {"role": "system", "content": "Be brief."},
+                {"role": "user", "content": "Weather in Paris and Rome?"},
+                {"role": "assistant", "content": null, "tool_calls": [
+                    {"id": "call_a", "type": "function", "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}},
+                    {"id": "call_b", "type": "function", "function": {"name": "get_weather", "arguments": "{\"city\":\"Rome\"}"}}
+                ]},
+                {"role": "tool", "tool_call_id": "call_a", "content": "sunny"},
+                {"role": "tool", "tool_call_id": "call_b", "content": "rainy"}
+            ],
+            "tools": tools(),
+            "tool_choice": "required"
+        }))
+        .unwrap();
+        let prepared = prepare_request(&request);
+        assert!(prepared.tools.is_none() && prepared.tool_choice.is_none());
+        assert_eq!(prepared.messages.len(), 4);
+
+        let system = prepared.messages[0].get_content_text();
+        assert!(system.starts_with("Be brief.\n\n"));
+        assert!(system.contains("\"name\":\"get_weather\""));
+        assert!(system.contains("You must call at least one tool"));
+        assert!(prepared.messages[2]
+            .get_content_text()
+            .contains("<tool_call>\n{\"arguments\":{\"city\":\"Rome\"},\"name\":\"get_weather\"}\n</tool_call>"));
+        // 连续工具结果合并为一条 user 消息
+        let results = &prepared.messages[3];
+        assert_eq!(results.role, "user");
+        assert!(results.tool_call_id.is_none());
+        assert_eq!(
+            results.get_content_text(),
+            "<tool_result name=\"get_weather\" id=\"call_a\">\nsunny\n</tool_result>\n<tool_result name=\"get_weather\" id=\"call_b\">\nrainy\n</tool_result>"
+        );
+
+        let mut response = json!({
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "<tool_call>\n{\"name\": \"get_weather\", \"arguments\": {\"city\": \"Oslo\"}}\n</tool_call>"},
+                "finish_reason": "stop"
+            }]
+        });
+        assert_eq!(rewrite_response(&mut response, &tool_names(&request)), 1);
+        let choice = &response["choices"][0];
+        assert_eq!(choice["finish_reason"], "tool_calls");
+        assert!(choice["message"]["content"].is_null());
+        let call = &choice["message"]["tool_calls"][0];
+        assert!(call["id"].as_str().unwrap().starts_with("call_"));
+        assert_eq!(call["function"]["name"], "get_weather");
+        assert_eq!(call["function"]["arguments"], "{\"city\":\"Oslo\"}");
+    }
+
+    #[test]
+    fn test_anthropic_conformance_history() {
+        let request: AnthropicMessagesRequest = serde_json::from_value(json!({
+            "model": "m",
+            "max_tokens": 100,
+            "messages": [
+                {"role": "user", "content": "Weather in Paris?"},
+                {"role": "assistant", "content": [
+                    {"type": "text", "text": "Checking."},
+                    {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Paris"}}
+                ]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1", "content": "sunny"}
+                ]}
+            ],
+            "tools": [{
+                "name": "get_weather",
+                "description": "Get the weather",
+                "input_schema": {"type": "object"}
+            }],
+            "tool_choice": {"type": "tool", "name": "get_weather"}
+        }))
+        .unwrap();
+        let prepared = prepare_request(&convert_anthropic_to_openai(&request));
+
+        assert_eq!(prepared.messages[0].role, "system");
+        assert!(prepared.messages[0]
+            .get_content_text()
+            .contains("You must call the `get_weather` tool"));
+        let assistant = prepared.messages[2].get_content_text();
+        assert!(assistant.starts_with("Checking.\n<tool_call>"));
+        assert!(prepared.messages[2].tool_calls.is_none());
+        assert_eq!(prepared.messages[3].role, "user");
+        assert!(prepared.messages[3]
+            .get_content_text()
+            .contains("<tool_result name=\"get_weather\" id=\"toolu_1\">\nsunny\n</tool_result>"));
+    }
+
+    #[test]
+    fn test_tool_choice_none_drops_tools_without_prompt() {
+        let request: ChatCompletionRequest = serde_json::from_value(json!({
+            "model": "m",
+            "messages": [{"role": "user", "content": "hi"}],
+            "tools": tools(),
+            "tool_choice": "none"
+        }))
+        .unwrap();
+        let prepared = prepare_request(&request);
+        assert!(prepared.tools.is_none());
+        assert_eq!(prepared.messages.len(), 1);
+    }
+}
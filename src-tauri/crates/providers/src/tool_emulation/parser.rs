@@ -0,0 +1,247 @@
+//! 工具调用文本协议解析
+//!
+//! 模型以如下格式输出工具调用：
+//!
+//! ```text
+//! <tool_call>
+//! {"name": "get_weather", "arguments": {"city": "Paris"}}
+//! </tool_call>
+//! ```
+//!
+//! 解析器按增量方式工作，流式和非流式共用。容忍代码块包裹、`parameters`/`input`
+//! 等参数别名、字符串形式的参数以及输出结束时缺少结束标签；无法解析或工具名未知的块按普通文本输出。
+
+use crate::structured_output::extract_json;
+use serde_json::Value;
+
+/// 工具调用开始标签
+pub const TOOL_CALL_OPEN: &str = "<tool_call>";
+/// 工具调用结束标签
+pub const TOOL_CALL_CLOSE: &str = "</tool_call>";
+
+/// 从模型输出中解析出的工具调用
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmulatedToolCall {
+    pub name: String,
+    /// JSON 编码的参数对象
+    pub arguments: String,
+}
+
+/// 解析结果片段
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParsedSegment {
+    Text(String),
+    ToolCall(EmulatedToolCall),
+}
+
+/// 增量工具调用解析器
+#[derive(Debug)]
+pub struct ToolCallParser {
+    tool_names: Vec<String>,
+    buffer: String,
+    in_call: bool,
+}
+
+impl ToolCallParser {
+    /// 创建解析器，`tool_names` 为请求中声明的工具（为空时不校验工具名）
+    pub fn new(tool_names: Vec<String>) -> Self {
+        Self {
+            tool_names,
+            buffer: String::new(),
+            in_call: false,
+        }
+    }
+
+    /// 追加模型输出，返回已能确定的片段
+    pub fn push(&mut self, delta: &str) -> Vec<ParsedSegment> {
+        self.buffer.push_str(delta);
+        let mut segments = Vec::new();
+        loop {
+            if self.in_call {
+                let Some(end) = self.buffer.find(TOOL_CALL_CLOSE) else {
+                    break;
+                };
+                let body: String = self.buffer.drain(..end).collect();
+                self.buffer.drain(..TOOL_CALL_CLOSE.len());
+                self.in_call = false;
+                segments.push(self.block_segment(&body, true));
+            } else if let Some(start) = self.buffer.find(TOOL_CALL_OPEN) {
+                let text: String = self.buffer.drain(..start).collect();
+                self.buffer.drain(..TOOL_CALL_OPEN.len());
+                self.in_call = true;
+                push_text(&mut segments, text);
+            } else {
+                // 保留可能是开始标签前缀的结尾部分
+                let keep = partial_tag_len(&self.buffer);
+                let text: String = self.buffer.drain(..self.buffer.len() - keep).collect();
+                push_text(&mut segments, text);
+                break;
+            }
+        }
+        segments
+    }
+
+    /// 输出结束，返回剩余片段（缺少结束标签的工具调用也尝试解析）
+    pub fn finish(&mut self) -> Vec<ParsedSegment> {
+        let rest = std::mem::take(&mut self.buffer);
+        let mut segments = Vec::new();
+        if std::mem::take(&mut self.in_call) {
+            segments.push(self.block_segment(&rest, false));
+        } else {
+            push_text(&mut segments, rest);
+        }
+        segments
+    }
+
+    fn block_segment(&self, body: &str, closed: bool) -> ParsedSegment {
+        match parse_tool_call_body(body, &self.tool_names) {
+            Some(call) => ParsedSegment::ToolCall(call),
+            None => {
+                let close = if closed { TOOL_CALL_CLOSE } else { "" };
+                ParsedSegment::Text(format!("{TOOL_CALL_OPEN}{body}{close}"))
+            }
+        }
+    }
+}
+
+fn push_text(segments: &mut Vec<ParsedSegment>, text: String) {
+    if !text.is_empty() {
+        segments.push(ParsedSegment::Text(text));
+    }
+}
+
+/// 结尾处与开始标签前缀重合的长度
+fn partial_tag_len(buffer: &str) -> usize {
+    (1..TOOL_CALL_OPEN.len().min(buffer.len() + 1))
+        .rev()
+        .find(|&len| {
+            buffer.is_char_boundary(buffer.len() - len)
+                && TOOL_CALL_OPEN.starts_with(&buffer[buffer.len() - len..])
+        })
+        .unwrap_or(0)
+}
+
+/// 解析 `<tool_call>` 块内容
+pub fn parse_tool_call_body(body: &str, tool_names: &[String]) -> Option<EmulatedToolCall> {
+    let value: Value = serde_json::from_str(extract_json(body)?).ok()?;
+    let object = value.as_object()?;
+    let name = ["name", "tool", "tool_name"]
+        .iter()
+        .find_map(|key| object.get(*key).and_then(Value::as_str))?
+        .trim()
+        .to_string();
+    if name.is_empty() || (!tool_names.is_empty() && !tool_names.contains(&name)) {
+        return None;
+    }
+    let arguments = match ["arguments", "parameters", "input", "args"]
+        .iter()
+        .find_map(|key| object.get(*key))
+    {
+        None | Some(Value::Null) => "{}".to_string(),
+        // 参数被编码为字符串时还原为对象
+        Some(Value::String(raw)) => match serde_json::from_str::<Value>(raw) {
+            Ok(parsed @ Value::Object(_)) => parsed.to_string(),
+            _ => return None,
+        },
+        Some(arguments @ Value::Object(_)) => arguments.to_string(),
+        Some(_) => return None,
+    };
+    Some(EmulatedToolCall { name, arguments })
+}
+
+/// 解析完整输出，返回去掉工具调用块后的文本和工具调用
+pub fn parse_output(text: &str, tool_names: &[String]) -> (String, Vec<EmulatedToolCall>) {
+    let mut parser = ToolCallParser::new(tool_names.to_vec());
+    let mut segments = parser.push(text);
+    segments.extend(parser.finish());
+
+    let mut content = String::new();
+    let mut calls = Vec::new();
+    for segment in segments {
+        match segment {
+            ParsedSegment::Text(text) => content.push_str(&text),
+            ParsedSegment::ToolCall(call) => calls.push(call),
+        }
+    }
+    (content.trim().to_string(), calls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names() -> Vec<String> {
+        vec!["get_weather".to_string(), "search".to_string()]
+    }
+
+    #[test]
+    fn test_parse_output_with_text_and_calls() {
+        let (content, calls) = parse_output(
+            "Let me check.\n<tool_call>\n{\"name\": \"get_weather\", \"arguments\": {\"city\": \"Paris\"}}\n</tool_call>\n<tool_call>```json\n{\"name\": \"search\", \"parameters\": {\"q\": \"rust\"}}\n```</tool_call>",
+            &names(),
+        );
+        assert_eq!(content, "Let me check.");
+        assert_eq!(
+            calls,
+            vec![
+                EmulatedToolCall {
+                    name: "get_weather".to_string(),
+                    arguments: "{\"city\":\"Paris\"}".to_string(),
+                },
+                EmulatedToolCall {
+                    name: "search".to_string(),
+                    arguments: "{\"q\":\"rust\"}".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tolerates_string_arguments_and_missing_close_tag() {
+        let (content, calls) = parse_output(
+            "<tool_call>{\"name\": \"search\", \"arguments\": \"{\\\"q\\\": \\\"x\\\"}\"}",
+            &names(),
+        );
+        assert_eq!(content, "");
+        assert_eq!(calls[0].arguments, "{\"q\":\"x\"}");
+    }
+
+    #[test]
+    fn test_invalid_or_unknown_blocks_stay_text() {
+        let text = "<tool_call>{\"name\": \"rm_rf\", \"arguments\": {}}</tool_call> and <tool_call>oops</tool_call>";
+        let (content, calls) = parse_output(text, &names());
+        assert!(calls.is_empty());
+        assert_eq!(content, text);
+    }
+
+    #[test]
+    fn test_incremental_parsing_holds_back_partial_tags() {
+        let mut parser = ToolCallParser::new(names());
+        let mut segments = Vec::new();
+        let output = "Hi <tool_call>{\"name\": \"search\", \"arguments\": {\"q\": \"天气\"}}</tool_call> <tool_c";
+        // 逐字符推送，包括多字节字符
+        for ch in output.chars() {
+            segments.extend(parser.push(&ch.to_string()));
+        }
+        segments.extend(parser.finish());
+
+        let text: String = segments
+            .iter()
+            .filter_map(|s| match s {
+                ParsedSegment::Text(t) => Some(t.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text, "Hi  <tool_c");
+        assert!(
+            segments.contains(&ParsedSegment::ToolCall(EmulatedToolCall {
+                name: "search".to_string(),
+                arguments: "{\"q\":\"天气\"}".to_string(),
+            }))
+        );
+        // 开始标签的任何前缀都不会提前作为文本输出
+        assert!(!segments
+            .iter()
+            .any(|s| matches!(s, ParsedSegment::Text(t) if t.contains("<tool_call"))));
+    }
+}
@@ -0,0 +1,259 @@
+//! 流式响应的工具调用模拟
+//!
+//! 逐块解析 OpenAI SSE 中的文本增量：普通文本立即下发，`<tool_call>` 块在完整后转换为
+//! `tool_calls` 增量（先下发 id 和名称，再下发参数），结束时把 `finish_reason` 改为 `tool_calls`。
+
+use super::new_call_id;
+use super::parser::{EmulatedToolCall, ParsedSegment, ToolCallParser};
+use crate::streaming::SseFramer;
+use serde_json::{json, Value};
+
+/// OpenAI SSE 工具调用模拟转换器
+#[derive(Debug)]
+pub struct EmulatedToolStream {
+    framer: SseFramer,
+    parser: ToolCallParser,
+    /// 最近一个数据块，用于生成新块的 id / model / created
+    template: Value,
+    call_count: usize,
+    finished: bool,
+}
+
+impl EmulatedToolStream {
+    pub fn new(tool_names: Vec<String>) -> Self {
+        Self {
+            framer: SseFramer::default(),
+            parser: ToolCallParser::new(tool_names),
+            template: json!({"object": "chat.completion.chunk"}),
+            call_count: 0,
+            finished: false,
+        }
+    }
+
+    /// 是否已输出工具调用
+    pub fn has_tool_calls(&self) -> bool {
+        self.call_count > 0
+    }
+
+    /// 追加上游字节，返回转换后的 SSE 事件
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        let mut out = Vec::new();
+        for event in self.framer.push(bytes) {
+            self.convert_event(event, &mut out);
+        }
+        out
+    }
+
+    /// 上游结束，返回剩余事件
+    pub fn finish(&mut self) -> Vec<String> {
+        let mut out = Vec::new();
+        if let Some(event) = self.framer.finish() {
+            self.convert_event(event, &mut out);
+        }
+        self.flush(&mut out);
+        out
+    }
+
+    fn convert_event(&mut self, event: String, out: &mut Vec<String>) {
+        let data: String = event
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(str::trim_start)
+            .collect();
+        if data.is_empty() {
+            out.push(event);
+            return;
+        }
+        if data.trim() == "[DONE]" {
+            self.flush(out);
+            out.push(event);
+            return;
+        }
+        let Ok(mut chunk) = serde_json::from_str::<Value>(&data) else {
+            out.push(event);
+            return;
+        };
+        let Some(choice) = chunk.pointer_mut("/choices/0") else {
+            // 只含 usage 等信息的块
+            out.push(event);
+            return;
+        };
+        let content = choice
+            .pointer_mut("/delta/content")
+            .map(Value::take)
+            .and_then(|c| c.as_str().map(str::to_string));
+        if let Some(delta) = choice.get_mut("delta").and_then(Value::as_object_mut) {
+            delta.remove("content");
+            delta.retain(|_, v| !v.is_null());
+        }
+        let finish_reason = choice.get("finish_reason").cloned().unwrap_or(Value::Null);
+        let rest_delta = choice.get("delta").cloned().unwrap_or_else(|| json!({}));
+        self.template = chunk.clone();
+
+        let segments = content
+            .map(|text| self.parser.push(&text))
+            .unwrap_or_default();
+        if finish_reason.is_null() {
+            if rest_delta.as_object().is_some_and(|d| !d.is_empty()) {
+                out.push(self.chunk(rest_delta, Value::Null));
+            }
+            self.emit(segments, out);
+        } else {
+            self.emit(segments, out);
+            let rest = self.parser.finish();
+            self.emit(rest, out);
+            // 结束块保留上游的 usage 等字段
+            if self.has_tool_calls() {
+                chunk["choices"][0]["finish_reason"] = json!("tool_calls");
+            }
+            chunk["choices"][0]["delta"] = rest_delta;
+            out.push(format!("data: {chunk}\n\n"));
+            self.finished = true;
+        }
+    }
+
+    /// 上游未发送 finish_reason 就结束时补齐剩余内容
+    fn flush(&mut self, out: &mut Vec<String>) {
+        if self.finished {
+            return;
+        }
+        let rest = self.parser.finish();
+        self.emit(rest, out);
+        if self.has_tool_calls() {
+            out.push(self.chunk(json!({}), json!("tool_calls")));
+            self.finished = true;
+        }
+    }
+
+    fn emit(&mut self, segments: Vec<ParsedSegment>, out: &mut Vec<String>) {
+        for segment in segments {
+            match segment {
+                // 工具调用之间的空白不单独下发
+                ParsedSegment::Text(text) if self.has_tool_calls() && text.trim().is_empty() => {}
+                ParsedSegment::Text(text) => {
+                    out.push(self.chunk(json!({"content": text}), Value::Null));
+                }
+                ParsedSegment::ToolCall(call) => self.emit_tool_call(call, out),
+            }
+        }
+    }
+
+    fn emit_tool_call(&mut self, call: EmulatedToolCall, out: &mut Vec<String>) {
+        let index = self.call_count;
+        self.call_count += 1;
+        out.push(self.chunk(
+            json!({"tool_calls": [{
+                "index": index,
+                "id": new_call_id(),
+                "type": "function",
+                "function": {"name": call.name, "arguments": ""}
+            }]}),
+            Value::Null,
+        ));
+        out.push(self.chunk(
+            json!({"tool_calls": [{
+                "index": index,
+                "function": {"arguments": call.arguments}
+            }]}),
+            Value::Null,
+        ));
+    }
+
+    fn chunk(&self, delta: Value, finish_reason: Value) -> String {
+        let mut chunk = self.template.clone();
+        if let Some(obj) = chunk.as_object_mut() {
+            obj.remove("usage");
+        }
+        chunk["choices"] = json!([{"index": 0, "delta": delta, "finish_reason": finish_reason}]);
+        format!("data: {chunk}\n\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sse(content: &str) -> String {
+        let chunk = json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "model": "m",
+            "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": null}]
+        });
+        format!("data: {chunk}\n\n")
+    }
+
+    fn chunks(events: &[String]) -> Vec<Value> {
+        events
+            .iter()
+            .filter_map(|e| e.strip_prefix("data: "))
+            .filter_map(|d| serde_json::from_str(d.trim()).ok())
+            .collect()
+    }
+
+    #[test]
+    fn test_openai_conformance_stream() {
+        let mut upstream = String::from(
+            "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"\"},\"finish_reason\":null}]}\n\n",
+        );
+        for piece in [
+            "Sure. <tool",
+            "_call>\n{\"name\": \"get_weather\", ",
+            "\"arguments\": {\"city\": \"Paris\"}}\n</tool_call>\n",
+            "<tool_call>{\"name\": \"get_weather\", \"arguments\": {\"city\": \"Rome\"}}",
+        ] {
+            upstream.push_str(&sse(piece));
+        }
+        upstream.push_str("data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"stop\"}],\"usage\":{\"total_tokens\":9}}\n\ndata: [DONE]\n\n");
+
+        // 按任意边界切分上游字节
+        let mut converter = EmulatedToolStream::new(vec!["get_weather".to_string()]);
+        let mut events = Vec::new();
+        for bytes in upstream.as_bytes().chunks(7) {
+            events.extend(converter.push(bytes));
+        }
+        events.extend(converter.finish());
+        assert_eq!(events.last().unwrap(), "data: [DONE]\n\n");
+
+        let chunks = chunks(&events);
+        assert_eq!(chunks[0]["choices"][0]["delta"]["role"], "assistant");
+        let text: String = chunks
+            .iter()
+            .filter_map(|c| c["choices"][0]["delta"]["content"].as_str())
+            .collect();
+        assert_eq!(text, "Sure. ");
+
+        let calls: Vec<&Value> = chunks
+            .iter()
+            .filter_map(|c| c["choices"][0]["delta"]["tool_calls"].get(0))
+            .collect();
+        assert_eq!(calls.len(), 4);
+        assert_eq!(calls[0]["index"], 0);
+        assert_eq!(calls[0]["function"]["name"], "get_weather");
+        assert!(calls[0]["id"].as_str().unwrap().starts_with("call_"));
+        assert_eq!(calls[1]["function"]["arguments"], "{\"city\":\"Paris\"}");
+        assert_eq!(calls[2]["index"], 1);
+        // 缺少结束标签的调用在结束时解析
+        assert_eq!(calls[3]["function"]["arguments"], "{\"city\":\"Rome\"}");
+
+        let last = chunks.last().unwrap();
+        assert_eq!(last["choices"][0]["finish_reason"], "tool_calls");
+        assert_eq!(last["usage"]["total_tokens"], 9);
+        assert!(chunks[..chunks.len() - 1]
+            .iter()
+            .all(|c| c.get("usage").is_none() && c["id"] == "chatcmpl-1"));
+    }
+
+    #[test]
+    fn test_plain_text_stream_is_unchanged() {
+        let mut converter = EmulatedToolStream::new(vec!["get_weather".to_string()]);
+        let mut events = converter.push(sse("a < b").as_bytes());
+        events.extend(converter.push(b"data: [DONE]\n\n"));
+        events.extend(converter.finish());
+
+        let chunks = chunks(&events);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0]["choices"][0]["delta"]["content"], "a < b");
+        assert!(!converter.has_tool_calls());
+    }
+}
@@ -6,7 +6,7 @@ use super::batch::{BatchTask, BatchTaskStatus};
 use super::template::TaskTemplate;
 use anyhow::{Context, Result};
 use proxycast_core::database::{lock_db, DbConnection};
-use rusqlite::{params, OptionalExtension};
+use rusqlite::{params, Connection, OptionalExtension};
 use uuid::Uuid;
 
 /// 批量任务 DAO
@@ -16,7 +16,11 @@ impl BatchTaskDao {
     /// 初始化数据库表
     pub fn init_tables(db: &DbConnection) -> Result<()> {
         let conn = lock_db(db).map_err(|e| anyhow::anyhow!(e))?;
+        Self::create_tables(&conn)
+    }
 
+    /// 创建批量任务与模板表（幂等）
+    pub fn create_tables(conn: &Connection) -> Result<()> {
         // 创建批量任务表
         conn.execute(
             "CREATE TABLE IF NOT EXISTS batch_tasks (
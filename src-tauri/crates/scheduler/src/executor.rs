@@ -6,6 +6,7 @@ use super::types::ScheduledTask;
 use async_trait::async_trait;
 use proxycast_agent::credential_bridge::CredentialBridge;
 use proxycast_core::database::DbConnection;
+use proxycast_services::concurrency_limiter::QueueClient;
 use std::sync::Arc;
 
 /// 任务执行器 Trait
@@ -69,10 +70,15 @@ impl TaskExecutor for AgentExecutor {
             task.model
         );
 
-        // 1. 从凭证池选择凭证
-        let aster_config = self
+        // 1. 从凭证池选择凭证并获取并发槽位（定时任务优先级低于交互式请求）
+        let (aster_config, _permit) = self
             .credential_bridge
-            .select_and_configure(db, &task.provider_type, &task.model)
+            .acquire_and_configure(
+                db,
+                &task.provider_type,
+                &task.model,
+                &QueueClient::batch("scheduler"),
+            )
             .await
             .map_err(|e| format!("选择凭证失败: {e}"))?;
 
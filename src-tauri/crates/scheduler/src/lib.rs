@@ -43,7 +43,9 @@ pub mod batch;
 pub mod batch_dao;
 pub mod dao;
 pub mod executor;
+pub mod migrations;
 pub mod scheduler;
+pub mod service;
 pub mod template;
 pub mod types;
 
@@ -54,7 +56,9 @@ pub use batch::{
 pub use batch_dao::{BatchTaskDao, TemplateDao};
 pub use dao::SchedulerDao;
 pub use executor::{AgentExecutor, TaskExecutor};
+pub use migrations::migrations;
 pub use scheduler::{AgentScheduler, SchedulerGovernanceConfig, SchedulerTrait};
+pub use service::{SchedulerService, SchedulerServiceConfig};
 pub use template::TaskTemplate;
 pub use types::{
     ScheduledTask, TaskFilter, TaskStatus, DEFAULT_TASK_COOLDOWN_SECS,
@@ -0,0 +1,24 @@
+//! 调度器数据库迁移
+//!
+//! 注册到 `proxycast_core::database::MigrationRegistry`，
+//! 由打开数据库的程序统一按版本执行。
+
+use proxycast_core::database::Migration;
+
+use crate::batch_dao::BatchTaskDao;
+use crate::dao::SchedulerDao;
+
+/// 迁移注册表中的组件名
+pub const MIGRATION_COMPONENT: &str = "scheduler";
+
+/// 调度器的数据库迁移
+pub fn migrations() -> Vec<Migration> {
+    vec![
+        Migration::new(MIGRATION_COMPONENT, 1, "scheduled_tasks", |conn| {
+            SchedulerDao::create_tables(conn).map_err(|e| format!("创建调度器表失败: {e}"))
+        }),
+        Migration::new(MIGRATION_COMPONENT, 2, "batch_tasks", |conn| {
+            BatchTaskDao::create_tables(conn).map_err(|e| format!("创建批量任务表失败: {e:#}"))
+        }),
+    ]
+}
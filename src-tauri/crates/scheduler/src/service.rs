@@ -2,8 +2,9 @@
 //!
 //! 提供后台心跳循环，定期检查并执行到期任务
 
+use crate::executor::{AgentExecutor, TaskExecutor};
+use crate::scheduler::{AgentScheduler, SchedulerTrait};
 use proxycast_core::database::DbConnection;
-use proxycast_scheduler::{AgentExecutor, AgentScheduler, SchedulerTrait, TaskExecutor};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::interval;
@@ -185,8 +186,9 @@ impl SchedulerService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dao::SchedulerDao;
+    use crate::types::ScheduledTask;
     use chrono::Utc;
-    use proxycast_scheduler::{ScheduledTask, SchedulerDao};
     use rusqlite::Connection;
     use std::sync::{Arc, Mutex};
 
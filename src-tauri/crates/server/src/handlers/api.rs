@@ -22,23 +22,42 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
 use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use crate::client_detector::ClientType;
 use crate::{record_request_telemetry, record_token_usage, AppState};
 use proxycast_core::errors::GatewayErrorCode;
-use proxycast_core::models::anthropic::AnthropicMessagesRequest;
-use proxycast_core::models::openai::ChatCompletionRequest;
+use proxycast_core::guardrails::TokenVault;
+use proxycast_core::models::anthropic::{AnthropicMessage, AnthropicMessagesRequest};
+use proxycast_core::models::openai::{ChatCompletionRequest, ChatMessage, MessageContent};
+use proxycast_core::models::provider_pool_model::ProviderCredential;
 use proxycast_core::ProviderType;
+use proxycast_infra::resilience::{
+    hedge_race, p90_latency, CircuitBreaker, CircuitKey, CircuitState, CircuitTransition,
+    HedgeLoser, HedgeOutcome, HedgeWinner,
+};
+use proxycast_infra::telemetry::RequestStatus;
 use proxycast_processor::RequestContext;
 use proxycast_providers::converter::anthropic_to_openai::convert_anthropic_to_openai;
 use proxycast_providers::streaming::StreamFormat as StreamingFormat;
+use proxycast_providers::structured_output::ResponseFormat;
 use proxycast_server_utils::{
     build_anthropic_response, build_anthropic_stream_response, build_error_response_with_meta,
     build_gateway_error_json, message_content_len, parse_cw_response, safe_truncate,
 };
+use proxycast_services::concurrency_limiter::{ConcurrencyError, ConcurrencyPermit, QueueClient};
 
-use super::{call_provider_anthropic, call_provider_openai};
+use super::call_provider_anthropic;
+use super::guardrails::{apply_guardrails, restore_guardrail_tokens};
+use super::stream_failover::{
+    with_stream_failover, ResumeAttempt, ResumeFn, ResumedStream, StreamFailover,
+};
+use super::stream_usage::track_stream_output;
+use super::structured_output::call_provider_openai_structured;
 
 async fn select_credential_for_request(
     state: &AppState,
@@ -50,8 +69,8 @@ async fn select_credential_for_request(
     log_prefix: &str,
     _include_error_code: bool,
 ) -> Result<Option<proxycast_core::models::provider_pool_model::ProviderCredential>, Response> {
-    let db = match &state.db {
-        Some(db) => db,
+    let pool = match &state.db_pool {
+        Some(pool) => pool,
         None => {
             eprintln!("[{log_prefix}] 数据库未初始化!");
             return Ok(None);
@@ -62,12 +81,8 @@ async fn select_credential_for_request(
         eprintln!("[{log_prefix}] 使用 X-Provider-Id 指定的 provider: {explicit_provider_id}");
         let cred = state
             .pool_service
-            .select_credential_with_client_check(
-                db,
-                explicit_provider_id,
-                Some(model),
-                Some(client_type),
-            )
+            .select_credential_pooled(pool, explicit_provider_id, Some(model), Some(client_type))
+            .await
             .ok()
             .flatten();
 
@@ -101,12 +116,11 @@ async fn select_credential_for_request(
         eprintln!(
             "[{log_prefix}] 已禁用自动降级（retry.auto_switch_provider=false），仅从 Provider Pool 选择"
         );
-        return match state.pool_service.select_credential_with_client_check(
-            db,
-            selected_provider,
-            Some(model),
-            Some(client_type),
-        ) {
+        return match state
+            .pool_service
+            .select_credential_pooled(pool, selected_provider, Some(model), Some(client_type))
+            .await
+        {
             Ok(cred) => {
                 if cred.is_some() {
                     eprintln!("[{log_prefix}] 找到凭证: provider={selected_provider}");
@@ -127,8 +141,8 @@ async fn select_credential_for_request(
     let provider_id_hint = selected_provider.to_lowercase();
     match state
         .pool_service
-        .select_credential_with_fallback(
-            db,
+        .select_credential_with_fallback_pooled(
+            pool,
             &state.api_key_service,
             selected_provider,
             Some(model),
@@ -152,10 +166,18 @@ async fn select_credential_for_request(
     }
 }
 
+/// 凭证所属 Provider 端点的熔断维度
+///
+/// 同类型的自定义端点（如多个 OpenAI 兼容服务）按 `base_url` 区分，互不连带熔断。
+fn provider_circuit_key(cred: &ProviderCredential) -> CircuitKey {
+    CircuitKey::provider_endpoint(cred.provider_type.to_string(), cred.credential.base_url())
+}
+
 async fn call_with_single_provider_resilience<F, Fut>(
     state: &AppState,
     request_id: &str,
     provider_label: &str,
+    cred: &ProviderCredential,
     is_stream: bool,
     mut operation: F,
 ) -> Response
@@ -165,6 +187,11 @@ where
 {
     let retrier = state.processor.retrier.clone();
     let timeout_controller = state.processor.timeout.clone();
+    let circuit_breaker = state.processor.circuit_breaker.clone();
+    let circuit_keys = [
+        provider_circuit_key(cred),
+        CircuitKey::credential(cred.uuid.as_str()),
+    ];
     let max_retries = if is_stream {
         0
     } else {
@@ -176,9 +203,36 @@ where
     loop {
         attempt += 1;
 
+        if let Err(open) = circuit_breaker.acquire(&circuit_keys) {
+            state.logs.write().await.add(
+                "warn",
+                &format!(
+                    "[CIRCUIT] request_id={} provider={} rejected key={} retry_after_ms={}",
+                    request_id, provider_label, open.key, open.retry_after_ms
+                ),
+            );
+            let response = build_error_response_with_meta(
+                StatusCode::SERVICE_UNAVAILABLE.as_u16(),
+                &format!("Circuit breaker open for {}", open.key),
+                Some(request_id),
+                Some(provider_label),
+                Some(GatewayErrorCode::UpstreamUnavailable),
+            );
+            let retry_after_secs = open.retry_after_ms.div_ceil(1000).max(1);
+            let (mut parts, body) = response.into_parts();
+            parts.headers.insert(
+                header::RETRY_AFTER,
+                header::HeaderValue::from(retry_after_secs),
+            );
+            return Response::from_parts(parts, body);
+        }
+
         let response = match timeout_controller.execute_with_timeout(operation()).await {
             Ok(resp) => resp,
             Err(timeout_err) => {
+                let transitions = circuit_breaker.record_failure(&circuit_keys);
+                log_circuit_transitions(state, request_id, &transitions).await;
+
                 if attempt <= max_retries {
                     let delay = retrier.backoff_delay(attempt - 1);
                     state.logs.write().await.add(
@@ -216,6 +270,13 @@ where
         };
 
         let status_code = response.status().as_u16();
+        let transitions = if CircuitBreaker::is_failure_status(status_code) {
+            circuit_breaker.record_failure(&circuit_keys)
+        } else {
+            circuit_breaker.record_success(&circuit_keys)
+        };
+        log_circuit_transitions(state, request_id, &transitions).await;
+
         let should_retry = attempt <= max_retries && retrier.config().is_retryable(status_code);
 
         if should_retry {
@@ -261,6 +322,405 @@ where
     }
 }
 
+/// 将熔断状态转换写入请求日志
+async fn log_circuit_transitions(
+    state: &AppState,
+    request_id: &str,
+    transitions: &[CircuitTransition],
+) {
+    if transitions.is_empty() {
+        return;
+    }
+    let mut logs = state.logs.write().await;
+    for transition in transitions {
+        let level = if transition.to == CircuitState::Open {
+            "warn"
+        } else {
+            "info"
+        };
+        logs.add(
+            level,
+            &format!(
+                "[CIRCUIT] request_id={} key={} {:?} -> {:?} reason={}",
+                request_id, transition.key, transition.from, transition.to, transition.reason
+            ),
+        );
+    }
+}
+
+/// 对冲请求的备用凭证
+struct HedgeTarget {
+    /// 备用凭证
+    credential: ProviderCredential,
+    /// 主请求未返回时发出对冲请求的延迟
+    delay: Duration,
+}
+
+impl HedgeTarget {
+    /// 对冲延迟到期、真正发出对冲请求时获取备用凭证的并发槽位
+    ///
+    /// 没有空闲槽位时返回 None（不发出对冲），避免对冲请求进入排队；
+    /// 主请求在延迟内返回时不会占用备用凭证的槽位。
+    fn try_acquire_permit(&self, state: &AppState) -> Option<ConcurrencyPermit> {
+        state.pool_service.concurrency_limiter().try_acquire(
+            &self.credential.uuid,
+            &self.credential.provider_type.to_string(),
+        )
+    }
+}
+
+/// 按对冲规则准备备用凭证（不对冲时返回 None）
+async fn prepare_hedge(
+    state: &AppState,
+    ctx: &RequestContext,
+    client_type: &ClientType,
+    selected_provider: &str,
+    model: &str,
+    primary: &ProviderCredential,
+) -> Option<HedgeTarget> {
+    let plan = state.processor.hedging.plan(
+        client_type.config_key(),
+        &primary.provider_type.to_string(),
+        &ctx.resolved_model,
+    )?;
+    let pool = state.db_pool.as_ref()?;
+    let credential = state
+        .pool_service
+        .select_alternate_credential_pooled(
+            pool,
+            selected_provider,
+            Some(model),
+            Some(client_type),
+            &[primary.uuid.as_str()],
+        )
+        .await
+        .ok()
+        .flatten()?;
+
+    // 未配置固定延迟时，使用同模型、同请求类型最近成功请求的 P90 耗时
+    let observed_p90 = if plan.needs_p90() {
+        let logs = state.processor.stats.read().get_all();
+        p90_latency(
+            logs.iter()
+                .rev()
+                .filter(|log| {
+                    log.model == ctx.resolved_model
+                        && log.is_streaming == ctx.is_stream
+                        && log.status == RequestStatus::Success
+                })
+                .take(100)
+                .map(|log| log.duration_ms),
+        )
+    } else {
+        None
+    };
+
+    Some(HedgeTarget {
+        credential,
+        delay: plan.delay(observed_p90),
+    })
+}
+
+/// 记录对冲结果：获胜方写入请求上下文，落败方单独记录一条遥测
+async fn record_hedge_outcome(
+    state: &AppState,
+    ctx: &mut RequestContext,
+    primary: &ProviderCredential,
+    target: &HedgeTarget,
+    outcome: &HedgeOutcome<Response>,
+) {
+    let Some(loser) = outcome.loser else {
+        return;
+    };
+    let (winner_cred, loser_cred, winner_label) = match outcome.winner {
+        HedgeWinner::Primary => (primary, &target.credential, "primary"),
+        HedgeWinner::Hedge => (&target.credential, primary, "hedge"),
+    };
+
+    ctx.set_credential_id(winner_cred.uuid.clone());
+    ctx.set_metadata("hedge_winner", serde_json::json!(winner_label));
+
+    let mut loser_ctx = ctx.clone();
+    loser_ctx.set_credential_id(loser_cred.uuid.clone());
+    loser_ctx.set_metadata("hedge_winner", serde_json::json!(winner_label));
+    let (loser_status, loser_error) = match loser {
+        HedgeLoser::Cancelled => (RequestStatus::Cancelled, None),
+        HedgeLoser::Failed => (
+            RequestStatus::Failed,
+            Some("Hedged attempt failed".to_string()),
+        ),
+    };
+    record_request_telemetry(state, &loser_ctx, loser_status, loser_error);
+
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[HEDGE] request_id={} delay_ms={} winner={} winner_credential={} loser_credential={} loser={:?}",
+            ctx.request_id,
+            target.delay.as_millis(),
+            winner_label,
+            &winner_cred.uuid[..8.min(winner_cred.uuid.len())],
+            &loser_cred.uuid[..8.min(loser_cred.uuid.len())],
+            loser
+        ),
+    );
+}
+
+/// 为选中的凭证获取并发槽位
+///
+/// 并发已满时按客户端类型和 API Key 做公平排队；排队超时返回 503，队列已满返回 429。
+async fn acquire_concurrency_permit(
+    state: &AppState,
+    headers: &HeaderMap,
+    ctx: &mut RequestContext,
+    client_type: &ClientType,
+    cred: &ProviderCredential,
+) -> Result<ConcurrencyPermit, Response> {
+    let class = client_type.config_key();
+    let api_key = headers
+        .get("x-api-key")
+        .or_else(|| headers.get("authorization"))
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("anonymous");
+    let digest = Sha256::digest(api_key.as_bytes());
+    let flow = format!(
+        "{class}:{:02x}{:02x}{:02x}{:02x}",
+        digest[0], digest[1], digest[2], digest[3]
+    );
+    let queue_client = QueueClient::interactive(class, flow);
+
+    let started = Instant::now();
+    match state.pool_service.acquire_slot(cred, &queue_client).await {
+        Ok(permit) => {
+            let waited_ms = started.elapsed().as_millis() as u64;
+            if waited_ms > 0 {
+                ctx.set_metadata("queue_wait_ms", serde_json::json!(waited_ms));
+                state.logs.write().await.add(
+                    "info",
+                    &format!(
+                        "[QUEUE] request_id={} credential={} flow={} waited_ms={}",
+                        ctx.request_id,
+                        &cred.uuid[..8.min(cred.uuid.len())],
+                        queue_client.flow,
+                        waited_ms
+                    ),
+                );
+            }
+            Ok(permit)
+        }
+        Err(err) => {
+            state.logs.write().await.add(
+                "warn",
+                &format!(
+                    "[QUEUE] request_id={} credential={} flow={} rejected: {}",
+                    ctx.request_id,
+                    &cred.uuid[..8.min(cred.uuid.len())],
+                    queue_client.flow,
+                    err
+                ),
+            );
+            let (status, code) = match err {
+                ConcurrencyError::QueueTimeout { .. } => (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    GatewayErrorCode::UpstreamUnavailable,
+                ),
+                ConcurrencyError::QueueFull { .. } => {
+                    (StatusCode::TOO_MANY_REQUESTS, GatewayErrorCode::RateLimited)
+                }
+            };
+            let provider_label = cred.provider_type.to_string();
+            let response = build_error_response_with_meta(
+                status.as_u16(),
+                &format!("Concurrency limit reached: {err}"),
+                Some(&ctx.request_id),
+                Some(&provider_label),
+                Some(code),
+            );
+            let (mut parts, body) = response.into_parts();
+            parts
+                .headers
+                .insert(header::RETRY_AFTER, header::HeaderValue::from_static("1"));
+            Err(Response::from_parts(parts, body))
+        }
+    }
+}
+
+/// 等待上游响应体的首个数据块，再把它拼回响应体
+///
+/// 对冲按首个数据块竞速：上游通常很快返回响应头，流式请求真正慢的是首个事件。
+/// 首块前读取失败时返回 502，交由对冲判定为不可用结果；非成功响应原样返回。
+async fn prime_first_chunk(
+    call: impl Future<Output = Response>,
+    request_id: &str,
+    provider_label: &str,
+) -> Response {
+    let response = call.await;
+    if !response.status().is_success() {
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let mut stream = body.into_data_stream();
+    match stream.next().await {
+        Some(Ok(first)) => {
+            let stream = futures::stream::once(async move { Ok(first) }).chain(stream);
+            Response::from_parts(parts, Body::from_stream(stream))
+        }
+        Some(Err(e)) => build_error_response_with_meta(
+            StatusCode::BAD_GATEWAY.as_u16(),
+            &format!("Upstream stream failed before first chunk: {e}"),
+            Some(request_id),
+            Some(provider_label),
+            Some(GatewayErrorCode::UpstreamUnavailable),
+        ),
+        None => Response::from_parts(parts, Body::empty()),
+    }
+}
+
+/// 让并发槽位随响应体一起释放（流式响应在流结束或客户端断开时才释放）
+fn hold_permit(response: Response, permit: ConcurrencyPermit) -> Response {
+    let (parts, body) = response.into_parts();
+    let stream = body.into_data_stream().map(move |chunk| {
+        let _held = &permit;
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+/// 为池凭证的流式响应启用中途故障转移
+///
+/// 上游流中途断开时，以已下发的文本作为预填充向同一 Provider 的其他凭证续传；
+/// `call` 根据续传凭证和预填充文本发起新的流式请求。
+#[allow(clippy::too_many_arguments)]
+fn attach_stream_failover<F, Fut>(
+    state: &AppState,
+    request_id: &str,
+    client_type: &ClientType,
+    selected_provider: &str,
+    model: &str,
+    served: &ProviderCredential,
+    format: StreamingFormat,
+    response: Response,
+    call: F,
+) -> Response
+where
+    F: Fn(AppState, ProviderCredential, String) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Response> + Send + 'static,
+{
+    let settings = state.processor.stream_failover.read().clone();
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    if !settings.enabled || settings.max_resumes == 0 || !response.status().is_success() || !is_sse
+    {
+        return response;
+    }
+
+    let failover = StreamFailover {
+        request_id: request_id.to_string(),
+        format,
+        credential_id: served.uuid.clone(),
+        max_resumes: settings.max_resumes,
+        timeout: state.processor.timeout.config().clone(),
+    };
+    let state = state.clone();
+    let request_id = request_id.to_string();
+    let client_type = *client_type;
+    let selected_provider = selected_provider.to_string();
+    let model = model.to_string();
+    // 当前正在下发的凭证所属端点，断流时据此记录 Provider 端点熔断
+    let served_circuit = Arc::new(parking_lot::Mutex::new(provider_circuit_key(served)));
+    let call = Arc::new(call);
+
+    let resume: ResumeFn = Box::new(move |attempt: ResumeAttempt| {
+        let state = state.clone();
+        let request_id = request_id.clone();
+        let selected_provider = selected_provider.clone();
+        let model = model.clone();
+        let served_circuit = served_circuit.clone();
+        let call = call.clone();
+        Box::pin(async move {
+            // 断流计入熔断器，连续断流的凭证会被熔断
+            if let Some(broken) = attempt.exclude.last() {
+                let broken_circuit = served_circuit.lock().clone();
+                let transitions = state
+                    .processor
+                    .circuit_breaker
+                    .record_failure(&[broken_circuit, CircuitKey::credential(broken.as_str())]);
+                log_circuit_transitions(&state, &request_id, &transitions).await;
+            }
+
+            let pool = state.db_pool.as_ref()?;
+            let exclude: Vec<&str> = attempt.exclude.iter().map(String::as_str).collect();
+            let alt = state
+                .pool_service
+                .select_alternate_credential_pooled(
+                    pool,
+                    &selected_provider,
+                    Some(&model),
+                    Some(&client_type),
+                    &exclude,
+                )
+                .await
+                .ok()
+                .flatten();
+            let Some(alt) = alt else {
+                state.logs.write().await.add(
+                    "warn",
+                    &format!(
+                        "[STREAM_FAILOVER] request_id={} attempt={} no alternate credential: {}",
+                        request_id, attempt.attempt, attempt.reason
+                    ),
+                );
+                return None;
+            };
+            // 续传不排队，备用凭证没有空闲并发槽位时放弃续传
+            let permit = state
+                .pool_service
+                .concurrency_limiter()
+                .try_acquire(&alt.uuid, &alt.provider_type.to_string())?;
+
+            let alt_label = alt.provider_type.to_string();
+            let response = call_with_single_provider_resilience(
+                &state,
+                &request_id,
+                &alt_label,
+                &alt,
+                true,
+                || (*call)(state.clone(), alt.clone(), attempt.prefill.clone()),
+            )
+            .await;
+            state.logs.write().await.add(
+                if response.status().is_success() {
+                    "info"
+                } else {
+                    "warn"
+                },
+                &format!(
+                    "[STREAM_FAILOVER] request_id={} attempt={} credential={} prefill_chars={} status={} reason={}",
+                    request_id,
+                    attempt.attempt,
+                    &alt.uuid[..8.min(alt.uuid.len())],
+                    attempt.prefill.chars().count(),
+                    response.status(),
+                    attempt.reason
+                ),
+            );
+            if !response.status().is_success() {
+                return None;
+            }
+            *served_circuit.lock() = provider_circuit_key(&alt);
+            Some(ResumedStream {
+                credential_id: alt.uuid.clone(),
+                response: hold_permit(response, permit),
+            })
+        })
+    });
+
+    with_stream_failover(response, failover, resume)
+}
+
 // ============================================================================
 // Provider 选择辅助函数
 // ============================================================================
@@ -374,11 +834,54 @@ pub async fn verify_api_key_anthropic(
     Ok(())
 }
 
+/// 解析 OpenAI chat completions 请求体，同时取出 `response_format`
+pub fn parse_chat_completion_body(
+    body: serde_json::Value,
+) -> Result<(ChatCompletionRequest, Option<ResponseFormat>), Response> {
+    let response_format = ResponseFormat::from_request_body(&body).map_err(|e| {
+        build_error_response_with_meta(
+            StatusCode::BAD_REQUEST.as_u16(),
+            &e,
+            None,
+            None,
+            Some(GatewayErrorCode::InvalidRequest),
+        )
+    })?;
+    let request = serde_json::from_value(body).map_err(|e| {
+        build_error_response_with_meta(
+            StatusCode::UNPROCESSABLE_ENTITY.as_u16(),
+            &format!("Invalid chat completion request: {e}"),
+            None,
+            None,
+            Some(GatewayErrorCode::InvalidRequest),
+        )
+    })?;
+    Ok((request, response_format))
+}
+
 pub async fn chat_completions(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(mut request): Json<ChatCompletionRequest>,
+    Json(body): Json<serde_json::Value>,
+) -> Response {
+    // 请求被令牌化时，所有返回路径的响应都需要还原令牌
+    let mut guardrail_vault = None;
+    let response = handle_chat_completions(state, headers, body, &mut guardrail_vault).await;
+    restore_guardrail_tokens(response, guardrail_vault).await
+}
+
+async fn handle_chat_completions(
+    state: AppState,
+    headers: HeaderMap,
+    body: serde_json::Value,
+    guardrail_vault: &mut Option<Arc<TokenVault>>,
 ) -> Response {
+    // `response_format` 不在 ChatCompletionRequest 中，需从原始请求体解析
+    let (mut request, response_format) = match parse_chat_completion_body(body) {
+        Ok(parsed) => parsed,
+        Err(resp) => return resp,
+    };
+
     // ========== 详细日志：请求入口 ==========
     eprintln!("\n========== [CHAT_COMPLETIONS] 收到请求 ==========");
     eprintln!("[CHAT_COMPLETIONS] URL: /v1/chat/completions");
@@ -554,6 +1057,28 @@ pub async fn chat_completions(
         }
     }
 
+    // 内容安全检查
+    match apply_guardrails(
+        &state,
+        &ctx.request_id,
+        "/v1/chat/completions",
+        &request.model.clone(),
+        &mut request,
+    )
+    .await
+    {
+        Ok(vault) => *guardrail_vault = vault,
+        Err(message) => {
+            return build_error_response_with_meta(
+                StatusCode::BAD_REQUEST.as_u16(),
+                &message,
+                Some(&ctx.request_id),
+                None,
+                Some(GatewayErrorCode::ContentBlocked),
+            );
+        }
+    }
+
     // 根据客户端类型选择 Provider
     // **Validates: Requirements 3.1, 3.3, 3.4**
     let (selected_provider, client_type) = select_provider_for_client(&headers, &state).await;
@@ -645,14 +1170,139 @@ pub async fn chat_completions(
 
         eprintln!("[CHAT_COMPLETIONS] 调用 Provider: {}", cred.provider_type);
         let provider_label = cred.provider_type.to_string();
-        let response = call_with_single_provider_resilience(
+        let permit =
+            match acquire_concurrency_permit(&state, &headers, &mut ctx, &client_type, &cred).await
+            {
+                Ok(permit) => permit,
+                Err(resp) => {
+                    record_request_telemetry(
+                        &state,
+                        &ctx,
+                        proxycast_infra::telemetry::RequestStatus::Failed,
+                        Some("Concurrency queue rejected".to_string()),
+                    );
+                    return resp;
+                }
+            };
+        let hedge = prepare_hedge(
+            &state,
+            &ctx,
+            &client_type,
+            &selected_provider,
+            &request.model,
+            &cred,
+        )
+        .await;
+        let primary = call_with_single_provider_resilience(
             &state,
             &ctx.request_id,
             &provider_label,
+            &cred,
             request.stream,
-            || async { call_provider_openai(&state, &cred, &request, None).await },
-        )
-        .await;
+            || async {
+                call_provider_openai_structured(
+                    &state,
+                    &cred,
+                    &request,
+                    response_format.as_ref(),
+                    Some(&ctx.request_id),
+                )
+                .await
+            },
+        );
+        let (response, permit, hedge_winner) = match hedge {
+            Some(target) => {
+                let alt = &target.credential;
+                let alt_label = alt.provider_type.to_string();
+                let mut hedge_permit = None;
+                // 双方都以首个数据块（而非响应头）作为“返回”，流式请求按首字节竞速
+                let outcome = hedge_race(
+                    prime_first_chunk(primary, &ctx.request_id, &provider_label),
+                    target.delay,
+                    || {
+                        hedge_permit = Some(target.try_acquire_permit(&state)?);
+                        let call = call_with_single_provider_resilience(
+                            &state,
+                            &ctx.request_id,
+                            &alt_label,
+                            alt,
+                            request.stream,
+                            || async {
+                                call_provider_openai_structured(
+                                    &state,
+                                    alt,
+                                    &request,
+                                    response_format.as_ref(),
+                                    Some(&ctx.request_id),
+                                )
+                                .await
+                            },
+                        );
+                        Some(prime_first_chunk(call, &ctx.request_id, &alt_label))
+                    },
+                    |resp: &Response| resp.status().is_success(),
+                )
+                .await;
+                record_hedge_outcome(&state, &mut ctx, &cred, &target, &outcome).await;
+                // 只保留获胜方的并发槽位，落败方的槽位随即释放
+                let (permit, winner) = match (outcome.winner, hedge_permit) {
+                    (HedgeWinner::Hedge, Some(hedge_permit)) => {
+                        (hedge_permit, Some(target.credential))
+                    }
+                    _ => (permit, None),
+                };
+                (outcome.value, permit, winner)
+            }
+            None => (primary.await, permit, None),
+        };
+        let response = track_stream_output(&ctx, hold_permit(response, permit));
+        let response = if request.stream {
+            let served = match &hedge_winner {
+                Some(alt) => alt,
+                None => &cred,
+            };
+            attach_stream_failover(
+                &state,
+                &ctx.request_id,
+                &client_type,
+                &selected_provider,
+                &request.model,
+                served,
+                StreamingFormat::OpenAiSse,
+                response,
+                {
+                    let request = request.clone();
+                    let response_format = response_format.clone();
+                    let request_id = ctx.request_id.clone();
+                    move |state: AppState, cred: ProviderCredential, prefill: String| {
+                        let mut request = request.clone();
+                        if !prefill.is_empty() {
+                            request.messages.push(ChatMessage {
+                                role: "assistant".to_string(),
+                                content: Some(MessageContent::Text(prefill)),
+                                tool_calls: None,
+                                tool_call_id: None,
+                                reasoning_content: None,
+                            });
+                        }
+                        let response_format = response_format.clone();
+                        let request_id = request_id.clone();
+                        async move {
+                            call_provider_openai_structured(
+                                &state,
+                                &cred,
+                                &request,
+                                response_format.as_ref(),
+                                Some(&request_id),
+                            )
+                            .await
+                        }
+                    }
+                },
+            )
+        } else {
+            response
+        };
         eprintln!(
             "[CHAT_COMPLETIONS] Provider 响应状态: {}",
             response.status()
@@ -1013,7 +1663,19 @@ pub async fn chat_completions(
 pub async fn anthropic_messages(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(mut request): Json<AnthropicMessagesRequest>,
+    Json(request): Json<AnthropicMessagesRequest>,
+) -> Response {
+    // 请求被令牌化时，所有返回路径的响应都需要还原令牌
+    let mut guardrail_vault = None;
+    let response = handle_anthropic_messages(state, headers, request, &mut guardrail_vault).await;
+    restore_guardrail_tokens(response, guardrail_vault).await
+}
+
+async fn handle_anthropic_messages(
+    state: AppState,
+    headers: HeaderMap,
+    mut request: AnthropicMessagesRequest,
+    guardrail_vault: &mut Option<Arc<TokenVault>>,
 ) -> Response {
     // 使用 Anthropic 格式的认证验证（优先检查 x-api-key）
     if let Err(e) = verify_api_key_anthropic(&headers, &state.api_key).await {
@@ -1216,6 +1878,28 @@ pub async fn anthropic_messages(
         }
     }
 
+    // 内容安全检查
+    match apply_guardrails(
+        &state,
+        &ctx.request_id,
+        "/v1/messages",
+        &request.model.clone(),
+        &mut request,
+    )
+    .await
+    {
+        Ok(vault) => *guardrail_vault = vault,
+        Err(message) => {
+            return build_error_response_with_meta(
+                StatusCode::BAD_REQUEST.as_u16(),
+                &message,
+                Some(&ctx.request_id),
+                None,
+                Some(GatewayErrorCode::ContentBlocked),
+            );
+        }
+    }
+
     // 根据客户端类型选择 Provider
     // **Validates: Requirements 3.1, 3.3, 3.4**
     let (selected_provider, client_type) = select_provider_for_client(&headers, &state).await;
@@ -1296,14 +1980,105 @@ pub async fn anthropic_messages(
         // **Validates: Requirements 2.1, 2.3, 2.5**
 
         let provider_label = cred.provider_type.to_string();
-        let response = call_with_single_provider_resilience(
+        let permit =
+            match acquire_concurrency_permit(&state, &headers, &mut ctx, &client_type, &cred).await
+            {
+                Ok(permit) => permit,
+                Err(resp) => {
+                    record_request_telemetry(
+                        &state,
+                        &ctx,
+                        proxycast_infra::telemetry::RequestStatus::Failed,
+                        Some("Concurrency queue rejected".to_string()),
+                    );
+                    return resp;
+                }
+            };
+        let hedge = prepare_hedge(
+            &state,
+            &ctx,
+            &client_type,
+            &selected_provider,
+            &request.model,
+            &cred,
+        )
+        .await;
+        let primary = call_with_single_provider_resilience(
             &state,
             &ctx.request_id,
             &provider_label,
+            &cred,
             request.stream,
             || async { call_provider_anthropic(&state, &cred, &request, None).await },
-        )
-        .await;
+        );
+        let (response, permit, hedge_winner) = match hedge {
+            Some(target) => {
+                let alt = &target.credential;
+                let alt_label = alt.provider_type.to_string();
+                let mut hedge_permit = None;
+                // 双方都以首个数据块（而非响应头）作为“返回”，流式请求按首字节竞速
+                let outcome = hedge_race(
+                    prime_first_chunk(primary, &ctx.request_id, &provider_label),
+                    target.delay,
+                    || {
+                        hedge_permit = Some(target.try_acquire_permit(&state)?);
+                        let call = call_with_single_provider_resilience(
+                            &state,
+                            &ctx.request_id,
+                            &alt_label,
+                            alt,
+                            request.stream,
+                            || async { call_provider_anthropic(&state, alt, &request, None).await },
+                        );
+                        Some(prime_first_chunk(call, &ctx.request_id, &alt_label))
+                    },
+                    |resp: &Response| resp.status().is_success(),
+                )
+                .await;
+                record_hedge_outcome(&state, &mut ctx, &cred, &target, &outcome).await;
+                // 只保留获胜方的并发槽位，落败方的槽位随即释放
+                let (permit, winner) = match (outcome.winner, hedge_permit) {
+                    (HedgeWinner::Hedge, Some(hedge_permit)) => {
+                        (hedge_permit, Some(target.credential))
+                    }
+                    _ => (permit, None),
+                };
+                (outcome.value, permit, winner)
+            }
+            None => (primary.await, permit, None),
+        };
+        let response = track_stream_output(&ctx, hold_permit(response, permit));
+        let response = if request.stream {
+            let served = match &hedge_winner {
+                Some(alt) => alt,
+                None => &cred,
+            };
+            attach_stream_failover(
+                &state,
+                &ctx.request_id,
+                &client_type,
+                &selected_provider,
+                &request.model,
+                served,
+                StreamingFormat::AnthropicSse,
+                response,
+                {
+                    let request = request.clone();
+                    move |state: AppState, cred: ProviderCredential, prefill: String| {
+                        let mut request = request.clone();
+                        if !prefill.is_empty() {
+                            request.messages.push(AnthropicMessage {
+                                role: "assistant".to_string(),
+                                content: serde_json::Value::String(prefill),
+                            });
+                        }
+                        async move { call_provider_anthropic(&state, &cred, &request, None).await }
+                    }
+                },
+            )
+        } else {
+            response
+        };
 
         // 记录请求统计
         let is_success = response.status().is_success();
@@ -0,0 +1,821 @@
+//! 音频 API 处理器
+//!
+//! 实现 OpenAI 兼容的音频端点：
+//! - `POST /v1/audio/transcriptions`：语音识别，分发到配置的 ASR 凭证
+//!   （本地 Whisper、讯飞、百度、OpenAI），支持 json/text/srt/vtt/verbose_json 输出。
+//!   WAV/PCM 在网关解码后交给任意后端；mp3、m4a、webm 等压缩格式不在网关解码，
+//!   原样转发给 OpenAI 凭证，其它后端返回 400
+//! - `POST /v1/audio/speech`：语音合成，只支持凭证池中的 OpenAI 兼容凭证；
+//!   `voice.tts_service` 配置为其它服务商时返回 501
+//!
+//! 音频请求与对话请求一样记录到遥测系统。ASR 后端不属于 `ProviderType`，
+//! 遥测中统一按 OpenAI 兼容协议归类，模型名标注实际后端（如 `asr/xunfei`）。
+
+use axum::{
+    body::Body,
+    extract::{Multipart, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use super::guardrails::apply_guardrails_redacting;
+use crate::handlers::verify_api_key;
+use crate::{record_request_telemetry, AppState, SKIP_ORCHESTRATOR_STATS};
+use proxycast_core::config::AsrProviderType;
+use proxycast_core::errors::GatewayErrorCode;
+use proxycast_core::models::provider_pool_model::CredentialData;
+use proxycast_core::ProviderType;
+use proxycast_infra::telemetry::RequestStatus;
+use proxycast_processor::RequestContext;
+use proxycast_providers::providers::OpenAICustomProvider;
+use proxycast_server_utils::build_error_response_with_meta;
+use proxycast_services::voice_asr_service::AsrService;
+use proxycast_services::voice_config_service;
+use voice_core::types::{AudioData, TranscribeResult};
+
+/// 原始 PCM 上传的默认采样率
+const DEFAULT_PCM_SAMPLE_RATE: u32 = 16000;
+
+/// 默认语音合成模型
+const DEFAULT_TTS_MODEL: &str = "tts-1";
+
+/// 网关支持的语音合成服务商
+const SUPPORTED_TTS_SERVICE: &str = "openai";
+
+/// 原样转发的压缩音频格式（扩展名, MIME 类型），与 OpenAI Whisper API 支持的格式一致
+const ENCODED_FORMATS: &[(&str, &str)] = &[
+    ("mp3", "audio/mpeg"),
+    ("mpga", "audio/mpeg"),
+    ("mpeg", "audio/mpeg"),
+    ("m4a", "audio/mp4"),
+    ("mp4", "audio/mp4"),
+    ("webm", "audio/webm"),
+    ("ogg", "audio/ogg"),
+    ("oga", "audio/ogg"),
+    ("flac", "audio/flac"),
+];
+
+/// 识别结果输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptionFormat {
+    Json,
+    Text,
+    Srt,
+    Vtt,
+    VerboseJson,
+}
+
+impl TranscriptionFormat {
+    /// 解析 `response_format` 参数
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "json" => Some(Self::Json),
+            "text" => Some(Self::Text),
+            "srt" => Some(Self::Srt),
+            "vtt" => Some(Self::Vtt),
+            "verbose_json" => Some(Self::VerboseJson),
+            _ => None,
+        }
+    }
+}
+
+/// 识别请求（multipart 表单）
+#[derive(Debug, Default)]
+struct TranscriptionForm {
+    file: Option<Vec<u8>>,
+    file_name: Option<String>,
+    content_type: Option<String>,
+    model: String,
+    language: Option<String>,
+    response_format: Option<String>,
+    /// 原始 PCM 上传时的采样率（扩展参数）
+    sample_rate: Option<u32>,
+}
+
+fn error_response(status: StatusCode, message: &str, error_type: &str, code: &str) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": {
+                "message": message,
+                "type": error_type,
+                "code": code
+            }
+        })),
+    )
+        .into_response()
+}
+
+fn invalid_request(message: &str, code: &str) -> Response {
+    error_response(
+        StatusCode::BAD_REQUEST,
+        message,
+        "invalid_request_error",
+        code,
+    )
+}
+
+/// 音频请求的遥测上下文
+fn audio_context(model: String, credential_id: &str) -> RequestContext {
+    let mut ctx = RequestContext::new(model);
+    ctx.provider = Some(ProviderType::OpenAI);
+    ctx.credential_id = Some(credential_id.to_string());
+    // 音频耗时与对话模型不可比，不计入模型编排器的性能统计
+    ctx.set_metadata(SKIP_ORCHESTRATOR_STATS, serde_json::json!(true));
+    ctx
+}
+
+fn asr_backend_name(provider: AsrProviderType) -> &'static str {
+    match provider {
+        AsrProviderType::WhisperLocal => "whisper_local",
+        AsrProviderType::Xunfei => "xunfei",
+        AsrProviderType::Baidu => "baidu",
+        AsrProviderType::OpenAI => "openai",
+    }
+}
+
+async fn read_transcription_form(mut multipart: Multipart) -> Result<TranscriptionForm, String> {
+    let mut form = TranscriptionForm::default();
+    while let Some(field) = multipart.next_field().await.map_err(|e| e.to_string())? {
+        let name = field.name().unwrap_or_default().to_string();
+        match name.as_str() {
+            "file" => {
+                form.file_name = field.file_name().map(str::to_string);
+                form.content_type = field.content_type().map(str::to_string);
+                form.file = Some(field.bytes().await.map_err(|e| e.to_string())?.to_vec());
+            }
+            "model" => form.model = field.text().await.map_err(|e| e.to_string())?,
+            "language" => form.language = Some(field.text().await.map_err(|e| e.to_string())?),
+            "response_format" => {
+                form.response_format = Some(field.text().await.map_err(|e| e.to_string())?)
+            }
+            "sample_rate" => {
+                let value = field.text().await.map_err(|e| e.to_string())?;
+                form.sample_rate = Some(
+                    value
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("invalid sample_rate: {value}"))?,
+                );
+            }
+            // prompt、temperature、timestamp_granularities 等参数各后端不支持，忽略
+            _ => {}
+        }
+    }
+    Ok(form)
+}
+
+/// 上传的音频
+#[derive(Debug)]
+enum Upload {
+    /// 已解码的 PCM（WAV 或原始 PCM16）
+    Decoded(AudioData),
+    /// 压缩格式，原样转发给支持的上游
+    Encoded { file_name: String, mime: String },
+}
+
+/// 按文件名或 Content-Type 识别压缩音频格式
+fn encoded_format(form: &TranscriptionForm) -> Option<(&'static str, &'static str)> {
+    let by_extension = form
+        .file_name
+        .as_deref()
+        .and_then(|name| name.rsplit_once('.'))
+        .and_then(|(_, ext)| {
+            ENCODED_FORMATS
+                .iter()
+                .find(|(known, _)| known.eq_ignore_ascii_case(ext))
+        });
+    let by_content_type = || {
+        let content_type = form.content_type.as_deref()?.to_ascii_lowercase();
+        let mime = content_type.split(';').next()?.trim().to_string();
+        let mime = match mime.as_str() {
+            "audio/mp3" => "audio/mpeg".to_string(),
+            "audio/x-m4a" | "audio/m4a" => "audio/mp4".to_string(),
+            "video/webm" => "audio/webm".to_string(),
+            "audio/x-flac" => "audio/flac".to_string(),
+            _ => mime,
+        };
+        ENCODED_FORMATS.iter().find(|(_, known)| *known == mime)
+    };
+    by_extension.or_else(by_content_type).copied()
+}
+
+/// 解码上传的音频
+///
+/// WAV 和带 `sample_rate` 的原始 PCM16 LE 在网关解码；mp3、m4a、webm 等压缩格式不解码，
+/// 由调用方原样转发。
+fn decode_upload(form: &TranscriptionForm, bytes: &[u8]) -> Result<Upload, String> {
+    let is_pcm = form
+        .file_name
+        .as_deref()
+        .is_some_and(|name| name.to_ascii_lowercase().ends_with(".pcm"))
+        || form
+            .content_type
+            .as_deref()
+            .is_some_and(|ct| ct.starts_with("audio/pcm") || ct.starts_with("audio/l16"));
+
+    if is_pcm {
+        let sample_rate = form.sample_rate.unwrap_or(DEFAULT_PCM_SAMPLE_RATE);
+        return Ok(Upload::Decoded(AudioData::from_pcm16le_bytes(
+            bytes,
+            sample_rate,
+            1,
+        )));
+    }
+    if bytes.starts_with(b"RIFF") {
+        return AudioData::from_wav_bytes(bytes)
+            .map(Upload::Decoded)
+            .map_err(|e| format!("Invalid WAV file: {e}"));
+    }
+
+    match encoded_format(form) {
+        Some((extension, mime)) => Ok(Upload::Encoded {
+            file_name: form
+                .file_name
+                .clone()
+                .unwrap_or_else(|| format!("audio.{extension}")),
+            mime: mime.to_string(),
+        }),
+        None => Err(format!(
+            "Unsupported audio file, supported formats: wav, pcm, {}",
+            ENCODED_FORMATS
+                .iter()
+                .map(|(extension, _)| *extension)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// 检查配置的语音合成服务商是否由网关支持
+fn check_tts_service(service: Option<&str>) -> Result<(), String> {
+    match service.map(str::trim).filter(|s| !s.is_empty()) {
+        None => Ok(()),
+        Some(service) if service.eq_ignore_ascii_case(SUPPORTED_TTS_SERVICE) => Ok(()),
+        Some(service) => Err(format!(
+            "TTS service '{service}' is not supported by the gateway, /v1/audio/speech only supports OpenAI-compatible credentials"
+        )),
+    }
+}
+
+/// 格式化字幕时间戳
+fn format_timestamp(secs: f32, decimal_separator: char) -> String {
+    let total_ms = (secs.max(0.0) * 1000.0).round() as u64;
+    let (hours, rest) = (total_ms / 3_600_000, total_ms % 3_600_000);
+    let (minutes, rest) = (rest / 60_000, rest % 60_000);
+    let (seconds, millis) = (rest / 1000, rest % 1000);
+    format!("{hours:02}:{minutes:02}:{seconds:02}{decimal_separator}{millis:03}")
+}
+
+/// 字幕分段，后端未返回分段时整段作为一条
+fn subtitle_cues(result: &TranscribeResult, duration_secs: f32) -> Vec<(f32, f32, String)> {
+    if result.segments.is_empty() {
+        if result.text.trim().is_empty() {
+            return Vec::new();
+        }
+        return vec![(0.0, duration_secs, result.text.trim().to_string())];
+    }
+    result
+        .segments
+        .iter()
+        .filter(|segment| !segment.text.trim().is_empty())
+        .map(|segment| (segment.start, segment.end, segment.text.trim().to_string()))
+        .collect()
+}
+
+/// 输出 SRT 字幕
+pub fn format_srt(result: &TranscribeResult, duration_secs: f32) -> String {
+    subtitle_cues(result, duration_secs)
+        .into_iter()
+        .enumerate()
+        .map(|(index, (start, end, text))| {
+            format!(
+                "{}\n{} --> {}\n{}\n",
+                index + 1,
+                format_timestamp(start, ','),
+                format_timestamp(end, ','),
+                text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 输出 WebVTT 字幕
+pub fn format_vtt(result: &TranscribeResult, duration_secs: f32) -> String {
+    let mut output = String::from("WEBVTT\n");
+    for (start, end, text) in subtitle_cues(result, duration_secs) {
+        output.push_str(&format!(
+            "\n{} --> {}\n{}\n",
+            format_timestamp(start, '.'),
+            format_timestamp(end, '.'),
+            text
+        ));
+    }
+    output
+}
+
+/// 输出 verbose_json
+pub fn format_verbose_json(result: &TranscribeResult, duration_secs: f32) -> serde_json::Value {
+    let segments: Vec<_> = result
+        .segments
+        .iter()
+        .enumerate()
+        .map(|(id, segment)| {
+            serde_json::json!({
+                "id": id,
+                "start": segment.start,
+                "end": segment.end,
+                "text": segment.text,
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "task": "transcribe",
+        "language": result.language,
+        "duration": duration_secs,
+        "text": result.text,
+        "segments": segments,
+    })
+}
+
+fn transcription_response(
+    format: TranscriptionFormat,
+    result: &TranscribeResult,
+    duration_secs: f32,
+) -> Response {
+    let text_response = |content_type: &'static str, body: String| {
+        ([(header::CONTENT_TYPE, content_type)], body).into_response()
+    };
+    match format {
+        TranscriptionFormat::Json => {
+            Json(serde_json::json!({ "text": result.text })).into_response()
+        }
+        TranscriptionFormat::VerboseJson => {
+            Json(format_verbose_json(result, duration_secs)).into_response()
+        }
+        TranscriptionFormat::Text => {
+            text_response("text/plain; charset=utf-8", result.text.clone())
+        }
+        TranscriptionFormat::Srt => text_response(
+            "application/x-subrip; charset=utf-8",
+            format_srt(result, duration_secs),
+        ),
+        TranscriptionFormat::Vtt => {
+            text_response("text/vtt; charset=utf-8", format_vtt(result, duration_secs))
+        }
+    }
+}
+
+/// 处理语音识别请求
+///
+/// # 端点
+/// `POST /v1/audio/transcriptions`（multipart/form-data）
+///
+/// # 表单字段
+/// - `file`: WAV 音频，或原始 PCM16 LE（`.pcm` 文件名或 `audio/pcm` 类型）；
+///   mp3、m4a、webm 等压缩格式原样转发，仅 OpenAI 凭证支持
+/// - `model`: ASR 凭证 ID、Provider 名称（`whisper_local`/`xunfei`/`baidu`/`openai`），
+///   其它值（如 `whisper-1`）使用默认 ASR 凭证
+/// - `language`: 可选，覆盖凭证配置的识别语言
+/// - `response_format`: `json`（默认）、`text`、`srt`、`vtt`、`verbose_json`
+/// - `sample_rate`: 可选，原始 PCM 的采样率（默认 16000）
+pub async fn handle_audio_transcription(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    multipart: Multipart,
+) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+
+    let form = match read_transcription_form(multipart).await {
+        Ok(form) => form,
+        Err(e) => return invalid_request(&format!("Invalid multipart body: {e}"), "invalid_form"),
+    };
+
+    let format = match form.response_format.as_deref() {
+        None => TranscriptionFormat::Json,
+        Some(value) => match TranscriptionFormat::parse(value) {
+            Some(format) => format,
+            None => {
+                return invalid_request(
+                    &format!("Unsupported response_format: {value}"),
+                    "invalid_response_format",
+                )
+            }
+        },
+    };
+
+    let Some(bytes) = form.file.as_deref() else {
+        return invalid_request("file is required", "missing_file");
+    };
+    let upload = match decode_upload(&form, bytes) {
+        Ok(Upload::Decoded(audio)) if audio.samples.is_empty() => {
+            return invalid_request("Audio file is empty", "invalid_file")
+        }
+        Ok(upload) => upload,
+        Err(e) => return invalid_request(&e, "invalid_file"),
+    };
+
+    let mut credential = match AsrService::resolve_credential(&form.model) {
+        Ok(Some(credential)) => credential,
+        Ok(None) => {
+            return error_response(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "No ASR credentials configured for transcription",
+                "server_error",
+                "no_credentials",
+            )
+        }
+        Err(e) => {
+            return error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &format!("Failed to load ASR credentials: {e}"),
+                "server_error",
+                "config_error",
+            )
+        }
+    };
+    if let Some(language) = form.language.clone().filter(|l| !l.trim().is_empty()) {
+        credential.language = language;
+    }
+
+    let backend = asr_backend_name(credential.provider);
+    if matches!(upload, Upload::Encoded { .. })
+        && !matches!(credential.provider, AsrProviderType::OpenAI)
+    {
+        return invalid_request(
+            &format!(
+                "ASR backend {backend} only accepts WAV or raw PCM16 audio, compressed formats require an OpenAI ASR credential"
+            ),
+            "unsupported_file_format",
+        );
+    }
+
+    let ctx = audio_context(format!("asr/{backend}"), &credential.id);
+    let size = match &upload {
+        Upload::Decoded(audio) => format!("duration={:.2}s", audio.duration_secs),
+        Upload::Encoded { mime, .. } => format!("type={mime}, bytes={}", bytes.len()),
+    };
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[AUDIO] 收到语音识别请求: model={}, backend={}, credential={}, {}, format={:?}",
+            form.model, backend, credential.id, size, format
+        ),
+    );
+
+    let transcribed = match &upload {
+        Upload::Decoded(audio) => AsrService::transcribe_audio(&credential, audio).await,
+        Upload::Encoded { file_name, mime } => {
+            AsrService::transcribe_file(&credential, bytes.to_vec(), file_name, mime).await
+        }
+    };
+    match transcribed {
+        Ok(result) => {
+            record_request_telemetry(&state, &ctx, RequestStatus::Success, None);
+            // 压缩格式未解码，时长取识别分段的结束时间
+            let duration_secs = match &upload {
+                Upload::Decoded(audio) => audio.duration_secs,
+                Upload::Encoded { .. } => result.segments.last().map_or(0.0, |s| s.end),
+            };
+            transcription_response(format, &result, duration_secs)
+        }
+        Err(e) => {
+            state
+                .logs
+                .write()
+                .await
+                .add("error", &format!("[AUDIO] 语音识别失败: {e}"));
+            record_request_telemetry(&state, &ctx, RequestStatus::Failed, Some(e.clone()));
+            error_response(
+                StatusCode::BAD_GATEWAY,
+                &format!("Transcription failed: {e}"),
+                "server_error",
+                "transcription_failed",
+            )
+        }
+    }
+}
+
+/// 处理语音合成请求
+///
+/// # 端点
+/// `POST /v1/audio/speech`
+///
+/// # 请求格式
+/// ```json
+/// {
+///   "model": "tts-1",
+///   "input": "你好",
+///   "voice": "alloy",
+///   "response_format": "mp3"
+/// }
+/// ```
+///
+/// 请求体原样转发给凭证池中的 OpenAI 兼容凭证，响应为音频数据流。
+/// 网关只实现 OpenAI 兼容的语音合成：`voice.tts_service` 配置为其它服务商
+/// （Azure、Google 等）时返回 501，而不是改用 OpenAI 凭证合成。
+pub async fn handle_audio_speech(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(mut request): Json<serde_json::Value>,
+) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+        return e.into_response();
+    }
+
+    let configured_service = voice_config_service::get_tts_service().unwrap_or_else(|e| {
+        tracing::warn!("[AUDIO] 读取语音合成配置失败: {}", e);
+        None
+    });
+    if let Err(message) = check_tts_service(configured_service.as_deref()) {
+        return error_response(
+            StatusCode::NOT_IMPLEMENTED,
+            &message,
+            "invalid_request_error",
+            "unsupported_tts_service",
+        );
+    }
+
+    let input_chars = match request["input"].as_str() {
+        Some(input) if !input.trim().is_empty() => input.chars().count(),
+        _ => return invalid_request("input is required and cannot be empty", "invalid_input"),
+    };
+    let model = request["model"]
+        .as_str()
+        .filter(|m| !m.is_empty())
+        .unwrap_or(DEFAULT_TTS_MODEL)
+        .to_string();
+    request["model"] = serde_json::Value::String(model.clone());
+
+    // 内容安全检查：输出是音频，令牌无法还原，tokenize 规则按 redact 处置
+    let request_id = uuid::Uuid::new_v4().to_string();
+    if let Err(message) = apply_guardrails_redacting(
+        &state,
+        &request_id,
+        "/v1/audio/speech",
+        &model,
+        &mut request,
+    )
+    .await
+    {
+        return build_error_response_with_meta(
+            StatusCode::BAD_REQUEST.as_u16(),
+            &message,
+            Some(&request_id),
+            None,
+            Some(GatewayErrorCode::ContentBlocked),
+        );
+    }
+
+    let Some(db) = &state.db else {
+        return error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database not available",
+            "server_error",
+            "database_unavailable",
+        );
+    };
+
+    // 只有 OpenAI 兼容凭证提供语音合成
+    let credential = match state.pool_service.select_credential(db, "openai", None) {
+        Ok(Some(credential)) => credential,
+        Ok(None) => {
+            return error_response(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "No OpenAI-compatible credentials available for speech synthesis",
+                "server_error",
+                "no_credentials",
+            )
+        }
+        Err(e) => {
+            return error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &format!("Failed to get credentials: {e}"),
+                "server_error",
+                "credential_error",
+            )
+        }
+    };
+    let CredentialData::OpenAIKey { api_key, base_url } = &credential.credential else {
+        return error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Selected credential does not support speech synthesis",
+            "server_error",
+            "unsupported_credential",
+        );
+    };
+
+    let ctx = audio_context(model.clone(), &credential.uuid);
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[AUDIO] 收到语音合成请求: model={}, credential={}, input_chars={}",
+            model, credential.uuid, input_chars
+        ),
+    );
+
+    let provider = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
+    let resp = match provider.audio_speech(&request).await {
+        Ok(resp) => resp,
+        Err(e) => {
+            let message = e.to_string();
+            state
+                .pool_service
+                .mark_unhealthy_in_background(db, &credential.uuid, Some(&message));
+            record_request_telemetry(&state, &ctx, RequestStatus::Failed, Some(message.clone()));
+            return error_response(
+                StatusCode::BAD_GATEWAY,
+                &format!("Speech synthesis failed: {message}"),
+                "server_error",
+                "api_error",
+            );
+        }
+    };
+
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        if status.is_server_error()
+            || status == StatusCode::UNAUTHORIZED
+            || status == StatusCode::FORBIDDEN
+        {
+            state
+                .pool_service
+                .mark_unhealthy_in_background(db, &credential.uuid, Some(&body));
+        }
+        record_request_telemetry(&state, &ctx, RequestStatus::Failed, Some(body.clone()));
+        return (status, [(header::CONTENT_TYPE, "application/json")], body).into_response();
+    }
+
+    state
+        .pool_service
+        .mark_healthy_in_background(db, &credential.uuid, Some(&model));
+    state
+        .pool_service
+        .record_usage_in_background(db, &credential.uuid);
+    record_request_telemetry(&state, &ctx, RequestStatus::Success, None);
+
+    let content_type = resp
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .cloned()
+        .unwrap_or_else(|| header::HeaderValue::from_static("audio/mpeg"));
+    (
+        [(header::CONTENT_TYPE, content_type)],
+        Body::from_stream(resp.bytes_stream()),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use voice_core::types::Segment;
+
+    fn result_with_segments() -> TranscribeResult {
+        TranscribeResult {
+            text: "你好 世界".to_string(),
+            language: Some("zh".to_string()),
+            confidence: None,
+            segments: vec![
+                Segment {
+                    start: 0.0,
+                    end: 1.5,
+                    text: " 你好".to_string(),
+                },
+                Segment {
+                    start: 1.5,
+                    end: 3661.25,
+                    text: "世界".to_string(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0.0, ','), "00:00:00,000");
+        assert_eq!(format_timestamp(3661.25, '.'), "01:01:01.250");
+        assert_eq!(format_timestamp(-1.0, ','), "00:00:00,000");
+    }
+
+    #[test]
+    fn test_format_srt() {
+        let srt = format_srt(&result_with_segments(), 4000.0);
+        assert_eq!(
+            srt,
+            "1\n00:00:00,000 --> 00:00:01,500\n你好\n\n2\n00:00:01,500 --> 01:01:01,250\n世界\n"
+        );
+    }
+
+    #[test]
+    fn test_format_vtt_without_segments() {
+        let result = TranscribeResult {
+            text: "你好".to_string(),
+            language: None,
+            confidence: None,
+            segments: Vec::new(),
+        };
+        assert_eq!(
+            format_vtt(&result, 2.0),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:02.000\n你好\n"
+        );
+        assert_eq!(
+            format_srt(
+                &TranscribeResult {
+                    text: " ".to_string(),
+                    ..result
+                },
+                2.0
+            ),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_format_verbose_json() {
+        let json = format_verbose_json(&result_with_segments(), 4.0);
+        assert_eq!(json["task"], "transcribe");
+        assert_eq!(json["language"], "zh");
+        assert_eq!(json["segments"][1]["id"], 1);
+        assert_eq!(json["segments"][1]["start"], 1.5);
+    }
+
+    #[test]
+    fn test_parse_response_format() {
+        assert_eq!(
+            TranscriptionFormat::parse("verbose_json"),
+            Some(TranscriptionFormat::VerboseJson)
+        );
+        assert_eq!(
+            TranscriptionFormat::parse("srt"),
+            Some(TranscriptionFormat::Srt)
+        );
+        assert_eq!(TranscriptionFormat::parse("xml"), None);
+    }
+
+    #[test]
+    fn test_decode_pcm_upload() {
+        let form = TranscriptionForm {
+            file_name: Some("clip.PCM".to_string()),
+            sample_rate: Some(8000),
+            ..Default::default()
+        };
+        let Upload::Decoded(audio) = decode_upload(&form, &[0x10, 0x00, 0xff, 0xff]).unwrap()
+        else {
+            panic!("pcm upload should be decoded");
+        };
+        assert_eq!(audio.sample_rate, 8000);
+        assert_eq!(audio.samples, vec![16, -1]);
+    }
+
+    #[test]
+    fn test_encoded_upload_is_forwarded() {
+        let form = TranscriptionForm {
+            file_name: Some("clip.MP3".to_string()),
+            ..Default::default()
+        };
+        match decode_upload(&form, b"ID3").unwrap() {
+            Upload::Encoded { file_name, mime } => {
+                assert_eq!(file_name, "clip.MP3");
+                assert_eq!(mime, "audio/mpeg");
+            }
+            other => panic!("unexpected upload: {other:?}"),
+        }
+
+        // 没有文件名时按 Content-Type 识别
+        let form = TranscriptionForm {
+            content_type: Some("audio/x-m4a".to_string()),
+            ..Default::default()
+        };
+        match decode_upload(&form, b"\0\0\0\x20ftyp").unwrap() {
+            Upload::Encoded { file_name, mime } => {
+                assert_eq!(file_name, "audio.m4a");
+                assert_eq!(mime, "audio/mp4");
+            }
+            other => panic!("unexpected upload: {other:?}"),
+        }
+
+        let form = TranscriptionForm {
+            file_name: Some("clip.aiff".to_string()),
+            ..Default::default()
+        };
+        assert!(decode_upload(&form, b"FORM").is_err());
+        // RIFF 头按 WAV 解码，损坏时报错而不是转发
+        assert!(decode_upload(&form, b"RIFF\0\0").is_err());
+    }
+
+    #[test]
+    fn test_check_tts_service() {
+        assert!(check_tts_service(None).is_ok());
+        assert!(check_tts_service(Some("")).is_ok());
+        assert!(check_tts_service(Some("OpenAI")).is_ok());
+        assert!(check_tts_service(Some("azure"))
+            .unwrap_err()
+            .contains("azure"));
+    }
+}
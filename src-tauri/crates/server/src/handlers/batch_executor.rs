@@ -10,6 +10,7 @@ use proxycast_core::models::openai::{
     ChatCompletionRequest, ChatCompletionResponse, ChatMessage, MessageContent,
 };
 use proxycast_scheduler::{BatchTaskDao, BatchTaskStatus, TaskResult, TemplateDao, TokenUsage};
+use proxycast_services::concurrency_limiter::QueueClient;
 use tokio::sync::RwLock;
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
@@ -375,21 +376,22 @@ impl BatchTaskExecutor {
     ) -> Result<(String, TokenUsage), String> {
         let db = state.db.as_ref().ok_or("数据库未初始化")?;
 
-        // 选择凭证
-        let credential = state
+        // 选择凭证并获取并发槽位（批量任务优先级低于交互式请求）
+        let (credential, _permit) = state
             .pool_service
-            .select_credential_with_fallback(
+            .acquire_credential_with_fallback(
                 db,
                 &state.api_key_service,
                 "",
                 Some(&request.model),
                 None,
                 None,
+                &QueueClient::batch("batch"),
             )
             .await?
             .ok_or_else(|| format!("没有可用的凭证来调用模型: {}", request.model))?;
 
-        // 调用 provider
+        // 调用 provider（非流式，读取完响应体后释放并发槽位）
         let response =
             super::provider_calls::call_provider_openai(state, &credential, request, None).await;
 
@@ -161,11 +161,25 @@ async fn try_select_oauth_credential(
     request: &SelectCredentialRequest,
 ) -> Result<Option<CredentialResponse>, CredentialApiError> {
     // 使用 ProviderPoolService 智能选择凭证
-    let credential = match state.pool_service.select_credential(
-        db,
-        &request.provider_type,
-        request.model.as_deref(),
-    ) {
+    let selected = match &state.db_pool {
+        Some(pool) => {
+            state
+                .pool_service
+                .select_credential_pooled(
+                    pool,
+                    &request.provider_type,
+                    request.model.as_deref(),
+                    None,
+                )
+                .await
+        }
+        None => state.pool_service.select_credential(
+            db,
+            &request.provider_type,
+            request.model.as_deref(),
+        ),
+    };
+    let credential = match selected {
         Ok(Some(cred)) => cred,
         Ok(None) => return Ok(None),
         Err(_) => return Ok(None),
@@ -220,7 +234,15 @@ async fn try_select_api_key_credential(
 
     for provider_id in candidate_provider_ids {
         // 尝试获取下一个可用的 API Key
-        let (key_id, api_key) = match api_key_service.get_next_api_key_entry(db, &provider_id) {
+        let next_key = match &state.db_pool {
+            Some(pool) => {
+                api_key_service
+                    .get_next_api_key_entry_pooled(pool, &provider_id)
+                    .await
+            }
+            None => api_key_service.get_next_api_key_entry(db, &provider_id),
+        };
+        let (key_id, api_key) = match next_key {
             Ok(Some((id, key))) => (id, key),
             Ok(None) => continue,
             Err(_) => continue,
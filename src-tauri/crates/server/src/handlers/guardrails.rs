@@ -0,0 +1,567 @@
+//! 内容安全检查与令牌还原
+//!
+//! 请求侧按 `guardrails` 配置检查消息内容（见 `proxycast_core::guardrails`），
+//! 命中 `tokenize` 规则时，在响应返回客户端前把令牌还原为原文：
+//! 非流式响应整体还原，流式响应逐块还原（被拆到多个增量中的令牌会暂存到下一块再还原）。
+//! 输出无法还原令牌的端点（如语音合成）使用 [`apply_guardrails_redacting`]。
+
+use crate::AppState;
+use axum::body::{Body, Bytes};
+use axum::http::header;
+use axum::response::Response;
+use futures::StreamExt;
+use proxycast_core::guardrails::{GuardrailEngine, GuardrailReport, TokenVault};
+use proxycast_providers::streaming::SseFramer;
+use proxycast_server_utils::build_error_response_with_status;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 读取非流式响应体的上限
+const MAX_RESPONSE_BYTES: usize = 32 * 1024 * 1024;
+
+/// 流式响应中可能包含令牌的文本增量字段
+const STREAM_TEXT_POINTERS: &[&str] = &[
+    "/choices/0/delta/content",
+    "/choices/0/delta/reasoning_content",
+    "/choices/0/delta/tool_calls/0/function/arguments",
+    "/delta/text",
+    "/delta/thinking",
+    "/delta/partial_json",
+];
+
+/// 内容为 JSON 字符串片段的字段（原文需按 JSON 转义后还原）
+const JSON_FRAGMENT_KEYS: &[&str] = &["arguments", "partial_json"];
+
+/// 检查请求内容
+///
+/// 返回本次请求的令牌表（未令牌化时为 `None`）；命中拦截规则时返回错误信息，
+/// 由调用方按所在协议构造 `CONTENT_BLOCKED` 错误。
+pub async fn apply_guardrails<T: Serialize + DeserializeOwned>(
+    state: &AppState,
+    request_id: &str,
+    endpoint: &str,
+    model: &str,
+    request: &mut T,
+) -> Result<Option<Arc<TokenVault>>, String> {
+    guard_request(state, request_id, endpoint, model, request, true).await
+}
+
+/// 检查输出无法还原令牌的请求（如语音合成）
+///
+/// `tokenize` 规则按 `redact` 处置，避免令牌原样出现在输出中；其余与 [`apply_guardrails`] 相同。
+pub async fn apply_guardrails_redacting<T: Serialize + DeserializeOwned>(
+    state: &AppState,
+    request_id: &str,
+    endpoint: &str,
+    model: &str,
+    request: &mut T,
+) -> Result<(), String> {
+    guard_request(state, request_id, endpoint, model, request, false)
+        .await
+        .map(|_| ())
+}
+
+async fn guard_request<T: Serialize + DeserializeOwned>(
+    state: &AppState,
+    request_id: &str,
+    endpoint: &str,
+    model: &str,
+    request: &mut T,
+    restorable: bool,
+) -> Result<Option<Arc<TokenVault>>, String> {
+    let engine = state.processor.guardrails.read().clone();
+    if !engine.is_active() {
+        return Ok(None);
+    }
+    let Some((payload, report)) = inspect_request(&engine, &*request, restorable)? else {
+        return Ok(None);
+    };
+
+    state
+        .processor
+        .guardrail_audit
+        .record(request_id, endpoint, model, &report);
+    let hits = report
+        .hits
+        .iter()
+        .map(|hit| format!("{}:{:?}x{}", hit.rule, hit.action, hit.count))
+        .collect::<Vec<_>>()
+        .join(",");
+    state.logs.write().await.add(
+        if report.is_blocked() { "warn" } else { "info" },
+        &format!("[GUARDRAIL] request_id={request_id} endpoint={endpoint} hits={hits}"),
+    );
+
+    if report.is_blocked() {
+        return Err(format!(
+            "请求内容违反内容安全策略（规则: {}）",
+            report.blocked_rules().join(", ")
+        ));
+    }
+    // 处置后的请求无法还原为原类型时拒绝请求，避免把未处置的内容发往上游
+    *request =
+        serde_json::from_value(payload).map_err(|e| format!("内容安全处理后请求格式无效: {e}"))?;
+    Ok((!report.vault.is_empty()).then(|| Arc::new(report.vault)))
+}
+
+/// 按规则处置请求体，未命中时返回 `None`
+///
+/// 请求无法转换为 JSON 时拒绝请求，而不是跳过检查把原始内容发往上游。
+fn inspect_request<T: Serialize>(
+    engine: &GuardrailEngine,
+    request: &T,
+    restorable: bool,
+) -> Result<Option<(Value, GuardrailReport)>, String> {
+    let mut payload =
+        serde_json::to_value(request).map_err(|e| format!("内容安全检查无法解析请求: {e}"))?;
+    let report = if restorable {
+        engine.apply_to_payload(&mut payload)
+    } else {
+        engine.apply_to_payload_redacting(&mut payload)
+    };
+    Ok(report.has_hits().then_some((payload, report)))
+}
+
+/// 还原 JSON 值中的令牌
+pub fn restore_value(value: &mut Value, vault: &TokenVault) {
+    match value {
+        Value::String(text) => *text = vault.restore(text),
+        Value::Array(items) => {
+            for item in items {
+                restore_value(item, vault);
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                match item {
+                    Value::String(text) if JSON_FRAGMENT_KEYS.contains(&key.as_str()) => {
+                        *text = vault.restore_json_fragment(text);
+                    }
+                    _ => restore_value(item, vault),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 还原响应中的令牌
+pub async fn restore_guardrail_tokens(
+    response: Response,
+    vault: Option<Arc<TokenVault>>,
+) -> Response {
+    let Some(vault) = vault else {
+        return response;
+    };
+    let is_stream = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    let (mut parts, body) = response.into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+
+    if is_stream {
+        let stream = async_stream::stream! {
+            let mut restorer = TokenRestoreStream::new(vault);
+            let mut source = body.into_data_stream();
+            while let Some(chunk) = source.next().await {
+                match chunk {
+                    Ok(bytes) => {
+                        for event in restorer.push(&bytes) {
+                            yield Ok::<Bytes, axum::Error>(Bytes::from(event));
+                        }
+                    }
+                    Err(e) => {
+                        yield Err(e);
+                        return;
+                    }
+                }
+            }
+            for event in restorer.finish() {
+                yield Ok(Bytes::from(event));
+            }
+        };
+        return Response::from_parts(parts, Body::from_stream(stream));
+    }
+
+    let bytes = match axum::body::to_bytes(body, MAX_RESPONSE_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("[GUARDRAIL] 读取响应失败: {}", e);
+            return build_error_response_with_status(502, &format!("读取上游响应失败: {e}"));
+        }
+    };
+    let body = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(mut json) => {
+            restore_value(&mut json, &vault);
+            json.to_string()
+        }
+        Err(_) => vault.restore(&String::from_utf8_lossy(&bytes)),
+    };
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// 暂存的未完整令牌
+struct PendingText {
+    /// 所在内容块（Anthropic 的 `index` 或 OpenAI 的 `tool_calls[0].index`）
+    block: Value,
+    text: String,
+    /// 用于补发暂存内容的事件模板
+    template: Value,
+    event_lines: Vec<String>,
+}
+
+/// SSE 流的令牌还原
+struct TokenRestoreStream {
+    vault: Arc<TokenVault>,
+    framer: SseFramer,
+    pending: HashMap<&'static str, PendingText>,
+}
+
+impl TokenRestoreStream {
+    fn new(vault: Arc<TokenVault>) -> Self {
+        Self {
+            vault,
+            framer: SseFramer::default(),
+            pending: HashMap::new(),
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        let mut out = Vec::new();
+        for event in self.framer.push(bytes) {
+            self.convert_event(event, &mut out);
+        }
+        out
+    }
+
+    fn finish(&mut self) -> Vec<String> {
+        let mut out = Vec::new();
+        if let Some(event) = self.framer.finish() {
+            self.convert_event(event, &mut out);
+        }
+        self.flush_all(&mut out);
+        out
+    }
+
+    fn convert_event(&mut self, event: String, out: &mut Vec<String>) {
+        let data: String = event
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(str::trim_start)
+            .collect();
+        let Ok(mut chunk) = serde_json::from_str::<Value>(&data) else {
+            // [DONE]、注释等非 JSON 事件之前先补发暂存内容
+            self.flush_all(out);
+            out.push(event);
+            return;
+        };
+        let event_lines: Vec<String> = event
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with("data:"))
+            .map(str::to_string)
+            .collect();
+
+        let pointer = STREAM_TEXT_POINTERS
+            .iter()
+            .copied()
+            .find(|pointer| chunk.pointer(pointer).is_some_and(Value::is_string));
+        let Some(pointer) = pointer else {
+            self.flush_all(out);
+            restore_value(&mut chunk, &self.vault);
+            out.push(render_event(&event_lines, &chunk));
+            return;
+        };
+
+        let block = block_key(&chunk);
+        if self
+            .pending
+            .get(pointer)
+            .is_some_and(|pending| pending.block != block)
+        {
+            self.flush(pointer, out);
+        }
+        let mut text = self
+            .pending
+            .remove(pointer)
+            .map(|pending| pending.text)
+            .unwrap_or_default();
+        text.push_str(
+            chunk
+                .pointer(pointer)
+                .and_then(Value::as_str)
+                .unwrap_or_default(),
+        );
+
+        // 带 finish_reason 的块之后不再有增量，不暂存
+        let closing = chunk
+            .pointer("/choices/0/finish_reason")
+            .is_some_and(|reason| !reason.is_null());
+        let split = if closing {
+            text.len()
+        } else {
+            self.vault.pending_start(&text).unwrap_or(text.len())
+        };
+        let rest = text.split_off(split);
+        if let Some(field) = chunk.pointer_mut(pointer) {
+            *field = Value::String(self.restore(pointer, &text));
+        }
+        if !rest.is_empty() {
+            self.pending.insert(
+                pointer,
+                PendingText {
+                    block,
+                    text: rest,
+                    template: chunk.clone(),
+                    event_lines: event_lines.clone(),
+                },
+            );
+        }
+        out.push(render_event(&event_lines, &chunk));
+    }
+
+    fn restore(&self, pointer: &str, text: &str) -> String {
+        let is_fragment = JSON_FRAGMENT_KEYS
+            .iter()
+            .any(|key| pointer.ends_with(&format!("/{key}")));
+        if is_fragment {
+            self.vault.restore_json_fragment(text)
+        } else {
+            self.vault.restore(text)
+        }
+    }
+
+    fn flush(&mut self, pointer: &'static str, out: &mut Vec<String>) {
+        let Some(pending) = self.pending.remove(pointer) else {
+            return;
+        };
+        // 补发块只保留暂存字段，避免重复下发角色、工具名等增量
+        let text = self.restore(pointer, &pending.text);
+        let (delta_pointer, field) = pointer.rsplit_once('/').unwrap_or_default();
+        let delta_pointer = delta_pointer
+            .split_once("/tool_calls")
+            .map_or(delta_pointer, |(delta, _)| delta);
+        let mut chunk = pending.template;
+        if let Some(obj) = chunk.as_object_mut() {
+            obj.remove("usage");
+        }
+        let mut delta = serde_json::json!({});
+        if let Some(kind) = chunk.pointer(&format!("{delta_pointer}/type")) {
+            delta["type"] = kind.clone();
+        }
+        if pointer.contains("/tool_calls/") {
+            delta["tool_calls"] = serde_json::json!([
+                {"index": pending.block, "function": {"arguments": text}}
+            ]);
+        } else {
+            delta[field] = Value::String(text);
+        }
+        if let Some(slot) = chunk.pointer_mut(delta_pointer) {
+            *slot = delta;
+        }
+        out.push(render_event(&pending.event_lines, &chunk));
+    }
+
+    fn flush_all(&mut self, out: &mut Vec<String>) {
+        for pointer in STREAM_TEXT_POINTERS.iter().copied() {
+            self.flush(pointer, out);
+        }
+    }
+}
+
+fn block_key(chunk: &Value) -> Value {
+    chunk
+        .get("index")
+        .or_else(|| chunk.pointer("/choices/0/delta/tool_calls/0/index"))
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
+fn render_event(event_lines: &[String], chunk: &Value) -> String {
+    let mut event = String::new();
+    for line in event_lines {
+        event.push_str(line);
+        event.push('\n');
+    }
+    event.push_str(&format!("data: {chunk}\n\n"));
+    event
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+    use serde_json::json;
+
+    fn vault() -> Arc<TokenVault> {
+        let mut vault = TokenVault::new();
+        vault.tokenize("EMAIL", "a\"b@example.com");
+        vault.tokenize("PHONE", "13812345678");
+        Arc::new(vault)
+    }
+
+    fn data(events: &[String]) -> Vec<Value> {
+        events
+            .iter()
+            .filter_map(|e| e.lines().find_map(|l| l.strip_prefix("data: ")))
+            .filter_map(|d| serde_json::from_str(d).ok())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_restore_non_stream_response() {
+        let body = json!({
+            "choices": [{"message": {
+                "content": "mail [PII_EMAIL_1]",
+                "tool_calls": [{"function": {
+                    "name": "send",
+                    "arguments": "{\"to\":\"[PII_EMAIL_1]\"}"
+                }}]
+            }}]
+        });
+        let response = (StatusCode::OK, axum::Json(body)).into_response();
+        let response = restore_guardrail_tokens(response, Some(vault())).await;
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&bytes).unwrap();
+        let message = &json["choices"][0]["message"];
+        assert_eq!(message["content"], "mail a\"b@example.com");
+        let args: Value = serde_json::from_str(
+            message["tool_calls"][0]["function"]["arguments"]
+                .as_str()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(args["to"], "a\"b@example.com");
+    }
+
+    #[test]
+    fn test_restore_split_tokens_in_openai_stream() {
+        let chunk = |content: &str| {
+            format!(
+                "data: {}\n\n",
+                json!({"id": "c1", "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": null}]})
+            )
+        };
+        let upstream = [
+            chunk("call [PII_PH"),
+            chunk("ONE_1] or [PII"),
+            "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n"
+                .to_string(),
+            "data: [DONE]\n\n".to_string(),
+        ]
+        .concat();
+
+        let mut restorer = TokenRestoreStream::new(vault());
+        let mut events = Vec::new();
+        for bytes in upstream.as_bytes().chunks(5) {
+            events.extend(restorer.push(bytes));
+        }
+        events.extend(restorer.finish());
+        assert_eq!(events.last().unwrap(), "data: [DONE]\n\n");
+
+        let chunks = data(&events);
+        let text: String = chunks
+            .iter()
+            .filter_map(|c| c["choices"][0]["delta"]["content"].as_str())
+            .collect();
+        // 未知的不完整令牌在流结束前原样补发
+        assert_eq!(text, "call 13812345678 or [PII");
+        assert_eq!(
+            chunks.last().unwrap()["choices"][0]["finish_reason"],
+            "stop"
+        );
+    }
+
+    #[test]
+    fn test_restore_anthropic_stream_keeps_event_names() {
+        let delta = |index: u32, delta: Value| {
+            format!(
+                "event: content_block_delta\ndata: {}\n\n",
+                json!({"type": "content_block_delta", "index": index, "delta": delta})
+            )
+        };
+        let upstream = [
+            delta(0, json!({"type": "text_delta", "text": "to [PII_EMA"})),
+            delta(0, json!({"type": "text_delta", "text": "IL_1]"})),
+            delta(
+                1,
+                json!({"type": "input_json_delta", "partial_json": "{\"to\":\"[PII_EMAIL"}),
+            ),
+            "event: content_block_stop\ndata: {\"type\":\"content_block_stop\",\"index\":1}\n\n"
+                .to_string(),
+        ]
+        .concat();
+
+        let mut restorer = TokenRestoreStream::new(vault());
+        let mut events = restorer.push(upstream.as_bytes());
+        events.extend(restorer.finish());
+
+        assert_eq!(events.len(), 5);
+        assert!(events[..4]
+            .iter()
+            .all(|e| e.starts_with("event: content_block_delta\n")));
+        let chunks = data(&events);
+        let text: String = chunks
+            .iter()
+            .filter_map(|c| c["delta"]["text"].as_str())
+            .collect();
+        assert_eq!(text, "to a\"b@example.com");
+        // 不完整的令牌在内容块结束前补发
+        assert_eq!(chunks[2]["delta"]["partial_json"], "{\"to\":\"");
+        assert_eq!(chunks[3]["delta"]["partial_json"], "[PII_EMAIL");
+        assert_eq!(chunks[3]["index"], 1);
+        assert_eq!(chunks[4]["type"], "content_block_stop");
+    }
+
+    fn email_tokenize_engine() -> GuardrailEngine {
+        use proxycast_core::config::{
+            GuardrailAction, GuardrailDetector, GuardrailRule, GuardrailSettings,
+        };
+        GuardrailEngine::from_settings(&GuardrailSettings {
+            enabled: true,
+            rules: vec![GuardrailRule {
+                name: "email".to_string(),
+                detector: GuardrailDetector::Email,
+                action: GuardrailAction::Tokenize,
+            }],
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_speech_input_is_redacted_instead_of_tokenized() {
+        let engine = email_tokenize_engine();
+        let speech =
+            json!({"model": "tts-1", "input": "请发邮件到 ops@example.com", "voice": "alloy"});
+
+        let (payload, report) = inspect_request(&engine, &speech, true).unwrap().unwrap();
+        assert_eq!(payload["input"], "请发邮件到 [PII_EMAIL_1]");
+        assert_eq!(report.vault.len(), 1);
+
+        // 语音合成无法还原令牌，改为脱敏，令牌不会被读出
+        let (payload, report) = inspect_request(&engine, &speech, false).unwrap().unwrap();
+        assert_eq!(payload["input"], "请发邮件到 [REDACTED_EMAIL]");
+        assert!(report.vault.is_empty());
+        assert_eq!(payload["voice"], "alloy");
+
+        let clean = json!({"model": "tts-1", "input": "你好"});
+        assert!(inspect_request(&engine, &clean, false).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_unserializable_request_is_rejected() {
+        // 非字符串键的 map 无法转换为 JSON
+        let request: HashMap<(u8, u8), String> =
+            HashMap::from([((1, 2), "ops@example.com".to_string())]);
+        let error = inspect_request(&email_tokenize_engine(), &request, true).unwrap_err();
+        assert!(error.starts_with("内容安全检查无法解析请求"));
+    }
+}
@@ -23,14 +23,17 @@ use axum::{
     Json,
 };
 
+use super::guardrails::apply_guardrails_redacting;
 use crate::handlers::verify_api_key;
 use crate::AppState;
+use proxycast_core::errors::GatewayErrorCode;
 use proxycast_core::models::openai::ImageGenerationRequest;
 use proxycast_core::models::provider_pool_model::CredentialData;
 use proxycast_providers::converter::openai_to_antigravity::{
     convert_antigravity_image_response, convert_image_request_to_antigravity,
 };
 use proxycast_providers::providers::AntigravityProvider;
+use proxycast_server_utils::build_error_response_with_meta;
 
 /// 处理图像生成请求
 ///
@@ -63,7 +66,7 @@ use proxycast_providers::providers::AntigravityProvider;
 pub async fn handle_image_generation(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(request): Json<ImageGenerationRequest>,
+    Json(mut request): Json<ImageGenerationRequest>,
 ) -> Response {
     // 验证 API Key
     if let Err(e) = verify_api_key(&headers, &state.api_key).await {
@@ -85,6 +88,31 @@ pub async fn handle_image_generation(
             .into_response();
     }
 
+    // 内容安全检查（prompt 同样会发往上游）：生成的图像无法还原令牌，tokenize 规则按 redact 处置
+    let request_id = uuid::Uuid::new_v4().to_string();
+    if let Err(message) = apply_guardrails_redacting(
+        &state,
+        &request_id,
+        "/v1/images/generations",
+        &request.model.clone(),
+        &mut request,
+    )
+    .await
+    {
+        return build_error_response_with_meta(
+            StatusCode::BAD_REQUEST.as_u16(),
+            &message,
+            Some(&request_id),
+            None,
+            Some(GatewayErrorCode::ContentBlocked),
+        );
+    }
+
+    generate_image(state, request).await
+}
+
+/// 使用经过内容安全处理的请求生成图像
+async fn generate_image(state: AppState, request: ImageGenerationRequest) -> Response {
     // 记录请求日志
     // 安全截取 prompt，避免 UTF-8 字符边界问题
     let prompt_preview: String = request.prompt.chars().take(50).collect();
@@ -192,7 +220,7 @@ pub async fn handle_image_generation(
         .load_credentials_from_path(&creds_file_path)
         .await
     {
-        let _ = state.pool_service.mark_unhealthy(
+        state.pool_service.mark_unhealthy_in_background(
             db,
             &credential.uuid,
             Some(&format!("Failed to load credentials: {e}")),
@@ -209,37 +237,57 @@ pub async fn handle_image_generation(
             .into_response();
     }
 
+    // 需要刷新时先获取凭证锁（与后台预刷新互斥），等锁期间 Token 可能已被刷新，重新加载凭证
+    let refresh_guard = if antigravity.validate_token().needs_refresh() {
+        let guard = state.token_cache.lock_credential(&credential.uuid).await;
+        let _ = antigravity
+            .load_credentials_from_path(&creds_file_path)
+            .await;
+        Some(guard)
+    } else {
+        None
+    };
+
     // 验证并刷新 Token
     let validation_result = antigravity.validate_token();
     if validation_result.needs_refresh() {
         tracing::info!("[IMAGE] Token 需要刷新，开始刷新...");
-        if let Err(refresh_error) = antigravity.refresh_token_with_retry(3).await {
-            tracing::error!("[IMAGE] Token 刷新失败: {:?}", refresh_error);
-            let _ = state.pool_service.mark_unhealthy_with_details(
-                db,
-                &credential.uuid,
-                &refresh_error,
-            );
-            let (status, message) = if refresh_error.requires_reauth() {
-                (StatusCode::UNAUTHORIZED, refresh_error.user_message())
-            } else {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    refresh_error.user_message(),
+        match antigravity.refresh_token_with_retry(3).await {
+            Ok(_) => {
+                state
+                    .token_cache
+                    .sync_after_external_refresh(Some(db), &credential.uuid)
+                    .await;
+            }
+            Err(refresh_error) => {
+                tracing::error!("[IMAGE] Token 刷新失败: {:?}", refresh_error);
+                let _ = state.pool_service.mark_unhealthy_with_details(
+                    db,
+                    &credential.uuid,
+                    &refresh_error,
+                );
+                let (status, message) = if refresh_error.requires_reauth() {
+                    (StatusCode::UNAUTHORIZED, refresh_error.user_message())
+                } else {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        refresh_error.user_message(),
+                    )
+                };
+                return (
+                    status,
+                    Json(serde_json::json!({
+                        "error": {
+                            "message": message,
+                            "type": "authentication_error"
+                        }
+                    })),
                 )
-            };
-            return (
-                status,
-                Json(serde_json::json!({
-                    "error": {
-                        "message": message,
-                        "type": "authentication_error"
-                    }
-                })),
-            )
-                .into_response();
+                    .into_response();
+            }
         }
     }
+    drop(refresh_guard);
 
     // 设置项目 ID
     if let Some(pid) = project_id {
@@ -295,10 +343,14 @@ pub async fn handle_image_generation(
             match convert_antigravity_image_response(&resp, &request.response_format) {
                 Ok(image_response) => {
                     // 记录成功
-                    let _ = state
+                    state.pool_service.mark_healthy_in_background(
+                        db,
+                        &credential.uuid,
+                        Some(model),
+                    );
+                    state
                         .pool_service
-                        .mark_healthy(db, &credential.uuid, Some(model));
-                    let _ = state.pool_service.record_usage(db, &credential.uuid);
+                        .record_usage_in_background(db, &credential.uuid);
 
                     state.logs.write().await.add(
                         "info",
@@ -328,9 +380,11 @@ pub async fn handle_image_generation(
             }
         }
         Err(e) => {
-            let _ = state
-                .pool_service
-                .mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+            state.pool_service.mark_unhealthy_in_background(
+                db,
+                &credential.uuid,
+                Some(&e.to_string()),
+            );
             state
                 .logs
                 .write()
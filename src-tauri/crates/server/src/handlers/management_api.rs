@@ -0,0 +1,391 @@
+//! 管理 API 端点
+//!
+//! 供 `proxycast` 命令行工具等外部工具管理运行中的网关：凭证增删改查 / 导入 / 刷新、
+//! 路由列表、凭证池健康、Token 用量与费用、日志拉取、内容安全审计记录。
+//! 所有端点都需要配置 `remote_management.secret_key` 中的管理密钥（与代理接口的 API Key 分开），
+//! 未配置管理密钥时管理 API 不可用。
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Duration, Utc};
+use proxycast_core::database::DbConnection;
+use proxycast_core::logger::LogEntry;
+use proxycast_services::management_service::{
+    load_model_pricing, CredentialInput, CredentialPatch, ManagementService, UsageEntry,
+    UsageGroupBy, UsageReport,
+};
+use serde::Deserialize;
+use subtle::ConstantTimeEq;
+
+use crate::AppState;
+
+/// 默认返回的日志条数
+const DEFAULT_LOG_LIMIT: usize = 200;
+
+/// GET /api/management/credentials 查询参数
+#[derive(Debug, Deserialize)]
+pub struct ListCredentialsQuery {
+    pub provider_type: Option<String>,
+}
+
+/// GET /api/management/usage 查询参数
+#[derive(Debug, Deserialize)]
+pub struct UsageQuery {
+    /// 统计最近 N 天；省略时统计全部保留的记录
+    pub days: Option<i64>,
+    #[serde(default)]
+    pub group_by: UsageGroupBy,
+}
+
+/// GET /api/management/logs 查询参数
+#[derive(Debug, Deserialize)]
+pub struct LogsQuery {
+    /// 只返回序号大于该值的日志，用于增量拉取（传上次收到的最后一条日志的 `seq`）
+    pub after: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+/// GET /api/management/guardrails/audit 查询参数
+#[derive(Debug, Deserialize)]
+pub struct GuardrailAuditQuery {
+    pub limit: Option<usize>,
+}
+
+fn error_response(status: StatusCode, error_type: &str, message: impl Into<String>) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": {
+                "message": message.into(),
+                "type": error_type
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// 校验管理密钥
+///
+/// 接受 `Authorization: Bearer <KEY>` 或 `x-api-key: <KEY>`；代理接口的 API Key 无效。
+fn verify_management_key(state: &AppState, headers: &HeaderMap) -> Result<(), Response> {
+    let Some(expected) = state.management_key.as_deref() else {
+        return Err(error_response(
+            StatusCode::FORBIDDEN,
+            "management_disabled",
+            "管理 API 未启用，请在配置 remote_management.secret_key 中设置管理密钥",
+        ));
+    };
+    let provided = headers
+        .get("authorization")
+        .or_else(|| headers.get("x-api-key"))
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.strip_prefix("Bearer ").unwrap_or(v));
+    match provided {
+        Some(key) if bool::from(key.as_bytes().ct_eq(expected.as_bytes())) => Ok(()),
+        Some(_) => Err(error_response(
+            StatusCode::UNAUTHORIZED,
+            "authentication_error",
+            "管理密钥无效",
+        )),
+        None => Err(error_response(
+            StatusCode::UNAUTHORIZED,
+            "authentication_error",
+            "缺少管理密钥",
+        )),
+    }
+}
+
+/// 校验管理密钥并取出数据库连接
+async fn authorize<'a>(
+    state: &'a AppState,
+    headers: &HeaderMap,
+) -> Result<&'a DbConnection, Response> {
+    verify_management_key(state, headers)?;
+    state.db.as_ref().ok_or_else(|| {
+        error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "database_error",
+            "数据库未初始化",
+        )
+    })
+}
+
+fn management_service(state: &AppState) -> ManagementService {
+    ManagementService::new(state.pool_service.clone(), state.token_cache.clone())
+}
+
+/// 将 `Result<T, String>` 转换为 JSON 响应；"不存在" 类错误映射为 404
+fn json_result<T: serde::Serialize>(result: Result<T, String>, success: StatusCode) -> Response {
+    match result {
+        Ok(value) => (success, Json(value)).into_response(),
+        Err(e) if e.contains("不存在") || e.contains("not found") => {
+            error_response(StatusCode::NOT_FOUND, "not_found", e)
+        }
+        Err(e) => error_response(StatusCode::BAD_REQUEST, "invalid_request", e),
+    }
+}
+
+/// GET /api/management/credentials - 列出凭证
+pub async fn management_list_credentials(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ListCredentialsQuery>,
+) -> Response {
+    let db = match authorize(&state, &headers).await {
+        Ok(db) => db,
+        Err(response) => return response,
+    };
+    json_result(
+        management_service(&state).list_credentials(db, query.provider_type.as_deref()),
+        StatusCode::OK,
+    )
+}
+
+/// POST /api/management/credentials - 新增凭证
+pub async fn management_add_credential(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(input): Json<CredentialInput>,
+) -> Response {
+    let db = match authorize(&state, &headers).await {
+        Ok(db) => db,
+        Err(response) => return response,
+    };
+    json_result(
+        management_service(&state).add_credential(db, input),
+        StatusCode::CREATED,
+    )
+}
+
+/// POST /api/management/credentials/import - 批量导入凭证
+pub async fn management_import_credentials(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(inputs): Json<Vec<CredentialInput>>,
+) -> Response {
+    let db = match authorize(&state, &headers).await {
+        Ok(db) => db,
+        Err(response) => return response,
+    };
+    let summary = management_service(&state).import_credentials(db, inputs);
+    (StatusCode::OK, Json(summary)).into_response()
+}
+
+/// PATCH /api/management/credentials/:uuid - 更新凭证
+pub async fn management_update_credential(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(uuid): Path<String>,
+    Json(patch): Json<CredentialPatch>,
+) -> Response {
+    let db = match authorize(&state, &headers).await {
+        Ok(db) => db,
+        Err(response) => return response,
+    };
+    json_result(
+        management_service(&state).update_credential(db, &uuid, patch),
+        StatusCode::OK,
+    )
+}
+
+/// DELETE /api/management/credentials/:uuid - 删除凭证
+pub async fn management_delete_credential(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(uuid): Path<String>,
+) -> Response {
+    let db = match authorize(&state, &headers).await {
+        Ok(db) => db,
+        Err(response) => return response,
+    };
+    json_result(
+        management_service(&state)
+            .delete_credential(db, &uuid)
+            .map(|_| serde_json::json!({ "deleted": uuid })),
+        StatusCode::OK,
+    )
+}
+
+/// POST /api/management/credentials/:uuid/refresh - 强制刷新 OAuth Token
+pub async fn management_refresh_credential(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(uuid): Path<String>,
+) -> Response {
+    let db = match authorize(&state, &headers).await {
+        Ok(db) => db,
+        Err(response) => return response,
+    };
+    json_result(
+        management_service(&state).refresh_token(db, &uuid).await,
+        StatusCode::OK,
+    )
+}
+
+/// GET /api/management/routes - 列出路由注册表
+pub async fn management_list_routes(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let db = match authorize(&state, &headers).await {
+        Ok(db) => db,
+        Err(response) => return response,
+    };
+    let default_provider = state.default_provider.read().await.clone();
+    json_result(
+        management_service(&state).list_routes(db, &default_provider),
+        StatusCode::OK,
+    )
+}
+
+/// GET /api/management/pool/health - 凭证池健康状态
+pub async fn management_pool_health(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let db = match authorize(&state, &headers).await {
+        Ok(db) => db,
+        Err(response) => return response,
+    };
+    json_result(management_service(&state).pool_health(db), StatusCode::OK)
+}
+
+/// GET /api/management/usage - Token 用量与费用报表（来自内存中的 TokenTracker）
+pub async fn management_usage(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<UsageQuery>,
+) -> Response {
+    let db = match authorize(&state, &headers).await {
+        Ok(db) => db,
+        Err(response) => return response,
+    };
+
+    let records = {
+        let tracker = state.processor.tokens.read();
+        match query.days {
+            Some(days) => {
+                let end = Utc::now();
+                tracker.get_by_time_range(end - Duration::days(days.max(1)), end)
+            }
+            None => tracker.get_all(),
+        }
+    };
+    let providers: Vec<String> = records.iter().map(|r| r.provider.to_string()).collect();
+    let entries = records
+        .iter()
+        .zip(&providers)
+        .map(|(record, provider)| UsageEntry {
+            provider: provider.as_str(),
+            model: &record.model,
+            input_tokens: record.input_tokens as u64,
+            output_tokens: record.output_tokens as u64,
+        });
+
+    let pricing = load_model_pricing(db).unwrap_or_else(|e| {
+        tracing::warn!("[MANAGEMENT] 加载模型定价失败，费用将不可用: {}", e);
+        Default::default()
+    });
+    let report = UsageReport::build(query.group_by, entries, &pricing);
+    (StatusCode::OK, Json(report)).into_response()
+}
+
+/// GET /api/management/logs - 拉取网关日志
+pub async fn management_logs(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<LogsQuery>,
+) -> Response {
+    if let Err(response) = verify_management_key(&state, &headers) {
+        return response;
+    }
+
+    let logs = state.logs.read().await.get_logs();
+    (
+        StatusCode::OK,
+        Json(filter_logs(logs, query.after, query.limit)),
+    )
+        .into_response()
+}
+
+/// GET /api/management/guardrails/audit - 内容安全审计记录（最近的在后）
+pub async fn management_guardrail_audit(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<GuardrailAuditQuery>,
+) -> Response {
+    if let Err(response) = verify_management_key(&state, &headers) {
+        return response;
+    }
+
+    let entries = state
+        .processor
+        .guardrail_audit
+        .entries(Some(query.limit.unwrap_or(DEFAULT_LOG_LIMIT)));
+    (StatusCode::OK, Json(entries)).into_response()
+}
+
+/// 选出要返回的日志
+///
+/// 指定 `after` 时返回序号大于它的最早 `limit` 条，客户端以最后一条的序号继续拉取，不会漏掉日志；
+/// 否则返回最后 `limit` 条。网关重启后序号从头开始，游标比最新日志还大时视为重启，从头返回。
+fn filter_logs(logs: Vec<LogEntry>, after: Option<u64>, limit: Option<usize>) -> Vec<LogEntry> {
+    let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT);
+    match after {
+        Some(after) => {
+            let newest = logs.last().map(|e| e.seq).unwrap_or(0);
+            let after = if after > newest { 0 } else { after };
+            logs.into_iter()
+                .filter(|entry| entry.seq > after)
+                .take(limit)
+                .collect()
+        }
+        None => {
+            let skip = logs.len().saturating_sub(limit);
+            logs.into_iter().skip(skip).collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(seq: u64, message: &str) -> LogEntry {
+        LogEntry {
+            seq,
+            timestamp: "2026-01-01T00:00:00+00:00".to_string(),
+            level: "info".to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    fn messages(logs: &[LogEntry]) -> Vec<&str> {
+        logs.iter().map(|e| e.message.as_str()).collect()
+    }
+
+    #[test]
+    fn test_filter_logs_after_cursor_and_limit() {
+        // 同一时间戳的多条日志也能按序号逐条拉取
+        let logs = vec![entry(1, "a"), entry(2, "b"), entry(3, "c"), entry(4, "d")];
+
+        assert_eq!(
+            messages(&filter_logs(logs.clone(), Some(1), None)),
+            vec!["b", "c", "d"]
+        );
+        // 带游标时从最早的开始分页，不丢日志
+        assert_eq!(
+            messages(&filter_logs(logs.clone(), Some(1), Some(2))),
+            vec!["b", "c"]
+        );
+        assert!(filter_logs(logs.clone(), Some(4), None).is_empty());
+        // 不带游标时返回最后 limit 条
+        assert_eq!(
+            messages(&filter_logs(logs.clone(), None, Some(1))),
+            vec!["d"]
+        );
+        // 游标超过最新序号（网关已重启）时从头返回
+        assert_eq!(
+            messages(&filter_logs(logs, Some(99), Some(2))),
+            vec!["a", "b"]
+        );
+    }
+}
@@ -4,16 +4,24 @@
 
 pub mod api;
 pub mod api_key_provider_utils;
+pub mod audio_api;
 pub mod batch_api;
 pub mod batch_executor;
 pub mod chrome_bridge_ws;
 pub mod credentials_api;
+pub mod guardrails;
 pub mod image_handler;
 pub mod kiro_credential;
+pub mod management_api;
 pub mod provider_calls;
+pub mod stream_failover;
+pub mod stream_usage;
+pub mod structured_output;
+pub mod tool_emulation;
 pub mod websocket;
 
 pub use api::*;
+pub use audio_api::{handle_audio_speech, handle_audio_transcription};
 pub use batch_api::*;
 pub use chrome_bridge_ws::*;
 pub use credentials_api::*;
@@ -24,5 +32,7 @@ pub use kiro_credential::{
     AvailableCredential, AvailableCredentialsResponse, RefreshCredentialResponse,
     SelectCredentialResponse,
 };
+pub use management_api::*;
 pub use provider_calls::*;
+pub use structured_output::call_provider_openai_structured;
 pub use websocket::*;
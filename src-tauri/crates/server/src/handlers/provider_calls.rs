@@ -49,6 +49,7 @@ use axum::{
 };
 use futures::StreamExt;
 
+use super::tool_emulation::rewrite_emulated_response;
 use crate::AppState;
 use proxycast_core::models::anthropic::AnthropicMessagesRequest;
 use proxycast_core::models::openai::ChatCompletionRequest;
@@ -68,6 +69,10 @@ use proxycast_providers::streaming::{
     StreamConfig, StreamContext, StreamError, StreamFormat as StreamingFormat, StreamManager,
     StreamResponse,
 };
+use proxycast_providers::structured_output::{
+    apply_to_antigravity_request, openai_request_body, ResponseFormat,
+};
+use proxycast_providers::tool_emulation;
 use proxycast_server_utils::{
     build_anthropic_response, build_anthropic_stream_response, build_error_response,
     build_error_response_with_status, parse_cw_response, safe_truncate, CWParsedResponse,
@@ -118,7 +123,7 @@ pub async fn call_provider_anthropic(
                     let mut kiro = KiroProvider::new();
                     if let Err(e) = kiro.load_credentials_from_path(creds_file_path).await {
                         // 记录凭证加载失败
-                        let _ = state.pool_service.mark_unhealthy(
+                        state.pool_service.mark_unhealthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&format!("Failed to load credentials: {e}")),
@@ -131,7 +136,7 @@ pub async fn call_provider_anthropic(
                     }
                     if let Err(e) = kiro.refresh_token().await {
                         // 记录 Token 刷新失败
-                        let _ = state.pool_service.mark_unhealthy(
+                        state.pool_service.mark_unhealthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&format!("Token refresh failed: {e}")),
@@ -157,7 +162,7 @@ pub async fn call_provider_anthropic(
                 Ok(r) => r,
                 Err(e) => {
                     // 记录 API 调用失败
-                    let _ = state.pool_service.mark_unhealthy(
+                    state.pool_service.mark_unhealthy_in_background(
                         db,
                         &credential.uuid,
                         Some(&e.to_string()),
@@ -176,17 +181,17 @@ pub async fn call_provider_anthropic(
                         let body = String::from_utf8_lossy(&bytes).to_string();
                         let parsed = parse_cw_response(&body);
                         // 记录成功
-                        let _ = state.pool_service.mark_healthy(
+                        state.pool_service.mark_healthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&request.model),
                         );
-                        let _ = state.pool_service.record_usage(db, &credential.uuid);
+                        state.pool_service.record_usage_in_background(db, &credential.uuid);
                         // 非流式请求返回完整 JSON 响应（需求 6.2）
                         build_anthropic_response(&request.model, &parsed)
                     }
                     Err(e) => {
-                        let _ = state.pool_service.mark_unhealthy(
+                        state.pool_service.mark_unhealthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&e.to_string()),
@@ -213,7 +218,7 @@ pub async fn call_provider_anthropic(
                     Ok(t) => t,
                     Err(e) => {
                         // 记录 Token 刷新失败
-                        let _ = state.pool_service.mark_unhealthy(
+                        state.pool_service.mark_unhealthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&format!("Token refresh failed: {e}")),
@@ -235,17 +240,17 @@ pub async fn call_provider_anthropic(
                                     let body = String::from_utf8_lossy(&bytes).to_string();
                                     let parsed = parse_cw_response(&body);
                                     // 记录重试成功
-                                    let _ = state.pool_service.mark_healthy(
+                                    state.pool_service.mark_healthy_in_background(
                                         db,
                                         &credential.uuid,
                                         Some(&request.model),
                                     );
-                                    let _ = state.pool_service.record_usage(db, &credential.uuid);
+                                    state.pool_service.record_usage_in_background(db, &credential.uuid);
                                     // 非流式请求返回完整 JSON 响应（需求 6.2）
                                     build_anthropic_response(&request.model, &parsed)
                                 }
                                 Err(e) => {
-                                    let _ = state.pool_service.mark_unhealthy(
+                                    state.pool_service.mark_unhealthy_in_background(
                                         db,
                                         &credential.uuid,
                                         Some(&e.to_string()),
@@ -259,7 +264,7 @@ pub async fn call_provider_anthropic(
                             }
                         } else {
                             let body = retry_resp.text().await.unwrap_or_default();
-                            let _ = state.pool_service.mark_unhealthy(
+                            state.pool_service.mark_unhealthy_in_background(
                                 db,
                                 &credential.uuid,
                                 Some(&format!("Retry failed: {body}")),
@@ -272,7 +277,7 @@ pub async fn call_provider_anthropic(
                         }
                     }
                     Err(e) => {
-                        let _ = state.pool_service.mark_unhealthy(
+                        state.pool_service.mark_unhealthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&e.to_string()),
@@ -290,9 +295,7 @@ pub async fn call_provider_anthropic(
                 eprintln!("[PROVIDER_CALL] Kiro 请求失败: status={} body={}", status_code, &body[..body.len().min(500)]);
                 // 只有 5xx 错误才标记为不健康
                 if status_code >= 500 {
-                    let _ = state
-                        .pool_service
-                        .mark_unhealthy(db, &credential.uuid, Some(&body));
+                    state.pool_service.mark_unhealthy_in_background(db, &credential.uuid, Some(&body));
                 }
                 // 转发上游的实际状态码
                 (
@@ -321,7 +324,7 @@ pub async fn call_provider_anthropic(
             {
                 // 记录凭证加载失败
                 if let Some(db) = &state.db {
-                    let _ = state.pool_service.mark_unhealthy(
+                    state.pool_service.mark_unhealthy_in_background(
                         db,
                         &credential.uuid,
                         Some(&format!("Failed to load credentials: {e}")),
@@ -334,6 +337,15 @@ pub async fn call_provider_anthropic(
                     .into_response();
             }
 
+            // 需要刷新时先获取凭证锁（与后台预刷新互斥），等锁期间 Token 可能已被刷新，重新加载凭证
+            let refresh_guard = if antigravity.validate_token().needs_refresh() {
+                let guard = state.token_cache.lock_credential(&credential.uuid).await;
+                let _ = antigravity.load_credentials_from_path(creds_file_path).await;
+                Some(guard)
+            } else {
+                None
+            };
+
             // 使用新的 validate_token() 方法检查 Token 状态
             let validation_result = antigravity.validate_token();
             tracing::info!("[Antigravity] Token 验证结果: {:?}", validation_result);
@@ -344,9 +356,13 @@ pub async fn call_provider_anthropic(
                 match antigravity.refresh_token_with_retry(3).await {
                     Ok(new_token) => {
                         tracing::info!("[Antigravity] Token 刷新成功，新 token 长度: {}", new_token.len());
+                        state
+                            .token_cache
+                            .sync_after_external_refresh(state.db.as_ref(), &credential.uuid)
+                            .await;
                         // 刷新成功，标记为健康
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_healthy(
+                            state.pool_service.mark_healthy_in_background(
                                 db,
                                 &credential.uuid,
                                 None,
@@ -379,6 +395,7 @@ pub async fn call_provider_anthropic(
                     }
                 }
             }
+            drop(refresh_guard);
 
             // 设置项目 ID
             if let Some(pid) = project_id {
@@ -408,12 +425,12 @@ pub async fn call_provider_anthropic(
                     };
                     // 记录成功
                     if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_healthy(
+                        state.pool_service.mark_healthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&request.model),
                         );
-                        let _ = state.pool_service.record_usage(db, &credential.uuid);
+                        state.pool_service.record_usage_in_background(db, &credential.uuid);
                     }
                     if request.stream {
                         build_anthropic_stream_response(&request.model, &parsed)
@@ -424,7 +441,7 @@ pub async fn call_provider_anthropic(
                 Err(api_err) => {
                     // 记录 API 调用失败
                     if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_unhealthy(
+                        state.pool_service.mark_unhealthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&api_err.message),
@@ -438,7 +455,24 @@ pub async fn call_provider_anthropic(
         }
         CredentialData::OpenAIKey { api_key, base_url } => {
             let openai = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
-            let openai_request = convert_anthropic_to_openai(request);
+            let mut openai_request = convert_anthropic_to_openai(request);
+            // 始终读取完整响应，流式请求再转换为 Anthropic SSE
+            openai_request.stream = false;
+            let tool_names = tool_emulation::tool_names(&openai_request);
+            let emulate = tool_emulation::should_emulate(
+                &state.processor.tool_emulation.read(),
+                credential,
+                &openai_request,
+            );
+            if emulate {
+                tracing::info!(
+                    "[TOOL_EMULATION] 模拟工具调用: provider_type={}, model={}, tools={}",
+                    credential.provider_type,
+                    openai_request.model,
+                    tool_names.len()
+                );
+                openai_request = tool_emulation::prepare_request(&openai_request);
+            }
             match openai.call_api(&openai_request).await {
                 Ok(resp) => {
                     let status = resp.status();
@@ -448,27 +482,31 @@ pub async fn call_provider_anthropic(
                                 // 记录原始响应以便调试
                                 eprintln!("[PROVIDER_CALL] OpenAI 响应: {}", &body[..body.len().min(500)]);
 
-                                if let Ok(openai_resp) =
+                                if let Ok(mut openai_resp) =
                                     serde_json::from_str::<serde_json::Value>(&body)
                                 {
-                                    let content = openai_resp["choices"][0]["message"]["content"]
-                                        .as_str()
-                                        .unwrap_or("");
+                                    if emulate {
+                                        tool_emulation::rewrite_response(&mut openai_resp, &tool_names);
+                                    }
+                                    let message = &openai_resp["choices"][0]["message"];
+                                    let content = message["content"].as_str().unwrap_or("");
+                                    let tool_calls =
+                                        serde_json::from_value(message["tool_calls"].clone())
+                                            .unwrap_or_default();
                                     let parsed = CWParsedResponse {
                                         content: content.to_string(),
-                                        tool_calls: Vec::new(),
+                                        tool_calls,
                                         usage_credits: 0.0,
                                         context_usage_percentage: 0.0,
                                     };
                                     // 记录成功
                                     if let Some(db) = &state.db {
-                                        let _ = state.pool_service.mark_healthy(
+                                        state.pool_service.mark_healthy_in_background(
                                             db,
                                             &credential.uuid,
                                             Some(&request.model),
                                         );
-                                        let _ =
-                                            state.pool_service.record_usage(db, &credential.uuid);
+                                        state.pool_service.record_usage_in_background(db, &credential.uuid);
                                     }
                                     if request.stream {
                                         build_anthropic_stream_response(&request.model, &parsed)
@@ -479,7 +517,7 @@ pub async fn call_provider_anthropic(
                                     // 记录解析失败和原始响应
                                     eprintln!("[PROVIDER_CALL] 解析 OpenAI 响应失败，原始响应: {}", &body);
                                     if let Some(db) = &state.db {
-                                        let _ = state.pool_service.mark_unhealthy(
+                                        state.pool_service.mark_unhealthy_in_background(
                                             db,
                                             &credential.uuid,
                                             Some("Failed to parse OpenAI response"),
@@ -494,7 +532,7 @@ pub async fn call_provider_anthropic(
                             }
                             Err(e) => {
                                 if let Some(db) = &state.db {
-                                    let _ = state.pool_service.mark_unhealthy(
+                                    state.pool_service.mark_unhealthy_in_background(
                                         db,
                                         &credential.uuid,
                                         Some(&e.to_string()),
@@ -514,7 +552,7 @@ pub async fn call_provider_anthropic(
                         // 只有 5xx 错误才标记为不健康，4xx 错误（如模型不支持）不应该标记凭证为不健康
                         if status_code >= 500 {
                             if let Some(db) = &state.db {
-                                let _ = state.pool_service.mark_unhealthy(
+                                state.pool_service.mark_unhealthy_in_background(
                                     db,
                                     &credential.uuid,
                                     Some(&body),
@@ -531,7 +569,7 @@ pub async fn call_provider_anthropic(
                 }
                 Err(e) => {
                     if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_unhealthy(
+                        state.pool_service.mark_unhealthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&e.to_string()),
@@ -591,12 +629,12 @@ pub async fn call_provider_anthropic(
                         );
                         // 记录成功
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_healthy(
+                            state.pool_service.mark_healthy_in_background(
                                 db,
                                 &credential.uuid,
                                 Some(&request.model),
                             );
-                            let _ = state.pool_service.record_usage(db, &credential.uuid);
+                            state.pool_service.record_usage_in_background(db, &credential.uuid);
                         }
                         // 透传流式响应，保持 SSE 格式
                         let stream = resp.bytes_stream();
@@ -631,12 +669,12 @@ pub async fn call_provider_anthropic(
                                 );
                                 // 记录成功
                                 if let Some(db) = &state.db {
-                                    let _ = state.pool_service.mark_healthy(
+                                    state.pool_service.mark_healthy_in_background(
                                         db,
                                         &credential.uuid,
                                         Some(&request.model),
                                     );
-                                    let _ = state.pool_service.record_usage(db, &credential.uuid);
+                                    state.pool_service.record_usage_in_background(db, &credential.uuid);
                                 }
                                 Response::builder()
                                     .status(StatusCode::OK)
@@ -659,7 +697,7 @@ pub async fn call_provider_anthropic(
                                     ),
                                 );
                                 if let Some(db) = &state.db {
-                                    let _ = state.pool_service.mark_unhealthy(
+                                    state.pool_service.mark_unhealthy_in_background(
                                         db,
                                         &credential.uuid,
                                         Some(&body),
@@ -679,7 +717,7 @@ pub async fn call_provider_anthropic(
                                 &format!("[CLAUDE] 读取响应失败: {e}"),
                             );
                             if let Some(db) = &state.db {
-                                let _ = state.pool_service.mark_unhealthy(
+                                state.pool_service.mark_unhealthy_in_background(
                                     db,
                                     &credential.uuid,
                                     Some(&e.to_string()),
@@ -695,7 +733,7 @@ pub async fn call_provider_anthropic(
                 }
                 Err(e) => {
                     if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_unhealthy(
+                        state.pool_service.mark_unhealthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&e.to_string()),
@@ -720,8 +758,8 @@ pub async fn call_provider_anthropic(
                         Ok(body) => {
                             if status.is_success() {
                                 if let Some(db) = &state.db {
-                                    let _ = state.pool_service.mark_healthy(db, &credential.uuid, Some(&request.model));
-                                    let _ = state.pool_service.record_usage(db, &credential.uuid);
+                                    state.pool_service.mark_healthy_in_background(db, &credential.uuid, Some(&request.model));
+                                    state.pool_service.record_usage_in_background(db, &credential.uuid);
                                 }
                                 Response::builder()
                                     .status(StatusCode::OK)
@@ -732,14 +770,14 @@ pub async fn call_provider_anthropic(
                                     })
                             } else {
                                 if let Some(db) = &state.db {
-                                    let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&body));
+                                    state.pool_service.mark_unhealthy_in_background(db, &credential.uuid, Some(&body));
                                 }
                                 (StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR), Json(serde_json::json!({"error": {"message": body}}))).into_response()
                             }
                         }
                         Err(e) => {
                             if let Some(db) = &state.db {
-                                let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+                                state.pool_service.mark_unhealthy_in_background(db, &credential.uuid, Some(&e.to_string()));
                             }
                             (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": e.to_string()}}))).into_response()
                         }
@@ -747,7 +785,7 @@ pub async fn call_provider_anthropic(
                 }
                 Err(e) => {
                     if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+                        state.pool_service.mark_unhealthy_in_background(db, &credential.uuid, Some(&e.to_string()));
                     }
                     (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": e.to_string()}}))).into_response()
                 }
@@ -804,12 +842,12 @@ pub async fn call_provider_anthropic(
                             "[ANTHROPIC] 流式请求，透传 SSE 响应",
                         );
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_healthy(
+                            state.pool_service.mark_healthy_in_background(
                                 db,
                                 &credential.uuid,
                                 Some(&request.model),
                             );
-                            let _ = state.pool_service.record_usage(db, &credential.uuid);
+                            state.pool_service.record_usage_in_background(db, &credential.uuid);
                         }
                         let stream = resp.bytes_stream();
                         return Response::builder()
@@ -834,12 +872,12 @@ pub async fn call_provider_anthropic(
                         Ok(body) => {
                             if status.is_success() {
                                 if let Some(db) = &state.db {
-                                    let _ = state.pool_service.mark_healthy(
+                                    state.pool_service.mark_healthy_in_background(
                                         db,
                                         &credential.uuid,
                                         Some(&request.model),
                                     );
-                                    let _ = state.pool_service.record_usage(db, &credential.uuid);
+                                    state.pool_service.record_usage_in_background(db, &credential.uuid);
                                 }
                                 Response::builder()
                                     .status(StatusCode::OK)
@@ -862,7 +900,7 @@ pub async fn call_provider_anthropic(
                                     ),
                                 );
                                 if let Some(db) = &state.db {
-                                    let _ = state.pool_service.mark_unhealthy(
+                                    state.pool_service.mark_unhealthy_in_background(
                                         db,
                                         &credential.uuid,
                                         Some(&format!("API error: {status}")),
@@ -885,7 +923,7 @@ pub async fn call_provider_anthropic(
                 }
                 Err(e) => {
                     if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_unhealthy(
+                        state.pool_service.mark_unhealthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&format!("API call failed: {e}")),
@@ -910,10 +948,55 @@ pub async fn call_provider_anthropic(
 /// - `request`: OpenAI 格式请求
 /// - `flow_id`: Flow ID（可选，用于流式响应处理）
 pub async fn call_provider_openai(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+    flow_id: Option<&str>,
+) -> Response {
+    call_provider_openai_with_format(state, credential, request, flow_id, None).await
+}
+
+/// 根据凭证调用 Provider (OpenAI 格式)，并按上游原生机制传递 `response_format`
+///
+/// 只处理原生支持的上游：OpenAI 兼容上游透传 `response_format`，
+/// Antigravity 非流式请求设置 `responseSchema`。其余上游的模拟和校验见
+/// `structured_output::call_provider_openai_structured`。
+///
+/// 凭证和模型匹配 `tool_emulation` 规则时，工具调用以文本协议模拟。
+pub async fn call_provider_openai_with_format(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+    flow_id: Option<&str>,
+    response_format: Option<&ResponseFormat>,
+) -> Response {
+    let emulate =
+        tool_emulation::should_emulate(&state.processor.tool_emulation.read(), credential, request);
+    if !emulate {
+        return call_provider_openai_native(state, credential, request, flow_id, response_format)
+            .await;
+    }
+
+    let tool_names = tool_emulation::tool_names(request);
+    tracing::info!(
+        "[TOOL_EMULATION] 模拟工具调用: provider_type={}, model={}, tools={}",
+        credential.provider_type,
+        request.model,
+        tool_names.len()
+    );
+    let prepared = tool_emulation::prepare_request(request);
+    let response =
+        call_provider_openai_native(state, credential, &prepared, flow_id, response_format).await;
+    rewrite_emulated_response(response, tool_names, request.stream).await
+}
+
+/// 按凭证类型调用上游 (OpenAI 格式)
+async fn call_provider_openai_native(
     state: &AppState,
     credential: &ProviderCredential,
     request: &ChatCompletionRequest,
     _flow_id: Option<&str>,
+    response_format: Option<&ResponseFormat>,
 ) -> Response {
     let _start_time = std::time::Instant::now();
 
@@ -962,7 +1045,7 @@ pub async fn call_provider_openai(
                     // 降级：从源文件加载并刷新
                     let mut kiro = KiroProvider::new();
                     if let Err(e) = kiro.load_credentials_from_path(creds_file_path).await {
-                        let _ = state.pool_service.mark_unhealthy(
+                        state.pool_service.mark_unhealthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&format!("Failed to load credentials: {e}")),
@@ -974,7 +1057,7 @@ pub async fn call_provider_openai(
                             .into_response();
                     }
                     if let Err(e) = kiro.refresh_token().await {
-                        let _ = state.pool_service.mark_unhealthy(
+                        state.pool_service.mark_unhealthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&format!("Token refresh failed: {e}")),
@@ -1007,8 +1090,8 @@ pub async fn call_provider_openai(
                     Ok(stream_response) => {
                         // 记录成功
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_healthy(db, &credential.uuid, Some(&request.model));
-                            let _ = state.pool_service.record_usage(db, &credential.uuid);
+                            state.pool_service.mark_healthy_in_background(db, &credential.uuid, Some(&request.model));
+                            state.pool_service.record_usage_in_background(db, &credential.uuid);
                         }
 
                         tracing::info!("[OPENAI_STREAM] 开始转换流式响应");
@@ -1106,7 +1189,7 @@ pub async fn call_provider_openai(
                     Err(e) => {
                         // 记录请求错误
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+                            state.pool_service.mark_unhealthy_in_background(db, &credential.uuid, Some(&e.to_string()));
                         }
                         return (
                             StatusCode::INTERNAL_SERVER_ERROR,
@@ -1124,8 +1207,8 @@ pub async fn call_provider_openai(
                     if status.is_success() {
                         // 记录成功
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_healthy(db, &credential.uuid, Some(&request.model));
-                            let _ = state.pool_service.record_usage(db, &credential.uuid);
+                            state.pool_service.mark_healthy_in_background(db, &credential.uuid, Some(&request.model));
+                            state.pool_service.record_usage_in_background(db, &credential.uuid);
                         }
                         match resp.text().await {
                             Ok(body) => {
@@ -1183,7 +1266,7 @@ pub async fn call_provider_openai(
                         // 记录 API 调用失败
                         let body = resp.text().await.unwrap_or_default();
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&format!("HTTP {}: {}", status, safe_truncate(&body, 100))));
+                            state.pool_service.mark_unhealthy_in_background(db, &credential.uuid, Some(&format!("HTTP {}: {}", status, safe_truncate(&body, 100))));
                         }
                         (
                             StatusCode::INTERNAL_SERVER_ERROR,
@@ -1195,7 +1278,7 @@ pub async fn call_provider_openai(
                 Err(e) => {
                     // 记录请求错误
                     if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+                        state.pool_service.mark_unhealthy_in_background(db, &credential.uuid, Some(&e.to_string()));
                     }
                     (
                         StatusCode::INTERNAL_SERVER_ERROR,
@@ -1224,7 +1307,7 @@ pub async fn call_provider_openai(
                 eprintln!("[ANTIGRAVITY] 加载凭证失败: {e}");
                 // 记录凭证加载失败
                 if let Some(db) = &state.db {
-                    let _ = state.pool_service.mark_unhealthy(
+                    state.pool_service.mark_unhealthy_in_background(
                         db,
                         &credential.uuid,
                         Some(&format!("Failed to load credentials: {e}")),
@@ -1238,6 +1321,15 @@ pub async fn call_provider_openai(
             }
             eprintln!("[ANTIGRAVITY] 凭证加载成功");
 
+            // 需要刷新时先获取凭证锁（与后台预刷新互斥），等锁期间 Token 可能已被刷新，重新加载凭证
+            let refresh_guard = if antigravity.validate_token().needs_refresh() {
+                let guard = state.token_cache.lock_credential(&credential.uuid).await;
+                let _ = antigravity.load_credentials_from_path(creds_file_path).await;
+                Some(guard)
+            } else {
+                None
+            };
+
             // 使用新的 validate_token() 方法检查 Token 状态
             let validation_result = antigravity.validate_token();
             eprintln!("[ANTIGRAVITY] Token 验证结果: {validation_result:?}");
@@ -1252,9 +1344,13 @@ pub async fn call_provider_openai(
                     Ok(new_token) => {
                         eprintln!("[ANTIGRAVITY] Token 刷新成功，新 token 长度: {}", new_token.len());
                         tracing::info!("[Antigravity] Token 刷新成功，新 token 长度: {}", new_token.len());
+                        state
+                            .token_cache
+                            .sync_after_external_refresh(state.db.as_ref(), &credential.uuid)
+                            .await;
                         // 刷新成功，标记为健康
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_healthy(
+                            state.pool_service.mark_healthy_in_background(
                                 db,
                                 &credential.uuid,
                                 None,
@@ -1290,6 +1386,7 @@ pub async fn call_provider_openai(
             } else {
                 eprintln!("[ANTIGRAVITY] Token 不需要刷新，继续使用现有 Token");
             }
+            drop(refresh_guard);
 
             // 设置项目 ID
             if let Some(pid) = project_id {
@@ -1554,7 +1651,10 @@ pub async fn call_provider_openai(
 
             // 转换请求格式
             eprintln!("[ANTIGRAVITY_OPENAI] 开始转换请求格式...");
-            let antigravity_request = convert_openai_to_antigravity_with_context(request, &proj_id);
+            let mut antigravity_request = convert_openai_to_antigravity_with_context(request, &proj_id);
+            if let Some(format) = response_format {
+                apply_to_antigravity_request(&mut antigravity_request, format);
+            }
             eprintln!("[ANTIGRAVITY_OPENAI] 请求格式转换完成");
 
             eprintln!("[ANTIGRAVITY_OPENAI] 调用 generate_content...");
@@ -1579,6 +1679,19 @@ pub async fn call_provider_openai(
 
             tracing::info!("[OPENAI_KEY] request.stream = {}, model = {}", request.stream, request.model);
 
+            // 带 response_format 的流式请求直接透传原始 SSE（类型化请求不含该字段）
+            if let (true, Some(format)) = (request.stream, response_format) {
+                tracing::info!("[OPENAI_KEY_STREAM] 透传 response_format, model={}", request.model);
+                return match openai.chat_completions(&openai_request_body(request, format)).await {
+                    Ok(resp) => passthrough_openai_stream(resp).await,
+                    Err(e) => (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                    )
+                        .into_response(),
+                };
+            }
+
             // 检查是否为流式请求
             if request.stream {
                 tracing::info!("[OPENAI_KEY_STREAM] 处理流式请求, model={}", request.model);
@@ -1623,7 +1736,11 @@ pub async fn call_provider_openai(
             }
 
             // 非流式请求处理
-            match openai.call_api(request).await {
+            let result = match response_format {
+                Some(format) => openai.chat_completions(&openai_request_body(request, format)).await,
+                None => openai.call_api(request).await,
+            };
+            match result {
                 Ok(resp) => {
                     if resp.status().is_success() {
                         match resp.text().await {
@@ -1820,7 +1937,11 @@ pub async fn call_provider_openai(
                         request.stream
                     ),
                 );
-                match openai.call_api(request).await {
+                let result = match response_format {
+                    Some(format) => openai.chat_completions(&openai_request_body(request, format)).await,
+                    None => openai.call_api(request).await,
+                };
+                match result {
                     Ok(resp) => {
                         let status = resp.status();
                         state.logs.write().await.add(
@@ -1839,12 +1960,12 @@ pub async fn call_provider_openai(
                                 "[OPENAI_COMPAT] 流式请求，透传 SSE 响应",
                             );
                             if let Some(db) = &state.db {
-                                let _ = state.pool_service.mark_healthy(
+                                state.pool_service.mark_healthy_in_background(
                                     db,
                                     &credential.uuid,
                                     Some(&request.model),
                                 );
-                                let _ = state.pool_service.record_usage(db, &credential.uuid);
+                                state.pool_service.record_usage_in_background(db, &credential.uuid);
                             }
                             let stream = resp.bytes_stream();
                             return Response::builder()
@@ -1867,15 +1988,15 @@ pub async fn call_provider_openai(
                         // 非流式响应
                         if status.is_success() {
                             if let Some(db) = &state.db {
-                                let _ = state.pool_service.mark_healthy(
+                                state.pool_service.mark_healthy_in_background(
                                     db,
                                     &credential.uuid,
                                     Some(&request.model),
                                 );
-                                let _ = state.pool_service.record_usage(db, &credential.uuid);
+                                state.pool_service.record_usage_in_background(db, &credential.uuid);
                             }
                         } else if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_unhealthy(
+                            state.pool_service.mark_unhealthy_in_background(
                                 db,
                                 &credential.uuid,
                                 Some(&format!("API error: {status}")),
@@ -1903,7 +2024,7 @@ pub async fn call_provider_openai(
                     }
                     Err(e) => {
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_unhealthy(
+                            state.pool_service.mark_unhealthy_in_background(
                                 db,
                                 &credential.uuid,
                                 Some(&format!("API call failed: {e}")),
@@ -1940,6 +2061,21 @@ pub async fn call_provider_openai(
                     .into_response();
             }
 
+            // 需要刷新时先获取凭证锁（与后台预刷新互斥），等锁期间 Token 可能已被刷新，重新加载凭证
+            let refresh_guard = if codex.needs_refresh(chrono::Duration::minutes(5)) {
+                let guard = state.token_cache.lock_credential(&credential.uuid).await;
+                if let Err(e) = codex.load_credentials_from_path(creds_file_path).await {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": {"message": format!("Failed to load Codex credentials: {}", e)}})),
+                    )
+                        .into_response();
+                }
+                Some(guard)
+            } else {
+                None
+            };
+
             // 如果配置了自定义 API Base URL，覆盖凭证文件中的配置
             if let Some(base_url) = api_base_url {
                 if !base_url.trim().is_empty() {
@@ -1955,6 +2091,13 @@ pub async fn call_provider_openai(
                 )
                     .into_response();
             }
+            if refresh_guard.is_some() {
+                state
+                    .token_cache
+                    .sync_after_external_refresh(state.db.as_ref(), &credential.uuid)
+                    .await;
+            }
+            drop(refresh_guard);
 
             // 将 ChatCompletionRequest 转换为 serde_json::Value
             let request_json = match serde_json::to_value(request) {
@@ -2137,6 +2280,31 @@ pub async fn call_provider_openai(
 // 流式传输支持
 // ============================================================================
 
+/// 透传 OpenAI 兼容上游的 SSE 响应，上游出错时返回其状态码和响应体
+async fn passthrough_openai_stream(resp: reqwest::Response) -> Response {
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return build_error_response_with_status(status.as_u16(), &body);
+    }
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::CONNECTION, "keep-alive")
+        .header("X-Accel-Buffering", "no")
+        .body(Body::from_stream(resp.bytes_stream()))
+        .unwrap_or_else(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(
+                    serde_json::json!({"error": {"message": "Failed to build streaming response"}}),
+                ),
+            )
+                .into_response()
+        })
+}
+
 /// 获取凭证对应的流式格式
 ///
 /// 根据凭证类型返回对应的流式响应格式。
@@ -2604,7 +2772,7 @@ pub async fn handle_kiro_stream(
             // 回退到从源文件加载
             let mut kiro = KiroProvider::new();
             if let Err(e) = kiro.load_credentials_from_path(&creds_file_path).await {
-                let _ = state.pool_service.mark_unhealthy(
+                state.pool_service.mark_unhealthy_in_background(
                     db,
                     &credential.uuid,
                     Some(&format!("Failed to load credentials: {e}")),
@@ -2616,7 +2784,7 @@ pub async fn handle_kiro_stream(
                     .into_response();
             }
             if let Err(e) = kiro.refresh_token().await {
-                let _ = state.pool_service.mark_unhealthy(
+                state.pool_service.mark_unhealthy_in_background(
                     db,
                     &credential.uuid,
                     Some(&format!("Token refresh failed: {e}")),
@@ -2671,7 +2839,7 @@ pub async fn handle_kiro_stream(
                     Ok(t) => t,
                     Err(refresh_err) => {
                         // 需求 4.3: Token 刷新失败时返回明确的错误信息
-                        let _ = state.pool_service.mark_unhealthy(
+                        state.pool_service.mark_unhealthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&format!("Token refresh failed: {refresh_err}")),
@@ -2694,7 +2862,7 @@ pub async fn handle_kiro_stream(
                 match kiro.call_api_stream_anthropic(request).await {
                     Ok(stream) => stream,
                     Err(retry_err) => {
-                        let _ = state.pool_service.mark_unhealthy(
+                        state.pool_service.mark_unhealthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&retry_err.to_string()),
@@ -2712,10 +2880,11 @@ pub async fn handle_kiro_stream(
                     }
                 }
             } else {
-                let _ =
-                    state
-                        .pool_service
-                        .mark_unhealthy(db, &credential.uuid, Some(&e.to_string()));
+                state.pool_service.mark_unhealthy_in_background(
+                    db,
+                    &credential.uuid,
+                    Some(&e.to_string()),
+                );
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(serde_json::json!({
@@ -2731,10 +2900,12 @@ pub async fn handle_kiro_stream(
     };
 
     // 记录成功
-    let _ = state
+    state
+        .pool_service
+        .mark_healthy_in_background(db, &credential.uuid, Some(&request.model));
+    state
         .pool_service
-        .mark_healthy(db, &credential.uuid, Some(&request.model));
-    let _ = state.pool_service.record_usage(db, &credential.uuid);
+        .record_usage_in_background(db, &credential.uuid);
 
     tracing::info!(
         "[KIRO_STREAM] 开始处理流式响应, model={}, flow_id={:?}",
@@ -0,0 +1,264 @@
+//! 流式响应中途故障转移
+//!
+//! 故障转移原本只发生在首字节之前。流式响应已开始下发后，上游流中途断开
+//! （读取错误、`StreamIdleDetector` 空闲超时、流内错误事件、未收到结束事件即关闭）时，
+//! 以已下发的助手文本作为预填充向另一个凭证重新发起请求，并把续传内容拼接到同一个客户端流中。
+//! 无法续传（已下发工具调用、超过续传次数、没有可用凭证）时按原行为下发错误事件并结束。
+
+use axum::body::{Body, Bytes};
+use axum::response::Response;
+use futures::future::BoxFuture;
+use futures::StreamExt;
+use proxycast_infra::resilience::{StreamIdleDetector, TimeoutConfig};
+use proxycast_providers::streaming::{
+    ResumeSplicer, SseFramer, StreamError, StreamFormat, StreamManager,
+};
+
+/// 续传请求参数
+#[derive(Debug, Clone)]
+pub struct ResumeAttempt {
+    /// 助手预填充文本（已下发的文本，去掉结尾空白）
+    pub prefill: String,
+    /// 第几次续传（从 1 开始）
+    pub attempt: u32,
+    /// 已使用过的凭证（最后一个为刚断流的凭证）
+    pub exclude: Vec<String>,
+    /// 断流原因
+    pub reason: String,
+}
+
+/// 续传得到的新流式响应
+pub struct ResumedStream {
+    /// 续传使用的凭证
+    pub credential_id: String,
+    /// 客户端格式的流式响应
+    pub response: Response,
+}
+
+/// 发起续传请求，没有可用凭证或续传请求失败时返回 None
+pub type ResumeFn =
+    Box<dyn FnMut(ResumeAttempt) -> BoxFuture<'static, Option<ResumedStream>> + Send>;
+
+/// 中途故障转移参数
+#[derive(Debug, Clone)]
+pub struct StreamFailover {
+    /// 请求 ID（用于日志）
+    pub request_id: String,
+    /// 客户端流格式（Anthropic SSE 或 OpenAI SSE）
+    pub format: StreamFormat,
+    /// 当前响应使用的凭证
+    pub credential_id: String,
+    /// 最多续传次数
+    pub max_resumes: u32,
+    /// 空闲超时配置
+    pub timeout: TimeoutConfig,
+}
+
+/// 为流式响应启用中途故障转移
+pub fn with_stream_failover(
+    response: Response,
+    failover: StreamFailover,
+    mut resume: ResumeFn,
+) -> Response {
+    let (parts, body) = response.into_parts();
+    let mut transcript = StreamManager::with_default_config().transcript(failover.format);
+
+    let stream = async_stream::stream! {
+        let mut source = body.into_data_stream();
+        let mut framer = SseFramer::default();
+        let mut splicer: Option<ResumeSplicer> = None;
+        let mut used = vec![failover.credential_id.clone()];
+        let mut resumes = 0u32;
+
+        loop {
+            let detector = StreamIdleDetector::new(failover.timeout.clone());
+            let failure: String = 'read: loop {
+                let chunk = tokio::select! {
+                    chunk = source.next() => chunk,
+                    Err(e) = detector.wait_for_timeout() => break 'read e.to_string(),
+                };
+                detector.record_activity();
+
+                let (events, ended) = match chunk {
+                    Some(Ok(bytes)) => (framer.push(&bytes), false),
+                    Some(Err(e)) => break 'read format!("上游流读取失败: {e}"),
+                    None => (framer.finish().into_iter().collect(), true),
+                };
+                for raw in events {
+                    let spliced = match splicer.as_mut() {
+                        Some(splicer) => splicer.splice(raw),
+                        None => Ok(vec![raw]),
+                    };
+                    let spliced = match spliced {
+                        Ok(spliced) => spliced,
+                        Err(e) => break 'read e,
+                    };
+                    for raw in spliced {
+                        match transcript.record(raw) {
+                            Ok(forward) => {
+                                for event in forward {
+                                    yield Ok::<Bytes, std::io::Error>(Bytes::from(event));
+                                }
+                            }
+                            Err(e) => break 'read e,
+                        }
+                    }
+                }
+                if ended {
+                    if transcript.is_finished() {
+                        return;
+                    }
+                    break 'read "上游流未收到结束事件即关闭".to_string();
+                }
+            };
+
+            if !transcript.can_resume() || resumes >= failover.max_resumes {
+                tracing::warn!(
+                    "[STREAM_FAILOVER] request_id={} 无法续传 (resumes={}): {}",
+                    failover.request_id,
+                    resumes,
+                    failure
+                );
+                yield Ok(Bytes::from(StreamError::network(failure).to_sse_error()));
+                return;
+            }
+
+            resumes += 1;
+            let attempt = ResumeAttempt {
+                prefill: transcript.prefill().to_string(),
+                attempt: resumes,
+                exclude: used.clone(),
+                reason: failure.clone(),
+            };
+            let next_splicer = transcript.resume();
+            match resume(attempt).await {
+                Some(resumed) => {
+                    let broken = used.last().map(String::as_str).unwrap_or_default();
+                    tracing::info!(
+                        "[STREAM_FAILOVER] request_id={} 第 {} 次续传: {} -> {}",
+                        failover.request_id,
+                        resumes,
+                        &broken[..8.min(broken.len())],
+                        &resumed.credential_id[..8.min(resumed.credential_id.len())]
+                    );
+                    used.push(resumed.credential_id);
+                    // 替换上游流会释放断流凭证的并发槽位
+                    source = resumed.response.into_body().into_data_stream();
+                    framer = SseFramer::default();
+                    splicer = Some(next_splicer);
+                }
+                None => {
+                    yield Ok(Bytes::from(StreamError::network(&failure).to_sse_error()));
+                    return;
+                }
+            }
+        }
+    };
+
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn anthropic(data: Value) -> String {
+        format!(
+            "event: {}\ndata: {data}\n\n",
+            data["type"].as_str().unwrap()
+        )
+    }
+
+    fn text_delta(text: &str) -> String {
+        anthropic(json!({
+            "type": "content_block_delta",
+            "index": 0,
+            "delta": {"type": "text_delta", "text": text}
+        }))
+    }
+
+    fn sse_response(chunks: Vec<Result<String, std::io::Error>>) -> Response {
+        Response::new(Body::from_stream(futures::stream::iter(chunks)))
+    }
+
+    async fn client_text(response: Response) -> String {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    fn failover(max_resumes: u32) -> StreamFailover {
+        StreamFailover {
+            request_id: "req".to_string(),
+            format: StreamFormat::AnthropicSse,
+            credential_id: "cred-a".to_string(),
+            max_resumes,
+            timeout: TimeoutConfig::new(0, 1000),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broken_stream_resumes_on_another_credential() {
+        let primary = sse_response(vec![
+            Ok(anthropic(
+                json!({"type": "message_start", "message": {"id": "msg_1"}}),
+            )),
+            Ok(anthropic(json!({
+                "type": "content_block_start",
+                "index": 0,
+                "content_block": {"type": "text", "text": ""}
+            }))),
+            Ok(text_delta("Hello ")),
+            Err(std::io::Error::other("connection reset")),
+        ]);
+        let attempts = std::sync::Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let recorded = attempts.clone();
+        let resume: ResumeFn = Box::new(move |attempt: ResumeAttempt| {
+            recorded.lock().push(attempt);
+            Box::pin(async move {
+                Some(ResumedStream {
+                    credential_id: "cred-b".to_string(),
+                    response: sse_response(vec![
+                        Ok(anthropic(
+                            json!({"type": "message_start", "message": {"id": "msg_2"}}),
+                        )),
+                        Ok(anthropic(json!({
+                            "type": "content_block_start",
+                            "index": 0,
+                            "content_block": {"type": "text", "text": ""}
+                        }))),
+                        Ok(text_delta(" world")),
+                        Ok(anthropic(json!({"type": "content_block_stop", "index": 0}))),
+                        Ok(anthropic(json!({"type": "message_stop"}))),
+                    ]),
+                })
+            })
+        });
+
+        let output = client_text(with_stream_failover(primary, failover(1), resume)).await;
+
+        let attempts = attempts.lock();
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(attempts[0].prefill, "Hello");
+        assert_eq!(attempts[0].exclude, vec!["cred-a".to_string()]);
+        assert_eq!(output.matches("message_start").count(), 2);
+        assert!(output.contains("\"text\":\"Hello \""));
+        assert!(output.contains("\"text\":\"world\""));
+        assert!(!output.contains("event: error"));
+        assert!(output.ends_with("event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n"));
+    }
+
+    #[tokio::test]
+    async fn test_truncated_stream_without_resume_budget_reports_error() {
+        let primary = sse_response(vec![Ok(text_delta("partial"))]);
+        let resume: ResumeFn = Box::new(|_| Box::pin(async { None }));
+
+        let output = client_text(with_stream_failover(primary, failover(0), resume)).await;
+
+        assert!(output.contains("partial"));
+        assert!(output.ends_with("\n\n"));
+        assert!(output.contains("event: error"));
+    }
+}
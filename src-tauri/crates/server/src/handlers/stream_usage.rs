@@ -0,0 +1,190 @@
+//! 流式响应的输出统计
+//!
+//! 流式请求的遥测在收到响应头时记录，此时还不知道输出了多少 Token。
+//! 这里在响应体转发给客户端的同时用 [`StreamMetrics`] 统计数据块，并解析上游报告的 usage；
+//! 流正常结束时把输出 Token 数和生成耗时（首个数据块到流结束）记入模型编排器的性能统计。
+//! 流中途出错或客户端断开时不记录，避免用不完整的流拉低输出速度。
+
+use axum::body::Body;
+use axum::http::header;
+use axum::response::Response;
+use futures::StreamExt;
+use proxycast_processor::RequestContext;
+use proxycast_providers::streaming::resume::SseEvent;
+use proxycast_providers::streaming::{SseFramer, StreamMetrics};
+use serde_json::Value;
+
+/// 上游报告输出 Token 数的字段（Anthropic 的 message_delta 为累计值，取最后一次）
+const USAGE_POINTERS: &[&str] = &[
+    "/usage/output_tokens",
+    "/usage/completion_tokens",
+    "/usageMetadata/candidatesTokenCount",
+    "/response/usage/output_tokens",
+];
+
+/// 上游未报告 usage 时用于估算输出 Token 数的文本增量字段
+const TEXT_POINTERS: &[&str] = &[
+    "/choices/0/delta/content",
+    "/choices/0/delta/reasoning_content",
+    "/choices/0/delta/tool_calls/0/function/arguments",
+    "/delta/text",
+    "/delta/thinking",
+    "/delta/partial_json",
+    "/candidates/0/content/parts/0/text",
+];
+
+/// 统计流式响应的输出，流结束时记入模型编排器
+///
+/// 非流式、非 SSE、失败的响应以及不计入编排器统计的请求原样返回。
+pub fn track_stream_output(ctx: &RequestContext, response: Response) -> Response {
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    if !ctx.is_stream || !response.status().is_success() || !is_sse {
+        return response;
+    }
+    let Some(credential_id) = crate::orchestrator_credential(ctx) else {
+        return response;
+    };
+    let credential_id = credential_id.to_string();
+    let model = ctx.resolved_model.clone();
+
+    let (parts, body) = response.into_parts();
+    let mut upstream = body.into_data_stream();
+    let stream = async_stream::stream! {
+        let mut usage = StreamUsage::default();
+        while let Some(chunk) = upstream.next().await {
+            match &chunk {
+                Ok(bytes) => usage.push(bytes),
+                Err(_) => {
+                    yield chunk;
+                    return;
+                }
+            }
+            yield chunk;
+        }
+        usage.finish();
+        if let Some(orchestrator) = proxycast_core::orchestrator::get_global_orchestrator() {
+            orchestrator.record_output_tokens(
+                &credential_id,
+                &model,
+                usage.output_tokens(),
+                usage.generation_ms(),
+            );
+        }
+    };
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+/// 单个流式响应的输出统计
+#[derive(Default)]
+struct StreamUsage {
+    metrics: StreamMetrics,
+    framer: SseFramer,
+    /// 上游报告的输出 Token 数
+    reported_tokens: Option<u32>,
+    /// 文本增量的累计字节数
+    text_bytes: usize,
+}
+
+impl StreamUsage {
+    fn push(&mut self, bytes: &[u8]) {
+        self.metrics.record_chunk(bytes.len());
+        for event in self.framer.push(bytes) {
+            self.observe(&event);
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Some(event) = self.framer.finish() {
+            self.observe(&event);
+        }
+        self.metrics.finish();
+    }
+
+    fn observe(&mut self, raw: &str) {
+        let Some(chunk) = SseEvent::parse(raw).and_then(|event| event.json()) else {
+            return;
+        };
+        if let Some(tokens) = USAGE_POINTERS
+            .iter()
+            .find_map(|pointer| chunk.pointer(pointer).and_then(Value::as_u64))
+        {
+            self.reported_tokens = Some(tokens as u32);
+        }
+        self.text_bytes += TEXT_POINTERS
+            .iter()
+            .filter_map(|pointer| chunk.pointer(pointer).and_then(Value::as_str))
+            .map(str::len)
+            .sum::<usize>();
+    }
+
+    /// 输出 Token 数（上游未报告时按 4 字节 / Token 估算）
+    fn output_tokens(&self) -> u32 {
+        self.reported_tokens.unwrap_or((self.text_bytes / 4) as u32)
+    }
+
+    /// 生成耗时：首个数据块到流结束（不含首 Token 延迟）
+    fn generation_ms(&self) -> u64 {
+        self.metrics
+            .duration_ms()
+            .saturating_sub(self.metrics.ttfb_ms.unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reported_usage_wins_over_estimate() {
+        let mut usage = StreamUsage::default();
+        let stream = concat!(
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hello world!\"}}\n\n",
+            "event: message_delta\n",
+            "data: {\"type\":\"message_delta\",\"usage\":{\"output_tokens\":3}}\n\n",
+            "event: message_delta\n",
+            "data: {\"type\":\"message_delta\",\"usage\":{\"output_tokens\":42}}\n\n",
+        );
+        // 事件被拆到多个数据块中
+        for chunk in stream.as_bytes().chunks(17) {
+            usage.push(chunk);
+        }
+        usage.finish();
+
+        assert_eq!(usage.output_tokens(), 42);
+        assert!(usage.metrics.chunk_count > 1);
+        assert!(usage.metrics.is_finished());
+    }
+
+    #[test]
+    fn test_estimates_tokens_without_usage() {
+        let mut usage = StreamUsage::default();
+        usage.push(b"data: {\"choices\":[{\"delta\":{\"content\":\"abcdefgh\"}}]}\n\n");
+        usage.push(b"data: {\"choices\":[{\"delta\":{\"content\":\"ijkl\"}}]}\n\n");
+        usage.push(b"data: [DONE]\n\n");
+        usage.finish();
+
+        assert_eq!(usage.output_tokens(), 3);
+    }
+
+    #[test]
+    fn test_openai_and_gemini_usage() {
+        let mut usage = StreamUsage::default();
+        usage.push(
+            b"data: {\"choices\":[],\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":7}}\n\n",
+        );
+        usage.finish();
+        assert_eq!(usage.output_tokens(), 7);
+
+        let mut usage = StreamUsage::default();
+        usage.push(
+            b"data: {\"usageMetadata\":{\"promptTokenCount\":5,\"candidatesTokenCount\":11}}",
+        );
+        usage.finish();
+        assert_eq!(usage.output_tokens(), 11);
+    }
+}
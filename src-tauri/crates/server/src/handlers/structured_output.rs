@@ -0,0 +1,303 @@
+//! 结构化输出（`response_format`）处理
+//!
+//! 按凭证选择实现方式（见 `proxycast_providers::structured_output`），改写请求后调用 Provider。
+//! 非流式响应按 schema 校验：不符合时把输出和错误反馈给模型重试，
+//! 超过 `structured_output.max_repair_attempts` 仍不符合则返回 422 `STRUCTURED_OUTPUT_INVALID`。
+//! 流式响应只做请求侧处理，不校验输出。
+
+use axum::body::Body;
+use axum::http::{header, StatusCode};
+use axum::response::Response;
+use std::future::Future;
+
+use crate::AppState;
+use proxycast_core::errors::GatewayErrorCode;
+use proxycast_core::models::openai::ChatCompletionRequest;
+use proxycast_core::models::provider_pool_model::ProviderCredential;
+use proxycast_providers::structured_output::{
+    has_tool_calls, prepare_request, repair_request, response_text, set_response_text,
+    unwrap_forced_tool_call, ResponseFormat, StructuredOutputMode,
+};
+use proxycast_server_utils::build_error_response_with_meta;
+
+use super::provider_calls::{call_provider_openai, call_provider_openai_with_format};
+
+/// 读取非流式响应体的上限
+const MAX_RESPONSE_BYTES: usize = 32 * 1024 * 1024;
+
+/// 根据凭证调用 Provider (OpenAI 格式)，并满足请求的 `response_format`
+///
+/// 未设置 `response_format` 或功能已关闭时等同于 `call_provider_openai`。
+pub async fn call_provider_openai_structured(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+    response_format: Option<&ResponseFormat>,
+    request_id: Option<&str>,
+) -> Response {
+    let settings = state.processor.structured_output.read().clone();
+    let Some(format) = response_format.filter(|_| settings.enabled) else {
+        return call_provider_openai(state, credential, request, None).await;
+    };
+
+    let mode = StructuredOutputMode::select(&credential.credential, request, format);
+    let native_format = matches!(
+        mode,
+        StructuredOutputMode::Passthrough | StructuredOutputMode::GeminiSchema
+    )
+    .then_some(format);
+    tracing::info!(
+        "[STRUCTURED_OUTPUT] request_id={} format={} mode={:?} stream={}",
+        request_id.unwrap_or("-"),
+        format.name(),
+        mode,
+        request.stream
+    );
+
+    let prepared = prepare_request(request, format, mode);
+    let call = |request: ChatCompletionRequest| async move {
+        call_provider_openai_with_format(state, credential, &request, None, native_format).await
+    };
+    if request.stream {
+        return call(prepared).await;
+    }
+
+    match enforce_structured_output(prepared, format, mode, settings.max_repair_attempts, call)
+        .await
+    {
+        Ok(response) => response,
+        Err(errors) => {
+            tracing::warn!(
+                "[STRUCTURED_OUTPUT] request_id={} 输出仍不符合要求: {}",
+                request_id.unwrap_or("-"),
+                errors.join("; ")
+            );
+            let code = GatewayErrorCode::StructuredOutputInvalid;
+            build_error_response_with_meta(
+                StatusCode::UNPROCESSABLE_ENTITY.as_u16(),
+                &format!("{}: {}", code.default_message(), errors.join("; ")),
+                request_id,
+                Some(&credential.provider_type.to_string()),
+                Some(code),
+            )
+        }
+    }
+}
+
+/// 校验非流式响应，不符合时发起修复请求
+///
+/// 返回符合要求（或无法校验，如上游错误、工具调用）的响应；
+/// 修复次数用尽时返回最后一次的校验错误。
+pub async fn enforce_structured_output<F, Fut>(
+    request: ChatCompletionRequest,
+    format: &ResponseFormat,
+    mode: StructuredOutputMode,
+    max_repair_attempts: u32,
+    mut call: F,
+) -> Result<Response, Vec<String>>
+where
+    F: FnMut(ChatCompletionRequest) -> Fut,
+    Fut: Future<Output = Response>,
+{
+    let mut request = request;
+    let mut attempt = 0;
+    loop {
+        let response = call(request.clone()).await;
+        if !response.status().is_success() {
+            return Ok(response);
+        }
+        let (mut parts, body) = response.into_parts();
+        let bytes = match axum::body::to_bytes(body, MAX_RESPONSE_BYTES).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                tracing::warn!("[STRUCTURED_OUTPUT] 读取响应失败: {}", e);
+                return Err(vec![format!("读取上游响应失败: {e}")]);
+            }
+        };
+        let Ok(mut json) = serde_json::from_slice::<serde_json::Value>(&bytes) else {
+            return Ok(Response::from_parts(parts, Body::from(bytes)));
+        };
+
+        if mode == StructuredOutputMode::ToolForcing {
+            unwrap_forced_tool_call(&mut json, format);
+        }
+        // 模型调用了客户端的工具，留给客户端处理
+        if has_tool_calls(&json) {
+            return Ok(Response::from_parts(parts, Body::from(bytes)));
+        }
+
+        let text = response_text(&json).unwrap_or_default().to_string();
+        match format.check_output(&text) {
+            Ok(output) => {
+                set_response_text(&mut json, output);
+                parts.headers.remove(header::CONTENT_LENGTH);
+                return Ok(Response::from_parts(parts, Body::from(json.to_string())));
+            }
+            Err(errors) if attempt >= max_repair_attempts => return Err(errors),
+            Err(errors) => {
+                attempt += 1;
+                tracing::info!(
+                    "[STRUCTURED_OUTPUT] 输出不符合要求，第 {} 次修复: {}",
+                    attempt,
+                    errors.join("; ")
+                );
+                request = repair_request(&request, &text, &errors);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::response::IntoResponse;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    fn format() -> ResponseFormat {
+        ResponseFormat::from_request_body(&json!({
+            "response_format": {
+                "type": "json_schema",
+                "json_schema": {
+                    "name": "answer",
+                    "schema": {
+                        "type": "object",
+                        "properties": {"value": {"type": "integer"}},
+                        "required": ["value"]
+                    }
+                }
+            }
+        }))
+        .unwrap()
+        .unwrap()
+    }
+
+    fn request() -> ChatCompletionRequest {
+        serde_json::from_value(json!({
+            "model": "m",
+            "messages": [{"role": "user", "content": "1+1?"}]
+        }))
+        .unwrap()
+    }
+
+    fn completion(message: serde_json::Value) -> Response {
+        axum::Json(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "choices": [{"index": 0, "message": message, "finish_reason": "stop"}]
+        }))
+        .into_response()
+    }
+
+    /// 依次返回给定内容的上游，并记录收到的请求
+    fn upstream(
+        outputs: Vec<&'static str>,
+    ) -> (
+        Arc<Mutex<Vec<ChatCompletionRequest>>>,
+        impl FnMut(ChatCompletionRequest) -> std::future::Ready<Response>,
+    ) {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorded = seen.clone();
+        let mut outputs = outputs.into_iter();
+        let call = move |request: ChatCompletionRequest| {
+            recorded.lock().unwrap().push(request);
+            let content = outputs.next().expect("上游调用次数超出预期");
+            std::future::ready(completion(json!({"role": "assistant", "content": content})))
+        };
+        (seen, call)
+    }
+
+    async fn body_json(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_repairs_invalid_output_and_strips_fences() {
+        let (seen, call) = upstream(vec!["The answer is two.", "```json\n{\"value\": 2}\n```"]);
+        let response = enforce_structured_output(
+            request(),
+            &format(),
+            StructuredOutputMode::Emulated,
+            2,
+            call,
+        )
+        .await
+        .unwrap();
+        let body = body_json(response).await;
+        assert_eq!(body["choices"][0]["message"]["content"], "{\"value\": 2}");
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 2);
+        // 修复请求附带上次输出和校验错误
+        let repair = &seen[1].messages;
+        assert_eq!(repair.len(), 3);
+        assert_eq!(repair[1].get_content_text(), "The answer is two.");
+        assert!(repair[2].get_content_text().contains("不是合法的 JSON"));
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_repair_attempts() {
+        let (seen, call) = upstream(vec!["{}", "{\"value\": \"2\"}"]);
+        let errors = enforce_structured_output(
+            request(),
+            &format(),
+            StructuredOutputMode::Emulated,
+            1,
+            call,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("/value: "));
+        assert_eq!(seen.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unwraps_forced_tool_call() {
+        let format = format();
+        let call = |_: ChatCompletionRequest| {
+            std::future::ready(completion(json!({
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [{
+                    "id": "toolu_1",
+                    "type": "function",
+                    "function": {"name": "answer", "arguments": "{\"value\":2}"}
+                }]
+            })))
+        };
+        let response = enforce_structured_output(
+            request(),
+            &format,
+            StructuredOutputMode::ToolForcing,
+            0,
+            call,
+        )
+        .await
+        .unwrap();
+        let body = body_json(response).await;
+        let message = &body["choices"][0]["message"];
+        assert_eq!(message["content"], "{\"value\":2}");
+        assert!(message.get("tool_calls").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_passes_upstream_errors_through() {
+        let call = |_: ChatCompletionRequest| {
+            std::future::ready((StatusCode::TOO_MANY_REQUESTS, "slow down").into_response())
+        };
+        let response = enforce_structured_output(
+            request(),
+            &format(),
+            StructuredOutputMode::Emulated,
+            2,
+            call,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+}
@@ -0,0 +1,139 @@
+//! 工具调用模拟的响应改写
+//!
+//! 请求侧改写见 `proxycast_providers::tool_emulation`。这里把模拟调用得到的
+//! OpenAI 格式响应中的 `<tool_call>` 文本块转换为标准 `tool_calls`：
+//! 非流式响应整体改写，流式响应逐块转换。
+
+use axum::body::{Body, Bytes};
+use axum::http::header;
+use axum::response::Response;
+use futures::StreamExt;
+use proxycast_providers::tool_emulation::{rewrite_response, EmulatedToolStream};
+use proxycast_server_utils::build_error_response_with_status;
+
+/// 读取非流式响应体的上限
+const MAX_RESPONSE_BYTES: usize = 32 * 1024 * 1024;
+
+/// 改写模拟工具调用的上游响应
+///
+/// 上游错误响应原样返回；无法解析的非流式响应体也原样返回。
+pub async fn rewrite_emulated_response(
+    response: Response,
+    tool_names: Vec<String>,
+    stream: bool,
+) -> Response {
+    if !response.status().is_success() {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+
+    if stream {
+        parts.headers.remove(header::CONTENT_LENGTH);
+        let stream = async_stream::stream! {
+            let mut converter = EmulatedToolStream::new(tool_names);
+            let mut source = body.into_data_stream();
+            while let Some(chunk) = source.next().await {
+                match chunk {
+                    Ok(bytes) => {
+                        for event in converter.push(&bytes) {
+                            yield Ok::<Bytes, axum::Error>(Bytes::from(event));
+                        }
+                    }
+                    Err(e) => {
+                        yield Err(e);
+                        return;
+                    }
+                }
+            }
+            for event in converter.finish() {
+                yield Ok(Bytes::from(event));
+            }
+        };
+        return Response::from_parts(parts, Body::from_stream(stream));
+    }
+
+    let bytes = match axum::body::to_bytes(body, MAX_RESPONSE_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("[TOOL_EMULATION] 读取响应失败: {}", e);
+            return build_error_response_with_status(502, &format!("读取上游响应失败: {e}"));
+        }
+    };
+    let Ok(mut json) = serde_json::from_slice::<serde_json::Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    let count = rewrite_response(&mut json, &tool_names);
+    if count == 0 {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+    tracing::info!("[TOOL_EMULATION] 解析出 {} 个工具调用", count);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(json.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+    use serde_json::json;
+
+    fn names() -> Vec<String> {
+        vec!["search".to_string()]
+    }
+
+    async fn body_text(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rewrites_non_stream_response() {
+        let upstream = axum::Json(json!({
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "<tool_call>{\"name\": \"search\", \"arguments\": {\"q\": \"rust\"}}</tool_call>"},
+                "finish_reason": "stop"
+            }]
+        }))
+        .into_response();
+        let response = rewrite_emulated_response(upstream, names(), false).await;
+        assert!(response.headers().get(header::CONTENT_LENGTH).is_none());
+
+        let body: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(body["choices"][0]["finish_reason"], "tool_calls");
+        assert_eq!(
+            body["choices"][0]["message"]["tool_calls"][0]["function"]["name"],
+            "search"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_converts_stream_body() {
+        let events = vec![
+            Ok::<_, std::io::Error>(Bytes::from(
+                "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"<tool_call>{\\\"name\\\": \\\"search\\\", \"},\"finish_reason\":null}]}\n\n",
+            )),
+            Ok(Bytes::from(
+                "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"\\\"arguments\\\": {}}</tool_call>\"},\"finish_reason\":null}]}\n\ndata: [DONE]\n\n",
+            )),
+        ];
+        let upstream = Response::new(Body::from_stream(futures::stream::iter(events)));
+        let text = body_text(rewrite_emulated_response(upstream, names(), true).await).await;
+
+        assert!(!text.contains("<tool_call>"));
+        assert!(text.contains("\"name\":\"search\""));
+        assert!(text.contains("\"finish_reason\":\"tool_calls\""));
+        assert!(text.ends_with("data: [DONE]\n\n"));
+    }
+
+    #[tokio::test]
+    async fn test_passes_errors_through() {
+        let upstream = (StatusCode::BAD_GATEWAY, "boom").into_response();
+        let response = rewrite_emulated_response(upstream, names(), true).await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(body_text(response).await, "boom");
+    }
+}
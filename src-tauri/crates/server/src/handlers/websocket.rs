@@ -16,6 +16,7 @@ use serde::Deserialize;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use super::guardrails::{apply_guardrails, restore_value};
 use crate::AppState;
 use proxycast_core::errors::GatewayErrorCode;
 use proxycast_core::models::anthropic::AnthropicMessagesRequest;
@@ -325,22 +326,25 @@ fn gateway_code_name(code: GatewayErrorCode) -> &'static str {
         GatewayErrorCode::UpstreamTimeout => "UPSTREAM_TIMEOUT",
         GatewayErrorCode::UpstreamUnavailable => "UPSTREAM_UNAVAILABLE",
         GatewayErrorCode::UpstreamError => "UPSTREAM_ERROR",
+        GatewayErrorCode::StructuredOutputInvalid => "STRUCTURED_OUTPUT_INVALID",
+        GatewayErrorCode::ContentBlocked => "CONTENT_BLOCKED",
         GatewayErrorCode::InternalError => "INTERNAL_ERROR",
     }
 }
 
 fn gateway_to_ws_error_code(code: GatewayErrorCode) -> WsErrorCode {
     match code {
-        GatewayErrorCode::InvalidRequest | GatewayErrorCode::RequestConflict => {
-            WsErrorCode::InvalidRequest
-        }
+        GatewayErrorCode::InvalidRequest
+        | GatewayErrorCode::RequestConflict
+        | GatewayErrorCode::ContentBlocked => WsErrorCode::InvalidRequest,
         GatewayErrorCode::AuthenticationFailed => WsErrorCode::Unauthorized,
         GatewayErrorCode::UpstreamTimeout => WsErrorCode::Timeout,
         GatewayErrorCode::InternalError => WsErrorCode::InternalError,
         GatewayErrorCode::RateLimited
         | GatewayErrorCode::NoCredentials
         | GatewayErrorCode::UpstreamUnavailable
-        | GatewayErrorCode::UpstreamError => WsErrorCode::UpstreamError,
+        | GatewayErrorCode::UpstreamError
+        | GatewayErrorCode::StructuredOutputInvalid => WsErrorCode::UpstreamError,
     }
 }
 
@@ -402,6 +406,26 @@ async fn handle_ws_chat_completions(
         }
     }
 
+    // 内容安全检查
+    let guardrail_vault = match apply_guardrails(
+        state,
+        request_id,
+        "ws:/v1/chat/completions",
+        &request.model.clone(),
+        &mut request,
+    )
+    .await
+    {
+        Ok(vault) => vault,
+        Err(message) => {
+            return build_ws_gateway_error(
+                Some(request_id.to_string()),
+                GatewayErrorCode::ContentBlocked,
+                message,
+            );
+        }
+    };
+
     // 获取默认 provider
     let default_provider = state.default_provider.read().await.clone();
 
@@ -420,10 +444,15 @@ async fn handle_ws_chat_completions(
         // 简化实现：直接调用 provider 并返回结果
         // 实际实现应该复用 call_provider_openai 的逻辑
         match call_provider_openai_for_ws(state, &cred, &request).await {
-            Ok(response) => WsProtoMessage::Response(WsApiResponse {
-                request_id: request_id.to_string(),
-                payload: response,
-            }),
+            Ok(mut response) => {
+                if let Some(vault) = &guardrail_vault {
+                    restore_value(&mut response, vault);
+                }
+                WsProtoMessage::Response(WsApiResponse {
+                    request_id: request_id.to_string(),
+                    payload: response,
+                })
+            }
             Err(e) => build_ws_error_from_text(Some(request_id.to_string()), e),
         }
     } else {
@@ -468,6 +497,26 @@ async fn handle_ws_anthropic_messages(
         }
     }
 
+    // 内容安全检查
+    let guardrail_vault = match apply_guardrails(
+        state,
+        request_id,
+        "ws:/v1/messages",
+        &request.model.clone(),
+        &mut request,
+    )
+    .await
+    {
+        Ok(vault) => vault,
+        Err(message) => {
+            return build_ws_gateway_error(
+                Some(request_id.to_string()),
+                GatewayErrorCode::ContentBlocked,
+                message,
+            );
+        }
+    };
+
     // 获取默认 provider
     let default_provider = state.default_provider.read().await.clone();
 
@@ -484,10 +533,15 @@ async fn handle_ws_anthropic_messages(
     // 如果找到凭证，使用它调用 API
     if let Some(cred) = credential {
         match call_provider_anthropic_for_ws(state, &cred, &request).await {
-            Ok(response) => WsProtoMessage::Response(WsApiResponse {
-                request_id: request_id.to_string(),
-                payload: response,
-            }),
+            Ok(mut response) => {
+                if let Some(vault) = &guardrail_vault {
+                    restore_value(&mut response, vault);
+                }
+                WsProtoMessage::Response(WsApiResponse {
+                    request_id: request_id.to_string(),
+                    payload: response,
+                })
+            }
             Err(e) => build_ws_error_from_text(Some(request_id.to_string()), e),
         }
     } else {
@@ -515,7 +569,7 @@ pub async fn call_provider_openai_for_ws(
             let mut kiro = KiroProvider::new();
             if let Err(e) = kiro.load_credentials_from_path(creds_file_path).await {
                 if let Some(db) = &state.db {
-                    let _ = state.pool_service.mark_unhealthy(
+                    state.pool_service.mark_unhealthy_in_background(
                         db,
                         &credential.uuid,
                         Some(&format!("Failed to load credentials: {e}")),
@@ -525,7 +579,7 @@ pub async fn call_provider_openai_for_ws(
             }
             if let Err(e) = kiro.refresh_token().await {
                 if let Some(db) = &state.db {
-                    let _ = state.pool_service.mark_unhealthy(
+                    state.pool_service.mark_unhealthy_in_background(
                         db,
                         &credential.uuid,
                         Some(&format!("Token refresh failed: {e}")),
@@ -538,7 +592,7 @@ pub async fn call_provider_openai_for_ws(
                 Ok(r) => r,
                 Err(e) => {
                     if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_unhealthy(
+                        state.pool_service.mark_unhealthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&e.to_string()),
@@ -554,11 +608,14 @@ pub async fn call_provider_openai_for_ws(
 
                 // 记录成功
                 if let Some(db) = &state.db {
-                    let _ =
-                        state
-                            .pool_service
-                            .mark_healthy(db, &credential.uuid, Some(&request.model));
-                    let _ = state.pool_service.record_usage(db, &credential.uuid);
+                    state.pool_service.mark_healthy_in_background(
+                        db,
+                        &credential.uuid,
+                        Some(&request.model),
+                    );
+                    state
+                        .pool_service
+                        .record_usage_in_background(db, &credential.uuid);
                 }
 
                 let message = if has_tool_calls {
@@ -605,9 +662,11 @@ pub async fn call_provider_openai_for_ws(
             } else {
                 let body = resp.text().await.unwrap_or_default();
                 if let Some(db) = &state.db {
-                    let _ = state
-                        .pool_service
-                        .mark_unhealthy(db, &credential.uuid, Some(&body));
+                    state.pool_service.mark_unhealthy_in_background(
+                        db,
+                        &credential.uuid,
+                        Some(&body),
+                    );
                 }
                 Err(format!("Upstream error: {body}"))
             }
@@ -618,7 +677,7 @@ pub async fn call_provider_openai_for_ws(
                 Ok(r) => r,
                 Err(e) => {
                     if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_unhealthy(
+                        state.pool_service.mark_unhealthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&e.to_string()),
@@ -630,11 +689,14 @@ pub async fn call_provider_openai_for_ws(
             if resp.status().is_success() {
                 // 记录成功
                 if let Some(db) = &state.db {
-                    let _ =
-                        state
-                            .pool_service
-                            .mark_healthy(db, &credential.uuid, Some(&request.model));
-                    let _ = state.pool_service.record_usage(db, &credential.uuid);
+                    state.pool_service.mark_healthy_in_background(
+                        db,
+                        &credential.uuid,
+                        Some(&request.model),
+                    );
+                    state
+                        .pool_service
+                        .record_usage_in_background(db, &credential.uuid);
                 }
                 resp.json::<serde_json::Value>()
                     .await
@@ -642,9 +704,11 @@ pub async fn call_provider_openai_for_ws(
             } else {
                 let body = resp.text().await.unwrap_or_default();
                 if let Some(db) = &state.db {
-                    let _ = state
-                        .pool_service
-                        .mark_unhealthy(db, &credential.uuid, Some(&body));
+                    state.pool_service.mark_unhealthy_in_background(
+                        db,
+                        &credential.uuid,
+                        Some(&body),
+                    );
                 }
                 Err(format!("Upstream error: {body}"))
             }
@@ -662,18 +726,20 @@ pub async fn call_provider_openai_for_ws(
                 Ok(result) => {
                     // 记录成功
                     if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_healthy(
+                        state.pool_service.mark_healthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&request.model),
                         );
-                        let _ = state.pool_service.record_usage(db, &credential.uuid);
+                        state
+                            .pool_service
+                            .record_usage_in_background(db, &credential.uuid);
                     }
                     Ok(result)
                 }
                 Err(e) => {
                     if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_unhealthy(
+                        state.pool_service.mark_unhealthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&e.to_string()),
@@ -693,7 +759,7 @@ pub async fn call_provider_openai_for_ws(
                 .await
             {
                 if let Some(db) = &state.db {
-                    let _ = state.pool_service.mark_unhealthy(
+                    state.pool_service.mark_unhealthy_in_background(
                         db,
                         &credential.uuid,
                         Some(&format!("Failed to load credentials: {e}")),
@@ -702,6 +768,17 @@ pub async fn call_provider_openai_for_ws(
                 return Err(e.to_string());
             }
 
+            // 需要刷新时先获取凭证锁（与后台预刷新互斥），等锁期间 Token 可能已被刷新，重新加载凭证
+            let refresh_guard = if antigravity.validate_token().needs_refresh() {
+                let guard = state.token_cache.lock_credential(&credential.uuid).await;
+                let _ = antigravity
+                    .load_credentials_from_path(creds_file_path)
+                    .await;
+                Some(guard)
+            } else {
+                None
+            };
+
             // 使用新的 validate_token() 方法检查 Token 状态
             let validation_result = antigravity.validate_token();
             tracing::info!("[Antigravity WS] Token 验证结果: {:?}", validation_result);
@@ -715,9 +792,17 @@ pub async fn call_provider_openai_for_ws(
                             "[Antigravity WS] Token 刷新成功，新 token 长度: {}",
                             new_token.len()
                         );
+                        state
+                            .token_cache
+                            .sync_after_external_refresh(state.db.as_ref(), &credential.uuid)
+                            .await;
                         // 刷新成功，标记为健康
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_healthy(db, &credential.uuid, None);
+                            state.pool_service.mark_healthy_in_background(
+                                db,
+                                &credential.uuid,
+                                None,
+                            );
                         }
                     }
                     Err(refresh_error) => {
@@ -734,6 +819,7 @@ pub async fn call_provider_openai_for_ws(
                     }
                 }
             }
+            drop(refresh_guard);
 
             // 设置项目 ID
             if let Some(pid) = project_id {
@@ -749,12 +835,14 @@ pub async fn call_provider_openai_for_ws(
                 Ok(resp) => {
                     // 记录成功
                     if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_healthy(
+                        state.pool_service.mark_healthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&request.model),
                         );
-                        let _ = state.pool_service.record_usage(db, &credential.uuid);
+                        state
+                            .pool_service
+                            .record_usage_in_background(db, &credential.uuid);
                     }
                     Ok(convert_antigravity_to_openai_response(
                         &resp,
@@ -763,7 +851,7 @@ pub async fn call_provider_openai_for_ws(
                 }
                 Err(e) => {
                     if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_unhealthy(
+                        state.pool_service.mark_unhealthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&e.to_string()),
@@ -803,7 +891,7 @@ pub async fn call_provider_anthropic_for_ws(
                 Ok(r) => r,
                 Err(e) => {
                     if let Some(db) = &state.db {
-                        let _ = state.pool_service.mark_unhealthy(
+                        state.pool_service.mark_unhealthy_in_background(
                             db,
                             &credential.uuid,
                             Some(&e.to_string()),
@@ -815,11 +903,14 @@ pub async fn call_provider_anthropic_for_ws(
             if resp.status().is_success() {
                 // 记录成功
                 if let Some(db) = &state.db {
-                    let _ =
-                        state
-                            .pool_service
-                            .mark_healthy(db, &credential.uuid, Some(&request.model));
-                    let _ = state.pool_service.record_usage(db, &credential.uuid);
+                    state.pool_service.mark_healthy_in_background(
+                        db,
+                        &credential.uuid,
+                        Some(&request.model),
+                    );
+                    state
+                        .pool_service
+                        .record_usage_in_background(db, &credential.uuid);
                 }
                 resp.json::<serde_json::Value>()
                     .await
@@ -827,9 +918,11 @@ pub async fn call_provider_anthropic_for_ws(
             } else {
                 let body = resp.text().await.unwrap_or_default();
                 if let Some(db) = &state.db {
-                    let _ = state
-                        .pool_service
-                        .mark_unhealthy(db, &credential.uuid, Some(&body));
+                    state.pool_service.mark_unhealthy_in_background(
+                        db,
+                        &credential.uuid,
+                        Some(&body),
+                    );
                 }
                 Err(format!("Upstream error: {body}"))
             }
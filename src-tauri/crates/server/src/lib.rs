@@ -4,6 +4,8 @@ pub mod auth;
 pub mod chrome_bridge;
 pub mod client_detector;
 pub mod middleware;
+#[cfg(feature = "test-support")]
+pub mod traffic;
 
 use axum::{
     extract::{DefaultBodyLimit, Path, State},
@@ -14,10 +16,12 @@ use axum::{
 };
 use proxycast_core::config::{
     Config, ConfigChangeKind, ConfigManager, EndpointProvidersConfig, FileChangeEvent, FileWatcher,
-    HotReloadManager, ReloadResult,
+    GuardrailSettings, HotReloadManager, ReloadResult,
 };
 use proxycast_core::database::dao::provider_pool::ProviderPoolDao;
-use proxycast_core::database::DbConnection;
+use proxycast_core::database::{DbConnection, DbPool, PoolConfig};
+use proxycast_core::errors::GatewayErrorCode;
+use proxycast_core::guardrails::GuardrailEngine;
 use proxycast_core::logger::LogStore;
 use proxycast_core::models::anthropic::*;
 use proxycast_core::models::openai::*;
@@ -34,8 +38,8 @@ use proxycast_providers::providers::kiro::KiroProvider;
 use proxycast_providers::providers::openai_custom::OpenAICustomProvider;
 use proxycast_server_utils::{
     build_anthropic_response, build_anthropic_stream_response, build_error_response,
-    build_error_response_with_status, build_gemini_cli_request, build_gemini_native_request,
-    health, models, parse_cw_response,
+    build_error_response_with_meta, build_error_response_with_status, build_gemini_cli_request,
+    build_gemini_native_request, health, models, parse_cw_response,
 };
 use proxycast_services::kiro_event_service::KiroEventService;
 use proxycast_services::provider_pool_service::ProviderPoolService;
@@ -107,6 +111,9 @@ pub fn record_request_telemetry(
         let _ = logger.record(log.clone());
     }
 
+    // 记录到模型编排器的实测性能统计（用于延迟感知的模型选择）
+    record_orchestrator_performance(ctx, status);
+
     tracing::info!(
         "[TELEMETRY] request_id={} provider={:?} model={} status={:?} duration_ms={}",
         ctx.request_id,
@@ -117,6 +124,49 @@ pub fn record_request_telemetry(
     );
 }
 
+/// 标记请求不计入模型编排器性能统计的元数据键（音频转写、语音合成等非对话请求）
+pub const SKIP_ORCHESTRATOR_STATS: &str = "skip_orchestrator_stats";
+
+/// 计入模型编排器统计的凭证 ID（无凭证或请求被标记跳过时为 None）
+pub(crate) fn orchestrator_credential(ctx: &RequestContext) -> Option<&str> {
+    if ctx.get_metadata(SKIP_ORCHESTRATOR_STATS).is_some() {
+        return None;
+    }
+    ctx.credential_id.as_deref()
+}
+
+/// 将请求结果记录到模型编排器的性能统计
+///
+/// 遥测在上游返回响应头时记录，流式请求此时的耗时即首 Token 延迟。
+fn record_orchestrator_performance(
+    ctx: &RequestContext,
+    status: proxycast_infra::telemetry::RequestStatus,
+) {
+    use proxycast_core::orchestrator::{get_global_orchestrator, PerformanceSample};
+    use proxycast_infra::telemetry::RequestStatus;
+
+    let success = match status {
+        RequestStatus::Success => true,
+        RequestStatus::Failed | RequestStatus::Timeout => false,
+        // 重试中和客户端取消不反映上游性能
+        RequestStatus::Retrying | RequestStatus::Cancelled => return,
+    };
+    let (Some(credential_id), Some(orchestrator)) =
+        (orchestrator_credential(ctx), get_global_orchestrator())
+    else {
+        return;
+    };
+
+    let latency_ms = ctx.elapsed_ms();
+    orchestrator.record_performance(PerformanceSample {
+        credential_id: credential_id.to_string(),
+        model: ctx.resolved_model.clone(),
+        latency_ms,
+        ttft_ms: ctx.is_stream.then_some(latency_ms),
+        success,
+    });
+}
+
 /// 记录 Token 使用量到遥测系统
 pub fn record_token_usage(
     state: &AppState,
@@ -148,6 +198,21 @@ pub fn record_token_usage(
         tokens.record(record);
     }
 
+    // 非流式请求在生成完成后才返回响应，耗时可用于计算输出速度；
+    // 流式请求的输出速度由 `handlers::stream_usage` 在流结束时记录
+    if let (Some(credential_id), Some(output_tokens), false) =
+        (orchestrator_credential(ctx), output_tokens, ctx.is_stream)
+    {
+        if let Some(orchestrator) = proxycast_core::orchestrator::get_global_orchestrator() {
+            orchestrator.record_output_tokens(
+                credential_id,
+                &ctx.resolved_model,
+                output_tokens,
+                ctx.elapsed_ms(),
+            );
+        }
+    }
+
     tracing::debug!(
         "[TOKEN] request_id={} input={} output={}",
         ctx.request_id,
@@ -167,10 +232,13 @@ pub struct ServerStatus {
     pub error_rate_1m: f64,
     /// 最近 1 分钟 P95 延迟（毫秒）
     pub p95_latency_ms_1m: Option<u64>,
-    /// 当前熔断的上游数量（当前版本暂未接入熔断器）
+    /// 当前熔断（打开或半开）的凭证与 Provider 数量
     pub open_circuit_count: u32,
-    /// 当前活跃请求数（近似值，当前版本默认 0）
+    /// 当前正在进行的上游请求数
     pub active_requests: u64,
+    /// 当前等待并发槽位的请求数
+    #[serde(default)]
+    pub queued_requests: u64,
 }
 
 pub struct ServerState {
@@ -185,7 +253,13 @@ pub struct ServerState {
     pub default_provider_ref: Arc<RwLock<String>>,
     /// 路由器引用（用于动态更新默认 Provider）
     pub router_ref: Option<Arc<RwLock<proxycast_core::router::Router>>>,
+    /// 熔断器引用（用于状态查询和手动重置）
+    pub circuit_breaker: Option<Arc<proxycast_infra::CircuitBreaker>>,
     shutdown_tx: Option<oneshot::Sender<()>>,
+    /// 服务器后台任务句柄（用于优雅停机时等待连接排空）
+    server_task: Option<tokio::task::JoinHandle<()>>,
+    /// 热重载监控的配置文件路径（None 时使用默认路径）
+    config_path: Option<PathBuf>,
     /// 服务器运行时使用的 API key（启动时从配置复制）
     /// 用于 test_api 命令，确保测试使用的 API key 和服务器一致
     pub running_api_key: Option<String>,
@@ -212,13 +286,18 @@ impl ServerState {
             claude_custom_provider: claude_custom,
             default_provider_ref,
             router_ref: None,
+            circuit_breaker: None,
             shutdown_tx: None,
+            server_task: None,
+            config_path: None,
             running_api_key: None,
             running_host: None,
         }
     }
 
     pub fn status(&self) -> ServerStatus {
+        let concurrency =
+            proxycast_services::concurrency_limiter::global_concurrency_limiter().snapshot();
         ServerStatus {
             running: self.running,
             // 使用实际运行的 host，如果没有则使用配置的 host
@@ -231,11 +310,32 @@ impl ServerState {
             uptime_secs: self.start_time.map(|t| t.elapsed().as_secs()).unwrap_or(0),
             error_rate_1m: 0.0,
             p95_latency_ms_1m: None,
-            open_circuit_count: 0,
-            active_requests: 0,
+            open_circuit_count: self
+                .circuit_breaker
+                .as_ref()
+                .map(|breaker| breaker.open_count() as u32)
+                .unwrap_or(0),
+            active_requests: concurrency.in_flight,
+            queued_requests: concurrency.queue_depth,
         }
     }
 
+    /// 设置热重载监控的配置文件路径
+    ///
+    /// 默认监控 `ConfigManager::default_config_path()`，独立网关使用 `--config` 时需覆盖。
+    pub fn set_config_path(&mut self, path: PathBuf) {
+        self.config_path = Some(path);
+    }
+
+    /// 服务器后台任务是否仍在运行
+    ///
+    /// 绑定端口失败等错误只会记录日志并结束后台任务，可据此检测服务意外退出。
+    pub fn is_serving(&self) -> bool {
+        self.server_task
+            .as_ref()
+            .is_some_and(|task| !task.is_finished())
+    }
+
     /// 增加请求计数
     pub fn increment_request_count(&mut self) {
         self.requests = self.requests.saturating_add(1);
@@ -345,7 +445,10 @@ impl ServerState {
 
         // 获取配置和配置路径用于热重载
         let config = self.config.clone();
-        let config_path = proxycast_core::config::ConfigManager::default_config_path();
+        let config_path = self
+            .config_path
+            .clone()
+            .unwrap_or_else(proxycast_core::config::ConfigManager::default_config_path);
 
         // 创建请求处理器（在 spawn 之前创建，以便保存 router_ref）
         let processor = match (&shared_stats, &shared_tokens) {
@@ -389,11 +492,12 @@ impl ServerState {
 
         // 保存 router_ref 以便后续动态更新
         self.router_ref = Some(processor.router.clone());
+        self.circuit_breaker = Some(processor.circuit_breaker.clone());
 
         // 保存实际使用的 host（在移动到 spawn 之前克隆）
         let running_host = host.clone();
 
-        tokio::spawn(async move {
+        let server_task = tokio::spawn(async move {
             if let Err(e) = run_server(
                 &host,
                 port,
@@ -421,6 +525,7 @@ impl ServerState {
             }
         });
 
+        self.server_task = Some(server_task);
         self.running = true;
         self.start_time = Some(std::time::Instant::now());
         // 保存服务器运行时使用的 API key，用于 test_api 命令
@@ -434,11 +539,45 @@ impl ServerState {
         if let Some(tx) = self.shutdown_tx.take() {
             let _ = tx.send(());
         }
+        self.server_task = None;
+        self.reset_running_state();
+    }
+
+    /// 优雅停机
+    ///
+    /// 发送停机信号后停止接受新连接，并等待进行中的请求（包括流式响应）结束。
+    /// 在 `timeout` 内排空返回 `true`；超时则中止服务器任务并返回 `false`。
+    pub async fn shutdown_gracefully(&mut self, timeout: std::time::Duration) -> bool {
+        if let Some(tx) = self.shutdown_tx.take() {
+            let _ = tx.send(());
+        }
+
+        let drained = match self.server_task.take() {
+            Some(mut task) => match tokio::time::timeout(timeout, &mut task).await {
+                Ok(_) => true,
+                Err(_) => {
+                    tracing::warn!(
+                        "[SERVER] 等待进行中的请求结束超时（{} 秒），强制停止",
+                        timeout.as_secs()
+                    );
+                    task.abort();
+                    false
+                }
+            },
+            None => true,
+        };
+
+        self.reset_running_state();
+        drained
+    }
+
+    fn reset_running_state(&mut self) {
         self.running = false;
         self.start_time = None;
         self.running_api_key = None;
         self.running_host = None;
         self.router_ref = None;
+        self.circuit_breaker = None;
     }
 }
 
@@ -448,6 +587,8 @@ pub mod handlers;
 #[allow(dead_code)]
 pub struct AppState {
     pub api_key: String,
+    /// 管理 API 密钥（来自配置 remote_management.secret_key，未配置时禁用管理 API）
+    pub management_key: Option<String>,
     pub base_url: String,
     pub default_provider: Arc<RwLock<String>>,
     pub kiro: Arc<RwLock<KiroProvider>>,
@@ -457,6 +598,8 @@ pub struct AppState {
     pub pool_service: Arc<ProviderPoolService>,
     pub token_cache: Arc<TokenCacheService>,
     pub db: Option<DbConnection>,
+    /// 一写多读连接池（写连接与 `db` 共用），热路径查询走只读连接
+    pub db_pool: Option<DbPool>,
     /// 参数注入器
     pub injector: Arc<RwLock<Injector>>,
     /// 是否启用参数注入
@@ -690,6 +833,39 @@ async fn update_processor_config(processor: &RequestProcessor, config: &Config)
         );
     }
 
+    // 更新熔断器配置（已有的熔断状态保留）
+    processor
+        .circuit_breaker
+        .update_config((&config.circuit_breaker).into());
+    tracing::debug!(
+        "[HOT_RELOAD] 熔断器配置已更新: enabled={}, threshold={}",
+        config.circuit_breaker.enabled,
+        config.circuit_breaker.failure_rate_threshold
+    );
+
+    // 更新对冲策略
+    processor.hedging.update_settings(config.hedging.clone());
+    tracing::debug!(
+        "[HOT_RELOAD] 对冲策略已更新: enabled={}, rules={}",
+        config.hedging.enabled,
+        config.hedging.rules.len()
+    );
+
+    // 更新流式中途故障转移配置（对新请求生效）
+    *processor.stream_failover.write() = config.stream_failover.clone();
+    *processor.structured_output.write() = config.structured_output.clone();
+    *processor.tool_emulation.write() = config.tool_emulation.clone();
+    apply_guardrail_settings(processor, &config.guardrails);
+
+    // 更新并发限制（上限提高时立即放行排队请求）
+    proxycast_services::concurrency_limiter::global_concurrency_limiter()
+        .update_settings(config.concurrency.clone());
+    tracing::debug!(
+        "[HOT_RELOAD] 并发限制已更新: enabled={}, max_per_credential={}",
+        config.concurrency.enabled,
+        config.concurrency.max_per_credential
+    );
+
     // 注意：重试配置目前不支持热更新，因为 Retrier 是不可变的
     // 如果需要更新重试配置，需要重启服务器
     tracing::debug!(
@@ -701,6 +877,24 @@ async fn update_processor_config(processor: &RequestProcessor, config: &Config)
     tracing::info!("[HOT_RELOAD] 处理器配置更新完成");
 }
 
+/// 按配置重建内容安全检查引擎（规则无效时保留原有引擎）
+fn apply_guardrail_settings(processor: &RequestProcessor, settings: &GuardrailSettings) {
+    processor
+        .guardrail_audit
+        .set_capacity(settings.audit_capacity);
+    match GuardrailEngine::from_settings(settings) {
+        Ok(engine) => {
+            *processor.guardrails.write() = Arc::new(engine);
+            tracing::debug!(
+                "[HOT_RELOAD] 内容安全配置已更新: enabled={}, rules={}",
+                settings.enabled,
+                settings.rules.len()
+            );
+        }
+        Err(e) => tracing::error!("[GUARDRAIL] 内容安全规则无效，保留原有规则: {}", e),
+    }
+}
+
 /// 从配置同步凭证池
 ///
 /// 当配置热重载成功后，从 YAML 配置中加载凭证并同步到数据库。
@@ -792,6 +986,12 @@ async fn run_server(
         },
     };
 
+    // 凭证选择跳过熔断打开的凭证，熔断状态转换写入遥测日志
+    pool_service.set_circuit_source(processor.circuit_breaker.clone());
+    if let Some(logger) = &shared_logger {
+        processor.circuit_breaker.set_telemetry(logger.clone());
+    }
+
     // 将注入器规则同步到处理器
     {
         let mut proc_injector = processor.injector.write().await;
@@ -800,6 +1000,20 @@ async fn run_server(
         }
     }
 
+    // 从配置初始化熔断器、对冲策略、流式故障转移、结构化输出、工具调用模拟、内容安全和并发限制
+    if let Some(cfg) = &config {
+        processor
+            .circuit_breaker
+            .update_config((&cfg.circuit_breaker).into());
+        processor.hedging.update_settings(cfg.hedging.clone());
+        *processor.stream_failover.write() = cfg.stream_failover.clone();
+        *processor.structured_output.write() = cfg.structured_output.clone();
+        *processor.tool_emulation.write() = cfg.tool_emulation.clone();
+        apply_guardrail_settings(&processor, &cfg.guardrails);
+        proxycast_services::concurrency_limiter::global_concurrency_limiter()
+            .update_settings(cfg.concurrency.clone());
+    }
+
     // 从配置初始化 Router 的默认 Provider
     if let Some(cfg) = &config {
         let default_provider_str = &cfg.routing.default_provider;
@@ -879,8 +1093,22 @@ async fn run_server(
         .map(|c| c.retry.auto_switch_provider)
         .unwrap_or(true);
 
+    let db_pool = db.as_ref().map(|db| {
+        DbPool::with_writer(db.clone(), PoolConfig::default()).unwrap_or_else(|e| {
+            tracing::warn!("[SERVER] 创建只读连接失败，读操作回退到写连接: {}", e);
+            DbPool::single(db.clone())
+        })
+    });
+
+    // 管理 API 使用独立的密钥，不接受代理接口的 API Key
+    let management_key = config
+        .as_ref()
+        .and_then(|c| c.remote_management.secret_key.clone())
+        .filter(|key| !key.is_empty());
+
     let state = AppState {
         api_key: api_key.to_string(),
+        management_key,
         base_url,
         default_provider,
         kiro: Arc::new(RwLock::new(kiro)),
@@ -890,6 +1118,7 @@ async fn run_server(
         pool_service,
         token_cache,
         db,
+        db_pool,
         injector: Arc::new(RwLock::new(injector)),
         injection_enabled: Arc::new(RwLock::new(injection_enabled)),
         processor: processor.clone(),
@@ -940,6 +1169,29 @@ async fn run_server(
         None
     };
 
+    let app = build_router(state);
+
+    let addr: std::net::SocketAddr = format!("{host}:{port}")
+        .parse()
+        .map_err(|e| format!("无效的监听地址 {host}:{port} - {e}"))?;
+
+    let listener = tokio::net::TcpListener::bind(addr).await.map_err(|e| {
+        format!("无法绑定到 {host}:{port}，错误: {e}。请检查地址是否有效或端口是否被占用。")
+    })?;
+
+    tracing::info!("Server listening on {}", addr);
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            let _ = shutdown.await;
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// 构建 HTTP 路由（含 CORS、请求体大小限制和超时中间件）
+pub fn build_router(state: AppState) -> Router {
     // 设置请求体大小限制为 100MB，支持大型上下文请求（如 Claude Code 的 /compact 命令）
     let body_limit = 100 * 1024 * 1024; // 100MB
 
@@ -987,6 +1239,40 @@ async fn run_server(
             axum::routing::delete(handlers::delete_template),
         );
 
+    // 管理 API 路由（`proxycast` 命令行工具使用）
+    let management_api_routes = Router::new()
+        .route(
+            "/api/management/credentials",
+            get(handlers::management_list_credentials).post(handlers::management_add_credential),
+        )
+        .route(
+            "/api/management/credentials/import",
+            post(handlers::management_import_credentials),
+        )
+        .route(
+            "/api/management/credentials/:uuid",
+            axum::routing::patch(handlers::management_update_credential)
+                .delete(handlers::management_delete_credential),
+        )
+        .route(
+            "/api/management/credentials/:uuid/refresh",
+            post(handlers::management_refresh_credential),
+        )
+        .route(
+            "/api/management/routes",
+            get(handlers::management_list_routes),
+        )
+        .route(
+            "/api/management/pool/health",
+            get(handlers::management_pool_health),
+        )
+        .route("/api/management/usage", get(handlers::management_usage))
+        .route("/api/management/logs", get(handlers::management_logs))
+        .route(
+            "/api/management/guardrails/audit",
+            get(handlers::management_guardrail_audit),
+        );
+
     let allowed_origins = vec![
         HeaderValue::from_static("http://localhost:1420"),
         HeaderValue::from_static("http://127.0.0.1:1420"),
@@ -1014,30 +1300,42 @@ async fn run_server(
             header::ORIGIN,
         ]);
 
-    let app = Router::new()
+    Router::new()
         .route("/health", get(health))
         .route("/v1/models", get(models))
         .route("/v1/routes", get(list_routes))
-        .route("/v1/chat/completions", post(
-            |State(state): State<AppState>,
-             headers: HeaderMap,
-             Json(request): Json<proxycast_core::models::openai::ChatCompletionRequest>| async {
-                handlers::chat_completions(State(state), headers, Json(request)).await
-            }
-        ))
-        .route("/v1/messages", post(
-            |State(state): State<AppState>,
-             headers: HeaderMap,
-             Json(request): Json<AnthropicMessagesRequest>| async {
-                handlers::anthropic_messages(State(state), headers, Json(request)).await
-            }
-        ))
+        .route(
+            "/v1/chat/completions",
+            post(
+                |State(state): State<AppState>,
+                 headers: HeaderMap,
+                 Json(body): Json<serde_json::Value>| async {
+                    handlers::chat_completions(State(state), headers, Json(body)).await
+                },
+            ),
+        )
+        .route(
+            "/v1/messages",
+            post(
+                |State(state): State<AppState>,
+                 headers: HeaderMap,
+                 Json(request): Json<AnthropicMessagesRequest>| async {
+                    handlers::anthropic_messages(State(state), headers, Json(request)).await
+                },
+            ),
+        )
         .route("/v1/messages/count_tokens", post(count_tokens))
         // 图像生成 API 路由
         .route(
             "/v1/images/generations",
             post(handlers::handle_image_generation),
         )
+        // 音频 API 路由
+        .route(
+            "/v1/audio/transcriptions",
+            post(handlers::handle_audio_transcription),
+        )
+        .route("/v1/audio/speech", post(handlers::handle_audio_speech))
         // WebSocket 路由
         .route("/v1/ws", get(handlers::ws_upgrade_handler))
         .route("/ws", get(handlers::ws_upgrade_handler))
@@ -1064,31 +1362,15 @@ async fn run_server(
         .merge(credentials_api_routes)
         // 批量任务 API 路由
         .merge(batch_api_routes)
+        // 管理 API 路由
+        .merge(management_api_routes)
         .layer(cors_layer)
         .layer(DefaultBodyLimit::max(body_limit))
         .layer(TimeoutLayer::with_status_code(
             StatusCode::REQUEST_TIMEOUT,
             std::time::Duration::from_secs(300),
         ))
-        .with_state(state);
-
-    let addr: std::net::SocketAddr = format!("{host}:{port}")
-        .parse()
-        .map_err(|e| format!("无效的监听地址 {host}:{port} - {e}"))?;
-
-    let listener = tokio::net::TcpListener::bind(addr).await.map_err(|e| {
-        format!("无法绑定到 {host}:{port}，错误: {e}。请检查地址是否有效或端口是否被占用。")
-    })?;
-
-    tracing::info!("Server listening on {}", addr);
-
-    axum::serve(listener, app)
-        .with_graceful_shutdown(async move {
-            let _ = shutdown.await;
-        })
-        .await?;
-
-    Ok(())
+        .with_state(state)
 }
 
 async fn count_tokens(
@@ -1219,6 +1501,17 @@ async fn gemini_generate_content(
                     .into_response();
             }
 
+            // 需要刷新时先获取凭证锁（与后台预刷新互斥），等锁期间 Token 可能已被刷新，重新加载凭证
+            let refresh_guard = if antigravity.validate_token().needs_refresh() {
+                let guard = state.token_cache.lock_credential(&cred.uuid).await;
+                let _ = antigravity
+                    .load_credentials_from_path(creds_file_path)
+                    .await;
+                Some(guard)
+            } else {
+                None
+            };
+
             // 使用新的 validate_token() 方法检查 Token 状态
             let validation_result = antigravity.validate_token();
             tracing::info!(
@@ -1235,6 +1528,10 @@ async fn gemini_generate_content(
                             "[Antigravity Gemini] Token 刷新成功，新 token 长度: {}",
                             new_token.len()
                         );
+                        state
+                            .token_cache
+                            .sync_after_external_refresh(state.db.as_ref(), &cred.uuid)
+                            .await;
                     }
                     Err(refresh_error) => {
                         tracing::error!("[Antigravity Gemini] Token 刷新失败: {:?}", refresh_error);
@@ -1261,6 +1558,7 @@ async fn gemini_generate_content(
                     }
                 }
             }
+            drop(refresh_guard);
 
             // 设置项目 ID
             if let Some(pid) = project_id {
@@ -1371,6 +1669,15 @@ async fn gemini_generate_content(
                     .into_response();
             }
 
+            // 需要刷新时先获取凭证锁（与后台预刷新互斥），等锁期间 Token 可能已被刷新，重新加载凭证
+            let refresh_guard = if !gemini.is_token_valid() {
+                let guard = state.token_cache.lock_credential(&cred.uuid).await;
+                let _ = gemini.load_credentials_from_path(creds_file_path).await;
+                Some(guard)
+            } else {
+                None
+            };
+
             // 检查并刷新 Token
             if !gemini.is_token_valid() {
                 tracing::info!("[Gemini CLI] Token 需要刷新，开始刷新...");
@@ -1380,6 +1687,10 @@ async fn gemini_generate_content(
                             "[Gemini CLI] Token 刷新成功，新 token 长度: {}",
                             new_token.len()
                         );
+                        state
+                            .token_cache
+                            .sync_after_external_refresh(state.db.as_ref(), &cred.uuid)
+                            .await;
                     }
                     Err(refresh_error) => {
                         tracing::error!("[Gemini CLI] Token 刷新失败: {:?}", refresh_error);
@@ -1395,6 +1706,7 @@ async fn gemini_generate_content(
                     }
                 }
             }
+            drop(refresh_guard);
 
             // 设置项目 ID
             if let Some(pid) = project_id {
@@ -1585,7 +1897,7 @@ async fn anthropic_messages_with_selector(
     State(state): State<AppState>,
     Path(selector): Path<String>,
     headers: HeaderMap,
-    Json(request): Json<AnthropicMessagesRequest>,
+    Json(mut request): Json<AnthropicMessagesRequest>,
 ) -> Response {
     // 使用 Anthropic 格式的认证验证
     if let Err(e) = handlers::verify_api_key_anthropic(&headers, &state.api_key).await {
@@ -1604,6 +1916,29 @@ async fn anthropic_messages_with_selector(
         ),
     );
 
+    // 内容安全检查（与默认路由一致，令牌化的内容在响应返回前还原）
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let guardrail_vault = match handlers::guardrails::apply_guardrails(
+        &state,
+        &request_id,
+        &format!("/{selector}/v1/messages"),
+        &request.model.clone(),
+        &mut request,
+    )
+    .await
+    {
+        Ok(vault) => vault,
+        Err(message) => {
+            return build_error_response_with_meta(
+                StatusCode::BAD_REQUEST.as_u16(),
+                &message,
+                Some(&request_id),
+                None,
+                Some(GatewayErrorCode::ContentBlocked),
+            );
+        }
+    };
+
     // 尝试解析凭证（不降级，指定什么就用什么）
     let credential = match &state.db {
         Some(db) => {
@@ -1643,7 +1978,8 @@ async fn anthropic_messages_with_selector(
 
             // 根据凭证类型调用相应的 Provider
             // 注意：这里没有 Flow 捕获，因为是通过 selector 路由的请求
-            handlers::call_provider_anthropic(&state, &cred, &request, None).await
+            let response = handlers::call_provider_anthropic(&state, &cred, &request, None).await;
+            handlers::guardrails::restore_guardrail_tokens(response, guardrail_vault).await
         }
         None => {
             // 不再回退到默认 provider，直接返回错误
@@ -1672,8 +2008,12 @@ async fn chat_completions_with_selector(
     State(state): State<AppState>,
     Path(selector): Path<String>,
     headers: HeaderMap,
-    Json(request): Json<ChatCompletionRequest>,
+    Json(body): Json<serde_json::Value>,
 ) -> Response {
+    let (mut request, response_format) = match handlers::parse_chat_completion_body(body) {
+        Ok(parsed) => parsed,
+        Err(resp) => return resp,
+    };
     if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
         state.logs.write().await.add(
             "warn",
@@ -1690,6 +2030,29 @@ async fn chat_completions_with_selector(
         ),
     );
 
+    // 内容安全检查（与默认路由一致，令牌化的内容在响应返回前还原）
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let guardrail_vault = match handlers::guardrails::apply_guardrails(
+        &state,
+        &request_id,
+        &format!("/{selector}/v1/chat/completions"),
+        &request.model.clone(),
+        &mut request,
+    )
+    .await
+    {
+        Ok(vault) => vault,
+        Err(message) => {
+            return build_error_response_with_meta(
+                StatusCode::BAD_REQUEST.as_u16(),
+                &message,
+                Some(&request_id),
+                None,
+                Some(GatewayErrorCode::ContentBlocked),
+            );
+        }
+    };
+
     // 尝试解析凭证（不降级，指定什么就用什么）
     let credential = match &state.db {
         Some(db) => {
@@ -1723,7 +2086,15 @@ async fn chat_completions_with_selector(
             );
 
             // 注意：这里没有 Flow 捕获，因为是通过 selector 路由的请求
-            handlers::call_provider_openai(&state, &cred, &request, None).await
+            let response = handlers::call_provider_openai_structured(
+                &state,
+                &cred,
+                &request,
+                response_format.as_ref(),
+                None,
+            )
+            .await;
+            handlers::guardrails::restore_guardrail_tokens(response, guardrail_vault).await
         }
         None => {
             // 不再回退到默认 provider，直接返回错误
@@ -0,0 +1,429 @@
+//! 录制文件格式
+//!
+//! 每个文件记录一次上游交互：网关发出的请求、上游返回的状态码/响应头，以及按到达顺序保存的
+//! 响应体分块和分块之间的时间间隔。文本分块原样保存，二进制分块（如 AWS Event Stream）保存为 base64。
+
+use super::output::Frontend;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use proxycast_core::sanitizer::CredentialSanitizer;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// 当前录制文件版本
+pub const FIXTURE_VERSION: u32 = 1;
+
+/// 替换敏感信息使用的文本
+const REDACTED: &str = "[REDACTED]";
+
+/// 需要脱敏的请求/响应头
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "x-api-key",
+    "x-goog-api-key",
+    "cookie",
+    "set-cookie",
+    "x-amz-security-token",
+];
+
+/// 与回放无关、每次都会变化的响应头
+const VOLATILE_HEADERS: &[&str] = &[
+    "content-length",
+    "transfer-encoding",
+    "connection",
+    "date",
+    "host",
+    "keep-alive",
+];
+
+/// 请求体中需要脱敏的字段名（小写、去掉下划线后比较）
+const SENSITIVE_FIELDS: &[&str] = &[
+    "accesstoken",
+    "refreshtoken",
+    "apikey",
+    "clientsecret",
+    "idtoken",
+    "profilearn",
+    "password",
+];
+
+/// 上游协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpstreamKind {
+    /// OpenAI 兼容接口（OpenAI API Key 凭证）
+    OpenAI,
+    /// Anthropic Messages 接口（Claude API Key 凭证）
+    Claude,
+    /// CodeWhisperer 接口（Kiro 凭证）
+    Kiro,
+    /// Gemini CloudCode 接口（Antigravity 凭证）
+    Antigravity,
+}
+
+impl std::fmt::Display for UpstreamKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpstreamKind::OpenAI => write!(f, "openai"),
+            UpstreamKind::Claude => write!(f, "claude"),
+            UpstreamKind::Kiro => write!(f, "kiro"),
+            UpstreamKind::Antigravity => write!(f, "antigravity"),
+        }
+    }
+}
+
+impl std::str::FromStr for UpstreamKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "openai" => Ok(Self::OpenAI),
+            "claude" | "anthropic" => Ok(Self::Claude),
+            "kiro" | "codewhisperer" => Ok(Self::Kiro),
+            "antigravity" => Ok(Self::Antigravity),
+            other => Err(format!(
+                "未知的上游协议: {other}（可选 openai、claude、kiro、antigravity）"
+            )),
+        }
+    }
+}
+
+/// 录制的上游请求
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    /// HTTP 方法
+    pub method: String,
+    /// 请求路径（含查询参数）
+    pub path: String,
+    /// 请求头（已脱敏）
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// 请求体：JSON 原样保存，其他内容保存为字符串
+    #[serde(default)]
+    pub body: serde_json::Value,
+}
+
+/// 录制的响应体分块
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedChunk {
+    /// 距上一个分块（首个分块为距响应头）的时间间隔（毫秒）
+    #[serde(default)]
+    pub delay_ms: u64,
+    /// UTF-8 文本内容
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// 二进制内容（base64）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base64: Option<String>,
+}
+
+impl RecordedChunk {
+    /// 根据原始字节创建分块，合法 UTF-8 保存为文本，否则保存为 base64
+    pub fn from_bytes(delay_ms: u64, bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Self {
+                delay_ms,
+                text: Some(text.to_string()),
+                base64: None,
+            },
+            Err(_) => Self {
+                delay_ms,
+                text: None,
+                base64: Some(base64::engine::general_purpose::STANDARD.encode(bytes)),
+            },
+        }
+    }
+
+    /// 还原原始字节
+    pub fn bytes(&self) -> Result<Vec<u8>, String> {
+        match (&self.text, &self.base64) {
+            (Some(text), _) => Ok(text.as_bytes().to_vec()),
+            (None, Some(encoded)) => base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .map_err(|e| format!("分块 base64 解码失败: {e}")),
+            (None, None) => Ok(Vec::new()),
+        }
+    }
+}
+
+/// 录制的上游响应
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    /// HTTP 状态码
+    pub status: u16,
+    /// 响应头（已去掉易变和敏感字段）
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// 响应体分块
+    #[serde(default)]
+    pub chunks: Vec<RecordedChunk>,
+}
+
+impl RecordedResponse {
+    /// 是否为流式响应（SSE 或 AWS Event Stream）
+    pub fn is_stream(&self) -> bool {
+        self.headers.get("content-type").is_some_and(|ct| {
+            ct.contains("text/event-stream") || ct.contains("application/vnd.amazon.eventstream")
+        })
+    }
+
+    /// 拼接完整响应体
+    pub fn body(&self) -> Result<Vec<u8>, String> {
+        let mut body = Vec::new();
+        for chunk in &self.chunks {
+            body.extend(chunk.bytes()?);
+        }
+        Ok(body)
+    }
+}
+
+/// 期望的工具调用
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpectedToolCall {
+    /// 工具名称
+    pub name: String,
+    /// 工具参数
+    #[serde(default)]
+    pub arguments: serde_json::Value,
+}
+
+/// 客户端应看到的输出（与前端格式无关）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExpectedOutput {
+    /// 助手文本
+    #[serde(default)]
+    pub text: String,
+    /// 工具调用
+    #[serde(default)]
+    pub tool_calls: Vec<ExpectedToolCall>,
+    /// 需要检查的前端格式（为空时检查所有前端）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub frontends: Vec<Frontend>,
+}
+
+impl ExpectedOutput {
+    /// 需要检查的前端格式
+    pub fn frontends(&self) -> Vec<Frontend> {
+        if self.frontends.is_empty() {
+            Frontend::ALL.to_vec()
+        } else {
+            self.frontends.clone()
+        }
+    }
+}
+
+/// 一次上游交互的录制
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrafficFixture {
+    /// 文件格式版本
+    #[serde(default = "default_fixture_version")]
+    pub version: u32,
+    /// 名称（同时用作文件名）
+    pub name: String,
+    /// 上游协议
+    pub upstream: UpstreamKind,
+    /// 录制时间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recorded_at: Option<DateTime<Utc>>,
+    /// 网关发给上游的请求
+    pub request: RecordedRequest,
+    /// 上游响应
+    pub response: RecordedResponse,
+    /// 回放时客户端应看到的输出，录制后手工补充
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect: Option<ExpectedOutput>,
+}
+
+fn default_fixture_version() -> u32 {
+    FIXTURE_VERSION
+}
+
+impl TrafficFixture {
+    /// 从文件加载
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("读取录制文件失败 {}: {e}", path.display()))?;
+        let fixture: Self = serde_json::from_str(&content)
+            .map_err(|e| format!("解析录制文件失败 {}: {e}", path.display()))?;
+        if fixture.version > FIXTURE_VERSION {
+            return Err(format!(
+                "录制文件 {} 版本 {} 高于支持的版本 {FIXTURE_VERSION}",
+                path.display(),
+                fixture.version
+            ));
+        }
+        Ok(fixture)
+    }
+
+    /// 加载目录下所有 `.json` 录制文件（按文件名排序）
+    pub fn load_dir(dir: &Path) -> Result<Vec<Self>, String> {
+        let entries = std::fs::read_dir(dir)
+            .map_err(|e| format!("读取录制目录失败 {}: {e}", dir.display()))?;
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        paths.iter().map(|path| Self::load(path)).collect()
+    }
+
+    /// 保存到目录，返回文件路径
+    pub fn save(&self, dir: &Path) -> Result<PathBuf, String> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("创建录制目录失败 {}: {e}", dir.display()))?;
+        let file_name: String = self
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let path = dir.join(format!("{file_name}.json"));
+        let content =
+            serde_json::to_string_pretty(self).map_err(|e| format!("序列化录制文件失败: {e}"))?;
+        std::fs::write(&path, content + "\n")
+            .map_err(|e| format!("写入录制文件失败 {}: {e}", path.display()))?;
+        Ok(path)
+    }
+
+    /// 脱敏：去掉凭证相关的请求头、请求体字段，以及文本中匹配到的密钥
+    ///
+    /// 二进制分块带有校验和，无法在不破坏格式的前提下改写，保持原样。
+    pub fn sanitize(&mut self, sanitizer: &CredentialSanitizer) {
+        sanitize_headers(&mut self.request.headers);
+        sanitize_headers(&mut self.response.headers);
+        sanitize_value(&mut self.request.body, sanitizer);
+        for chunk in &mut self.response.chunks {
+            if let Some(text) = &chunk.text {
+                chunk.text = Some(sanitizer.sanitize(text));
+            }
+        }
+    }
+}
+
+fn sanitize_headers(headers: &mut BTreeMap<String, String>) {
+    headers.retain(|name, _| !VOLATILE_HEADERS.contains(&name.as_str()));
+    for (name, value) in headers.iter_mut() {
+        if SENSITIVE_HEADERS.contains(&name.as_str()) {
+            *value = REDACTED.to_string();
+        }
+    }
+}
+
+fn sanitize_value(value: &mut serde_json::Value, sanitizer: &CredentialSanitizer) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                let normalized = key.to_ascii_lowercase().replace(['_', '-'], "");
+                if SENSITIVE_FIELDS.contains(&normalized.as_str()) && !field.is_null() {
+                    *field = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    sanitize_value(field, sanitizer);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                sanitize_value(item, sanitizer);
+            }
+        }
+        serde_json::Value::String(text) => *text = sanitizer.sanitize(text),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fixture() -> TrafficFixture {
+        TrafficFixture {
+            version: FIXTURE_VERSION,
+            name: "openai stream/text".to_string(),
+            upstream: UpstreamKind::OpenAI,
+            recorded_at: None,
+            request: RecordedRequest {
+                method: "POST".to_string(),
+                path: "/v1/chat/completions".to_string(),
+                headers: BTreeMap::from([
+                    (
+                        "authorization".to_string(),
+                        "Bearer sk-live-0123456789abcdefghij".to_string(),
+                    ),
+                    ("content-type".to_string(), "application/json".to_string()),
+                    ("content-length".to_string(), "42".to_string()),
+                ]),
+                body: json!({
+                    "model": "gpt-4o",
+                    "profileArn": "arn:aws:codewhisperer:us-east-1:123456789012:profile/ABC",
+                    "messages": [{"role": "user", "content": "my key is sk-abcdefghijklmnopqrstuvwx"}]
+                }),
+            },
+            response: RecordedResponse {
+                status: 200,
+                headers: BTreeMap::from([
+                    ("content-type".to_string(), "text/event-stream".to_string()),
+                    (
+                        "date".to_string(),
+                        "Mon, 01 Jan 2024 00:00:00 GMT".to_string(),
+                    ),
+                ]),
+                chunks: vec![
+                    RecordedChunk::from_bytes(0, b"data: {\"choices\":[]}\n\n"),
+                    RecordedChunk::from_bytes(12, &[0x00, 0x00, 0x00, 0xff, 0xfe]),
+                ],
+            },
+            expect: None,
+        }
+    }
+
+    #[test]
+    fn test_sanitize_redacts_credentials() {
+        let mut fixture = fixture();
+        fixture.sanitize(&CredentialSanitizer::with_defaults());
+
+        assert_eq!(fixture.request.headers["authorization"], REDACTED);
+        assert!(!fixture.request.headers.contains_key("content-length"));
+        assert!(!fixture.response.headers.contains_key("date"));
+        assert_eq!(fixture.request.body["profileArn"], REDACTED);
+        assert_eq!(fixture.request.body["model"], "gpt-4o");
+        let content = fixture.request.body["messages"][0]["content"]
+            .as_str()
+            .unwrap();
+        assert!(!content.contains("sk-abcdefghijklmnopqrstuvwx"));
+    }
+
+    #[test]
+    fn test_chunks_round_trip_text_and_binary() {
+        let fixture = fixture();
+        let chunks = &fixture.response.chunks;
+        assert!(chunks[0].text.is_some());
+        assert!(chunks[1].base64.is_some());
+        assert_eq!(
+            chunks[1].bytes().unwrap(),
+            vec![0x00, 0x00, 0x00, 0xff, 0xfe]
+        );
+        assert!(fixture.response.is_stream());
+        assert_eq!(fixture.response.body().unwrap().len(), 22 + 5);
+    }
+
+    #[test]
+    fn test_save_and_load_dir() {
+        let dir = std::env::temp_dir().join(format!("proxycast-fixture-{}", uuid::Uuid::new_v4()));
+        let fixture = fixture();
+        let path = fixture.save(&dir).unwrap();
+        assert_eq!(path.file_name().unwrap(), "openai_stream_text.json");
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let loaded = TrafficFixture::load_dir(&dir).unwrap();
+        assert_eq!(loaded, vec![fixture]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,297 @@
+//! 回放测试工具
+//!
+//! 为一个录制文件启动模拟上游和完整的网关路由（内存数据库 + 指向模拟上游的池凭证），
+//! 再以各前端格式发起请求，返回客户端看到的输出。
+
+use super::fixture::{RecordedRequest, TrafficFixture, UpstreamKind};
+use super::mock_upstream::MockUpstream;
+use super::output::{ClientOutput, Frontend};
+use crate::{build_router, handlers, middleware, AppState};
+use proxycast_core::config::EndpointProvidersConfig;
+use proxycast_core::database::dao::provider_pool::ProviderPoolDao;
+use proxycast_core::database::{schema, DbConnection, DbPool};
+use proxycast_core::logger::LogStore;
+use proxycast_core::models::provider_pool_model::{
+    CachedTokenInfo, CredentialData, PoolProviderType, ProviderCredential,
+};
+use proxycast_infra::injection::Injector;
+use proxycast_processor::RequestProcessor;
+use proxycast_providers::providers::kiro::KiroProvider;
+use proxycast_services::api_key_provider_service::ApiKeyProviderService;
+use proxycast_services::kiro_event_service::KiroEventService;
+use proxycast_services::provider_pool_service::ProviderPoolService;
+use proxycast_services::token_cache_service::TokenCacheService;
+use proxycast_websocket::{WsConfig, WsConnectionManager};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{oneshot, RwLock};
+
+/// 回放网关使用的 API Key
+const REPLAY_API_KEY: &str = "proxycast-replay";
+
+/// 回放测试工具
+pub struct ReplayHarness {
+    upstream: Option<MockUpstream>,
+    addr: SocketAddr,
+    provider_id: String,
+    model: String,
+    client: reqwest::Client,
+    shutdown: Option<oneshot::Sender<()>>,
+    work_dir: PathBuf,
+}
+
+impl ReplayHarness {
+    /// 为录制文件启动模拟上游和网关（按录制时的时间间隔回放）
+    pub async fn start(fixture: &TrafficFixture) -> Result<Self, String> {
+        Self::start_with_time_scale(fixture, 1.0).await
+    }
+
+    /// 同 `start`，分块间隔乘以 `time_scale`（0 表示不等待）
+    pub async fn start_with_time_scale(
+        fixture: &TrafficFixture,
+        time_scale: f64,
+    ) -> Result<Self, String> {
+        let upstream =
+            MockUpstream::start_with_time_scale(vec![fixture.clone()], time_scale).await?;
+        let work_dir =
+            std::env::temp_dir().join(format!("proxycast-replay-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&work_dir).map_err(|e| format!("创建回放目录失败: {e}"))?;
+
+        let conn = rusqlite::Connection::open_in_memory().map_err(|e| e.to_string())?;
+        schema::create_tables(&conn).map_err(|e| e.to_string())?;
+        let db: DbConnection = Arc::new(Mutex::new(conn));
+        let credential = replay_credential(fixture, &upstream, &work_dir)?;
+        {
+            let conn = proxycast_core::database::lock_db(&db)?;
+            ProviderPoolDao::insert(&conn, &credential).map_err(|e| e.to_string())?;
+            if fixture.upstream == UpstreamKind::Kiro {
+                // 预置有效的 Token 缓存，回放时不触发刷新
+                let token = CachedTokenInfo {
+                    access_token: Some("replay-access-token".to_string()),
+                    refresh_token: Some("replay-refresh-token".to_string()),
+                    expiry_time: Some(chrono::Utc::now() + chrono::Duration::days(1)),
+                    last_refresh: Some(chrono::Utc::now()),
+                    refresh_error_count: 0,
+                    last_refresh_error: None,
+                };
+                ProviderPoolDao::update_token_cache(&conn, &credential.uuid, &token)
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+
+        let provider_id = credential.provider_type.to_string();
+        let state = replay_state(db, provider_id.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| format!("回放网关绑定端口失败: {e}"))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| format!("获取回放网关地址失败: {e}"))?;
+        let app = build_router(state);
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app)
+                .with_graceful_shutdown(async move {
+                    let _ = shutdown_rx.await;
+                })
+                .await;
+        });
+
+        let model = fixture
+            .request
+            .body
+            .get("model")
+            .and_then(|m| m.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| match fixture.upstream {
+                UpstreamKind::OpenAI => "gpt-4o".to_string(),
+                UpstreamKind::Claude | UpstreamKind::Kiro => "claude-sonnet-4-5".to_string(),
+                UpstreamKind::Antigravity => "gemini-2.5-flash".to_string(),
+            });
+
+        Ok(Self {
+            upstream: Some(upstream),
+            addr,
+            provider_id,
+            model,
+            client: reqwest::Client::new(),
+            shutdown: Some(shutdown),
+            work_dir,
+        })
+    }
+
+    /// 以指定前端格式向网关发起请求
+    pub async fn send(&self, frontend: Frontend, stream: bool) -> Result<ClientOutput, String> {
+        let messages = serde_json::json!([{"role": "user", "content": "replay"}]);
+        let body = match frontend {
+            Frontend::OpenAI => serde_json::json!({
+                "model": self.model,
+                "stream": stream,
+                "messages": messages,
+            }),
+            Frontend::Anthropic => serde_json::json!({
+                "model": self.model,
+                "max_tokens": 1024,
+                "stream": stream,
+                "messages": messages,
+            }),
+        };
+        let response = self
+            .client
+            .post(format!("http://{}{}", self.addr, frontend.path()))
+            .header("authorization", format!("Bearer {REPLAY_API_KEY}"))
+            .header("x-api-key", REPLAY_API_KEY)
+            .header("x-provider-id", &self.provider_id)
+            .header("user-agent", "proxycast-replay")
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("请求回放网关失败: {e}"))?;
+        let status = response.status().as_u16();
+        let raw = response
+            .text()
+            .await
+            .map_err(|e| format!("读取回放网关响应失败: {e}"))?;
+        Ok(ClientOutput::parse(frontend, stream, status, raw))
+    }
+
+    /// 模拟上游收到的请求（用于检查请求转换结果）
+    pub fn upstream_requests(&self) -> Vec<RecordedRequest> {
+        self.upstream
+            .as_ref()
+            .map(MockUpstream::requests)
+            .unwrap_or_default()
+    }
+
+    /// 停止网关和模拟上游
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        let _ = std::fs::remove_dir_all(&self.work_dir);
+        if let Some(upstream) = self.upstream.take() {
+            upstream.shutdown().await;
+        }
+    }
+}
+
+impl Drop for ReplayHarness {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        let _ = std::fs::remove_dir_all(&self.work_dir);
+    }
+}
+
+/// 创建指向模拟上游的池凭证
+fn replay_credential(
+    fixture: &TrafficFixture,
+    upstream: &MockUpstream,
+    work_dir: &std::path::Path,
+) -> Result<ProviderCredential, String> {
+    let base_url = upstream.base_url();
+    let credential = match fixture.upstream {
+        UpstreamKind::OpenAI => ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: "replay-upstream-key".to_string(),
+                base_url: Some(base_url),
+            },
+        ),
+        UpstreamKind::Claude => ProviderCredential::new(
+            PoolProviderType::Claude,
+            CredentialData::ClaudeKey {
+                api_key: "replay-upstream-key".to_string(),
+                base_url: Some(base_url),
+            },
+        ),
+        UpstreamKind::Kiro => {
+            let path = fixture.request.path.split('?').next().unwrap_or_default();
+            let creds_path = work_dir.join("kiro-auth-token.json");
+            let creds = serde_json::json!({
+                "accessToken": "replay-access-token",
+                "refreshToken": "replay-refresh-token",
+                "clientId": "replay-client",
+                "clientSecret": "replay-secret",
+                "region": "us-east-1",
+                "authMethod": "social",
+                "expiresAt": (chrono::Utc::now() + chrono::Duration::days(1)).to_rfc3339(),
+                "apiEndpoint": format!("{base_url}{path}"),
+            });
+            std::fs::write(&creds_path, creds.to_string())
+                .map_err(|e| format!("写入 Kiro 回放凭证失败: {e}"))?;
+            ProviderCredential::new(
+                PoolProviderType::Kiro,
+                CredentialData::KiroOAuth {
+                    creds_file_path: creds_path.to_string_lossy().into_owned(),
+                },
+            )
+        }
+        UpstreamKind::Antigravity => {
+            let creds_path = work_dir.join("antigravity-oauth.json");
+            let expiry = chrono::Utc::now() + chrono::Duration::days(1);
+            let creds = serde_json::json!({
+                "access_token": "replay-access-token",
+                "refresh_token": "replay-refresh-token",
+                "token_type": "Bearer",
+                "expiry_date": expiry.timestamp_millis(),
+                "project_id": "replay-project",
+                "api_endpoint": base_url,
+            });
+            std::fs::write(&creds_path, creds.to_string())
+                .map_err(|e| format!("写入 Antigravity 回放凭证失败: {e}"))?;
+            ProviderCredential::new(
+                PoolProviderType::Antigravity,
+                CredentialData::AntigravityOAuth {
+                    creds_file_path: creds_path.to_string_lossy().into_owned(),
+                    project_id: Some("replay-project".to_string()),
+                },
+            )
+        }
+    };
+    Ok(credential)
+}
+
+/// 构建回放网关状态（无速率限制、无热重载、不写日志文件）
+fn replay_state(db: DbConnection, default_provider: String) -> AppState {
+    let pool_service = Arc::new(ProviderPoolService::new());
+    let processor = Arc::new(RequestProcessor::with_defaults(pool_service.clone()));
+    let ws_manager = Arc::new(WsConnectionManager::new(WsConfig::default()));
+    let ws_stats = ws_manager.stats().clone();
+    AppState {
+        api_key: REPLAY_API_KEY.to_string(),
+        management_key: None,
+        base_url: String::new(),
+        default_provider: Arc::new(RwLock::new(default_provider)),
+        kiro: Arc::new(RwLock::new(KiroProvider::new())),
+        logs: Arc::new(RwLock::new(LogStore::with_custom_config(1, false))),
+        kiro_refresh_lock: Arc::new(tokio::sync::Mutex::new(())),
+        gemini_refresh_lock: Arc::new(tokio::sync::Mutex::new(())),
+        pool_service,
+        token_cache: Arc::new(TokenCacheService::new()),
+        db: Some(db.clone()),
+        db_pool: Some(DbPool::single(db)),
+        injector: Arc::new(RwLock::new(Injector::new())),
+        injection_enabled: Arc::new(RwLock::new(false)),
+        processor,
+        allow_provider_fallback: false,
+        ws_manager,
+        ws_stats,
+        hot_reload_manager: None,
+        request_logger: None,
+        amp_router: Arc::new(proxycast_core::router::AmpRouter::new(Default::default())),
+        endpoint_providers: Arc::new(RwLock::new(EndpointProvidersConfig::default())),
+        kiro_event_service: Arc::new(KiroEventService::new()),
+        api_key_service: Arc::new(ApiKeyProviderService::new()),
+        batch_executor: Arc::new(tokio::sync::RwLock::new(
+            None::<handlers::batch_executor::BatchTaskExecutor>,
+        )),
+        rate_limiter: None,
+        idempotency_store: Arc::new(middleware::idempotency::IdempotencyStore::new(
+            middleware::idempotency::IdempotencyConfig::default(),
+        )),
+        sanitizer: Arc::new(proxycast_core::sanitizer::CredentialSanitizer::with_defaults()),
+    }
+}
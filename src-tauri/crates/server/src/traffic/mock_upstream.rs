@@ -0,0 +1,313 @@
+//! 本地模拟上游
+//!
+//! 按 `方法 + 路径` 匹配录制文件并回放响应，流式响应按录制时的分块和时间间隔下发。
+//! 同一路径有多个录制文件时按顺序依次返回，用完后重复最后一个。
+
+use super::fixture::{RecordedChunk, RecordedRequest, TrafficFixture};
+use axum::body::{Body, Bytes};
+use axum::extract::{Request, State};
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+#[derive(Default)]
+struct MockState {
+    /// (方法, 路径) -> 待回放的录制文件
+    queues: Mutex<HashMap<(String, String), VecDeque<TrafficFixture>>>,
+    /// 收到的请求
+    received: Mutex<Vec<RecordedRequest>>,
+    /// 回放时间缩放（0 表示不等待）
+    time_scale: f64,
+}
+
+/// 本地模拟上游服务器
+pub struct MockUpstream {
+    addr: SocketAddr,
+    state: Arc<MockState>,
+    shutdown: Option<oneshot::Sender<()>>,
+    handle: JoinHandle<()>,
+}
+
+impl MockUpstream {
+    /// 启动模拟上游，按录制时的时间间隔回放
+    pub async fn start(fixtures: Vec<TrafficFixture>) -> Result<Self, String> {
+        Self::start_with_time_scale(fixtures, 1.0).await
+    }
+
+    /// 启动模拟上游，分块间隔乘以 `time_scale`（0 表示不等待）
+    pub async fn start_with_time_scale(
+        fixtures: Vec<TrafficFixture>,
+        time_scale: f64,
+    ) -> Result<Self, String> {
+        let mut queues: HashMap<(String, String), VecDeque<TrafficFixture>> = HashMap::new();
+        for fixture in fixtures {
+            let key = (
+                fixture.request.method.to_ascii_uppercase(),
+                route_path(&fixture.request.path).to_string(),
+            );
+            queues.entry(key).or_default().push_back(fixture);
+        }
+        let state = Arc::new(MockState {
+            queues: Mutex::new(queues),
+            received: Mutex::new(Vec::new()),
+            time_scale: time_scale.max(0.0),
+        });
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| format!("模拟上游绑定端口失败: {e}"))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| format!("获取模拟上游地址失败: {e}"))?;
+        let app = Router::new().fallback(replay).with_state(state.clone());
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let handle = tokio::spawn(async move {
+            let _ = axum::serve(listener, app)
+                .with_graceful_shutdown(async move {
+                    let _ = shutdown_rx.await;
+                })
+                .await;
+        });
+
+        Ok(Self {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+            handle,
+        })
+    }
+
+    /// 监听地址
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// 基础 URL（如 `http://127.0.0.1:12345`）
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// 已收到的请求
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.received.lock().clone()
+    }
+
+    /// 停止服务器
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        let _ = (&mut self.handle).await;
+    }
+}
+
+impl Drop for MockUpstream {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+/// 去掉查询参数后的路径
+fn route_path(path: &str) -> &str {
+    path.split('?').next().unwrap_or(path)
+}
+
+async fn replay(State(state): State<Arc<MockState>>, request: Request) -> Response {
+    let (parts, body) = request.into_parts();
+    let body = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .unwrap_or_default();
+    let method = parts.method.as_str().to_string();
+    let path = parts
+        .uri
+        .path_and_query()
+        .map(|pq| pq.as_str().to_string())
+        .unwrap_or_else(|| parts.uri.path().to_string());
+    let headers: BTreeMap<String, String> = parts
+        .headers
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|v| (name.as_str().to_string(), v.to_string()))
+        })
+        .collect();
+    state.received.lock().push(RecordedRequest {
+        method: method.clone(),
+        path: path.clone(),
+        headers,
+        body: serde_json::from_slice(&body)
+            .unwrap_or_else(|_| serde_json::Value::String(String::from_utf8_lossy(&body).into())),
+    });
+
+    let fixture = {
+        let mut queues = state.queues.lock();
+        let key = (method.clone(), route_path(&path).to_string());
+        match queues.get_mut(&key) {
+            Some(queue) if queue.len() > 1 => queue.pop_front(),
+            Some(queue) => queue.front().cloned(),
+            None => None,
+        }
+    };
+    let Some(fixture) = fixture else {
+        return (
+            StatusCode::NOT_FOUND,
+            axum::Json(serde_json::json!({
+                "error": {"message": format!("没有匹配的录制文件: {method} {path}")}
+            })),
+        )
+            .into_response();
+    };
+
+    let recorded = fixture.response;
+    let time_scale = state.time_scale;
+    let chunks: Vec<RecordedChunk> = recorded.chunks;
+    let stream = async_stream::stream! {
+        for chunk in chunks {
+            if time_scale > 0.0 && chunk.delay_ms > 0 {
+                tokio::time::sleep(Duration::from_secs_f64(
+                    chunk.delay_ms as f64 * time_scale / 1000.0,
+                ))
+                .await;
+            }
+            match chunk.bytes() {
+                Ok(bytes) => yield Ok::<Bytes, std::io::Error>(Bytes::from(bytes)),
+                Err(e) => {
+                    yield Err(std::io::Error::other(e));
+                    return;
+                }
+            }
+        }
+    };
+
+    let mut response = Response::new(Body::from_stream(stream));
+    *response.status_mut() = StatusCode::from_u16(recorded.status).unwrap_or(StatusCode::OK);
+    for (name, value) in &recorded.headers {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            response.headers_mut().insert(name, value);
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traffic::fixture::{RecordedResponse, UpstreamKind, FIXTURE_VERSION};
+    use std::time::Instant;
+
+    fn fixture(path: &str, chunks: Vec<RecordedChunk>) -> TrafficFixture {
+        TrafficFixture {
+            version: FIXTURE_VERSION,
+            name: "mock".to_string(),
+            upstream: UpstreamKind::OpenAI,
+            recorded_at: None,
+            request: RecordedRequest {
+                method: "POST".to_string(),
+                path: path.to_string(),
+                headers: BTreeMap::new(),
+                body: serde_json::Value::Null,
+            },
+            response: RecordedResponse {
+                status: 200,
+                headers: BTreeMap::from([(
+                    "content-type".to_string(),
+                    "text/event-stream".to_string(),
+                )]),
+                chunks,
+            },
+            expect: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replays_chunks_with_recorded_timing() {
+        let upstream = MockUpstream::start(vec![fixture(
+            "/v1/chat/completions",
+            vec![
+                RecordedChunk::from_bytes(0, b"data: a\n\n"),
+                RecordedChunk::from_bytes(50, b"data: b\n\n"),
+            ],
+        )])
+        .await
+        .unwrap();
+
+        let started = Instant::now();
+        let response = reqwest::Client::new()
+            .post(format!(
+                "{}/v1/chat/completions?beta=1",
+                upstream.base_url()
+            ))
+            .json(&serde_json::json!({"model": "gpt-4o"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            response.headers()["content-type"].to_str().unwrap(),
+            "text/event-stream"
+        );
+        assert_eq!(response.text().await.unwrap(), "data: a\n\ndata: b\n\n");
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        let requests = upstream.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/v1/chat/completions?beta=1");
+        assert_eq!(requests[0].body["model"], "gpt-4o");
+        upstream.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_fixtures_for_same_path_are_served_in_order() {
+        let upstream = MockUpstream::start_with_time_scale(
+            vec![
+                fixture("/v1/messages", vec![RecordedChunk::from_bytes(0, b"first")]),
+                fixture(
+                    "/v1/messages",
+                    vec![RecordedChunk::from_bytes(0, b"second")],
+                ),
+            ],
+            0.0,
+        )
+        .await
+        .unwrap();
+        let client = reqwest::Client::new();
+        let url = format!("{}/v1/messages", upstream.base_url());
+
+        let mut bodies = Vec::new();
+        for _ in 0..3 {
+            bodies.push(
+                client
+                    .post(&url)
+                    .send()
+                    .await
+                    .unwrap()
+                    .text()
+                    .await
+                    .unwrap(),
+            );
+        }
+        assert_eq!(bodies, vec!["first", "second", "second"]);
+
+        let missing = client
+            .post(format!("{}/v1/other", upstream.base_url()))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), 404);
+        upstream.shutdown().await;
+    }
+}
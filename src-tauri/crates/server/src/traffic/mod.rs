@@ -0,0 +1,25 @@
+//! 上游流量录制与回放
+//!
+//! 转换器（`openai_to_cw`、`cw_to_openai`、`anthropic_to_openai` 等）和流解析器依赖上游的真实格式，
+//! 手写片段难以覆盖格式漂移。本模块提供：
+//!
+//! - `fixture`: 录制文件格式（脱敏后的请求/响应，含流式原始字节和时间间隔）
+//! - `recorder`: 录制代理，把凭证的上游地址指向它即可录制真实流量
+//! - `mock_upstream`: 本地模拟上游，按录制文件回放响应
+//! - `output`: 把客户端收到的响应归一化为文本和工具调用
+//! - `harness`: 回放测试工具，让录制文件经过完整的网关请求链路，检查各前端格式下客户端看到的输出
+
+pub mod fixture;
+pub mod harness;
+pub mod mock_upstream;
+pub mod output;
+pub mod recorder;
+
+pub use fixture::{
+    ExpectedOutput, ExpectedToolCall, RecordedChunk, RecordedRequest, RecordedResponse,
+    TrafficFixture, UpstreamKind,
+};
+pub use harness::ReplayHarness;
+pub use mock_upstream::MockUpstream;
+pub use output::{ClientOutput, Frontend};
+pub use recorder::{RecorderConfig, RecordingProxy};
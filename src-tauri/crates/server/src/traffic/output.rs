@@ -0,0 +1,387 @@
+//! 客户端输出解析
+//!
+//! 把网关返回给客户端的响应（OpenAI / Anthropic 格式，流式或非流式）归一化为
+//! 文本 + 工具调用，便于与录制文件中的期望输出比较。
+
+use super::fixture::{ExpectedOutput, ExpectedToolCall};
+use proxycast_providers::streaming::resume::{SseEvent, SseFramer};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// 前端（客户端）格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Frontend {
+    /// `/v1/chat/completions`
+    OpenAI,
+    /// `/v1/messages`
+    Anthropic,
+}
+
+impl Frontend {
+    /// 所有前端格式
+    pub const ALL: [Frontend; 2] = [Frontend::OpenAI, Frontend::Anthropic];
+
+    /// 请求路径
+    pub fn path(&self) -> &'static str {
+        match self {
+            Frontend::OpenAI => "/v1/chat/completions",
+            Frontend::Anthropic => "/v1/messages",
+        }
+    }
+}
+
+/// 客户端看到的输出
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientOutput {
+    /// HTTP 状态码
+    pub status: u16,
+    /// 助手文本
+    pub text: String,
+    /// 工具调用（参数已解析为 JSON）
+    pub tool_calls: Vec<ExpectedToolCall>,
+    /// 是否正常结束（流式收到结束事件，非流式解析成功）
+    pub completed: bool,
+    /// 错误信息（错误事件或错误响应）
+    pub errors: Vec<String>,
+    /// 原始响应体
+    pub raw: String,
+}
+
+/// 流式工具调用累积状态
+#[derive(Default)]
+struct PendingToolCall {
+    name: String,
+    arguments: String,
+}
+
+impl PendingToolCall {
+    fn finish(self) -> ExpectedToolCall {
+        let arguments = if self.arguments.trim().is_empty() {
+            Value::Object(Default::default())
+        } else {
+            serde_json::from_str(&self.arguments).unwrap_or(Value::String(self.arguments))
+        };
+        ExpectedToolCall {
+            name: self.name,
+            arguments,
+        }
+    }
+}
+
+impl ClientOutput {
+    /// 解析响应体
+    pub fn parse(frontend: Frontend, stream: bool, status: u16, raw: String) -> Self {
+        let mut output = Self {
+            status,
+            raw,
+            ..Default::default()
+        };
+        if !(200..300).contains(&status) {
+            output.errors.push(format!("HTTP {status}: {}", output.raw));
+            return output;
+        }
+        let raw = output.raw.clone();
+        match (frontend, stream) {
+            (Frontend::OpenAI, true) => output.parse_openai_stream(&raw),
+            (Frontend::OpenAI, false) => output.parse_openai_json(&raw),
+            (Frontend::Anthropic, true) => output.parse_anthropic_stream(&raw),
+            (Frontend::Anthropic, false) => output.parse_anthropic_json(&raw),
+        }
+        output
+    }
+
+    /// 与期望输出比较，返回第一处差异
+    pub fn check(&self, expected: &ExpectedOutput) -> Result<(), String> {
+        if !self.errors.is_empty() {
+            return Err(format!("客户端收到错误: {:?}", self.errors));
+        }
+        if !self.completed {
+            return Err(format!("响应未正常结束:\n{}", self.raw));
+        }
+        if self.text != expected.text {
+            return Err(format!(
+                "文本不一致:\n  期望: {:?}\n  实际: {:?}",
+                expected.text, self.text
+            ));
+        }
+        if self.tool_calls != expected.tool_calls {
+            return Err(format!(
+                "工具调用不一致:\n  期望: {:?}\n  实际: {:?}",
+                expected.tool_calls, self.tool_calls
+            ));
+        }
+        Ok(())
+    }
+
+    fn events(raw: &str) -> Vec<SseEvent> {
+        let mut framer = SseFramer::default();
+        let mut events = framer.push(raw.as_bytes());
+        events.extend(framer.finish());
+        events.iter().filter_map(|e| SseEvent::parse(e)).collect()
+    }
+
+    fn parse_openai_stream(&mut self, raw: &str) {
+        let mut tools: Vec<PendingToolCall> = Vec::new();
+        for event in Self::events(raw) {
+            if event.is_done() {
+                self.completed = true;
+                continue;
+            }
+            if let Some(message) = event.error_message() {
+                self.errors.push(message);
+                continue;
+            }
+            let Some(json) = event.json() else {
+                continue;
+            };
+            let Some(delta) = json.pointer("/choices/0/delta") else {
+                continue;
+            };
+            if let Some(text) = delta.get("content").and_then(Value::as_str) {
+                self.text.push_str(text);
+            }
+            for call in delta
+                .get("tool_calls")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                let index = call.get("index").and_then(Value::as_u64).unwrap_or(0) as usize;
+                if tools.len() <= index {
+                    tools.resize_with(index + 1, PendingToolCall::default);
+                }
+                if let Some(name) = call.pointer("/function/name").and_then(Value::as_str) {
+                    tools[index].name.push_str(name);
+                }
+                if let Some(args) = call.pointer("/function/arguments").and_then(Value::as_str) {
+                    tools[index].arguments.push_str(args);
+                }
+            }
+        }
+        self.tool_calls = tools.into_iter().map(PendingToolCall::finish).collect();
+    }
+
+    fn parse_openai_json(&mut self, raw: &str) {
+        let Ok(json) = serde_json::from_str::<Value>(raw) else {
+            self.errors.push(format!("响应不是 JSON: {raw}"));
+            return;
+        };
+        if let Some(message) = json.pointer("/error/message").and_then(Value::as_str) {
+            self.errors.push(message.to_string());
+            return;
+        }
+        let Some(message) = json.pointer("/choices/0/message") else {
+            self.errors
+                .push(format!("响应缺少 choices[0].message: {raw}"));
+            return;
+        };
+        if let Some(text) = message.get("content").and_then(Value::as_str) {
+            self.text.push_str(text);
+        }
+        self.tool_calls = message
+            .get("tool_calls")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|call| {
+                PendingToolCall {
+                    name: call
+                        .pointer("/function/name")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                    arguments: call
+                        .pointer("/function/arguments")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                }
+                .finish()
+            })
+            .collect();
+        self.completed = true;
+    }
+
+    fn parse_anthropic_stream(&mut self, raw: &str) {
+        let mut tools: Vec<PendingToolCall> = Vec::new();
+        // 内容块索引 -> 工具调用序号
+        let mut tool_blocks: Vec<(u64, usize)> = Vec::new();
+        for event in Self::events(raw) {
+            if let Some(message) = event.error_message() {
+                self.errors.push(message);
+                continue;
+            }
+            let Some(json) = event.json() else {
+                continue;
+            };
+            let index = json.get("index").and_then(Value::as_u64).unwrap_or(0);
+            match json.get("type").and_then(Value::as_str).unwrap_or_default() {
+                "content_block_start" => {
+                    let block = &json["content_block"];
+                    match block.get("type").and_then(Value::as_str) {
+                        Some("tool_use") => {
+                            tool_blocks.push((index, tools.len()));
+                            tools.push(PendingToolCall {
+                                name: block["name"].as_str().unwrap_or_default().to_string(),
+                                arguments: String::new(),
+                            });
+                        }
+                        Some("text") => {
+                            if let Some(text) = block.get("text").and_then(Value::as_str) {
+                                self.text.push_str(text);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                "content_block_delta" => {
+                    let delta = &json["delta"];
+                    match delta.get("type").and_then(Value::as_str) {
+                        Some("text_delta") => {
+                            self.text
+                                .push_str(delta["text"].as_str().unwrap_or_default());
+                        }
+                        Some("input_json_delta") => {
+                            if let Some((_, tool)) =
+                                tool_blocks.iter().rev().find(|(block, _)| *block == index)
+                            {
+                                tools[*tool]
+                                    .arguments
+                                    .push_str(delta["partial_json"].as_str().unwrap_or_default());
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                "message_stop" => self.completed = true,
+                _ => {}
+            }
+        }
+        self.tool_calls = tools.into_iter().map(PendingToolCall::finish).collect();
+    }
+
+    fn parse_anthropic_json(&mut self, raw: &str) {
+        let Ok(json) = serde_json::from_str::<Value>(raw) else {
+            self.errors.push(format!("响应不是 JSON: {raw}"));
+            return;
+        };
+        if json.get("type").and_then(Value::as_str) == Some("error") {
+            self.errors.push(
+                json.pointer("/error/message")
+                    .and_then(Value::as_str)
+                    .unwrap_or(raw)
+                    .to_string(),
+            );
+            return;
+        }
+        let Some(content) = json.get("content").and_then(Value::as_array) else {
+            self.errors.push(format!("响应缺少 content: {raw}"));
+            return;
+        };
+        for block in content {
+            match block.get("type").and_then(Value::as_str) {
+                Some("text") => self
+                    .text
+                    .push_str(block["text"].as_str().unwrap_or_default()),
+                Some("tool_use") => self.tool_calls.push(ExpectedToolCall {
+                    name: block["name"].as_str().unwrap_or_default().to_string(),
+                    arguments: block
+                        .get("input")
+                        .cloned()
+                        .unwrap_or(Value::Object(Default::default())),
+                }),
+                _ => {}
+            }
+        }
+        self.completed = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn expected() -> ExpectedOutput {
+        ExpectedOutput {
+            text: "Checking.".to_string(),
+            tool_calls: vec![ExpectedToolCall {
+                name: "get_weather".to_string(),
+                arguments: json!({"city": "Paris"}),
+            }],
+            frontends: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_openai_stream_accumulates_text_and_tool_arguments() {
+        let raw = [
+            json!({"choices": [{"index": 0, "delta": {"role": "assistant", "content": "Check"}}]}),
+            json!({"choices": [{"index": 0, "delta": {"content": "ing."}}]}),
+            json!({"choices": [{"index": 0, "delta": {"tool_calls": [{"index": 0, "id": "call_1", "type": "function", "function": {"name": "get_weather", "arguments": ""}}]}}]}),
+            json!({"choices": [{"index": 0, "delta": {"tool_calls": [{"index": 0, "function": {"arguments": "{\"city\":"}}]}}]}),
+            json!({"choices": [{"index": 0, "delta": {"tool_calls": [{"index": 0, "function": {"arguments": "\"Paris\"}"}}]}}]}),
+        ]
+        .iter()
+        .map(|chunk| format!("data: {chunk}\n\n"))
+        .collect::<String>()
+            + "data: [DONE]\n\n";
+
+        let output = ClientOutput::parse(Frontend::OpenAI, true, 200, raw);
+        assert_eq!(output.check(&expected()), Ok(()));
+    }
+
+    #[test]
+    fn test_anthropic_stream_accumulates_text_and_tool_input() {
+        let events = [
+            json!({"type": "message_start", "message": {"id": "msg_1"}}),
+            json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "Checking."}}),
+            json!({"type": "content_block_stop", "index": 0}),
+            json!({"type": "content_block_start", "index": 1, "content_block": {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {}}}),
+            json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "{\"city\": \"Pa"}}),
+            json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "ris\"}"}}),
+            json!({"type": "content_block_stop", "index": 1}),
+            json!({"type": "message_stop"}),
+        ];
+        let raw: String = events
+            .iter()
+            .map(|e| format!("event: {}\ndata: {e}\n\n", e["type"].as_str().unwrap()))
+            .collect();
+
+        let output = ClientOutput::parse(Frontend::Anthropic, true, 200, raw);
+        assert_eq!(output.check(&expected()), Ok(()));
+    }
+
+    #[test]
+    fn test_non_stream_responses() {
+        let openai = json!({"choices": [{"message": {"role": "assistant", "content": "Checking.", "tool_calls": [
+            {"id": "call_1", "type": "function", "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}}
+        ]}}]});
+        let output = ClientOutput::parse(Frontend::OpenAI, false, 200, openai.to_string());
+        assert_eq!(output.check(&expected()), Ok(()));
+
+        let anthropic = json!({"type": "message", "content": [
+            {"type": "text", "text": "Checking."},
+            {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Paris"}}
+        ]});
+        let output = ClientOutput::parse(Frontend::Anthropic, false, 200, anthropic.to_string());
+        assert_eq!(output.check(&expected()), Ok(()));
+    }
+
+    #[test]
+    fn test_errors_and_truncation_are_reported() {
+        let truncated = "data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n".to_string();
+        let output = ClientOutput::parse(Frontend::OpenAI, true, 200, truncated);
+        assert!(output.check(&ExpectedOutput::default()).is_err());
+        assert!(!output.completed);
+
+        let error = "event: error\ndata: {\"error\":{\"type\":\"network_error\",\"message\":\"reset\"}}\n\n";
+        let output = ClientOutput::parse(Frontend::Anthropic, true, 200, error.to_string());
+        assert_eq!(output.errors, vec!["reset".to_string()]);
+
+        let output = ClientOutput::parse(Frontend::OpenAI, false, 503, "busy".to_string());
+        assert!(output.check(&ExpectedOutput::default()).is_err());
+    }
+}
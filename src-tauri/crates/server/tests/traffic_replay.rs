@@ -0,0 +1,65 @@
+//! 录制流量回放测试
+//!
+//! 让 `tests/fixtures/traffic` 下的每个录制文件经过完整的网关请求链路，
+//! 检查各前端格式下客户端看到的文本和工具调用。新增录制文件可用
+//! `proxycast-gateway record` 生成，补充 `expect` 后放入该目录。
+
+use proxycast_server::traffic::{ReplayHarness, TrafficFixture};
+use std::path::PathBuf;
+
+fn fixture_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/traffic")
+}
+
+#[tokio::test]
+async fn test_recorded_traffic_replays_in_every_frontend() {
+    let fixtures = TrafficFixture::load_dir(&fixture_dir()).unwrap();
+    assert!(!fixtures.is_empty(), "未找到录制文件");
+
+    let mut failures = Vec::new();
+    for fixture in &fixtures {
+        let Some(expect) = &fixture.expect else {
+            failures.push(format!("{}: 缺少 expect", fixture.name));
+            continue;
+        };
+        let stream = fixture.response.is_stream();
+        for frontend in expect.frontends() {
+            // 每个前端使用独立的网关和模拟上游，避免凭证状态互相影响
+            let harness = ReplayHarness::start_with_time_scale(fixture, 0.0)
+                .await
+                .unwrap();
+            let result = match harness.send(frontend, stream).await {
+                Ok(output) => output.check(expect),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                failures.push(format!(
+                    "{} ({frontend:?}, stream={stream}): {e}",
+                    fixture.name
+                ));
+            }
+            if harness.upstream_requests().is_empty() {
+                failures.push(format!("{} ({frontend:?}): 上游未收到请求", fixture.name));
+            }
+            harness.shutdown().await;
+        }
+    }
+
+    assert!(failures.is_empty(), "回放失败:\n{}", failures.join("\n"));
+}
+
+#[tokio::test]
+async fn test_streams_keep_recorded_timing() {
+    let fixture = TrafficFixture::load(&fixture_dir().join("claude-stream-text.json")).unwrap();
+    let recorded: u64 = fixture.response.chunks.iter().map(|c| c.delay_ms).sum();
+
+    let harness = ReplayHarness::start(&fixture).await.unwrap();
+    let started = std::time::Instant::now();
+    let output = harness
+        .send(proxycast_server::traffic::Frontend::Anthropic, true)
+        .await
+        .unwrap();
+    assert!(output.completed);
+    assert!(started.elapsed().as_millis() as u64 >= recorded);
+    harness.shutdown().await;
+}
@@ -13,7 +13,7 @@ use proxycast_core::database::dao::api_key_provider::{
     ProviderWithKeys,
 };
 use proxycast_core::database::system_providers::{get_system_providers, to_api_key_provider};
-use proxycast_core::database::DbConnection;
+use proxycast_core::database::{DbConnection, DbPool};
 use proxycast_core::models::{
     CredentialData, CredentialSource, PoolProviderType, ProviderCredential,
 };
@@ -953,12 +953,36 @@ impl ApiKeyProviderService {
         db: &DbConnection,
         provider_id: &str,
     ) -> Result<Option<(String, String)>, String> {
-        let conn = proxycast_core::database::lock_db(db)?;
+        let keys = {
+            let conn = proxycast_core::database::lock_db(db)?;
+            ApiKeyProviderDao::get_enabled_api_keys_by_provider(&conn, provider_id)
+                .map_err(|e| e.to_string())?
+        };
+        self.pick_api_key_entry(provider_id, &keys)
+    }
 
-        // 获取所有启用的 API Keys
-        let keys = ApiKeyProviderDao::get_enabled_api_keys_by_provider(&conn, provider_id)
-            .map_err(|e| e.to_string())?;
+    /// 获取下一个可用的 API Key 条目（连接池版本，查询走只读连接）
+    pub async fn get_next_api_key_entry_pooled(
+        &self,
+        pool: &DbPool,
+        provider_id: &str,
+    ) -> Result<Option<(String, String)>, String> {
+        let id = provider_id.to_string();
+        let keys = pool
+            .read_async(move |conn| {
+                ApiKeyProviderDao::get_enabled_api_keys_by_provider(conn, &id)
+                    .map_err(|e| e.to_string())
+            })
+            .await?;
+        self.pick_api_key_entry(provider_id, &keys)
+    }
 
+    /// 轮询选择 API Key 并解密
+    fn pick_api_key_entry(
+        &self,
+        provider_id: &str,
+        keys: &[ApiKeyEntry],
+    ) -> Result<Option<(String, String)>, String> {
         if keys.is_empty() {
             return Ok(None);
         }
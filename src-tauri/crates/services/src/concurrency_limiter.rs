@@ -0,0 +1,678 @@
+//! 凭证并发限制与公平排队
+//!
+//! 按凭证和 Provider 限制同时进行的上游请求数，超出上限的请求进入等待队列：
+//!
+//! - 交互式请求（IDE、CLI 客户端）严格优先于批量任务和定时任务
+//! - 同一优先级内按客户端做加权公平排队（起始时间公平排队，SFQ），
+//!   避免单个客户端的大量请求饿死其他客户端
+//! - 排队超时或队列已满时返回错误，由调用方转换为 429/503
+//!
+//! 进程内所有 `ProviderPoolService` 共享同一个限制器（见 [`global_concurrency_limiter`]），
+//! 因此网关请求、`BatchTaskExecutor` 和定时任务的并发会统一计数。
+
+use parking_lot::{Mutex, RwLock};
+use proxycast_core::config::ConcurrencySettings;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// 请求优先级
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestPriority {
+    /// 交互式请求（优先调度）
+    Interactive,
+    /// 批量任务和定时任务
+    Batch,
+}
+
+/// 排队请求的来源标识
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueClient {
+    /// 公平调度的流标识（如 `claude_code:ab12cd34`，同一流内先进先出）
+    pub flow: String,
+    /// 权重配置键（通常为客户端类型，如 `claude_code`、`batch`）
+    pub class: String,
+    /// 优先级
+    pub priority: RequestPriority,
+}
+
+impl QueueClient {
+    /// 交互式客户端
+    pub fn interactive(class: impl Into<String>, flow: impl Into<String>) -> Self {
+        Self {
+            flow: flow.into(),
+            class: class.into(),
+            priority: RequestPriority::Interactive,
+        }
+    }
+
+    /// 批量任务 / 定时任务
+    pub fn batch(class: impl Into<String>) -> Self {
+        let class = class.into();
+        Self {
+            flow: class.clone(),
+            class,
+            priority: RequestPriority::Batch,
+        }
+    }
+}
+
+/// 并发限制错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ConcurrencyError {
+    /// 排队超时
+    #[error("排队超时: 等待 {waited_ms}ms 后仍无可用并发槽位")]
+    QueueTimeout { waited_ms: u64 },
+    /// 队列已满
+    #[error("排队请求已达上限 ({max_depth})")]
+    QueueFull { max_depth: usize },
+}
+
+/// 并发统计快照
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConcurrencySnapshot {
+    /// 是否启用并发限制
+    pub enabled: bool,
+    /// 当前进行中的请求数
+    pub in_flight: u64,
+    /// 当前排队的请求数
+    pub queue_depth: u64,
+    /// 按优先级的排队数
+    pub queue_depth_by_priority: HashMap<RequestPriority, u64>,
+    /// 按凭证的排队数
+    pub queue_depth_by_credential: HashMap<String, u64>,
+    /// 按凭证的进行中请求数
+    pub in_flight_by_credential: HashMap<String, u64>,
+    /// 按 Provider 的进行中请求数
+    pub in_flight_by_provider: HashMap<String, u64>,
+    /// 累计进入排队的请求数
+    pub total_queued: u64,
+    /// 累计排队超时数
+    pub total_timeouts: u64,
+    /// 累计因队列已满被拒绝的请求数
+    pub total_rejected: u64,
+    /// 最近一次出队请求的排队耗时（毫秒）
+    pub last_wait_ms: u64,
+}
+
+/// 排队中的请求
+#[derive(Debug)]
+struct Waiter {
+    id: u64,
+    credential_id: String,
+    provider: String,
+    priority: RequestPriority,
+    /// SFQ 起始标签（越小越先调度）
+    start_tag: f64,
+    enqueued_at: Instant,
+    tx: oneshot::Sender<()>,
+}
+
+#[derive(Debug, Default)]
+struct LimiterState {
+    in_flight_credential: HashMap<String, u32>,
+    in_flight_provider: HashMap<String, u32>,
+    waiters: Vec<Waiter>,
+    next_id: u64,
+    /// 系统虚拟时间（最近出队请求的起始标签）
+    virtual_time: f64,
+    /// 各流最近一个请求的结束标签
+    flow_finish: HashMap<String, f64>,
+    total_queued: u64,
+    total_timeouts: u64,
+    total_rejected: u64,
+    last_wait_ms: u64,
+}
+
+/// 凭证和 Provider 的并发上限（0 表示不限制）
+fn limits(settings: &ConcurrencySettings, credential_id: &str, provider: &str) -> (u32, u32) {
+    let credential_limit = settings
+        .credential_limits
+        .get(credential_id)
+        .copied()
+        .unwrap_or(settings.max_per_credential);
+    let provider_limit = settings
+        .provider_limits
+        .get(provider)
+        .copied()
+        .unwrap_or(settings.max_per_provider);
+    (credential_limit, provider_limit)
+}
+
+impl LimiterState {
+    fn increment(&mut self, credential_id: &str, provider: &str) {
+        *self
+            .in_flight_credential
+            .entry(credential_id.to_string())
+            .or_default() += 1;
+        *self
+            .in_flight_provider
+            .entry(provider.to_string())
+            .or_default() += 1;
+    }
+
+    fn decrement(&mut self, credential_id: &str, provider: &str) {
+        for (map, key) in [
+            (&mut self.in_flight_credential, credential_id),
+            (&mut self.in_flight_provider, provider),
+        ] {
+            if let Some(count) = map.get_mut(key) {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    map.remove(key);
+                }
+            }
+        }
+    }
+
+    fn has_capacity(
+        &self,
+        settings: &ConcurrencySettings,
+        credential_id: &str,
+        provider: &str,
+    ) -> bool {
+        if !settings.enabled {
+            return true;
+        }
+        let (credential_limit, provider_limit) = limits(settings, credential_id, provider);
+        let credential_ok = credential_limit == 0
+            || self
+                .in_flight_credential
+                .get(credential_id)
+                .copied()
+                .unwrap_or(0)
+                < credential_limit;
+        let provider_ok = provider_limit == 0
+            || self.in_flight_provider.get(provider).copied().unwrap_or(0) < provider_limit;
+        credential_ok && provider_ok
+    }
+
+    /// 有可用槽位且没有同凭证 / 同 Provider 的请求在排队（新请求不能插队）
+    fn is_free(&self, settings: &ConcurrencySettings, credential_id: &str, provider: &str) -> bool {
+        let (_, provider_limit) = limits(settings, credential_id, provider);
+        self.has_capacity(settings, credential_id, provider)
+            && !self.waiters.iter().any(|w| {
+                w.credential_id == credential_id || (provider_limit > 0 && w.provider == provider)
+            })
+    }
+
+    /// 按优先级和 SFQ 标签依次放行有可用槽位的排队请求
+    fn dispatch(&mut self, settings: &ConcurrencySettings) {
+        loop {
+            let next = self
+                .waiters
+                .iter()
+                .enumerate()
+                .filter(|(_, w)| self.has_capacity(settings, &w.credential_id, &w.provider))
+                .min_by(|(_, a), (_, b)| {
+                    a.priority
+                        .cmp(&b.priority)
+                        .then(a.start_tag.total_cmp(&b.start_tag))
+                        .then(a.id.cmp(&b.id))
+                })
+                .map(|(index, _)| index);
+
+            let Some(index) = next else {
+                return;
+            };
+            let waiter = self.waiters.swap_remove(index);
+            self.virtual_time = self.virtual_time.max(waiter.start_tag);
+            self.last_wait_ms = waiter.enqueued_at.elapsed().as_millis() as u64;
+            self.increment(&waiter.credential_id, &waiter.provider);
+            // 接收端只会在持有锁时从队列移除自身后才被丢弃，因此这里不会发送失败
+            let _ = waiter.tx.send(());
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct LimiterInner {
+    settings: RwLock<ConcurrencySettings>,
+    state: Mutex<LimiterState>,
+}
+
+impl LimiterInner {
+    fn release(&self, credential_id: &str, provider: &str) {
+        let settings = self.settings.read().clone();
+        let mut state = self.state.lock();
+        state.decrement(credential_id, provider);
+        state.dispatch(&settings);
+    }
+}
+
+/// 并发槽位
+///
+/// 持有期间占用凭证和 Provider 的并发名额，drop 时释放并唤醒下一个排队请求。
+#[derive(Debug)]
+pub struct ConcurrencyPermit {
+    inner: Arc<LimiterInner>,
+    credential_id: String,
+    provider: String,
+}
+
+impl ConcurrencyPermit {
+    /// 占用的凭证 ID
+    pub fn credential_id(&self) -> &str {
+        &self.credential_id
+    }
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        self.inner.release(&self.credential_id, &self.provider);
+    }
+}
+
+/// 等待中的请求（被取消时从队列移除，或释放已分配的槽位）
+struct QueuedGuard<'a> {
+    inner: &'a Arc<LimiterInner>,
+    id: u64,
+    credential_id: &'a str,
+    provider: &'a str,
+    rx: oneshot::Receiver<()>,
+    done: bool,
+}
+
+impl QueuedGuard<'_> {
+    /// 超时后确认是否在最后时刻已被放行
+    fn resolve_timeout(&mut self) -> bool {
+        let mut state = self.inner.state.lock();
+        if let Some(index) = state.waiters.iter().position(|w| w.id == self.id) {
+            state.waiters.swap_remove(index);
+            state.total_timeouts += 1;
+            self.done = true;
+            return false;
+        }
+        drop(state);
+        // 已不在队列中说明已被放行，通知必然已送达
+        self.done = self.rx.try_recv().is_ok();
+        self.done
+    }
+}
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        let granted = {
+            let mut state = self.inner.state.lock();
+            match state.waiters.iter().position(|w| w.id == self.id) {
+                Some(index) => {
+                    state.waiters.swap_remove(index);
+                    false
+                }
+                None => self.rx.try_recv().is_ok(),
+            }
+        };
+        if granted {
+            self.inner.release(self.credential_id, self.provider);
+        }
+    }
+}
+
+/// 并发限制器
+#[derive(Debug, Clone, Default)]
+pub struct ConcurrencyLimiter {
+    inner: Arc<LimiterInner>,
+}
+
+/// 全局并发限制器实例
+static GLOBAL_LIMITER: OnceLock<ConcurrencyLimiter> = OnceLock::new();
+
+/// 获取全局并发限制器（进程内共享）
+pub fn global_concurrency_limiter() -> ConcurrencyLimiter {
+    GLOBAL_LIMITER
+        .get_or_init(ConcurrencyLimiter::default)
+        .clone()
+}
+
+impl ConcurrencyLimiter {
+    /// 创建新的并发限制器
+    pub fn new(settings: ConcurrencySettings) -> Self {
+        let limiter = Self::default();
+        limiter.update_settings(settings);
+        limiter
+    }
+
+    /// 获取配置
+    pub fn settings(&self) -> ConcurrencySettings {
+        self.inner.settings.read().clone()
+    }
+
+    /// 更新配置（上限提高时立即放行排队请求）
+    pub fn update_settings(&self, settings: ConcurrencySettings) {
+        *self.inner.settings.write() = settings.clone();
+        self.inner.state.lock().dispatch(&settings);
+    }
+
+    /// 凭证当前是否有可用并发槽位（无排队请求时）
+    pub fn has_capacity(&self, credential_id: &str, provider: &str) -> bool {
+        let settings = self.inner.settings.read().clone();
+        let state = self.inner.state.lock();
+        state.is_free(&settings, credential_id, &provider.to_lowercase())
+    }
+
+    /// 立即获取槽位（无可用槽位时返回 None，不排队）
+    pub fn try_acquire(&self, credential_id: &str, provider: &str) -> Option<ConcurrencyPermit> {
+        let settings = self.inner.settings.read().clone();
+        let provider = provider.to_lowercase();
+        let mut state = self.inner.state.lock();
+        if !state.is_free(&settings, credential_id, &provider) {
+            return None;
+        }
+        state.increment(credential_id, &provider);
+        drop(state);
+        Some(self.permit(credential_id, &provider))
+    }
+
+    /// 获取槽位，无可用槽位时按优先级和公平调度排队等待
+    pub async fn acquire(
+        &self,
+        credential_id: &str,
+        provider: &str,
+        client: &QueueClient,
+    ) -> Result<ConcurrencyPermit, ConcurrencyError> {
+        let settings = self.inner.settings.read().clone();
+        let provider = provider.to_lowercase();
+
+        let (id, rx) = {
+            let mut state = self.inner.state.lock();
+
+            // 有空闲槽位且没有同凭证 / 同 Provider 的请求在排队时直接放行
+            if state.is_free(&settings, credential_id, &provider) {
+                state.increment(credential_id, &provider);
+                drop(state);
+                return Ok(self.permit(credential_id, &provider));
+            }
+
+            if state.waiters.len() >= settings.max_queue_depth {
+                state.total_rejected += 1;
+                return Err(ConcurrencyError::QueueFull {
+                    max_depth: settings.max_queue_depth,
+                });
+            }
+
+            // SFQ：起始标签 = max(系统虚拟时间, 该流上一个请求的结束标签)
+            let weight = settings
+                .client_weights
+                .get(&client.class)
+                .copied()
+                .unwrap_or(1)
+                .max(1) as f64;
+            let last_finish = state.flow_finish.get(&client.flow).copied().unwrap_or(0.0);
+            let start_tag = state.virtual_time.max(last_finish);
+            state
+                .flow_finish
+                .insert(client.flow.clone(), start_tag + 1.0 / weight);
+
+            let id = state.next_id;
+            state.next_id += 1;
+            state.total_queued += 1;
+            let (tx, rx) = oneshot::channel();
+            state.waiters.push(Waiter {
+                id,
+                credential_id: credential_id.to_string(),
+                provider: provider.clone(),
+                priority: client.priority,
+                start_tag,
+                enqueued_at: Instant::now(),
+                tx,
+            });
+            state.dispatch(&settings);
+            if state.waiters.is_empty() {
+                state.flow_finish.clear();
+            }
+            (id, rx)
+        };
+
+        tracing::debug!(
+            "[CONCURRENCY] 请求排队: credential={} provider={} flow={} priority={:?}",
+            credential_id,
+            provider,
+            client.flow,
+            client.priority
+        );
+
+        let started = Instant::now();
+        let mut guard = QueuedGuard {
+            inner: &self.inner,
+            id,
+            credential_id,
+            provider: &provider,
+            rx,
+            done: false,
+        };
+        let timeout = Duration::from_secs(settings.queue_timeout_secs);
+
+        match tokio::time::timeout(timeout, &mut guard.rx).await {
+            Ok(Ok(())) => {
+                guard.done = true;
+                Ok(self.permit(credential_id, &provider))
+            }
+            _ => {
+                if guard.resolve_timeout() {
+                    return Ok(self.permit(credential_id, &provider));
+                }
+                tracing::warn!(
+                    "[CONCURRENCY] 排队超时: credential={} provider={} flow={}",
+                    credential_id,
+                    provider,
+                    client.flow
+                );
+                Err(ConcurrencyError::QueueTimeout {
+                    waited_ms: started.elapsed().as_millis() as u64,
+                })
+            }
+        }
+    }
+
+    fn permit(&self, credential_id: &str, provider: &str) -> ConcurrencyPermit {
+        ConcurrencyPermit {
+            inner: self.inner.clone(),
+            credential_id: credential_id.to_string(),
+            provider: provider.to_lowercase(),
+        }
+    }
+
+    /// 获取并发统计快照
+    pub fn snapshot(&self) -> ConcurrencySnapshot {
+        let enabled = self.inner.settings.read().enabled;
+        let state = self.inner.state.lock();
+
+        let mut snapshot = ConcurrencySnapshot {
+            enabled,
+            in_flight: state.in_flight_credential.values().map(|&n| n as u64).sum(),
+            queue_depth: state.waiters.len() as u64,
+            in_flight_by_credential: state
+                .in_flight_credential
+                .iter()
+                .map(|(k, &v)| (k.clone(), v as u64))
+                .collect(),
+            in_flight_by_provider: state
+                .in_flight_provider
+                .iter()
+                .map(|(k, &v)| (k.clone(), v as u64))
+                .collect(),
+            total_queued: state.total_queued,
+            total_timeouts: state.total_timeouts,
+            total_rejected: state.total_rejected,
+            last_wait_ms: state.last_wait_ms,
+            ..Default::default()
+        };
+        for waiter in &state.waiters {
+            *snapshot
+                .queue_depth_by_priority
+                .entry(waiter.priority)
+                .or_default() += 1;
+            *snapshot
+                .queue_depth_by_credential
+                .entry(waiter.credential_id.clone())
+                .or_default() += 1;
+        }
+        snapshot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limited(max_per_credential: u32) -> ConcurrencyLimiter {
+        ConcurrencyLimiter::new(ConcurrencySettings {
+            enabled: true,
+            max_per_credential,
+            queue_timeout_secs: 5,
+            ..Default::default()
+        })
+    }
+
+    /// 排队一个请求，放行后把 `label` 写入 `order`
+    fn spawn_waiter(
+        limiter: &ConcurrencyLimiter,
+        client: QueueClient,
+        label: &'static str,
+        order: &Arc<Mutex<Vec<&'static str>>>,
+    ) -> tokio::task::JoinHandle<()> {
+        let limiter = limiter.clone();
+        let order = order.clone();
+        tokio::spawn(async move {
+            let permit = limiter.acquire("cred-1", "kiro", &client).await.unwrap();
+            order.lock().push(label);
+            drop(permit);
+        })
+    }
+
+    async fn wait_for_queue(limiter: &ConcurrencyLimiter, depth: u64) {
+        while limiter.snapshot().queue_depth < depth {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_limit_and_release() {
+        let limiter = limited(1);
+        let client = QueueClient::interactive("cursor", "cursor");
+
+        let permit = limiter.acquire("cred-1", "kiro", &client).await.unwrap();
+        assert!(limiter.try_acquire("cred-1", "kiro").is_none());
+        // 其他凭证不受影响
+        assert!(limiter.try_acquire("cred-2", "kiro").is_some());
+
+        let waiter = {
+            let limiter = limiter.clone();
+            let client = client.clone();
+            tokio::spawn(async move { limiter.acquire("cred-1", "kiro", &client).await })
+        };
+        wait_for_queue(&limiter, 1).await;
+        assert_eq!(limiter.snapshot().queue_depth_by_credential["cred-1"], 1);
+
+        drop(permit);
+        let second = waiter.await.unwrap().unwrap();
+        assert_eq!(second.credential_id(), "cred-1");
+        assert_eq!(limiter.snapshot().in_flight, 1);
+        drop(second);
+        assert_eq!(limiter.snapshot().in_flight, 0);
+    }
+
+    #[tokio::test]
+    async fn test_interactive_before_batch_and_fair_across_flows() {
+        let limiter = limited(1);
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let holder = limiter
+            .acquire("cred-1", "kiro", &QueueClient::batch("batch"))
+            .await
+            .unwrap();
+
+        let mut handles = Vec::new();
+        for label in ["batch-1", "batch-2"] {
+            handles.push(spawn_waiter(
+                &limiter,
+                QueueClient::batch("batch"),
+                label,
+                &order,
+            ));
+            wait_for_queue(&limiter, handles.len() as u64).await;
+        }
+        // 客户端 A 连续发送 3 个请求后，客户端 B 才发送 1 个
+        for label in ["a-1", "a-2", "a-3"] {
+            handles.push(spawn_waiter(
+                &limiter,
+                QueueClient::interactive("cursor", "cursor:a"),
+                label,
+                &order,
+            ));
+            wait_for_queue(&limiter, handles.len() as u64).await;
+        }
+        handles.push(spawn_waiter(
+            &limiter,
+            QueueClient::interactive("cursor", "cursor:b"),
+            "b-1",
+            &order,
+        ));
+        wait_for_queue(&limiter, handles.len() as u64).await;
+
+        drop(holder);
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        // 交互式请求全部先于批量任务；B 不需要等 A 的全部请求完成
+        assert_eq!(
+            *order.lock(),
+            vec!["a-1", "b-1", "a-2", "a-3", "batch-1", "batch-2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_queue_timeout_and_full() {
+        let limiter = ConcurrencyLimiter::new(ConcurrencySettings {
+            enabled: true,
+            max_per_credential: 1,
+            queue_timeout_secs: 0,
+            max_queue_depth: 1,
+            ..Default::default()
+        });
+        let client = QueueClient::interactive("cursor", "cursor");
+        let _permit = limiter.acquire("cred-1", "kiro", &client).await.unwrap();
+
+        let err = limiter
+            .acquire("cred-1", "kiro", &client)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ConcurrencyError::QueueTimeout { .. }));
+        let snapshot = limiter.snapshot();
+        assert_eq!(snapshot.total_timeouts, 1);
+        assert_eq!(snapshot.queue_depth, 0);
+
+        limiter.update_settings(ConcurrencySettings {
+            queue_timeout_secs: 5,
+            max_queue_depth: 0,
+            ..limiter.settings()
+        });
+        let err = limiter
+            .acquire("cred-1", "kiro", &client)
+            .await
+            .unwrap_err();
+        assert_eq!(err, ConcurrencyError::QueueFull { max_depth: 0 });
+    }
+
+    #[tokio::test]
+    async fn test_provider_limit_and_disabled() {
+        let limiter = ConcurrencyLimiter::new(ConcurrencySettings {
+            enabled: true,
+            max_per_credential: 0,
+            provider_limits: HashMap::from([("kiro".to_string(), 1)]),
+            ..Default::default()
+        });
+        let _permit = limiter.try_acquire("cred-1", "kiro").unwrap();
+        assert!(limiter.try_acquire("cred-2", "kiro").is_none());
+        assert!(limiter.try_acquire("cred-3", "openai").is_some());
+
+        limiter.update_settings(ConcurrencySettings::default());
+        assert!(limiter.try_acquire("cred-2", "kiro").is_some());
+    }
+}
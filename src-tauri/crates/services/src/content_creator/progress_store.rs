@@ -1,26 +1,50 @@
 //! 进度持久化存储
 //!
-//! 将工作流进度保存到 SQLite 数据库
+//! 将工作流进度保存到主 SQLite 数据库，表结构由 [`migrations`] 注册到
+//! `MigrationRegistry`，随主数据库统一迁移、备份和做版本检查。
 
 use super::types::*;
 use anyhow::Result;
+use proxycast_core::database::{lock_db, DbConnection, Migration};
 use rusqlite::{params, Connection};
-use std::path::Path;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use tracing::{debug, info};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info, warn};
+
+/// 迁移注册表中的组件名
+pub const MIGRATION_COMPONENT: &str = "content_workflow";
+
+/// 内容创作工作流的数据库迁移
+pub fn migrations() -> Vec<Migration> {
+    vec![Migration::new(
+        MIGRATION_COMPONENT,
+        1,
+        "workflow_progress",
+        |conn| ProgressStore::create_tables(conn).map_err(|e| format!("创建工作流进度表失败: {e}")),
+    )]
+}
 
 /// 进度存储服务
 pub struct ProgressStore {
-    conn: Arc<Mutex<Connection>>,
+    db: DbConnection,
 }
 
 impl ProgressStore {
-    /// 创建新的进度存储
-    pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
-        let conn = Connection::open(db_path)?;
+    /// 基于共享的数据库连接创建进度存储
+    ///
+    /// 表由迁移创建，调用前数据库需已执行 [`migrations`]。
+    pub fn new(db: DbConnection) -> Self {
+        Self { db }
+    }
+
+    /// 创建内存进度存储（测试用）
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        Self::create_tables(&conn)?;
+        Ok(Self::new(Arc::new(Mutex::new(conn))))
+    }
 
-        // 创建表
+    /// 创建 workflow_progress / workflow_step_runs 表及索引（幂等）
+    pub fn create_tables(conn: &Connection) -> rusqlite::Result<()> {
         conn.execute(
             "CREATE TABLE IF NOT EXISTS workflow_progress (
                 workflow_id TEXT PRIMARY KEY,
@@ -34,22 +58,41 @@ impl ProgressStore {
             [],
         )?;
 
-        // 创建索引
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_workflow_updated_at ON workflow_progress(updated_at DESC)",
             [],
         )?;
 
-        info!("进度存储初始化完成");
+        // 步骤执行记录：每次执行/重跑步骤都会追加一条
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS workflow_step_runs (
+                id TEXT PRIMARY KEY,
+                workflow_id TEXT NOT NULL,
+                step_index INTEGER NOT NULL,
+                step_id TEXT NOT NULL,
+                parameters_json TEXT,
+                result_json TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_step_runs_workflow ON workflow_step_runs(workflow_id, step_index)",
+            [],
+        )?;
+
+        info!("工作流进度表初始化完成");
+        Ok(())
+    }
 
-        Ok(Self {
-            conn: Arc::new(Mutex::new(conn)),
-        })
+    fn conn(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        lock_db(&self.db).map_err(anyhow::Error::msg)
     }
 
     /// 保存工作流进度
     pub async fn save_progress(&self, workflow: &WorkflowState) -> Result<()> {
-        let conn = self.conn.lock().await;
+        let conn = self.conn()?;
 
         let steps_json = serde_json::to_string(&workflow.steps)?;
         let theme_str = serde_json::to_string(&workflow.theme)?;
@@ -76,46 +119,17 @@ impl ProgressStore {
 
     /// 加载工作流进度
     pub async fn load_progress(&self, workflow_id: &str) -> Result<Option<WorkflowState>> {
-        let conn = self.conn.lock().await;
+        let conn = self.conn()?;
 
         let mut stmt = conn.prepare(
             "SELECT workflow_id, theme, mode, steps_json, current_step_index, created_at, updated_at
              FROM workflow_progress WHERE workflow_id = ?1",
         )?;
 
-        let result = stmt.query_row(params![workflow_id], |row| {
-            let workflow_id: String = row.get(0)?;
-            let theme_str: String = row.get(1)?;
-            let mode_str: String = row.get(2)?;
-            let steps_json: String = row.get(3)?;
-            let current_step_index: i32 = row.get(4)?;
-            let created_at: i64 = row.get(5)?;
-            let updated_at: i64 = row.get(6)?;
-
-            Ok(WorkflowProgress {
-                workflow_id,
-                theme: serde_json::from_str(&theme_str).unwrap_or_default(),
-                mode: serde_json::from_str(&mode_str).unwrap_or_default(),
-                steps_json,
-                current_step_index,
-                created_at,
-                updated_at,
-            })
-        });
+        let result = stmt.query_row(params![workflow_id], row_to_progress);
 
         match result {
-            Ok(progress) => {
-                let steps: Vec<WorkflowStep> = serde_json::from_str(&progress.steps_json)?;
-                Ok(Some(WorkflowState {
-                    id: progress.workflow_id,
-                    theme: progress.theme,
-                    mode: progress.mode,
-                    steps,
-                    current_step_index: progress.current_step_index as usize,
-                    created_at: progress.created_at,
-                    updated_at: progress.updated_at,
-                }))
-            }
+            Ok(progress) => Ok(Some(progress_to_state(progress)?)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(e.into()),
         }
@@ -123,43 +137,29 @@ impl ProgressStore {
 
     /// 删除工作流进度
     pub async fn delete_progress(&self, workflow_id: &str) -> Result<()> {
-        let conn = self.conn.lock().await;
+        let conn = self.conn()?;
         conn.execute(
             "DELETE FROM workflow_progress WHERE workflow_id = ?1",
             params![workflow_id],
         )?;
+        conn.execute(
+            "DELETE FROM workflow_step_runs WHERE workflow_id = ?1",
+            params![workflow_id],
+        )?;
         debug!("删除工作流进度: {}", workflow_id);
         Ok(())
     }
 
     /// 获取最近的工作流列表
     pub async fn list_recent(&self, limit: usize) -> Result<Vec<WorkflowProgress>> {
-        let conn = self.conn.lock().await;
+        let conn = self.conn()?;
 
         let mut stmt = conn.prepare(
             "SELECT workflow_id, theme, mode, steps_json, current_step_index, created_at, updated_at
              FROM workflow_progress ORDER BY updated_at DESC LIMIT ?1",
         )?;
 
-        let rows = stmt.query_map(params![limit as i32], |row| {
-            let workflow_id: String = row.get(0)?;
-            let theme_str: String = row.get(1)?;
-            let mode_str: String = row.get(2)?;
-            let steps_json: String = row.get(3)?;
-            let current_step_index: i32 = row.get(4)?;
-            let created_at: i64 = row.get(5)?;
-            let updated_at: i64 = row.get(6)?;
-
-            Ok(WorkflowProgress {
-                workflow_id,
-                theme: serde_json::from_str(&theme_str).unwrap_or_default(),
-                mode: serde_json::from_str(&mode_str).unwrap_or_default(),
-                steps_json,
-                current_step_index,
-                created_at,
-                updated_at,
-            })
-        })?;
+        let rows = stmt.query_map(params![limit as i32], row_to_progress)?;
 
         let mut results = Vec::new();
         for row in rows {
@@ -171,7 +171,7 @@ impl ProgressStore {
 
     /// 清理过期的工作流（超过指定天数）
     pub async fn cleanup_expired(&self, days: i64) -> Result<usize> {
-        let conn = self.conn.lock().await;
+        let conn = self.conn()?;
 
         let cutoff = chrono::Utc::now().timestamp_millis() - (days * 24 * 60 * 60 * 1000);
 
@@ -179,6 +179,11 @@ impl ProgressStore {
             "DELETE FROM workflow_progress WHERE updated_at < ?1",
             params![cutoff],
         )?;
+        conn.execute(
+            "DELETE FROM workflow_step_runs
+             WHERE workflow_id NOT IN (SELECT workflow_id FROM workflow_progress)",
+            [],
+        )?;
 
         if count > 0 {
             info!("清理了 {} 个过期工作流", count);
@@ -186,4 +191,247 @@ impl ProgressStore {
 
         Ok(count)
     }
+
+    /// 加载所有未完成的工作流（用于启动时恢复）
+    ///
+    /// 一次查询取回全部进度行，解析失败的行记录警告后跳过。
+    pub async fn list_incomplete(&self) -> Result<Vec<WorkflowState>> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT workflow_id, theme, mode, steps_json, current_step_index, created_at, updated_at
+             FROM workflow_progress ORDER BY updated_at DESC",
+        )?;
+        let rows = stmt.query_map([], row_to_progress)?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            let progress = row?;
+            let workflow_id = progress.workflow_id.clone();
+            match progress_to_state(progress) {
+                Ok(workflow) if !workflow.is_finished() => results.push(workflow),
+                Ok(_) => {}
+                Err(e) => warn!("加载工作流 {} 失败，已忽略: {}", workflow_id, e),
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// 记录一次步骤执行
+    pub async fn record_step_run(&self, run: &StepRunRecord) -> Result<()> {
+        let conn = self.conn()?;
+
+        let parameters_json = run
+            .parameters
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+        let result_json = serde_json::to_string(&run.result)?;
+
+        conn.execute(
+            "INSERT OR REPLACE INTO workflow_step_runs
+             (id, workflow_id, step_index, step_id, parameters_json, result_json, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                run.id,
+                run.workflow_id,
+                run.step_index as i32,
+                run.step_id,
+                parameters_json,
+                result_json,
+                run.created_at,
+            ],
+        )?;
+
+        debug!(
+            "记录步骤执行: {} step={} run={}",
+            run.workflow_id, run.step_index, run.id
+        );
+        Ok(())
+    }
+
+    /// 获取工作流的步骤执行记录（按时间升序）
+    pub async fn list_step_runs(&self, workflow_id: &str) -> Result<Vec<StepRunRecord>> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, workflow_id, step_index, step_id, parameters_json, result_json, created_at
+             FROM workflow_step_runs WHERE workflow_id = ?1
+             ORDER BY created_at ASC, rowid ASC",
+        )?;
+
+        let rows = stmt.query_map(params![workflow_id], |row| {
+            let id: String = row.get(0)?;
+            let workflow_id: String = row.get(1)?;
+            let step_index: i32 = row.get(2)?;
+            let step_id: String = row.get(3)?;
+            let parameters_json: Option<String> = row.get(4)?;
+            let result_json: String = row.get(5)?;
+            let created_at: i64 = row.get(6)?;
+            Ok((
+                id,
+                workflow_id,
+                step_index,
+                step_id,
+                parameters_json,
+                result_json,
+                created_at,
+            ))
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            let (id, workflow_id, step_index, step_id, parameters_json, result_json, created_at) =
+                row?;
+            results.push(StepRunRecord {
+                id,
+                workflow_id,
+                step_index: step_index as usize,
+                step_id,
+                parameters: parameters_json
+                    .as_deref()
+                    .map(serde_json::from_str)
+                    .transpose()?,
+                result: serde_json::from_str(&result_json)?,
+                created_at,
+            });
+        }
+
+        Ok(results)
+    }
+}
+
+/// 将 `workflow_progress` 行映射为 [`WorkflowProgress`]
+fn row_to_progress(row: &rusqlite::Row<'_>) -> rusqlite::Result<WorkflowProgress> {
+    let theme_str: String = row.get(1)?;
+    let mode_str: String = row.get(2)?;
+
+    Ok(WorkflowProgress {
+        workflow_id: row.get(0)?,
+        theme: serde_json::from_str(&theme_str).unwrap_or_default(),
+        mode: serde_json::from_str(&mode_str).unwrap_or_default(),
+        steps_json: row.get(3)?,
+        current_step_index: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+/// 解析步骤 JSON，还原为 [`WorkflowState`]
+fn progress_to_state(progress: WorkflowProgress) -> Result<WorkflowState> {
+    let steps: Vec<WorkflowStep> = serde_json::from_str(&progress.steps_json)?;
+    Ok(WorkflowState {
+        id: progress.workflow_id,
+        theme: progress.theme,
+        mode: progress.mode,
+        steps,
+        current_step_index: progress.current_step_index as usize,
+        created_at: progress.created_at,
+        updated_at: progress.updated_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn sample_workflow(id: &str, statuses: &[StepStatus]) -> WorkflowState {
+        let steps = statuses
+            .iter()
+            .enumerate()
+            .map(|(i, status)| WorkflowStep {
+                definition: StepDefinition {
+                    id: format!("step-{i}"),
+                    step_type: StepType::Write,
+                    title: format!("步骤 {i}"),
+                    description: None,
+                    form: None,
+                    ai_task: None,
+                    behavior: StepBehavior::default(),
+                },
+                status: status.clone(),
+                result: None,
+            })
+            .collect();
+
+        WorkflowState {
+            id: id.to_string(),
+            theme: ThemeType::Novel,
+            mode: CreationMode::Guided,
+            steps,
+            current_step_index: 0,
+            created_at: 1,
+            updated_at: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_incomplete_filters_finished() {
+        let store = ProgressStore::open_in_memory().unwrap();
+        store
+            .save_progress(&sample_workflow(
+                "done",
+                &[StepStatus::Completed, StepStatus::Skipped],
+            ))
+            .await
+            .unwrap();
+        store
+            .save_progress(&sample_workflow(
+                "running",
+                &[StepStatus::Completed, StepStatus::Active],
+            ))
+            .await
+            .unwrap();
+        // 步骤 JSON 损坏的行被跳过，不影响其他工作流恢复
+        store
+            .conn()
+            .unwrap()
+            .execute(
+                "INSERT INTO workflow_progress VALUES ('broken', '\"novel\"', '\"guided\"', 'not json', 0, 2, 2)",
+                [],
+            )
+            .unwrap();
+
+        let incomplete = store.list_incomplete().await.unwrap();
+        assert_eq!(incomplete.len(), 1);
+        assert_eq!(incomplete[0].id, "running");
+    }
+
+    #[tokio::test]
+    async fn test_step_runs_roundtrip_and_delete() {
+        let store = ProgressStore::open_in_memory().unwrap();
+        store
+            .save_progress(&sample_workflow("wf", &[StepStatus::Active]))
+            .await
+            .unwrap();
+
+        let mut parameters = HashMap::new();
+        parameters.insert("style".to_string(), serde_json::json!("casual"));
+        let run = StepRunRecord {
+            id: "run-1".to_string(),
+            workflow_id: "wf".to_string(),
+            step_index: 0,
+            step_id: "step-0".to_string(),
+            parameters: Some(parameters),
+            result: StepResult {
+                ai_output: Some(serde_json::json!({"content": "hello"})),
+                ..Default::default()
+            },
+            created_at: 10,
+        };
+        store.record_step_run(&run).await.unwrap();
+
+        let runs = store.list_step_runs("wf").await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].step_id, "step-0");
+        assert_eq!(
+            runs[0].parameters.as_ref().unwrap()["style"],
+            serde_json::json!("casual")
+        );
+
+        store.delete_progress("wf").await.unwrap();
+        assert!(store.list_step_runs("wf").await.unwrap().is_empty());
+    }
 }
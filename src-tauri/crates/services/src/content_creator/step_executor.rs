@@ -4,6 +4,7 @@
 
 use super::types::*;
 use anyhow::Result;
+use std::collections::HashMap;
 use tracing::{debug, info};
 
 /// 步骤执行器
@@ -176,18 +177,27 @@ pub struct StepExecutionContext {
     pub style: Option<String>,
     /// 之前步骤的结果
     pub previous_results: Vec<StepResult>,
+    /// 本次执行的参数覆盖（重跑步骤时传入）
+    pub parameters: HashMap<String, serde_json::Value>,
 }
 
 impl StepExecutionContext {
     /// 从工作流状态创建执行上下文
     pub fn from_workflow(workflow: &WorkflowState) -> Self {
+        Self::from_workflow_until(workflow, workflow.steps.len())
+    }
+
+    /// 仅使用 `step_index` 之前步骤的结果创建执行上下文
+    ///
+    /// 用于单独重跑某个步骤：前置步骤的结果保持不变，不会被重新执行。
+    pub fn from_workflow_until(workflow: &WorkflowState, step_index: usize) -> Self {
         let mut topic = None;
         let mut audience = None;
         let mut style = None;
         let mut previous_results = Vec::new();
 
         // 从已完成的步骤中提取信息
-        for step in &workflow.steps {
+        for step in workflow.steps.iter().take(step_index) {
             if let Some(result) = &step.result {
                 previous_results.push(result.clone());
 
@@ -217,6 +227,31 @@ impl StepExecutionContext {
             audience,
             style,
             previous_results,
+            parameters: HashMap::new(),
+        }
+    }
+
+    /// 应用参数覆盖
+    ///
+    /// `topic` / `audience` / `style` 会覆盖从 clarify 步骤提取的值，
+    /// 其余参数原样保留在 `parameters` 中供具体任务使用。
+    pub fn with_parameters(mut self, parameters: HashMap<String, serde_json::Value>) -> Self {
+        let get = |key: &str| {
+            parameters
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+        };
+        if let Some(topic) = get("topic") {
+            self.topic = Some(topic);
+        }
+        if let Some(audience) = get("audience") {
+            self.audience = Some(audience);
+        }
+        if let Some(style) = get("style") {
+            self.style = Some(style);
         }
+        self.parameters = parameters;
+        self
     }
 }
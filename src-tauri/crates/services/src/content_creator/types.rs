@@ -244,6 +244,46 @@ pub struct WorkflowState {
     pub updated_at: i64,
 }
 
+impl WorkflowState {
+    /// 是否所有步骤都已完成或跳过
+    pub fn is_finished(&self) -> bool {
+        self.steps
+            .iter()
+            .all(|s| s.status == StepStatus::Completed || s.status == StepStatus::Skipped)
+    }
+}
+
+/// 步骤执行记录
+///
+/// 每次执行（包括使用不同参数重跑）某个步骤都会生成一条记录，
+/// 便于回溯和对比不同参数下的产出。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepRunRecord {
+    pub id: String,
+    pub workflow_id: String,
+    pub step_index: usize,
+    pub step_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<HashMap<String, serde_json::Value>>,
+    pub result: StepResult,
+    pub created_at: i64,
+}
+
+/// 工作流导出格式版本
+pub const WORKFLOW_EXPORT_VERSION: u32 = 1;
+
+/// 工作流导出文件（JSON）
+///
+/// 用于将一次工作流运行分享给他人，导入时会分配新的工作流 ID。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowExport {
+    pub version: u32,
+    pub exported_at: i64,
+    pub workflow: WorkflowState,
+    #[serde(default)]
+    pub step_runs: Vec<StepRunRecord>,
+}
+
 /// 工作流进度（持久化用）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkflowProgress {
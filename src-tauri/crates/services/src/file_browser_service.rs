@@ -8,15 +8,35 @@
 //! - 读取文件预览
 //! - 获取文件元信息
 //! - 获取文件权限和 MIME 类型
-
+//! - 远程文件（SFTP）浏览、编辑与传输
+//!
+//! # 远程路径
+//! 远程文件使用 `ssh://<连接名>/<远程路径>` 表示，例如 `ssh://my-server/var/log`、
+//! `ssh://user@host:2222/~/notes.md`。连接名与终端连接一致，
+//! 通过 `SSHConnRegistry` 复用已认证的 SSH 连接（含 ProxyJump）。
+//! 除打开 / 在 Finder 中显示外，所有接口对本地与远程路径的行为一致，
+//! 返回的条目路径也使用同样的格式，前端可直接回传。
+
+use proxycast_terminal::connections::{RemoteEntry, SSHConnRegistry, SftpClient};
 use serde::{Deserialize, Serialize};
 use std::fs::{self, Metadata};
+use std::io::{Read, Seek, SeekFrom, Write};
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
-use std::time::UNIX_EPOCH;
+use std::sync::Arc;
+use std::time::{Duration, Instant, UNIX_EPOCH};
 use tracing::{debug, error};
 
+/// 远程路径前缀
+pub const REMOTE_PATH_PREFIX: &str = "ssh://";
+
+/// 传输进度事件最小间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 传输缓冲区大小
+const TRANSFER_CHUNK_SIZE: usize = 64 * 1024;
+
 /// 文件条目
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileEntry {
@@ -94,7 +114,6 @@ fn is_hidden_file(name: &str) -> bool {
 }
 
 /// 将 Unix 文件模式转换为权限字符串（如 -rw-r--r--）
-#[cfg(unix)]
 fn mode_to_string(mode: u32, is_dir: bool, is_symlink: bool) -> String {
     let mut result = String::with_capacity(10);
 
@@ -153,7 +172,11 @@ fn get_mime_type(path: &Path, metadata: &Metadata) -> String {
         }
     }
 
-    // 基于扩展名的 MIME 类型映射
+    mime_from_extension(path).to_string()
+}
+
+/// 基于扩展名的 MIME 类型映射
+fn mime_from_extension(path: &Path) -> &'static str {
     let ext = path
         .extension()
         .and_then(|e| e.to_str())
@@ -255,7 +278,6 @@ fn get_mime_type(path: &Path, metadata: &Metadata) -> String {
 
         _ => "application/octet-stream",
     }
-    .to_string()
 }
 
 /// 判断是否为文本文件（基于扩展名）
@@ -311,16 +333,21 @@ fn is_text_file(extension: Option<&str>) -> bool {
     }
 }
 
-/// 列出目录内容
-pub fn list_directory(path: &str) -> DirectoryListing {
-    let path_buf = if path.is_empty() || path == "~" {
+/// 展开本地路径中的 `~`
+fn expand_local_path(path: &str) -> PathBuf {
+    if path.is_empty() || path == "~" {
         dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"))
     } else if path.starts_with('~') {
         let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
         home.join(&path[2..])
     } else {
         PathBuf::from(path)
-    };
+    }
+}
+
+/// 列出目录内容
+pub fn list_directory(path: &str) -> DirectoryListing {
+    let path_buf = expand_local_path(path);
 
     let canonical_path = match path_buf.canonicalize() {
         Ok(p) => p,
@@ -517,9 +544,629 @@ pub fn read_file_preview(path: &str, max_size: Option<usize>) -> FilePreview {
     }
 }
 
+// ============================================================================
+// 远程路径（SFTP）
+// ============================================================================
+
+/// 文件位置：本地路径或远程连接上的路径
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FsLocation {
+    Local(PathBuf),
+    Remote { connection: String, path: String },
+}
+
+impl FsLocation {
+    /// 解析路径，`ssh://<连接名>/<路径>` 为远程路径
+    fn parse(path: &str) -> Self {
+        let Some(rest) = path.strip_prefix(REMOTE_PATH_PREFIX) else {
+            return Self::Local(expand_local_path(path));
+        };
+        let (connection, remote_path) = match rest.find('/') {
+            Some(pos) => (&rest[..pos], &rest[pos..]),
+            None => (rest, "~"),
+        };
+        // `ssh://host/~/dir` 表示相对远程主目录
+        let remote_path = match remote_path.strip_prefix("/~") {
+            Some(home_relative) if home_relative.is_empty() || home_relative.starts_with('/') => {
+                format!("~{home_relative}")
+            }
+            _ => remote_path.to_string(),
+        };
+        Self::Remote {
+            connection: connection.to_string(),
+            path: remote_path,
+        }
+    }
+}
+
+/// 构造远程路径
+fn remote_uri(connection: &str, path: &str) -> String {
+    if path.starts_with('/') {
+        format!("{REMOTE_PATH_PREFIX}{connection}{path}")
+    } else {
+        format!("{REMOTE_PATH_PREFIX}{connection}/{path}")
+    }
+}
+
+/// 获取连接上的 SFTP 客户端
+async fn sftp_client(connection: &str) -> Result<Arc<SftpClient>, String> {
+    SSHConnRegistry::global()
+        .sftp(connection)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 在阻塞线程上执行 SFTP 操作
+async fn run_sftp<T, F>(connection: &str, op: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce(&SftpClient) -> Result<T, String> + Send + 'static,
+{
+    let client = sftp_client(connection).await?;
+    tokio::task::spawn_blocking(move || op(&client))
+        .await
+        .map_err(|e| format!("SFTP 任务执行失败: {e}"))?
+}
+
+/// 远程条目转换为文件条目
+fn remote_to_file_entry(connection: &str, entry: RemoteEntry) -> FileEntry {
+    let path = Path::new(&entry.path);
+    let (file_type, mime_type) = if entry.is_dir {
+        (Some("folder".to_string()), "directory")
+    } else {
+        (get_file_extension(path), mime_from_extension(path))
+    };
+    FileEntry {
+        is_hidden: is_hidden_file(&entry.name),
+        mode_str: entry
+            .mode
+            .map(|m| mode_to_string(m, entry.is_dir, entry.is_symlink)),
+        mode: entry.mode,
+        mime_type: Some(mime_type.to_string()),
+        file_type,
+        path: remote_uri(connection, &entry.path),
+        name: entry.name,
+        is_dir: entry.is_dir,
+        size: entry.size,
+        modified_at: entry.modified_at,
+        is_symlink: entry.is_symlink,
+    }
+}
+
+/// 本地文件条目
+fn local_file_entry(path: &Path) -> Result<FileEntry, String> {
+    let symlink_metadata =
+        fs::symlink_metadata(path).map_err(|e| format!("无法读取文件元信息: {e}"))?;
+    let is_symlink = symlink_metadata.file_type().is_symlink();
+    let metadata = if is_symlink {
+        fs::metadata(path).unwrap_or(symlink_metadata)
+    } else {
+        symlink_metadata
+    };
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string());
+
+    #[cfg(unix)]
+    let (mode, mode_str) = {
+        let m = metadata.permissions().mode() & 0o777;
+        (
+            Some(m),
+            Some(mode_to_string(m, metadata.is_dir(), is_symlink)),
+        )
+    };
+    #[cfg(not(unix))]
+    let (mode, mode_str): (Option<u32>, Option<String>) = (None, None);
+
+    Ok(FileEntry {
+        is_hidden: is_hidden_file(&name),
+        name,
+        path: path.to_string_lossy().to_string(),
+        is_dir: metadata.is_dir(),
+        size: metadata.len(),
+        modified_at: metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+        file_type: if metadata.is_dir() {
+            Some("folder".to_string())
+        } else {
+            get_file_extension(path)
+        },
+        mode_str,
+        mode,
+        mime_type: Some(get_mime_type(path, &metadata)),
+        is_symlink,
+    })
+}
+
+/// 列出远程目录
+fn list_remote_directory(client: &SftpClient, connection: &str, path: &str) -> DirectoryListing {
+    let resolved = match client.realpath(path) {
+        Ok(p) => p,
+        Err(e) => {
+            error!("无法解析远程路径 {}: {}", path, e);
+            return DirectoryListing {
+                path: remote_uri(connection, path),
+                parent_path: None,
+                entries: vec![],
+                error: Some(format!("无法解析路径: {e}")),
+            };
+        }
+    };
+    let parent_path = proxycast_terminal::connections::sftp::remote_parent(&resolved)
+        .map(|p| remote_uri(connection, &p));
+
+    match client.list_dir(&resolved) {
+        Ok(entries) => {
+            let mut entries: Vec<FileEntry> = entries
+                .into_iter()
+                .map(|e| remote_to_file_entry(connection, e))
+                .collect();
+            entries.sort_by(|a, b| match (a.is_dir, b.is_dir) {
+                (true, false) => std::cmp::Ordering::Less,
+                (false, true) => std::cmp::Ordering::Greater,
+                _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            });
+            debug!(
+                "列出远程目录 {}:{}: {} 个条目",
+                connection,
+                resolved,
+                entries.len()
+            );
+            DirectoryListing {
+                path: remote_uri(connection, &resolved),
+                parent_path,
+                entries,
+                error: None,
+            }
+        }
+        Err(e) => {
+            error!("无法读取远程目录 {}:{}: {}", connection, resolved, e);
+            DirectoryListing {
+                path: remote_uri(connection, &resolved),
+                parent_path,
+                entries: vec![],
+                error: Some(format!("无法读取目录: {e}")),
+            }
+        }
+    }
+}
+
+/// 读取远程文件预览
+fn read_remote_file_preview(
+    client: &SftpClient,
+    uri: &str,
+    path: &str,
+    max_size: usize,
+) -> FilePreview {
+    let preview_error = |size: u64, error: String| FilePreview {
+        path: uri.to_string(),
+        content: None,
+        is_binary: false,
+        size,
+        error: Some(error),
+    };
+
+    let entry = match client.stat(path) {
+        Ok(Some(entry)) => entry,
+        Ok(None) => return preview_error(0, "文件不存在".to_string()),
+        Err(e) => return preview_error(0, format!("无法读取文件元信息: {e}")),
+    };
+    if entry.is_dir {
+        return preview_error(0, "不能预览目录".to_string());
+    }
+
+    let extension = get_file_extension(Path::new(path));
+    if !is_text_file(extension.as_deref()) {
+        return FilePreview {
+            path: uri.to_string(),
+            content: None,
+            is_binary: true,
+            size: entry.size,
+            error: None,
+        };
+    }
+
+    match client.read_file(path, max_size) {
+        Ok(bytes) => match String::from_utf8(bytes) {
+            Ok(content) => FilePreview {
+                path: uri.to_string(),
+                content: Some(content),
+                is_binary: false,
+                size: entry.size,
+                error: None,
+            },
+            Err(_) => FilePreview {
+                path: uri.to_string(),
+                content: None,
+                is_binary: true,
+                size: entry.size,
+                error: None,
+            },
+        },
+        Err(e) => preview_error(entry.size, format!("无法读取文件: {e}")),
+    }
+}
+
+// ============================================================================
+// 文件传输
+// ============================================================================
+
+/// 传输选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TransferOptions {
+    /// 复制目录时递归复制
+    #[serde(default)]
+    pub recursive: bool,
+    /// 目标已存在部分内容时断点续传
+    #[serde(default)]
+    pub resume: bool,
+}
+
+/// 传输进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferProgress {
+    /// 传输 ID
+    #[serde(rename = "transferId")]
+    pub transfer_id: String,
+    /// 源路径
+    pub source: String,
+    /// 目标路径
+    pub destination: String,
+    /// 当前文件
+    #[serde(rename = "currentFile")]
+    pub current_file: Option<String>,
+    /// 已传输字节数（含续传跳过的部分）
+    #[serde(rename = "transferredBytes")]
+    pub transferred_bytes: u64,
+    /// 总字节数
+    #[serde(rename = "totalBytes")]
+    pub total_bytes: u64,
+    /// 已完成文件数
+    #[serde(rename = "filesDone")]
+    pub files_done: u64,
+    /// 文件总数
+    #[serde(rename = "filesTotal")]
+    pub files_total: u64,
+    /// 是否结束
+    pub done: bool,
+    /// 错误信息
+    pub error: Option<String>,
+}
+
+/// 传输进度回调
+pub type TransferProgressEmitter =
+    Arc<dyn Fn(&TransferProgress) -> Result<(), String> + Send + Sync>;
+
+/// 传输一端的文件系统
+enum TransferFs {
+    Local,
+    Remote(Arc<SftpClient>),
+}
+
+/// 传输中的条目信息
+struct TransferStat {
+    is_dir: bool,
+    is_symlink: bool,
+    size: u64,
+}
+
+impl TransferFs {
+    /// 获取条目信息（不跟随符号链接）
+    fn stat(&self, path: &str) -> Result<Option<TransferStat>, String> {
+        match self {
+            Self::Local => match fs::symlink_metadata(path) {
+                Ok(m) => Ok(Some(TransferStat {
+                    is_dir: m.is_dir(),
+                    is_symlink: m.file_type().is_symlink(),
+                    size: m.len(),
+                })),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(format!("无法读取 {path}: {e}")),
+            },
+            Self::Remote(client) => {
+                Ok(client
+                    .stat(path)
+                    .map_err(|e| e.to_string())?
+                    .map(|e| TransferStat {
+                        is_dir: e.is_dir,
+                        is_symlink: e.is_symlink,
+                        size: e.size,
+                    }))
+            }
+        }
+    }
+
+    /// `path`（位于 `other`）是否与本端的 `ancestor` 相同或位于其内部
+    ///
+    /// 仅在两端为同一文件系统（均为本地，或同一 SSH 连接）时成立。
+    fn contains(&self, ancestor: &str, other: &TransferFs, path: &str) -> bool {
+        match (self, other) {
+            (Self::Local, Self::Local) => canonicalize_lenient(Path::new(path))
+                .starts_with(canonicalize_lenient(Path::new(ancestor))),
+            (Self::Remote(a), Self::Remote(b)) if Arc::ptr_eq(a, b) => {
+                let ancestor = ancestor.trim_end_matches('/');
+                let path = path.trim_end_matches('/');
+                ancestor.is_empty() || path == ancestor || path.starts_with(&format!("{ancestor}/"))
+            }
+            _ => false,
+        }
+    }
+
+    /// 列出子条目（名称, 路径）
+    fn list(&self, path: &str) -> Result<Vec<(String, String)>, String> {
+        match self {
+            Self::Local => fs::read_dir(path)
+                .map_err(|e| format!("无法读取目录 {path}: {e}"))?
+                .map(|entry| {
+                    let entry = entry.map_err(|e| format!("无法读取目录 {path}: {e}"))?;
+                    Ok((
+                        entry.file_name().to_string_lossy().to_string(),
+                        entry.path().to_string_lossy().to_string(),
+                    ))
+                })
+                .collect(),
+            Self::Remote(client) => Ok(client
+                .list_dir(path)
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .map(|e| (e.name, e.path))
+                .collect()),
+        }
+    }
+
+    fn join(&self, dir: &str, name: &str) -> String {
+        match self {
+            Self::Local => Path::new(dir).join(name).to_string_lossy().to_string(),
+            Self::Remote(_) => proxycast_terminal::connections::sftp::join_remote_path(dir, name),
+        }
+    }
+
+    fn create_dir_all(&self, path: &str) -> Result<(), String> {
+        match self {
+            Self::Local => fs::create_dir_all(path).map_err(|e| format!("无法创建目录: {e}")),
+            Self::Remote(client) => client.create_dir_all(path).map_err(|e| e.to_string()),
+        }
+    }
+
+    fn open_read(&self, path: &str, offset: u64) -> Result<Box<dyn Read + Send>, String> {
+        match self {
+            Self::Local => {
+                let mut file = fs::File::open(path).map_err(|e| format!("无法打开 {path}: {e}"))?;
+                file.seek(SeekFrom::Start(offset))
+                    .map_err(|e| format!("无法定位 {path}: {e}"))?;
+                Ok(Box::new(file))
+            }
+            Self::Remote(client) => Ok(Box::new(
+                client
+                    .open_reader(path, offset)
+                    .map_err(|e| e.to_string())?,
+            )),
+        }
+    }
+
+    /// 打开写入；`offset` 为 None 时截断，否则从偏移续写
+    fn open_write(&self, path: &str, offset: Option<u64>) -> Result<Box<dyn Write + Send>, String> {
+        match self {
+            Self::Local => {
+                let mut file = fs::OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .truncate(offset.is_none())
+                    .open(path)
+                    .map_err(|e| format!("无法打开 {path}: {e}"))?;
+                if let Some(offset) = offset {
+                    file.set_len(offset)
+                        .and_then(|_| file.seek(SeekFrom::Start(offset)).map(|_| ()))
+                        .map_err(|e| format!("无法定位 {path}: {e}"))?;
+                }
+                Ok(Box::new(file))
+            }
+            Self::Remote(client) => Ok(Box::new(
+                client
+                    .open_writer(path, offset)
+                    .map_err(|e| e.to_string())?,
+            )),
+        }
+    }
+}
+
+/// 传输进度跟踪
+struct TransferTracker {
+    progress: TransferProgress,
+    emitter: Option<TransferProgressEmitter>,
+    last_emit: Option<Instant>,
+}
+
+impl TransferTracker {
+    fn emit(&mut self, force: bool) {
+        let Some(emitter) = &self.emitter else {
+            return;
+        };
+        if !force
+            && self
+                .last_emit
+                .is_some_and(|last| last.elapsed() < PROGRESS_INTERVAL)
+        {
+            return;
+        }
+        self.last_emit = Some(Instant::now());
+        if let Err(e) = emitter(&self.progress) {
+            debug!("发送传输进度失败: {}", e);
+        }
+    }
+
+    fn advance(&mut self, bytes: u64) {
+        self.progress.transferred_bytes += bytes;
+        self.emit(false);
+    }
+
+    fn file_done(&mut self) {
+        self.progress.files_done += 1;
+        self.emit(true);
+    }
+}
+
+/// 规范化可能尚不存在的路径：解析最近的已存在祖先，再拼回其余部分
+fn canonicalize_lenient(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = fs::canonicalize(existing) {
+            return rest
+                .iter()
+                .rev()
+                .fold(canonical, |acc: PathBuf, name| acc.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name);
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+/// 统计源的文件数与总字节数（符号链接不跟随，不计入）
+fn measure_tree(fs: &TransferFs, path: &str, recursive: bool) -> Result<(u64, u64), String> {
+    let stat = fs
+        .stat(path)?
+        .ok_or_else(|| format!("源文件或目录不存在: {path}"))?;
+    if stat.is_symlink {
+        return Ok((0, 0));
+    }
+    if !stat.is_dir {
+        return Ok((1, stat.size));
+    }
+    if !recursive {
+        return Err("复制目录需要启用递归复制".to_string());
+    }
+    let mut totals = (0, 0);
+    for (_, child) in fs.list(path)? {
+        let (files, bytes) = measure_tree(fs, &child, recursive)?;
+        totals.0 += files;
+        totals.1 += bytes;
+    }
+    Ok(totals)
+}
+
+fn copy_tree(
+    src_fs: &TransferFs,
+    src: &str,
+    dst_fs: &TransferFs,
+    dst: &str,
+    options: &TransferOptions,
+    tracker: &mut TransferTracker,
+) -> Result<(), String> {
+    let stat = src_fs
+        .stat(src)?
+        .ok_or_else(|| format!("源文件或目录不存在: {src}"))?;
+    if stat.is_symlink {
+        debug!("跳过符号链接: {}", src);
+        return Ok(());
+    }
+    if stat.is_dir {
+        dst_fs.create_dir_all(dst)?;
+        for (name, child) in src_fs.list(src)? {
+            copy_tree(
+                src_fs,
+                &child,
+                dst_fs,
+                &dst_fs.join(dst, &name),
+                options,
+                tracker,
+            )?;
+        }
+        return Ok(());
+    }
+
+    tracker.progress.current_file = Some(src.to_string());
+    let offset = if options.resume {
+        dst_fs
+            .stat(dst)?
+            .filter(|existing| !existing.is_dir && existing.size <= stat.size)
+            .map(|existing| existing.size)
+            .unwrap_or(0)
+    } else {
+        0
+    };
+    if offset > 0 {
+        debug!("断点续传 {} -> {}，从 {} 字节开始", src, dst, offset);
+        tracker.advance(offset);
+    }
+    if offset == stat.size && offset > 0 {
+        tracker.file_done();
+        return Ok(());
+    }
+
+    let mut reader = src_fs.open_read(src, offset)?;
+    let mut writer = dst_fs.open_write(dst, (offset > 0).then_some(offset))?;
+    let mut buf = vec![0u8; TRANSFER_CHUNK_SIZE];
+    loop {
+        let n = reader
+            .read(&mut buf)
+            .map_err(|e| format!("读取 {src} 失败: {e}"))?;
+        if n == 0 {
+            break;
+        }
+        writer
+            .write_all(&buf[..n])
+            .map_err(|e| format!("写入 {dst} 失败: {e}"))?;
+        tracker.advance(n as u64);
+    }
+    writer
+        .flush()
+        .map_err(|e| format!("写入 {dst} 失败: {e}"))?;
+    tracker.file_done();
+    Ok(())
+}
+
+async fn transfer_fs(location: &FsLocation) -> Result<(TransferFs, String), String> {
+    match location {
+        FsLocation::Local(path) => Ok((TransferFs::Local, path.to_string_lossy().to_string())),
+        FsLocation::Remote { connection, path } => {
+            let client = sftp_client(connection).await?;
+            let resolved = if path.starts_with('~') {
+                let client = client.clone();
+                let path = path.clone();
+                tokio::task::spawn_blocking(move || client.realpath(&path))
+                    .await
+                    .map_err(|e| format!("SFTP 任务执行失败: {e}"))?
+                    .map_err(|e| e.to_string())?
+            } else {
+                path.clone()
+            };
+            Ok((TransferFs::Remote(client), resolved))
+        }
+    }
+}
+
 /// 服务接口：列出目录
 pub async fn list_dir(path: String) -> Result<DirectoryListing, String> {
-    Ok(list_directory(&path))
+    match FsLocation::parse(&path) {
+        FsLocation::Local(_) => Ok(list_directory(&path)),
+        FsLocation::Remote {
+            connection,
+            path: remote_path,
+        } => match sftp_client(&connection).await {
+            Ok(client) => tokio::task::spawn_blocking(move || {
+                list_remote_directory(&client, &connection, &remote_path)
+            })
+            .await
+            .map_err(|e| format!("SFTP 任务执行失败: {e}")),
+            Err(e) => Ok(DirectoryListing {
+                path,
+                parent_path: None,
+                entries: vec![],
+                error: Some(e),
+            }),
+        },
+    }
 }
 
 /// 服务接口：读取文件预览
@@ -527,7 +1174,46 @@ pub async fn read_file_preview_cmd(
     path: String,
     max_size: Option<usize>,
 ) -> Result<FilePreview, String> {
-    Ok(read_file_preview(&path, max_size))
+    match FsLocation::parse(&path) {
+        FsLocation::Local(_) => Ok(read_file_preview(&path, max_size)),
+        FsLocation::Remote {
+            connection,
+            path: remote_path,
+        } => {
+            let max_size = max_size.unwrap_or(100 * 1024);
+            run_sftp(&connection, move |client| {
+                Ok(read_remote_file_preview(
+                    client,
+                    &path,
+                    &remote_path,
+                    max_size,
+                ))
+            })
+            .await
+        }
+    }
+}
+
+/// 服务接口：获取文件元信息
+pub async fn stat_path(path: String) -> Result<FileEntry, String> {
+    match FsLocation::parse(&path) {
+        FsLocation::Local(local) => local_file_entry(&local),
+        FsLocation::Remote {
+            connection,
+            path: remote_path,
+        } => {
+            let conn_name = connection.clone();
+            run_sftp(&connection, move |client| {
+                let resolved = client.realpath(&remote_path).map_err(|e| e.to_string())?;
+                let entry = client
+                    .stat(&resolved)
+                    .map_err(|e| e.to_string())?
+                    .ok_or_else(|| "文件或目录不存在".to_string())?;
+                Ok(remote_to_file_entry(&conn_name, entry))
+            })
+            .await
+        }
+    }
 }
 
 /// 服务接口：获取用户主目录
@@ -537,9 +1223,39 @@ pub async fn get_home_dir() -> Result<String, String> {
         .ok_or_else(|| "无法获取主目录".to_string())
 }
 
+/// 服务接口：获取远程连接的主目录（返回远程路径）
+pub async fn get_remote_home_dir(connection: String) -> Result<String, String> {
+    let conn_name = connection.clone();
+    run_sftp(&connection, move |client| {
+        client
+            .home_dir()
+            .map(|home| remote_uri(&conn_name, &home))
+            .map_err(|e| e.to_string())
+    })
+    .await
+}
+
 /// 服务接口：创建新文件
 pub async fn create_file(path: String) -> Result<(), String> {
-    let path_buf = PathBuf::from(&path);
+    let path_buf = match FsLocation::parse(&path) {
+        FsLocation::Local(path_buf) => path_buf,
+        FsLocation::Remote {
+            connection,
+            path: remote_path,
+        } => {
+            return run_sftp(&connection, move |client| {
+                if let Some(parent) =
+                    proxycast_terminal::connections::sftp::remote_parent(&remote_path)
+                {
+                    client.create_dir_all(&parent).map_err(|e| e.to_string())?;
+                }
+                client
+                    .create_file(&remote_path)
+                    .map_err(|e| format!("无法创建文件: {e}"))
+            })
+            .await;
+        }
+    };
 
     // 检查文件是否已存在
     if path_buf.exists() {
@@ -560,9 +1276,52 @@ pub async fn create_file(path: String) -> Result<(), String> {
     Ok(())
 }
 
+/// 服务接口：写入文件内容（编辑保存，覆盖原内容）
+pub async fn write_file_content(path: String, content: String) -> Result<(), String> {
+    match FsLocation::parse(&path) {
+        FsLocation::Local(path_buf) => {
+            fs::write(&path_buf, content).map_err(|e| format!("无法写入文件: {e}"))?;
+        }
+        FsLocation::Remote {
+            connection,
+            path: remote_path,
+        } => {
+            run_sftp(&connection, move |client| {
+                client
+                    .write_file(&remote_path, content.as_bytes())
+                    .map_err(|e| format!("无法写入文件: {e}"))
+            })
+            .await?;
+        }
+    }
+
+    debug!("写入文件: {}", path);
+    Ok(())
+}
+
 /// 服务接口：创建新目录
 pub async fn create_directory(path: String) -> Result<(), String> {
-    let path_buf = PathBuf::from(&path);
+    let path_buf = match FsLocation::parse(&path) {
+        FsLocation::Local(path_buf) => path_buf,
+        FsLocation::Remote {
+            connection,
+            path: remote_path,
+        } => {
+            return run_sftp(&connection, move |client| {
+                if client
+                    .stat(&remote_path)
+                    .map_err(|e| e.to_string())?
+                    .is_some()
+                {
+                    return Err("目录已存在".to_string());
+                }
+                client
+                    .create_dir_all(&remote_path)
+                    .map_err(|e| format!("无法创建目录: {e}"))
+            })
+            .await;
+        }
+    };
 
     // 检查目录是否已存在
     if path_buf.exists() {
@@ -577,7 +1336,22 @@ pub async fn create_directory(path: String) -> Result<(), String> {
 
 /// 服务接口：删除文件或目录
 pub async fn delete_file(path: String, recursive: bool) -> Result<(), String> {
-    let path_buf = PathBuf::from(&path);
+    let path_buf = match FsLocation::parse(&path) {
+        FsLocation::Local(path_buf) => path_buf,
+        FsLocation::Remote {
+            connection,
+            path: remote_path,
+        } => {
+            run_sftp(&connection, move |client| {
+                client
+                    .remove(&remote_path, recursive)
+                    .map_err(|e| format!("无法删除: {e}"))
+            })
+            .await?;
+            debug!("删除远程路径: {}", path);
+            return Ok(());
+        }
+    };
 
     if !path_buf.exists() {
         return Err("文件或目录不存在".to_string());
@@ -600,9 +1374,39 @@ pub async fn delete_file(path: String, recursive: bool) -> Result<(), String> {
 }
 
 /// 服务接口：重命名文件或目录
+///
+/// 远程路径只能在同一连接内重命名，跨连接或本地与远程之间请使用复制。
 pub async fn rename_file(old_path: String, new_path: String) -> Result<(), String> {
-    let old_path_buf = PathBuf::from(&old_path);
-    let new_path_buf = PathBuf::from(&new_path);
+    let (old_path_buf, new_path_buf) =
+        match (FsLocation::parse(&old_path), FsLocation::parse(&new_path)) {
+            (FsLocation::Local(old), FsLocation::Local(new)) => (old, new),
+            (
+                FsLocation::Remote {
+                    connection,
+                    path: from,
+                },
+                FsLocation::Remote {
+                    connection: target_connection,
+                    path: to,
+                },
+            ) if connection == target_connection => {
+                run_sftp(&connection, move |client| {
+                    if client.stat(&from).map_err(|e| e.to_string())?.is_none() {
+                        return Err("源文件或目录不存在".to_string());
+                    }
+                    if client.stat(&to).map_err(|e| e.to_string())?.is_some() {
+                        return Err("目标文件或目录已存在".to_string());
+                    }
+                    client
+                        .rename(&from, &to)
+                        .map_err(|e| format!("无法重命名: {e}"))
+                })
+                .await?;
+                debug!("重命名: {} -> {}", old_path, new_path);
+                return Ok(());
+            }
+            _ => return Err("不支持跨连接重命名，请使用复制".to_string()),
+        };
 
     if !old_path_buf.exists() {
         return Err("源文件或目录不存在".to_string());
@@ -618,6 +1422,68 @@ pub async fn rename_file(old_path: String, new_path: String) -> Result<(), Strin
     Ok(())
 }
 
+/// 服务接口：复制文件或目录
+///
+/// 支持本地与远程之间的任意组合（上传、下载、远程间复制），
+/// 可选递归复制与断点续传，进度通过 `emitter` 回调。
+/// 符号链接不跟随、直接跳过；目标位于源自身或其内部时拒绝复制。
+pub async fn copy_path(
+    source: String,
+    destination: String,
+    options: TransferOptions,
+    emitter: Option<TransferProgressEmitter>,
+) -> Result<TransferProgress, String> {
+    let (src_fs, src) = transfer_fs(&FsLocation::parse(&source)).await?;
+    let (dst_fs, dst) = transfer_fs(&FsLocation::parse(&destination)).await?;
+
+    tokio::task::spawn_blocking(move || {
+        let mut tracker = TransferTracker {
+            progress: TransferProgress {
+                transfer_id: uuid::Uuid::new_v4().to_string(),
+                source: source.clone(),
+                destination: destination.clone(),
+                current_file: None,
+                transferred_bytes: 0,
+                total_bytes: 0,
+                files_done: 0,
+                files_total: 0,
+                done: false,
+                error: None,
+            },
+            emitter,
+            last_emit: None,
+        };
+
+        if src_fs.contains(&src, &dst_fs, &dst) {
+            return Err("不能将文件或目录复制到其自身或其子目录中".to_string());
+        }
+
+        let result = measure_tree(&src_fs, &src, options.recursive).and_then(|(files, bytes)| {
+            tracker.progress.files_total = files;
+            tracker.progress.total_bytes = bytes;
+            tracker.emit(true);
+            copy_tree(&src_fs, &src, &dst_fs, &dst, &options, &mut tracker)
+        });
+
+        tracker.progress.done = true;
+        tracker.progress.current_file = None;
+        tracker.progress.error = result.as_ref().err().cloned();
+        tracker.emit(true);
+        match result {
+            Ok(()) => {
+                debug!("复制完成: {} -> {}", source, destination);
+                Ok(tracker.progress)
+            }
+            Err(e) => {
+                error!("复制失败: {} -> {}: {}", source, destination, e);
+                Err(e)
+            }
+        }
+    })
+    .await
+    .map_err(|e| format!("传输任务执行失败: {e}"))?
+}
+
 /// 服务接口：复制文件名到剪贴板（返回文件名供前端处理）
 pub async fn get_file_name(path: String) -> Result<String, String> {
     let path_buf = PathBuf::from(&path);
@@ -630,6 +1496,9 @@ pub async fn get_file_name(path: String) -> Result<String, String> {
 
 /// 服务接口：在 Finder 中显示文件
 pub async fn reveal_in_finder(path: String) -> Result<(), String> {
+    if matches!(FsLocation::parse(&path), FsLocation::Remote { .. }) {
+        return Err("远程文件不支持在 Finder 中显示".to_string());
+    }
     let path_buf = PathBuf::from(&path);
 
     if !path_buf.exists() {
@@ -667,6 +1536,9 @@ pub async fn reveal_in_finder(path: String) -> Result<(), String> {
 
 /// 服务接口：使用默认应用打开文件
 pub async fn open_with_default_app(path: String) -> Result<(), String> {
+    if matches!(FsLocation::parse(&path), FsLocation::Remote { .. }) {
+        return Err("远程文件不支持使用默认应用打开，请先下载".to_string());
+    }
     let path_buf = PathBuf::from(&path);
 
     if !path_buf.exists() {
@@ -718,6 +1590,172 @@ mod tests {
         assert!(!is_hidden_file("readme.md"));
     }
 
+    #[test]
+    fn test_parse_remote_location() {
+        assert_eq!(
+            FsLocation::parse("ssh://my-server/var/log"),
+            FsLocation::Remote {
+                connection: "my-server".to_string(),
+                path: "/var/log".to_string(),
+            }
+        );
+        assert_eq!(
+            FsLocation::parse("ssh://user@host:2222/~/notes.md"),
+            FsLocation::Remote {
+                connection: "user@host:2222".to_string(),
+                path: "~/notes.md".to_string(),
+            }
+        );
+        assert_eq!(
+            FsLocation::parse("ssh://my-server"),
+            FsLocation::Remote {
+                connection: "my-server".to_string(),
+                path: "~".to_string(),
+            }
+        );
+        assert_eq!(
+            FsLocation::parse("/tmp/a.txt"),
+            FsLocation::Local(PathBuf::from("/tmp/a.txt"))
+        );
+        assert_eq!(
+            remote_uri("my-server", "/var/log"),
+            "ssh://my-server/var/log"
+        );
+        assert_eq!(remote_uri("my-server", "~/a"), "ssh://my-server/~/a");
+    }
+
+    #[tokio::test]
+    async fn test_copy_path_recursive_with_resume() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(src.join("nested")).unwrap();
+        fs::write(src.join("a.txt"), b"hello world").unwrap();
+        fs::write(src.join("nested").join("b.txt"), b"nested").unwrap();
+
+        // 目标中已有部分内容，应从断点续传
+        let dst = dir.path().join("dst");
+        fs::create_dir_all(&dst).unwrap();
+        fs::write(dst.join("a.txt"), b"hello").unwrap();
+
+        let events = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        let emitter: TransferProgressEmitter = Arc::new(move |p: &TransferProgress| {
+            recorded.lock().push(p.clone());
+            Ok(())
+        });
+
+        let progress = copy_path(
+            src.to_string_lossy().to_string(),
+            dst.to_string_lossy().to_string(),
+            TransferOptions {
+                recursive: true,
+                resume: true,
+            },
+            Some(emitter),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(fs::read(dst.join("a.txt")).unwrap(), b"hello world");
+        assert_eq!(
+            fs::read(dst.join("nested").join("b.txt")).unwrap(),
+            b"nested"
+        );
+        assert_eq!(progress.files_total, 2);
+        assert_eq!(progress.files_done, 2);
+        assert_eq!(progress.transferred_bytes, 17);
+        assert!(events.lock().last().unwrap().done);
+    }
+
+    #[tokio::test]
+    async fn test_copy_directory_requires_recursive() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+
+        let result = copy_path(
+            dir.path().join("src").to_string_lossy().to_string(),
+            dir.path().join("dst").to_string_lossy().to_string(),
+            TransferOptions::default(),
+            None,
+        )
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_copy_into_itself_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("a.txt"), b"hello").unwrap();
+
+        for dst in [src.clone(), src.join("nested").join("copy")] {
+            let result = copy_path(
+                src.to_string_lossy().to_string(),
+                dst.to_string_lossy().to_string(),
+                TransferOptions {
+                    recursive: true,
+                    resume: false,
+                },
+                None,
+            )
+            .await;
+            assert!(result.unwrap_err().contains("其自身或其子目录"));
+        }
+        assert!(!src.join("nested").exists());
+
+        // 同一文件复制到自身也会被拒绝，不会被截断
+        let file = src.join("a.txt").to_string_lossy().to_string();
+        assert!(
+            copy_path(file.clone(), file, TransferOptions::default(), None)
+                .await
+                .is_err()
+        );
+        assert_eq!(fs::read(src.join("a.txt")).unwrap(), b"hello");
+
+        // 名称前缀相同的兄弟目录不受影响
+        let sibling = dir.path().join("src-copy");
+        copy_path(
+            src.to_string_lossy().to_string(),
+            sibling.to_string_lossy().to_string(),
+            TransferOptions {
+                recursive: true,
+                resume: false,
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(fs::read(sibling.join("a.txt")).unwrap(), b"hello");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_copy_skips_symlink_loops() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("a.txt"), b"hello").unwrap();
+        std::os::unix::fs::symlink(&src, src.join("loop")).unwrap();
+
+        let dst = dir.path().join("dst");
+        let progress = copy_path(
+            src.to_string_lossy().to_string(),
+            dst.to_string_lossy().to_string(),
+            TransferOptions {
+                recursive: true,
+                resume: false,
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(progress.files_total, 1);
+        assert_eq!(fs::read(dst.join("a.txt")).unwrap(), b"hello");
+        assert!(!dst.join("loop").exists());
+    }
+
     #[test]
     fn test_is_text_file() {
         assert!(is_text_file(Some("txt")));
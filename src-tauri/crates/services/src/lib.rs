@@ -4,7 +4,7 @@
 //!
 //! ## 模块结构
 //! - `context_memory_service` - 上下文记忆服务
-//! - `file_browser_service` - 文件浏览服务
+//! - `file_browser_service` - 文件浏览服务（本地与 SFTP 远程）
 //! - `sysinfo_service` - 系统信息服务
 //! - `update_check_service` - 更新检查服务
 //! - `usage_service` - 使用统计服务
@@ -33,13 +33,16 @@
 //! - `aster_session_store` - Aster 会话存储
 //! - `general_chat` - 通用聊天
 //! - `content_creator` - 内容创作
+//! - `manuscript_export` - 稿件导出（EPUB/DOCX/Markdown 包）
 //! - `session_context_service` - 会话上下文服务
 //! - `project_context_builder` - 项目上下文构建器
 //! - `tool_hooks_service` - 工具钩子服务
 //! - `kiro_event_service` - Kiro 事件服务
 //! - `api_key_provider_service` - API Key Provider 服务
 //! - `provider_pool_service` - Provider 池服务
+//! - `concurrency_limiter` - 凭证并发限制与公平排队
 //! - `token_cache_service` - Token 缓存服务
+//! - `token_refresh_scheduler` - OAuth Token 后台预刷新调度器
 
 // 无外部依赖的服务
 pub mod context_memory_service;
@@ -78,8 +81,10 @@ pub mod template_service;
 // 子模块
 pub mod content_creator;
 pub mod general_chat;
+pub mod manuscript_export;
 
 // 依赖其他 services 的服务
+pub mod management_service;
 pub mod project_context_builder;
 pub mod session_context_service;
 pub mod tool_hooks_service;
@@ -89,7 +94,9 @@ pub mod kiro_event_service;
 
 // 依赖 providers 的服务
 pub mod api_key_provider_service;
+pub mod concurrency_limiter;
 pub mod provider_pool_service;
 pub mod provider_type_mapping;
 pub mod token_cache_service;
+pub mod token_refresh_scheduler;
 pub mod video_generation_service;
@@ -0,0 +1,665 @@
+//! 网关管理服务
+//!
+//! 管理 CLI 与 HTTP 管理 API（`/api/management/*`）共用的数据层逻辑。
+//! 只依赖数据库连接，网关离线时 CLI 也可以直接调用。
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use proxycast_core::database::dao::provider_pool::ProviderPoolDao;
+use proxycast_core::database::{lock_db, DbConnection};
+use proxycast_core::models::model_registry::ModelPricing;
+use proxycast_core::models::provider_pool_model::{
+    CredentialData, CredentialDisplay, PoolStats, ProviderCredential,
+};
+use proxycast_core::router::{RegisteredRoute, RouteRegistry};
+use serde::{Deserialize, Serialize};
+
+use crate::provider_pool_service::{CredentialHealthInfo, ProviderPoolService};
+use crate::token_cache_service::TokenCacheService;
+
+/// 新增 / 导入凭证的输入
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialInput {
+    /// Provider 类型；省略时根据凭证数据推断
+    #[serde(default)]
+    pub provider_type: Option<String>,
+    /// 凭证数据
+    pub credential: CredentialData,
+    /// 凭证名称
+    #[serde(default)]
+    pub name: Option<String>,
+    /// 是否启用自动健康检查
+    #[serde(default)]
+    pub check_health: Option<bool>,
+    /// 健康检查模型
+    #[serde(default)]
+    pub check_model_name: Option<String>,
+}
+
+/// 凭证更新（字段为 None 表示不修改，空字符串表示清除）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CredentialPatch {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub is_disabled: Option<bool>,
+    #[serde(default)]
+    pub check_health: Option<bool>,
+    #[serde(default)]
+    pub proxy_url: Option<String>,
+}
+
+/// 单条导入失败信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportFailure {
+    /// 在导入列表中的序号（从 0 开始）
+    pub index: usize,
+    pub error: String,
+}
+
+/// 批量导入结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub imported: Vec<CredentialDisplay>,
+    pub failed: Vec<ImportFailure>,
+}
+
+/// Token 刷新结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshOutcome {
+    pub uuid: String,
+    /// 新 Token 的过期时间（RFC 3339）
+    pub expires_at: Option<String>,
+}
+
+/// 单个 Provider 的凭证池统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderHealthSummary {
+    pub provider_type: String,
+    #[serde(flatten)]
+    pub stats: PoolStats,
+}
+
+/// 凭证池健康报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolHealthReport {
+    pub providers: Vec<ProviderHealthSummary>,
+    pub credentials: Vec<CredentialHealthInfo>,
+}
+
+/// 用量报表的分组维度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageGroupBy {
+    #[default]
+    Provider,
+    Model,
+}
+
+impl FromStr for UsageGroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "provider" => Ok(Self::Provider),
+            "model" => Ok(Self::Model),
+            other => Err(format!("未知的分组维度: {other}（可选 provider、model）")),
+        }
+    }
+}
+
+/// 一条 Token 使用记录（与遥测模块解耦的最小视图）
+#[derive(Debug, Clone, Copy)]
+pub struct UsageEntry<'a> {
+    pub provider: &'a str,
+    pub model: &'a str,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// 用量报表中的一行
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageRow {
+    pub key: String,
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub total_tokens: u64,
+    /// 估算费用；没有任何记录能匹配到定价时为 None
+    pub cost: Option<f64>,
+    pub currency: Option<String>,
+}
+
+impl UsageRow {
+    fn add(&mut self, entry: &UsageEntry<'_>, pricing: Option<&ModelPricing>) {
+        self.requests += 1;
+        self.input_tokens += entry.input_tokens;
+        self.output_tokens += entry.output_tokens;
+        self.total_tokens += entry.input_tokens + entry.output_tokens;
+
+        if let Some(pricing) = pricing {
+            let cost = entry.input_tokens as f64 / 1_000_000.0
+                * pricing.input_per_million.unwrap_or(0.0)
+                + entry.output_tokens as f64 / 1_000_000.0
+                    * pricing.output_per_million.unwrap_or(0.0);
+            *self.cost.get_or_insert(0.0) += cost;
+            self.currency
+                .get_or_insert_with(|| pricing.currency.clone());
+        }
+    }
+}
+
+/// Token / 费用报表
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageReport {
+    pub group_by: UsageGroupBy,
+    pub rows: Vec<UsageRow>,
+    pub total: UsageRow,
+}
+
+impl UsageReport {
+    /// 按维度汇总使用记录，并根据模型定价估算费用
+    ///
+    /// 行按总 Token 数降序排列。
+    pub fn build<'a>(
+        group_by: UsageGroupBy,
+        entries: impl IntoIterator<Item = UsageEntry<'a>>,
+        pricing: &HashMap<String, ModelPricing>,
+    ) -> Self {
+        let mut rows: HashMap<String, UsageRow> = HashMap::new();
+        let mut total = UsageRow {
+            key: "total".to_string(),
+            ..UsageRow::default()
+        };
+
+        for entry in entries {
+            let key = match group_by {
+                UsageGroupBy::Provider => entry.provider,
+                UsageGroupBy::Model => entry.model,
+            };
+            let model_pricing = lookup_pricing(pricing, entry.model);
+            rows.entry(key.to_string())
+                .or_insert_with(|| UsageRow {
+                    key: key.to_string(),
+                    ..UsageRow::default()
+                })
+                .add(&entry, model_pricing);
+            total.add(&entry, model_pricing);
+        }
+
+        let mut rows: Vec<UsageRow> = rows.into_values().collect();
+        rows.sort_by(|a, b| {
+            b.total_tokens
+                .cmp(&a.total_tokens)
+                .then_with(|| a.key.cmp(&b.key))
+        });
+
+        Self {
+            group_by,
+            rows,
+            total,
+        }
+    }
+}
+
+/// 查找模型定价：先精确匹配，再忽略 `provider/` 前缀匹配
+fn lookup_pricing<'a>(
+    pricing: &'a HashMap<String, ModelPricing>,
+    model: &str,
+) -> Option<&'a ModelPricing> {
+    let model = model.to_ascii_lowercase();
+    pricing.get(&model).or_else(|| {
+        model
+            .rsplit_once('/')
+            .and_then(|(_, short)| pricing.get(short))
+    })
+}
+
+/// 网关管理服务
+pub struct ManagementService {
+    pool: Arc<ProviderPoolService>,
+    token_cache: Arc<TokenCacheService>,
+}
+
+impl ManagementService {
+    pub fn new(pool: Arc<ProviderPoolService>, token_cache: Arc<TokenCacheService>) -> Self {
+        Self { pool, token_cache }
+    }
+
+    /// 列出凭证（API Key 明文不会返回）
+    pub fn list_credentials(
+        &self,
+        db: &DbConnection,
+        provider_type: Option<&str>,
+    ) -> Result<Vec<CredentialDisplay>, String> {
+        let credentials = match provider_type {
+            Some(pt) => self.pool.get_by_type(db, pt)?,
+            None => self
+                .pool
+                .get_overview(db)?
+                .into_iter()
+                .flat_map(|overview| overview.credentials)
+                .collect(),
+        };
+        Ok(credentials.into_iter().map(redact).collect())
+    }
+
+    /// 新增凭证
+    pub fn add_credential(
+        &self,
+        db: &DbConnection,
+        input: CredentialInput,
+    ) -> Result<CredentialDisplay, String> {
+        validate_credential(&input.credential)?;
+        let provider_type = input
+            .provider_type
+            .unwrap_or_else(|| input.credential.provider_type().to_string());
+        let credential = self.pool.add_credential(
+            db,
+            &provider_type,
+            input.credential,
+            input.name,
+            input.check_health,
+            input.check_model_name,
+        )?;
+        Ok(redact(CredentialDisplay::from(&credential)))
+    }
+
+    /// 批量导入凭证，单条失败不影响其余条目
+    pub fn import_credentials(
+        &self,
+        db: &DbConnection,
+        inputs: Vec<CredentialInput>,
+    ) -> ImportSummary {
+        let mut summary = ImportSummary::default();
+        for (index, input) in inputs.into_iter().enumerate() {
+            match self.add_credential(db, input) {
+                Ok(display) => summary.imported.push(display),
+                Err(error) => summary.failed.push(ImportFailure { index, error }),
+            }
+        }
+        summary
+    }
+
+    /// 更新凭证
+    pub fn update_credential(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        patch: CredentialPatch,
+    ) -> Result<CredentialDisplay, String> {
+        let credential = self.pool.update_credential(
+            db,
+            uuid,
+            patch.name,
+            patch.is_disabled,
+            patch.check_health,
+            None,
+            None,
+            patch.proxy_url,
+        )?;
+        Ok(redact(CredentialDisplay::from(&credential)))
+    }
+
+    /// 删除凭证
+    pub fn delete_credential(&self, db: &DbConnection, uuid: &str) -> Result<(), String> {
+        if self.pool.delete_credential(db, uuid)? {
+            Ok(())
+        } else {
+            Err(format!("凭证不存在: {uuid}"))
+        }
+    }
+
+    /// 强制刷新 OAuth Token
+    pub async fn refresh_token(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+    ) -> Result<RefreshOutcome, String> {
+        let credential = self.get_credential(db, uuid)?;
+        if !TokenCacheService::supports_refresh(credential.provider_type) {
+            return Err(format!(
+                "{} 类型的凭证不支持 Token 刷新",
+                credential.provider_type
+            ));
+        }
+
+        self.token_cache.refresh_and_cache(db, uuid, true).await?;
+        let expires_at = self
+            .token_cache
+            .get_cache_status(db, uuid)?
+            .and_then(|cache| cache.expiry_time)
+            .map(|t| t.to_rfc3339());
+
+        Ok(RefreshOutcome {
+            uuid: uuid.to_string(),
+            expires_at,
+        })
+    }
+
+    /// 凭证池健康报告
+    pub fn pool_health(&self, db: &DbConnection) -> Result<PoolHealthReport, String> {
+        let providers = self
+            .pool
+            .get_overview(db)?
+            .into_iter()
+            .map(|overview| ProviderHealthSummary {
+                provider_type: overview.provider_type,
+                stats: overview.stats,
+            })
+            .collect();
+        let credentials = self.pool.get_all_credential_health(db)?;
+        Ok(PoolHealthReport {
+            providers,
+            credentials,
+        })
+    }
+
+    /// 根据当前凭证构建路由注册表
+    pub fn route_registry(
+        &self,
+        db: &DbConnection,
+        default_provider: &str,
+    ) -> Result<RouteRegistry, String> {
+        let credentials = {
+            let conn = lock_db(db)?;
+            ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?
+        };
+
+        let mut registry = RouteRegistry::new();
+        registry.register(RegisteredRoute::default_route(default_provider));
+        for credential in &credentials {
+            let mut route = RegisteredRoute::provider_namespace(
+                &credential.provider_type.to_string(),
+                &credential.uuid,
+                credential.name.as_deref(),
+            );
+            route.enabled = credential.is_available();
+            registry.register(route);
+        }
+        Ok(registry)
+    }
+
+    /// 列出所有路由（按优先级排序）
+    pub fn list_routes(
+        &self,
+        db: &DbConnection,
+        default_provider: &str,
+    ) -> Result<Vec<RegisteredRoute>, String> {
+        Ok(self
+            .route_registry(db, default_provider)?
+            .all_routes()
+            .to_vec())
+    }
+
+    fn get_credential(&self, db: &DbConnection, uuid: &str) -> Result<ProviderCredential, String> {
+        self.pool
+            .get_by_uuid(db, uuid)?
+            .ok_or_else(|| format!("凭证不存在: {uuid}"))
+    }
+}
+
+/// 从模型注册表加载定价（键为小写模型 ID）
+pub fn load_model_pricing(db: &DbConnection) -> Result<HashMap<String, ModelPricing>, String> {
+    let conn = lock_db(db)?;
+    let mut stmt = conn
+        .prepare("SELECT id, pricing FROM model_registry WHERE pricing IS NOT NULL")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut pricing = HashMap::new();
+    for (id, json) in rows.flatten() {
+        if let Ok(p) = serde_json::from_str::<ModelPricing>(&json) {
+            pricing.insert(id.to_ascii_lowercase(), p);
+        }
+    }
+    Ok(pricing)
+}
+
+/// 管理接口不回传 API Key 明文
+fn redact(mut display: CredentialDisplay) -> CredentialDisplay {
+    display.api_key = None;
+    display
+}
+
+fn validate_credential(credential: &CredentialData) -> Result<(), String> {
+    match credential {
+        CredentialData::KiroOAuth { creds_file_path }
+        | CredentialData::GeminiOAuth {
+            creds_file_path, ..
+        }
+        | CredentialData::AntigravityOAuth {
+            creds_file_path, ..
+        }
+        | CredentialData::CodexOAuth {
+            creds_file_path, ..
+        }
+        | CredentialData::ClaudeOAuth { creds_file_path } => {
+            if !Path::new(creds_file_path).is_file() {
+                return Err(format!("凭证文件不存在: {creds_file_path}"));
+            }
+        }
+        CredentialData::OpenAIKey { api_key, .. }
+        | CredentialData::ClaudeKey { api_key, .. }
+        | CredentialData::VertexKey { api_key, .. }
+        | CredentialData::GeminiApiKey { api_key, .. }
+        | CredentialData::AnthropicKey { api_key, .. } => {
+            if api_key.trim().is_empty() {
+                return Err("API Key 不能为空".to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proxycast_core::database::schema::create_tables;
+    use proxycast_core::router::RouteType;
+    use rusqlite::Connection;
+    use std::sync::Mutex;
+
+    fn setup() -> (ManagementService, DbConnection) {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let service = ManagementService::new(
+            Arc::new(ProviderPoolService::new()),
+            Arc::new(TokenCacheService::new()),
+        );
+        (service, Arc::new(Mutex::new(conn)))
+    }
+
+    fn openai_input(api_key: &str, name: Option<&str>) -> CredentialInput {
+        CredentialInput {
+            provider_type: None,
+            credential: CredentialData::OpenAIKey {
+                api_key: api_key.to_string(),
+                base_url: None,
+            },
+            name: name.map(str::to_string),
+            check_health: Some(false),
+            check_model_name: None,
+        }
+    }
+
+    #[test]
+    fn test_add_list_and_redact() {
+        let (service, db) = setup();
+        let added = service
+            .add_credential(&db, openai_input("sk-secret-1234567890", Some("main")))
+            .unwrap();
+        assert_eq!(added.provider_type, "openai");
+        assert!(added.api_key.is_none());
+
+        let all = service.list_credentials(&db, None).unwrap();
+        assert_eq!(all.len(), 1);
+        assert!(all[0].api_key.is_none());
+        assert!(!all[0].display_credential.contains("secret-1234567890"));
+
+        let filtered = service.list_credentials(&db, Some("claude")).unwrap();
+        assert!(filtered.is_empty());
+    }
+
+    #[test]
+    fn test_update_and_delete() {
+        let (service, db) = setup();
+        let added = service
+            .add_credential(&db, openai_input("sk-a", None))
+            .unwrap();
+
+        let patch = CredentialPatch {
+            name: Some("backup".to_string()),
+            is_disabled: Some(true),
+            ..CredentialPatch::default()
+        };
+        let updated = service.update_credential(&db, &added.uuid, patch).unwrap();
+        assert_eq!(updated.name.as_deref(), Some("backup"));
+        assert!(updated.is_disabled);
+
+        service.delete_credential(&db, &added.uuid).unwrap();
+        assert!(service.delete_credential(&db, &added.uuid).is_err());
+    }
+
+    #[test]
+    fn test_import_reports_failures() {
+        let (service, db) = setup();
+        let summary = service.import_credentials(
+            &db,
+            vec![
+                openai_input("sk-ok", None),
+                openai_input("  ", None),
+                CredentialInput {
+                    credential: CredentialData::KiroOAuth {
+                        creds_file_path: "/nonexistent/kiro.json".to_string(),
+                    },
+                    ..openai_input("unused", None)
+                },
+            ],
+        );
+        assert_eq!(summary.imported.len(), 1);
+        assert_eq!(
+            summary.failed.iter().map(|f| f.index).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rejects_api_key_credentials() {
+        let (service, db) = setup();
+        let added = service
+            .add_credential(&db, openai_input("sk-a", None))
+            .unwrap();
+        let err = service.refresh_token(&db, &added.uuid).await.unwrap_err();
+        assert!(err.contains("不支持"));
+        assert!(service.refresh_token(&db, "missing").await.is_err());
+    }
+
+    #[test]
+    fn test_routes_from_registry() {
+        let (service, db) = setup();
+        let named = service
+            .add_credential(&db, openai_input("sk-a", Some("Team Key")))
+            .unwrap();
+        service
+            .update_credential(
+                &db,
+                &named.uuid,
+                CredentialPatch {
+                    is_disabled: Some(true),
+                    ..CredentialPatch::default()
+                },
+            )
+            .unwrap();
+
+        let routes = service.list_routes(&db, "kiro").unwrap();
+        assert_eq!(routes.len(), 2);
+        // 按优先级排序：命名空间路由在默认路由之前
+        assert_eq!(routes[0].route_type, RouteType::ProviderNamespace);
+        assert_eq!(routes[0].path_pattern, "/team-key/v1/{endpoint}");
+        assert!(!routes[0].enabled);
+        assert_eq!(routes[1].route_type, RouteType::Default);
+        assert_eq!(routes[1].provider_type.as_deref(), Some("kiro"));
+    }
+
+    #[test]
+    fn test_pool_health() {
+        let (service, db) = setup();
+        service
+            .add_credential(&db, openai_input("sk-a", None))
+            .unwrap();
+        let report = service.pool_health(&db).unwrap();
+        assert_eq!(report.providers.len(), 1);
+        assert_eq!(report.providers[0].stats.total_count, 1);
+        assert_eq!(report.credentials.len(), 1);
+    }
+
+    #[test]
+    fn test_usage_report_with_pricing() {
+        let mut pricing = HashMap::new();
+        pricing.insert(
+            "gpt-4o".to_string(),
+            ModelPricing {
+                input_per_million: Some(2.0),
+                output_per_million: Some(10.0),
+                ..ModelPricing::default()
+            },
+        );
+
+        let entries = [
+            UsageEntry {
+                provider: "openai",
+                model: "openai/gpt-4o",
+                input_tokens: 500_000,
+                output_tokens: 100_000,
+            },
+            UsageEntry {
+                provider: "openai",
+                model: "gpt-4o",
+                input_tokens: 500_000,
+                output_tokens: 100_000,
+            },
+            UsageEntry {
+                provider: "kiro",
+                model: "unknown-model",
+                input_tokens: 10,
+                output_tokens: 5,
+            },
+        ];
+
+        let report = UsageReport::build(UsageGroupBy::Provider, entries, &pricing);
+        assert_eq!(report.rows.len(), 2);
+        assert_eq!(report.rows[0].key, "openai");
+        assert_eq!(report.rows[0].requests, 2);
+        assert!((report.rows[0].cost.unwrap() - 4.0).abs() < 1e-9);
+        assert_eq!(report.rows[0].currency.as_deref(), Some("USD"));
+        assert_eq!(report.rows[1].cost, None);
+        assert_eq!(report.total.total_tokens, 1_200_015);
+
+        let by_model = UsageReport::build(UsageGroupBy::Model, entries, &pricing);
+        assert_eq!(by_model.rows.len(), 3);
+    }
+
+    #[test]
+    fn test_load_model_pricing() {
+        let (_, db) = setup();
+        {
+            let conn = db.lock().unwrap();
+            conn.execute(
+                "INSERT INTO model_registry (id, display_name, provider_id, provider_name, pricing, created_at, updated_at)
+                 VALUES ('GPT-4o', 'GPT-4o', 'openai', 'OpenAI', ?1, 0, 0)",
+                [r#"{"input_per_million":2.5,"output_per_million":10.0,"currency":"USD"}"#],
+            )
+            .unwrap();
+        }
+        let pricing = load_model_pricing(&db).unwrap();
+        assert_eq!(pricing["gpt-4o"].input_per_million, Some(2.5));
+    }
+}
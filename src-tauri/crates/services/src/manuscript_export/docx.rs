@@ -0,0 +1,468 @@
+//! DOCX（Office Open XML）写出
+
+use super::markdown::{escape_xml, parse_blocks, Block, Inline};
+use super::types::*;
+use std::io::{Cursor, Write};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// 1 像素 = 9525 EMU（按 96 DPI）
+const EMU_PER_PX: u64 = 9525;
+/// 版心宽度上限（约 15.24 cm）
+const MAX_WIDTH_EMU: u64 = 5_486_400;
+/// 无法识别尺寸时的默认图片大小
+const FALLBACK_SIZE: (u32, u32) = (600, 800);
+
+const STYLES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:docDefaults>
+    <w:rPrDefault><w:rPr><w:rFonts w:ascii="Times New Roman" w:hAnsi="Times New Roman" w:eastAsia="SimSun"/><w:sz w:val="24"/><w:lang w:val="en-US" w:eastAsia="zh-CN"/></w:rPr></w:rPrDefault>
+    <w:pPrDefault><w:pPr><w:spacing w:after="120" w:line="360" w:lineRule="auto"/></w:pPr></w:pPrDefault>
+  </w:docDefaults>
+  <w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:qFormat/><w:pPr><w:ind w:firstLineChars="200"/></w:pPr></w:style>
+  <w:style w:type="paragraph" w:styleId="Title"><w:name w:val="Title"/><w:basedOn w:val="Normal"/><w:qFormat/><w:pPr><w:jc w:val="center"/><w:spacing w:before="2400" w:after="480"/><w:ind w:firstLineChars="0"/></w:pPr><w:rPr><w:b/><w:sz w:val="52"/></w:rPr></w:style>
+  <w:style w:type="paragraph" w:styleId="Subtitle"><w:name w:val="Subtitle"/><w:basedOn w:val="Normal"/><w:qFormat/><w:pPr><w:jc w:val="center"/><w:ind w:firstLineChars="0"/></w:pPr><w:rPr><w:sz w:val="32"/></w:rPr></w:style>
+  <w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:jc w:val="center"/><w:spacing w:before="480" w:after="360"/><w:ind w:firstLineChars="0"/><w:outlineLvl w:val="0"/></w:pPr><w:rPr><w:b/><w:sz w:val="36"/></w:rPr></w:style>
+  <w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="360" w:after="240"/><w:ind w:firstLineChars="0"/><w:outlineLvl w:val="1"/></w:pPr><w:rPr><w:b/><w:sz w:val="30"/></w:rPr></w:style>
+  <w:style w:type="paragraph" w:styleId="Heading3"><w:name w:val="heading 3"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="240" w:after="120"/><w:ind w:firstLineChars="0"/><w:outlineLvl w:val="2"/></w:pPr><w:rPr><w:b/><w:sz w:val="26"/></w:rPr></w:style>
+  <w:style w:type="paragraph" w:styleId="Quote"><w:name w:val="Quote"/><w:basedOn w:val="Normal"/><w:qFormat/><w:pPr><w:ind w:left="720" w:right="720" w:firstLineChars="0"/></w:pPr><w:rPr><w:i/><w:color w:val="555555"/></w:rPr></w:style>
+  <w:style w:type="paragraph" w:styleId="TOCHeading"><w:name w:val="TOC Heading"/><w:basedOn w:val="Normal"/><w:pPr><w:jc w:val="center"/><w:spacing w:before="480" w:after="360"/><w:ind w:firstLineChars="0"/></w:pPr><w:rPr><w:b/><w:sz w:val="36"/></w:rPr></w:style>
+  <w:style w:type="paragraph" w:styleId="TOC1"><w:name w:val="toc 1"/><w:basedOn w:val="Normal"/><w:pPr><w:ind w:firstLineChars="0"/></w:pPr></w:style>
+  <w:style w:type="paragraph" w:styleId="Centered"><w:name w:val="Centered"/><w:basedOn w:val="Normal"/><w:pPr><w:jc w:val="center"/><w:ind w:firstLineChars="0"/></w:pPr></w:style>
+</w:styles>
+"#;
+
+const RELS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>
+  <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/>
+  <Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/extended-properties" Target="docProps/app.xml"/>
+</Relationships>
+"#;
+
+/// 嵌入文档的图片
+struct EmbeddedImage {
+    rel_id: String,
+    target: String,
+    data: Vec<u8>,
+}
+
+/// 文档正文构建状态
+struct DocumentBuilder<'a> {
+    manuscript: &'a Manuscript,
+    body: String,
+    images: Vec<EmbeddedImage>,
+    drawing_id: u32,
+}
+
+impl<'a> DocumentBuilder<'a> {
+    fn new(manuscript: &'a Manuscript) -> Self {
+        Self {
+            manuscript,
+            body: String::new(),
+            images: Vec::new(),
+            drawing_id: 0,
+        }
+    }
+
+    fn paragraph(&mut self, style: Option<&str>, runs: &str) {
+        self.body.push_str("<w:p>");
+        if let Some(style) = style {
+            self.body
+                .push_str(&format!("<w:pPr><w:pStyle w:val=\"{style}\"/></w:pPr>"));
+        }
+        self.body.push_str(runs);
+        self.body.push_str("</w:p>\n");
+    }
+
+    fn page_break(&mut self) {
+        self.body
+            .push_str("<w:p><w:r><w:br w:type=\"page\"/></w:r></w:p>\n");
+    }
+
+    /// 嵌入图片并输出居中的图片段落
+    fn image(&mut self, asset: &ManuscriptAsset, alt: &str) {
+        let rel_id = format!("rIdImg{}", self.images.len() + 1);
+        let target = format!(
+            "media/image{}.{}",
+            self.images.len() + 1,
+            image_extension(asset)
+        );
+        self.images.push(EmbeddedImage {
+            rel_id: rel_id.clone(),
+            target,
+            data: asset.data.clone(),
+        });
+
+        let (width, height) = image_size(&asset.data).unwrap_or(FALLBACK_SIZE);
+        let mut cx = width as u64 * EMU_PER_PX;
+        let mut cy = height as u64 * EMU_PER_PX;
+        if cx > MAX_WIDTH_EMU {
+            cy = cy * MAX_WIDTH_EMU / cx;
+            cx = MAX_WIDTH_EMU;
+        }
+
+        self.drawing_id += 1;
+        let id = self.drawing_id;
+        let name = escape_xml(asset.file_name());
+        let descr = escape_xml(alt);
+        let runs = format!(
+            r#"<w:r><w:drawing><wp:inline distT="0" distB="0" distL="0" distR="0"><wp:extent cx="{cx}" cy="{cy}"/><wp:docPr id="{id}" name="{name}" descr="{descr}"/><a:graphic xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"><a:graphicData uri="http://schemas.openxmlformats.org/drawingml/2006/picture"><pic:pic xmlns:pic="http://schemas.openxmlformats.org/drawingml/2006/picture"><pic:nvPicPr><pic:cNvPr id="{id}" name="{name}"/><pic:cNvPicPr/></pic:nvPicPr><pic:blipFill><a:blip r:embed="{rel_id}"/><a:stretch><a:fillRect/></a:stretch></pic:blipFill><pic:spPr><a:xfrm><a:off x="0" y="0"/><a:ext cx="{cx}" cy="{cy}"/></a:xfrm><a:prstGeom prst="rect"><a:avLst/></a:prstGeom></pic:spPr></pic:pic></a:graphicData></a:graphic></wp:inline></w:drawing></w:r>"#
+        );
+        self.paragraph(Some("Centered"), &runs);
+    }
+
+    fn title_page(&mut self) {
+        let meta = &self.manuscript.metadata;
+        self.paragraph(Some("Title"), &text_run(&meta.title, ""));
+        if let Some(subtitle) = &meta.subtitle {
+            self.paragraph(Some("Subtitle"), &text_run(subtitle, ""));
+        }
+        if !meta.authors.is_empty() {
+            self.paragraph(Some("Subtitle"), &text_run(&meta.authors.join("、"), ""));
+        }
+        if let Some(publisher) = &meta.publisher {
+            self.paragraph(Some("Centered"), &text_run(publisher, ""));
+        }
+    }
+
+    /// 目录使用 Word 的 TOC 域；预先填充带书签链接的条目，
+    /// 未更新域的阅读器也能直接看到目录
+    fn toc(&mut self) {
+        self.paragraph(Some("TOCHeading"), &text_run("目录", ""));
+        self.body.push_str(
+            "<w:p><w:pPr><w:pStyle w:val=\"TOC1\"/></w:pPr><w:r><w:fldChar w:fldCharType=\"begin\"/></w:r><w:r><w:instrText xml:space=\"preserve\"> TOC \\o \"1-1\" \\h \\z \\u </w:instrText></w:r><w:r><w:fldChar w:fldCharType=\"separate\"/></w:r></w:p>\n",
+        );
+        for (index, chapter) in self.manuscript.chapters.iter().enumerate() {
+            let runs = format!(
+                "<w:hyperlink w:anchor=\"{}\" w:history=\"1\">{}</w:hyperlink>",
+                bookmark_name(index),
+                text_run(&chapter.title, "")
+            );
+            self.paragraph(Some("TOC1"), &runs);
+        }
+        self.body
+            .push_str("<w:p><w:r><w:fldChar w:fldCharType=\"end\"/></w:r></w:p>\n");
+    }
+
+    fn chapter(&mut self, index: usize, chapter: &ManuscriptChapter) {
+        let bookmark_id = index + 1;
+        let runs = format!(
+            "<w:bookmarkStart w:id=\"{bookmark_id}\" w:name=\"{}\"/>{}<w:bookmarkEnd w:id=\"{bookmark_id}\"/>",
+            bookmark_name(index),
+            text_run(&chapter.title, "")
+        );
+        self.paragraph(Some("Heading1"), &runs);
+
+        for block in parse_blocks(&chapter.body) {
+            match block {
+                Block::Heading(level, inlines) => {
+                    // 章节标题占用 Heading1，正文内标题整体下移一级
+                    let style = format!("Heading{}", (level + 1).min(3));
+                    self.paragraph(Some(&style), &inline_runs(&inlines));
+                }
+                Block::Paragraph(inlines) => self.paragraph(None, &inline_runs(&inlines)),
+                Block::Quote(inlines) => self.paragraph(Some("Quote"), &inline_runs(&inlines)),
+                Block::Image { alt, src } => match self.manuscript.find_asset(&src) {
+                    Some(asset) => self.image(asset, &alt),
+                    None => {
+                        tracing::warn!("[Manuscript] 未找到图片资源: {}", src);
+                        self.paragraph(Some("Centered"), &text_run(&format!("[{alt}]"), ""));
+                    }
+                },
+                Block::Rule => self.paragraph(Some("Centered"), &text_run("* * *", "")),
+            }
+        }
+    }
+
+    fn finish(self) -> (String, Vec<EmbeddedImage>) {
+        let document = format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing">
+<w:body>
+{}<w:sectPr><w:pgSz w:w="11906" w:h="16838"/><w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440" w:header="851" w:footer="992" w:gutter="0"/></w:sectPr>
+</w:body>
+</w:document>
+"#,
+            self.body
+        );
+        (document, self.images)
+    }
+}
+
+fn bookmark_name(index: usize) -> String {
+    format!("_Chapter{}", index + 1)
+}
+
+fn text_run(text: &str, run_props: &str) -> String {
+    let props = if run_props.is_empty() {
+        String::new()
+    } else {
+        format!("<w:rPr>{run_props}</w:rPr>")
+    };
+    text.split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            let br = if i > 0 { "<w:br/>" } else { "" };
+            format!(
+                "<w:r>{props}{br}<w:t xml:space=\"preserve\">{}</w:t></w:r>",
+                escape_xml(line)
+            )
+        })
+        .collect()
+}
+
+fn inline_runs(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(t) => text_run(t, ""),
+            Inline::Bold(t) => text_run(t, "<w:b/>"),
+            Inline::Italic(t) => text_run(t, "<w:i/>"),
+        })
+        .collect()
+}
+
+fn image_extension(asset: &ManuscriptAsset) -> &'static str {
+    match asset.media_type.as_str() {
+        "image/jpeg" => "jpeg",
+        "image/gif" => "gif",
+        "image/bmp" => "bmp",
+        _ => "png",
+    }
+}
+
+/// 从 PNG / GIF / JPEG 文件头读取像素尺寸
+fn image_size(data: &[u8]) -> Option<(u32, u32)> {
+    if data.len() >= 24 && data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        let width = u32::from_be_bytes(data[16..20].try_into().ok()?);
+        let height = u32::from_be_bytes(data[20..24].try_into().ok()?);
+        return Some((width, height));
+    }
+    if data.len() >= 10 && (data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a")) {
+        let width = u16::from_le_bytes([data[6], data[7]]) as u32;
+        let height = u16::from_le_bytes([data[8], data[9]]) as u32;
+        return Some((width, height));
+    }
+    if data.starts_with(&[0xFF, 0xD8]) {
+        let mut pos = 2;
+        while pos + 9 < data.len() {
+            if data[pos] != 0xFF {
+                pos += 1;
+                continue;
+            }
+            let marker = data[pos + 1];
+            let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+            // SOF0..SOF15，排除 DHT(C4)、JPG(C8)、DAC(CC)
+            if (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+                let height = u16::from_be_bytes([data[pos + 5], data[pos + 6]]) as u32;
+                let width = u16::from_be_bytes([data[pos + 7], data[pos + 8]]) as u32;
+                return Some((width, height));
+            }
+            pos += 2 + len;
+        }
+    }
+    None
+}
+
+fn core_xml(meta: &ManuscriptMetadata) -> String {
+    let modified = chrono::DateTime::from_timestamp_millis(meta.modified_at)
+        .unwrap_or_else(chrono::Utc::now)
+        .format("%Y-%m-%dT%H:%M:%SZ");
+    let mut extra = String::new();
+    if let Some(description) = &meta.description {
+        extra.push_str(&format!(
+            "<dc:description>{}</dc:description>",
+            escape_xml(description)
+        ));
+    }
+    if !meta.subjects.is_empty() {
+        extra.push_str(&format!(
+            "<cp:keywords>{}</cp:keywords>",
+            escape_xml(&meta.subjects.join(", "))
+        ));
+    }
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+<dc:title>{}</dc:title><dc:creator>{}</dc:creator><dc:language>{}</dc:language><dc:identifier>{}</dc:identifier>{extra}<dcterms:modified xsi:type="dcterms:W3CDTF">{modified}</dcterms:modified>
+</cp:coreProperties>
+"#,
+        escape_xml(&meta.title),
+        escape_xml(&meta.authors.join("; ")),
+        escape_xml(&meta.language),
+        escape_xml(&meta.identifier),
+    )
+}
+
+fn app_xml(meta: &ManuscriptMetadata) -> String {
+    let company = meta
+        .publisher
+        .as_deref()
+        .map(|p| format!("<Company>{}</Company>", escape_xml(p)))
+        .unwrap_or_default();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Properties xmlns="http://schemas.openxmlformats.org/officeDocument/2006/extended-properties"><Application>ProxyCast</Application>{company}</Properties>
+"#
+    )
+}
+
+/// 生成 DOCX 文件内容
+pub fn write_docx(
+    manuscript: &Manuscript,
+    options: &ManuscriptExportOptions,
+) -> Result<Vec<u8>, ManuscriptError> {
+    if manuscript.chapters.is_empty() {
+        return Err(ManuscriptError::Empty);
+    }
+
+    let mut builder = DocumentBuilder::new(manuscript);
+    let mut need_break = false;
+    if let Some(cover) = &manuscript.cover {
+        builder.image(cover, &manuscript.metadata.title);
+        need_break = true;
+    }
+    if options.include_title_page {
+        if need_break {
+            builder.page_break();
+        }
+        builder.title_page();
+        need_break = true;
+    }
+    if options.include_toc {
+        if need_break {
+            builder.page_break();
+        }
+        builder.toc();
+        need_break = true;
+    }
+    for (index, chapter) in manuscript.chapters.iter().enumerate() {
+        if need_break {
+            builder.page_break();
+        }
+        builder.chapter(index, chapter);
+        need_break = true;
+    }
+    let (document, images) = builder.finish();
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    zip.start_file("[Content_Types].xml", options)?;
+    zip.write_all(
+        br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+  <Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+  <Default Extension="xml" ContentType="application/xml"/>
+  <Default Extension="png" ContentType="image/png"/>
+  <Default Extension="jpeg" ContentType="image/jpeg"/>
+  <Default Extension="gif" ContentType="image/gif"/>
+  <Default Extension="bmp" ContentType="image/bmp"/>
+  <Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>
+  <Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/>
+  <Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>
+  <Override PartName="/docProps/app.xml" ContentType="application/vnd.openxmlformats-officedocument.extended-properties+xml"/>
+</Types>
+"#,
+    )?;
+
+    zip.start_file("_rels/.rels", options)?;
+    zip.write_all(RELS_XML.as_bytes())?;
+
+    zip.start_file("docProps/core.xml", options)?;
+    zip.write_all(core_xml(&manuscript.metadata).as_bytes())?;
+    zip.start_file("docProps/app.xml", options)?;
+    zip.write_all(app_xml(&manuscript.metadata).as_bytes())?;
+
+    let mut relationships = vec![
+        r#"<Relationship Id="rIdStyles" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>"#.to_string(),
+    ];
+    for image in &images {
+        relationships.push(format!(
+            r#"<Relationship Id="{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="{}"/>"#,
+            image.rel_id, image.target
+        ));
+        zip.start_file(format!("word/{}", image.target), options)?;
+        zip.write_all(&image.data)?;
+    }
+    zip.start_file("word/_rels/document.xml.rels", options)?;
+    zip.write_all(
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\n  {}\n</Relationships>\n",
+            relationships.join("\n  ")
+        )
+        .as_bytes(),
+    )?;
+
+    zip.start_file("word/styles.xml", options)?;
+    zip.write_all(STYLES_XML.as_bytes())?;
+    zip.start_file("word/document.xml", options)?;
+    zip.write_all(document.as_bytes())?;
+
+    Ok(zip.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        data.extend_from_slice(&width.to_be_bytes());
+        data.extend_from_slice(&height.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn test_image_size() {
+        assert_eq!(image_size(&png(320, 480)), Some((320, 480)));
+        assert_eq!(image_size(b"GIF89a\x10\x00\x20\x00"), Some((16, 32)));
+        let jpeg = [
+            0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, 0xFF, 0xC0, 0x00, 0x11, 0x08, 0x00,
+            0x30, 0x00, 0x40, 0x03,
+        ];
+        assert_eq!(image_size(&jpeg), Some((64, 48)));
+        assert_eq!(image_size(b"not an image"), None);
+    }
+
+    #[test]
+    fn test_docx_structure() {
+        let manuscript = Manuscript {
+            metadata: ManuscriptMetadata::new("测试书"),
+            chapters: vec![ManuscriptChapter {
+                title: "第一章 <开端>".to_string(),
+                body: "第一段**重点**\n\n![插图](assets/a.png)".to_string(),
+            }],
+            cover: Some(ManuscriptAsset {
+                path: "assets/cover.png".to_string(),
+                media_type: "image/png".to_string(),
+                data: png(2000, 3000),
+            }),
+            assets: vec![ManuscriptAsset {
+                path: "assets/a.png".to_string(),
+                media_type: "image/png".to_string(),
+                data: png(100, 50),
+            }],
+        };
+
+        let bytes = write_docx(&manuscript, &ManuscriptExportOptions::default()).unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut document = String::new();
+        archive
+            .by_name("word/document.xml")
+            .unwrap()
+            .read_to_string(&mut document)
+            .unwrap();
+
+        assert!(document.contains("第一章 &lt;开端&gt;"));
+        assert!(document.contains("<w:b/>"));
+        assert!(document.contains("TOC \\o"));
+        assert!(document.contains("w:anchor=\"_Chapter1\""));
+        // 封面按版心宽度等比缩放
+        assert!(document.contains(&format!("cx=\"{MAX_WIDTH_EMU}\" cy=\"8229600\"")));
+        assert!(archive.by_name("word/media/image1.png").is_ok());
+        assert!(archive.by_name("word/media/image2.png").is_ok());
+    }
+}
@@ -0,0 +1,319 @@
+//! EPUB 3 写出
+
+use super::markdown::{blocks_to_xhtml, escape_xml, parse_blocks};
+use super::types::*;
+use std::io::{Cursor, Write};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const STYLE_CSS: &str = "body { font-family: serif; line-height: 1.8; margin: 0 5%; }
+h1 { text-align: center; margin: 2em 0 1em; }
+p { text-indent: 2em; margin: 0 0 0.6em; }
+blockquote { margin: 1em 2em; color: #555; }
+figure { text-align: center; margin: 1em 0; }
+figure img, .cover img { max-width: 100%; }
+.title-page { text-align: center; margin-top: 30%; }
+.title-page p { text-indent: 0; }
+.cover { text-align: center; margin: 0; padding: 0; }
+nav ol { list-style: none; }
+";
+
+/// 生成 EPUB 3 文件内容
+pub fn write_epub(
+    manuscript: &Manuscript,
+    options: &ManuscriptExportOptions,
+) -> Result<Vec<u8>, ManuscriptError> {
+    if manuscript.chapters.is_empty() {
+        return Err(ManuscriptError::Empty);
+    }
+
+    let meta = &manuscript.metadata;
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    // mimetype 必须是第一个且不压缩
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(b"application/epub+zip")?;
+
+    zip.start_file("META-INF/container.xml", deflated)?;
+    zip.write_all(
+        br#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#,
+    )?;
+
+    let mut manifest = vec![
+        r#"<item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>"#
+            .to_string(),
+        r#"<item id="css" href="style.css" media-type="text/css"/>"#.to_string(),
+    ];
+    let mut spine = Vec::new();
+
+    zip.start_file("OEBPS/style.css", deflated)?;
+    zip.write_all(STYLE_CSS.as_bytes())?;
+
+    // 封面与其他资源一样按原路径写入，正文中对封面的引用也能解析
+    if let Some(cover) = &manuscript.cover {
+        let href = &cover.path;
+        zip.start_file(format!("OEBPS/{href}"), deflated)?;
+        zip.write_all(&cover.data)?;
+        manifest.push(format!(
+            r#"<item id="cover-image" href="{}" media-type="{}" properties="cover-image"/>"#,
+            escape_xml(href),
+            escape_xml(&cover.media_type)
+        ));
+
+        let body = format!(
+            "<div class=\"cover\"><img src=\"{}\" alt=\"{}\"/></div>",
+            escape_xml(href),
+            escape_xml(&meta.title)
+        );
+        write_xhtml(&mut zip, "cover.xhtml", &meta.title, &meta.language, &body)?;
+        manifest.push(
+            r#"<item id="cover" href="cover.xhtml" media-type="application/xhtml+xml"/>"#
+                .to_string(),
+        );
+        spine.push(r#"<itemref idref="cover"/>"#.to_string());
+    }
+
+    if options.include_title_page {
+        let mut body = String::from("<div class=\"title-page\">\n");
+        body.push_str(&format!("<h1>{}</h1>\n", escape_xml(&meta.title)));
+        if let Some(subtitle) = &meta.subtitle {
+            body.push_str(&format!("<p>{}</p>\n", escape_xml(subtitle)));
+        }
+        if !meta.authors.is_empty() {
+            body.push_str(&format!(
+                "<p>{}</p>\n",
+                escape_xml(&meta.authors.join("、"))
+            ));
+        }
+        if let Some(publisher) = &meta.publisher {
+            body.push_str(&format!("<p>{}</p>\n", escape_xml(publisher)));
+        }
+        body.push_str("</div>");
+        write_xhtml(&mut zip, "title.xhtml", &meta.title, &meta.language, &body)?;
+        manifest.push(
+            r#"<item id="title-page" href="title.xhtml" media-type="application/xhtml+xml"/>"#
+                .to_string(),
+        );
+        spine.push(r#"<itemref idref="title-page"/>"#.to_string());
+    }
+
+    let mut toc_entries = String::new();
+    let mut chapter_items = Vec::new();
+    for (index, chapter) in manuscript.chapters.iter().enumerate() {
+        let id = format!("chapter-{:03}", index + 1);
+        let href = format!("{id}.xhtml");
+        let body = format!(
+            "<section epub:type=\"chapter\">\n<h1>{}</h1>\n{}</section>",
+            escape_xml(&chapter.title),
+            blocks_to_xhtml(&parse_blocks(&chapter.body))
+        );
+        write_xhtml(&mut zip, &href, &chapter.title, &meta.language, &body)?;
+        manifest.push(format!(
+            r#"<item id="{id}" href="{href}" media-type="application/xhtml+xml"/>"#
+        ));
+        chapter_items.push(format!(r#"<itemref idref="{id}"/>"#));
+        toc_entries.push_str(&format!(
+            "<li><a href=\"{href}\">{}</a></li>\n",
+            escape_xml(&chapter.title)
+        ));
+    }
+
+    // nav.xhtml 是 EPUB 3 必需的导航文档；不需要目录页时不放入 spine
+    let nav_body = format!(
+        "<nav epub:type=\"toc\" id=\"toc\">\n<h1>目录</h1>\n<ol>\n{toc_entries}</ol>\n</nav>"
+    );
+    write_xhtml(&mut zip, "nav.xhtml", "目录", &meta.language, &nav_body)?;
+    if options.include_toc {
+        spine.push(r#"<itemref idref="nav"/>"#.to_string());
+    }
+    spine.extend(chapter_items);
+
+    let cover_path = manuscript.cover.as_ref().map(|cover| cover.path.as_str());
+    for (index, asset) in manuscript.assets.iter().enumerate() {
+        if Some(asset.path.as_str()) == cover_path {
+            continue;
+        }
+        zip.start_file(format!("OEBPS/{}", asset.path), deflated)?;
+        zip.write_all(&asset.data)?;
+        manifest.push(format!(
+            r#"<item id="asset-{}" href="{}" media-type="{}"/>"#,
+            index + 1,
+            escape_xml(&asset.path),
+            escape_xml(&asset.media_type)
+        ));
+    }
+
+    zip.start_file("OEBPS/content.opf", deflated)?;
+    zip.write_all(build_opf(manuscript, &manifest, &spine).as_bytes())?;
+
+    Ok(zip.finish()?.into_inner())
+}
+
+fn build_opf(manuscript: &Manuscript, manifest: &[String], spine: &[String]) -> String {
+    let meta = &manuscript.metadata;
+    let modified = chrono::DateTime::from_timestamp_millis(meta.modified_at)
+        .unwrap_or_else(chrono::Utc::now)
+        .format("%Y-%m-%dT%H:%M:%SZ");
+
+    let mut metadata = vec![
+        format!(
+            r#"<dc:identifier id="book-id">urn:uuid:{}</dc:identifier>"#,
+            escape_xml(&meta.identifier)
+        ),
+        format!("<dc:title>{}</dc:title>", escape_xml(&meta.title)),
+        format!("<dc:language>{}</dc:language>", escape_xml(&meta.language)),
+        format!(r#"<meta property="dcterms:modified">{modified}</meta>"#),
+    ];
+    for author in &meta.authors {
+        metadata.push(format!("<dc:creator>{}</dc:creator>", escape_xml(author)));
+    }
+    if let Some(description) = &meta.description {
+        metadata.push(format!(
+            "<dc:description>{}</dc:description>",
+            escape_xml(description)
+        ));
+    }
+    if let Some(publisher) = &meta.publisher {
+        metadata.push(format!(
+            "<dc:publisher>{}</dc:publisher>",
+            escape_xml(publisher)
+        ));
+    }
+    for subject in &meta.subjects {
+        metadata.push(format!("<dc:subject>{}</dc:subject>", escape_xml(subject)));
+    }
+    if manuscript.cover.is_some() {
+        // 兼容只识别 EPUB 2 封面声明的阅读器
+        metadata.push(r#"<meta name="cover" content="cover-image"/>"#.to_string());
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id" xml:lang="{lang}">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    {metadata}
+  </metadata>
+  <manifest>
+    {manifest}
+  </manifest>
+  <spine>
+    {spine}
+  </spine>
+</package>
+"#,
+        lang = escape_xml(&meta.language),
+        metadata = metadata.join("\n    "),
+        manifest = manifest.join("\n    "),
+        spine = spine.join("\n    "),
+    )
+}
+
+fn write_xhtml(
+    zip: &mut ZipWriter<Cursor<Vec<u8>>>,
+    href: &str,
+    title: &str,
+    language: &str,
+    body: &str,
+) -> Result<(), ManuscriptError> {
+    zip.start_file(
+        format!("OEBPS/{href}"),
+        FileOptions::default().compression_method(CompressionMethod::Deflated),
+    )?;
+    let document = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{lang}" lang="{lang}">
+<head>
+<meta charset="UTF-8"/>
+<title>{title}</title>
+<link rel="stylesheet" type="text/css" href="style.css"/>
+</head>
+<body>
+{body}
+</body>
+</html>
+"#,
+        lang = escape_xml(language),
+        title = escape_xml(title),
+    );
+    zip.write_all(document.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_epub_structure() {
+        let mut metadata = ManuscriptMetadata::new("测试书 & 副本");
+        metadata.authors.push("作者".to_string());
+        let manuscript = Manuscript {
+            metadata,
+            chapters: vec![
+                ManuscriptChapter {
+                    title: "第一章".to_string(),
+                    body: "正文一".to_string(),
+                },
+                ManuscriptChapter {
+                    title: "第二章".to_string(),
+                    body: "正文二".to_string(),
+                },
+            ],
+            cover: Some(ManuscriptAsset {
+                path: "assets/cover.png".to_string(),
+                media_type: "image/png".to_string(),
+                data: vec![0x89, b'P', b'N', b'G'],
+            }),
+            assets: vec![],
+        };
+
+        let bytes = write_epub(&manuscript, &ManuscriptExportOptions::default()).unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+
+        let first = archive.by_index(0).unwrap();
+        assert_eq!(first.name(), "mimetype");
+        assert_eq!(first.compression(), CompressionMethod::Stored);
+        drop(first);
+
+        let mut opf = String::new();
+        archive
+            .by_name("OEBPS/content.opf")
+            .unwrap()
+            .read_to_string(&mut opf)
+            .unwrap();
+        assert!(opf.contains(r#"version="3.0""#));
+        assert!(opf.contains("测试书 &amp; 副本"));
+        assert!(opf.contains(
+            r#"href="assets/cover.png" media-type="image/png" properties="cover-image""#
+        ));
+        assert!(opf.contains(r#"properties="nav""#));
+        assert!(opf.contains("dcterms:modified"));
+        assert!(archive.by_name("OEBPS/chapter-002.xhtml").is_ok());
+        assert!(archive.by_name("OEBPS/assets/cover.png").is_ok());
+        assert!(archive.by_name("OEBPS/title.xhtml").is_ok());
+    }
+
+    #[test]
+    fn test_empty_manuscript_is_rejected() {
+        let manuscript = Manuscript {
+            metadata: ManuscriptMetadata::new("空"),
+            chapters: vec![],
+            cover: None,
+            assets: vec![],
+        };
+        assert!(matches!(
+            write_epub(&manuscript, &ManuscriptExportOptions::default()),
+            Err(ManuscriptError::Empty)
+        ));
+    }
+}
@@ -0,0 +1,267 @@
+//! 稿件正文的 Markdown 子集解析
+//!
+//! 只覆盖长文稿常用的结构：标题、段落、引用、分隔线、独立成段的图片，
+//! 以及行内的粗体/斜体。解析结果同时用于 EPUB（XHTML）和 DOCX 渲染。
+
+/// 行内片段
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inline {
+    Text(String),
+    Bold(String),
+    Italic(String),
+}
+
+/// 块级元素
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Heading(u8, Vec<Inline>),
+    Paragraph(Vec<Inline>),
+    Quote(Vec<Inline>),
+    Image { alt: String, src: String },
+    Rule,
+}
+
+/// 解析 Markdown 正文为块列表
+pub fn parse_blocks(markdown: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut quote: Vec<&str> = Vec::new();
+
+    fn flush(lines: &mut Vec<&str>, blocks: &mut Vec<Block>, is_quote: bool) {
+        if lines.is_empty() {
+            return;
+        }
+        let text = lines.join("\n");
+        lines.clear();
+        if is_quote {
+            blocks.push(Block::Quote(parse_inlines(&text)));
+        } else if let Some((alt, src)) = parse_image(text.trim()) {
+            blocks.push(Block::Image { alt, src });
+        } else {
+            blocks.push(Block::Paragraph(parse_inlines(&text)));
+        }
+    }
+
+    for raw_line in markdown.lines() {
+        let line = raw_line.trim_end();
+        let trimmed = line.trim_start();
+
+        if trimmed.is_empty() {
+            flush(&mut paragraph, &mut blocks, false);
+            flush(&mut quote, &mut blocks, true);
+            continue;
+        }
+
+        if let Some(rest) = trimmed.strip_prefix('>') {
+            flush(&mut paragraph, &mut blocks, false);
+            quote.push(rest.trim_start());
+            continue;
+        }
+        flush(&mut quote, &mut blocks, true);
+
+        if is_rule(trimmed) {
+            flush(&mut paragraph, &mut blocks, false);
+            blocks.push(Block::Rule);
+            continue;
+        }
+
+        let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+            flush(&mut paragraph, &mut blocks, false);
+            blocks.push(Block::Heading(
+                hashes as u8,
+                parse_inlines(trimmed[hashes..].trim()),
+            ));
+            continue;
+        }
+
+        paragraph.push(trimmed);
+    }
+    flush(&mut paragraph, &mut blocks, false);
+    flush(&mut quote, &mut blocks, true);
+
+    blocks
+}
+
+fn is_rule(line: &str) -> bool {
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && (compact.chars().all(|c| c == '-')
+            || compact.chars().all(|c| c == '*')
+            || compact.chars().all(|c| c == '_'))
+}
+
+fn parse_image(text: &str) -> Option<(String, String)> {
+    let rest = text.strip_prefix("![")?;
+    let (alt, rest) = rest.split_once("](")?;
+    let src = rest.strip_suffix(')')?;
+    if src.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((alt.to_string(), src.to_string()))
+}
+
+/// 解析行内粗体（`**`）与斜体（`*`）
+pub fn parse_inlines(text: &str) -> Vec<Inline> {
+    let mut result = Vec::new();
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let Some(pos) = rest.find('*') else {
+            push_text(&mut result, rest);
+            break;
+        };
+        push_text(&mut result, &rest[..pos]);
+        let after = &rest[pos..];
+
+        if let Some(inner) = after.strip_prefix("**") {
+            if let Some(end) = inner.find("**").filter(|e| *e > 0) {
+                result.push(Inline::Bold(inner[..end].to_string()));
+                rest = &inner[end + 2..];
+                continue;
+            }
+            push_text(&mut result, "**");
+            rest = inner;
+            continue;
+        }
+
+        let inner = &after[1..];
+        if let Some(end) = inner.find('*').filter(|e| *e > 0) {
+            result.push(Inline::Italic(inner[..end].to_string()));
+            rest = &inner[end + 1..];
+        } else {
+            push_text(&mut result, "*");
+            rest = inner;
+        }
+    }
+
+    result
+}
+
+fn push_text(result: &mut Vec<Inline>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    if let Some(Inline::Text(last)) = result.last_mut() {
+        last.push_str(text);
+    } else {
+        result.push(Inline::Text(text.to_string()));
+    }
+}
+
+/// 转义 XML 特殊字符
+pub fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // XML 1.0 不允许的控制字符直接丢弃
+            c if (c as u32) < 0x20 && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn inlines_to_xhtml(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(t) => escape_xml(t).replace('\n', "<br/>"),
+            Inline::Bold(t) => format!("<strong>{}</strong>", escape_xml(t)),
+            Inline::Italic(t) => format!("<em>{}</em>", escape_xml(t)),
+        })
+        .collect()
+}
+
+/// 将块列表渲染为 XHTML 片段
+pub fn blocks_to_xhtml(blocks: &[Block]) -> String {
+    let mut out = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, inlines) => {
+                out.push_str(&format!(
+                    "<h{level}>{}</h{level}>\n",
+                    inlines_to_xhtml(inlines)
+                ));
+            }
+            Block::Paragraph(inlines) => {
+                out.push_str(&format!("<p>{}</p>\n", inlines_to_xhtml(inlines)));
+            }
+            Block::Quote(inlines) => {
+                out.push_str(&format!(
+                    "<blockquote><p>{}</p></blockquote>\n",
+                    inlines_to_xhtml(inlines)
+                ));
+            }
+            Block::Image { alt, src } => {
+                out.push_str(&format!(
+                    "<figure><img src=\"{}\" alt=\"{}\"/></figure>\n",
+                    escape_xml(src),
+                    escape_xml(alt)
+                ));
+            }
+            Block::Rule => out.push_str("<hr/>\n"),
+        }
+    }
+    out
+}
+
+/// 纯文本（去掉行内标记），用于目录等场景
+pub fn inlines_to_plain(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(t) | Inline::Bold(t) | Inline::Italic(t) => t.as_str(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_blocks() {
+        let md = "## 序\n\n第一段**重点**内容\n继续\n\n> 引用\n\n![插图](assets/a.png)\n\n---\n\n末段*轻声*";
+        let blocks = parse_blocks(md);
+        assert_eq!(blocks.len(), 6);
+        assert!(matches!(blocks[0], Block::Heading(2, _)));
+        assert_eq!(
+            blocks[1],
+            Block::Paragraph(vec![
+                Inline::Text("第一段".to_string()),
+                Inline::Bold("重点".to_string()),
+                Inline::Text("内容\n继续".to_string()),
+            ])
+        );
+        assert!(matches!(blocks[2], Block::Quote(_)));
+        assert_eq!(
+            blocks[3],
+            Block::Image {
+                alt: "插图".to_string(),
+                src: "assets/a.png".to_string()
+            }
+        );
+        assert_eq!(blocks[4], Block::Rule);
+        assert!(matches!(&blocks[5], Block::Paragraph(i) if i.len() == 2));
+    }
+
+    #[test]
+    fn test_unmatched_markers_are_literal() {
+        assert_eq!(
+            parse_inlines("5 * 3 = 15"),
+            vec![Inline::Text("5 * 3 = 15".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_xhtml_escapes() {
+        let html = blocks_to_xhtml(&parse_blocks("a < b & \"c\""));
+        assert_eq!(html, "<p>a &lt; b &amp; &quot;c&quot;</p>\n");
+    }
+}
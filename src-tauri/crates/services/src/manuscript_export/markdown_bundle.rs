@@ -0,0 +1,334 @@
+//! Markdown 包（zip）导出与导入
+//!
+//! 包结构：
+//! ```text
+//! manifest.json        元数据、章节顺序、资源清单
+//! index.md             目录
+//! chapters/001.md      每章一个文件，首行为 `# 章节标题`
+//! assets/...           封面与插图
+//! ```
+//!
+//! 导入时优先读取 `manifest.json`；缺失时按文件名顺序读取 `chapters/*.md`，
+//! 以便接受手工整理的 Markdown 目录。
+
+use super::types::*;
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read, Write};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// manifest 中的格式标识
+pub const BUNDLE_FORMAT: &str = "proxycast-markdown-bundle";
+/// 当前包版本
+pub const BUNDLE_VERSION: u32 = 1;
+
+const MANIFEST_PATH: &str = "manifest.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleManifest {
+    format: String,
+    version: u32,
+    metadata: ManuscriptMetadata,
+    chapters: Vec<BundleChapter>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cover: Option<ManuscriptAsset>,
+    #[serde(default)]
+    assets: Vec<ManuscriptAsset>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleChapter {
+    title: String,
+    file: String,
+}
+
+/// 生成 Markdown 包
+pub fn write_bundle(manuscript: &Manuscript) -> Result<Vec<u8>, ManuscriptError> {
+    if manuscript.chapters.is_empty() {
+        return Err(ManuscriptError::Empty);
+    }
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut chapters = Vec::with_capacity(manuscript.chapters.len());
+    let mut index = format!("# {}\n\n", manuscript.metadata.title);
+    for (i, chapter) in manuscript.chapters.iter().enumerate() {
+        let file = format!("chapters/{:03}.md", i + 1);
+        zip.start_file(file.as_str(), options)?;
+        zip.write_all(chapter_markdown(chapter).as_bytes())?;
+        index.push_str(&format!("{}. [{}]({})\n", i + 1, chapter.title, file));
+        chapters.push(BundleChapter {
+            title: chapter.title.clone(),
+            file,
+        });
+    }
+
+    zip.start_file("index.md", options)?;
+    zip.write_all(index.as_bytes())?;
+
+    for asset in manuscript.cover.iter().chain(manuscript.assets.iter()) {
+        zip.start_file(asset.path.as_str(), options)?;
+        zip.write_all(&asset.data)?;
+    }
+
+    let manifest = BundleManifest {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        metadata: manuscript.metadata.clone(),
+        chapters,
+        cover: manuscript.cover.clone(),
+        assets: manuscript.assets.clone(),
+    };
+    zip.start_file(MANIFEST_PATH, options)?;
+    zip.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+
+    Ok(zip.finish()?.into_inner())
+}
+
+fn chapter_markdown(chapter: &ManuscriptChapter) -> String {
+    let body = chapter.body.trim_end();
+    if body.is_empty() {
+        format!("# {}\n", chapter.title)
+    } else {
+        format!("# {}\n\n{}\n", chapter.title, body)
+    }
+}
+
+/// 从 Markdown 包还原稿件
+pub fn read_bundle(bytes: &[u8]) -> Result<Manuscript, ManuscriptError> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+
+    let manifest = match read_entry(&mut archive, MANIFEST_PATH)? {
+        Some(raw) => Some(serde_json::from_slice::<BundleManifest>(&raw)?),
+        None => None,
+    };
+
+    let Some(manifest) = manifest else {
+        return read_loose_bundle(&mut archive);
+    };
+
+    if manifest.format != BUNDLE_FORMAT {
+        return Err(ManuscriptError::InvalidBundle(format!(
+            "未知的格式标识: {}",
+            manifest.format
+        )));
+    }
+    if manifest.version > BUNDLE_VERSION {
+        return Err(ManuscriptError::InvalidBundle(format!(
+            "不支持的包版本: {}",
+            manifest.version
+        )));
+    }
+
+    let mut chapters = Vec::with_capacity(manifest.chapters.len());
+    for entry in &manifest.chapters {
+        let raw = read_entry(&mut archive, &entry.file)?.ok_or_else(|| {
+            ManuscriptError::InvalidBundle(format!("缺少章节文件: {}", entry.file))
+        })?;
+        let text = String::from_utf8_lossy(&raw);
+        let (_, body) = split_chapter(&text);
+        chapters.push(ManuscriptChapter {
+            title: entry.title.clone(),
+            body,
+        });
+    }
+
+    let cover = match manifest.cover {
+        Some(cover) => Some(load_asset(&mut archive, cover)?),
+        None => None,
+    };
+    let mut assets = Vec::with_capacity(manifest.assets.len());
+    for asset in manifest.assets {
+        assets.push(load_asset(&mut archive, asset)?);
+    }
+
+    Ok(Manuscript {
+        metadata: manifest.metadata,
+        chapters,
+        cover,
+        assets,
+    })
+}
+
+/// 没有 manifest 的包：按文件名排序读取章节，书名取 index.md 的一级标题
+fn read_loose_bundle(
+    archive: &mut ZipArchive<Cursor<&[u8]>>,
+) -> Result<Manuscript, ManuscriptError> {
+    let mut files: Vec<String> = archive
+        .file_names()
+        .filter(|name| name.starts_with("chapters/") && name.ends_with(".md"))
+        .map(str::to_string)
+        .collect();
+    if files.is_empty() {
+        return Err(ManuscriptError::InvalidBundle(
+            "缺少 manifest.json 与 chapters/ 目录".to_string(),
+        ));
+    }
+    files.sort();
+
+    let mut chapters = Vec::with_capacity(files.len());
+    for file in &files {
+        let raw = read_entry(archive, file)?.unwrap_or_default();
+        let text = String::from_utf8_lossy(&raw);
+        let (title, body) = split_chapter(&text);
+        let title = title.unwrap_or_else(|| {
+            file.trim_start_matches("chapters/")
+                .trim_end_matches(".md")
+                .to_string()
+        });
+        chapters.push(ManuscriptChapter { title, body });
+    }
+
+    let title = read_entry(archive, "index.md")?
+        .and_then(|raw| {
+            let text = String::from_utf8_lossy(&raw).into_owned();
+            split_chapter(&text).0
+        })
+        .unwrap_or_else(|| "未命名稿件".to_string());
+
+    Ok(Manuscript {
+        metadata: ManuscriptMetadata::new(title),
+        chapters,
+        cover: None,
+        assets: Vec::new(),
+    })
+}
+
+/// 拆出首行 `# 标题`，返回（标题，正文）
+fn split_chapter(text: &str) -> (Option<String>, String) {
+    let text = text.trim_start_matches('\u{feff}');
+    let trimmed = text.trim_start();
+    if let Some(rest) = trimmed.strip_prefix("# ") {
+        let (title, body) = rest.split_once('\n').unwrap_or((rest, ""));
+        return (
+            Some(title.trim().to_string()),
+            body.trim_matches('\n').trim_end().to_string(),
+        );
+    }
+    (None, text.trim_end().to_string())
+}
+
+fn load_asset(
+    archive: &mut ZipArchive<Cursor<&[u8]>>,
+    mut asset: ManuscriptAsset,
+) -> Result<ManuscriptAsset, ManuscriptError> {
+    if !asset.has_safe_path() {
+        return Err(ManuscriptError::InvalidBundle(format!(
+            "资源路径无效: {}",
+            asset.path
+        )));
+    }
+    asset.data = read_entry(archive, &asset.path)?
+        .ok_or_else(|| ManuscriptError::InvalidBundle(format!("缺少资源文件: {}", asset.path)))?;
+    Ok(asset)
+}
+
+fn read_entry(
+    archive: &mut ZipArchive<Cursor<&[u8]>>,
+    name: &str,
+) -> Result<Option<Vec<u8>>, ManuscriptError> {
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut data = Vec::with_capacity(file.size() as usize);
+    file.read_to_end(&mut data)?;
+    Ok(Some(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Manuscript {
+        let mut metadata = ManuscriptMetadata::new("往返测试");
+        metadata.authors = vec!["甲".to_string(), "乙".to_string()];
+        metadata.description = Some("简介".to_string());
+        metadata.subjects = vec!["科幻".to_string()];
+        Manuscript {
+            metadata,
+            chapters: vec![
+                ManuscriptChapter {
+                    title: "第一章".to_string(),
+                    body: "第一段\n\n![插图](assets/a.png)\n\n> 引用".to_string(),
+                },
+                ManuscriptChapter {
+                    title: "第二章".to_string(),
+                    body: String::new(),
+                },
+            ],
+            cover: Some(ManuscriptAsset {
+                path: "assets/cover.jpg".to_string(),
+                media_type: "image/jpeg".to_string(),
+                data: vec![0xFF, 0xD8, 0xFF],
+            }),
+            assets: vec![ManuscriptAsset {
+                path: "assets/a.png".to_string(),
+                media_type: "image/png".to_string(),
+                data: vec![1, 2, 3],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_bundle_roundtrip() {
+        let manuscript = sample();
+        let bytes = write_bundle(&manuscript).unwrap();
+        let restored = read_bundle(&bytes).unwrap();
+        assert_eq!(restored, manuscript);
+    }
+
+    #[test]
+    fn test_loose_bundle_without_manifest() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default();
+        zip.start_file("index.md", options).unwrap();
+        zip.write_all("# 手工稿件\n".as_bytes()).unwrap();
+        zip.start_file("chapters/02.md", options).unwrap();
+        zip.write_all("# 后章\n\n内容二\n".as_bytes()).unwrap();
+        zip.start_file("chapters/01.md", options).unwrap();
+        zip.write_all("无标题正文\n".as_bytes()).unwrap();
+        let bytes = zip.finish().unwrap().into_inner();
+
+        let manuscript = read_bundle(&bytes).unwrap();
+        assert_eq!(manuscript.metadata.title, "手工稿件");
+        assert_eq!(manuscript.chapters[0].title, "01");
+        assert_eq!(manuscript.chapters[0].body, "无标题正文");
+        assert_eq!(manuscript.chapters[1].title, "后章");
+        assert_eq!(manuscript.chapters[1].body, "内容二");
+    }
+
+    #[test]
+    fn test_rejects_newer_version() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file(MANIFEST_PATH, FileOptions::default())
+            .unwrap();
+        let manifest = serde_json::json!({
+            "format": BUNDLE_FORMAT,
+            "version": BUNDLE_VERSION + 1,
+            "metadata": ManuscriptMetadata::new("x"),
+            "chapters": [],
+        });
+        zip.write_all(manifest.to_string().as_bytes()).unwrap();
+        let bytes = zip.finish().unwrap().into_inner();
+
+        assert!(matches!(
+            read_bundle(&bytes),
+            Err(ManuscriptError::InvalidBundle(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_unsafe_asset_path() {
+        let mut manuscript = sample();
+        manuscript.assets[0].path = "../a.png".to_string();
+        let bytes = write_bundle(&manuscript).unwrap();
+
+        assert!(matches!(
+            read_bundle(&bytes),
+            Err(ManuscriptError::InvalidBundle(_))
+        ));
+    }
+}
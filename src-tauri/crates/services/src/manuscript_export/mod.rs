@@ -0,0 +1,300 @@
+//! 长文稿件导出模块
+//!
+//! 将项目内容（小说章节、文稿等）导出为 EPUB 3、DOCX 或 Markdown 包，
+//! 并支持把 Markdown 包重新导入为稿件。
+//!
+//! ## 模块结构
+//! - `types` - 稿件、元数据、导出选项与错误类型
+//! - `markdown` - 正文 Markdown 子集解析
+//! - `epub` - EPUB 3 写出
+//! - `docx` - DOCX 写出
+//! - `markdown_bundle` - Markdown 包导出与导入
+//!
+//! 封面取自项目素材中的图片，元数据取自项目与默认人设。
+
+mod docx;
+mod epub;
+pub mod markdown;
+mod markdown_bundle;
+mod types;
+
+pub use markdown_bundle::{BUNDLE_FORMAT, BUNDLE_VERSION};
+pub use types::*;
+
+use proxycast_core::content::Content;
+use proxycast_core::models::project_model::Material;
+use proxycast_core::models::Persona;
+use std::path::Path;
+
+/// 导出稿件为指定格式的字节内容
+pub fn export_manuscript(
+    manuscript: &Manuscript,
+    format: ManuscriptFormat,
+    options: &ManuscriptExportOptions,
+) -> Result<Vec<u8>, ManuscriptError> {
+    match format {
+        ManuscriptFormat::Epub => epub::write_epub(manuscript, options),
+        ManuscriptFormat::Docx => docx::write_docx(manuscript, options),
+        ManuscriptFormat::MarkdownBundle => markdown_bundle::write_bundle(manuscript),
+    }
+}
+
+/// 导出稿件并写入文件（自动创建父目录），返回写入的字节数
+pub fn export_to_file(
+    manuscript: &Manuscript,
+    format: ManuscriptFormat,
+    options: &ManuscriptExportOptions,
+    path: &Path,
+) -> Result<usize, ManuscriptError> {
+    let bytes = export_manuscript(manuscript, format, options)?;
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, &bytes)?;
+    Ok(bytes.len())
+}
+
+/// 从 Markdown 包导入稿件
+pub fn import_markdown_bundle(bytes: &[u8]) -> Result<Manuscript, ManuscriptError> {
+    markdown_bundle::read_bundle(bytes)
+}
+
+/// 从 Markdown 包文件导入稿件
+pub fn import_markdown_bundle_file(path: &Path) -> Result<Manuscript, ManuscriptError> {
+    import_markdown_bundle(&std::fs::read(path)?)
+}
+
+/// 把稿件的封面与资源按相对路径写入目录
+///
+/// 用于保存导入的 Markdown 包资源；之后可用 [`load_assets`] 按同样的路径读回。
+pub fn save_assets(manuscript: &Manuscript, dir: &Path) -> Result<(), ManuscriptError> {
+    for asset in manuscript.cover.iter().chain(manuscript.assets.iter()) {
+        if !asset.has_safe_path() {
+            return Err(ManuscriptError::InvalidBundle(format!(
+                "资源路径无效: {}",
+                asset.path
+            )));
+        }
+        let target = dir.join(&asset.path);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(target, &asset.data)?;
+    }
+    Ok(())
+}
+
+/// 从目录读回资源数据（`data` 为空的资源描述按相对路径补全）
+///
+/// 路径不安全或文件读取失败的资源会被跳过。
+pub fn load_assets(dir: &Path, assets: Vec<ManuscriptAsset>) -> Vec<ManuscriptAsset> {
+    assets
+        .into_iter()
+        .filter_map(|mut asset| {
+            if !asset.has_safe_path() {
+                tracing::warn!("[Manuscript] 跳过路径无效的资源: {}", asset.path);
+                return None;
+            }
+            match std::fs::read(dir.join(&asset.path)) {
+                Ok(data) => {
+                    asset.data = data;
+                    Some(asset)
+                }
+                Err(e) => {
+                    tracing::warn!("[Manuscript] 读取资源失败: {} - {}", asset.path, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+impl Manuscript {
+    /// 由项目内容列表构建稿件（按 `order` 排序）
+    pub fn from_contents(metadata: ManuscriptMetadata, contents: &[Content]) -> Self {
+        let mut sorted: Vec<&Content> = contents.iter().collect();
+        sorted.sort_by_key(|c| c.order);
+        Self {
+            metadata,
+            chapters: sorted
+                .into_iter()
+                .map(|c| ManuscriptChapter {
+                    title: c.title.clone(),
+                    body: c.body.clone(),
+                })
+                .collect(),
+            cover: None,
+            assets: Vec::new(),
+        }
+    }
+}
+
+impl ManuscriptMetadata {
+    /// 用人设补充作者与简介（已有值不覆盖）
+    pub fn apply_persona(&mut self, persona: &Persona) {
+        if self.authors.is_empty() && !persona.name.trim().is_empty() {
+            self.authors.push(persona.name.trim().to_string());
+        }
+        if self.description.is_none() {
+            self.description = persona
+                .description
+                .as_deref()
+                .map(str::trim)
+                .filter(|d| !d.is_empty())
+                .map(str::to_string);
+        }
+    }
+}
+
+/// 从项目素材中挑选封面
+///
+/// 只考虑带本地文件的图片素材；优先标签为 `cover`/`封面` 或名称含“封面”/“cover”的，
+/// 否则取最早上传的一张。文件读取失败时跳过。
+pub fn cover_from_materials(materials: &[Material]) -> Option<ManuscriptAsset> {
+    let mut images: Vec<&Material> = materials
+        .iter()
+        .filter(|m| m.material_type == "image" && m.file_path.is_some())
+        .collect();
+    images.sort_by_key(|m| (!is_cover_material(m), m.created_at));
+
+    images.into_iter().find_map(|material| {
+        let path = material.file_path.as_deref()?;
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::warn!("[Manuscript] 读取封面素材失败: {} - {}", path, e);
+                return None;
+            }
+        };
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("png")
+            .to_ascii_lowercase();
+        let media_type = material
+            .mime_type
+            .clone()
+            .filter(|m| m.starts_with("image/"))
+            .unwrap_or_else(|| media_type_for_extension(&extension).to_string());
+        Some(ManuscriptAsset {
+            path: format!("assets/cover.{extension}"),
+            media_type,
+            data,
+        })
+    })
+}
+
+fn is_cover_material(material: &Material) -> bool {
+    let name = material.name.to_lowercase();
+    material
+        .tags
+        .iter()
+        .any(|t| t.eq_ignore_ascii_case("cover") || t == "封面")
+        || name.contains("cover")
+        || name.contains("封面")
+}
+
+/// 根据扩展名推断图片 MIME 类型
+pub fn media_type_for_extension(extension: &str) -> &'static str {
+    match extension.to_ascii_lowercase().as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        _ => "image/png",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn material(name: &str, tags: &[&str], path: &str, created_at: i64) -> Material {
+        Material {
+            id: name.to_string(),
+            project_id: "p".to_string(),
+            name: name.to_string(),
+            material_type: "image".to_string(),
+            file_path: Some(path.to_string()),
+            file_size: None,
+            mime_type: None,
+            content: None,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            description: None,
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_cover_prefers_tagged_material() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first.png");
+        let cover = dir.path().join("art.JPG");
+        std::fs::write(&first, b"first").unwrap();
+        std::fs::write(&cover, b"cover").unwrap();
+
+        let materials = vec![
+            material("插图", &[], first.to_str().unwrap(), 1),
+            material("主视觉", &["封面"], cover.to_str().unwrap(), 2),
+            material("丢失", &["cover"], "/nonexistent/cover.png", 0),
+        ];
+        let asset = cover_from_materials(&materials).unwrap();
+        assert_eq!(asset.path, "assets/cover.jpg");
+        assert_eq!(asset.media_type, "image/jpeg");
+        assert_eq!(asset.data, b"cover");
+    }
+
+    #[test]
+    fn test_save_and_load_assets() {
+        let dir = tempfile::tempdir().unwrap();
+        let asset = |path: &str, data: &[u8]| ManuscriptAsset {
+            path: path.to_string(),
+            media_type: "image/png".to_string(),
+            data: data.to_vec(),
+        };
+        let mut manuscript = Manuscript::from_contents(ManuscriptMetadata::new("书"), &[]);
+        manuscript.cover = Some(asset("assets/cover.png", b"cover"));
+        manuscript.assets = vec![asset("assets/img/a.png", b"a")];
+        save_assets(&manuscript, dir.path()).unwrap();
+
+        let described = vec![
+            asset("assets/cover.png", b""),
+            asset("assets/img/a.png", b""),
+            asset("assets/missing.png", b""),
+            asset("../escape.png", b""),
+        ];
+        let loaded = load_assets(dir.path(), described);
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].data, b"cover");
+        assert_eq!(loaded[1].data, b"a");
+
+        manuscript.assets = vec![asset("/etc/passwd", b"x")];
+        assert!(matches!(
+            save_assets(&manuscript, dir.path()),
+            Err(ManuscriptError::InvalidBundle(_))
+        ));
+    }
+
+    #[test]
+    fn test_export_all_formats() {
+        let manuscript = Manuscript {
+            metadata: ManuscriptMetadata::new("书"),
+            chapters: vec![ManuscriptChapter {
+                title: "一".to_string(),
+                body: "正文".to_string(),
+            }],
+            cover: None,
+            assets: vec![],
+        };
+        let options = ManuscriptExportOptions::default();
+        for format in [
+            ManuscriptFormat::Epub,
+            ManuscriptFormat::Docx,
+            ManuscriptFormat::MarkdownBundle,
+        ] {
+            let bytes = export_manuscript(&manuscript, format, &options).unwrap();
+            assert!(bytes.starts_with(b"PK"));
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! 稿件导出类型定义
+
+use serde::{Deserialize, Serialize};
+
+/// 稿件元数据
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ManuscriptMetadata {
+    /// 书名
+    pub title: String,
+    /// 副标题
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+    /// 作者列表
+    #[serde(default)]
+    pub authors: Vec<String>,
+    /// 语言（BCP 47，如 `zh-CN`）
+    pub language: String,
+    /// 简介
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 出版方
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publisher: Option<String>,
+    /// 主题/题材标签
+    #[serde(default)]
+    pub subjects: Vec<String>,
+    /// 唯一标识（UUID）
+    pub identifier: String,
+    /// 修改时间（Unix 毫秒）
+    pub modified_at: i64,
+}
+
+impl ManuscriptMetadata {
+    /// 以书名创建默认元数据
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            subtitle: None,
+            authors: Vec::new(),
+            language: "zh-CN".to_string(),
+            description: None,
+            publisher: None,
+            subjects: Vec::new(),
+            identifier: uuid::Uuid::new_v4().to_string(),
+            modified_at: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+/// 稿件章节（正文为 Markdown）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ManuscriptChapter {
+    pub title: String,
+    pub body: String,
+}
+
+/// 稿件资源（封面、插图等）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ManuscriptAsset {
+    /// 相对路径，如 `assets/cover.png`；正文中的图片引用使用同一路径
+    pub path: String,
+    /// MIME 类型
+    pub media_type: String,
+    #[serde(skip)]
+    pub data: Vec<u8>,
+}
+
+impl ManuscriptAsset {
+    /// 文件名（路径最后一段）
+    pub fn file_name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    /// 路径是否为安全的相对路径（非空、非绝对路径、不含 `..`）
+    ///
+    /// 导入的资源会按该路径写入磁盘，不安全的路径可能写到目标目录之外。
+    pub fn has_safe_path(&self) -> bool {
+        !self.path.is_empty()
+            && !self.path.contains('\\')
+            && std::path::Path::new(&self.path)
+                .components()
+                .all(|c| matches!(c, std::path::Component::Normal(_)))
+    }
+}
+
+/// 待导出的完整稿件
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manuscript {
+    pub metadata: ManuscriptMetadata,
+    /// 已排序的章节
+    pub chapters: Vec<ManuscriptChapter>,
+    pub cover: Option<ManuscriptAsset>,
+    pub assets: Vec<ManuscriptAsset>,
+}
+
+impl Manuscript {
+    /// 按路径查找资源（包括封面）
+    pub fn find_asset(&self, path: &str) -> Option<&ManuscriptAsset> {
+        let path = path.trim_start_matches("./");
+        self.cover
+            .iter()
+            .chain(self.assets.iter())
+            .find(|a| a.path == path)
+    }
+}
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ManuscriptFormat {
+    /// EPUB 3
+    Epub,
+    /// Word 文档
+    Docx,
+    /// Markdown + 资源的 zip 包
+    MarkdownBundle,
+}
+
+impl ManuscriptFormat {
+    /// 推荐的文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ManuscriptFormat::Epub => "epub",
+            ManuscriptFormat::Docx => "docx",
+            ManuscriptFormat::MarkdownBundle => "zip",
+        }
+    }
+}
+
+/// 导出选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManuscriptExportOptions {
+    /// 是否生成扉页
+    #[serde(default = "default_true")]
+    pub include_title_page: bool,
+    /// 是否生成目录
+    #[serde(default = "default_true")]
+    pub include_toc: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for ManuscriptExportOptions {
+    fn default() -> Self {
+        Self {
+            include_title_page: true,
+            include_toc: true,
+        }
+    }
+}
+
+/// 稿件导出错误
+#[derive(Debug, thiserror::Error)]
+pub enum ManuscriptError {
+    #[error("IO 错误: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("ZIP 错误: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("JSON 错误: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("无效的 Markdown 包: {0}")]
+    InvalidBundle(String),
+    #[error("稿件没有任何章节")]
+    Empty,
+}
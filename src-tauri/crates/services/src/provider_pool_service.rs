@@ -5,13 +5,19 @@
 #![allow(dead_code)]
 
 use crate::api_key_provider_service::ApiKeyProviderService;
+use crate::concurrency_limiter::{
+    global_concurrency_limiter, ConcurrencyError, ConcurrencyLimiter, ConcurrencyPermit,
+    QueueClient,
+};
 use crate::provider_type_mapping::{
     api_provider_type_to_pool_type, is_custom_provider_id, parse_pool_provider_type,
     resolve_pool_provider_type_or_default,
 };
 use chrono::Utc;
+use proxycast_core::credential::{CircuitHealth, CircuitHealthSource};
 use proxycast_core::database::dao::provider_pool::ProviderPoolDao;
-use proxycast_core::database::DbConnection;
+use proxycast_core::database::pool::spawn_write;
+use proxycast_core::database::{DbConnection, DbPool};
 use proxycast_core::models::client_type::ClientType;
 use proxycast_core::models::provider_pool_model::{
     get_default_check_model, get_oauth_creds_path, CredentialData, CredentialDisplay,
@@ -22,6 +28,7 @@ use proxycast_core::models::route_model::RouteInfo;
 use proxycast_providers::providers::antigravity::TokenRefreshError;
 use proxycast_providers::providers::kiro::KiroProvider;
 use reqwest::Client;
+use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 
 /// 扩展 ProviderCredential 的客户端兼容性检查
@@ -42,6 +49,7 @@ impl ProviderCredentialClientCompat for ProviderCredential {
 }
 use std::collections::HashMap;
 use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
 use std::time::Duration;
 
 /// 凭证健康信息
@@ -88,6 +96,10 @@ pub struct ProviderPoolService {
     max_error_count: u32,
     /// 健康检查超时时间
     health_check_timeout: Duration,
+    /// 并发限制器（进程内共享）
+    limiter: ConcurrencyLimiter,
+    /// 熔断状态来源（熔断打开的凭证不参与选择）
+    circuit: std::sync::RwLock<Option<Arc<dyn CircuitHealthSource>>>,
 }
 
 impl Default for ProviderPoolService {
@@ -106,9 +118,34 @@ impl ProviderPoolService {
             round_robin_index: std::sync::RwLock::new(HashMap::new()),
             max_error_count: 3,
             health_check_timeout: Duration::from_secs(30),
+            limiter: global_concurrency_limiter(),
+            circuit: std::sync::RwLock::new(None),
+        }
+    }
+
+    /// 设置熔断状态来源，选择凭证时跳过熔断打开的凭证
+    pub fn set_circuit_source(&self, source: Arc<dyn CircuitHealthSource>) {
+        if let Ok(mut circuit) = self.circuit.write() {
+            *circuit = Some(source);
         }
     }
 
+    /// 凭证熔断打开时返回距离半开探测的剩余时间（毫秒）
+    ///
+    /// 熔断时长已到的凭证视为可用，由下一次请求触发半开探测。
+    fn circuit_retry_after_ms(&self, uuid: &str) -> Option<u64> {
+        let circuit = self.circuit.read().ok()?;
+        match circuit.as_ref()?.credential_circuit(uuid)? {
+            CircuitHealth::Open { retry_after_ms } if retry_after_ms > 0 => Some(retry_after_ms),
+            _ => None,
+        }
+    }
+
+    /// 获取并发限制器
+    pub fn concurrency_limiter(&self) -> &ConcurrencyLimiter {
+        &self.limiter
+    }
+
     /// 获取所有凭证概览
     pub fn get_overview(&self, db: &DbConnection) -> Result<Vec<ProviderPoolOverview>, String> {
         let conn = proxycast_core::database::lock_db(db)?;
@@ -258,46 +295,101 @@ impl ProviderPoolService {
         model: Option<&str>,
         client_type: Option<&proxycast_core::models::client_type::ClientType>,
     ) -> Result<Option<ProviderCredential>, String> {
+        let Some(pt) = Self::resolve_selectable_type(provider_type) else {
+            return Ok(None);
+        };
+        let credentials = {
+            let conn = proxycast_core::database::lock_db(db)?;
+            Self::load_candidates(&conn, &pt)?
+        };
+        Ok(self.choose_credential(credentials, model, client_type))
+    }
+
+    /// 选择凭证并检查客户端兼容性（连接池版本）
+    ///
+    /// 候选凭证在只读连接上查询，并卸载到阻塞线程池执行，
+    /// 不与写连接抢锁，也不阻塞 tokio 工作线程。
+    pub async fn select_credential_pooled(
+        &self,
+        pool: &DbPool,
+        provider_type: &str,
+        model: Option<&str>,
+        client_type: Option<&proxycast_core::models::client_type::ClientType>,
+    ) -> Result<Option<ProviderCredential>, String> {
+        let Some(pt) = Self::resolve_selectable_type(provider_type) else {
+            return Ok(None);
+        };
+        let credentials = pool
+            .read_async(move |conn| Self::load_candidates(conn, &pt))
+            .await?;
+        Ok(self.choose_credential(credentials, model, client_type))
+    }
+
+    /// 选择另一个可用凭证（排除指定凭证，用于对冲请求和流式中途故障转移）
+    pub async fn select_alternate_credential_pooled(
+        &self,
+        pool: &DbPool,
+        provider_type: &str,
+        model: Option<&str>,
+        client_type: Option<&proxycast_core::models::client_type::ClientType>,
+        exclude_uuids: &[&str],
+    ) -> Result<Option<ProviderCredential>, String> {
+        let Some(pt) = Self::resolve_selectable_type(provider_type) else {
+            return Ok(None);
+        };
+        let mut credentials = pool
+            .read_async(move |conn| Self::load_candidates(conn, &pt))
+            .await?;
+        credentials.retain(|c| !exclude_uuids.contains(&c.uuid.as_str()));
+        Ok(self.choose_credential(credentials, model, client_type))
+    }
+
+    /// 解析可从凭证池选择的 Provider 类型
+    ///
+    /// custom provider 与未知类型返回 None（不是错误），
+    /// 这样可以让 select_credential_with_fallback 继续尝试智能降级
+    fn resolve_selectable_type(provider_type: &str) -> Option<PoolProviderType> {
         if is_custom_provider_id(provider_type) {
             eprintln!("[SELECT_CREDENTIAL] custom provider '{provider_type}' 使用智能降级路径");
-            return Ok(None);
+            return None;
         }
 
-        // 对于未知的 provider_type，直接返回 None（不是错误）
-        // 这样可以让 select_credential_with_fallback 继续尝试智能降级
-        let pt: PoolProviderType = match parse_pool_provider_type(provider_type) {
-            Ok(pt) => pt,
+        match parse_pool_provider_type(provider_type) {
+            Ok(pt) => Some(pt),
             Err(_) => {
                 eprintln!(
                     "[SELECT_CREDENTIAL] 未知的 provider_type '{provider_type}', 返回 None 以便智能降级"
                 );
-                return Ok(None);
+                None
             }
-        };
-        let conn = proxycast_core::database::lock_db(db)?;
+        }
+    }
 
+    /// 加载候选凭证
+    fn load_candidates(
+        conn: &Connection,
+        pt: &PoolProviderType,
+    ) -> Result<Vec<ProviderCredential>, String> {
         // 获取凭证，对于 AI Provider 类型，也查找 Assistant 类型的凭证
-        let mut credentials =
-            ProviderPoolDao::get_by_type(&conn, &pt).map_err(|e| e.to_string())?;
+        let mut credentials = ProviderPoolDao::get_by_type(conn, pt).map_err(|e| e.to_string())?;
         eprintln!(
-            "[SELECT_CREDENTIAL] provider_type={}, pt={:?}, initial_count={}",
-            provider_type,
+            "[SELECT_CREDENTIAL] pt={:?}, initial_count={}",
             pt,
             credentials.len()
         );
 
         // AI Provider 和 Assistant 共享凭证（都使用 AI Provider API）
-        if pt == PoolProviderType::Anthropic {
-            let assistant_creds = ProviderPoolDao::get_by_type(&conn, &PoolProviderType::Claude)
+        if *pt == PoolProviderType::Anthropic {
+            let assistant_creds = ProviderPoolDao::get_by_type(conn, &PoolProviderType::Claude)
                 .map_err(|e| e.to_string())?;
             eprintln!(
                 "[SELECT_CREDENTIAL] AI Provider: adding {} Assistant credentials",
                 assistant_creds.len()
             );
             credentials.extend(assistant_creds);
-        } else if pt == PoolProviderType::Claude {
+        } else if *pt == PoolProviderType::Claude {
             let ai_provider_creds =
-                ProviderPoolDao::get_by_type(&conn, &PoolProviderType::Anthropic)
+                ProviderPoolDao::get_by_type(conn, &PoolProviderType::Anthropic)
                     .map_err(|e| e.to_string())?;
             eprintln!(
                 "[SELECT_CREDENTIAL] Assistant: adding {} AI Provider credentials",
@@ -306,8 +398,16 @@ impl ProviderPoolService {
             credentials.extend(ai_provider_creds);
         }
 
-        drop(conn);
+        Ok(credentials)
+    }
 
+    /// 从候选凭证中过滤可用、支持模型且兼容客户端的凭证，并按权重选出最优凭证
+    fn choose_credential(
+        &self,
+        credentials: Vec<ProviderCredential>,
+        model: Option<&str>,
+        client_type: Option<&proxycast_core::models::client_type::ClientType>,
+    ) -> Option<ProviderCredential> {
         eprintln!(
             "[SELECT_CREDENTIAL] total_credentials={}, model={:?}",
             credentials.len(),
@@ -347,6 +447,19 @@ impl ProviderPoolService {
             available.len()
         );
 
+        // 跳过熔断打开的凭证，让请求转移到池中其他凭证
+        available.retain(|c| match self.circuit_retry_after_ms(&c.uuid) {
+            Some(retry_after_ms) => {
+                eprintln!(
+                    "[SELECT_CREDENTIAL] credential {} 熔断中，{}ms 后半开探测",
+                    c.name.as_deref().unwrap_or("unnamed"),
+                    retry_after_ms
+                );
+                false
+            }
+            None => true,
+        });
+
         // 如果指定了模型，进一步过滤支持该模型的凭证
         if let Some(m) = model {
             available.retain(|c| {
@@ -380,18 +493,33 @@ impl ProviderPoolService {
         );
 
         if available.is_empty() {
-            return Ok(None);
+            return None;
+        }
+
+        // 优先选择还有空闲并发槽位的凭证（都已满时保留全部候选，由调用方排队）
+        if available.len() > 1 {
+            let with_capacity: Vec<_> = available
+                .iter()
+                .filter(|c| {
+                    self.limiter
+                        .has_capacity(&c.uuid, &c.provider_type.to_string())
+                })
+                .cloned()
+                .collect();
+            if !with_capacity.is_empty() {
+                available = with_capacity;
+            }
         }
 
         // 如果只有一个可用凭证，直接返回
         if available.len() == 1 {
-            return Ok(Some(available.into_iter().next().unwrap()));
+            return available.into_iter().next();
         }
 
         // 智能选择：基于权重分数选择最优凭证
         let selected = self.select_best_credential_by_weight(&available);
 
-        Ok(Some(selected))
+        Some(selected)
     }
 
     /// 带智能降级的凭证选择
@@ -434,7 +562,105 @@ impl ProviderPoolService {
             return Ok(Some(cred));
         }
         eprintln!("[select_credential_with_fallback] Provider Pool 未找到凭证，尝试智能降级");
+        self.fallback_to_api_key_provider(
+            db,
+            api_key_service,
+            provider_type,
+            provider_id_hint,
+            client_type,
+        )
+        .await
+    }
+
+    /// 带智能降级的凭证选择，并获取该凭证的并发槽位
+    ///
+    /// 凭证并发已满时按 `queue_client` 的优先级和权重排队等待；
+    /// 排队超时或队列已满时返回错误。
+    #[allow(clippy::too_many_arguments)]
+    pub async fn acquire_credential_with_fallback(
+        &self,
+        db: &DbConnection,
+        api_key_service: &ApiKeyProviderService,
+        provider_type: &str,
+        model: Option<&str>,
+        provider_id_hint: Option<&str>,
+        client_type: Option<&proxycast_core::models::client_type::ClientType>,
+        queue_client: &QueueClient,
+    ) -> Result<Option<(ProviderCredential, ConcurrencyPermit)>, String> {
+        let Some(credential) = self
+            .select_credential_with_fallback(
+                db,
+                api_key_service,
+                provider_type,
+                model,
+                provider_id_hint,
+                client_type,
+            )
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let permit = self
+            .acquire_slot(&credential, queue_client)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(Some((credential, permit)))
+    }
+
+    /// 获取凭证的并发槽位（并发已满时排队等待）
+    pub async fn acquire_slot(
+        &self,
+        credential: &ProviderCredential,
+        queue_client: &QueueClient,
+    ) -> Result<ConcurrencyPermit, ConcurrencyError> {
+        self.limiter
+            .acquire(
+                &credential.uuid,
+                &credential.provider_type.to_string(),
+                queue_client,
+            )
+            .await
+    }
+
+    /// 带智能降级的凭证选择（连接池版本）
+    ///
+    /// Provider Pool 查询走只读连接；降级到 API Key Provider 的逻辑与
+    /// [`Self::select_credential_with_fallback`] 相同。
+    pub async fn select_credential_with_fallback_pooled(
+        &self,
+        pool: &DbPool,
+        api_key_service: &ApiKeyProviderService,
+        provider_type: &str,
+        model: Option<&str>,
+        provider_id_hint: Option<&str>,
+        client_type: Option<&proxycast_core::models::client_type::ClientType>,
+    ) -> Result<Option<ProviderCredential>, String> {
+        if let Some(cred) = self
+            .select_credential_pooled(pool, provider_type, model, client_type)
+            .await?
+        {
+            return Ok(Some(cred));
+        }
+        self.fallback_to_api_key_provider(
+            pool.writer(),
+            api_key_service,
+            provider_type,
+            provider_id_hint,
+            client_type,
+        )
+        .await
+    }
 
+    /// 智能降级到 API Key Provider
+    async fn fallback_to_api_key_provider(
+        &self,
+        db: &DbConnection,
+        api_key_service: &ApiKeyProviderService,
+        provider_type: &str,
+        provider_id_hint: Option<&str>,
+        client_type: Option<&proxycast_core::models::client_type::ClientType>,
+    ) -> Result<Option<ProviderCredential>, String> {
         // Step 2: 智能降级到 API Key Provider
         let mut pt = resolve_pool_provider_type_or_default(provider_type);
         let mut resolved_provider_id_hint = provider_id_hint;
@@ -597,12 +823,7 @@ impl ProviderPoolService {
     /// 记录凭证使用
     pub fn record_usage(&self, db: &DbConnection, uuid: &str) -> Result<(), String> {
         let conn = proxycast_core::database::lock_db(db)?;
-        let cred = ProviderPoolDao::get_by_uuid(&conn, uuid)
-            .map_err(|e| e.to_string())?
-            .ok_or_else(|| format!("Credential not found: {uuid}"))?;
-
-        ProviderPoolDao::update_usage(&conn, uuid, cred.usage_count + 1, Utc::now())
-            .map_err(|e| e.to_string())
+        Self::record_usage_on(&conn, uuid)
     }
 
     /// 标记凭证为健康
@@ -613,8 +834,75 @@ impl ProviderPoolService {
         check_model: Option<&str>,
     ) -> Result<(), String> {
         let conn = proxycast_core::database::lock_db(db)?;
+        Self::mark_healthy_on(&conn, uuid, check_model)
+    }
+
+    /// 标记凭证为不健康
+    pub fn mark_unhealthy(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        error_message: Option<&str>,
+    ) -> Result<(), String> {
+        let conn = proxycast_core::database::lock_db(db)?;
+        Self::mark_unhealthy_on(&conn, uuid, error_message, self.max_error_count)
+    }
+
+    /// 后台记录凭证使用（请求热路径使用，不阻塞 tokio 工作线程）
+    ///
+    /// 以下 `*_in_background` 写入经同一个写入队列按提交顺序执行。
+    pub fn record_usage_in_background(&self, db: &DbConnection, uuid: &str) {
+        let uuid = uuid.to_string();
+        spawn_write(db, "record_usage", move |conn| {
+            Self::record_usage_on(conn, &uuid)
+        });
+    }
+
+    /// 后台标记凭证为健康
+    pub fn mark_healthy_in_background(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        check_model: Option<&str>,
+    ) {
+        let uuid = uuid.to_string();
+        let check_model = check_model.map(str::to_string);
+        spawn_write(db, "mark_healthy", move |conn| {
+            Self::mark_healthy_on(conn, &uuid, check_model.as_deref())
+        });
+    }
+
+    /// 后台标记凭证为不健康
+    pub fn mark_unhealthy_in_background(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        error_message: Option<&str>,
+    ) {
+        let uuid = uuid.to_string();
+        let error_message = error_message.map(str::to_string);
+        let max_error_count = self.max_error_count;
+        spawn_write(db, "mark_unhealthy", move |conn| {
+            Self::mark_unhealthy_on(conn, &uuid, error_message.as_deref(), max_error_count)
+        });
+    }
+
+    fn record_usage_on(conn: &Connection, uuid: &str) -> Result<(), String> {
+        let cred = ProviderPoolDao::get_by_uuid(conn, uuid)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Credential not found: {uuid}"))?;
+
+        ProviderPoolDao::update_usage(conn, uuid, cred.usage_count + 1, Utc::now())
+            .map_err(|e| e.to_string())
+    }
+
+    fn mark_healthy_on(
+        conn: &Connection,
+        uuid: &str,
+        check_model: Option<&str>,
+    ) -> Result<(), String> {
         ProviderPoolDao::update_health_status(
-            &conn,
+            conn,
             uuid,
             true,
             0,
@@ -626,23 +914,21 @@ impl ProviderPoolService {
         .map_err(|e| e.to_string())
     }
 
-    /// 标记凭证为不健康
-    pub fn mark_unhealthy(
-        &self,
-        db: &DbConnection,
+    fn mark_unhealthy_on(
+        conn: &Connection,
         uuid: &str,
         error_message: Option<&str>,
+        max_error_count: u32,
     ) -> Result<(), String> {
-        let conn = proxycast_core::database::lock_db(db)?;
-        let cred = ProviderPoolDao::get_by_uuid(&conn, uuid)
+        let cred = ProviderPoolDao::get_by_uuid(conn, uuid)
             .map_err(|e| e.to_string())?
             .ok_or_else(|| format!("Credential not found: {uuid}"))?;
 
         let new_error_count = cred.error_count + 1;
-        let is_healthy = new_error_count < self.max_error_count;
+        let is_healthy = new_error_count < max_error_count;
 
         ProviderPoolDao::update_health_status(
-            &conn,
+            conn,
             uuid,
             is_healthy,
             new_error_count,
@@ -681,20 +967,7 @@ impl ProviderPoolService {
         let conn = proxycast_core::database::lock_db(db)?;
         let cred = ProviderPoolDao::get_by_uuid(&conn, uuid).map_err(|e| e.to_string())?;
 
-        Ok(cred.map(|c| CredentialHealthInfo {
-            uuid: c.uuid.clone(),
-            name: c.name.clone(),
-            provider_type: c.provider_type.to_string(),
-            is_healthy: c.is_healthy,
-            last_error: c.last_error_message.clone(),
-            last_error_time: c.last_error_time.map(|t| t.to_rfc3339()),
-            failure_count: c.error_count,
-            requires_reauth: c
-                .last_error_message
-                .as_ref()
-                .map(|e| e.contains("invalid_grant") || e.contains("重新授权"))
-                .unwrap_or(false),
-        }))
+        Ok(cred.map(|c| self.health_info(&c)))
     }
 
     /// 获取所有凭证的健康状态
@@ -706,23 +979,33 @@ impl ProviderPoolService {
         let conn = proxycast_core::database::lock_db(db)?;
         let credentials = ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?;
 
-        Ok(credentials
-            .into_iter()
-            .map(|c| CredentialHealthInfo {
-                uuid: c.uuid.clone(),
-                name: c.name.clone(),
-                provider_type: c.provider_type.to_string(),
-                is_healthy: c.is_healthy,
-                last_error: c.last_error_message.clone(),
-                last_error_time: c.last_error_time.map(|t| t.to_rfc3339()),
-                failure_count: c.error_count,
-                requires_reauth: c
-                    .last_error_message
-                    .as_ref()
-                    .map(|e| e.contains("invalid_grant") || e.contains("重新授权"))
-                    .unwrap_or(false),
-            })
-            .collect())
+        Ok(credentials.iter().map(|c| self.health_info(c)).collect())
+    }
+
+    /// 构建凭证健康信息（熔断打开的凭证视为不健康）
+    fn health_info(&self, c: &ProviderCredential) -> CredentialHealthInfo {
+        let requires_reauth = c
+            .last_error_message
+            .as_ref()
+            .map(|e| e.contains("invalid_grant") || e.contains("重新授权"))
+            .unwrap_or(false);
+        let (is_healthy, last_error) = match self.circuit_retry_after_ms(&c.uuid) {
+            Some(retry_after_ms) => (
+                false,
+                Some(format!("熔断中，{}ms 后半开探测", retry_after_ms)),
+            ),
+            None => (c.is_healthy, c.last_error_message.clone()),
+        };
+        CredentialHealthInfo {
+            uuid: c.uuid.clone(),
+            name: c.name.clone(),
+            provider_type: c.provider_type.to_string(),
+            is_healthy,
+            last_error,
+            last_error_time: c.last_error_time.map(|t| t.to_rfc3339()),
+            failure_count: c.error_count,
+            requires_reauth,
+        }
     }
 
     /// 标记凭证为不健康（带详细错误信息）
@@ -2183,4 +2466,43 @@ mod tests {
             PoolProviderType::OpenAI
         );
     }
+
+    struct OpenCircuit(&'static str);
+
+    impl CircuitHealthSource for OpenCircuit {
+        fn credential_circuit(&self, credential_id: &str) -> Option<CircuitHealth> {
+            (credential_id == self.0).then_some(CircuitHealth::Open {
+                retry_after_ms: 30_000,
+            })
+        }
+    }
+
+    #[test]
+    fn test_choose_credential_skips_open_circuit() {
+        let credential = |uuid: &str| {
+            let mut cred = ProviderCredential::new(
+                PoolProviderType::Kiro,
+                CredentialData::KiroOAuth {
+                    creds_file_path: format!("/tmp/{uuid}.json"),
+                },
+            );
+            cred.uuid = uuid.to_string();
+            cred
+        };
+        let service = ProviderPoolService::new();
+        service.set_circuit_source(Arc::new(OpenCircuit("open")));
+
+        let selected = service
+            .choose_credential(vec![credential("open"), credential("closed")], None, None)
+            .unwrap();
+        assert_eq!(selected.uuid, "closed");
+        assert!(service
+            .choose_credential(vec![credential("open")], None, None)
+            .is_none());
+
+        let health = service.health_info(&credential("open"));
+        assert!(!health.is_healthy);
+        assert!(health.last_error.unwrap().contains("熔断中"));
+        assert!(service.health_info(&credential("closed")).is_healthy);
+    }
 }
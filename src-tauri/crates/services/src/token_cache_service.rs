@@ -19,7 +19,7 @@ use proxycast_core::models::provider_pool_model::{
 use proxycast_providers::providers::gemini::GeminiProvider;
 use proxycast_providers::providers::kiro::KiroProvider;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, OwnedMutexGuard};
 
 /// Token 刷新错误类型
 #[derive(Debug, Clone, PartialEq)]
@@ -242,12 +242,7 @@ impl TokenCacheService {
         }
 
         // 获取该凭证的锁
-        let lock = self
-            .locks
-            .entry(uuid.to_string())
-            .or_insert_with(|| Arc::new(Mutex::new(())))
-            .clone();
-
+        let lock = self.credential_lock(uuid);
         let _guard = lock.lock().await;
 
         // 双重检查：可能其他线程已完成刷新
@@ -270,6 +265,81 @@ impl TokenCacheService {
             }
         }
 
+        self.refresh_locked(db, uuid, kiro_event_service).await
+    }
+
+    /// 在 Token 即将于 `within` 内过期时刷新（供后台预刷新调度器使用）
+    ///
+    /// 与按需刷新共用同一把凭证锁，拿到锁后重新检查缓存，
+    /// 避免与请求路径上的刷新并发使用同一个 refresh token。
+    ///
+    /// # 返回
+    /// - `Ok(Some(info))`: 已刷新，返回新的缓存信息
+    /// - `Ok(None)`: 其他路径已完成刷新，无需再刷新
+    pub async fn refresh_ahead_of_expiry(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        within: chrono::Duration,
+        kiro_event_service: Option<Arc<KiroEventService>>,
+    ) -> Result<Option<CachedTokenInfo>, String> {
+        let lock = self.credential_lock(uuid);
+        let _guard = lock.lock().await;
+
+        let cached = self.get_cache_status(db, uuid)?;
+        if let Some(cache) = &cached {
+            let fresh = cache.is_valid()
+                && cache
+                    .expiry_time
+                    .is_some_and(|expiry| expiry > Utc::now() + within);
+            if fresh {
+                return Ok(None);
+            }
+        }
+
+        self.refresh_locked(db, uuid, kiro_event_service).await?;
+        self.get_cache_status(db, uuid)
+    }
+
+    /// 获取凭证的刷新锁（每凭证一把，按需刷新与后台预刷新共用）
+    fn credential_lock(&self, uuid: &str) -> Arc<Mutex<()>> {
+        self.locks
+            .entry(uuid.to_string())
+            .or_insert_with(|| Arc::new(Mutex::new(())))
+            .clone()
+    }
+
+    /// 锁定凭证刷新（供请求路径上直接通过 Provider 刷新 Token 时使用）
+    ///
+    /// 与按需刷新、后台预刷新共用同一把锁。拿到锁后应重新从源文件加载凭证，
+    /// 确认仍需刷新再刷新；持有期间不要调用本服务的刷新方法。
+    pub async fn lock_credential(&self, uuid: &str) -> OwnedMutexGuard<()> {
+        self.credential_lock(uuid).lock_owned().await
+    }
+
+    /// 请求路径在凭证锁内完成刷新后，从源文件同步 Token 缓存
+    ///
+    /// 使后台预刷新调度器看到新的过期时间，不再重复刷新。
+    pub async fn sync_after_external_refresh(&self, db: Option<&DbConnection>, uuid: &str) {
+        let Some(db) = db else {
+            return;
+        };
+        if let Err(e) = self.load_initial_token(db, uuid).await {
+            tracing::warn!(
+                "[TOKEN_CACHE] 刷新后同步 Token 缓存失败 {}: {}",
+                &uuid[..8.min(uuid.len())],
+                e
+            );
+        }
+    }
+
+    /// 执行刷新并缓存到数据库（调用方需持有凭证锁）
+    async fn refresh_locked(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        kiro_event_service: Option<Arc<KiroEventService>>,
+    ) -> Result<String, String> {
         // 获取凭证信息
         let credential = {
             let conn = db.lock().map_err(|e| e.to_string())?;
@@ -750,7 +820,7 @@ impl TokenCacheService {
                 let refresh_token = creds["refresh_token"].as_str().map(|s| s.to_string());
                 let expiry_time = creds["expiry_date"]
                     .as_i64()
-                    .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0));
+                    .and_then(chrono::DateTime::from_timestamp_millis);
 
                 Ok(CachedTokenInfo {
                     access_token,
@@ -862,7 +932,11 @@ impl TokenCacheService {
     pub fn supports_refresh(provider_type: PoolProviderType) -> bool {
         matches!(
             provider_type,
-            PoolProviderType::Kiro | PoolProviderType::Gemini
+            PoolProviderType::Kiro
+                | PoolProviderType::Gemini
+                | PoolProviderType::Antigravity
+                | PoolProviderType::ClaudeOAuth
+                | PoolProviderType::Codex
         )
     }
 
@@ -896,7 +970,7 @@ impl TokenCacheService {
     /// 智能错误分类方法
     ///
     /// 基于错误信息智能识别错误类型，提供针对性的处理建议
-    fn classify_refresh_error(&self, error_message: &str) -> RefreshErrorClassification {
+    pub(crate) fn classify_refresh_error(&self, error_message: &str) -> RefreshErrorClassification {
         let error_lower = error_message.to_lowercase();
 
         // Token 被截断问题检测（最严重的问题，优先检查）
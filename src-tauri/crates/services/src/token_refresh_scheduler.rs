@@ -0,0 +1,522 @@
+//! OAuth Token 后台预刷新调度器
+//!
+//! 跟踪凭证池中所有 OAuth 凭证（Kiro、Gemini、Antigravity、Claude OAuth、Codex）的
+//! Token 过期时间，在过期前按 `refresh_ahead_secs` + 随机抖动主动刷新：
+//! - 刷新失败时按指数退避重试，不会每轮都冲击刷新端点
+//! - 认证类错误（refresh token 失效等）标记为需要重新登录，停止自动刷新
+//! - 刷新与请求路径上的按需刷新共用 `TokenCacheService` 的凭证锁，避免 refresh token 竞争
+//! - 每次刷新结果通过 [`TokenRefreshEvent`] 广播
+
+use crate::token_cache_service::{RefreshErrorType, TokenCacheService};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use parking_lot::{Mutex, RwLock};
+use proxycast_core::config::TokenRefreshSettings;
+use proxycast_core::database::dao::provider_pool::ProviderPoolDao;
+use proxycast_core::database::DbConnection;
+use proxycast_core::models::provider_pool_model::ProviderCredential;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, Notify};
+
+/// 需要重新登录的凭证错误信息前缀（与凭证健康状态中的 `requires_reauth` 判断保持一致）
+const REAUTH_PREFIX: &str = "[需要重新授权]";
+
+/// 预刷新事件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TokenRefreshEvent {
+    /// 刷新成功
+    Refreshed {
+        uuid: String,
+        provider_type: String,
+        expires_at: Option<DateTime<Utc>>,
+    },
+    /// 刷新失败，将在 `retry_at` 重试
+    Failed {
+        uuid: String,
+        provider_type: String,
+        error: String,
+        consecutive_failures: u32,
+        retry_at: DateTime<Utc>,
+    },
+    /// refresh token 已失效，需要重新登录
+    ReauthRequired {
+        uuid: String,
+        provider_type: String,
+        error: String,
+    },
+}
+
+/// 单个凭证的预刷新状态
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TokenRefreshStatus {
+    pub uuid: String,
+    pub name: Option<String>,
+    pub provider_type: String,
+    /// 当前 Token 过期时间
+    pub expires_at: Option<DateTime<Utc>>,
+    /// 下次计划刷新时间
+    pub next_refresh_at: Option<DateTime<Utc>>,
+    /// 连续失败次数
+    pub consecutive_failures: u32,
+    /// 是否需要重新登录
+    pub needs_relogin: bool,
+    /// 最近一次刷新错误
+    pub last_error: Option<String>,
+}
+
+/// 调度器内部跟踪的凭证状态
+#[derive(Debug, Clone, Default)]
+struct RefreshEntry {
+    name: Option<String>,
+    provider_type: String,
+    expires_at: Option<DateTime<Utc>>,
+    next_refresh_at: Option<DateTime<Utc>>,
+    consecutive_failures: u32,
+    needs_relogin: bool,
+    last_error: Option<String>,
+}
+
+/// 计算计划刷新时间：过期时间 - 提前量 - 抖动
+///
+/// 抖动由凭证 UUID 和过期时间确定，同一 Token 的计划时间稳定，不同凭证之间分散。
+pub fn plan_refresh_at(
+    uuid: &str,
+    expires_at: DateTime<Utc>,
+    settings: &TokenRefreshSettings,
+) -> DateTime<Utc> {
+    let mut hasher = DefaultHasher::new();
+    uuid.hash(&mut hasher);
+    expires_at.timestamp().hash(&mut hasher);
+    let jitter_secs = if settings.jitter_secs == 0 {
+        0
+    } else {
+        hasher.finish() % settings.jitter_secs
+    };
+    expires_at - ChronoDuration::seconds((settings.refresh_ahead_secs + jitter_secs) as i64)
+}
+
+/// 计算第 `failures` 次连续失败后的退避时间（指数增长，不超过上限）
+pub fn backoff_delay(failures: u32, settings: &TokenRefreshSettings) -> Duration {
+    let exponent = failures.saturating_sub(1).min(16);
+    let secs = settings
+        .initial_backoff_secs
+        .saturating_mul(1u64 << exponent)
+        .min(settings.max_backoff_secs);
+    Duration::from_secs(secs.max(1))
+}
+
+/// OAuth Token 后台预刷新调度器
+pub struct TokenRefreshScheduler {
+    token_cache: Arc<TokenCacheService>,
+    settings: RwLock<TokenRefreshSettings>,
+    entries: Mutex<HashMap<String, RefreshEntry>>,
+    events: broadcast::Sender<TokenRefreshEvent>,
+    running: AtomicBool,
+    shutdown: Notify,
+}
+
+impl TokenRefreshScheduler {
+    /// 创建调度器（与请求路径共用同一个 `TokenCacheService`）
+    pub fn new(token_cache: Arc<TokenCacheService>, settings: TokenRefreshSettings) -> Self {
+        let (events, _) = broadcast::channel(256);
+        Self {
+            token_cache,
+            settings: RwLock::new(settings),
+            entries: Mutex::new(HashMap::new()),
+            events,
+            running: AtomicBool::new(false),
+            shutdown: Notify::new(),
+        }
+    }
+
+    /// 订阅预刷新事件
+    pub fn subscribe(&self) -> broadcast::Receiver<TokenRefreshEvent> {
+        self.events.subscribe()
+    }
+
+    /// 获取配置
+    pub fn settings(&self) -> TokenRefreshSettings {
+        self.settings.read().clone()
+    }
+
+    /// 更新配置（下一轮检查生效）
+    pub fn update_settings(&self, settings: TokenRefreshSettings) {
+        *self.settings.write() = settings;
+        // 提前量或抖动变化后重新计算计划时间
+        for entry in self.entries.lock().values_mut() {
+            if entry.consecutive_failures == 0 {
+                entry.next_refresh_at = None;
+            }
+        }
+    }
+
+    /// 是否正在运行
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// 启动后台检查循环
+    pub fn start(self: &Arc<Self>, db: DbConnection) -> Option<tokio::task::JoinHandle<()>> {
+        if self.running.swap(true, Ordering::SeqCst) {
+            tracing::debug!("[TOKEN_REFRESH] 调度器已在运行");
+            return None;
+        }
+
+        let scheduler = self.clone();
+        Some(tokio::spawn(async move {
+            tracing::info!("[TOKEN_REFRESH] 后台预刷新调度器已启动");
+            while scheduler.is_running() {
+                let settings = scheduler.settings();
+                if settings.enabled {
+                    scheduler.tick(&db).await;
+                }
+                let interval = Duration::from_secs(settings.check_interval_secs.max(1));
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {}
+                    _ = scheduler.shutdown.notified() => break,
+                }
+            }
+            scheduler.running.store(false, Ordering::SeqCst);
+            tracing::info!("[TOKEN_REFRESH] 后台预刷新调度器已停止");
+        }))
+    }
+
+    /// 停止后台检查循环
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        // notify_one 会保留通知，即使循环此刻正在执行刷新也能在下一次等待时立即退出
+        self.shutdown.notify_one();
+    }
+
+    /// 获取所有 OAuth 凭证的预刷新状态
+    pub fn status(&self) -> Vec<TokenRefreshStatus> {
+        let mut statuses: Vec<_> = self
+            .entries
+            .lock()
+            .iter()
+            .map(|(uuid, entry)| TokenRefreshStatus {
+                uuid: uuid.clone(),
+                name: entry.name.clone(),
+                provider_type: entry.provider_type.clone(),
+                expires_at: entry.expires_at,
+                next_refresh_at: entry.next_refresh_at,
+                consecutive_failures: entry.consecutive_failures,
+                needs_relogin: entry.needs_relogin,
+                last_error: entry.last_error.clone(),
+            })
+            .collect();
+        statuses.sort_by(|a, b| a.next_refresh_at.cmp(&b.next_refresh_at));
+        statuses
+    }
+
+    /// 清除凭证的退避和重新登录标记（用户重新登录后调用）
+    pub fn reset(&self, uuid: &str) {
+        self.entries.lock().remove(uuid);
+    }
+
+    /// 执行一轮检查：刷新所有到期的凭证
+    pub async fn tick(&self, db: &DbConnection) {
+        let credentials = match self.load_oauth_credentials(db) {
+            Ok(credentials) => credentials,
+            Err(e) => {
+                tracing::warn!("[TOKEN_REFRESH] 读取凭证失败: {}", e);
+                return;
+            }
+        };
+
+        let due = self.sync_entries(db, &credentials, Utc::now());
+        for credential in due {
+            self.refresh_credential(db, &credential).await;
+        }
+    }
+
+    fn load_oauth_credentials(&self, db: &DbConnection) -> Result<Vec<ProviderCredential>, String> {
+        let conn = proxycast_core::database::lock_db(db)?;
+        let credentials = ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?;
+        Ok(credentials
+            .into_iter()
+            .filter(|c| !c.is_disabled && TokenCacheService::supports_refresh(c.provider_type))
+            .collect())
+    }
+
+    /// 根据当前 Token 缓存更新跟踪状态，返回到期需要刷新的凭证
+    fn sync_entries(
+        &self,
+        db: &DbConnection,
+        credentials: &[ProviderCredential],
+        now: DateTime<Utc>,
+    ) -> Vec<ProviderCredential> {
+        let settings = self.settings();
+        let mut entries = self.entries.lock();
+        entries.retain(|uuid, _| credentials.iter().any(|c| &c.uuid == uuid));
+
+        let mut due = Vec::new();
+        for credential in credentials {
+            let cache = self
+                .token_cache
+                .get_cache_status(db, &credential.uuid)
+                .ok()
+                .flatten();
+            let entry = entries.entry(credential.uuid.clone()).or_default();
+            entry.name = credential.name.clone();
+            entry.provider_type = credential.provider_type.to_string();
+
+            let has_token = cache.as_ref().is_some_and(|c| c.access_token.is_some());
+            let expires_at = cache.as_ref().and_then(|c| c.expiry_time);
+
+            // 过期时间变化说明 Token 已被其他路径刷新或用户重新登录，重新计划
+            if entry.expires_at != expires_at {
+                entry.expires_at = expires_at;
+                entry.next_refresh_at = None;
+                entry.consecutive_failures = 0;
+                entry.needs_relogin = false;
+                entry.last_error = None;
+            }
+
+            if entry.needs_relogin {
+                continue;
+            }
+
+            if entry.next_refresh_at.is_none() {
+                entry.next_refresh_at = match expires_at {
+                    Some(expiry) => Some(plan_refresh_at(&credential.uuid, expiry, &settings)),
+                    // 尚未加载 Token 时立即加载；有 Token 但无过期时间时无需预刷新
+                    None if !has_token => Some(now),
+                    None => None,
+                };
+            }
+
+            if entry.next_refresh_at.is_some_and(|at| at <= now) {
+                due.push(credential.clone());
+            }
+        }
+        due
+    }
+
+    async fn refresh_credential(&self, db: &DbConnection, credential: &ProviderCredential) {
+        let settings = self.settings();
+        let uuid = credential.uuid.as_str();
+        let provider_type = credential.provider_type.to_string();
+        let within =
+            ChronoDuration::seconds((settings.refresh_ahead_secs + settings.jitter_secs) as i64);
+
+        match self
+            .token_cache
+            .refresh_ahead_of_expiry(db, uuid, within, None)
+            .await
+        {
+            Ok(refreshed) => {
+                let refreshed_now = refreshed.is_some();
+                // 其他路径已完成刷新时读取最新缓存
+                let info = match refreshed {
+                    Some(info) => Some(info),
+                    None => self.token_cache.get_cache_status(db, uuid).ok().flatten(),
+                };
+                let expires_at = info.and_then(|info| info.expiry_time);
+                {
+                    let mut entries = self.entries.lock();
+                    if let Some(entry) = entries.get_mut(uuid) {
+                        entry.expires_at = expires_at;
+                        entry.next_refresh_at =
+                            expires_at.map(|expiry| plan_refresh_at(uuid, expiry, &settings));
+                        entry.consecutive_failures = 0;
+                        entry.last_error = None;
+                    }
+                }
+                if !refreshed_now {
+                    return;
+                }
+                tracing::info!(
+                    "[TOKEN_REFRESH] 已预刷新 {} ({}), 新过期时间 {:?}",
+                    &uuid[..8.min(uuid.len())],
+                    provider_type,
+                    expires_at
+                );
+                let _ = self.events.send(TokenRefreshEvent::Refreshed {
+                    uuid: uuid.to_string(),
+                    provider_type,
+                    expires_at,
+                });
+            }
+            Err(error) => {
+                let classification = self.token_cache.classify_refresh_error(&error);
+                if classification.error_type == RefreshErrorType::AuthenticationFailed {
+                    self.mark_needs_relogin(db, credential, &error);
+                    let _ = self.events.send(TokenRefreshEvent::ReauthRequired {
+                        uuid: uuid.to_string(),
+                        provider_type,
+                        error,
+                    });
+                    return;
+                }
+
+                let (failures, retry_at) = {
+                    let mut entries = self.entries.lock();
+                    let entry = entries.entry(uuid.to_string()).or_default();
+                    entry.consecutive_failures += 1;
+                    let delay = backoff_delay(entry.consecutive_failures, &settings);
+                    let retry_at = Utc::now()
+                        + ChronoDuration::from_std(delay).unwrap_or(ChronoDuration::zero());
+                    entry.next_refresh_at = Some(retry_at);
+                    entry.last_error = Some(error.clone());
+                    (entry.consecutive_failures, retry_at)
+                };
+                tracing::warn!(
+                    "[TOKEN_REFRESH] 预刷新失败 {} ({}), 第 {} 次, {} 后重试: {}",
+                    &uuid[..8.min(uuid.len())],
+                    provider_type,
+                    failures,
+                    retry_at,
+                    error
+                );
+                let _ = self.events.send(TokenRefreshEvent::Failed {
+                    uuid: uuid.to_string(),
+                    provider_type,
+                    error,
+                    consecutive_failures: failures,
+                    retry_at,
+                });
+            }
+        }
+    }
+
+    /// 标记凭证需要重新登录：停止自动刷新，并将凭证标记为不健康
+    fn mark_needs_relogin(&self, db: &DbConnection, credential: &ProviderCredential, error: &str) {
+        let uuid = credential.uuid.as_str();
+        if let Some(entry) = self.entries.lock().get_mut(uuid) {
+            entry.needs_relogin = true;
+            entry.next_refresh_at = None;
+            entry.last_error = Some(error.to_string());
+        }
+
+        let message = format!("{REAUTH_PREFIX} Token 刷新失败: {error}");
+        let result = proxycast_core::database::lock_db(db).and_then(|conn| {
+            ProviderPoolDao::update_health_status(
+                &conn,
+                uuid,
+                false,
+                credential.error_count + 1,
+                Some(Utc::now()),
+                Some(&message),
+                None,
+                None,
+            )
+            .map_err(|e| e.to_string())
+        });
+        if let Err(e) = result {
+            tracing::warn!(
+                "[TOKEN_REFRESH] 标记凭证 {} 需要重新登录失败: {}",
+                &uuid[..8.min(uuid.len())],
+                e
+            );
+        }
+        tracing::warn!(
+            "[TOKEN_REFRESH] 凭证 {} ({}) 需要重新登录: {}",
+            &uuid[..8.min(uuid.len())],
+            credential.provider_type,
+            error
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proxycast_core::models::provider_pool_model::PoolProviderType;
+
+    fn settings() -> TokenRefreshSettings {
+        TokenRefreshSettings {
+            refresh_ahead_secs: 600,
+            jitter_secs: 120,
+            initial_backoff_secs: 30,
+            max_backoff_secs: 600,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_plan_refresh_at_applies_ahead_and_jitter() {
+        let settings = settings();
+        let expiry = Utc::now() + ChronoDuration::hours(1);
+
+        let plan = plan_refresh_at("cred-1", expiry, &settings);
+        let ahead = expiry - plan;
+        assert!(ahead >= ChronoDuration::seconds(600));
+        assert!(ahead < ChronoDuration::seconds(720));
+        // 同一 Token 的计划时间稳定
+        assert_eq!(plan, plan_refresh_at("cred-1", expiry, &settings));
+
+        let no_jitter = TokenRefreshSettings {
+            jitter_secs: 0,
+            ..settings
+        };
+        assert_eq!(
+            plan_refresh_at("cred-1", expiry, &no_jitter),
+            expiry - ChronoDuration::seconds(600)
+        );
+    }
+
+    #[test]
+    fn test_plan_refresh_at_spreads_credentials() {
+        let settings = settings();
+        let expiry = Utc::now() + ChronoDuration::hours(1);
+        let plans: std::collections::HashSet<_> = (0..20)
+            .map(|i| plan_refresh_at(&format!("cred-{i}"), expiry, &settings))
+            .collect();
+        assert!(plans.len() > 1);
+    }
+
+    #[test]
+    fn test_backoff_grows_exponentially_with_cap() {
+        let settings = settings();
+        assert_eq!(backoff_delay(1, &settings), Duration::from_secs(30));
+        assert_eq!(backoff_delay(2, &settings), Duration::from_secs(60));
+        assert_eq!(backoff_delay(3, &settings), Duration::from_secs(120));
+        assert_eq!(backoff_delay(10, &settings), Duration::from_secs(600));
+        assert_eq!(backoff_delay(100, &settings), Duration::from_secs(600));
+    }
+
+    #[test]
+    fn test_tracked_providers() {
+        for provider in [
+            PoolProviderType::Kiro,
+            PoolProviderType::Gemini,
+            PoolProviderType::Antigravity,
+            PoolProviderType::ClaudeOAuth,
+            PoolProviderType::Codex,
+        ] {
+            assert!(TokenCacheService::supports_refresh(provider), "{provider}");
+        }
+        assert!(!TokenCacheService::supports_refresh(
+            PoolProviderType::OpenAI
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_request_path_lock_shared_with_refresh() {
+        let token_cache = Arc::new(TokenCacheService::new());
+        let guard = token_cache.lock_credential("cred-1").await;
+
+        // 请求路径持锁刷新期间，同一凭证的其他刷新需要等待
+        let waiter = {
+            let token_cache = token_cache.clone();
+            tokio::spawn(async move {
+                let _guard = token_cache.lock_credential("cred-1").await;
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+
+        // 其他凭证不受影响
+        let _other = token_cache.lock_credential("cred-2").await;
+
+        drop(guard);
+        waiter.await.unwrap();
+    }
+}
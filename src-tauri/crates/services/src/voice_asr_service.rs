@@ -7,6 +7,7 @@
 //! - OpenAI Whisper API
 //! - 百度语音识别
 //! - 讯飞语音识别（WebSocket 流式）
+//! - 边录音边识别（讯飞、百度实时识别、本地 Whisper 分块窗口）
 //!
 //! ## 模型文件路径
 //! Whisper 模型文件存储在：`~/Library/Application Support/proxycast/models/whisper/`
@@ -21,6 +22,18 @@
 //! ```rust,ignore
 //! let credential = AsrService::get_default_credential()?.unwrap();
 //! let text = AsrService::transcribe(&credential, &audio_data, 16000).await?;
+//!
+//! // 需要分段时间戳时（如网关 `/v1/audio/transcriptions` 输出字幕）
+//! let result = AsrService::transcribe_audio(&credential, &audio).await?;
+//!
+//! // mp3、m4a、webm 等已编码文件原样转发（仅 OpenAI 凭证支持）
+//! let result = AsrService::transcribe_file(&credential, bytes, "speech.mp3", "audio/mpeg").await?;
+//!
+//! // 边录音边识别：不支持流式的服务返回 None，录音结束后再批量识别
+//! if let Some(stream) = AsrService::start_stream(&credential, 48000).await? {
+//!     stream.send_audio(&samples)?;
+//!     let result = stream.finish().await?;
+//! }
 //! ```
 
 #[cfg(feature = "local-whisper")]
@@ -31,8 +44,13 @@ use proxycast_core::config::WhisperModelSize;
 use proxycast_core::config::{AsrCredentialEntry, AsrProviderType};
 
 use super::voice_config_service;
-use voice_core::asr_client::{AsrClient, BaiduClient, OpenAIWhisperClient, XunfeiClient};
-use voice_core::types::AudioData;
+use voice_core::asr_client::{
+    AsrClient, AsrStream, BaiduClient, OpenAIWhisperClient, StreamingAsrClient, XunfeiClient,
+};
+use voice_core::types::{AudioData, TranscribeResult};
+
+/// 百度实时识别英语模型 ID
+const BAIDU_REALTIME_DEV_PID_EN: u32 = 17372;
 
 /// ASR 服务
 pub struct AsrService;
@@ -48,6 +66,11 @@ impl AsrService {
         voice_config_service::get_asr_credential(id)
     }
 
+    /// 按 OpenAI 兼容请求中的 `model` 解析 ASR 凭证
+    pub fn resolve_credential(model: &str) -> Result<Option<AsrCredentialEntry>, String> {
+        voice_config_service::resolve_asr_credential(model)
+    }
+
     /// 使用指定凭证进行语音识别
     ///
     /// 当云端服务失败时，自动回退到本地 Whisper（需求 3.4）
@@ -56,22 +79,33 @@ impl AsrService {
         audio_data: &[u8],
         sample_rate: u32,
     ) -> Result<String, String> {
+        let audio = Self::build_audio_data(audio_data, sample_rate)?;
+        Self::transcribe_audio(credential, &audio)
+            .await
+            .map(|result| result.text)
+    }
+
+    /// 使用指定凭证识别音频，返回包含分段信息的完整结果
+    ///
+    /// 回退策略与 [`AsrService::transcribe`] 一致
+    pub async fn transcribe_audio(
+        credential: &AsrCredentialEntry,
+        audio: &AudioData,
+    ) -> Result<TranscribeResult, String> {
+        if audio.samples.is_empty() {
+            return Err("音频数据为空".to_string());
+        }
+
         // 如果是本地 Whisper，直接调用
         if matches!(credential.provider, AsrProviderType::WhisperLocal) {
-            return Self::transcribe_whisper_local(credential, audio_data, sample_rate).await;
+            return Self::transcribe_whisper_local(credential, audio).await;
         }
 
         // 云端服务：先尝试云端，失败则回退到本地 Whisper
         let cloud_result = match credential.provider {
-            AsrProviderType::OpenAI => {
-                Self::transcribe_openai(credential, audio_data, sample_rate).await
-            }
-            AsrProviderType::Baidu => {
-                Self::transcribe_baidu(credential, audio_data, sample_rate).await
-            }
-            AsrProviderType::Xunfei => {
-                Self::transcribe_xunfei(credential, audio_data, sample_rate).await
-            }
+            AsrProviderType::OpenAI => Self::transcribe_openai(credential, audio).await,
+            AsrProviderType::Baidu => Self::transcribe_baidu(credential, audio).await,
+            AsrProviderType::Xunfei => Self::transcribe_xunfei(credential, audio).await,
             AsrProviderType::WhisperLocal => unreachable!(), // 已在上面处理
         };
 
@@ -92,12 +126,10 @@ impl AsrService {
         match Self::get_whisper_local_credential() {
             Ok(Some(whisper_credential)) => {
                 tracing::info!("正在使用本地 Whisper 进行回退识别...");
-                match Self::transcribe_whisper_local(&whisper_credential, audio_data, sample_rate)
-                    .await
-                {
-                    Ok(text) => {
+                match Self::transcribe_whisper_local(&whisper_credential, audio).await {
+                    Ok(result) => {
                         tracing::info!("本地 Whisper 回退识别成功");
-                        Ok(text)
+                        Ok(result)
                     }
                     Err(whisper_error) => {
                         tracing::error!("本地 Whisper 回退也失败: {}", whisper_error);
@@ -123,18 +155,129 @@ impl AsrService {
         }
     }
 
+    /// 识别已编码的音频文件（mp3、m4a、webm 等），原样转发给上游
+    ///
+    /// 网关不解码压缩音频，只有接受原始文件的 OpenAI Whisper API 凭证支持；
+    /// 其它服务返回错误，且无法回退到本地 Whisper。
+    pub async fn transcribe_file(
+        credential: &AsrCredentialEntry,
+        bytes: Vec<u8>,
+        file_name: &str,
+        mime: &str,
+    ) -> Result<TranscribeResult, String> {
+        if bytes.is_empty() {
+            return Err("音频数据为空".to_string());
+        }
+        if !matches!(credential.provider, AsrProviderType::OpenAI) {
+            return Err(format!(
+                "{} 仅支持 WAV 或 PCM 音频，压缩格式需要使用 OpenAI 凭证",
+                voice_config_service::asr_provider_name(credential.provider)
+            ));
+        }
+
+        Self::openai_client(credential)?
+            .transcribe_file(bytes, file_name, mime)
+            .await
+            .map_err(|e| format!("OpenAI Whisper 识别失败: {e}"))
+    }
+
+    /// 使用指定凭证开始一次流式识别
+    ///
+    /// 返回 `Ok(None)` 表示该凭证不支持流式识别（OpenAI Whisper、未配置 App ID 的百度），
+    /// 调用方应在录音结束后改用 [`AsrService::transcribe_audio`]。
+    pub async fn start_stream(
+        credential: &AsrCredentialEntry,
+        sample_rate: u32,
+    ) -> Result<Option<AsrStream>, String> {
+        let stream = match credential.provider {
+            AsrProviderType::Xunfei => Self::xunfei_client(credential)?
+                .start_stream(sample_rate)
+                .await
+                .map_err(|e| format!("讯飞流式识别启动失败: {e}"))?,
+            AsrProviderType::Baidu => {
+                let config = credential.baidu_config.as_ref().ok_or("百度配置缺失")?;
+                let Some(app_id) = config.app_id else {
+                    return Ok(None);
+                };
+                let mut client =
+                    BaiduClient::new(config.api_key.clone(), config.secret_key.clone())
+                        .with_app_id(app_id);
+                if credential.language == "en" {
+                    client = client.with_dev_pid(BAIDU_REALTIME_DEV_PID_EN);
+                }
+                client
+                    .start_stream(sample_rate)
+                    .await
+                    .map_err(|e| format!("百度实时识别启动失败: {e}"))?
+            }
+            AsrProviderType::WhisperLocal => {
+                return Self::start_whisper_local_stream(credential, sample_rate).await;
+            }
+            AsrProviderType::OpenAI => return Ok(None),
+        };
+        Ok(Some(stream))
+    }
+
+    /// 本地 Whisper 流式识别（分块窗口）
+    #[cfg(feature = "local-whisper")]
+    async fn start_whisper_local_stream(
+        credential: &AsrCredentialEntry,
+        sample_rate: u32,
+    ) -> Result<Option<AsrStream>, String> {
+        let transcriber = std::sync::Arc::new(Self::whisper_transcriber(credential)?);
+        voice_core::asr_client::ChunkedStreamingClient::new(transcriber)
+            .start_stream(sample_rate)
+            .await
+            .map(Some)
+            .map_err(|e| format!("Whisper 流式识别启动失败: {e}"))
+    }
+
+    /// 本地 Whisper 流式识别（未启用 local-whisper feature 时不支持流式）
+    #[cfg(not(feature = "local-whisper"))]
+    async fn start_whisper_local_stream(
+        _credential: &AsrCredentialEntry,
+        _sample_rate: u32,
+    ) -> Result<Option<AsrStream>, String> {
+        Ok(None)
+    }
+
     /// 获取本地 Whisper 凭证（用于回退）
     fn get_whisper_local_credential() -> Result<Option<AsrCredentialEntry>, String> {
         voice_config_service::get_enabled_asr_credential_by_provider(AsrProviderType::WhisperLocal)
     }
 
     /// 本地 Whisper 识别
+    ///
+    /// 模型加载和推理都是 CPU 密集的同步操作，放到阻塞线程池执行，避免占用异步运行时的工作线程
     #[cfg(feature = "local-whisper")]
     async fn transcribe_whisper_local(
         credential: &AsrCredentialEntry,
-        audio_data: &[u8],
-        sample_rate: u32,
-    ) -> Result<String, String> {
+        audio: &AudioData,
+    ) -> Result<TranscribeResult, String> {
+        // 检查录音时长
+        if !audio.is_valid() {
+            return Err("录音时间过短（需要至少 0.5 秒）".to_string());
+        }
+
+        let credential = credential.clone();
+        let audio = audio.clone();
+        tokio::task::spawn_blocking(move || {
+            let transcriber = Self::whisper_transcriber(&credential)?;
+
+            // 执行识别
+            transcriber
+                .transcribe(&audio)
+                .map_err(|e| format!("Whisper 识别失败: {e}"))
+        })
+        .await
+        .map_err(|e| format!("Whisper 识别任务异常退出: {e}"))?
+    }
+
+    /// 按凭证配置创建 Whisper 识别器
+    #[cfg(feature = "local-whisper")]
+    fn whisper_transcriber(
+        credential: &AsrCredentialEntry,
+    ) -> Result<voice_core::WhisperTranscriber, String> {
         // 获取 Whisper 配置
         let whisper_config = credential
             .whisper_config
@@ -144,37 +287,20 @@ impl AsrService {
         // 获取模型文件路径
         let model_path = Self::get_whisper_model_path(&whisper_config.model)?;
 
-        // 将 PCM 字节转换为 i16 采样
-        let audio = Self::build_audio_data(audio_data, sample_rate)?;
-
-        // 检查录音时长
-        if !audio.is_valid() {
-            return Err("录音时间过短（需要至少 0.5 秒）".to_string());
-        }
-
         // 转换模型大小枚举
         let model = Self::convert_model_size(&whisper_config.model);
 
         // 创建 Whisper 识别器
-        let transcriber =
-            voice_core::WhisperTranscriber::new(model_path, model, &credential.language)
-                .map_err(|e| format!("Whisper 模型加载失败: {e}"))?;
-
-        // 执行识别
-        let result = transcriber
-            .transcribe(&audio)
-            .map_err(|e| format!("Whisper 识别失败: {e}"))?;
-
-        Ok(result.text)
+        voice_core::WhisperTranscriber::new(model_path, model, &credential.language)
+            .map_err(|e| format!("Whisper 模型加载失败: {e}"))
     }
 
     /// 本地 Whisper 识别（未启用 local-whisper feature 时的 stub）
     #[cfg(not(feature = "local-whisper"))]
     async fn transcribe_whisper_local(
         _credential: &AsrCredentialEntry,
-        _audio_data: &[u8],
-        _sample_rate: u32,
-    ) -> Result<String, String> {
+        _audio: &AudioData,
+    ) -> Result<TranscribeResult, String> {
         Err("本地 Whisper 功能未启用。请使用云端 ASR 服务（OpenAI、百度、讯飞）".to_string())
     }
 
@@ -224,11 +350,17 @@ impl AsrService {
     /// OpenAI Whisper API 识别
     async fn transcribe_openai(
         credential: &AsrCredentialEntry,
-        audio_data: &[u8],
-        sample_rate: u32,
-    ) -> Result<String, String> {
+        audio: &AudioData,
+    ) -> Result<TranscribeResult, String> {
+        Self::openai_client(credential)?
+            .transcribe(audio)
+            .await
+            .map_err(|e| format!("OpenAI Whisper 识别失败: {e}"))
+    }
+
+    /// 按凭证配置创建 OpenAI Whisper 客户端
+    fn openai_client(credential: &AsrCredentialEntry) -> Result<OpenAIWhisperClient, String> {
         let config = credential.openai_config.as_ref().ok_or("OpenAI 配置缺失")?;
-        let audio = Self::build_audio_data(audio_data, sample_rate)?;
 
         let mut client = OpenAIWhisperClient::new(config.api_key.clone());
         if let Some(base_url) = config.base_url.clone() {
@@ -237,31 +369,21 @@ impl AsrService {
         if !credential.language.is_empty() {
             client = client.with_language(credential.language.clone());
         }
-
-        let result = client
-            .transcribe(&audio)
-            .await
-            .map_err(|e| format!("OpenAI Whisper 识别失败: {e}"))?;
-
-        Ok(result.text)
+        Ok(client)
     }
 
     /// 百度语音识别
     async fn transcribe_baidu(
         credential: &AsrCredentialEntry,
-        audio_data: &[u8],
-        sample_rate: u32,
-    ) -> Result<String, String> {
+        audio: &AudioData,
+    ) -> Result<TranscribeResult, String> {
         let config = credential.baidu_config.as_ref().ok_or("百度配置缺失")?;
-        let audio = Self::build_audio_data(audio_data, sample_rate)?;
 
         let client = BaiduClient::new(config.api_key.clone(), config.secret_key.clone());
-        let result = client
-            .transcribe(&audio)
+        client
+            .transcribe(audio)
             .await
-            .map_err(|e| format!("百度识别失败: {e}"))?;
-
-        Ok(result.text)
+            .map_err(|e| format!("百度识别失败: {e}"))
     }
 
     /// 讯飞语音识别
@@ -269,13 +391,18 @@ impl AsrService {
     /// 使用 WebSocket 流式识别，支持实时语音转文字
     async fn transcribe_xunfei(
         credential: &AsrCredentialEntry,
-        audio_data: &[u8],
-        sample_rate: u32,
-    ) -> Result<String, String> {
+        audio: &AudioData,
+    ) -> Result<TranscribeResult, String> {
+        Self::xunfei_client(credential)?
+            .transcribe(audio)
+            .await
+            .map_err(|e| format!("讯飞识别失败: {e}"))
+    }
+
+    /// 按凭证配置创建讯飞客户端
+    fn xunfei_client(credential: &AsrCredentialEntry) -> Result<XunfeiClient, String> {
         let config = credential.xunfei_config.as_ref().ok_or("讯飞配置缺失")?;
-        let audio = Self::build_audio_data(audio_data, sample_rate)?;
 
-        // 创建讯飞客户端
         // 讯飞语言代码转换：zh -> zh_cn, en -> en_us
         let xunfei_language = match credential.language.as_str() {
             "zh" => "zh_cn".to_string(),
@@ -283,19 +410,12 @@ impl AsrService {
             other => other.to_string(),
         };
 
-        let client = XunfeiClient::new(
+        Ok(XunfeiClient::new(
             config.app_id.clone(),
             config.api_key.clone(),
             config.api_secret.clone(),
         )
-        .with_language(xunfei_language);
-
-        let result = client
-            .transcribe(&audio)
-            .await
-            .map_err(|e| format!("讯飞识别失败: {e}"))?;
-
-        Ok(result.text)
+        .with_language(xunfei_language))
     }
 
     /// 将 PCM 字节构造成 voice-core 的 AudioData
@@ -2,7 +2,9 @@
 //!
 //! 封装语音转写、润色、输出等可复用业务流程。
 
+use proxycast_core::config::AsrCredentialEntry;
 use serde::{Deserialize, Serialize};
+use voice_core::VadEvent;
 
 use super::voice_asr_service::AsrService;
 use super::voice_config_service;
@@ -18,6 +20,9 @@ pub struct TranscribeResult {
     pub provider: String,
 }
 
+/// 流式识别中间结果
+pub use voice_core::asr_client::StreamingTranscript;
+
 /// 润色文本结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PolishResult {
@@ -57,45 +62,7 @@ pub async fn transcribe_audio(
         tracing::warn!("[语音识别] 音频数据几乎全为静音，可能是麦克风权限问题或未正确录音");
     }
 
-    let credential = if let Some(id) = credential_id {
-        tracing::info!("[语音识别] 使用指定凭证: {}", id);
-        AsrService::get_credential(id)?.ok_or_else(|| format!("凭证不存在: {id}"))?
-    } else {
-        tracing::info!("[语音识别] 获取默认凭证...");
-        match AsrService::get_default_credential() {
-            Ok(Some(credential)) => {
-                tracing::info!(
-                    "[语音识别] 找到默认凭证: id={}, provider={:?}",
-                    credential.id,
-                    credential.provider
-                );
-                credential
-            }
-            Ok(None) => {
-                if let Ok(credentials) = voice_config_service::list_asr_credentials() {
-                    tracing::error!(
-                        "[语音识别] 未找到默认凭证，当前 ASR 凭证数量: {}",
-                        credentials.len()
-                    );
-                    for (index, credential) in credentials.iter().enumerate() {
-                        tracing::error!(
-                            "[语音识别] 凭证 {}: id={}, is_default={}, disabled={}",
-                            index,
-                            credential.id,
-                            credential.is_default,
-                            credential.disabled
-                        );
-                    }
-                }
-                return Err("未配置语音识别服务。请在设置 → 凭证池 → ASR 中添加讯飞、百度或 OpenAI Whisper 凭证。".to_string());
-            }
-            Err(error) => {
-                tracing::error!("[语音识别] 获取默认凭证失败: {}", error);
-                return Err(format!("获取凭证失败: {error}"));
-            }
-        }
-    };
-
+    let credential = resolve_credential(credential_id)?;
     let provider_name = voice_config_service::asr_provider_name(credential.provider);
     tracing::info!("[语音识别] 使用服务: {}", provider_name);
 
@@ -108,6 +75,115 @@ pub async fn transcribe_audio(
     })
 }
 
+/// 边录音边识别
+///
+/// 消费录音服务的 VAD 事件，把检测到的语音送入流式 ASR，中间结果通过 `on_transcript` 回调；
+/// VAD 事件通道关闭（录音停止或取消）后结束送入并等待最终结果。
+/// 凭证不支持流式识别时返回 `Ok(None)`，调用方应在录音结束后改用 [`transcribe_audio`]。
+pub async fn stream_transcription<F>(
+    vad_events: std::sync::mpsc::Receiver<VadEvent>,
+    sample_rate: u32,
+    credential_id: Option<&str>,
+    mut on_transcript: F,
+) -> Result<Option<TranscribeResult>, String>
+where
+    F: FnMut(StreamingTranscript) + Send,
+{
+    let credential = resolve_credential(credential_id)?;
+    let provider_name = voice_config_service::asr_provider_name(credential.provider);
+    let Some(mut stream) = AsrService::start_stream(&credential, sample_rate).await? else {
+        tracing::info!(
+            "[流式识别] {} 不支持流式识别，录音结束后批量识别",
+            provider_name
+        );
+        return Ok(None);
+    };
+    tracing::info!(
+        "[流式识别] 使用服务: {}，采样率: {}",
+        provider_name,
+        sample_rate
+    );
+
+    // VAD 事件来自录音线程的同步通道，转到异步通道后与识别结果一起等待
+    let (events_tx, mut events_rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::task::spawn_blocking(move || {
+        while let Ok(event) = vad_events.recv() {
+            if events_tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    loop {
+        tokio::select! {
+            event = events_rx.recv() => match event {
+                Some(VadEvent::SpeechAudio(samples)) => {
+                    // 驱动任务已退出（连接断开等），错误由 finish 返回
+                    if stream.send_audio(&samples).is_err() {
+                        break;
+                    }
+                }
+                Some(VadEvent::SpeechStart { .. } | VadEvent::SpeechEnd(_)) => {}
+                None => break,
+            },
+            Some(transcript) = stream.next_transcript() => on_transcript(transcript),
+        }
+    }
+
+    let result = stream
+        .finish()
+        .await
+        .map_err(|e| format!("流式识别失败: {e}"))?;
+    tracing::info!("[流式识别] 识别完成，文本长度: {} 字符", result.text.len());
+
+    Ok(Some(TranscribeResult {
+        text: result.text,
+        provider: provider_name.to_string(),
+    }))
+}
+
+/// 解析要使用的 ASR 凭证（未指定时使用默认凭证）
+fn resolve_credential(credential_id: Option<&str>) -> Result<AsrCredentialEntry, String> {
+    if let Some(id) = credential_id {
+        tracing::info!("[语音识别] 使用指定凭证: {}", id);
+        return AsrService::get_credential(id)?.ok_or_else(|| format!("凭证不存在: {id}"));
+    }
+
+    tracing::info!("[语音识别] 获取默认凭证...");
+    match AsrService::get_default_credential() {
+        Ok(Some(credential)) => {
+            tracing::info!(
+                "[语音识别] 找到默认凭证: id={}, provider={:?}",
+                credential.id,
+                credential.provider
+            );
+            Ok(credential)
+        }
+        Ok(None) => {
+            if let Ok(credentials) = voice_config_service::list_asr_credentials() {
+                tracing::error!(
+                    "[语音识别] 未找到默认凭证，当前 ASR 凭证数量: {}",
+                    credentials.len()
+                );
+                for (index, credential) in credentials.iter().enumerate() {
+                    tracing::error!(
+                        "[语音识别] 凭证 {}: id={}, is_default={}, disabled={}",
+                        index,
+                        credential.id,
+                        credential.is_default,
+                        credential.disabled
+                    );
+                }
+            }
+            Err("未配置语音识别服务。请在设置 → 凭证池 → ASR 中添加讯飞、百度或 OpenAI Whisper 凭证。".to_string())
+        }
+        Err(error) => {
+            tracing::error!("[语音识别] 获取默认凭证失败: {}", error);
+            Err(format!("获取凭证失败: {error}"))
+        }
+    }
+}
+
 /// 润色文本
 pub async fn polish_voice_text(
     text: &str,
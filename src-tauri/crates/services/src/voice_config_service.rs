@@ -48,6 +48,36 @@ pub fn list_asr_credentials() -> Result<Vec<AsrCredentialEntry>, String> {
     Ok(config.credential_pool.asr)
 }
 
+/// 按 OpenAI 兼容请求中的 `model` 解析 ASR 凭证
+///
+/// `model` 可以是凭证 ID 或 Provider 名称（`whisper_local`、`xunfei`、`baidu`、`openai`），
+/// 其它值（如 `whisper-1`）使用默认凭证。禁用的凭证不会被选中。
+pub fn resolve_asr_credential(model: &str) -> Result<Option<AsrCredentialEntry>, String> {
+    let credentials = list_asr_credentials()?;
+    Ok(select_asr_credential(credentials, model))
+}
+
+fn select_asr_credential(
+    credentials: Vec<AsrCredentialEntry>,
+    model: &str,
+) -> Option<AsrCredentialEntry> {
+    let model = model.trim();
+    let provider = match model.to_ascii_lowercase().replace('-', "_").as_str() {
+        "whisper_local" | "local" => Some(AsrProviderType::WhisperLocal),
+        "xunfei" => Some(AsrProviderType::Xunfei),
+        "baidu" => Some(AsrProviderType::Baidu),
+        "openai" => Some(AsrProviderType::OpenAI),
+        _ => None,
+    };
+
+    let enabled = || credentials.iter().filter(|c| !c.disabled);
+    enabled()
+        .find(|c| c.id == model)
+        .or_else(|| provider.and_then(|p| enabled().find(|c| c.provider == p)))
+        .or_else(|| enabled().find(|c| c.is_default))
+        .cloned()
+}
+
 /// 获取首个启用的指定 Provider 凭证
 pub fn get_enabled_asr_credential_by_provider(
     provider: AsrProviderType,
@@ -60,6 +90,12 @@ pub fn get_enabled_asr_credential_by_provider(
         .find(|credential| credential.provider == provider && !credential.disabled))
 }
 
+/// 获取配置的语音合成服务商（`voice.tts_service`）
+pub fn get_tts_service() -> Result<Option<String>, String> {
+    let config = load_config().map_err(|e| e.to_string())?;
+    Ok(config.voice.tts_service)
+}
+
 /// 获取指令列表
 pub fn get_instructions() -> Result<Vec<VoiceInstruction>, String> {
     let config = load_config().map_err(|e| e.to_string())?;
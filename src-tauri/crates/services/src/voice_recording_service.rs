@@ -5,6 +5,9 @@
 
 use parking_lot::Mutex;
 use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+use super::voice_command_service::TranscribeResult;
 
 pub use voice_core::{AudioDeviceInfo, RecordingCommand, RecordingResponse, RecordingService};
 
@@ -29,6 +32,14 @@ impl Default for RecordingServiceState {
     }
 }
 
+/// 流式识别任务状态（Tauri State 包装）
+///
+/// 持有 `start_streaming_transcription` 启动的识别任务，停止录音后等待其最终结果。
+#[derive(Default)]
+pub struct StreamingTranscriptionState(
+    pub tokio::sync::Mutex<Option<JoinHandle<Result<Option<TranscribeResult>, String>>>>,
+);
+
 /// 创建录音服务状态
 pub fn create_recording_service_state() -> RecordingServiceState {
     RecordingServiceState::new()
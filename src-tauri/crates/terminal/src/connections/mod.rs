@@ -6,6 +6,9 @@
 //! - `local_pty` - 本地 PTY 连接
 //! - `ssh_connection` - SSH 远程连接
 //! - `ssh_shell_proc` - SSH 远程 Shell 进程
+//! - `ssh_forward` - SSH 端口转发（本地 / 远程 / SOCKS5 动态）
+//! - `ssh_registry` - SSH 连接注册表（按名称复用已认证连接，支持 ProxyJump）
+//! - `sftp` - SFTP 客户端
 //! - `wsl_connection` - WSL 连接（仅 Windows）
 //! - `connection_router` - 连接类型路由
 //! - `connection_config` - 连接配置持久化
@@ -13,7 +16,10 @@
 pub mod connection_config;
 pub mod connection_router;
 pub mod local_pty;
+pub mod sftp;
 pub mod ssh_connection;
+pub mod ssh_forward;
+pub mod ssh_registry;
 pub mod ssh_shell_proc;
 pub mod wsl_connection;
 
@@ -23,12 +29,15 @@ pub use connection_config::{
 };
 pub use connection_router::{ConnectionInfo, ConnectionRouter, ConnectionType};
 pub use local_pty::ShellProc;
+pub use sftp::{RemoteEntry, RemoteFile, SftpClient};
 pub use ssh_connection::{
     build_default_auth_methods, get_default_identity_files, is_local_conn_name,
     is_ssh_agent_available, is_ssh_conn_name, ConnKeywords, ConnStatus, ConnectionState,
     HostKeyVerification, NoOpAuthCallback, SSHAuthCallback, SSHAuthMethod, SSHConfigEntry,
     SSHConfigParser, SSHConn, SSHOpts, DEFAULT_SSH_PORT, MAX_PROXY_JUMP_DEPTH,
 };
+pub use ssh_forward::{ForwardInfo, ForwardSpec, ForwardStatus, PortForwarder};
+pub use ssh_registry::SSHConnRegistry;
 pub use ssh_shell_proc::SSHShellProc;
 pub use wsl_connection::{
     is_wsl_conn_name, WSLConn, WSLDistro, WSLDistroState, WSLOpts, WSLShellProc,
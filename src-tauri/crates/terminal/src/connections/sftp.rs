@@ -0,0 +1,382 @@
+//! SFTP 客户端
+//!
+//! 在已认证的 `SSHConn` 会话上打开 SFTP 子系统，提供远程文件浏览与读写。
+//!
+//! ## 功能
+//! - 目录列表、stat、realpath
+//! - 按偏移打开远程文件读写（用于断点续传）
+//! - 创建、重命名、删除（支持递归）
+//!
+//! 会话可能已被 Shell 或端口转发切换为非阻塞模式，
+//! 所有操作都会重试 EAGAIN，对调用方表现为阻塞调用。
+//!
+//! _Requirements: 4.14_
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use ssh2::{ErrorCode, FileStat, OpenFlags, OpenType, Sftp};
+
+use super::ssh_connection::SSHConn;
+use super::ssh_forward::retry_would_block;
+use crate::error::TerminalError;
+
+/// libssh2 readdir 结束时返回的错误码
+const LIBSSH2_ERROR_FILE: i32 = -16;
+
+/// SFTP: 文件不存在
+const LIBSSH2_FX_NO_SUCH_FILE: i32 = 2;
+
+/// 读写 EAGAIN 重试间隔
+const IO_RETRY_INTERVAL: Duration = Duration::from_millis(5);
+
+/// 单次读写的最长等待
+const IO_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 新建目录 / 文件的默认权限
+const DEFAULT_DIR_MODE: i32 = 0o755;
+const DEFAULT_FILE_MODE: i32 = 0o644;
+
+/// 远程文件条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteEntry {
+    /// 文件名
+    pub name: String,
+    /// 远程绝对路径
+    pub path: String,
+    /// 是否为目录（符号链接按目标判断）
+    pub is_dir: bool,
+    /// 是否为符号链接
+    pub is_symlink: bool,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 修改时间（Unix 时间戳毫秒）
+    pub modified_at: u64,
+    /// 权限位（如 0o644）
+    pub mode: Option<u32>,
+}
+
+impl RemoteEntry {
+    fn from_stat(path: &str, stat: &FileStat, is_symlink: bool) -> Self {
+        let name = path
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .filter(|n| !n.is_empty())
+            .unwrap_or("/")
+            .to_string();
+        Self {
+            name,
+            path: path.to_string(),
+            is_dir: stat.is_dir(),
+            is_symlink,
+            size: stat.size.unwrap_or(0),
+            modified_at: stat.mtime.map(|t| t * 1000).unwrap_or(0),
+            mode: stat.perm.map(|p| p & 0o777),
+        }
+    }
+}
+
+/// 拼接远程路径
+pub fn join_remote_path(dir: &str, name: &str) -> String {
+    if dir.ends_with('/') {
+        format!("{dir}{name}")
+    } else {
+        format!("{dir}/{name}")
+    }
+}
+
+/// 远程路径的父目录（根目录返回 None）
+pub fn remote_parent(path: &str) -> Option<String> {
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() {
+        return None;
+    }
+    match trimmed.rfind('/') {
+        Some(0) => Some("/".to_string()),
+        Some(pos) => Some(trimmed[..pos].to_string()),
+        None => None,
+    }
+}
+
+fn sftp_error(action: &str, path: &str, e: ssh2::Error) -> TerminalError {
+    TerminalError::SftpFailed(format!("{action} {path} 失败: {e}"))
+}
+
+fn is_not_found(e: &ssh2::Error) -> bool {
+    matches!(e.code(), ErrorCode::SFTP(LIBSSH2_FX_NO_SUCH_FILE))
+}
+
+/// SFTP 客户端
+///
+/// 持有所属连接的引用，连接断开后需要重新创建。
+pub struct SftpClient {
+    conn: Arc<SSHConn>,
+    sftp: Mutex<Sftp>,
+}
+
+impl SftpClient {
+    /// 在已认证的连接上打开 SFTP 子系统
+    pub fn open(conn: Arc<SSHConn>) -> Result<Self, TerminalError> {
+        let session = conn
+            .get_session()
+            .ok_or_else(|| TerminalError::SftpFailed("SSH 会话不存在".to_string()))?;
+        let sftp = retry_would_block(|| session.sftp())
+            .map_err(|e| TerminalError::SftpFailed(format!("打开 SFTP 子系统失败: {e}")))?;
+        tracing::info!("[SFTP] 已打开 SFTP 子系统: {}", conn.opts());
+        Ok(Self {
+            conn,
+            sftp: Mutex::new(sftp),
+        })
+    }
+
+    /// 所属连接
+    pub fn connection(&self) -> &Arc<SSHConn> {
+        &self.conn
+    }
+
+    /// 连接是否仍可用
+    pub fn is_alive(&self) -> bool {
+        self.conn.is_connected()
+    }
+
+    /// 解析为远程绝对路径（`~` 与 `~/...` 相对于远程主目录）
+    pub fn realpath(&self, path: &str) -> Result<String, TerminalError> {
+        let path = match path {
+            "" | "~" => ".".to_string(),
+            p => match p.strip_prefix("~/") {
+                Some(rest) => format!("./{rest}"),
+                None => p.to_string(),
+            },
+        };
+        let sftp = self.sftp.lock();
+        retry_would_block(|| sftp.realpath(Path::new(&path)))
+            .map(|p| p.to_string_lossy().to_string())
+            .map_err(|e| sftp_error("解析路径", &path, e))
+    }
+
+    /// 远程主目录
+    pub fn home_dir(&self) -> Result<String, TerminalError> {
+        self.realpath("~")
+    }
+
+    /// 获取文件信息（不跟随符号链接，不存在时返回 None）
+    ///
+    /// 符号链接返回链接本身的信息，`is_dir` 为 false，避免递归遍历时沿链接进入环路。
+    pub fn stat(&self, path: &str) -> Result<Option<RemoteEntry>, TerminalError> {
+        let sftp = self.sftp.lock();
+        let lstat = match retry_would_block(|| sftp.lstat(Path::new(path))) {
+            Ok(stat) => stat,
+            Err(e) if is_not_found(&e) => return Ok(None),
+            Err(e) => return Err(sftp_error("读取文件信息", path, e)),
+        };
+        let is_symlink = lstat.file_type().is_symlink();
+        Ok(Some(RemoteEntry::from_stat(path, &lstat, is_symlink)))
+    }
+
+    /// 列出目录内容（不含 `.` 与 `..`）
+    pub fn list_dir(&self, path: &str) -> Result<Vec<RemoteEntry>, TerminalError> {
+        let sftp = self.sftp.lock();
+        let mut dir = retry_would_block(|| sftp.opendir(Path::new(path)))
+            .map_err(|e| sftp_error("打开目录", path, e))?;
+
+        let mut entries = Vec::new();
+        loop {
+            let (name, stat) = match retry_would_block(|| dir.readdir()) {
+                Ok(item) => item,
+                Err(e) if matches!(e.code(), ErrorCode::Session(LIBSSH2_ERROR_FILE)) => break,
+                Err(e) => return Err(sftp_error("读取目录", path, e)),
+            };
+            let name = name.to_string_lossy().to_string();
+            if name == "." || name == ".." {
+                continue;
+            }
+            let entry_path = join_remote_path(path, &name);
+            let is_symlink = stat.file_type().is_symlink();
+            let stat = if is_symlink {
+                retry_would_block(|| sftp.stat(Path::new(&entry_path))).unwrap_or(stat)
+            } else {
+                stat
+            };
+            entries.push(RemoteEntry::from_stat(&entry_path, &stat, is_symlink));
+        }
+        Ok(entries)
+    }
+
+    /// 从指定偏移打开远程文件读取
+    pub fn open_reader(&self, path: &str, offset: u64) -> Result<RemoteFile, TerminalError> {
+        let sftp = self.sftp.lock();
+        let mut file = retry_would_block(|| {
+            sftp.open_mode(Path::new(path), OpenFlags::READ, 0, OpenType::File)
+        })
+        .map_err(|e| sftp_error("打开文件", path, e))?;
+        if offset > 0 {
+            file.seek(SeekFrom::Start(offset))
+                .map_err(|e| TerminalError::SftpFailed(format!("定位 {path} 失败: {e}")))?;
+        }
+        Ok(RemoteFile { file })
+    }
+
+    /// 打开远程文件写入
+    ///
+    /// `offset` 为 None 时截断重写；否则从该偏移续写（断点续传）。
+    pub fn open_writer(
+        &self,
+        path: &str,
+        offset: Option<u64>,
+    ) -> Result<RemoteFile, TerminalError> {
+        let flags = match offset {
+            None => OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE,
+            Some(_) => OpenFlags::WRITE | OpenFlags::CREATE,
+        };
+        let sftp = self.sftp.lock();
+        let mut file = retry_would_block(|| {
+            sftp.open_mode(Path::new(path), flags, DEFAULT_FILE_MODE, OpenType::File)
+        })
+        .map_err(|e| sftp_error("打开文件", path, e))?;
+        if let Some(offset) = offset.filter(|o| *o > 0) {
+            file.seek(SeekFrom::Start(offset))
+                .map_err(|e| TerminalError::SftpFailed(format!("定位 {path} 失败: {e}")))?;
+        }
+        Ok(RemoteFile { file })
+    }
+
+    /// 读取远程文件（最多 max_size 字节）
+    pub fn read_file(&self, path: &str, max_size: usize) -> Result<Vec<u8>, TerminalError> {
+        let mut reader = self.open_reader(path, 0)?;
+        let mut data = Vec::new();
+        (&mut reader)
+            .take(max_size as u64)
+            .read_to_end(&mut data)
+            .map_err(|e| TerminalError::SftpFailed(format!("读取 {path} 失败: {e}")))?;
+        Ok(data)
+    }
+
+    /// 覆盖写入远程文件（远程编辑保存）
+    pub fn write_file(&self, path: &str, data: &[u8]) -> Result<(), TerminalError> {
+        let mut writer = self.open_writer(path, None)?;
+        writer
+            .write_all(data)
+            .map_err(|e| TerminalError::SftpFailed(format!("写入 {path} 失败: {e}")))
+    }
+
+    /// 创建空文件（已存在时报错）
+    pub fn create_file(&self, path: &str) -> Result<(), TerminalError> {
+        let sftp = self.sftp.lock();
+        retry_would_block(|| {
+            sftp.open_mode(
+                Path::new(path),
+                OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::EXCLUSIVE,
+                DEFAULT_FILE_MODE,
+                OpenType::File,
+            )
+        })
+        .map(|_| ())
+        .map_err(|e| sftp_error("创建文件", path, e))
+    }
+
+    /// 创建目录（递归创建缺失的父目录）
+    pub fn create_dir_all(&self, path: &str) -> Result<(), TerminalError> {
+        if self.stat(path)?.is_some() {
+            return Ok(());
+        }
+        if let Some(parent) = remote_parent(path) {
+            self.create_dir_all(&parent)?;
+        }
+        let sftp = self.sftp.lock();
+        retry_would_block(|| sftp.mkdir(Path::new(path), DEFAULT_DIR_MODE))
+            .map_err(|e| sftp_error("创建目录", path, e))
+    }
+
+    /// 重命名
+    pub fn rename(&self, from: &str, to: &str) -> Result<(), TerminalError> {
+        let sftp = self.sftp.lock();
+        retry_would_block(|| sftp.rename(Path::new(from), Path::new(to), None))
+            .map_err(|e| sftp_error("重命名", from, e))
+    }
+
+    /// 删除文件或目录
+    ///
+    /// 非递归删除非空目录会失败；递归删除不跟随符号链接。
+    pub fn remove(&self, path: &str, recursive: bool) -> Result<(), TerminalError> {
+        let entry = self
+            .stat(path)?
+            .ok_or_else(|| TerminalError::SftpFailed(format!("{path} 不存在")))?;
+
+        if entry.is_dir && !entry.is_symlink {
+            if recursive {
+                for child in self.list_dir(path)? {
+                    self.remove(&child.path, true)?;
+                }
+            }
+            let sftp = self.sftp.lock();
+            retry_would_block(|| sftp.rmdir(Path::new(path)))
+                .map_err(|e| sftp_error("删除目录", path, e))
+        } else {
+            let sftp = self.sftp.lock();
+            retry_would_block(|| sftp.unlink(Path::new(path)))
+                .map_err(|e| sftp_error("删除文件", path, e))
+        }
+    }
+}
+
+/// 远程文件句柄
+///
+/// 读写时重试非阻塞会话的 WouldBlock。
+pub struct RemoteFile {
+    file: ssh2::File,
+}
+
+fn retry_io<T>(mut op: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let deadline = Instant::now() + IO_TIMEOUT;
+    loop {
+        match op() {
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock && Instant::now() < deadline => {
+                std::thread::sleep(IO_RETRY_INTERVAL);
+            }
+            result => return result,
+        }
+    }
+}
+
+impl Read for RemoteFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        retry_io(|| self.file.read(buf))
+    }
+}
+
+impl Write for RemoteFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        retry_io(|| self.file.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        retry_io(|| self.file.flush())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join_remote_path() {
+        assert_eq!(join_remote_path("/home/user", "a.txt"), "/home/user/a.txt");
+        assert_eq!(join_remote_path("/", "etc"), "/etc");
+    }
+
+    #[test]
+    fn test_remote_parent() {
+        assert_eq!(
+            remote_parent("/home/user/a.txt").as_deref(),
+            Some("/home/user")
+        );
+        assert_eq!(remote_parent("/home/").as_deref(), Some("/"));
+        assert_eq!(remote_parent("/etc").as_deref(), Some("/"));
+        assert_eq!(remote_parent("/"), None);
+    }
+}
@@ -9,6 +9,7 @@
 //! - 远程 PTY 创建和数据转发
 //! - SSH 配置文件解析
 //! - known_hosts 验证
+//! - 端口转发（LocalForward / RemoteForward / DynamicForward）
 //!
 //! ## Requirements
 //! - 4.1: 解析连接字符串
@@ -19,6 +20,7 @@
 //! - 4.10: 连接断开处理
 //! - 4.11: 终端大小同步
 //! - 4.12: SSH 配置文件解析
+//! - 4.13: 端口转发
 //! - 7.1-7.7: 连接状态管理
 
 use std::collections::HashMap;
@@ -32,6 +34,7 @@ use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use ssh2::{KeyboardInteractivePrompt as SshKeyboardInteractivePrompt, Session};
 
+use super::ssh_forward::{open_tunnel, EmitterSlot, ForwardInfo, ForwardSpec, PortForwarder};
 use crate::emit_helper;
 use crate::emitter::TerminalEventEmit;
 use crate::error::TerminalError;
@@ -577,13 +580,19 @@ pub struct SSHConn {
     wsh_error: RwLock<Option<String>>,
     /// 不使用 wsh 的原因
     no_wsh_reason: RwLock<Option<String>>,
-    /// 事件发射器（用于事件广播）
-    app_handle: RwLock<Option<Arc<dyn TerminalEventEmit>>>,
+    /// 事件发射器（用于事件广播，与端口转发共享）
+    app_handle: EmitterSlot,
+    /// 端口转发管理器
+    forwarder: PortForwarder,
+    /// 跳板机连接（ProxyJump，经其隧道建立本连接）
+    proxy_jump: RwLock<Option<Arc<SSHConn>>>,
 }
 
 impl SSHConn {
     /// 创建新的 SSH 连接管理器
     pub fn new(opts: SSHOpts) -> Self {
+        let app_handle: EmitterSlot = Arc::new(RwLock::new(None));
+        let forwarder = PortForwarder::new(opts.to_connection_string(), app_handle.clone());
         Self {
             opts,
             state: RwLock::new(ConnectionState::Init),
@@ -597,7 +606,9 @@ impl SSHConn {
             wsh_version: RwLock::new(None),
             wsh_error: RwLock::new(None),
             no_wsh_reason: RwLock::new(None),
-            app_handle: RwLock::new(None),
+            app_handle,
+            forwarder,
+            proxy_jump: RwLock::new(None),
         }
     }
 
@@ -615,6 +626,26 @@ impl SSHConn {
         *self.app_handle.write() = Some(Arc::new(app_handle));
     }
 
+    /// 设置跳板机
+    ///
+    /// 之后的 `connect` 会经跳板机的 `direct-tcpip` 隧道连接目标主机，
+    /// 跳板机连接需已完成认证。
+    ///
+    /// _Requirements: 4.7_
+    pub fn set_proxy_jump(&self, jump: Arc<SSHConn>) {
+        *self.proxy_jump.write() = Some(jump);
+    }
+
+    /// 经本连接打开到 host:port 的 TCP 隧道
+    ///
+    /// _Requirements: 4.7_
+    pub fn open_tunnel(&self, host: &str, port: u16) -> Result<TcpStream, TerminalError> {
+        let session = self
+            .get_session()
+            .ok_or_else(|| TerminalError::SSHConnectionFailed("跳板机未连接".to_string()))?;
+        open_tunnel(&session, host, port).map_err(TerminalError::SSHConnectionFailed)
+    }
+
     /// 广播连接状态变更事件
     ///
     /// _Requirements: 7.3_
@@ -718,11 +749,19 @@ impl SSHConn {
         let addr = format!("{}:{}", self.opts.ssh_host, self.opts.effective_port());
         tracing::info!("[SSHConn] 正在连接到 {}", addr);
 
-        // 建立 TCP 连接
-        let tcp = match TcpStream::connect(&addr) {
+        // 建立 TCP 连接（配置了跳板机时经跳板机隧道）
+        let jump = self.proxy_jump.read().clone();
+        let tcp = match jump {
+            Some(jump) => {
+                tracing::info!("[SSHConn] 经跳板机 {} 连接", jump.opts());
+                jump.open_tunnel(&self.opts.ssh_host, self.opts.effective_port())
+                    .map_err(|e| e.to_string())
+            }
+            None => TcpStream::connect(&addr).map_err(|e| format!("TCP 连接失败: {e}")),
+        };
+        let tcp = match tcp {
             Ok(stream) => stream,
-            Err(e) => {
-                let error_msg = format!("TCP 连接失败: {e}");
+            Err(error_msg) => {
                 tracing::error!("[SSHConn] {}", error_msg);
                 self.set_state(ConnectionState::Error);
                 self.set_error(Some(error_msg.clone()));
@@ -1066,6 +1105,9 @@ impl SSHConn {
     pub async fn close(&self) -> Result<(), TerminalError> {
         tracing::info!("[SSHConn] 断开连接: {}", self.opts);
 
+        // 先停止端口转发，避免转发线程继续使用会话
+        self.forwarder.stop_all();
+
         // 断开 SSH 会话
         {
             let mut session = self.session.write();
@@ -1306,7 +1348,58 @@ impl SSHConn {
 
         // 3. 执行认证
         self.authenticate_with_callback(auth_methods, callback)
-            .await
+            .await?;
+
+        // 4. 开启配置中的端口转发（失败不影响连接本身）
+        self.start_configured_forwards(conn_flags);
+        Ok(())
+    }
+
+    /// 开启 SSH 配置中的端口转发
+    ///
+    /// 单个转发失败只记录日志并发送 `error` 状态事件，返回成功开启的转发。
+    ///
+    /// _Requirements: 4.13_
+    pub fn start_configured_forwards(&self, conn_flags: &ConnKeywords) -> Vec<ForwardInfo> {
+        let (specs, errors) = ForwardSpec::from_keywords(conn_flags);
+        for error in errors {
+            tracing::warn!("[SSHConn] 忽略端口转发配置: {}", error);
+        }
+        specs
+            .into_iter()
+            .filter_map(|spec| match self.add_forward(spec) {
+                Ok(info) => Some(info),
+                Err(e) => {
+                    tracing::warn!("[SSHConn] 开启端口转发失败: {}", e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// 在当前连接上开启端口转发
+    ///
+    /// _Requirements: 4.13_
+    pub fn add_forward(&self, spec: ForwardSpec) -> Result<ForwardInfo, TerminalError> {
+        if !self.is_connected() {
+            return Err(TerminalError::PortForwardFailed("SSH 未连接".to_string()));
+        }
+        let session = self
+            .get_session()
+            .ok_or_else(|| TerminalError::PortForwardFailed("SSH 会话不存在".to_string()))?;
+        self.forwarder.add(&session, spec)
+    }
+
+    /// 停止端口转发
+    ///
+    /// _Requirements: 4.13_
+    pub fn remove_forward(&self, id: &str) -> Result<(), TerminalError> {
+        self.forwarder.remove(id)
+    }
+
+    /// 当前连接上的端口转发
+    pub fn list_forwards(&self) -> Vec<ForwardInfo> {
+        self.forwarder.list()
     }
 }
 
@@ -0,0 +1,1145 @@
+//! SSH 端口转发
+//!
+//! 在已建立的 SSH 会话上提供三种转发，语义与 OpenSSH 一致：
+//! - 本地转发（LocalForward / `-L`）：本地监听，经 `direct-tcpip` 通道连到远端可达的地址；
+//! - 远程转发（RemoteForward / `-R`）：远端监听，连接回传到本地可达的地址；
+//! - 动态转发（DynamicForward / `-D`）：本地 SOCKS5 代理，目标地址由客户端指定。
+//!
+//! 会话在开启转发后切换为非阻塞模式（与 `SSHShellProc` 一致），
+//! 每个监听器和每条隧道各占一个线程，轮询读写。
+//! 转发的状态变化通过 `terminal:ssh-forward` 事件通知前端。
+//! `open_tunnel` 复用同一套隧道实现为 ProxyJump 提供到下一跳的 TCP 连接。
+//!
+//! _Requirements: 4.13_
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use ssh2::{Channel, ErrorCode, Session};
+
+use super::ssh_connection::ConnKeywords;
+use crate::emit_helper;
+use crate::emitter::TerminalEventEmit;
+use crate::error::TerminalError;
+use crate::events::{event_names, SshForwardEvent};
+
+/// libssh2 非阻塞模式下的 EAGAIN
+const LIBSSH2_ERROR_EAGAIN: i32 = -37;
+
+/// 空闲时的轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// 打开通道 / 建立远程监听的超时
+const OPEN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// SOCKS5 握手超时
+const SOCKS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 本地 / 动态转发的默认监听地址（与 OpenSSH 的 GatewayPorts=no 一致）
+const DEFAULT_LOCAL_BIND: &str = "127.0.0.1";
+
+/// 远程转发的默认监听地址
+const DEFAULT_REMOTE_BIND: &str = "localhost";
+
+// ============================================================================
+// 转发配置
+// ============================================================================
+
+type SpecParser = fn(&str) -> Result<ForwardSpec, TerminalError>;
+
+/// 端口转发配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ForwardSpec {
+    /// 本地转发：本地 bind_address:bind_port -> 远端视角的 host:host_port
+    Local {
+        bind_address: String,
+        bind_port: u16,
+        host: String,
+        host_port: u16,
+    },
+    /// 远程转发：远端 bind_address:bind_port -> 本地视角的 host:host_port
+    Remote {
+        bind_address: String,
+        bind_port: u16,
+        host: String,
+        host_port: u16,
+    },
+    /// 动态转发：本地 SOCKS5 代理
+    Dynamic {
+        bind_address: String,
+        bind_port: u16,
+    },
+}
+
+impl ForwardSpec {
+    /// 解析 LocalForward 值
+    ///
+    /// 支持 ssh_config 形式 `[bind_address:]port host:hostport`
+    /// 与命令行形式 `[bind_address:]port:host:hostport`，IPv6 地址用方括号包裹。
+    pub fn parse_local(value: &str) -> Result<Self, TerminalError> {
+        let (bind_address, bind_port, host, host_port) =
+            parse_forward_value(value, DEFAULT_LOCAL_BIND)?;
+        Ok(Self::Local {
+            bind_address,
+            bind_port,
+            host,
+            host_port,
+        })
+    }
+
+    /// 解析 RemoteForward 值（格式同 LocalForward）
+    pub fn parse_remote(value: &str) -> Result<Self, TerminalError> {
+        let (bind_address, bind_port, host, host_port) =
+            parse_forward_value(value, DEFAULT_REMOTE_BIND)?;
+        Ok(Self::Remote {
+            bind_address,
+            bind_port,
+            host,
+            host_port,
+        })
+    }
+
+    /// 解析 DynamicForward 值 `[bind_address:]port`
+    pub fn parse_dynamic(value: &str) -> Result<Self, TerminalError> {
+        let fields = split_fields(value.trim())?;
+        let (bind_address, bind_port) = parse_listen(&fields, DEFAULT_LOCAL_BIND, value)?;
+        Ok(Self::Dynamic {
+            bind_address,
+            bind_port,
+        })
+    }
+
+    /// 从 SSH 配置中收集所有转发，无法解析的条目返回错误信息
+    pub fn from_keywords(keywords: &ConnKeywords) -> (Vec<Self>, Vec<String>) {
+        let mut specs = Vec::new();
+        let mut errors = Vec::new();
+        let groups: [(&Option<Vec<String>>, SpecParser); 3] = [
+            (&keywords.local_forward, Self::parse_local),
+            (&keywords.remote_forward, Self::parse_remote),
+            (&keywords.dynamic_forward, Self::parse_dynamic),
+        ];
+        for (values, parse) in groups {
+            for value in values.iter().flatten() {
+                match parse(value) {
+                    Ok(spec) => specs.push(spec),
+                    Err(e) => errors.push(e.to_string()),
+                }
+            }
+        }
+        (specs, errors)
+    }
+
+    /// 转发 ID（按监听端区分，同一监听地址只能有一个转发）
+    pub fn id(&self) -> String {
+        match self {
+            Self::Local {
+                bind_address,
+                bind_port,
+                ..
+            } => format!("L:{}", join_host_port(bind_address, *bind_port)),
+            Self::Remote {
+                bind_address,
+                bind_port,
+                ..
+            } => format!("R:{}", join_host_port(bind_address, *bind_port)),
+            Self::Dynamic {
+                bind_address,
+                bind_port,
+            } => format!("D:{}", join_host_port(bind_address, *bind_port)),
+        }
+    }
+}
+
+impl std::fmt::Display for ForwardSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Local {
+                bind_address,
+                bind_port,
+                host,
+                host_port,
+            } => write!(
+                f,
+                "-L {} -> {}",
+                join_host_port(bind_address, *bind_port),
+                join_host_port(host, *host_port)
+            ),
+            Self::Remote {
+                bind_address,
+                bind_port,
+                host,
+                host_port,
+            } => write!(
+                f,
+                "-R {} -> {}",
+                join_host_port(bind_address, *bind_port),
+                join_host_port(host, *host_port)
+            ),
+            Self::Dynamic {
+                bind_address,
+                bind_port,
+            } => write!(f, "-D {}", join_host_port(bind_address, *bind_port)),
+        }
+    }
+}
+
+fn join_host_port(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    }
+}
+
+fn invalid_forward(value: &str, reason: &str) -> TerminalError {
+    TerminalError::PortForwardFailed(format!("无效的转发配置 '{value}': {reason}"))
+}
+
+/// 按冒号切分字段，方括号内的冒号（IPv6）不切分
+fn split_fields(value: &str) -> Result<Vec<String>, TerminalError> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut in_brackets = false;
+    for c in value.chars() {
+        match c {
+            '[' if !in_brackets => in_brackets = true,
+            ']' if in_brackets => in_brackets = false,
+            ':' if !in_brackets => fields.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    if in_brackets {
+        return Err(invalid_forward(value, "方括号未闭合"));
+    }
+    fields.push(current);
+    Ok(fields)
+}
+
+fn parse_port(port: &str, value: &str) -> Result<u16, TerminalError> {
+    port.trim()
+        .parse()
+        .map_err(|_| invalid_forward(value, &format!("端口 '{port}' 无效")))
+}
+
+/// 解析监听端 `[bind_address:]port`
+fn parse_listen(
+    fields: &[String],
+    default_bind: &str,
+    value: &str,
+) -> Result<(String, u16), TerminalError> {
+    match fields {
+        [port] => Ok((default_bind.to_string(), parse_port(port, value)?)),
+        [bind, port] => {
+            let bind = match bind.as_str() {
+                "" | "localhost" => default_bind.to_string(),
+                "*" => "0.0.0.0".to_string(),
+                other => other.to_string(),
+            };
+            Ok((bind, parse_port(port, value)?))
+        }
+        _ => Err(invalid_forward(
+            value,
+            "监听地址格式应为 [bind_address:]port",
+        )),
+    }
+}
+
+fn parse_forward_value(
+    value: &str,
+    default_bind: &str,
+) -> Result<(String, u16, String, u16), TerminalError> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let (listen, target) = match parts.as_slice() {
+        // ssh_config 形式：监听端与目标端以空白分隔
+        [listen, target] => (split_fields(listen)?, split_fields(target)?),
+        // 命令行形式：[bind_address:]port:host:hostport
+        [single] => {
+            let fields = split_fields(single)?;
+            if fields.len() < 3 {
+                return Err(invalid_forward(value, "缺少目标地址"));
+            }
+            let split = fields.len() - 2;
+            (fields[..split].to_vec(), fields[split..].to_vec())
+        }
+        _ => {
+            return Err(invalid_forward(
+                value,
+                "格式应为 [bind_address:]port host:hostport",
+            ))
+        }
+    };
+
+    let (bind_address, bind_port) = parse_listen(&listen, default_bind, value)?;
+    match target.as_slice() {
+        [host, port] if !host.is_empty() => Ok((
+            bind_address,
+            bind_port,
+            host.clone(),
+            parse_port(port, value)?,
+        )),
+        _ => Err(invalid_forward(value, "目标地址格式应为 host:hostport")),
+    }
+}
+
+// ============================================================================
+// 转发状态
+// ============================================================================
+
+/// 转发状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForwardStatus {
+    /// 正在监听
+    Active,
+    /// 出错（监听失败或监听器异常退出）
+    Error,
+    /// 已停止
+    Stopped,
+}
+
+/// 转发信息（用于 API 返回与事件）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForwardInfo {
+    pub id: String,
+    pub spec: ForwardSpec,
+    pub status: ForwardStatus,
+    /// 最近一次错误（监听错误或单条隧道的错误）
+    pub error: Option<String>,
+    /// 实际监听端口（远程转发请求端口 0 时由服务端分配）
+    pub bound_port: Option<u16>,
+    /// 当前活跃隧道数
+    pub active_channels: u32,
+}
+
+struct ForwardState {
+    spec: ForwardSpec,
+    status: RwLock<ForwardStatus>,
+    error: RwLock<Option<String>>,
+    bound_port: AtomicU16,
+    active_channels: AtomicU32,
+    shutdown: AtomicBool,
+}
+
+impl ForwardState {
+    fn info(&self) -> ForwardInfo {
+        let bound_port = self.bound_port.load(Ordering::SeqCst);
+        ForwardInfo {
+            id: self.spec.id(),
+            spec: self.spec.clone(),
+            status: *self.status.read(),
+            error: self.error.read().clone(),
+            bound_port: (bound_port != 0).then_some(bound_port),
+            active_channels: self.active_channels.load(Ordering::SeqCst),
+        }
+    }
+
+    fn is_shutdown(&self) -> bool {
+        self.shutdown.load(Ordering::SeqCst)
+    }
+}
+
+/// 活跃隧道计数守卫
+struct ChannelGuard(Arc<ForwardState>);
+
+impl ChannelGuard {
+    fn new(state: &Arc<ForwardState>) -> Self {
+        state.active_channels.fetch_add(1, Ordering::SeqCst);
+        Self(state.clone())
+    }
+}
+
+impl Drop for ChannelGuard {
+    fn drop(&mut self) {
+        self.0.active_channels.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+struct ForwardHandle {
+    state: Arc<ForwardState>,
+    listener_thread: Option<JoinHandle<()>>,
+}
+
+/// 共享的事件发射器槽位（与 `SSHConn` 共用，支持连接建立后再设置发射器）
+pub type EmitterSlot = Arc<RwLock<Option<Arc<dyn TerminalEventEmit>>>>;
+
+#[derive(Clone)]
+struct Notifier {
+    connection: String,
+    emitter: EmitterSlot,
+}
+
+impl Notifier {
+    fn notify(&self, state: &ForwardState) {
+        let Some(emitter) = self.emitter.read().clone() else {
+            return;
+        };
+        let event = SshForwardEvent {
+            connection: self.connection.clone(),
+            forward: state.info(),
+        };
+        if let Err(e) = emit_helper::emit(emitter.as_ref(), event_names::SSH_FORWARD, &event) {
+            tracing::warn!("[SSHForward] 发送转发状态事件失败: {}", e);
+        }
+    }
+
+    /// 记录单条隧道的错误（不影响转发本身的状态）
+    fn channel_error(&self, state: &ForwardState, error: String) {
+        tracing::warn!("[SSHForward] {} 隧道错误: {}", state.spec, error);
+        *state.error.write() = Some(error);
+        self.notify(state);
+    }
+
+    /// 监听器异常退出
+    fn listener_failed(&self, state: &ForwardState, error: String) {
+        tracing::error!("[SSHForward] {} 监听失败: {}", state.spec, error);
+        *state.status.write() = ForwardStatus::Error;
+        *state.error.write() = Some(error);
+        self.notify(state);
+    }
+}
+
+// ============================================================================
+// 转发管理器
+// ============================================================================
+
+/// 单个 SSH 连接上的端口转发管理器
+pub struct PortForwarder {
+    notifier: Notifier,
+    forwards: Mutex<HashMap<String, ForwardHandle>>,
+}
+
+impl PortForwarder {
+    pub fn new(connection: impl Into<String>, emitter: EmitterSlot) -> Self {
+        Self {
+            notifier: Notifier {
+                connection: connection.into(),
+                emitter,
+            },
+            forwards: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 在会话上开启转发
+    ///
+    /// 监听失败时返回错误，同时发送 `error` 状态事件。
+    pub fn add(&self, session: &Session, spec: ForwardSpec) -> Result<ForwardInfo, TerminalError> {
+        let id = spec.id();
+        let mut forwards = self.forwards.lock();
+        if forwards.contains_key(&id) {
+            return Err(TerminalError::PortForwardFailed(format!(
+                "转发已存在: {id}"
+            )));
+        }
+
+        let state = Arc::new(ForwardState {
+            spec: spec.clone(),
+            status: RwLock::new(ForwardStatus::Active),
+            error: RwLock::new(None),
+            bound_port: AtomicU16::new(0),
+            active_channels: AtomicU32::new(0),
+            shutdown: AtomicBool::new(false),
+        });
+
+        // 转发线程与 Shell 共用会话，统一使用非阻塞模式避免互相阻塞
+        session.set_blocking(false);
+
+        let started = match &spec {
+            ForwardSpec::Local {
+                bind_address,
+                bind_port,
+                ..
+            }
+            | ForwardSpec::Dynamic {
+                bind_address,
+                bind_port,
+            } => self.start_local_listener(session, &state, bind_address, *bind_port),
+            ForwardSpec::Remote {
+                bind_address,
+                bind_port,
+                ..
+            } => self.start_remote_listener(session, &state, bind_address, *bind_port),
+        };
+
+        let listener_thread = match started {
+            Ok(thread) => thread,
+            Err(e) => {
+                self.notifier.listener_failed(&state, e.to_string());
+                return Err(e);
+            }
+        };
+
+        tracing::info!("[SSHForward] 已开启转发 {}", spec);
+        self.notifier.notify(&state);
+        let info = state.info();
+        forwards.insert(
+            id,
+            ForwardHandle {
+                state,
+                listener_thread: Some(listener_thread),
+            },
+        );
+        Ok(info)
+    }
+
+    /// 停止并移除转发（已建立的隧道随之关闭）
+    pub fn remove(&self, id: &str) -> Result<(), TerminalError> {
+        let handle = self
+            .forwards
+            .lock()
+            .remove(id)
+            .ok_or_else(|| TerminalError::PortForwardFailed(format!("转发不存在: {id}")))?;
+        self.stop(handle);
+        Ok(())
+    }
+
+    /// 停止所有转发（连接关闭时调用）
+    pub fn stop_all(&self) {
+        let handles: Vec<ForwardHandle> = self.forwards.lock().drain().map(|(_, h)| h).collect();
+        for handle in handles {
+            self.stop(handle);
+        }
+    }
+
+    /// 当前转发列表
+    pub fn list(&self) -> Vec<ForwardInfo> {
+        let mut infos: Vec<ForwardInfo> = self
+            .forwards
+            .lock()
+            .values()
+            .map(|h| h.state.info())
+            .collect();
+        infos.sort_by(|a, b| a.id.cmp(&b.id));
+        infos
+    }
+
+    fn stop(&self, mut handle: ForwardHandle) {
+        handle.state.shutdown.store(true, Ordering::SeqCst);
+        if let Some(thread) = handle.listener_thread.take() {
+            let _ = thread.join();
+        }
+        *handle.state.status.write() = ForwardStatus::Stopped;
+        tracing::info!("[SSHForward] 已停止转发 {}", handle.state.spec);
+        self.notifier.notify(&handle.state);
+    }
+
+    fn start_local_listener(
+        &self,
+        session: &Session,
+        state: &Arc<ForwardState>,
+        bind_address: &str,
+        bind_port: u16,
+    ) -> Result<JoinHandle<()>, TerminalError> {
+        let listener = TcpListener::bind((bind_address, bind_port)).map_err(|e| {
+            TerminalError::PortForwardFailed(format!(
+                "监听 {} 失败: {e}",
+                join_host_port(bind_address, bind_port)
+            ))
+        })?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| TerminalError::PortForwardFailed(format!("设置非阻塞监听失败: {e}")))?;
+        if let Ok(addr) = listener.local_addr() {
+            state.bound_port.store(addr.port(), Ordering::SeqCst);
+        }
+
+        let session = session.clone();
+        let state = state.clone();
+        let notifier = self.notifier.clone();
+        Ok(std::thread::spawn(move || {
+            while !state.is_shutdown() {
+                match listener.accept() {
+                    Ok((stream, peer)) => {
+                        spawn_local_tunnel(&session, &state, &notifier, stream, peer);
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        std::thread::sleep(POLL_INTERVAL);
+                    }
+                    Err(e) => {
+                        notifier.listener_failed(&state, format!("接受连接失败: {e}"));
+                        break;
+                    }
+                }
+            }
+        }))
+    }
+
+    fn start_remote_listener(
+        &self,
+        session: &Session,
+        state: &Arc<ForwardState>,
+        bind_address: &str,
+        bind_port: u16,
+    ) -> Result<JoinHandle<()>, TerminalError> {
+        let (mut listener, bound_port) = retry_would_block(|| {
+            session.channel_forward_listen(bind_port, Some(bind_address), None)
+        })
+        .map_err(|e| {
+            TerminalError::PortForwardFailed(format!(
+                "远端监听 {} 失败: {e}",
+                join_host_port(bind_address, bind_port)
+            ))
+        })?;
+        state.bound_port.store(bound_port, Ordering::SeqCst);
+
+        let ForwardSpec::Remote {
+            host, host_port, ..
+        } = state.spec.clone()
+        else {
+            unreachable!("start_remote_listener 只用于远程转发");
+        };
+        let state = state.clone();
+        let notifier = self.notifier.clone();
+        Ok(std::thread::spawn(move || {
+            while !state.is_shutdown() {
+                match listener.accept() {
+                    Ok(channel) => {
+                        spawn_remote_tunnel(&state, &notifier, channel, host.clone(), host_port);
+                    }
+                    Err(e) if is_would_block(&e) => std::thread::sleep(POLL_INTERVAL),
+                    Err(e) => {
+                        notifier.listener_failed(&state, format!("接受远端连接失败: {e}"));
+                        break;
+                    }
+                }
+            }
+            // listener 在此 drop，ssh2 会向服务端发送 cancel-tcpip-forward
+        }))
+    }
+}
+
+impl Drop for PortForwarder {
+    fn drop(&mut self) {
+        self.stop_all();
+    }
+}
+
+// ============================================================================
+// 隧道
+// ============================================================================
+
+fn is_would_block(e: &ssh2::Error) -> bool {
+    matches!(e.code(), ErrorCode::Session(LIBSSH2_ERROR_EAGAIN))
+}
+
+/// 非阻塞会话上重试返回 EAGAIN 的操作，直到成功、出错或超时
+pub(crate) fn retry_would_block<T>(
+    mut op: impl FnMut() -> Result<T, ssh2::Error>,
+) -> Result<T, ssh2::Error> {
+    let deadline = Instant::now() + OPEN_TIMEOUT;
+    loop {
+        match op() {
+            Err(e) if is_would_block(&e) && Instant::now() < deadline => {
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            result => return result,
+        }
+    }
+}
+
+fn open_direct_channel(
+    session: &Session,
+    host: &str,
+    port: u16,
+    peer: SocketAddr,
+) -> Result<Channel, String> {
+    let peer_ip = peer.ip().to_string();
+    retry_would_block(|| session.channel_direct_tcpip(host, port, Some((&peer_ip, peer.port()))))
+        .map_err(|e| format!("打开到 {} 的通道失败: {e}", join_host_port(host, port)))
+}
+
+/// 经会话打开到 host:port 的隧道，返回本地一端的 TCP 连接
+///
+/// 用于 ProxyJump：下一跳的 SSH 会话直接建立在返回的连接上。
+/// 隧道在任一端关闭时结束。
+pub(crate) fn open_tunnel(session: &Session, host: &str, port: u16) -> Result<TcpStream, String> {
+    session.set_blocking(false);
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .map_err(|e| format!("创建隧道监听失败: {e}"))?;
+    let addr = listener
+        .local_addr()
+        .map_err(|e| format!("获取隧道地址失败: {e}"))?;
+    let channel = open_direct_channel(session, host, port, addr)?;
+
+    let stream = TcpStream::connect(addr).map_err(|e| format!("连接隧道失败: {e}"))?;
+    let (tunnel_end, _) = listener
+        .accept()
+        .map_err(|e| format!("建立隧道失败: {e}"))?;
+    let target = join_host_port(host, port);
+    std::thread::spawn(move || {
+        let shutdown = AtomicBool::new(false);
+        if let Err(e) = pump(tunnel_end, channel, &shutdown) {
+            tracing::warn!("[SSHForward] 到 {} 的隧道异常关闭: {}", target, e);
+        }
+    });
+    Ok(stream)
+}
+
+fn spawn_local_tunnel(
+    session: &Session,
+    state: &Arc<ForwardState>,
+    notifier: &Notifier,
+    stream: TcpStream,
+    peer: SocketAddr,
+) {
+    let session = session.clone();
+    let state = state.clone();
+    let notifier = notifier.clone();
+    std::thread::spawn(move || {
+        let _guard = ChannelGuard::new(&state);
+        let result = match state.spec.clone() {
+            ForwardSpec::Local {
+                host, host_port, ..
+            } => open_direct_channel(&session, &host, host_port, peer).and_then(|channel| {
+                pump(stream, channel, &state.shutdown).map_err(|e| e.to_string())
+            }),
+            ForwardSpec::Dynamic { .. } => run_socks_tunnel(&session, &state, stream, peer),
+            ForwardSpec::Remote { .. } => Ok(()),
+        };
+        if let Err(e) = result {
+            notifier.channel_error(&state, e);
+        }
+    });
+}
+
+fn run_socks_tunnel(
+    session: &Session,
+    state: &ForwardState,
+    mut stream: TcpStream,
+    peer: SocketAddr,
+) -> Result<(), String> {
+    stream
+        .set_nonblocking(false)
+        .and_then(|_| stream.set_read_timeout(Some(SOCKS_HANDSHAKE_TIMEOUT)))
+        .map_err(|e| format!("设置 SOCKS 连接失败: {e}"))?;
+    let (host, port) =
+        socks5_handshake(&mut stream).map_err(|e| format!("SOCKS5 握手失败 ({peer}): {e}"))?;
+
+    let channel = match open_direct_channel(session, &host, port, peer) {
+        Ok(channel) => channel,
+        Err(e) => {
+            let _ = socks5_reply(&mut stream, SOCKS5_REPLY_HOST_UNREACHABLE);
+            return Err(e);
+        }
+    };
+    socks5_reply(&mut stream, SOCKS5_REPLY_SUCCEEDED).map_err(|e| e.to_string())?;
+    stream
+        .set_read_timeout(None)
+        .map_err(|e| format!("设置 SOCKS 连接失败: {e}"))?;
+    pump(stream, channel, &state.shutdown).map_err(|e| e.to_string())
+}
+
+fn spawn_remote_tunnel(
+    state: &Arc<ForwardState>,
+    notifier: &Notifier,
+    mut channel: Channel,
+    host: String,
+    port: u16,
+) {
+    let state = state.clone();
+    let notifier = notifier.clone();
+    std::thread::spawn(move || {
+        let _guard = ChannelGuard::new(&state);
+        let stream = (host.as_str(), port)
+            .to_socket_addrs()
+            .and_then(|mut addrs| {
+                addrs
+                    .next()
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "无法解析地址"))
+            })
+            .and_then(|addr| TcpStream::connect_timeout(&addr, OPEN_TIMEOUT));
+        let result = match stream {
+            Ok(stream) => pump(stream, channel, &state.shutdown).map_err(|e| e.to_string()),
+            Err(e) => {
+                let _ = channel.close();
+                Err(format!("连接 {} 失败: {e}", join_host_port(&host, port)))
+            }
+        };
+        if let Err(e) = result {
+            notifier.channel_error(&state, e);
+        }
+    });
+}
+
+/// 隧道的 SSH 端（抽象出来便于测试数据泵）
+trait TunnelChannel: Read + Write {
+    fn is_eof(&self) -> bool;
+    fn send_eof(&mut self) -> io::Result<()>;
+    fn close(&mut self);
+}
+
+impl TunnelChannel for Channel {
+    fn is_eof(&self) -> bool {
+        self.eof()
+    }
+
+    fn send_eof(&mut self) -> io::Result<()> {
+        Channel::send_eof(self).map_err(io::Error::from)
+    }
+
+    fn close(&mut self) {
+        let _ = Channel::close(self);
+    }
+}
+
+fn is_retryable(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+    )
+}
+
+/// 在 TCP 连接与 SSH 通道之间双向转发数据，直到远端关闭、出错或转发停止
+fn pump<C: TunnelChannel>(
+    mut tcp: TcpStream,
+    mut channel: C,
+    shutdown: &AtomicBool,
+) -> io::Result<()> {
+    tcp.set_nonblocking(true)?;
+    let mut buf = [0u8; 16 * 1024];
+    // TCP -> 通道、通道 -> TCP 方向上尚未写出的数据
+    let mut upstream: Vec<u8> = Vec::new();
+    let mut downstream: Vec<u8> = Vec::new();
+    let mut tcp_eof = false;
+    let mut channel_eof = false;
+
+    let result = loop {
+        if shutdown.load(Ordering::SeqCst) {
+            break Ok(());
+        }
+        let mut progressed = false;
+
+        if upstream.is_empty() && !tcp_eof {
+            match tcp.read(&mut buf) {
+                Ok(0) => {
+                    tcp_eof = true;
+                    progressed = true;
+                    // 本地半关闭，通知远端
+                    if let Err(e) = channel.send_eof() {
+                        if !is_retryable(&e) {
+                            break Err(e);
+                        }
+                    }
+                }
+                Ok(n) => {
+                    upstream.extend_from_slice(&buf[..n]);
+                    progressed = true;
+                }
+                Err(e) if is_retryable(&e) => {}
+                Err(e) => break Err(e),
+            }
+        }
+        if !upstream.is_empty() {
+            match channel.write(&upstream) {
+                Ok(n) => {
+                    upstream.drain(..n);
+                    progressed |= n > 0;
+                }
+                Err(e) if is_retryable(&e) => {}
+                Err(e) => break Err(e),
+            }
+        }
+
+        if downstream.is_empty() && !channel_eof {
+            match channel.read(&mut buf) {
+                Ok(0) => {
+                    if channel.is_eof() {
+                        channel_eof = true;
+                        progressed = true;
+                    }
+                }
+                Ok(n) => {
+                    downstream.extend_from_slice(&buf[..n]);
+                    progressed = true;
+                }
+                Err(e) if is_retryable(&e) => {}
+                Err(e) => break Err(e),
+            }
+        }
+        if !downstream.is_empty() {
+            match tcp.write(&downstream) {
+                Ok(n) => {
+                    downstream.drain(..n);
+                    progressed |= n > 0;
+                }
+                Err(e) if is_retryable(&e) => {}
+                Err(e) => break Err(e),
+            }
+        }
+
+        // 远端已关闭且数据已全部写回本地
+        if channel_eof && downstream.is_empty() {
+            break Ok(());
+        }
+        if !progressed {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    };
+
+    let _ = tcp.shutdown(Shutdown::Both);
+    channel.close();
+    result
+}
+
+// ============================================================================
+// SOCKS5
+// ============================================================================
+
+const SOCKS5_VERSION: u8 = 0x05;
+const SOCKS5_NO_AUTH: u8 = 0x00;
+const SOCKS5_NO_ACCEPTABLE_METHOD: u8 = 0xFF;
+const SOCKS5_CMD_CONNECT: u8 = 0x01;
+const SOCKS5_REPLY_SUCCEEDED: u8 = 0x00;
+const SOCKS5_REPLY_HOST_UNREACHABLE: u8 = 0x04;
+const SOCKS5_REPLY_COMMAND_NOT_SUPPORTED: u8 = 0x07;
+const SOCKS5_REPLY_ADDRESS_NOT_SUPPORTED: u8 = 0x08;
+
+fn socks_error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// SOCKS5 握手（仅支持无认证的 CONNECT），返回目标地址
+fn socks5_handshake<S: Read + Write>(stream: &mut S) -> io::Result<(String, u16)> {
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header)?;
+    if header[0] != SOCKS5_VERSION {
+        return Err(socks_error("仅支持 SOCKS5"));
+    }
+    let mut methods = vec![0u8; header[1] as usize];
+    stream.read_exact(&mut methods)?;
+    if !methods.contains(&SOCKS5_NO_AUTH) {
+        stream.write_all(&[SOCKS5_VERSION, SOCKS5_NO_ACCEPTABLE_METHOD])?;
+        return Err(socks_error("客户端不支持无认证方式"));
+    }
+    stream.write_all(&[SOCKS5_VERSION, SOCKS5_NO_AUTH])?;
+
+    let mut request = [0u8; 4];
+    stream.read_exact(&mut request)?;
+    if request[0] != SOCKS5_VERSION {
+        return Err(socks_error("请求版本号错误"));
+    }
+    if request[1] != SOCKS5_CMD_CONNECT {
+        socks5_reply(stream, SOCKS5_REPLY_COMMAND_NOT_SUPPORTED)?;
+        return Err(socks_error("仅支持 CONNECT 命令"));
+    }
+
+    let host = match request[3] {
+        0x01 => {
+            let mut addr = [0u8; 4];
+            stream.read_exact(&mut addr)?;
+            std::net::Ipv4Addr::from(addr).to_string()
+        }
+        0x03 => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len)?;
+            let mut name = vec![0u8; len[0] as usize];
+            stream.read_exact(&mut name)?;
+            String::from_utf8(name).map_err(|_| socks_error("域名不是有效的 UTF-8"))?
+        }
+        0x04 => {
+            let mut addr = [0u8; 16];
+            stream.read_exact(&mut addr)?;
+            std::net::Ipv6Addr::from(addr).to_string()
+        }
+        _ => {
+            socks5_reply(stream, SOCKS5_REPLY_ADDRESS_NOT_SUPPORTED)?;
+            return Err(socks_error("不支持的地址类型"));
+        }
+    };
+    let mut port = [0u8; 2];
+    stream.read_exact(&mut port)?;
+    Ok((host, u16::from_be_bytes(port)))
+}
+
+fn socks5_reply<S: Write>(stream: &mut S, code: u8) -> io::Result<()> {
+    // BND.ADDR / BND.PORT 对客户端没有意义，统一返回 0.0.0.0:0
+    stream.write_all(&[SOCKS5_VERSION, code, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn test_parse_local_config_form() {
+        let spec = ForwardSpec::parse_local("8080 localhost:80").unwrap();
+        assert_eq!(
+            spec,
+            ForwardSpec::Local {
+                bind_address: "127.0.0.1".to_string(),
+                bind_port: 8080,
+                host: "localhost".to_string(),
+                host_port: 80,
+            }
+        );
+        assert_eq!(spec.id(), "L:127.0.0.1:8080");
+    }
+
+    #[test]
+    fn test_parse_forward_variants() {
+        let spec = ForwardSpec::parse_local("*:5432:db.internal:5432").unwrap();
+        assert_eq!(spec.id(), "L:0.0.0.0:5432");
+
+        let spec = ForwardSpec::parse_local("[::1]:8443 [fd00::2]:443").unwrap();
+        assert_eq!(
+            spec,
+            ForwardSpec::Local {
+                bind_address: "::1".to_string(),
+                bind_port: 8443,
+                host: "fd00::2".to_string(),
+                host_port: 443,
+            }
+        );
+        assert_eq!(spec.id(), "L:[::1]:8443");
+
+        let spec = ForwardSpec::parse_remote("9000 localhost:9000").unwrap();
+        assert_eq!(spec.id(), "R:localhost:9000");
+
+        let spec = ForwardSpec::parse_dynamic("1080").unwrap();
+        assert_eq!(spec.id(), "D:127.0.0.1:1080");
+        assert_eq!(spec.to_string(), "-D 127.0.0.1:1080");
+    }
+
+    #[test]
+    fn test_parse_invalid_forward() {
+        assert!(ForwardSpec::parse_local("8080").is_err());
+        assert!(ForwardSpec::parse_local("abc host:80").is_err());
+        assert!(ForwardSpec::parse_local("8080 host").is_err());
+        assert!(ForwardSpec::parse_dynamic("[::1:1080").is_err());
+    }
+
+    #[test]
+    fn test_from_keywords_collects_errors() {
+        let keywords = ConnKeywords {
+            local_forward: Some(vec!["8080 localhost:80".to_string(), "bad".to_string()]),
+            dynamic_forward: Some(vec!["1080".to_string()]),
+            ..Default::default()
+        };
+        let (specs, errors) = ForwardSpec::from_keywords(&keywords);
+        assert_eq!(specs.len(), 2);
+        assert_eq!(errors.len(), 1);
+    }
+
+    /// 内存中的读写流，用于测试 SOCKS5 握手
+    struct MockStream {
+        input: VecDeque<u8>,
+        output: Vec<u8>,
+    }
+
+    impl Read for MockStream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for MockStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_socks5_handshake_domain() {
+        let mut request = vec![5, 1, 0, 5, 1, 0, 3, 11];
+        request.extend_from_slice(b"example.com");
+        request.extend_from_slice(&443u16.to_be_bytes());
+        let mut stream = MockStream {
+            input: request.into(),
+            output: Vec::new(),
+        };
+
+        let target = socks5_handshake(&mut stream).unwrap();
+        assert_eq!(target, ("example.com".to_string(), 443));
+        assert_eq!(stream.output, vec![5, 0]);
+    }
+
+    #[test]
+    fn test_socks5_rejects_bind_command() {
+        let mut stream = MockStream {
+            input: vec![5, 1, 0, 5, 2, 0, 1, 127, 0, 0, 1, 0, 80].into(),
+            output: Vec::new(),
+        };
+        assert!(socks5_handshake(&mut stream).is_err());
+        assert_eq!(stream.output[2..4], [5, SOCKS5_REPLY_COMMAND_NOT_SUPPORTED]);
+    }
+
+    /// 回显通道：写入的数据原样读回，客户端半关闭后报告 EOF
+    #[derive(Default)]
+    struct EchoChannel {
+        buffer: VecDeque<u8>,
+        eof_sent: bool,
+        closed: Arc<AtomicBool>,
+    }
+
+    impl Read for EchoChannel {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.buffer.is_empty() && !self.eof_sent {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            self.buffer.read(buf)
+        }
+    }
+
+    impl Write for EchoChannel {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.buffer.extend(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl TunnelChannel for EchoChannel {
+        fn is_eof(&self) -> bool {
+            self.eof_sent && self.buffer.is_empty()
+        }
+
+        fn send_eof(&mut self) -> io::Result<()> {
+            self.eof_sent = true;
+            Ok(())
+        }
+
+        fn close(&mut self) {
+            self.closed.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_pump_round_trip() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+
+        let closed = Arc::new(AtomicBool::new(false));
+        let channel = EchoChannel {
+            closed: closed.clone(),
+            ..Default::default()
+        };
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let pump_shutdown = shutdown.clone();
+        let pump_thread = std::thread::spawn(move || pump(server, channel, &pump_shutdown));
+
+        client.write_all(b"hello tunnel").unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+        let mut echoed = Vec::new();
+        client.read_to_end(&mut echoed).unwrap();
+
+        assert_eq!(echoed, b"hello tunnel");
+        pump_thread.join().unwrap().unwrap();
+        assert!(closed.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,214 @@
+//! SSH 连接注册表
+//!
+//! 按连接名称共享已认证的 `SSHConn`，让终端、SFTP 等子系统复用同一条 SSH 连接。
+//!
+//! 连接名称可以是用户配置（connections.json）中的名称、`~/.ssh/config` 中的 Host 别名，
+//! 或 `user@host:port` 形式的连接字符串。配置了 ProxyJump 时逐跳建立连接，
+//! 每一跳都经上一跳的隧道连接，且同样登记在注册表中复用。
+//!
+//! 注册表内的连接使用非交互认证（SSH Agent / 身份文件），
+//! 未知主机密钥会被拒绝，需要先在终端中确认。
+//!
+//! _Requirements: 4.7, 4.14_
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+use parking_lot::Mutex;
+
+use super::connection_config::{ConnectionConfigManager, ConnectionConfigType};
+use super::sftp::SftpClient;
+use super::ssh_connection::{
+    build_default_auth_methods, ConnKeywords, NoOpAuthCallback, SSHConfigParser, SSHConn, SSHOpts,
+};
+use crate::error::TerminalError;
+
+/// SSH 连接注册表
+#[derive(Default)]
+pub struct SSHConnRegistry {
+    conns: Mutex<HashMap<String, Arc<SSHConn>>>,
+    sftp: Mutex<HashMap<String, Arc<SftpClient>>>,
+    /// 串行化建立连接，避免并发请求重复连接同一主机
+    connecting: tokio::sync::Mutex<()>,
+}
+
+static GLOBAL_REGISTRY: OnceLock<SSHConnRegistry> = OnceLock::new();
+
+impl SSHConnRegistry {
+    /// 创建空注册表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 进程级共享注册表
+    pub fn global() -> &'static SSHConnRegistry {
+        GLOBAL_REGISTRY.get_or_init(SSHConnRegistry::new)
+    }
+
+    /// 登记已认证的连接（例如终端打开的连接），供其他子系统复用
+    pub fn register(&self, name: impl Into<String>, conn: Arc<SSHConn>) {
+        let name = name.into();
+        self.sftp.lock().remove(&name);
+        self.conns.lock().insert(name, conn);
+    }
+
+    /// 获取已连接的连接
+    pub fn get(&self, name: &str) -> Option<Arc<SSHConn>> {
+        self.conns
+            .lock()
+            .get(name)
+            .filter(|conn| conn.is_connected())
+            .cloned()
+    }
+
+    /// 获取连接，不存在或已断开时按配置建立
+    pub async fn get_or_connect(&self, name: &str) -> Result<Arc<SSHConn>, TerminalError> {
+        if let Some(conn) = self.get(name) {
+            return Ok(conn);
+        }
+
+        let _guard = self.connecting.lock().await;
+        if let Some(conn) = self.get(name) {
+            return Ok(conn);
+        }
+
+        let (opts, keywords) = resolve_target(name)?;
+
+        // 先逐跳建立跳板机连接
+        let mut jump: Option<Arc<SSHConn>> = None;
+        if let Some(proxy_jump) = keywords.proxy_jump.as_deref() {
+            for (hop_opts, hop_keywords) in
+                SSHConfigParser::resolve_proxy_jump_chain(proxy_jump, 0)?
+            {
+                let hop_name = hop_opts.to_connection_string();
+                let hop = match self.get(&hop_name) {
+                    Some(conn) => conn,
+                    None => {
+                        let hop_opts = apply_host_config(hop_opts, &hop_keywords);
+                        let conn = self.establish(hop_opts, &hop_keywords, jump.take()).await?;
+                        self.register(hop_name, conn.clone());
+                        conn
+                    }
+                };
+                jump = Some(hop);
+            }
+        }
+
+        let conn = self.establish(opts, &keywords, jump).await?;
+        self.register(name, conn.clone());
+        Ok(conn)
+    }
+
+    /// 获取连接上的 SFTP 客户端（按连接复用）
+    pub async fn sftp(&self, name: &str) -> Result<Arc<SftpClient>, TerminalError> {
+        if let Some(client) = self.sftp.lock().get(name).filter(|c| c.is_alive()) {
+            return Ok(client.clone());
+        }
+
+        let conn = self.get_or_connect(name).await?;
+        let client = tokio::task::spawn_blocking(move || SftpClient::open(conn))
+            .await
+            .map_err(|e| TerminalError::Internal(format!("SFTP 任务失败: {e}")))??;
+        let client = Arc::new(client);
+        self.sftp.lock().insert(name.to_string(), client.clone());
+        Ok(client)
+    }
+
+    /// 断开并移除连接
+    pub async fn disconnect(&self, name: &str) -> Result<(), TerminalError> {
+        self.sftp.lock().remove(name);
+        let conn = self.conns.lock().remove(name);
+        match conn {
+            Some(conn) => conn.close().await,
+            None => Ok(()),
+        }
+    }
+
+    async fn establish(
+        &self,
+        opts: SSHOpts,
+        keywords: &ConnKeywords,
+        jump: Option<Arc<SSHConn>>,
+    ) -> Result<Arc<SSHConn>, TerminalError> {
+        tracing::info!("[SSHConnRegistry] 建立连接: {}", opts);
+        let conn = Arc::new(SSHConn::new(opts));
+        if let Some(jump) = jump {
+            conn.set_proxy_jump(jump);
+        }
+        let auth_methods = build_default_auth_methods(keywords, None);
+        conn.connect_and_authenticate(keywords, &auth_methods, &NoOpAuthCallback)
+            .await?;
+        Ok(conn)
+    }
+}
+
+/// 将 ssh_config 中的 HostName / User / Port 应用到连接选项（显式指定的优先）
+fn apply_host_config(opts: SSHOpts, keywords: &ConnKeywords) -> SSHOpts {
+    SSHOpts {
+        ssh_host: keywords.host.clone().unwrap_or(opts.ssh_host),
+        ssh_user: opts.ssh_user.or_else(|| keywords.user.clone()),
+        ssh_port: opts.ssh_port.or(keywords.port),
+    }
+}
+
+/// 解析连接名称为连接选项与配置
+fn resolve_target(name: &str) -> Result<(SSHOpts, ConnKeywords), TerminalError> {
+    // 1. 用户配置中的 SSH 连接
+    let saved = ConnectionConfigManager::new()
+        .load()
+        .ok()
+        .and_then(|file| file.get(name).cloned())
+        .filter(|config| config.conn_type == ConnectionConfigType::Ssh);
+    if let Some(config) = saved {
+        let host = config
+            .host
+            .clone()
+            .ok_or_else(|| TerminalError::SSHConnectionFailed(format!("连接 {name} 缺少主机名")))?;
+        let mut keywords = SSHConfigParser::get_host_config(&host).unwrap_or_default();
+        let mut identity_files = config.identity_files.clone().unwrap_or_default();
+        identity_files.extend(config.identity_file.clone());
+        if !identity_files.is_empty() {
+            keywords.identity_file = Some(identity_files);
+        }
+        if config.proxy_jump.is_some() {
+            keywords.proxy_jump = config.proxy_jump.clone();
+        }
+        let opts = SSHOpts {
+            ssh_host: host,
+            ssh_user: config.user.clone(),
+            ssh_port: config.port,
+        };
+        return Ok((apply_host_config(opts, &keywords), keywords));
+    }
+
+    // 2. ssh_config Host 别名或连接字符串
+    let opts = SSHOpts::parse(name)?;
+    let keywords = SSHConfigParser::get_host_config(&opts.ssh_host).unwrap_or_default();
+    Ok((apply_host_config(opts, &keywords), keywords))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_host_config_prefers_explicit_values() {
+        let keywords = ConnKeywords {
+            host: Some("10.0.0.5".to_string()),
+            user: Some("deploy".to_string()),
+            port: Some(2222),
+            ..Default::default()
+        };
+
+        let opts = apply_host_config(SSHOpts::new("web").with_user("root"), &keywords);
+        assert_eq!(opts.ssh_host, "10.0.0.5");
+        assert_eq!(opts.ssh_user.as_deref(), Some("root"));
+        assert_eq!(opts.ssh_port, Some(2222));
+    }
+
+    #[test]
+    fn test_get_ignores_unknown_connection() {
+        let registry = SSHConnRegistry::new();
+        assert!(registry.get("nobody@example.invalid").is_none());
+    }
+}
@@ -32,6 +32,7 @@ use crate::events::{event_names, SessionStatus, TerminalOutputEvent, TerminalSta
 use crate::persistence::BlockFile;
 
 use super::ssh_connection::SSHConn;
+use super::ssh_forward::retry_would_block;
 
 /// SSH Shell 进程封装
 ///
@@ -94,31 +95,33 @@ impl SSHShellProc {
         );
 
         // 创建 SSH Channel
-        let mut channel = session.channel_session().map_err(|e| {
+        // 开启端口转发后会话已处于非阻塞模式，需要重试 EAGAIN
+        let mut channel = retry_would_block(|| session.channel_session()).map_err(|e| {
             TerminalError::SSHConnectionFailed(format!("创建 SSH Channel 失败: {e}"))
         })?;
 
         // 请求 PTY
         // 使用 xterm-256color 终端类型
-        channel
-            .request_pty(
+        retry_would_block(|| {
+            channel.request_pty(
                 "xterm-256color",
                 None,
                 Some((cols as u32, rows as u32, 0, 0)),
             )
-            .map_err(|e| TerminalError::SSHConnectionFailed(format!("请求远程 PTY 失败: {e}")))?;
+        })
+        .map_err(|e| TerminalError::SSHConnectionFailed(format!("请求远程 PTY 失败: {e}")))?;
 
         // 根据控制器类型启动 Shell 或执行命令
         if controller_type == "cmd" {
             // 命令执行模式
             let cmd = Self::build_remote_command(&block_meta)?;
             tracing::info!("[SSHShellProc] 执行远程命令: {}", cmd);
-            channel.exec(&cmd).map_err(|e| {
+            retry_would_block(|| channel.exec(&cmd)).map_err(|e| {
                 TerminalError::SSHConnectionFailed(format!("执行远程命令失败: {e}"))
             })?;
         } else {
             // Shell 模式 - 启动交互式 Shell
-            channel.shell().map_err(|e| {
+            retry_would_block(|| channel.shell()).map_err(|e| {
                 TerminalError::SSHConnectionFailed(format!("启动远程 Shell 失败: {e}"))
             })?;
         }
@@ -7,6 +7,7 @@
 //! - PTY 操作错误
 //! - 块文件存储错误
 //! - 数据库错误
+//! - 会话录制错误
 //! - 序列化支持
 
 use thiserror::Error;
@@ -85,6 +86,18 @@ pub enum TerminalError {
     /// 无效的连接类型
     #[error("无效的连接类型: {0}")]
     InvalidConnectionType(String),
+
+    /// 端口转发失败
+    #[error("端口转发失败: {0}")]
+    PortForwardFailed(String),
+
+    /// SFTP 操作失败
+    #[error("SFTP 操作失败: {0}")]
+    SftpFailed(String),
+
+    /// 录制错误
+    #[error("录制错误: {0}")]
+    RecordingError(String),
 }
 
 impl From<TerminalError> for String {
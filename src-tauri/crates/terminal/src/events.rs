@@ -8,10 +8,11 @@
 //! - `terminal:shell-integration` - Shell 集成状态变化
 //! - `terminal:clipboard-write` - 剪贴板写入请求
 //! - `terminal:conn-change` - 连接状态变化
+//! - `terminal:ssh-forward` - SSH 端口转发状态变化
 
 use serde::{Deserialize, Serialize};
 
-use crate::connections::ConnStatus;
+use crate::connections::{ConnStatus, ForwardInfo};
 
 /// 会话状态
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -72,6 +73,19 @@ pub struct ConnChangeEvent {
     pub status: ConnStatus,
 }
 
+/// SSH 端口转发状态事件
+///
+/// Event name: `terminal:ssh-forward`
+///
+/// 转发开启、停止、监听出错或单条隧道出错时发送。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshForwardEvent {
+    /// 连接名称
+    pub connection: String,
+    /// 转发详情
+    pub forward: ForwardInfo,
+}
+
 /// 事件名称常量
 pub mod event_names {
     /// 终端输出事件名
@@ -84,4 +98,6 @@ pub mod event_names {
     pub const CLIPBOARD_WRITE: &str = "terminal:clipboard-write";
     /// 连接状态变更事件名
     pub const CONN_CHANGE: &str = "terminal:conn-change";
+    /// SSH 端口转发状态事件名
+    pub const SSH_FORWARD: &str = "terminal:ssh-forward";
 }
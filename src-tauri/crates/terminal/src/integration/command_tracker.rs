@@ -0,0 +1,467 @@
+//! 命令分段器
+//!
+//! 按 OSC 133 标记把终端输出流切分为一条条命令记录：
+//!
+//! ```text
+//! A 提示符 B 用户输入 C 命令输出 D;退出码
+//! ```
+//!
+//! ## 功能
+//! - 记录命令行（优先使用 `133;C;cmdline_url=`，否则取 B 与 C 之间的回显）
+//! - 跟踪 OSC 7 上报的当前目录
+//! - 记录退出码、耗时以及输出在块文件流中的偏移
+//! - 保留去除控制序列后的输出文本（超出上限时保留末尾）
+//!
+//! 分段器只处理字节流，不涉及存储，由 `CommandIndexer` 负责落库。
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use super::osc_parser::{OSCParser, OSCSequence, PromptMarkType};
+
+/// 单条命令保留的输出文本上限（字节）
+pub const MAX_CAPTURED_OUTPUT: usize = 64 * 1024;
+
+/// 命令行回显的捕获上限（字节）
+const MAX_CAPTURED_INPUT: usize = 4 * 1024;
+
+/// 跨数据块缓存的未结束 OSC 序列上限（字节）
+const MAX_PENDING_OSC: usize = 4 * 1024;
+
+/// 已完成的命令
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompletedCommand {
+    /// 命令行
+    pub command: String,
+    /// 执行时的工作目录
+    pub cwd: Option<String>,
+    /// 退出码（Shell 未上报时为 None）
+    pub exit_code: Option<i32>,
+    /// 开始时间（Unix 时间戳，毫秒）
+    pub started_at: i64,
+    /// 结束时间（Unix 时间戳，毫秒）
+    pub finished_at: i64,
+    /// 输出在块文件流中的起始偏移
+    pub output_start: u64,
+    /// 输出在块文件流中的结束偏移（不含）
+    pub output_end: u64,
+    /// 去除控制序列后的输出文本
+    pub output: String,
+}
+
+impl CompletedCommand {
+    /// 命令耗时（毫秒）
+    pub fn duration_ms(&self) -> i64 {
+        (self.finished_at - self.started_at).max(0)
+    }
+
+    /// 是否执行失败（退出码非 0）
+    pub fn is_failure(&self) -> bool {
+        self.exit_code.is_some_and(|code| code != 0)
+    }
+}
+
+/// 分段状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    /// 未见到任何标记或处于提示符阶段
+    Idle,
+    /// B 之后，正在输入命令
+    Input,
+    /// C 之后，命令正在执行
+    Running,
+}
+
+/// 正在执行的命令
+#[derive(Debug)]
+struct RunningCommand {
+    command: String,
+    cwd: Option<String>,
+    started_at: i64,
+    output_start: u64,
+    output: Vec<u8>,
+    /// 输出超过上限后被丢弃的字节数
+    dropped: usize,
+}
+
+/// 命令分段器
+///
+/// 逐块输入终端输出（附带该块在流中的起始偏移），返回期间完成的命令。
+#[derive(Debug)]
+pub struct CommandTracker {
+    phase: Phase,
+    cwd: Option<String>,
+    input: Vec<u8>,
+    running: Option<RunningCommand>,
+    /// 上一块末尾未结束的 OSC 序列
+    pending: Vec<u8>,
+    pending_offset: u64,
+}
+
+impl Default for CommandTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandTracker {
+    /// 创建新的分段器
+    pub fn new() -> Self {
+        Self {
+            phase: Phase::Idle,
+            cwd: None,
+            input: Vec::new(),
+            running: None,
+            pending: Vec::new(),
+            pending_offset: 0,
+        }
+    }
+
+    /// 当前工作目录（最近一次 OSC 7 上报）
+    pub fn current_dir(&self) -> Option<&str> {
+        self.cwd.as_deref()
+    }
+
+    /// 是否有命令正在执行
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// 输入一块输出数据
+    ///
+    /// # 参数
+    /// - `data`: 输出数据
+    /// - `offset`: `data` 第一个字节在块文件流中的偏移
+    ///
+    /// # 返回
+    /// 本块数据中完成的命令
+    pub fn feed(&mut self, data: &[u8], offset: u64) -> Vec<CompletedCommand> {
+        self.feed_at(data, offset, current_timestamp_ms())
+    }
+
+    fn feed_at(&mut self, data: &[u8], offset: u64, now: i64) -> Vec<CompletedCommand> {
+        // 拼接上一块遗留的未结束 OSC 序列
+        let (buf, base) = if self.pending.is_empty() {
+            (data.to_vec(), offset)
+        } else {
+            let mut buf = std::mem::take(&mut self.pending);
+            buf.extend_from_slice(data);
+            (buf, self.pending_offset)
+        };
+
+        let parsed = OSCParser::parse(&buf);
+        let mut completed = Vec::new();
+        let mut cursor = 0;
+
+        for osc in parsed {
+            self.consume_text(&buf[cursor..osc.range.start]);
+            cursor = osc.range.end;
+            let range = base + osc.range.start as u64..base + osc.range.end as u64;
+            if let Some(command) = self.handle_osc(&osc.sequence, range, now) {
+                completed.push(command);
+            }
+        }
+
+        // 末尾未结束的 OSC 序列留到下一块处理
+        let tail = &buf[cursor..];
+        match unterminated_osc_start(tail) {
+            Some(start) if tail.len() - start <= MAX_PENDING_OSC => {
+                self.consume_text(&tail[..start]);
+                self.pending = tail[start..].to_vec();
+                self.pending_offset = base + (cursor + start) as u64;
+            }
+            _ => self.consume_text(tail),
+        }
+
+        completed
+    }
+
+    /// 处理两个 OSC 序列之间的普通输出
+    fn consume_text(&mut self, text: &[u8]) {
+        if text.is_empty() {
+            return;
+        }
+        match self.phase {
+            Phase::Input => {
+                let room = MAX_CAPTURED_INPUT.saturating_sub(self.input.len());
+                self.input.extend_from_slice(&text[..text.len().min(room)]);
+            }
+            Phase::Running => {
+                if let Some(running) = self.running.as_mut() {
+                    running.output.extend_from_slice(text);
+                    // 只保留末尾：失败原因通常出现在输出最后
+                    let limit = MAX_CAPTURED_OUTPUT * 2;
+                    if running.output.len() > limit {
+                        let excess = running.output.len() - MAX_CAPTURED_OUTPUT;
+                        running.output.drain(..excess);
+                        running.dropped += excess;
+                    }
+                }
+            }
+            Phase::Idle => {}
+        }
+    }
+
+    fn handle_osc(
+        &mut self,
+        sequence: &OSCSequence,
+        range: Range<u64>,
+        now: i64,
+    ) -> Option<CompletedCommand> {
+        match sequence {
+            OSCSequence::CurrentDirectory { path, .. } => {
+                self.cwd = Some(path.clone());
+                None
+            }
+            OSCSequence::WaveCommand { command } => {
+                if let Some(path) = command.strip_prefix("setcwd ") {
+                    self.cwd = Some(path.to_string());
+                }
+                None
+            }
+            OSCSequence::PromptMark {
+                mark_type,
+                exit_code,
+                command_line,
+            } => match mark_type {
+                PromptMarkType::PromptStart => {
+                    // 没有 D 就回到提示符（如 Ctrl+C），按未知退出码结束
+                    let finished = self.finish(None, range.start, now);
+                    self.phase = Phase::Idle;
+                    finished
+                }
+                PromptMarkType::CommandStart => {
+                    self.phase = Phase::Input;
+                    self.input.clear();
+                    None
+                }
+                PromptMarkType::CommandExecuted => {
+                    // bash 的 DEBUG trap 可能重复上报 C，保留第一次
+                    if self.running.is_none() {
+                        let command = command_line
+                            .clone()
+                            .unwrap_or_else(|| clean_command_echo(&self.input));
+                        self.running = Some(RunningCommand {
+                            command,
+                            cwd: self.cwd.clone(),
+                            started_at: now,
+                            output_start: range.end,
+                            output: Vec::new(),
+                            dropped: 0,
+                        });
+                    }
+                    self.input.clear();
+                    self.phase = Phase::Running;
+                    None
+                }
+                PromptMarkType::CommandFinished => {
+                    let finished = self.finish(*exit_code, range.start, now);
+                    self.phase = Phase::Idle;
+                    finished
+                }
+                PromptMarkType::Unknown(_) => None,
+            },
+            OSCSequence::Clipboard { .. } | OSCSequence::Unknown { .. } => None,
+        }
+    }
+
+    fn finish(
+        &mut self,
+        exit_code: Option<i32>,
+        output_end: u64,
+        now: i64,
+    ) -> Option<CompletedCommand> {
+        let running = self.running.take()?;
+        if running.command.trim().is_empty() {
+            return None;
+        }
+
+        let mut output = strip_control_sequences(&running.output);
+        if output.len() > MAX_CAPTURED_OUTPUT {
+            let mut cut = output.len() - MAX_CAPTURED_OUTPUT;
+            while !output.is_char_boundary(cut) {
+                cut += 1;
+            }
+            output.drain(..cut);
+        }
+        if running.dropped > 0 {
+            tracing::debug!(
+                "[CommandTracker] 命令输出超出上限，丢弃前 {} 字节: {}",
+                running.dropped,
+                running.command
+            );
+        }
+
+        Some(CompletedCommand {
+            command: running.command.trim().to_string(),
+            cwd: running.cwd,
+            exit_code,
+            started_at: running.started_at,
+            finished_at: now,
+            output_start: running.output_start,
+            output_end: output_end.max(running.output_start),
+            output,
+        })
+    }
+}
+
+/// 查找末尾未结束的 OSC 序列起点
+fn unterminated_osc_start(data: &[u8]) -> Option<usize> {
+    let start = data.windows(2).rposition(|w| w == b"\x1b]")?;
+    let body = &data[start + 2..];
+    let terminated = body
+        .iter()
+        .enumerate()
+        .any(|(i, &b)| b == 0x07 || (b == 0x1b && body.get(i + 1) == Some(&b'\\')));
+    (!terminated).then_some(start)
+}
+
+/// 从 B 与 C 之间的回显中提取命令行
+fn clean_command_echo(input: &[u8]) -> String {
+    let text = strip_control_sequences(input);
+    text.lines().last().unwrap_or_default().trim().to_string()
+}
+
+/// 去除终端控制序列（CSI / OSC / 其他 ESC 序列）和回车，返回纯文本
+pub fn strip_control_sequences(data: &[u8]) -> String {
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+
+    while i < data.len() {
+        match data[i] {
+            0x1b => {
+                i += 1;
+                match data.get(i) {
+                    // CSI: ESC [ 参数 终止字节(0x40-0x7E)
+                    Some(b'[') => {
+                        i += 1;
+                        while i < data.len() && !(0x40..=0x7e).contains(&data[i]) {
+                            i += 1;
+                        }
+                        i += 1;
+                    }
+                    // OSC / DCS 等字符串序列: 以 BEL 或 ST 结束
+                    Some(b']') | Some(b'P') | Some(b'_') | Some(b'^') => {
+                        i += 1;
+                        while i < data.len() {
+                            if data[i] == 0x07 {
+                                i += 1;
+                                break;
+                            }
+                            if data[i] == 0x1b && data.get(i + 1) == Some(&b'\\') {
+                                i += 2;
+                                break;
+                            }
+                            i += 1;
+                        }
+                    }
+                    // 字符集选择等三字节序列
+                    Some(b'(') | Some(b')') | Some(b'#') => i += 2,
+                    Some(_) => i += 1,
+                    None => {}
+                }
+            }
+            b'\r' => i += 1,
+            0x08 => {
+                out.pop();
+                i += 1;
+            }
+            b => {
+                if b >= 0x20 || b == b'\n' || b == b'\t' {
+                    out.push(b);
+                }
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// 获取当前时间戳（毫秒）
+fn current_timestamp_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_command_with_cmdline_and_exit_code() {
+        let mut tracker = CommandTracker::new();
+        let data = b"\x1b]7;file://host/home/u\x1b\\\x1b]133;A\x1b\\$ \x1b]133;C;cmdline_url=cargo%20build\x1b\\\x1b[31merror\x1b[0m: oops\r\n\x1b]133;D;101\x1b\\";
+
+        let done = tracker.feed_at(data, 1000, 10);
+        assert_eq!(done.len(), 1);
+        let cmd = &done[0];
+        assert_eq!(cmd.command, "cargo build");
+        assert_eq!(cmd.cwd.as_deref(), Some("/home/u"));
+        assert_eq!(cmd.exit_code, Some(101));
+        assert!(cmd.is_failure());
+        assert_eq!(cmd.output, "error: oops\n");
+
+        let start = data.windows(4).position(|w| w == b"\x1b[31").unwrap() as u64;
+        let end = data.windows(7).position(|w| w == b"\x1b]133;D").unwrap() as u64;
+        assert_eq!(cmd.output_start, 1000 + start);
+        assert_eq!(cmd.output_end, 1000 + end);
+    }
+
+    #[test]
+    fn test_segment_uses_echo_when_cmdline_missing_and_handles_split_osc() {
+        let mut tracker = CommandTracker::new();
+        assert!(tracker
+            .feed_at(b"\x1b]133;A\x07$ \x1b]133;B\x07ls -a", 0, 1)
+            .is_empty());
+        assert!(tracker
+            .feed_at(b"\r\n\x1b]133;C\x07.  ..\r\n\x1b]13", 23, 2)
+            .is_empty());
+        assert!(tracker.is_running());
+
+        let done = tracker.feed_at(b"3;D;0\x07", 44, 5);
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0].command, "ls -a");
+        assert_eq!(done[0].exit_code, Some(0));
+        assert_eq!(done[0].output, ".  ..\n");
+        assert_eq!(done[0].duration_ms(), 3);
+        assert_eq!(done[0].output_end, 40);
+    }
+
+    #[test]
+    fn test_prompt_without_finish_and_empty_command() {
+        let mut tracker = CommandTracker::new();
+        // 空命令行不记录
+        let done = tracker.feed_at(b"\x1b]133;C;cmdline_url=\x07\x1b]133;D;0\x07", 0, 0);
+        assert!(done.is_empty());
+
+        // 中断后直接出现提示符，退出码未知
+        let done = tracker.feed_at(
+            b"\x1b]133;C;cmdline_url=sleep%2010\x07^C\x1b]133;A\x07",
+            100,
+            0,
+        );
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0].exit_code, None);
+        assert!(!done[0].is_failure());
+    }
+
+    #[test]
+    fn test_output_keeps_tail_when_too_long() {
+        let mut tracker = CommandTracker::new();
+        tracker.feed_at(b"\x1b]133;C;cmdline=yes\x07", 0, 0);
+        let chunk = vec![b'y'; MAX_CAPTURED_OUTPUT];
+        for i in 0..3 {
+            tracker.feed_at(&chunk, (i * chunk.len()) as u64, 0);
+        }
+        let done = tracker.feed_at(b"tail\x1b]133;D;1\x07", 0, 0);
+        assert_eq!(done[0].output.len(), MAX_CAPTURED_OUTPUT);
+        assert!(done[0].output.ends_with("tail"));
+    }
+
+    #[test]
+    fn test_strip_control_sequences() {
+        let text = strip_control_sequences(b"\x1b[1;32mok\x1b[0m\r\nab\x08c\x1b]0;title\x07!");
+        assert_eq!(text, "ok\nac!");
+    }
+}
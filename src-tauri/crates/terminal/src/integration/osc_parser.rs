@@ -53,10 +53,14 @@ pub enum OSCSequence {
     },
 
     /// OSC 133 - 命令提示符标记（Shell Integration）
-    /// 格式: OSC 133 ; type ST
+    /// 格式: OSC 133 ; type [; 参数...] ST
     PromptMark {
         /// 标记类型
         mark_type: PromptMarkType,
+        /// 退出码（D 标记携带，如 `133;D;1`）
+        exit_code: Option<i32>,
+        /// 命令行（C 标记携带，如 `133;C;cmdline_url=ls%20-la`）
+        command_line: Option<String>,
     },
 
     /// OSC 16162 - Wave 特定命令
@@ -75,6 +79,17 @@ pub enum OSCSequence {
     },
 }
 
+impl OSCSequence {
+    /// 创建不带参数的 OSC 133 标记
+    pub fn prompt_mark(mark_type: PromptMarkType) -> Self {
+        Self::PromptMark {
+            mark_type,
+            exit_code: None,
+            command_line: None,
+        }
+    }
+}
+
 /// 命令提示符标记类型（OSC 133）
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PromptMarkType {
@@ -314,14 +329,33 @@ impl OSCParser {
 
     /// 解析 OSC 133 - 命令提示符标记
     ///
-    /// 格式: type (A/B/C/D)
+    /// 格式: type (A/B/C/D)，其后可跟 `;` 分隔的参数：
+    /// - `D;<exit_code>`: 命令退出码
+    /// - `C;cmdline_url=<url 编码命令行>` 或 `C;cmdline=<命令行>`
     ///
     /// _Requirements: 6.3_
     fn parse_osc_133(params: &str) -> Option<OSCSequence> {
-        let mark_char = params.chars().next()?;
+        let mut parts = params.split(';');
+        let mark_char = parts.next()?.chars().next()?;
         let mark_type = PromptMarkType::from_char(mark_char);
 
-        Some(OSCSequence::PromptMark { mark_type })
+        let mut exit_code = None;
+        let mut command_line = None;
+        for part in parts {
+            if let Some(value) = part.strip_prefix("cmdline_url=") {
+                command_line = Some(Self::url_decode(value));
+            } else if let Some(value) = part.strip_prefix("cmdline=") {
+                command_line = Some(value.to_string());
+            } else if mark_type == PromptMarkType::CommandFinished && exit_code.is_none() {
+                exit_code = part.trim().parse().ok();
+            }
+        }
+
+        Some(OSCSequence::PromptMark {
+            mark_type,
+            exit_code,
+            command_line,
+        })
     }
 
     /// 解析 OSC 16162 - Wave 命令
@@ -334,29 +368,29 @@ impl OSCParser {
     }
 
     /// URL 解码
+    ///
+    /// 按字节解码，多字节 UTF-8 字符（如中文路径）可正确还原。
     fn url_decode(input: &str) -> String {
-        let mut result = String::with_capacity(input.len());
-        let mut chars = input.chars().peekable();
+        let bytes = input.as_bytes();
+        let mut result = Vec::with_capacity(bytes.len());
+        let mut i = 0;
 
-        while let Some(c) = chars.next() {
-            if c == '%' {
+        while i < bytes.len() {
+            if bytes[i] == b'%' && i + 2 < bytes.len() {
                 // 尝试解析两个十六进制字符
-                let hex: String = chars.by_ref().take(2).collect();
-                if hex.len() == 2 {
-                    if let Ok(byte) = u8::from_str_radix(&hex, 16) {
-                        result.push(byte as char);
-                        continue;
-                    }
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    result.push(byte);
+                    i += 3;
+                    continue;
                 }
-                // 解析失败，保留原样
-                result.push('%');
-                result.push_str(&hex);
-            } else {
-                result.push(c);
             }
+            // 解析失败，保留原样
+            result.push(bytes[i]);
+            i += 1;
         }
 
-        result
+        String::from_utf8_lossy(&result).into_owned()
     }
 
     /// 从 OSC 52 数据中解码剪贴板内容
@@ -533,7 +567,7 @@ mod tests {
             let results = OSCParser::parse(data);
             assert_eq!(results.len(), 1);
             match &results[0].sequence {
-                OSCSequence::PromptMark { mark_type } => {
+                OSCSequence::PromptMark { mark_type, .. } => {
                     assert_eq!(*mark_type, expected_type);
                 }
                 _ => panic!("Expected PromptMark"),
@@ -652,13 +686,38 @@ mod tests {
 
         assert_eq!(results.len(), 1);
         match &results[0].sequence {
-            OSCSequence::PromptMark { mark_type } => {
+            OSCSequence::PromptMark { mark_type, .. } => {
                 assert_eq!(*mark_type, PromptMarkType::PromptStart);
             }
             _ => panic!("Expected PromptMark"),
         }
     }
 
+    #[test]
+    fn test_parse_osc_133_params() {
+        let results = OSCParser::parse(
+            b"\x1b]133;D;127\x1b\\\x1b]133;C;cmdline_url=ls%20-la%20%E4%B8%AD\x07",
+        );
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[0].sequence,
+            OSCSequence::PromptMark {
+                mark_type: PromptMarkType::CommandFinished,
+                exit_code: Some(127),
+                command_line: None,
+            }
+        );
+        assert_eq!(
+            results[1].sequence,
+            OSCSequence::PromptMark {
+                mark_type: PromptMarkType::CommandExecuted,
+                exit_code: None,
+                command_line: Some("ls -la 中".to_string()),
+            }
+        );
+    }
+
     #[test]
     fn test_prompt_mark_type_roundtrip() {
         let types = [
@@ -270,7 +270,7 @@ impl ShellIntegration {
             OSCSequence::Clipboard { selection, data } => {
                 self.handle_clipboard(selection, data)?;
             }
-            OSCSequence::PromptMark { mark_type } => {
+            OSCSequence::PromptMark { mark_type, .. } => {
                 self.handle_prompt_mark(*mark_type);
             }
             OSCSequence::WaveCommand { command } => {
@@ -579,9 +579,7 @@ mod tests {
         let integration = ShellIntegration::new("test-block".to_string());
 
         // 先设置为 RunningCommand
-        let osc_exec = OSCSequence::PromptMark {
-            mark_type: PromptMarkType::CommandExecuted,
-        };
+        let osc_exec = OSCSequence::prompt_mark(PromptMarkType::CommandExecuted);
         integration.process_osc(&osc_exec).unwrap();
         assert_eq!(
             integration.get_status(),
@@ -589,9 +587,7 @@ mod tests {
         );
 
         // 然后 PromptStart 应该切换到 Ready
-        let osc_prompt = OSCSequence::PromptMark {
-            mark_type: PromptMarkType::PromptStart,
-        };
+        let osc_prompt = OSCSequence::prompt_mark(PromptMarkType::PromptStart);
         integration.process_osc(&osc_prompt).unwrap();
         assert_eq!(integration.get_status(), ShellIntegrationStatus::Ready);
     }
@@ -600,9 +596,7 @@ mod tests {
     fn test_process_osc_133_command_executed() {
         let integration = ShellIntegration::new("test-block".to_string());
 
-        let osc = OSCSequence::PromptMark {
-            mark_type: PromptMarkType::CommandExecuted,
-        };
+        let osc = OSCSequence::prompt_mark(PromptMarkType::CommandExecuted);
 
         integration.process_osc(&osc).unwrap();
         assert_eq!(
@@ -617,18 +611,14 @@ mod tests {
         let integration = ShellIntegration::new("test-block".to_string());
 
         // 先执行命令
-        let osc_exec = OSCSequence::PromptMark {
-            mark_type: PromptMarkType::CommandExecuted,
-        };
+        let osc_exec = OSCSequence::prompt_mark(PromptMarkType::CommandExecuted);
         integration.process_osc(&osc_exec).unwrap();
 
         // 等待一小段时间
         std::thread::sleep(std::time::Duration::from_millis(10));
 
         // 命令结束
-        let osc_finish = OSCSequence::PromptMark {
-            mark_type: PromptMarkType::CommandFinished,
-        };
+        let osc_finish = OSCSequence::prompt_mark(PromptMarkType::CommandFinished);
         integration.process_osc(&osc_finish).unwrap();
 
         assert_eq!(integration.get_status(), ShellIntegrationStatus::Ready);
@@ -693,9 +683,7 @@ mod tests {
         };
         integration.process_osc(&osc_dir).unwrap();
 
-        let osc_exec = OSCSequence::PromptMark {
-            mark_type: PromptMarkType::CommandExecuted,
-        };
+        let osc_exec = OSCSequence::prompt_mark(PromptMarkType::CommandExecuted);
         integration.process_osc(&osc_exec).unwrap();
 
         // 重置
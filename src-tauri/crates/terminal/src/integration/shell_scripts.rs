@@ -45,6 +45,19 @@ __proxycast_osc7() {
     printf '\033]7;file://%s%s\033\\' "${HOSTNAME:-localhost}" "$PWD"
 }
 
+# URL 编码（用于 OSC 133;C 携带命令行）
+__proxycast_urlencode() {
+    local LC_ALL=C str="$1" out="" c i
+    for (( i = 0; i < ${#str}; i++ )); do
+        c="${str:i:1}"
+        case "$c" in
+            [a-zA-Z0-9.~_/-]) out+="$c" ;;
+            *) printf -v c '%%%02X' "'$c"; out+="$c" ;;
+        esac
+    done
+    printf '%s' "$out"
+}
+
 # OSC 133 - 命令提示符标记
 __proxycast_prompt_start() {
     printf '\033]133;A\033\\'
@@ -55,24 +68,30 @@ __proxycast_command_start() {
 }
 
 __proxycast_command_executed() {
-    printf '\033]133;C\033\\'
+    printf '\033]133;C;cmdline_url=%s\033\\' "$(__proxycast_urlencode "$1")"
 }
 
 __proxycast_command_finished() {
-    printf '\033]133;D;%s\033\\' "$?"
+    printf '\033]133;D;%s\033\\' "${1:-$?}"
 }
 
 # 设置 PROMPT_COMMAND
 __proxycast_precmd() {
     local exit_code=$?
-    __proxycast_command_finished
+    __proxycast_command_finished "$exit_code"
     __proxycast_osc7
     __proxycast_prompt_start
+    __proxycast_cmd_running=
     return $exit_code
 }
 
+# DEBUG trap 对每个简单命令都会触发，只在每条命令行的第一次上报
 __proxycast_preexec() {
-    __proxycast_command_executed
+    if [ -n "$__proxycast_cmd_running" ]; then
+        return
+    fi
+    __proxycast_cmd_running=1
+    __proxycast_command_executed "$BASH_COMMAND"
 }
 
 # 安装 preexec 钩子（如果可用）
@@ -85,6 +104,9 @@ if [ -n "$BASH_VERSION" ]; then
         if [ "$BASH_COMMAND" = "$PROMPT_COMMAND" ]; then
             return
         fi
+        case "$BASH_COMMAND" in
+            __proxycast_*) return ;;
+        esac
         __proxycast_preexec
     }
     
@@ -116,6 +138,19 @@ __proxycast_osc7() {
     printf '\033]7;file://%s%s\033\\' "${HOST:-localhost}" "$PWD"
 }
 
+# URL 编码（用于 OSC 133;C 携带命令行）
+__proxycast_urlencode() {
+    local LC_ALL=C str="$1" out="" c i
+    for (( i = 0; i < ${#str}; i++ )); do
+        c="${str:i:1}"
+        case "$c" in
+            [a-zA-Z0-9.~_/-]) out+="$c" ;;
+            *) printf -v c '%%%02X' "'$c"; out+="$c" ;;
+        esac
+    done
+    printf '%s' "$out"
+}
+
 # OSC 133 - 命令提示符标记
 __proxycast_prompt_start() {
     printf '\033]133;A\033\\'
@@ -126,25 +161,25 @@ __proxycast_command_start() {
 }
 
 __proxycast_command_executed() {
-    printf '\033]133;C\033\\'
+    printf '\033]133;C;cmdline_url=%s\033\\' "$(__proxycast_urlencode "$1")"
 }
 
 __proxycast_command_finished() {
-    printf '\033]133;D;%s\033\\' "$?"
+    printf '\033]133;D;%s\033\\' "${1:-$?}"
 }
 
 # precmd 钩子 - 命令执行后
 __proxycast_precmd() {
     local exit_code=$?
-    __proxycast_command_finished
+    __proxycast_command_finished "$exit_code"
     __proxycast_osc7
     __proxycast_prompt_start
     return $exit_code
 }
 
-# preexec 钩子 - 命令执行前
+# preexec 钩子 - 命令执行前（$1 为用户输入的命令行）
 __proxycast_preexec() {
-    __proxycast_command_executed
+    __proxycast_command_executed "$1"
 }
 
 # 注册钩子
@@ -193,7 +228,7 @@ function __proxycast_prompt_start
 end
 
 function __proxycast_command_executed
-    printf '\033]133;C\033\\'
+    printf '\033]133;C;cmdline_url=%s\033\\' (string escape --style=url -- $argv[1])
 end
 
 function __proxycast_command_finished
@@ -208,7 +243,7 @@ function __proxycast_fish_prompt --on-event fish_prompt
 end
 
 function __proxycast_fish_preexec --on-event fish_preexec
-    __proxycast_command_executed
+    __proxycast_command_executed $argv[1]
 end
 
 # 初始化
@@ -235,7 +270,9 @@ function Send-ProxyCastPromptStart {
 }
 
 function Send-ProxyCastCommandExecuted {
-    Write-Host -NoNewline "`e]133;C`e\"
+    param([string]$CommandLine = "")
+    $encoded = [Uri]::EscapeDataString($CommandLine)
+    Write-Host -NoNewline "`e]133;C;cmdline_url=$encoded`e\"
 }
 
 function Send-ProxyCastCommandFinished {
@@ -267,7 +304,7 @@ if (Get-Module -ListAvailable -Name PSReadLine) {
     $existingHandler = (Get-PSReadLineOption).AddToHistoryHandler
     Set-PSReadLineOption -AddToHistoryHandler {
         param([string]$line)
-        Send-ProxyCastCommandExecuted
+        Send-ProxyCastCommandExecuted -CommandLine $line
         if ($existingHandler) {
             return & $existingHandler $line
         }
@@ -9,7 +9,7 @@
 //! - `events` - 事件定义
 //! - `pty_session` - PTY 会话封装
 //! - `session_manager` - 会话管理器
-//! - `persistence` - 持久化存储（块文件、会话元数据）
+//! - `persistence` - 持久化存储（块文件、会话元数据、命令历史、会话录制）
 //! - `block_controller` - 块控制器抽象层
 //! - `connections` - 连接模块（本地 PTY、SSH、WSL）
 //! - `integration` - 集成模块（Shell 集成、OSC 解析、状态重同步）
@@ -57,4 +57,8 @@ pub use integration::{
     resync_controller, ResyncController, ResyncOptions, ResyncResult, TERMINAL_RESET_SEQUENCE,
     TERMINAL_SOFT_RESET_SEQUENCE,
 };
-pub use persistence::{BlockFile, SessionMetadataStore, SessionRecord};
+pub use persistence::{
+    AsciicastRecorder, BlockFile, Cast, CastEvent, CastEventKind, CastHeader, CommandHistoryStore,
+    CommandIndexer, CommandRecord, CommandSearchHit, RecordingInfo, SessionMetadataStore,
+    SessionRecord,
+};
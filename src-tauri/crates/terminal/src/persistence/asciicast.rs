@@ -0,0 +1,703 @@
+//! asciicast v2 录制与回放
+//!
+//! 以 asciicast v2 格式录制终端会话，便于回放和分享调试现场。
+//!
+//! ## 文件格式
+//! 首行为 JSON 头部（版本、终端尺寸、开始时间等），其后每行一个事件 `[时间, 类型, 数据]`：
+//! - `o` - 终端输出
+//! - `i` - 用户输入
+//! - `r` - 尺寸变化（数据为 `列x行`）
+//! - `m` - 标记
+//!
+//! ## 脱敏
+//! 输出中出现密码提示（如 `[sudo] password for user:`）后，到回车为止的输入
+//! 只记录为 `[REDACTED]`，不会写入录制文件。
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::TerminalError;
+use crate::integration::command_tracker::strip_control_sequences;
+
+/// asciicast 格式版本
+pub const CAST_VERSION: u32 = 2;
+/// 录制文件扩展名
+pub const CAST_EXTENSION: &str = "cast";
+/// 脱敏后的输入占位符
+pub const REDACTED_INPUT: &str = "[REDACTED]";
+
+/// 用于检测密码提示的输出尾部最大长度（字符数）
+const PROMPT_TAIL_MAX_CHARS: usize = 256;
+/// 密码提示关键字（小写匹配）
+const PASSWORD_PROMPT_KEYWORDS: &[&str] = &[
+    "password",
+    "passphrase",
+    "passcode",
+    "pin",
+    "verification code",
+    "密码",
+    "口令",
+];
+
+/// 录制文件头部
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CastHeader {
+    /// 格式版本（固定为 2）
+    pub version: u32,
+    /// 终端列数
+    pub width: u16,
+    /// 终端行数
+    pub height: u16,
+    /// 开始时间（Unix 时间戳，秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    /// 标题
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// 回放时的最大空闲时间（秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_time_limit: Option<f64>,
+    /// 环境变量（SHELL、TERM）
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+}
+
+impl CastHeader {
+    /// 创建头部
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            version: CAST_VERSION,
+            width,
+            height,
+            timestamp: None,
+            title: None,
+            idle_time_limit: None,
+            env: HashMap::new(),
+        }
+    }
+}
+
+/// 事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CastEventKind {
+    /// 终端输出
+    Output,
+    /// 用户输入
+    Input,
+    /// 尺寸变化
+    Resize,
+    /// 标记
+    Marker,
+}
+
+impl CastEventKind {
+    /// asciicast 中的事件代码
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Output => "o",
+            Self::Input => "i",
+            Self::Resize => "r",
+            Self::Marker => "m",
+        }
+    }
+
+    /// 从事件代码解析
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "o" => Some(Self::Output),
+            "i" => Some(Self::Input),
+            "r" => Some(Self::Resize),
+            "m" => Some(Self::Marker),
+            _ => None,
+        }
+    }
+}
+
+/// 录制事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CastEvent {
+    /// 相对开始时间（秒）
+    pub time: f64,
+    /// 事件类型
+    pub kind: CastEventKind,
+    /// 事件数据
+    pub data: String,
+}
+
+impl CastEvent {
+    /// 序列化为一行 `[时间, 类型, 数据]`
+    pub fn to_line(&self) -> Result<String, TerminalError> {
+        serde_json::to_string(&(self.time, self.kind.code(), &self.data))
+            .map_err(|e| TerminalError::RecordingError(format!("序列化事件失败: {e}")))
+    }
+
+    /// 从一行 `[时间, 类型, 数据]` 解析
+    pub fn parse_line(line: &str) -> Result<Self, TerminalError> {
+        let (time, code, data): (f64, String, String) = serde_json::from_str(line)
+            .map_err(|e| TerminalError::RecordingError(format!("无效的事件行: {e}")))?;
+        let kind = CastEventKind::from_code(&code)
+            .ok_or_else(|| TerminalError::RecordingError(format!("未知的事件类型: {code}")))?;
+        if !time.is_finite() || time < 0.0 {
+            return Err(TerminalError::RecordingError(format!(
+                "无效的事件时间: {time}"
+            )));
+        }
+        Ok(Self { time, kind, data })
+    }
+
+    /// 解析尺寸变化事件，返回 (列, 行)
+    pub fn resize_size(&self) -> Option<(u16, u16)> {
+        if self.kind != CastEventKind::Resize {
+            return None;
+        }
+        let (cols, rows) = self.data.split_once('x')?;
+        Some((cols.trim().parse().ok()?, rows.trim().parse().ok()?))
+    }
+}
+
+/// 已解析的录制
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cast {
+    /// 头部
+    pub header: CastHeader,
+    /// 事件列表（按时间排序）
+    pub events: Vec<CastEvent>,
+}
+
+impl Cast {
+    /// 从读取器解析 asciicast v2 内容
+    pub fn parse(reader: impl BufRead) -> Result<Self, TerminalError> {
+        let mut lines = reader.lines();
+
+        let header_line = lines
+            .next()
+            .ok_or_else(|| TerminalError::RecordingError("录制文件为空".to_string()))?
+            .map_err(|e| TerminalError::RecordingError(format!("读取录制失败: {e}")))?;
+        let header: CastHeader = serde_json::from_str(&header_line)
+            .map_err(|e| TerminalError::RecordingError(format!("无效的录制头部: {e}")))?;
+        if header.version != CAST_VERSION {
+            return Err(TerminalError::RecordingError(format!(
+                "不支持的 asciicast 版本: {}",
+                header.version
+            )));
+        }
+
+        let mut events = Vec::new();
+        for line in lines {
+            let line =
+                line.map_err(|e| TerminalError::RecordingError(format!("读取录制失败: {e}")))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            events.push(CastEvent::parse_line(&line)?);
+        }
+        events.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        Ok(Self { header, events })
+    }
+
+    /// 从文件加载
+    pub fn load(path: &Path) -> Result<Self, TerminalError> {
+        let file = File::open(path)
+            .map_err(|e| TerminalError::RecordingError(format!("无法打开录制 {path:?}: {e}")))?;
+        Self::parse(BufReader::new(file))
+    }
+
+    /// 录制时长（秒）
+    pub fn duration(&self) -> f64 {
+        self.events.last().map(|e| e.time).unwrap_or(0.0)
+    }
+
+    /// 计算回放时每个事件前的等待时间
+    ///
+    /// # 参数
+    /// - `speed`: 回放速度倍数（必须大于 0）
+    /// - `idle_time_limit`: 最大空闲时间（秒），未指定时使用头部中的值
+    pub fn playback_delays(
+        &self,
+        speed: f64,
+        idle_time_limit: Option<f64>,
+    ) -> Result<Vec<Duration>, TerminalError> {
+        if !speed.is_finite() || speed <= 0.0 {
+            return Err(TerminalError::RecordingError(format!(
+                "无效的回放速度: {speed}"
+            )));
+        }
+        let idle_limit = idle_time_limit
+            .or(self.header.idle_time_limit)
+            .filter(|limit| limit.is_finite() && *limit > 0.0);
+
+        let mut previous = 0.0;
+        Ok(self
+            .events
+            .iter()
+            .map(|event| {
+                let mut gap = (event.time - previous).max(0.0);
+                previous = event.time;
+                if let Some(limit) = idle_limit {
+                    gap = gap.min(limit);
+                }
+                Duration::from_secs_f64(gap / speed)
+            })
+            .collect())
+    }
+}
+
+/// 录制文件信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingInfo {
+    /// 文件路径
+    pub path: String,
+    /// 标题
+    pub title: Option<String>,
+    /// 终端列数
+    pub width: u16,
+    /// 终端行数
+    pub height: u16,
+    /// 开始时间（Unix 时间戳，秒）
+    pub timestamp: Option<i64>,
+    /// 时长（秒）
+    pub duration: f64,
+    /// 事件数
+    pub event_count: usize,
+    /// 文件大小（字节）
+    pub size: u64,
+}
+
+impl RecordingInfo {
+    /// 读取录制文件信息
+    pub fn from_path(path: &Path) -> Result<Self, TerminalError> {
+        let cast = Cast::load(path)?;
+        let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        Ok(Self {
+            path: path.to_string_lossy().into_owned(),
+            title: cast.header.title.clone(),
+            width: cast.header.width,
+            height: cast.header.height,
+            timestamp: cast.header.timestamp,
+            duration: cast.duration(),
+            event_count: cast.events.len(),
+            size,
+        })
+    }
+}
+
+/// 默认录制目录
+pub fn default_recordings_dir() -> Result<PathBuf, TerminalError> {
+    let home = dirs::home_dir()
+        .ok_or_else(|| TerminalError::RecordingError("无法获取主目录".to_string()))?;
+    Ok(home.join(".proxycast").join("terminal_recordings"))
+}
+
+/// 列出目录中的录制文件（按开始时间倒序）
+///
+/// 无法解析的文件会被跳过。
+pub fn list_recordings(dir: &Path) -> Result<Vec<RecordingInfo>, TerminalError> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let entries = fs::read_dir(dir)
+        .map_err(|e| TerminalError::RecordingError(format!("无法读取录制目录: {e}")))?;
+    let mut recordings: Vec<RecordingInfo> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some(CAST_EXTENSION))
+        .filter_map(|path| match RecordingInfo::from_path(&path) {
+            Ok(info) => Some(info),
+            Err(e) => {
+                tracing::warn!("[录制] 跳过无效录制 {:?}: {}", path, e);
+                None
+            }
+        })
+        .collect();
+    recordings.sort_by_key(|r| std::cmp::Reverse(r.timestamp));
+    Ok(recordings)
+}
+
+/// 导入外部 `.cast` 文件到录制目录
+///
+/// 校验格式后复制，目标文件已存在时追加序号。
+pub fn import_recording(source: &Path, dir: &Path) -> Result<RecordingInfo, TerminalError> {
+    Cast::load(source)?;
+
+    fs::create_dir_all(dir)
+        .map_err(|e| TerminalError::RecordingError(format!("无法创建录制目录: {e}")))?;
+    let stem = source
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .filter(|s| !s.is_empty())
+        .unwrap_or("recording");
+
+    let mut target = dir.join(format!("{stem}.{CAST_EXTENSION}"));
+    let mut index = 1;
+    while target.exists() {
+        target = dir.join(format!("{stem}-{index}.{CAST_EXTENSION}"));
+        index += 1;
+    }
+
+    fs::copy(source, &target)
+        .map_err(|e| TerminalError::RecordingError(format!("导入录制失败: {e}")))?;
+    RecordingInfo::from_path(&target)
+}
+
+/// 导出录制文件到指定路径
+pub fn export_recording(source: &Path, destination: &Path) -> Result<(), TerminalError> {
+    Cast::load(source)?;
+    if let Some(parent) = destination.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| TerminalError::RecordingError(format!("无法创建导出目录: {e}")))?;
+    }
+    fs::copy(source, destination)
+        .map_err(|e| TerminalError::RecordingError(format!("导出录制失败: {e}")))?;
+    Ok(())
+}
+
+/// 增量 UTF-8 解码器
+///
+/// 保留块尾不完整的多字节字符，拼接到下一块再解码。
+#[derive(Default)]
+struct Utf8Decoder {
+    pending: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    fn decode(&mut self, data: &[u8]) -> String {
+        self.pending.extend_from_slice(data);
+        let complete = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+        let rest = self.pending.split_off(complete);
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending = rest;
+        text
+    }
+}
+
+/// 密码输入脱敏器
+#[derive(Default)]
+struct PasswordRedactor {
+    /// 当前输出行（去除控制序列后）
+    line: String,
+    /// 是否正在脱敏输入
+    active: bool,
+    /// 本轮是否已写入占位符
+    placeholder_written: bool,
+}
+
+impl PasswordRedactor {
+    /// 观察终端输出，检测密码提示
+    fn observe_output(&mut self, data: &[u8]) {
+        let text = strip_control_sequences(data);
+        match text.rfind('\n') {
+            Some(pos) => {
+                self.line = text[pos + 1..].to_string();
+                self.reset();
+            }
+            None => self.line.push_str(&text),
+        }
+
+        let len = self.line.chars().count();
+        if len > PROMPT_TAIL_MAX_CHARS {
+            self.line = self
+                .line
+                .chars()
+                .skip(len - PROMPT_TAIL_MAX_CHARS)
+                .collect();
+        }
+
+        if is_password_prompt(&self.line) {
+            self.active = true;
+        }
+    }
+
+    /// 过滤输入，返回应记录的内容
+    fn filter_input(&mut self, text: &str) -> Option<String> {
+        if !self.active {
+            return Some(text.to_string());
+        }
+
+        let end = text.find(['\r', '\n', '\x03', '\x04']);
+        let mut recorded = String::new();
+        if !self.placeholder_written {
+            recorded.push_str(REDACTED_INPUT);
+            self.placeholder_written = true;
+        }
+        if let Some(pos) = end {
+            recorded.push_str(&text[pos..]);
+            self.line.clear();
+            self.reset();
+        }
+
+        (!recorded.is_empty()).then_some(recorded)
+    }
+
+    fn reset(&mut self) {
+        self.active = false;
+        self.placeholder_written = false;
+    }
+}
+
+/// 判断输出行是否以密码提示结尾
+fn is_password_prompt(line: &str) -> bool {
+    let line = line.trim_end().to_lowercase();
+    if !(line.ends_with(':') || line.ends_with('：')) {
+        return false;
+    }
+    PASSWORD_PROMPT_KEYWORDS
+        .iter()
+        .any(|keyword| line.contains(keyword))
+}
+
+/// asciicast 录制器
+///
+/// 录制为显式开启，事件按到达顺序追加写入文件。
+pub struct AsciicastRecorder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    started_at: Instant,
+    output_decoder: Utf8Decoder,
+    input_decoder: Utf8Decoder,
+    redactor: PasswordRedactor,
+}
+
+impl AsciicastRecorder {
+    /// 创建录制文件并写入头部
+    pub fn create(path: impl Into<PathBuf>, header: &CastHeader) -> Result<Self, TerminalError> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| TerminalError::RecordingError(format!("无法创建录制目录: {e}")))?;
+        }
+
+        let file = File::create(&path)
+            .map_err(|e| TerminalError::RecordingError(format!("无法创建录制文件: {e}")))?;
+        let mut writer = BufWriter::new(file);
+        let header_line = serde_json::to_string(header)
+            .map_err(|e| TerminalError::RecordingError(format!("序列化头部失败: {e}")))?;
+        writeln!(writer, "{header_line}")
+            .map_err(|e| TerminalError::RecordingError(format!("写入录制失败: {e}")))?;
+
+        tracing::info!("[录制] 开始录制: {:?}", path);
+        Ok(Self {
+            path,
+            writer,
+            started_at: Instant::now(),
+            output_decoder: Utf8Decoder::default(),
+            input_decoder: Utf8Decoder::default(),
+            redactor: PasswordRedactor::default(),
+        })
+    }
+
+    /// 录制文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 记录终端输出
+    pub fn record_output(&mut self, data: &[u8]) -> Result<(), TerminalError> {
+        self.redactor.observe_output(data);
+        let text = self.output_decoder.decode(data);
+        self.write_event(CastEventKind::Output, &text)
+    }
+
+    /// 记录用户输入（密码提示后的输入会被脱敏）
+    pub fn record_input(&mut self, data: &[u8]) -> Result<(), TerminalError> {
+        let text = self.input_decoder.decode(data);
+        match self.redactor.filter_input(&text) {
+            Some(text) => self.write_event(CastEventKind::Input, &text),
+            None => Ok(()),
+        }
+    }
+
+    /// 记录尺寸变化
+    pub fn record_resize(&mut self, cols: u16, rows: u16) -> Result<(), TerminalError> {
+        self.write_event(CastEventKind::Resize, &format!("{cols}x{rows}"))
+    }
+
+    /// 记录标记
+    pub fn record_marker(&mut self, label: &str) -> Result<(), TerminalError> {
+        self.write_event(CastEventKind::Marker, label)
+    }
+
+    /// 结束录制，返回文件路径
+    pub fn finish(mut self) -> Result<PathBuf, TerminalError> {
+        self.writer
+            .flush()
+            .map_err(|e| TerminalError::RecordingError(format!("写入录制失败: {e}")))?;
+        tracing::info!("[录制] 录制已结束: {:?}", self.path);
+        Ok(self.path)
+    }
+
+    fn write_event(&mut self, kind: CastEventKind, data: &str) -> Result<(), TerminalError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        // 保留微秒精度
+        let time = (self.started_at.elapsed().as_secs_f64() * 1_000_000.0).round() / 1_000_000.0;
+        let line = CastEvent {
+            time,
+            kind,
+            data: data.to_string(),
+        }
+        .to_line()?;
+        writeln!(self.writer, "{line}")
+            .map_err(|e| TerminalError::RecordingError(format!("写入录制失败: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_and_parse_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session.cast");
+        let mut header = CastHeader::new(80, 24);
+        header.title = Some("demo".to_string());
+
+        let mut recorder = AsciicastRecorder::create(&path, &header).unwrap();
+        recorder.record_output(b"$ ").unwrap();
+        recorder.record_input(b"ls\r").unwrap();
+        // 多字节字符跨块
+        let bytes = "中文\r\n".as_bytes();
+        recorder.record_output(&bytes[..2]).unwrap();
+        recorder.record_output(&bytes[2..]).unwrap();
+        recorder.record_resize(120, 40).unwrap();
+        recorder.record_marker("checkpoint").unwrap();
+        recorder.finish().unwrap();
+
+        let cast = Cast::load(&path).unwrap();
+        assert_eq!(cast.header, header);
+        let events: Vec<(CastEventKind, &str)> = cast
+            .events
+            .iter()
+            .map(|e| (e.kind, e.data.as_str()))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (CastEventKind::Output, "$ "),
+                (CastEventKind::Input, "ls\r"),
+                (CastEventKind::Output, "中文\r\n"),
+                (CastEventKind::Resize, "120x40"),
+                (CastEventKind::Marker, "checkpoint"),
+            ]
+        );
+        assert_eq!(cast.events[3].resize_size(), Some((120, 40)));
+    }
+
+    #[test]
+    fn test_password_input_is_redacted() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("sudo.cast");
+        let mut recorder = AsciicastRecorder::create(&path, &CastHeader::new(80, 24)).unwrap();
+
+        recorder.record_input(b"sudo ls\r").unwrap();
+        recorder
+            .record_output(b"sudo ls\r\n[sudo] password for dev: ")
+            .unwrap();
+        recorder.record_input(b"hun").unwrap();
+        recorder.record_input(b"ter2\r").unwrap();
+        recorder.record_output(b"\r\nfile.txt\r\n$ ").unwrap();
+        recorder.record_input(b"echo ok\r").unwrap();
+        recorder.finish().unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("hunter2"));
+        assert!(!content.contains("hun"));
+
+        let inputs: Vec<String> = Cast::load(&path)
+            .unwrap()
+            .events
+            .into_iter()
+            .filter(|e| e.kind == CastEventKind::Input)
+            .map(|e| e.data)
+            .collect();
+        assert_eq!(inputs, vec!["sudo ls\r", "[REDACTED]", "\r", "echo ok\r"]);
+    }
+
+    #[test]
+    fn test_password_prompt_detection() {
+        assert!(is_password_prompt("[sudo] password for dev: "));
+        assert!(is_password_prompt("dev@host's password:"));
+        assert!(is_password_prompt(
+            "Enter passphrase for key '/home/dev/.ssh/id_ed25519':"
+        ));
+        assert!(is_password_prompt("请输入密码："));
+        assert!(!is_password_prompt("$ echo password"));
+        assert!(!is_password_prompt("Username:"));
+    }
+
+    #[test]
+    fn test_playback_delays() {
+        let cast = Cast::parse(
+            "{\"version\": 2, \"width\": 80, \"height\": 24, \"idle_time_limit\": 2.0}\n\
+             [0.5, \"o\", \"a\"]\n\
+             [1.0, \"o\", \"b\"]\n\
+             \n\
+             [11.0, \"o\", \"c\"]\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(cast.duration(), 11.0);
+
+        let delays = cast.playback_delays(2.0, None).unwrap();
+        assert_eq!(
+            delays,
+            vec![
+                Duration::from_millis(250),
+                Duration::from_millis(250),
+                Duration::from_secs(1),
+            ]
+        );
+        assert!(cast.playback_delays(0.0, None).is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_content() {
+        assert!(Cast::parse("".as_bytes()).is_err());
+        assert!(
+            Cast::parse("{\"version\": 1, \"width\": 80, \"height\": 24}\n".as_bytes()).is_err()
+        );
+        assert!(Cast::parse(
+            "{\"version\": 2, \"width\": 80, \"height\": 24}\n[0.1, \"x\", \"a\"]\n".as_bytes()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_import_and_list_recordings() {
+        let source_dir = TempDir::new().unwrap();
+        let source = source_dir.path().join("shared.cast");
+        fs::write(
+            &source,
+            "{\"version\": 2, \"width\": 100, \"height\": 30, \"timestamp\": 1700000000}\n[1.5, \"o\", \"hi\"]\n",
+        )
+        .unwrap();
+
+        let dir = TempDir::new().unwrap();
+        let first = import_recording(&source, dir.path()).unwrap();
+        let second = import_recording(&source, dir.path()).unwrap();
+        assert!(first.path.ends_with("shared.cast"));
+        assert!(second.path.ends_with("shared-1.cast"));
+        assert_eq!(first.width, 100);
+        assert_eq!(first.duration, 1.5);
+
+        let recordings = list_recordings(dir.path()).unwrap();
+        assert_eq!(recordings.len(), 2);
+
+        let exported = source_dir.path().join("out").join("copy.cast");
+        export_recording(Path::new(&first.path), &exported).unwrap();
+        assert!(exported.exists());
+    }
+}
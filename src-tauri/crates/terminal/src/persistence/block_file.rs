@@ -6,6 +6,7 @@
 //! - 循环缓冲写入（超过最大大小时覆盖旧数据）
 //! - 文件读取和截断
 //! - 可配置最大文件大小
+//! - 逻辑流偏移（累计写入字节数），供命令索引定位输出
 //!
 //! ## 设计说明
 //! 采用简单的循环缓冲策略：当文件大小超过配置的最大值时，
@@ -16,10 +17,12 @@
 use std::fs::{self, File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use parking_lot::RwLock;
 
+use super::command_store::CommandIndexer;
 use crate::error::TerminalError;
 
 /// 默认终端块文件最大大小 (256KB)
@@ -43,6 +46,10 @@ pub struct BlockFile {
     is_wrapped: RwLock<bool>,
     /// 文件句柄（用于写入）
     file: RwLock<Option<File>>,
+    /// 逻辑流偏移：本次打开以来（含已有内容）累计写入的字节数
+    stream_offset: AtomicU64,
+    /// 命令索引器（可选）
+    command_indexer: RwLock<Option<Arc<CommandIndexer>>>,
 }
 
 impl BlockFile {
@@ -102,6 +109,8 @@ impl BlockFile {
             current_size: AtomicUsize::new(current_size),
             is_wrapped: RwLock::new(is_wrapped),
             file: RwLock::new(Some(file)),
+            stream_offset: AtomicU64::new(current_size as u64),
+            command_indexer: RwLock::new(None),
         })
     }
 
@@ -143,6 +152,21 @@ impl BlockFile {
         self.current_size.load(Ordering::Relaxed)
     }
 
+    /// 获取逻辑流偏移（累计写入字节数，循环覆盖后仍单调递增）
+    pub fn stream_offset(&self) -> u64 {
+        self.stream_offset.load(Ordering::Relaxed)
+    }
+
+    /// 挂载命令索引器，之后写入的输出会按 OSC 133 标记建立命令索引
+    pub fn set_command_indexer(&self, indexer: Arc<CommandIndexer>) {
+        *self.command_indexer.write() = Some(indexer);
+    }
+
+    /// 获取命令索引器
+    pub fn command_indexer(&self) -> Option<Arc<CommandIndexer>> {
+        self.command_indexer.read().clone()
+    }
+
     /// 追加数据到块文件
     ///
     /// 使用循环缓冲策略：当文件大小超过最大值时，覆盖最旧的数据。
@@ -160,6 +184,17 @@ impl BlockFile {
             return Ok(());
         }
 
+        let offset = self.write_data(data)?;
+
+        if let Some(indexer) = self.command_indexer() {
+            indexer.feed(data, offset);
+        }
+
+        Ok(())
+    }
+
+    /// 写入数据，返回数据在逻辑流中的起始偏移
+    fn write_data(&self, data: &[u8]) -> Result<u64, TerminalError> {
         let mut file_guard = self.file.write();
         let file = file_guard
             .as_mut()
@@ -191,7 +226,9 @@ impl BlockFile {
             self.apply_circular_buffer(file, data_to_write)?;
         }
 
-        Ok(())
+        Ok(self
+            .stream_offset
+            .fetch_add(data.len() as u64, Ordering::Relaxed))
     }
 
     /// 应用循环缓冲策略
@@ -264,6 +301,37 @@ impl BlockFile {
         Ok(data)
     }
 
+    /// 按逻辑流偏移读取数据
+    ///
+    /// # 参数
+    /// - `start`: 起始偏移
+    /// - `end`: 结束偏移（不含）
+    ///
+    /// # 返回
+    /// - `Ok(Some(data))`: 范围仍在文件中
+    /// - `Ok(None)`: 范围已被循环覆盖或截断
+    pub fn read_range(&self, start: u64, end: u64) -> Result<Option<Vec<u8>>, TerminalError> {
+        let mut file_guard = self.file.write();
+        let file = file_guard
+            .as_mut()
+            .ok_or_else(|| TerminalError::BlockFileError("文件已关闭".to_string()))?;
+
+        let stream_end = self.stream_offset.load(Ordering::Relaxed);
+        let window_start = stream_end - self.current_size.load(Ordering::Relaxed) as u64;
+        let end = end.min(stream_end);
+        if start < window_start || start > end {
+            return Ok(None);
+        }
+
+        file.seek(SeekFrom::Start(start - window_start))
+            .map_err(|e| TerminalError::BlockFileError(format!("Seek 失败: {e}")))?;
+        let mut data = vec![0u8; (end - start) as usize];
+        file.read_exact(&mut data)
+            .map_err(|e| TerminalError::BlockFileError(format!("读取失败: {e}")))?;
+
+        Ok(Some(data))
+    }
+
     /// 截断文件（清空内容）
     ///
     /// # 返回
@@ -333,3 +401,28 @@ impl Drop for BlockFile {
         *file_guard = None;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_range_follows_stream_offset_after_wrap() {
+        let dir = tempfile::tempdir().unwrap();
+        let bf = BlockFile::new("blk", &dir.path().to_path_buf(), 8).unwrap();
+
+        bf.append_data(b"abcdef").unwrap();
+        assert_eq!(bf.read_range(2, 5).unwrap().as_deref(), Some(&b"cde"[..]));
+
+        bf.append_data(b"ghij").unwrap();
+        assert_eq!(bf.stream_offset(), 10);
+        assert_eq!(bf.read_all().unwrap(), b"cdefghij");
+        // 被覆盖的范围不可读
+        assert!(bf.read_range(0, 4).unwrap().is_none());
+        assert_eq!(bf.read_range(6, 20).unwrap().as_deref(), Some(&b"ghij"[..]));
+
+        bf.truncate().unwrap();
+        assert_eq!(bf.stream_offset(), 10);
+        assert!(bf.read_range(6, 10).unwrap().is_none());
+    }
+}
@@ -0,0 +1,453 @@
+//! 命令历史索引
+//!
+//! 将 `CommandTracker` 切分出的命令记录存入 SQLite，并通过 FTS5 建立全文索引，
+//! 支持跨会话搜索命令行与输出，以及查询最近一次失败的命令。
+//!
+//! ## 功能
+//! - 命令记录（命令行、目录、退出码、耗时、块文件偏移、输出文本）的存储
+//! - 命令行 + 输出的全文搜索
+//! - 最近失败命令查询（供 Agent 使用）
+//! - `CommandIndexer`：挂载到 `BlockFile` 上，随终端输出实时建立索引
+
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::error::TerminalError;
+use crate::integration::command_tracker::{CommandTracker, CompletedCommand};
+use proxycast_core::database::DbConnection;
+
+/// 搜索结果默认条数
+pub const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+const SELECT_COLUMNS: &str = "c.id, c.block_id, c.command, c.cwd, c.exit_code, c.started_at, \
+     c.finished_at, c.duration_ms, c.output_start, c.output_end, c.output";
+
+/// 命令记录（存储在 SQLite）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandRecord {
+    /// 记录 ID
+    pub id: i64,
+    /// 块 ID（关联 BlockFile / terminal_sessions）
+    pub block_id: String,
+    /// 命令行
+    pub command: String,
+    /// 执行时的工作目录
+    pub cwd: Option<String>,
+    /// 退出码
+    pub exit_code: Option<i32>,
+    /// 开始时间（Unix 时间戳，毫秒）
+    pub started_at: i64,
+    /// 结束时间（Unix 时间戳，毫秒）
+    pub finished_at: i64,
+    /// 耗时（毫秒）
+    pub duration_ms: i64,
+    /// 输出在块文件流中的起始偏移
+    pub output_start: u64,
+    /// 输出在块文件流中的结束偏移（不含）
+    pub output_end: u64,
+    /// 去除控制序列后的输出文本
+    pub output: String,
+}
+
+impl CommandRecord {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            block_id: row.get(1)?,
+            command: row.get(2)?,
+            cwd: row.get(3)?,
+            exit_code: row.get(4)?,
+            started_at: row.get(5)?,
+            finished_at: row.get(6)?,
+            duration_ms: row.get(7)?,
+            output_start: row.get::<_, i64>(8)? as u64,
+            output_end: row.get::<_, i64>(9)? as u64,
+            output: row.get(10)?,
+        })
+    }
+}
+
+/// 命令搜索结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandSearchHit {
+    /// 命令记录
+    pub record: CommandRecord,
+    /// 命中片段（匹配词以 `[` `]` 标出）
+    pub snippet: String,
+}
+
+/// 命令历史存储服务
+pub struct CommandHistoryStore {
+    db: DbConnection,
+}
+
+impl CommandHistoryStore {
+    /// 创建新的命令历史存储服务
+    pub fn new(db: DbConnection) -> Self {
+        Self { db }
+    }
+
+    /// 创建 terminal_commands 表、全文索引及同步触发器（幂等）
+    pub fn create_tables(conn: &Connection) -> Result<(), TerminalError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS terminal_commands (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                block_id TEXT NOT NULL,
+                command TEXT NOT NULL,
+                cwd TEXT,
+                exit_code INTEGER,
+                started_at INTEGER NOT NULL,
+                finished_at INTEGER NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                output_start INTEGER NOT NULL,
+                output_end INTEGER NOT NULL,
+                output TEXT NOT NULL DEFAULT ''
+            );
+            CREATE INDEX IF NOT EXISTS idx_terminal_commands_block_id
+                ON terminal_commands(block_id, finished_at);
+            CREATE INDEX IF NOT EXISTS idx_terminal_commands_finished_at
+                ON terminal_commands(finished_at);
+
+            CREATE VIRTUAL TABLE IF NOT EXISTS terminal_commands_fts USING fts5(
+                command, output, cwd,
+                content='terminal_commands', content_rowid='id',
+                tokenize='unicode61'
+            );
+            CREATE TRIGGER IF NOT EXISTS terminal_commands_ai AFTER INSERT ON terminal_commands BEGIN
+                INSERT INTO terminal_commands_fts(rowid, command, output, cwd)
+                VALUES (new.id, new.command, new.output, new.cwd);
+            END;
+            CREATE TRIGGER IF NOT EXISTS terminal_commands_ad AFTER DELETE ON terminal_commands BEGIN
+                INSERT INTO terminal_commands_fts(terminal_commands_fts, rowid, command, output, cwd)
+                VALUES ('delete', old.id, old.command, old.output, old.cwd);
+            END;",
+        )
+        .map_err(|e| TerminalError::DatabaseError(format!("创建命令历史表失败: {e}")))?;
+
+        tracing::debug!("[CommandStore] 数据库表初始化完成");
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Connection>, TerminalError> {
+        self.db
+            .lock()
+            .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {e}")))
+    }
+
+    /// 保存一条已完成的命令，返回记录 ID
+    pub fn insert(&self, block_id: &str, command: &CompletedCommand) -> Result<i64, TerminalError> {
+        let conn = self.lock()?;
+        conn.execute(
+            "INSERT INTO terminal_commands
+             (block_id, command, cwd, exit_code, started_at, finished_at, duration_ms,
+              output_start, output_end, output)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                block_id,
+                command.command,
+                command.cwd,
+                command.exit_code,
+                command.started_at,
+                command.finished_at,
+                command.duration_ms(),
+                command.output_start as i64,
+                command.output_end as i64,
+                command.output,
+            ],
+        )
+        .map_err(|e| TerminalError::DatabaseError(format!("保存命令记录失败: {e}")))?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 全文搜索命令行与输出
+    ///
+    /// # 参数
+    /// - `query`: 搜索词（空白分隔，全部命中才返回；按字面匹配，不支持 FTS 语法）
+    /// - `block_id`: 限定会话（None 表示跨所有会话）
+    /// - `limit`: 最大返回条数
+    ///
+    /// # 返回
+    /// 按相关度排序的结果
+    pub fn search(
+        &self,
+        query: &str,
+        block_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<CommandSearchHit>, TerminalError> {
+        let Some(fts_query) = build_fts_query(query) else {
+            return Ok(Vec::new());
+        };
+
+        let conn = self.lock()?;
+        let sql = format!(
+            "SELECT {SELECT_COLUMNS},
+                    snippet(terminal_commands_fts, -1, '[', ']', '…', 16)
+             FROM terminal_commands_fts
+             JOIN terminal_commands c ON c.id = terminal_commands_fts.rowid
+             WHERE terminal_commands_fts MATCH ?1 AND (?2 IS NULL OR c.block_id = ?2)
+             ORDER BY bm25(terminal_commands_fts), c.finished_at DESC
+             LIMIT ?3"
+        );
+        let mut stmt = conn
+            .prepare(&sql)
+            .map_err(|e| TerminalError::DatabaseError(format!("准备查询失败: {e}")))?;
+
+        let hits = stmt
+            .query_map(params![fts_query, block_id, limit as i64], |row| {
+                Ok(CommandSearchHit {
+                    record: CommandRecord::from_row(row)?,
+                    snippet: row.get(11)?,
+                })
+            })
+            .map_err(|e| TerminalError::DatabaseError(format!("搜索命令失败: {e}")))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| TerminalError::DatabaseError(format!("读取命令记录失败: {e}")))?;
+
+        Ok(hits)
+    }
+
+    /// 根据 ID 获取命令记录
+    pub fn get_by_id(&self, id: i64) -> Result<Option<CommandRecord>, TerminalError> {
+        let conn = self.lock()?;
+        let sql = format!("SELECT {SELECT_COLUMNS} FROM terminal_commands c WHERE c.id = ?1");
+        conn.query_row(&sql, params![id], CommandRecord::from_row)
+            .optional()
+            .map_err(|e| TerminalError::DatabaseError(format!("查询命令记录失败: {e}")))
+    }
+
+    /// 最近一次失败（退出码非 0）的命令
+    ///
+    /// # 参数
+    /// - `block_id`: 限定会话（None 表示跨所有会话）
+    pub fn last_failed(
+        &self,
+        block_id: Option<&str>,
+    ) -> Result<Option<CommandRecord>, TerminalError> {
+        let conn = self.lock()?;
+        let sql = format!(
+            "SELECT {SELECT_COLUMNS} FROM terminal_commands c
+             WHERE c.exit_code IS NOT NULL AND c.exit_code != 0
+               AND (?1 IS NULL OR c.block_id = ?1)
+             ORDER BY c.finished_at DESC, c.id DESC
+             LIMIT 1"
+        );
+        conn.query_row(&sql, params![block_id], CommandRecord::from_row)
+            .optional()
+            .map_err(|e| TerminalError::DatabaseError(format!("查询失败命令失败: {e}")))
+    }
+
+    /// 最近执行的命令（按结束时间倒序）
+    pub fn recent(
+        &self,
+        block_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<CommandRecord>, TerminalError> {
+        let conn = self.lock()?;
+        let sql = format!(
+            "SELECT {SELECT_COLUMNS} FROM terminal_commands c
+             WHERE (?1 IS NULL OR c.block_id = ?1)
+             ORDER BY c.finished_at DESC, c.id DESC
+             LIMIT ?2"
+        );
+        let mut stmt = conn
+            .prepare(&sql)
+            .map_err(|e| TerminalError::DatabaseError(format!("准备查询失败: {e}")))?;
+
+        let records = stmt
+            .query_map(params![block_id, limit as i64], CommandRecord::from_row)
+            .map_err(|e| TerminalError::DatabaseError(format!("查询命令失败: {e}")))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| TerminalError::DatabaseError(format!("读取命令记录失败: {e}")))?;
+
+        Ok(records)
+    }
+
+    /// 删除指定会话的命令记录
+    pub fn delete_by_block_id(&self, block_id: &str) -> Result<usize, TerminalError> {
+        let conn = self.lock()?;
+        let count = conn
+            .execute(
+                "DELETE FROM terminal_commands WHERE block_id = ?1",
+                params![block_id],
+            )
+            .map_err(|e| TerminalError::DatabaseError(format!("删除命令记录失败: {e}")))?;
+
+        tracing::debug!("[CommandStore] 删除会话 {} 的 {} 条命令", block_id, count);
+        Ok(count)
+    }
+
+    /// 清理早于指定时间的命令记录
+    pub fn cleanup_before(&self, before_timestamp: i64) -> Result<usize, TerminalError> {
+        let conn = self.lock()?;
+        let count = conn
+            .execute(
+                "DELETE FROM terminal_commands WHERE finished_at < ?1",
+                params![before_timestamp],
+            )
+            .map_err(|e| TerminalError::DatabaseError(format!("清理命令记录失败: {e}")))?;
+
+        if count > 0 {
+            tracing::info!("[CommandStore] 清理了 {} 条旧命令", count);
+        }
+        Ok(count)
+    }
+}
+
+/// 将用户输入转换为 FTS5 查询：每个词作为短语匹配，词之间为 AND
+fn build_fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// 命令索引器
+///
+/// 包装 `CommandTracker` 与 `CommandHistoryStore`，挂载到 `BlockFile` 后，
+/// 每次写入块文件的输出都会被分段，完成的命令即时落库。
+pub struct CommandIndexer {
+    block_id: String,
+    tracker: Mutex<CommandTracker>,
+    store: Arc<CommandHistoryStore>,
+}
+
+impl CommandIndexer {
+    /// 创建新的命令索引器
+    pub fn new(block_id: impl Into<String>, store: Arc<CommandHistoryStore>) -> Self {
+        Self {
+            block_id: block_id.into(),
+            tracker: Mutex::new(CommandTracker::new()),
+            store,
+        }
+    }
+
+    /// 块 ID
+    pub fn block_id(&self) -> &str {
+        &self.block_id
+    }
+
+    /// 输入一块终端输出
+    ///
+    /// # 参数
+    /// - `data`: 输出数据
+    /// - `offset`: `data` 在块文件流中的起始偏移
+    pub fn feed(&self, data: &[u8], offset: u64) {
+        let completed = self.tracker.lock().feed(data, offset);
+        for command in completed {
+            if let Err(e) = self.store.insert(&self.block_id, &command) {
+                tracing::warn!(
+                    "[CommandIndexer] 保存命令失败: block_id={}, error={}",
+                    self.block_id,
+                    e
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> CommandHistoryStore {
+        let conn = Connection::open_in_memory().unwrap();
+        CommandHistoryStore::create_tables(&conn).unwrap();
+        CommandHistoryStore::new(Arc::new(std::sync::Mutex::new(conn)))
+    }
+
+    fn command(
+        cmd: &str,
+        exit_code: Option<i32>,
+        finished_at: i64,
+        output: &str,
+    ) -> CompletedCommand {
+        CompletedCommand {
+            command: cmd.to_string(),
+            cwd: Some("/work".to_string()),
+            exit_code,
+            started_at: finished_at - 5,
+            finished_at,
+            output_start: 0,
+            output_end: output.len() as u64,
+            output: output.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_search_across_sessions() {
+        let store = store();
+        store
+            .insert(
+                "a",
+                &command("cargo test", Some(101), 10, "test foo ... FAILED"),
+            )
+            .unwrap();
+        store
+            .insert("b", &command("npm run build", Some(0), 20, "built in 3s"))
+            .unwrap();
+
+        let hits = store.search("failed", None, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].record.block_id, "a");
+        assert!(hits[0].snippet.contains("[FAILED]"));
+
+        assert_eq!(store.search("build", None, 10).unwrap().len(), 1);
+        assert!(store.search("build", Some("a"), 10).unwrap().is_empty());
+        // FTS 语法字符按字面处理
+        assert!(store.search("\"npm AND", None, 10).unwrap().is_empty());
+        assert!(store.search("   ", None, 10).unwrap().is_empty());
+
+        assert_eq!(store.delete_by_block_id("a").unwrap(), 1);
+        assert!(store.search("failed", None, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_last_failed() {
+        let store = store();
+        store
+            .insert("a", &command("make", Some(2), 10, "make: *** Error 2"))
+            .unwrap();
+        store
+            .insert("b", &command("false", Some(1), 20, ""))
+            .unwrap();
+        store
+            .insert("a", &command("ls", Some(0), 30, "src"))
+            .unwrap();
+        store
+            .insert("a", &command("sleep 9", None, 40, "^C"))
+            .unwrap();
+
+        assert_eq!(store.last_failed(None).unwrap().unwrap().command, "false");
+        let failed = store.last_failed(Some("a")).unwrap().unwrap();
+        assert_eq!(failed.command, "make");
+        assert_eq!(store.get_by_id(failed.id).unwrap().unwrap().command, "make");
+        assert_eq!(failed.exit_code, Some(2));
+        assert_eq!(failed.output, "make: *** Error 2");
+        assert!(store.last_failed(Some("c")).unwrap().is_none());
+
+        let recent = store.recent(Some("a"), 2).unwrap();
+        assert_eq!(recent[0].command, "sleep 9");
+        assert_eq!(recent[1].command, "ls");
+    }
+
+    #[test]
+    fn test_indexer_records_completed_commands() {
+        let store = Arc::new(store());
+        let indexer = CommandIndexer::new("blk", store.clone());
+        indexer.feed(
+            b"\x1b]133;C;cmdline_url=cat%20nope\x07cat: nope: No such file\r\n",
+            0,
+        );
+        indexer.feed(b"\x1b]133;D;1\x07\x1b]133;A\x07$ ", 60);
+
+        let failed = store.last_failed(Some("blk")).unwrap().unwrap();
+        assert_eq!(failed.command, "cat nope");
+        assert_eq!(failed.output, "cat: nope: No such file\n");
+        assert_eq!(failed.output_end, 60);
+    }
+}
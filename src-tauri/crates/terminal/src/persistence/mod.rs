@@ -3,16 +3,24 @@
 //! 提供终端会话数据的持久化存储能力。
 //!
 //! ## 模块结构
+//! - `asciicast` - asciicast v2 会话录制与回放
 //! - `block_file` - 块文件循环缓冲存储
+//! - `command_store` - 命令历史索引（OSC 133 分段 + FTS5 全文搜索）
 //! - `session_store` - 会话元数据 SQLite 存储
 //!
 //! ## 功能
 //! - 终端输出历史的文件存储（循环缓冲）
 //! - 会话元数据的数据库存储
+//! - 命令级历史记录与全文搜索
 //! - 会话恢复支持
+//! - 会话录制、回放与 `.cast` 导入导出
 
+pub mod asciicast;
 pub mod block_file;
+pub mod command_store;
 pub mod session_store;
 
+pub use asciicast::{AsciicastRecorder, Cast, CastEvent, CastEventKind, CastHeader, RecordingInfo};
 pub use block_file::BlockFile;
+pub use command_store::{CommandHistoryStore, CommandIndexer, CommandRecord, CommandSearchHit};
 pub use session_store::{SessionMetadataStore, SessionRecord};
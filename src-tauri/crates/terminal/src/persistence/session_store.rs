@@ -10,11 +10,27 @@
 //! _Requirements: 3.5, 3.9_
 
 use chrono::Utc;
-use rusqlite::{params, OptionalExtension};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 
+use super::command_store::CommandHistoryStore;
 use crate::error::TerminalError;
-use proxycast_core::database::DbConnection;
+use proxycast_core::database::{DbConnection, Migration};
+
+/// 迁移注册表中的组件名
+pub const MIGRATION_COMPONENT: &str = "terminal";
+
+/// 终端模块的数据库迁移，注册到 `MigrationRegistry`
+pub fn migrations() -> Vec<Migration> {
+    vec![
+        Migration::new(MIGRATION_COMPONENT, 1, "terminal_sessions", |conn| {
+            SessionMetadataStore::create_tables(conn).map_err(|e| e.to_string())
+        }),
+        Migration::new(MIGRATION_COMPONENT, 2, "terminal_commands", |conn| {
+            CommandHistoryStore::create_tables(conn).map_err(|e| e.to_string())
+        }),
+    ]
+}
 
 /// 会话记录（存储在 SQLite）
 ///
@@ -88,7 +104,11 @@ impl SessionMetadataStore {
             .db
             .lock()
             .map_err(|e| TerminalError::DatabaseError(format!("无法获取数据库锁: {e}")))?;
+        Self::create_tables(&conn)
+    }
 
+    /// 创建 terminal_sessions 表及索引（幂等）
+    pub fn create_tables(conn: &Connection) -> Result<(), TerminalError> {
         conn.execute(
             "CREATE TABLE IF NOT EXISTS terminal_sessions (
                 id TEXT PRIMARY KEY,
@@ -8,12 +8,15 @@
 //! - 处理 PTY 输入写入
 //! - 监控进程退出状态
 //! - 保存输出历史（循环缓冲区）
+//! - 可选的 asciicast 录制（输出、输入、尺寸变化）
+//! - 输出写入块文件（加载 Shell 集成脚本，块文件上的命令索引器据 OSC 133 标记建立索引）
 //!
 //! ## 架构说明
 //! PTY 在后端预创建，使用默认大小 (24x80)。前端连接后通过 resize 同步实际大小。
 //! 输出历史保存在循环缓冲区中，前端连接时可以获取历史数据。
 
 use std::io::{Read, Write};
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
@@ -26,6 +29,8 @@ use crate::emit_helper;
 use crate::emitter::TerminalEventEmit;
 use crate::error::TerminalError;
 use crate::events::{event_names, SessionStatus, TerminalOutputEvent, TerminalStatusEvent};
+use crate::integration::ShellLaunchBuilder;
+use crate::persistence::{AsciicastRecorder, BlockFile};
 
 /// 默认终端行数
 pub const DEFAULT_ROWS: u16 = 24;
@@ -86,6 +91,8 @@ pub struct PtySession {
     shutdown_flag: Arc<AtomicBool>,
     /// 输出历史缓冲区
     output_buffer: Arc<Mutex<CircularBuffer>>,
+    /// asciicast 录制器（未录制时为 None）
+    recorder: Arc<Mutex<Option<AsciicastRecorder>>>,
 }
 
 impl PtySession {
@@ -121,7 +128,7 @@ impl PtySession {
         cols: u16,
         app_handle: impl TerminalEventEmit,
     ) -> Result<Self, TerminalError> {
-        Self::with_size_and_cwd(id, rows, cols, None, app_handle)
+        Self::with_size_and_cwd(id, rows, cols, None, None, app_handle)
     }
 
     /// 创建新的 PTY 会话（指定大小和工作目录）
@@ -131,6 +138,7 @@ impl PtySession {
     /// - `rows`: 终端行数
     /// - `cols`: 终端列数
     /// - `cwd`: 工作目录（可选，默认为用户主目录）
+    /// - `block_file`: 输出写入的块文件（可选）
     /// - `app_handle`: Tauri 应用句柄
     ///
     /// # 返回
@@ -141,6 +149,7 @@ impl PtySession {
         rows: u16,
         cols: u16,
         cwd: Option<String>,
+        block_file: Option<Arc<BlockFile>>,
         app_handle: impl TerminalEventEmit,
     ) -> Result<Self, TerminalError> {
         tracing::info!(
@@ -168,8 +177,7 @@ impl PtySession {
         tracing::info!("[终端] 使用 shell: {}", shell);
 
         // 构建命令
-        let mut cmd = CommandBuilder::new(&shell);
-        cmd.env("TERM", "xterm-256color");
+        let mut cmd = Self::build_shell_command(&shell, &id, &app_handle);
 
         // 设置工作目录
         if let Some(dir) = cwd {
@@ -232,6 +240,10 @@ impl PtySession {
         let output_buffer = Arc::new(Mutex::new(CircularBuffer::new(OUTPUT_BUFFER_MAX_SIZE)));
         let output_buffer_clone = output_buffer.clone();
 
+        // 创建录制器槽位
+        let recorder: Arc<Mutex<Option<AsciicastRecorder>>> = Arc::new(Mutex::new(None));
+        let recorder_clone = recorder.clone();
+
         // 获取当前 tokio runtime handle（在主线程中获取）
         let runtime_handle = tokio::runtime::Handle::current();
 
@@ -274,6 +286,20 @@ impl PtySession {
                         // 保存到输出缓冲区
                         output_buffer_clone.lock().append(output_data);
 
+                        // 保存到块文件
+                        if let Some(bf) = &block_file {
+                            if let Err(e) = bf.append_data(output_data) {
+                                tracing::warn!("[终端] 会话 {} 写入块文件失败: {}", id_clone, e);
+                            }
+                        }
+
+                        // 录制输出
+                        if let Some(recorder) = recorder_clone.lock().as_mut() {
+                            if let Err(e) = recorder.record_output(output_data) {
+                                tracing::warn!("[终端] 会话 {} 录制输出失败: {}", id_clone, e);
+                            }
+                        }
+
                         // 发送输出事件
                         let data = BASE64.encode(output_data);
                         let _ = emit_helper::emit(
@@ -321,9 +347,43 @@ impl PtySession {
             status,
             shutdown_flag,
             output_buffer,
+            recorder,
         })
     }
 
+    /// 构建 Shell 启动命令
+    ///
+    /// 加载 Shell 集成脚本以输出 OSC 133 命令标记；脚本安装失败时退回到普通 Shell。
+    fn build_shell_command(
+        shell: &str,
+        block_id: &str,
+        app_handle: &impl TerminalEventEmit,
+    ) -> CommandBuilder {
+        let launch_config = app_handle
+            .app_data_dir()
+            .map_err(TerminalError::Internal)
+            .and_then(|dir| ShellLaunchBuilder::new(&dir, block_id.to_string()).build(shell, None));
+
+        match launch_config {
+            Ok(config) => {
+                let mut cmd = CommandBuilder::new(&config.shell_path);
+                for arg in &config.args {
+                    cmd.arg(arg);
+                }
+                for (key, value) in &config.env {
+                    cmd.env(key, value);
+                }
+                cmd
+            }
+            Err(e) => {
+                tracing::warn!("[终端] 加载 Shell 集成失败，使用普通 Shell: {}", e);
+                let mut cmd = CommandBuilder::new(shell);
+                cmd.env("TERM", "xterm-256color");
+                cmd
+            }
+        }
+    }
+
     /// 获取会话 ID
     pub fn id(&self) -> &str {
         &self.id
@@ -338,6 +398,13 @@ impl PtySession {
         writer
             .flush()
             .map_err(|e| TerminalError::WriteFailed(e.to_string()))?;
+        drop(writer);
+
+        if let Some(recorder) = self.recorder.lock().as_mut() {
+            if let Err(e) = recorder.record_input(data) {
+                tracing::warn!("[终端] 会话 {} 录制输入失败: {}", self.id, e);
+            }
+        }
         Ok(())
     }
 
@@ -352,10 +419,56 @@ impl PtySession {
                 pixel_height: 0,
             })
             .map_err(|e| TerminalError::ResizeFailed(e.to_string()))?;
+        drop(master);
         tracing::debug!("[终端] 会话 {} 调整大小为 {}x{}", self.id, cols, rows);
+
+        if let Some(recorder) = self.recorder.lock().as_mut() {
+            if let Err(e) = recorder.record_resize(cols, rows) {
+                tracing::warn!("[终端] 会话 {} 录制尺寸变化失败: {}", self.id, e);
+            }
+        }
         Ok(())
     }
 
+    /// 开始录制
+    ///
+    /// 已在录制时返回错误。
+    pub fn start_recording(&self, recorder: AsciicastRecorder) -> Result<(), TerminalError> {
+        let mut slot = self.recorder.lock();
+        if slot.is_some() {
+            return Err(TerminalError::RecordingError(format!(
+                "会话 {} 已在录制中",
+                self.id
+            )));
+        }
+        *slot = Some(recorder);
+        Ok(())
+    }
+
+    /// 停止录制，返回录制文件路径（未在录制时返回 None）
+    pub fn stop_recording(&self) -> Result<Option<PathBuf>, TerminalError> {
+        match self.recorder.lock().take() {
+            Some(recorder) => recorder.finish().map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// 是否正在录制
+    pub fn is_recording(&self) -> bool {
+        self.recorder.lock().is_some()
+    }
+
+    /// 在录制中插入标记
+    pub fn add_recording_marker(&self, label: &str) -> Result<(), TerminalError> {
+        match self.recorder.lock().as_mut() {
+            Some(recorder) => recorder.record_marker(label),
+            None => Err(TerminalError::RecordingError(format!(
+                "会话 {} 未在录制",
+                self.id
+            ))),
+        }
+    }
+
     /// 获取当前状态
     pub async fn status(&self) -> SessionStatus {
         *self.status.read().await
@@ -373,6 +486,11 @@ impl PtySession {
         // 设置关闭标志
         self.shutdown_flag.store(true, Ordering::Relaxed);
 
+        // 结束录制
+        if let Err(e) = self.stop_recording() {
+            tracing::warn!("[终端] 会话 {} 结束录制失败: {}", self.id, e);
+        }
+
         // 更新状态
         *self.status.write().await = SessionStatus::Done;
 
@@ -8,6 +8,7 @@
 //! - 集成 BlockFile 进行输出持久化
 //! - 集成 SessionMetadataStore 进行元数据存储
 //! - 支持会话状态生命周期管理
+//! - 会话录制（asciicast v2）与回放
 //!
 //! ## Requirements
 //! - 3.1: 终端会话创建时创建对应的 Block_File
@@ -17,7 +18,7 @@
 //! - 3.9: 会话关闭时更新会话元数据状态为已完成
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
@@ -29,10 +30,15 @@ use uuid::Uuid;
 use proxycast_core::database::DbConnection;
 
 use crate::block_controller::ControllerRegistry;
+use crate::emit_helper;
 use crate::emitter::{DynEmitter, TerminalEventEmit};
 use crate::error::TerminalError;
-use crate::events::SessionStatus;
-use crate::persistence::{BlockFile, SessionMetadataStore, SessionRecord};
+use crate::events::{event_names, SessionStatus, TerminalOutputEvent, TerminalStatusEvent};
+use crate::persistence::asciicast::{self, CAST_EXTENSION};
+use crate::persistence::{
+    AsciicastRecorder, BlockFile, Cast, CastEventKind, CastHeader, CommandHistoryStore,
+    CommandIndexer, RecordingInfo, SessionMetadataStore, SessionRecord,
+};
 use crate::pty_session::{PtySession, DEFAULT_COLS, DEFAULT_ROWS};
 
 /// 会话元数据（用于前端展示）
@@ -89,8 +95,6 @@ impl SessionMetadata {
 struct SessionData {
     /// 会话元数据
     metadata: SessionMetadata,
-    /// 块文件存储
-    block_file: Arc<BlockFile>,
     /// 旧版 PTY 会话（兼容模式）
     legacy_pty: Option<PtySession>,
 }
@@ -113,8 +117,12 @@ pub struct TerminalSessionManager {
     controller_registry: Arc<ControllerRegistry>,
     /// 会话元数据存储
     session_store: Option<Arc<SessionMetadataStore>>,
+    /// 命令历史存储
+    command_store: Option<Arc<CommandHistoryStore>>,
     /// 块文件基础目录
     block_file_base_dir: PathBuf,
+    /// 录制文件目录
+    recordings_dir: PathBuf,
     /// Tauri 应用句柄（抽象为事件发射器）
     app_handle: DynEmitter,
 }
@@ -127,6 +135,8 @@ impl TerminalSessionManager {
     pub fn new(app_handle: impl TerminalEventEmit) -> Self {
         let block_file_base_dir = BlockFile::default_base_dir()
             .unwrap_or_else(|_| PathBuf::from(".proxycast/terminal_blocks"));
+        let recordings_dir = asciicast::default_recordings_dir()
+            .unwrap_or_else(|_| PathBuf::from(".proxycast/terminal_recordings"));
 
         tracing::info!(
             "[终端] 会话管理器已初始化，块文件目录: {:?}",
@@ -137,7 +147,9 @@ impl TerminalSessionManager {
             sessions: Arc::new(RwLock::new(HashMap::new())),
             controller_registry: Arc::new(ControllerRegistry::new()),
             session_store: None,
+            command_store: None,
             block_file_base_dir,
+            recordings_dir,
             app_handle: DynEmitter::new(app_handle),
         }
     }
@@ -156,10 +168,11 @@ impl TerminalSessionManager {
         let mut manager = Self::new(app_handle);
 
         // 创建会话存储服务
-        let session_store = SessionMetadataStore::new(db);
+        let session_store = SessionMetadataStore::new(db.clone());
         session_store.init_tables()?;
 
         manager.session_store = Some(Arc::new(session_store));
+        manager.command_store = Some(Arc::new(CommandHistoryStore::new(db)));
 
         tracing::info!("[终端] 会话管理器已初始化（带数据库支持）");
         Ok(manager)
@@ -175,6 +188,23 @@ impl TerminalSessionManager {
         self.session_store.as_ref()
     }
 
+    /// 获取命令历史存储服务
+    pub fn command_store(&self) -> Option<&Arc<CommandHistoryStore>> {
+        self.command_store.as_ref()
+    }
+
+    /// 打开会话的块文件，有命令历史存储时挂载 `CommandIndexer`
+    fn open_block_file(&self, block_id: &str) -> Result<Arc<BlockFile>, TerminalError> {
+        let block_file = Arc::new(BlockFile::with_default_size(
+            block_id,
+            &self.block_file_base_dir,
+        )?);
+        if let Some(store) = &self.command_store {
+            block_file.set_command_indexer(Arc::new(CommandIndexer::new(block_id, store.clone())));
+        }
+        Ok(block_file)
+    }
+
     /// 创建新的终端会话
     ///
     /// 使用默认大小 (24x80) 创建 PTY 会话。
@@ -239,15 +269,15 @@ impl TerminalSessionManager {
         );
 
         // 创建块文件
-        let block_file = BlockFile::with_default_size(&block_id, &self.block_file_base_dir)?;
-        let block_file = Arc::new(block_file);
+        let block_file = self.open_block_file(&block_id)?;
 
-        // 创建旧版 PTY 会话（兼容模式）
+        // 创建旧版 PTY 会话（兼容模式），输出写入块文件
         let pty_session = PtySession::with_size_and_cwd(
             session_id.clone(),
             rows,
             cols,
             cwd,
+            Some(block_file),
             self.app_handle.clone(),
         )?;
 
@@ -284,7 +314,6 @@ impl TerminalSessionManager {
         // 创建会话数据
         let session_data = SessionData {
             metadata,
-            block_file,
             legacy_pty: Some(pty_session),
         };
 
@@ -331,14 +360,11 @@ impl TerminalSessionManager {
             .get(session_id)
             .ok_or_else(|| TerminalError::SessionNotFound(session_id.to_string()))?;
 
-        // 使用旧版 PTY 会话写入
+        // 使用旧版 PTY 会话写入（输入经 Shell 回显后随输出写入块文件）
         if let Some(pty) = &session.legacy_pty {
             pty.write(data)?;
         }
 
-        // 同时写入块文件（用于持久化）
-        session.block_file.append_data(data)?;
-
         Ok(())
     }
 
@@ -443,8 +469,7 @@ impl TerminalSessionManager {
         }
 
         // 创建块文件引用
-        let block_file = BlockFile::with_default_size(&record.block_id, &self.block_file_base_dir)?;
-        let block_file = Arc::new(block_file);
+        let block_file = self.open_block_file(&record.block_id)?;
 
         // 读取历史数据
         let _history = block_file.read_all()?;
@@ -452,8 +477,14 @@ impl TerminalSessionManager {
         // 创建新的 PTY 会话
         let rows = DEFAULT_ROWS;
         let cols = DEFAULT_COLS;
-        let pty_session =
-            PtySession::with_size(session_id.to_string(), rows, cols, self.app_handle.clone())?;
+        let pty_session = PtySession::with_size_and_cwd(
+            session_id.to_string(),
+            rows,
+            cols,
+            None,
+            Some(block_file),
+            self.app_handle.clone(),
+        )?;
 
         // 创建会话元数据
         let metadata = SessionMetadata::from_record(&record, rows, cols);
@@ -461,7 +492,6 @@ impl TerminalSessionManager {
         // 创建会话数据
         let session_data = SessionData {
             metadata: metadata.clone(),
-            block_file,
             legacy_pty: Some(pty_session),
         };
 
@@ -499,4 +529,266 @@ impl TerminalSessionManager {
         tracing::info!("[终端] 加载了 {} 个已保存的会话", result.len());
         Ok(result)
     }
+
+    /// 开始录制会话（asciicast v2）
+    ///
+    /// # 参数
+    /// - `session_id`: 会话 ID
+    /// - `title`: 录制标题（可选）
+    ///
+    /// # 返回
+    /// 录制文件路径
+    pub async fn start_recording(
+        &self,
+        session_id: &str,
+        title: Option<String>,
+    ) -> Result<PathBuf, TerminalError> {
+        let sessions = self.sessions.read().await;
+        let session = sessions
+            .get(session_id)
+            .ok_or_else(|| TerminalError::SessionNotFound(session_id.to_string()))?;
+        let pty = session.legacy_pty.as_ref().ok_or_else(|| {
+            TerminalError::RecordingError(format!("会话 {session_id} 不支持录制"))
+        })?;
+
+        let now = Utc::now();
+        let mut header = CastHeader::new(session.metadata.cols, session.metadata.rows);
+        header.timestamp = Some(now.timestamp());
+        header.title = title;
+        header
+            .env
+            .insert("TERM".to_string(), "xterm-256color".to_string());
+        if let Ok(shell) = std::env::var("SHELL") {
+            header.env.insert("SHELL".to_string(), shell);
+        }
+
+        let path = self.recordings_dir.join(format!(
+            "{}-{}.{CAST_EXTENSION}",
+            now.format("%Y%m%d-%H%M%S"),
+            &session_id[..session_id.len().min(8)]
+        ));
+        pty.start_recording(AsciicastRecorder::create(&path, &header)?)?;
+
+        tracing::info!("[终端] 会话 {} 开始录制: {:?}", session_id, path);
+        Ok(path)
+    }
+
+    /// 停止录制会话
+    ///
+    /// # 返回
+    /// 录制文件路径（会话未在录制时返回 None）
+    pub async fn stop_recording(&self, session_id: &str) -> Result<Option<PathBuf>, TerminalError> {
+        let sessions = self.sessions.read().await;
+        let session = sessions
+            .get(session_id)
+            .ok_or_else(|| TerminalError::SessionNotFound(session_id.to_string()))?;
+
+        match &session.legacy_pty {
+            Some(pty) => pty.stop_recording(),
+            None => Ok(None),
+        }
+    }
+
+    /// 在会话录制中插入标记
+    pub async fn add_recording_marker(
+        &self,
+        session_id: &str,
+        label: &str,
+    ) -> Result<(), TerminalError> {
+        let sessions = self.sessions.read().await;
+        let session = sessions
+            .get(session_id)
+            .ok_or_else(|| TerminalError::SessionNotFound(session_id.to_string()))?;
+        let pty = session.legacy_pty.as_ref().ok_or_else(|| {
+            TerminalError::RecordingError(format!("会话 {session_id} 不支持录制"))
+        })?;
+        pty.add_recording_marker(label)
+    }
+
+    /// 列出录制目录中的所有录制
+    pub fn list_recordings(&self) -> Result<Vec<RecordingInfo>, TerminalError> {
+        asciicast::list_recordings(&self.recordings_dir)
+    }
+
+    /// 导入外部 `.cast` 文件
+    pub fn import_recording(&self, source: &Path) -> Result<RecordingInfo, TerminalError> {
+        asciicast::import_recording(source, &self.recordings_dir)
+    }
+
+    /// 导出录制到指定路径
+    pub fn export_recording(&self, source: &Path, destination: &Path) -> Result<(), TerminalError> {
+        asciicast::export_recording(source, destination)
+    }
+
+    /// 回放录制到新的终端块
+    ///
+    /// 创建一个只读的回放会话，按录制时间（除以 `speed`）推送输出事件，
+    /// 输出同时写入该会话的块文件。关闭回放会话即停止回放。
+    ///
+    /// # 参数
+    /// - `path`: 录制文件路径
+    /// - `speed`: 回放速度倍数
+    /// - `idle_time_limit`: 最大空闲时间（秒，可选）
+    ///
+    /// # 返回
+    /// 回放会话 ID
+    pub async fn replay_recording(
+        &self,
+        path: &Path,
+        speed: f64,
+        idle_time_limit: Option<f64>,
+    ) -> Result<String, TerminalError> {
+        let cast = Cast::load(path)?;
+        let delays = cast.playback_delays(speed, idle_time_limit)?;
+
+        let session_id = Uuid::new_v4().to_string();
+        let block_file = Arc::new(BlockFile::with_default_size(
+            &session_id,
+            &self.block_file_base_dir,
+        )?);
+
+        let metadata = SessionMetadata {
+            id: session_id.clone(),
+            block_id: session_id.clone(),
+            tab_id: "default".to_string(),
+            controller_type: "replay".to_string(),
+            connection: None,
+            status: SessionStatus::Running,
+            created_at: Utc::now().timestamp_millis(),
+            rows: cast.header.height,
+            cols: cast.header.width,
+            exit_code: None,
+        };
+        self.sessions.write().await.insert(
+            session_id.clone(),
+            SessionData {
+                metadata,
+                legacy_pty: None,
+            },
+        );
+
+        tracing::info!(
+            "[终端] 回放录制 {:?} 到会话 {}，速度 {}x",
+            path,
+            session_id,
+            speed
+        );
+
+        let sessions = self.sessions.clone();
+        let app_handle = self.app_handle.clone();
+        let id = session_id.clone();
+        tokio::spawn(async move {
+            for (event, delay) in cast.events.iter().zip(delays) {
+                if !delay.is_zero() {
+                    tokio::time::sleep(delay).await;
+                }
+
+                match event.kind {
+                    CastEventKind::Output => {
+                        if let Err(e) = block_file.append_data(event.data.as_bytes()) {
+                            tracing::warn!("[终端] 回放会话 {} 写入块文件失败: {}", id, e);
+                        }
+                        let _ = emit_helper::emit(
+                            &app_handle,
+                            event_names::TERMINAL_OUTPUT,
+                            &TerminalOutputEvent {
+                                session_id: id.clone(),
+                                data: BASE64.encode(event.data.as_bytes()),
+                            },
+                        );
+                    }
+                    CastEventKind::Resize => {
+                        if let Some((cols, rows)) = event.resize_size() {
+                            if let Some(session) = sessions.write().await.get_mut(&id) {
+                                session.metadata.cols = cols;
+                                session.metadata.rows = rows;
+                            }
+                        }
+                    }
+                    CastEventKind::Input | CastEventKind::Marker => {}
+                }
+
+                // 会话被关闭时停止回放
+                if !sessions.read().await.contains_key(&id) {
+                    tracing::info!("[终端] 回放会话 {} 已关闭，停止回放", id);
+                    return;
+                }
+            }
+
+            if let Some(session) = sessions.write().await.get_mut(&id) {
+                session.metadata.status = SessionStatus::Done;
+                session.metadata.exit_code = Some(0);
+            }
+            let _ = emit_helper::emit(
+                &app_handle,
+                event_names::TERMINAL_STATUS,
+                &TerminalStatusEvent {
+                    session_id: id.clone(),
+                    status: SessionStatus::Done,
+                    exit_code: Some(0),
+                    error: None,
+                },
+            );
+            tracing::info!("[终端] 回放会话 {} 已完成", id);
+        });
+
+        Ok(session_id)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// 不加载 Shell 集成的事件发射器，命令标记由测试命令自行输出
+    struct PlainShellEmitter;
+
+    impl TerminalEventEmit for PlainShellEmitter {
+        fn emit_event(&self, _event: &str, _payload: &serde_json::Value) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn app_data_dir(&self) -> Result<PathBuf, String> {
+            Err("测试不加载 Shell 集成".to_string())
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_session_commands_are_indexed_and_searchable() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        CommandHistoryStore::create_tables(&conn).unwrap();
+        let db: DbConnection = Arc::new(std::sync::Mutex::new(conn));
+        let manager = TerminalSessionManager::with_database(PlainShellEmitter, db).unwrap();
+        let session_id = manager.create_session().await.unwrap();
+
+        manager
+            .write_to_session(
+                &session_id,
+                b"printf '\\033]133;A\\007\\033]133;C;cmdline_url=make%%20indexed\\007indexed-output\\r\\n\\033]133;D;0\\007'\n",
+            )
+            .await
+            .unwrap();
+
+        let store = manager.command_store().unwrap().clone();
+        let mut hits = Vec::new();
+        for _ in 0..50 {
+            hits = store.search("indexed", Some(&session_id), 10).unwrap();
+            if !hits.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        manager.close_session(&session_id).await.unwrap();
+        let _ = std::fs::remove_file(
+            manager
+                .block_file_base_dir
+                .join(format!("{session_id}.block")),
+        );
+
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].record.command, "make indexed");
+        assert_eq!(hits[0].record.exit_code, Some(0));
+    }
 }
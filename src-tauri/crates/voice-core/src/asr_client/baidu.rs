@@ -1,14 +1,38 @@
 //! 百度语音识别客户端
 //!
 //! 使用百度 AI 开放平台的语音识别 API。
+//!
+//! - 整段识别：短语音识别 REST API（`AsrClient`）
+//! - 流式识别：实时语音识别 WebSocket API（`StreamingAsrClient`，需要 App ID）
+//!
+//! ## 实时识别协议
+//! 1. 连接 `wss://vop.baidu.com/realtime_asr?sn=<随机串>`
+//! 2. 发送 START 文本帧（appid、appkey、dev_pid、采样率）
+//! 3. 发送二进制音频帧（16kHz PCM16，每帧 160ms）
+//! 4. 发送 FINISH 文本帧，服务端返回剩余结果后关闭连接
+//!
+//! 服务端返回 `MID_TEXT`（临时结果）和 `FIN_TEXT`（一句话的最终结果）。
 
 use async_trait::async_trait;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::Utc;
+use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
 
+use super::streaming::{AsrStream, PcmFramer, StreamingAsrClient, StreamingTranscript};
 use super::AsrClient;
 use crate::error::{Result, VoiceError};
-use crate::types::{AudioData, TranscribeResult};
+use crate::types::{AudioData, Segment, TranscribeResult};
+
+/// 实时语音识别 WebSocket 地址
+const REALTIME_URL: &str = "wss://vop.baidu.com/realtime_asr";
+/// 实时识别每帧字节数（160ms 的 16kHz 16bit 单声道音频）
+const REALTIME_FRAME_SIZE: usize = 5120;
+/// 实时识别默认模型（中文普通话，带标点）
+const DEFAULT_REALTIME_DEV_PID: u32 = 15372;
+/// 未检测到有效语音的错误码（不视为失败）
+const ERR_NO_SPEECH: i32 = -3005;
 
 /// 百度 Token 响应
 #[derive(Debug, Deserialize)]
@@ -44,6 +68,10 @@ pub struct BaiduClient {
     api_key: String,
     secret_key: String,
     cached_token: Option<String>,
+    /// App ID（实时识别需要）
+    app_id: Option<u64>,
+    /// 实时识别模型 ID
+    dev_pid: u32,
 }
 
 impl BaiduClient {
@@ -53,9 +81,23 @@ impl BaiduClient {
             api_key,
             secret_key,
             cached_token: None,
+            app_id: None,
+            dev_pid: DEFAULT_REALTIME_DEV_PID,
         }
     }
 
+    /// 设置 App ID（实时识别需要）
+    pub fn with_app_id(mut self, app_id: u64) -> Self {
+        self.app_id = Some(app_id);
+        self
+    }
+
+    /// 设置实时识别模型 ID（如 15372 中文普通话、17372 英语）
+    pub fn with_dev_pid(mut self, dev_pid: u32) -> Self {
+        self.dev_pid = dev_pid;
+        self
+    }
+
     /// 获取 Access Token
     async fn get_token(&mut self) -> Result<String> {
         if let Some(ref token) = self.cached_token {
@@ -142,3 +184,184 @@ impl AsrClient for BaiduClient {
         "百度语音"
     }
 }
+
+#[async_trait]
+impl StreamingAsrClient for BaiduClient {
+    async fn start_stream(&self, sample_rate: u32) -> Result<AsrStream> {
+        let app_id = self
+            .app_id
+            .ok_or_else(|| VoiceError::AsrAuthError("百度实时识别需要配置 App ID".to_string()))?;
+
+        let sn = format!(
+            "proxycast-{}",
+            Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        );
+        let url = format!("{REALTIME_URL}?sn={sn}");
+        let (ws_stream, _) = connect_async(&url).await.map_err(|e| {
+            tracing::error!("百度实时识别连接失败: {:?}", e);
+            VoiceError::NetworkError(format!("WebSocket 连接失败: {e}"))
+        })?;
+        tracing::info!("百度实时识别已连接");
+
+        let start = serde_json::to_string(&BaiduRealtimeStart {
+            kind: "START",
+            data: BaiduRealtimeParams {
+                appid: app_id,
+                appkey: self.api_key.clone(),
+                dev_pid: self.dev_pid,
+                cuid: "proxycast".to_string(),
+                format: "pcm".to_string(),
+                sample: 16000,
+            },
+        })
+        .map_err(|e| VoiceError::AsrError(format!("序列化请求失败: {e}")))?;
+
+        Ok(AsrStream::spawn(move |mut audio_rx, sink| async move {
+            let (mut write, mut read) = ws_stream.split();
+            write
+                .send(Message::Text(start))
+                .await
+                .map_err(|e| VoiceError::NetworkError(format!("发送数据失败: {e}")))?;
+
+            let mut framer = PcmFramer::new(sample_rate, REALTIME_FRAME_SIZE);
+            let mut assembler = BaiduRealtimeAssembler::default();
+            let mut audio_done = false;
+
+            loop {
+                tokio::select! {
+                    chunk = audio_rx.recv(), if !audio_done => {
+                        let mut messages: Vec<Message> = Vec::new();
+                        match chunk {
+                            Some(samples) => {
+                                messages.extend(framer.push(&samples).into_iter().map(Message::Binary));
+                            }
+                            None => {
+                                audio_done = true;
+                                let rest = framer.flush();
+                                if !rest.is_empty() {
+                                    messages.push(Message::Binary(rest));
+                                }
+                                messages.push(Message::Text(r#"{"type":"FINISH"}"#.to_string()));
+                            }
+                        }
+
+                        for message in messages {
+                            write.send(message).await.map_err(|e| {
+                                VoiceError::NetworkError(format!("发送数据失败: {e}"))
+                            })?;
+                        }
+                    }
+                    msg = read.next() => {
+                        let text = match msg {
+                            Some(Ok(Message::Text(text))) => text,
+                            Some(Ok(Message::Close(_))) | None => break,
+                            Some(Ok(_)) => continue,
+                            Some(Err(e)) => {
+                                return Err(VoiceError::NetworkError(format!("接收数据失败: {e}")));
+                            }
+                        };
+
+                        let response: BaiduRealtimeResponse = serde_json::from_str(&text)
+                            .map_err(|e| VoiceError::AsrError(format!("解析响应失败: {e}")))?;
+                        if let Some(transcript) = assembler.apply(response)? {
+                            let _ = sink.send(transcript);
+                        }
+                    }
+                }
+            }
+
+            let result = assembler.into_result();
+            tracing::info!("百度实时识别完成: {}", result.text);
+            Ok(result)
+        }))
+    }
+
+    fn name(&self) -> &'static str {
+        "百度语音"
+    }
+}
+
+/// 实时识别开始帧
+#[derive(Debug, Serialize)]
+struct BaiduRealtimeStart {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    data: BaiduRealtimeParams,
+}
+
+/// 实时识别参数
+#[derive(Debug, Serialize)]
+struct BaiduRealtimeParams {
+    appid: u64,
+    appkey: String,
+    dev_pid: u32,
+    cuid: String,
+    format: String,
+    sample: u32,
+}
+
+/// 实时识别响应
+#[derive(Debug, Deserialize)]
+struct BaiduRealtimeResponse {
+    /// 消息类型（MID_TEXT / FIN_TEXT / HEARTBEAT）
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    err_no: i32,
+    #[serde(default)]
+    err_msg: String,
+    #[serde(default)]
+    result: String,
+    /// 句子开始时间（毫秒）
+    start_time: Option<i64>,
+    /// 句子结束时间（毫秒）
+    end_time: Option<i64>,
+}
+
+/// 实时识别结果拼接器
+#[derive(Default)]
+struct BaiduRealtimeAssembler {
+    /// 已确认的句子
+    sentences: Vec<Segment>,
+}
+
+impl BaiduRealtimeAssembler {
+    /// 应用一条响应，返回需要推送的识别结果
+    fn apply(&mut self, response: BaiduRealtimeResponse) -> Result<Option<StreamingTranscript>> {
+        match response.kind.as_str() {
+            "MID_TEXT" if response.err_no == 0 => Ok(Some(StreamingTranscript {
+                text: format!("{}{}", self.text(), response.result),
+                is_final: false,
+            })),
+            "FIN_TEXT" if response.err_no == 0 => {
+                self.sentences.push(Segment {
+                    start: response.start_time.unwrap_or(0) as f32 / 1000.0,
+                    end: response.end_time.unwrap_or(0) as f32 / 1000.0,
+                    text: response.result,
+                });
+                Ok(Some(StreamingTranscript {
+                    text: self.text(),
+                    is_final: true,
+                }))
+            }
+            _ if response.err_no == 0 || response.err_no == ERR_NO_SPEECH => Ok(None),
+            _ => Err(VoiceError::AsrError(format!(
+                "百度 ASR 错误: {} - {}",
+                response.err_no, response.err_msg
+            ))),
+        }
+    }
+
+    fn text(&self) -> String {
+        self.sentences.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    fn into_result(self) -> TranscribeResult {
+        TranscribeResult {
+            text: self.text(),
+            language: Some("zh".to_string()),
+            confidence: None,
+            segments: self.sentences,
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! 分块窗口流式识别
+//!
+//! 为只支持整段识别的本地引擎（Whisper）提供流式识别：
+//! 音频累积在当前窗口中，每新增 `step` 时长就对整个窗口识别一次作为中间结果；
+//! 窗口满 `window` 时长后识别一次并确认，然后开启新窗口。
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use super::streaming::{AsrStream, StreamingAsrClient, StreamingTranscript};
+use crate::error::{Result, VoiceError};
+use crate::types::{AudioData, Segment, TranscribeResult};
+
+/// 短于该时长的剩余音频不再识别（秒）
+const MIN_TAIL_SECS: f32 = 0.3;
+
+/// 同步整段识别器（在阻塞线程中执行）
+pub trait BatchTranscriber: Send + Sync + 'static {
+    /// 识别一段音频
+    fn transcribe_blocking(&self, audio: &AudioData) -> Result<TranscribeResult>;
+
+    /// 获取引擎名称
+    fn name(&self) -> &'static str;
+}
+
+/// 分块窗口配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkedWindowConfig {
+    /// 窗口时长（秒），窗口满后确认结果
+    pub window_secs: f32,
+    /// 中间结果的识别间隔（秒）
+    pub step_secs: f32,
+}
+
+impl Default for ChunkedWindowConfig {
+    fn default() -> Self {
+        Self {
+            window_secs: 10.0,
+            step_secs: 1.0,
+        }
+    }
+}
+
+/// 窗口动作
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowAction {
+    /// 识别当前窗口作为中间结果
+    Partial {
+        /// 窗口音频
+        samples: Vec<i16>,
+        /// 窗口开始位置（采样）
+        offset: usize,
+    },
+    /// 识别并确认当前窗口
+    Commit {
+        /// 窗口音频
+        samples: Vec<i16>,
+        /// 窗口开始位置（采样）
+        offset: usize,
+    },
+}
+
+/// 分块窗口
+pub struct ChunkedWindow {
+    window_len: usize,
+    step_len: usize,
+    buffer: Vec<i16>,
+    /// 当前窗口开始位置（采样）
+    offset: usize,
+    /// 上次中间结果后新增的采样数
+    since_partial: usize,
+}
+
+impl ChunkedWindow {
+    /// 创建窗口
+    pub fn new(config: &ChunkedWindowConfig, sample_rate: u32) -> Self {
+        let window_len = ((config.window_secs * sample_rate as f32) as usize).max(1);
+        let step_len = ((config.step_secs * sample_rate as f32) as usize).clamp(1, window_len);
+        Self {
+            window_len,
+            step_len,
+            buffer: Vec::with_capacity(window_len),
+            offset: 0,
+            since_partial: 0,
+        }
+    }
+
+    /// 送入采样，返回需要执行的识别动作
+    pub fn push(&mut self, mut samples: &[i16]) -> Vec<WindowAction> {
+        let mut actions = Vec::new();
+        while !samples.is_empty() {
+            let take = samples.len().min(self.window_len - self.buffer.len());
+            self.buffer.extend_from_slice(&samples[..take]);
+            self.since_partial += take;
+            samples = &samples[take..];
+
+            if self.buffer.len() >= self.window_len {
+                actions.push(self.commit());
+            } else if self.since_partial >= self.step_len {
+                self.since_partial = 0;
+                actions.push(WindowAction::Partial {
+                    samples: self.buffer.clone(),
+                    offset: self.offset,
+                });
+            }
+        }
+        actions
+    }
+
+    /// 结束，确认剩余音频（不足 `min_len` 采样时丢弃）
+    pub fn finish(&mut self, min_len: usize) -> Option<WindowAction> {
+        if self.buffer.len() < min_len.max(1) {
+            self.buffer.clear();
+            return None;
+        }
+        Some(self.commit())
+    }
+
+    fn commit(&mut self) -> WindowAction {
+        let samples = std::mem::take(&mut self.buffer);
+        let offset = self.offset;
+        self.offset += samples.len();
+        self.since_partial = 0;
+        WindowAction::Commit { samples, offset }
+    }
+}
+
+/// 分块窗口流式识别客户端
+pub struct ChunkedStreamingClient<T: BatchTranscriber> {
+    transcriber: Arc<T>,
+    config: ChunkedWindowConfig,
+}
+
+impl<T: BatchTranscriber> ChunkedStreamingClient<T> {
+    /// 创建客户端
+    pub fn new(transcriber: Arc<T>) -> Self {
+        Self {
+            transcriber,
+            config: ChunkedWindowConfig::default(),
+        }
+    }
+
+    /// 设置窗口配置
+    pub fn with_config(mut self, config: ChunkedWindowConfig) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+/// 在阻塞线程中识别一个窗口，时间戳按窗口位置偏移
+async fn transcribe_window<T: BatchTranscriber>(
+    transcriber: &Arc<T>,
+    samples: Vec<i16>,
+    offset: usize,
+    sample_rate: u32,
+) -> Result<TranscribeResult> {
+    let transcriber = transcriber.clone();
+    let mut result = tokio::task::spawn_blocking(move || {
+        transcriber.transcribe_blocking(&AudioData::new(samples, sample_rate, 1))
+    })
+    .await
+    .map_err(|e| VoiceError::TranscriberError(format!("识别任务失败: {e}")))??;
+
+    let start = offset as f32 / sample_rate as f32;
+    for segment in &mut result.segments {
+        segment.start += start;
+        segment.end += start;
+    }
+    Ok(result)
+}
+
+#[async_trait]
+impl<T: BatchTranscriber> StreamingAsrClient for ChunkedStreamingClient<T> {
+    async fn start_stream(&self, sample_rate: u32) -> Result<AsrStream> {
+        let transcriber = self.transcriber.clone();
+        let mut window = ChunkedWindow::new(&self.config, sample_rate);
+        let min_tail = (MIN_TAIL_SECS * sample_rate as f32) as usize;
+
+        Ok(AsrStream::spawn(move |mut audio_rx, sink| async move {
+            let mut committed = String::new();
+            let mut segments: Vec<Segment> = Vec::new();
+            let mut language = None;
+
+            let mut finished = false;
+            while !finished {
+                let actions = match audio_rx.recv().await {
+                    Some(samples) => window.push(&samples),
+                    None => {
+                        finished = true;
+                        window.finish(min_tail).into_iter().collect()
+                    }
+                };
+
+                for action in actions {
+                    match action {
+                        WindowAction::Partial { samples, offset } => {
+                            let result =
+                                transcribe_window(&transcriber, samples, offset, sample_rate)
+                                    .await?;
+                            let _ = sink.send(StreamingTranscript {
+                                text: format!("{committed}{}", result.text),
+                                is_final: false,
+                            });
+                        }
+                        WindowAction::Commit { samples, offset } => {
+                            let result =
+                                transcribe_window(&transcriber, samples, offset, sample_rate)
+                                    .await?;
+                            committed.push_str(&result.text);
+                            segments.extend(result.segments);
+                            language = language.or(result.language);
+                            let _ = sink.send(StreamingTranscript {
+                                text: committed.clone(),
+                                is_final: true,
+                            });
+                        }
+                    }
+                }
+            }
+
+            Ok(TranscribeResult {
+                text: committed.trim().to_string(),
+                language,
+                confidence: None,
+                segments,
+            })
+        }))
+    }
+
+    fn name(&self) -> &'static str {
+        self.transcriber.name()
+    }
+}
@@ -1,9 +1,12 @@
 //! 云端 ASR 客户端模块
 //!
 //! 支持讯飞、百度、OpenAI Whisper 等云端语音识别服务。
+//! 讯飞、百度支持流式识别；本地 Whisper 通过分块窗口实现流式识别。
 
 pub mod baidu;
+pub mod chunked;
 pub mod openai;
+pub mod streaming;
 pub mod xunfei;
 
 use async_trait::async_trait;
@@ -22,5 +25,98 @@ pub trait AsrClient: Send + Sync {
 }
 
 pub use baidu::BaiduClient;
+pub use chunked::{BatchTranscriber, ChunkedStreamingClient, ChunkedWindowConfig};
 pub use openai::OpenAIWhisperClient;
+pub use streaming::{AsrStream, StreamingAsrClient, StreamingTranscript};
 pub use xunfei::XunfeiClient;
+
+/// 流式线性插值重采样器
+///
+/// 音频按任意大小的块陆续送入时，跨块保留输出位置和插值所需的末尾采样，
+/// 输出与一次性重采样整段音频一致，不会在块边界处丢失或重复采样。
+/// 位置用整数比例计算，长音频也不会累积浮点误差。
+pub(crate) struct StreamResampler {
+    from_rate: u64,
+    to_rate: u64,
+    /// 已输出的采样数
+    emitted: u64,
+    /// 已从 `buffer` 头部丢弃的输入采样数
+    dropped: u64,
+    /// 尚未消费完的输入采样
+    buffer: Vec<i16>,
+}
+
+impl StreamResampler {
+    pub(crate) fn new(from_rate: u32, to_rate: u32) -> Self {
+        Self {
+            from_rate: from_rate as u64,
+            to_rate: to_rate as u64,
+            emitted: 0,
+            dropped: 0,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// 下一个输出采样在 `buffer` 中的整数下标和小数部分
+    fn next_position(&self) -> (usize, f64) {
+        let num = self.emitted * self.from_rate;
+        let index = (num / self.to_rate - self.dropped) as usize;
+        let frac = (num % self.to_rate) as f64 / self.to_rate as f64;
+        (index, frac)
+    }
+
+    /// 送入采样，返回当前可以确定的输出采样
+    pub(crate) fn push(&mut self, samples: &[i16]) -> Vec<i16> {
+        if self.from_rate == self.to_rate {
+            return samples.to_vec();
+        }
+        self.buffer.extend_from_slice(samples);
+
+        let mut result = Vec::new();
+        loop {
+            let (index, frac) = self.next_position();
+            // 右侧插值点还没到，留到下一块
+            if index + 1 >= self.buffer.len() {
+                break;
+            }
+            let s1 = self.buffer[index] as f64;
+            let s2 = self.buffer[index + 1] as f64;
+            result.push((s1 + (s2 - s1) * frac) as i16);
+            self.emitted += 1;
+        }
+
+        let consumed = self.next_position().0.min(self.buffer.len());
+        self.buffer.drain(..consumed);
+        self.dropped += consumed as u64;
+        result
+    }
+
+    /// 音频结束，输出剩余采样（末尾没有右侧插值点，直接取原值）
+    pub(crate) fn flush(&mut self) -> Vec<i16> {
+        let mut result = Vec::new();
+        if self.from_rate != self.to_rate {
+            loop {
+                let (index, _) = self.next_position();
+                if index >= self.buffer.len() {
+                    break;
+                }
+                result.push(self.buffer[index]);
+                self.emitted += 1;
+            }
+        }
+        self.buffer.clear();
+        self.emitted = 0;
+        self.dropped = 0;
+        result
+    }
+}
+
+/// 简单的线性插值重采样
+///
+/// 将音频从源采样率转换到目标采样率
+pub(crate) fn resample(samples: &[i16], from_rate: u32, to_rate: u32) -> Vec<i16> {
+    let mut resampler = StreamResampler::new(from_rate, to_rate);
+    let mut result = resampler.push(samples);
+    result.extend(resampler.flush());
+    result
+}
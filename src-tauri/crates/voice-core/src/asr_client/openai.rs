@@ -48,18 +48,20 @@ impl OpenAIWhisperClient {
         self.language = Some(language);
         self
     }
-}
 
-#[async_trait]
-impl AsrClient for OpenAIWhisperClient {
-    async fn transcribe(&self, audio: &AudioData) -> Result<TranscribeResult> {
+    /// 识别已编码的音频文件（mp3、m4a、webm 等 Whisper API 支持的格式），原样上传
+    pub async fn transcribe_file(
+        &self,
+        bytes: Vec<u8>,
+        file_name: &str,
+        mime: &str,
+    ) -> Result<TranscribeResult> {
         let url = format!("{}/v1/audio/transcriptions", self.api_host);
-        let wav_bytes = audio.to_wav_bytes();
 
         // 构建 multipart form
-        let file_part = Part::bytes(wav_bytes)
-            .file_name("audio.wav")
-            .mime_str("audio/wav")
+        let file_part = Part::bytes(bytes)
+            .file_name(file_name.to_string())
+            .mime_str(mime)
             .map_err(|e| VoiceError::AsrError(e.to_string()))?;
 
         let mut form = Form::new()
@@ -100,6 +102,14 @@ impl AsrClient for OpenAIWhisperClient {
             segments: vec![],
         })
     }
+}
+
+#[async_trait]
+impl AsrClient for OpenAIWhisperClient {
+    async fn transcribe(&self, audio: &AudioData) -> Result<TranscribeResult> {
+        self.transcribe_file(audio.to_wav_bytes(), "audio.wav", "audio/wav")
+            .await
+    }
 
     fn name(&self) -> &'static str {
         "OpenAI Whisper"
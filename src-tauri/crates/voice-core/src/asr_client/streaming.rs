@@ -0,0 +1,182 @@
+//! 流式语音识别
+//!
+//! 边录音边识别：调用方持续送入 PCM16 单声道音频，后端异步返回中间结果（partial）
+//! 和最终结果（final），结束时得到完整的识别结果。
+//!
+//! 各后端通过 [`AsrStream::spawn`] 启动驱动任务，驱动任务从音频通道读取采样、
+//! 向结果通道写入识别文本，返回值即最终识别结果。
+
+use std::future::Future;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::error::{Result, VoiceError};
+use crate::types::TranscribeResult;
+
+/// 流式识别结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamingTranscript {
+    /// 当前完整文本（已确认部分 + 识别中的部分）
+    pub text: String,
+    /// 是否为最终结果（之后不会再被修正）
+    pub is_final: bool,
+}
+
+/// 流式识别结果发送端（由后端驱动任务持有）
+pub type TranscriptSink = mpsc::UnboundedSender<StreamingTranscript>;
+
+/// 音频接收端（由后端驱动任务持有）
+pub type AudioSource = mpsc::UnboundedReceiver<Vec<i16>>;
+
+/// 一次流式识别会话
+pub struct AsrStream {
+    audio_tx: Option<mpsc::UnboundedSender<Vec<i16>>>,
+    transcripts: mpsc::UnboundedReceiver<StreamingTranscript>,
+    task: JoinHandle<Result<TranscribeResult>>,
+}
+
+impl AsrStream {
+    /// 启动后端驱动任务
+    pub fn spawn<F, Fut>(driver: F) -> Self
+    where
+        F: FnOnce(AudioSource, TranscriptSink) -> Fut,
+        Fut: Future<Output = Result<TranscribeResult>> + Send + 'static,
+    {
+        let (audio_tx, audio_rx) = mpsc::unbounded_channel();
+        let (transcript_tx, transcripts) = mpsc::unbounded_channel();
+        let task = tokio::spawn(driver(audio_rx, transcript_tx));
+        Self {
+            audio_tx: Some(audio_tx),
+            transcripts,
+            task,
+        }
+    }
+
+    /// 送入音频（PCM16 单声道，采样率与 `start_stream` 一致）
+    pub fn send_audio(&self, samples: &[i16]) -> Result<()> {
+        if samples.is_empty() {
+            return Ok(());
+        }
+        self.audio_tx
+            .as_ref()
+            .ok_or_else(|| VoiceError::AsrError("流式识别已结束".to_string()))?
+            .send(samples.to_vec())
+            .map_err(|_| VoiceError::AsrError("流式识别已中断".to_string()))
+    }
+
+    /// 等待下一条识别结果，识别结束后返回 None
+    pub async fn next_transcript(&mut self) -> Option<StreamingTranscript> {
+        self.transcripts.recv().await
+    }
+
+    /// 获取已到达的识别结果（不等待）
+    pub fn try_next_transcript(&mut self) -> Option<StreamingTranscript> {
+        self.transcripts.try_recv().ok()
+    }
+
+    /// 结束送入音频，等待最终识别结果
+    pub async fn finish(mut self) -> Result<TranscribeResult> {
+        self.audio_tx.take();
+        self.task
+            .await
+            .map_err(|e| VoiceError::AsrError(format!("流式识别任务失败: {e}")))?
+    }
+
+    /// 取消识别
+    pub fn abort(self) {
+        self.task.abort();
+    }
+}
+
+/// 流式 ASR 客户端 trait
+#[async_trait]
+pub trait StreamingAsrClient: Send + Sync {
+    /// 开始一次流式识别
+    ///
+    /// # 参数
+    /// - `sample_rate`: 后续送入音频的采样率
+    async fn start_stream(&self, sample_rate: u32) -> Result<AsrStream>;
+
+    /// 获取服务名称
+    fn name(&self) -> &'static str;
+}
+
+/// 将任意采样率的音频重采样为 16kHz 并切成固定大小的 PCM16 LE 帧
+pub(crate) struct PcmFramer {
+    resampler: super::StreamResampler,
+    frame_bytes: usize,
+    pending: Vec<u8>,
+}
+
+impl PcmFramer {
+    pub(crate) fn new(from_rate: u32, frame_bytes: usize) -> Self {
+        Self {
+            resampler: super::StreamResampler::new(from_rate, 16000),
+            frame_bytes,
+            pending: Vec::new(),
+        }
+    }
+
+    /// 送入采样，返回凑满的帧
+    pub(crate) fn push(&mut self, samples: &[i16]) -> Vec<Vec<u8>> {
+        let samples = self.resampler.push(samples);
+        self.pending
+            .extend(samples.iter().flat_map(|sample| sample.to_le_bytes()));
+
+        let mut frames = Vec::new();
+        while self.pending.len() >= self.frame_bytes {
+            let rest = self.pending.split_off(self.frame_bytes);
+            frames.push(std::mem::replace(&mut self.pending, rest));
+        }
+        frames
+    }
+
+    /// 取出剩余不足一帧的数据（含重采样器中尚未输出的尾部采样）
+    pub(crate) fn flush(&mut self) -> Vec<u8> {
+        let tail = self.resampler.flush();
+        self.pending
+            .extend(tail.iter().flat_map(|sample| sample.to_le_bytes()));
+        std::mem::take(&mut self.pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(framer: &mut PcmFramer, chunks: &[&[i16]]) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        for chunk in chunks {
+            bytes.extend(framer.push(chunk).concat());
+        }
+        bytes.extend(framer.flush());
+        bytes
+    }
+
+    #[test]
+    fn test_framer_chunked_matches_one_shot() {
+        let samples: Vec<i16> = (0..44100)
+            .map(|i| ((i % 400) * 50 - 10000) as i16)
+            .collect();
+        let one_shot = collect(&mut PcmFramer::new(44100, 1280), &[&samples]);
+
+        // 录音回调常见的小块（441 采样 = 10ms），逐块重采样时不能丢失小数部分
+        let chunks: Vec<&[i16]> = samples.chunks(441).collect();
+        let chunked = collect(&mut PcmFramer::new(44100, 1280), &chunks);
+
+        assert_eq!(one_shot.len(), 16000 * 2);
+        assert_eq!(chunked, one_shot);
+    }
+
+    #[test]
+    fn test_framer_passthrough_at_16k() {
+        let samples: Vec<i16> = (0..1000).collect();
+        let mut framer = PcmFramer::new(16000, 640);
+        let frames = framer.push(&samples);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(framer.flush().len(), 2000 - 3 * 640);
+    }
+}
@@ -10,6 +10,9 @@
 //! 3. 接收识别结果（流式返回）
 //! 4. 发送结束帧，等待最终结果
 //!
+//! 同时实现了 [`AsrClient`]（整段识别）和 [`StreamingAsrClient`]（边录边识别），
+//! 流式识别时每条响应都会按动态修正规则重新拼接文本，作为中间结果返回。
+//!
 //! ## 参考文档
 //! https://www.xfyun.cn/doc/asr/voicedictation/API.html
 
@@ -22,7 +25,8 @@ use serde::{Deserialize, Serialize};
 use sha2::Sha256;
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 
-use super::AsrClient;
+use super::streaming::{AsrStream, PcmFramer, StreamingAsrClient, StreamingTranscript};
+use super::{resample, AsrClient};
 use crate::error::{Result, VoiceError};
 use crate::types::{AudioData, Segment, TranscribeResult};
 
@@ -30,40 +34,11 @@ use crate::types::{AudioData, Segment, TranscribeResult};
 /// 讯飞建议每帧发送 1280 字节（约 40ms 的 16kHz 16bit 单声道音频）
 const FRAME_SIZE: usize = 1280;
 
-/// 简单的线性插值重采样
-///
-/// 将音频从源采样率转换到目标采样率
-fn resample(samples: &[i16], from_rate: u32, to_rate: u32) -> Vec<i16> {
-    if from_rate == to_rate {
-        return samples.to_vec();
-    }
-
-    let ratio = from_rate as f64 / to_rate as f64;
-    let new_len = (samples.len() as f64 / ratio) as usize;
-    let mut result = Vec::with_capacity(new_len);
-
-    for i in 0..new_len {
-        let src_idx = i as f64 * ratio;
-        let idx_floor = src_idx.floor() as usize;
-        let idx_ceil = (idx_floor + 1).min(samples.len() - 1);
-        let frac = src_idx - idx_floor as f64;
-
-        // 线性插值
-        let sample = if idx_floor < samples.len() {
-            let s1 = samples[idx_floor] as f64;
-            let s2 = samples[idx_ceil] as f64;
-            (s1 + (s2 - s1) * frac) as i16
-        } else {
-            0
-        };
-
-        result.push(sample);
-    }
-
-    result
-}
+/// 发送尾帧后等待最终结果的超时时间（秒）
+const FINAL_RESULT_TIMEOUT_SECS: u64 = 30;
 
 /// 讯飞客户端
+#[derive(Clone)]
 pub struct XunfeiClient {
     app_id: String,
     api_key: String,
@@ -192,75 +167,65 @@ impl XunfeiClient {
     }
 
     /// 解析识别结果（支持动态修正）
-    ///
-    /// 动态修正说明：
-    /// - pgs="apd": 追加到之前的结果
-    /// - pgs="rpl": 替换之前的部分结果，替换范围由 rg 字段指定
     fn parse_result(responses: &[XunfeiResponse]) -> TranscribeResult {
-        // 使用 HashMap 存储每个 sn 对应的文本，支持动态修正替换
-        let mut sn_texts: std::collections::HashMap<i32, String> = std::collections::HashMap::new();
-        let mut max_sn: i32 = 0;
-
+        let mut assembler = XunfeiTranscriptAssembler::default();
         for resp in responses {
-            if let Some(ref data) = resp.data {
-                if let Some(ref result) = data.result {
-                    let sn = result.sn.unwrap_or(0);
-                    max_sn = max_sn.max(sn);
-
-                    // 提取当前结果的文本
-                    let mut current_text = String::new();
-                    for ws in &result.ws {
-                        for cw in &ws.cw {
-                            current_text.push_str(&cw.w);
-                        }
-                    }
-
-                    // 根据 pgs 字段处理
-                    match result.pgs.as_deref() {
-                        Some("rpl") => {
-                            // 替换模式：删除 rg 范围内的结果，然后添加当前结果
-                            if let Some(ref rg) = result.rg {
-                                if rg.len() >= 2 {
-                                    let start = rg[0];
-                                    let end = rg[1];
-                                    // 删除 [start, end] 范围内的所有 sn
-                                    for i in start..=end {
-                                        sn_texts.remove(&i);
-                                    }
-                                    tracing::debug!(
-                                        "动态修正替换: sn={}, rg=[{}, {}], text={}",
-                                        sn,
-                                        start,
-                                        end,
-                                        current_text
-                                    );
-                                }
-                            }
-                            sn_texts.insert(sn, current_text);
-                        }
-                        Some("apd") | None => {
-                            // 追加模式或无标识：直接添加
-                            sn_texts.insert(sn, current_text);
-                        }
-                        _ => {
-                            // 其他情况也直接添加
-                            sn_texts.insert(sn, current_text);
-                        }
-                    }
-                }
-            }
+            assembler.apply(resp);
         }
+        assembler.into_result()
+    }
+}
 
-        // 按 sn 顺序拼接最终文本
-        let mut full_text = String::new();
-        let mut sorted_sns: Vec<i32> = sn_texts.keys().cloned().collect();
-        sorted_sns.sort();
+/// 识别结果拼接器（支持动态修正）
+///
+/// 动态修正说明：
+/// - pgs="apd": 追加到之前的结果
+/// - pgs="rpl": 替换之前的部分结果，替换范围由 rg 字段指定
+#[derive(Default)]
+struct XunfeiTranscriptAssembler {
+    /// 每个 sn 对应的文本（按 sn 排序）
+    sn_texts: std::collections::BTreeMap<i32, String>,
+}
 
-        for sn in sorted_sns {
-            if let Some(text) = sn_texts.get(&sn) {
-                full_text.push_str(text);
+impl XunfeiTranscriptAssembler {
+    /// 应用一条响应
+    fn apply(&mut self, resp: &XunfeiResponse) {
+        let Some(result) = resp.data.as_ref().and_then(|data| data.result.as_ref()) else {
+            return;
+        };
+        let sn = result.sn.unwrap_or(0);
+
+        // 提取当前结果的文本
+        let current_text: String = result
+            .ws
+            .iter()
+            .flat_map(|ws| ws.cw.iter().map(|cw| cw.w.as_str()))
+            .collect();
+
+        // 替换模式：删除 rg 范围内的结果，然后添加当前结果
+        if result.pgs.as_deref() == Some("rpl") {
+            if let Some(rg) = result.rg.as_ref().filter(|rg| rg.len() >= 2) {
+                let (start, end) = (rg[0], rg[1]);
+                self.sn_texts.retain(|sn, _| *sn < start || *sn > end);
+                tracing::debug!(
+                    "动态修正替换: sn={}, rg=[{}, {}], text={}",
+                    sn,
+                    start,
+                    end,
+                    current_text
+                );
             }
         }
+        self.sn_texts.insert(sn, current_text);
+    }
+
+    /// 按 sn 顺序拼接的当前文本
+    fn text(&self) -> String {
+        self.sn_texts.values().map(String::as_str).collect()
+    }
+
+    fn into_result(self) -> TranscribeResult {
+        let full_text = self.text();
 
         let mut segments = Vec::new();
         // 如果有文本，创建一个整体的 segment
@@ -478,6 +443,116 @@ impl AsrClient for XunfeiClient {
     }
 }
 
+#[async_trait]
+impl StreamingAsrClient for XunfeiClient {
+    async fn start_stream(&self, sample_rate: u32) -> Result<AsrStream> {
+        let url = self.generate_auth_url()?;
+        let (ws_stream, _) = connect_async(&url).await.map_err(|e| {
+            tracing::error!("讯飞 WebSocket 连接失败: {:?}", e);
+            VoiceError::NetworkError(format!("WebSocket 连接失败: {e}"))
+        })?;
+        tracing::info!("讯飞流式识别已连接");
+
+        let client = self.clone();
+        Ok(AsrStream::spawn(move |mut audio_rx, sink| async move {
+            let (mut write, mut read) = ws_stream.split();
+            let mut framer = PcmFramer::new(sample_rate, FRAME_SIZE);
+            let mut assembler = XunfeiTranscriptAssembler::default();
+            let mut first_sent = false;
+            let mut audio_done = false;
+
+            loop {
+                tokio::select! {
+                    chunk = audio_rx.recv(), if !audio_done => {
+                        let requests = match chunk {
+                            Some(samples) => framer
+                                .push(&samples)
+                                .iter()
+                                .map(|frame| {
+                                    let request = if first_sent {
+                                        client.build_continue_frame(frame)
+                                    } else {
+                                        client.build_first_frame(frame)
+                                    };
+                                    first_sent = true;
+                                    request
+                                })
+                                .collect(),
+                            None => {
+                                // 音频结束：发送剩余数据和尾帧
+                                audio_done = true;
+                                let rest = framer.flush();
+                                let mut requests = Vec::new();
+                                if !first_sent {
+                                    requests.push(client.build_first_frame(&rest));
+                                    requests.push(client.build_last_frame(&[]));
+                                } else {
+                                    requests.push(client.build_last_frame(&rest));
+                                }
+                                requests
+                            }
+                        };
+
+                        for request in requests {
+                            let json = serde_json::to_string(&request).map_err(|e| {
+                                VoiceError::AsrError(format!("序列化请求失败: {e}"))
+                            })?;
+                            write.send(Message::Text(json)).await.map_err(|e| {
+                                VoiceError::NetworkError(format!("发送数据失败: {e}"))
+                            })?;
+                        }
+                    }
+                    msg = read.next() => {
+                        let text = match msg {
+                            Some(Ok(Message::Text(text))) => text,
+                            Some(Ok(Message::Close(frame))) => {
+                                tracing::info!("WebSocket 连接关闭: {:?}", frame);
+                                break;
+                            }
+                            Some(Ok(_)) => continue,
+                            Some(Err(e)) => {
+                                return Err(VoiceError::NetworkError(format!("接收数据失败: {e}")));
+                            }
+                            None => break,
+                        };
+
+                        let response: XunfeiResponse = serde_json::from_str(&text)
+                            .map_err(|e| VoiceError::AsrError(format!("解析响应失败: {e}")))?;
+                        if response.code != 0 {
+                            return Err(VoiceError::AsrError(format!(
+                                "讯飞 ASR 错误 [{}]: {}",
+                                response.code,
+                                response.message.clone().unwrap_or_default()
+                            )));
+                        }
+
+                        assembler.apply(&response);
+                        let is_final = response.data.as_ref().is_some_and(|d| d.status == 2);
+                        let _ = sink.send(StreamingTranscript {
+                            text: assembler.text(),
+                            is_final,
+                        });
+                        if is_final {
+                            break;
+                        }
+                    }
+                    _ = tokio::time::sleep(tokio::time::Duration::from_secs(FINAL_RESULT_TIMEOUT_SECS)), if audio_done => {
+                        return Err(VoiceError::AsrError("等待识别结果超时".to_string()));
+                    }
+                }
+            }
+
+            let result = assembler.into_result();
+            tracing::info!("讯飞流式识别完成: {}", result.text);
+            Ok(result)
+        }))
+    }
+
+    fn name(&self) -> &'static str {
+        "讯飞语音"
+    }
+}
+
 // ============================================================================
 // 讯飞 WebSocket 协议数据结构
 // ============================================================================
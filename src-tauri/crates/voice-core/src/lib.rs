@@ -13,6 +13,7 @@ pub mod threaded_recorder;
 #[cfg(feature = "local-whisper")]
 pub mod transcriber;
 pub mod types;
+pub mod vad;
 
 pub use device::{list_audio_devices, AudioDeviceInfo};
 pub use error::{Result, VoiceError};
@@ -22,3 +23,6 @@ pub use threaded_recorder::{RecordingCommand, RecordingResponse, RecordingServic
 #[cfg(feature = "local-whisper")]
 pub use transcriber::WhisperTranscriber;
 pub use types::*;
+pub use vad::{
+    split_utterances, trim_silence, Utterance, VadConfig, VadEvent, VoiceActivityDetector,
+};
@@ -18,8 +18,12 @@
 //! - 录音线程拥有 `cpal::Stream`，在独立线程中运行
 //! - Tauri 命令通过 channel 发送控制指令
 //! - 录音线程通过 channel 返回结果
+//!
+//! 通过 [`RecordingService::start_with_vad`] 开始录音时，录音回调中同时运行 VAD，
+//! 实时推送语句切分事件；停止录音时返回裁剪掉静音后的音频。
 
 use crate::types::AudioData;
+use crate::vad::{trim_silence, VadConfig, VadEvent, VoiceActivityDetector};
 use parking_lot::Mutex;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::mpsc::{self, Receiver, Sender};
@@ -32,6 +36,15 @@ use std::time::Instant;
 pub enum RecordingCommand {
     /// 开始录音（可选指定设备 ID）
     Start(Option<String>),
+    /// 开始录音并启用 VAD，事件通过 `events` 推送
+    StartWithVad {
+        /// 设备 ID
+        device_id: Option<String>,
+        /// VAD 配置
+        config: VadConfig,
+        /// VAD 事件发送端
+        events: Sender<VadEvent>,
+    },
     /// 停止录音
     Stop,
     /// 取消录音
@@ -67,6 +80,8 @@ pub struct RecordingService {
     volume_level: Arc<AtomicU32>,
     /// 录音开始时间（共享状态）
     start_time: Arc<Mutex<Option<Instant>>>,
+    /// 当前录音的实际采样率（共享状态）
+    sample_rate: Arc<AtomicU32>,
 }
 
 impl RecordingService {
@@ -79,6 +94,7 @@ impl RecordingService {
             is_recording: Arc::new(AtomicBool::new(false)),
             volume_level: Arc::new(AtomicU32::new(0)),
             start_time: Arc::new(Mutex::new(None)),
+            sample_rate: Arc::new(AtomicU32::new(16000)),
         }
     }
 
@@ -94,9 +110,17 @@ impl RecordingService {
         let is_recording = Arc::clone(&self.is_recording);
         let volume_level = Arc::clone(&self.volume_level);
         let start_time = Arc::clone(&self.start_time);
+        let sample_rate = Arc::clone(&self.sample_rate);
 
         let handle = thread::spawn(move || {
-            recording_thread_main(cmd_rx, resp_tx, is_recording, volume_level, start_time);
+            recording_thread_main(
+                cmd_rx,
+                resp_tx,
+                is_recording,
+                volume_level,
+                start_time,
+                sample_rate,
+            );
         });
 
         self.command_tx = Some(cmd_tx);
@@ -108,13 +132,34 @@ impl RecordingService {
 
     /// 开始录音（可选指定设备 ID）
     pub fn start(&mut self, device_id: Option<String>) -> Result<(), String> {
+        self.send_start(RecordingCommand::Start(device_id))
+    }
+
+    /// 开始录音并启用 VAD
+    ///
+    /// 返回 VAD 事件接收端；停止录音时返回的音频只包含检测到的语句，
+    /// 未检测到语音时返回错误。
+    pub fn start_with_vad(
+        &mut self,
+        device_id: Option<String>,
+        config: VadConfig,
+    ) -> Result<Receiver<VadEvent>, String> {
+        let (events_tx, events_rx) = mpsc::channel();
+        self.send_start(RecordingCommand::StartWithVad {
+            device_id,
+            config,
+            events: events_tx,
+        })?;
+        Ok(events_rx)
+    }
+
+    fn send_start(&mut self, command: RecordingCommand) -> Result<(), String> {
         self.ensure_thread_started();
 
         let tx = self.command_tx.as_ref().ok_or("录音线程未启动")?;
         let rx = self.response_rx.as_ref().ok_or("录音线程未启动")?;
 
-        tx.send(RecordingCommand::Start(device_id))
-            .map_err(|e| format!("发送命令失败: {e}"))?;
+        tx.send(command).map_err(|e| format!("发送命令失败: {e}"))?;
 
         match rx.recv() {
             Ok(RecordingResponse::Ok) => {
@@ -184,6 +229,11 @@ impl RecordingService {
             .unwrap_or(0.0)
     }
 
+    /// 当前录音的实际采样率（VAD 事件中的音频即为该采样率）
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate.load(Ordering::SeqCst)
+    }
+
     /// 是否正在录音
     pub fn is_recording(&self) -> bool {
         self.is_recording.load(Ordering::SeqCst)
@@ -223,11 +273,15 @@ fn recording_thread_main(
     is_recording: Arc<AtomicBool>,
     volume_level: Arc<AtomicU32>,
     start_time: Arc<Mutex<Option<Instant>>>,
+    sample_rate: Arc<AtomicU32>,
 ) {
     use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 
     // 录音数据缓冲区
     let samples: Arc<Mutex<Vec<i16>>> = Arc::new(Mutex::new(Vec::new()));
+    // VAD 检测器及事件发送端（仅 StartWithVad 时启用）
+    let vad: Arc<Mutex<Option<(VoiceActivityDetector, Sender<VadEvent>)>>> =
+        Arc::new(Mutex::new(None));
     // 当前活跃的音频流
     let mut active_stream: Option<cpal::Stream> = None;
     // 实际使用的采样率和声道数
@@ -238,7 +292,20 @@ fn recording_thread_main(
     tracing::debug!("[录音线程] 开始运行");
 
     loop {
-        match cmd_rx.recv() {
+        // StartWithVad 与 Start 共用启动流程，VAD 在确定采样率后创建
+        let (command, vad_setup) = match cmd_rx.recv() {
+            Ok(RecordingCommand::StartWithVad {
+                device_id,
+                config,
+                events,
+            }) => (
+                Ok(RecordingCommand::Start(device_id)),
+                Some((config, events)),
+            ),
+            other => (other, None),
+        };
+
+        match command {
             Ok(RecordingCommand::Start(device_id)) => {
                 // 如果已在录音，返回错误
                 if is_recording.load(Ordering::SeqCst) {
@@ -248,6 +315,7 @@ fn recording_thread_main(
 
                 // 清空缓冲区
                 samples.lock().clear();
+                *vad.lock() = None;
 
                 // 获取输入设备
                 let host = cpal::default_host();
@@ -295,6 +363,7 @@ fn recording_thread_main(
 
                 // 使用设备默认配置
                 actual_sample_rate = supported_config.sample_rate().0;
+                sample_rate.store(actual_sample_rate, Ordering::SeqCst);
                 actual_channels = supported_config.channels();
 
                 let config = cpal::StreamConfig {
@@ -303,8 +372,16 @@ fn recording_thread_main(
                     buffer_size: cpal::BufferSize::Default,
                 };
 
+                *vad.lock() = vad_setup.map(|(vad_config, events)| {
+                    (
+                        VoiceActivityDetector::new(vad_config, actual_sample_rate),
+                        events,
+                    )
+                });
+
                 // 创建共享状态的克隆
                 let samples_clone = Arc::clone(&samples);
+                let vad_clone = Arc::clone(&vad);
                 let volume_clone = Arc::clone(&volume_level);
                 let is_rec_clone = Arc::clone(&is_recording);
                 let channels = actual_channels;
@@ -359,6 +436,12 @@ fn recording_thread_main(
                             .map(|&s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
                             .collect();
 
+                        if let Some((detector, events)) = vad_clone.lock().as_mut() {
+                            for event in detector.push(&i16_samples) {
+                                let _ = events.send(event);
+                            }
+                        }
+
                         samples_clone.lock().extend(i16_samples);
                     },
                     |err| {
@@ -422,9 +505,29 @@ fn recording_thread_main(
                     let _ = resp_tx.send(RecordingResponse::Error(
                         "录音时间过短（需要至少 0.5 秒）".to_string(),
                     ));
+                    *vad.lock() = None;
                     continue;
                 }
 
+                // 启用 VAD 时推送剩余事件，并裁剪静音
+                let vad_state = vad.lock().take();
+                let audio = match vad_state {
+                    Some((mut detector, events)) => {
+                        for event in detector.flush() {
+                            let _ = events.send(event);
+                        }
+                        match trim_silence(&audio, detector.config()) {
+                            Some(trimmed) => trimmed,
+                            None => {
+                                let _ = resp_tx
+                                    .send(RecordingResponse::Error("未检测到语音".to_string()));
+                                continue;
+                            }
+                        }
+                    }
+                    None => audio,
+                };
+
                 let _ = resp_tx.send(RecordingResponse::AudioData(audio));
                 tracing::info!("[录音线程] 停止录音");
             }
@@ -440,6 +543,7 @@ fn recording_thread_main(
 
                 // 清空缓冲区
                 samples.lock().clear();
+                *vad.lock() = None;
 
                 // 重置状态
                 *start_time.lock() = None;
@@ -5,6 +5,7 @@
 use std::path::PathBuf;
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
 
+use crate::asr_client::BatchTranscriber;
 use crate::error::{Result, VoiceError};
 use crate::types::{AudioData, Segment, TranscribeResult, WhisperModel};
 
@@ -122,3 +123,13 @@ impl WhisperTranscriber {
         &self.language
     }
 }
+
+impl BatchTranscriber for WhisperTranscriber {
+    fn transcribe_blocking(&self, audio: &AudioData) -> Result<TranscribeResult> {
+        self.transcribe(audio)
+    }
+
+    fn name(&self) -> &'static str {
+        "本地 Whisper"
+    }
+}
@@ -4,6 +4,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::error::{Result, VoiceError};
+
 /// 音频数据
 #[derive(Debug, Clone)]
 pub struct AudioData {
@@ -44,6 +46,53 @@ impl AudioData {
         Self::new(samples, sample_rate, channels)
     }
 
+    /// 从 WAV 文件字节创建单声道音频数据
+    ///
+    /// 支持 8/16/24/32 位整数和 32 位浮点 PCM，多声道会被混合为单声道。
+    pub fn from_wav_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = hound::WavReader::new(std::io::Cursor::new(bytes))
+            .map_err(|e| VoiceError::AudioFormatError(format!("无法解析 WAV: {e}")))?;
+        let spec = reader.spec();
+        let format_error = |e: hound::Error| VoiceError::AudioFormatError(e.to_string());
+
+        let samples: Vec<i16> = match (spec.sample_format, spec.bits_per_sample) {
+            (hound::SampleFormat::Float, 32) => reader
+                .samples::<f32>()
+                .map(|s| s.map(|v| (v.clamp(-1.0, 1.0) * i16::MAX as f32) as i16))
+                .collect::<std::result::Result<_, _>>()
+                .map_err(format_error)?,
+            (hound::SampleFormat::Int, bits @ 1..=16) => reader
+                .samples::<i16>()
+                .map(|s| s.map(|v| v << (16 - bits)))
+                .collect::<std::result::Result<_, _>>()
+                .map_err(format_error)?,
+            (hound::SampleFormat::Int, bits @ 17..=32) => reader
+                .samples::<i32>()
+                .map(|s| s.map(|v| (v >> (bits - 16)) as i16))
+                .collect::<std::result::Result<_, _>>()
+                .map_err(format_error)?,
+            (format, bits) => {
+                return Err(VoiceError::AudioFormatError(format!(
+                    "不支持的 WAV 格式: {format:?} {bits} 位"
+                )))
+            }
+        };
+
+        let channels = spec.channels.max(1) as usize;
+        let samples = if channels > 1 {
+            samples
+                .chunks(channels)
+                .map(|frame| {
+                    (frame.iter().map(|&s| s as i32).sum::<i32>() / frame.len() as i32) as i16
+                })
+                .collect()
+        } else {
+            samples
+        };
+
+        Ok(Self::new(samples, spec.sample_rate, 1))
+    }
+
     /// 转换为 PCM16 LE 字节
     pub fn to_pcm16le_bytes(&self) -> Vec<u8> {
         self.samples
@@ -0,0 +1,322 @@
+//! 语音活动检测（VAD）
+//!
+//! 基于短时能量的 VAD：按帧计算 RMS，与自适应噪声基线比较判定语音帧，
+//! 再用起止帧数做迟滞，避免在语音/静音之间抖动。
+//!
+//! 录音时用于自动切分语句、裁剪首尾静音，避免把静音发送给按时长计费的云端 ASR。
+//! 输入均为单声道 PCM16 采样。
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::AudioData;
+
+/// VAD 配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VadConfig {
+    /// 帧长（毫秒）
+    pub frame_ms: u32,
+    /// 语音帧的最低 RMS（0.0 - 1.0，约 -40 dBFS）
+    pub min_energy: f32,
+    /// 语音帧 RMS 需高于噪声基线的倍数
+    pub noise_ratio: f32,
+    /// 连续语音达到该时长才判定语句开始（毫秒）
+    pub speech_start_ms: u32,
+    /// 连续静音达到该时长判定语句结束（毫秒）
+    pub speech_end_ms: u32,
+    /// 语句前后保留的静音（毫秒）
+    pub padding_ms: u32,
+    /// 短于该时长的语句被丢弃（毫秒）
+    pub min_utterance_ms: u32,
+    /// 超过该时长的语句被强制切分（毫秒）
+    pub max_utterance_ms: u32,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            frame_ms: 30,
+            min_energy: 0.01,
+            noise_ratio: 3.0,
+            speech_start_ms: 90,
+            speech_end_ms: 600,
+            padding_ms: 200,
+            min_utterance_ms: 250,
+            max_utterance_ms: 30_000,
+        }
+    }
+}
+
+/// 切分出的语句
+#[derive(Debug, Clone)]
+pub struct Utterance {
+    /// 开始时间（秒，相对录音开始）
+    pub start_secs: f32,
+    /// 结束时间（秒，相对录音开始）
+    pub end_secs: f32,
+    /// 语句音频（含前后 padding）
+    pub audio: AudioData,
+}
+
+/// VAD 事件
+#[derive(Debug, Clone)]
+pub enum VadEvent {
+    /// 语句开始
+    SpeechStart {
+        /// 开始时间（秒，含 padding）
+        start_secs: f32,
+    },
+    /// 语句中的音频（首个事件包含 padding），可直接送入流式 ASR
+    SpeechAudio(Vec<i16>),
+    /// 语句结束
+    SpeechEnd(Utterance),
+}
+
+/// 语音活动检测器
+pub struct VoiceActivityDetector {
+    config: VadConfig,
+    sample_rate: u32,
+    frame_len: usize,
+    /// 未凑满一帧的采样
+    pending: Vec<i16>,
+    /// 已处理的采样数
+    processed: u64,
+    /// 噪声基线（RMS）
+    noise_floor: Option<f32>,
+    /// 静音状态下保留的最近音频（用作 padding）
+    pre_roll: VecDeque<i16>,
+    /// 疑似语音的起始帧
+    candidate: Vec<i16>,
+    /// 连续语音帧数
+    speech_frames: u32,
+    /// 连续静音帧数
+    silence_frames: u32,
+    /// 当前语句
+    utterance: Option<Vec<i16>>,
+    /// 当前语句开始位置（采样）
+    utterance_start: u64,
+}
+
+impl VoiceActivityDetector {
+    /// 创建检测器
+    pub fn new(config: VadConfig, sample_rate: u32) -> Self {
+        let frame_len = (sample_rate as u64 * config.frame_ms.max(1) as u64 / 1000).max(1) as usize;
+        Self {
+            config,
+            sample_rate,
+            frame_len,
+            pending: Vec::new(),
+            processed: 0,
+            noise_floor: None,
+            pre_roll: VecDeque::new(),
+            candidate: Vec::new(),
+            speech_frames: 0,
+            silence_frames: 0,
+            utterance: None,
+            utterance_start: 0,
+        }
+    }
+
+    /// VAD 配置
+    pub fn config(&self) -> &VadConfig {
+        &self.config
+    }
+
+    /// 采样率
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// 当前是否处于语句中
+    pub fn is_speaking(&self) -> bool {
+        self.utterance.is_some()
+    }
+
+    /// 送入音频，返回产生的事件
+    pub fn push(&mut self, samples: &[i16]) -> Vec<VadEvent> {
+        let mut events = Vec::new();
+        self.pending.extend_from_slice(samples);
+
+        let frame_len = self.frame_len;
+        let mut offset = 0;
+        while self.pending.len() - offset >= frame_len {
+            let frame = self.pending[offset..offset + frame_len].to_vec();
+            offset += frame_len;
+            self.process_frame(frame, &mut events);
+        }
+        self.pending.drain(..offset);
+
+        events
+    }
+
+    /// 录音结束，结束当前语句并重置状态
+    pub fn flush(&mut self) -> Vec<VadEvent> {
+        let mut events = Vec::new();
+        let pending = std::mem::take(&mut self.pending);
+        if let Some(utterance) = self.utterance.as_mut() {
+            utterance.extend_from_slice(&pending);
+            if !pending.is_empty() {
+                events.push(VadEvent::SpeechAudio(pending));
+            }
+            self.finish_utterance(true, &mut events);
+        }
+
+        self.processed = 0;
+        self.pre_roll.clear();
+        self.candidate.clear();
+        self.speech_frames = 0;
+        self.silence_frames = 0;
+        events
+    }
+
+    fn process_frame(&mut self, frame: Vec<i16>, events: &mut Vec<VadEvent>) {
+        let energy = frame_rms(&frame);
+        // 初始噪声基线不高于最低能量，避免录音一开始就说话时把语音当作噪声
+        let noise = *self
+            .noise_floor
+            .get_or_insert(energy.min(self.config.min_energy));
+        let is_speech = energy > self.config.min_energy.max(noise * self.config.noise_ratio);
+        self.processed += frame.len() as u64;
+
+        if let Some(utterance) = self.utterance.as_mut() {
+            utterance.extend_from_slice(&frame);
+            let utterance_len = utterance.len();
+            events.push(VadEvent::SpeechAudio(frame));
+
+            if is_speech {
+                self.silence_frames = 0;
+            } else {
+                self.silence_frames += 1;
+            }
+
+            let too_long = self.samples_to_ms(utterance_len) >= self.config.max_utterance_ms;
+            if self.silence_frames * self.config.frame_ms >= self.config.speech_end_ms {
+                self.finish_utterance(true, events);
+            } else if too_long {
+                self.finish_utterance(false, events);
+            }
+            return;
+        }
+
+        if is_speech {
+            self.candidate.extend_from_slice(&frame);
+            self.speech_frames += 1;
+            if self.speech_frames * self.config.frame_ms >= self.config.speech_start_ms {
+                self.start_utterance(events);
+            }
+        } else {
+            // 仅在静音时更新噪声基线
+            self.noise_floor = Some(noise * 0.95 + energy * 0.05);
+            self.speech_frames = 0;
+            let candidate = std::mem::take(&mut self.candidate);
+            self.push_pre_roll(&candidate);
+            self.push_pre_roll(&frame);
+        }
+    }
+
+    fn start_utterance(&mut self, events: &mut Vec<VadEvent>) {
+        let mut audio: Vec<i16> = self.pre_roll.drain(..).collect();
+        audio.append(&mut self.candidate);
+        self.utterance_start = self.processed - audio.len() as u64;
+        self.speech_frames = 0;
+        self.silence_frames = 0;
+
+        events.push(VadEvent::SpeechStart {
+            start_secs: self.utterance_start as f32 / self.sample_rate as f32,
+        });
+        events.push(VadEvent::SpeechAudio(audio.clone()));
+        self.utterance = Some(audio);
+    }
+
+    fn finish_utterance(&mut self, trim_trailing: bool, events: &mut Vec<VadEvent>) {
+        let Some(mut audio) = self.utterance.take() else {
+            return;
+        };
+
+        if trim_trailing {
+            // 只保留 padding 长度的尾部静音
+            let trailing = self.silence_frames as usize * self.frame_len;
+            let keep = self.ms_to_samples(self.config.padding_ms);
+            let cut = trailing.saturating_sub(keep).min(audio.len());
+            let tail = audio.split_off(audio.len() - cut);
+            self.push_pre_roll(&tail);
+        }
+        self.silence_frames = 0;
+
+        if self.samples_to_ms(audio.len()) < self.config.min_utterance_ms {
+            return;
+        }
+
+        let start_secs = self.utterance_start as f32 / self.sample_rate as f32;
+        let audio = AudioData::new(audio, self.sample_rate, 1);
+        events.push(VadEvent::SpeechEnd(Utterance {
+            start_secs,
+            end_secs: start_secs + audio.duration_secs,
+            audio,
+        }));
+    }
+
+    fn push_pre_roll(&mut self, samples: &[i16]) {
+        self.pre_roll.extend(samples.iter().copied());
+        let max = self.ms_to_samples(self.config.padding_ms);
+        while self.pre_roll.len() > max {
+            self.pre_roll.pop_front();
+        }
+    }
+
+    fn ms_to_samples(&self, ms: u32) -> usize {
+        (self.sample_rate as u64 * ms as u64 / 1000) as usize
+    }
+
+    fn samples_to_ms(&self, samples: usize) -> u32 {
+        (samples as u64 * 1000 / self.sample_rate.max(1) as u64) as u32
+    }
+}
+
+/// 计算一帧的 RMS（归一化到 0.0 - 1.0）
+pub fn frame_rms(frame: &[i16]) -> f32 {
+    if frame.is_empty() {
+        return 0.0;
+    }
+    let sum_sq: f64 = frame
+        .iter()
+        .map(|&s| {
+            let v = s as f64 / i16::MAX as f64;
+            v * v
+        })
+        .sum();
+    (sum_sq / frame.len() as f64).sqrt() as f32
+}
+
+/// 将整段录音切分为语句
+pub fn split_utterances(audio: &AudioData, config: &VadConfig) -> Vec<Utterance> {
+    let mut vad = VoiceActivityDetector::new(config.clone(), audio.sample_rate);
+    let mut events = vad.push(&audio.samples);
+    events.extend(vad.flush());
+
+    events
+        .into_iter()
+        .filter_map(|event| match event {
+            VadEvent::SpeechEnd(utterance) => Some(utterance),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 裁剪录音中的静音，只保留语句部分
+///
+/// 未检测到语音时返回 None。
+pub fn trim_silence(audio: &AudioData, config: &VadConfig) -> Option<AudioData> {
+    let utterances = split_utterances(audio, config);
+    if utterances.is_empty() {
+        return None;
+    }
+
+    let samples = utterances
+        .into_iter()
+        .flat_map(|utterance| utterance.audio.samples)
+        .collect();
+    Some(AudioData::new(samples, audio.sample_rate, 1))
+}
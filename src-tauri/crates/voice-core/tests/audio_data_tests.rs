@@ -0,0 +1,36 @@
+//! AudioData 格式转换测试
+
+use voice_core::types::AudioData;
+use voice_core::VoiceError;
+
+#[test]
+fn test_wav_round_trip() {
+    let bytes = std::fs::read(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/speech_two_utterances.wav"
+    ))
+    .unwrap();
+    let audio = AudioData::from_wav_bytes(&bytes).unwrap();
+
+    assert_eq!(audio.sample_rate, 16000);
+    assert_eq!(audio.channels, 1);
+    assert!((audio.duration_secs - 4.0).abs() < 0.01);
+
+    let decoded = AudioData::from_wav_bytes(&audio.to_wav_bytes()).unwrap();
+    assert_eq!(decoded.samples, audio.samples);
+}
+
+#[test]
+fn test_wav_stereo_is_downmixed() {
+    let stereo = AudioData::new(vec![1000, 3000, -2000, -4000], 8000, 2);
+    let audio = AudioData::from_wav_bytes(&stereo.to_wav_bytes()).unwrap();
+
+    assert_eq!(audio.channels, 1);
+    assert_eq!(audio.samples, vec![2000, -3000]);
+}
+
+#[test]
+fn test_invalid_wav() {
+    let result = AudioData::from_wav_bytes(b"not a wav file");
+    assert!(matches!(result, Err(VoiceError::AudioFormatError(_))));
+}
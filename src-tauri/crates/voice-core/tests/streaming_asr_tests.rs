@@ -0,0 +1,162 @@
+//! 流式识别测试
+//!
+//! 使用 WAV 音频和模拟的整段识别器测试分块窗口流式识别，不依赖真实 ASR 服务。
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use voice_core::asr_client::{
+    BatchTranscriber, ChunkedStreamingClient, ChunkedWindowConfig, StreamingAsrClient,
+};
+use voice_core::types::{AudioData, Segment, TranscribeResult};
+use voice_core::vad::{VadConfig, VadEvent, VoiceActivityDetector};
+use voice_core::Result;
+
+fn load_fixture(name: &str) -> AudioData {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    let mut reader = hound::WavReader::open(&path).expect("打开 WAV 失败");
+    let spec = reader.spec();
+    let samples: Vec<i16> = reader.samples::<i16>().map(|s| s.unwrap()).collect();
+    AudioData::new(samples, spec.sample_rate, spec.channels)
+}
+
+/// 模拟识别器：每 0.5 秒音频识别为一个字
+struct FakeTranscriber {
+    calls: AtomicUsize,
+}
+
+impl BatchTranscriber for FakeTranscriber {
+    fn transcribe_blocking(&self, audio: &AudioData) -> Result<TranscribeResult> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let words = (audio.duration_secs / 0.5) as usize;
+        Ok(TranscribeResult {
+            text: "字".repeat(words),
+            language: Some("zh".to_string()),
+            confidence: None,
+            segments: vec![Segment {
+                start: 0.0,
+                end: audio.duration_secs,
+                text: "字".repeat(words),
+            }],
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "模拟识别"
+    }
+}
+
+fn fake_client(
+    window_secs: f32,
+) -> (
+    Arc<FakeTranscriber>,
+    ChunkedStreamingClient<FakeTranscriber>,
+) {
+    let transcriber = Arc::new(FakeTranscriber {
+        calls: AtomicUsize::new(0),
+    });
+    let client =
+        ChunkedStreamingClient::new(transcriber.clone()).with_config(ChunkedWindowConfig {
+            window_secs,
+            step_secs: 0.5,
+        });
+    (transcriber, client)
+}
+
+#[tokio::test]
+async fn test_chunked_stream_partial_and_final() {
+    let audio = load_fixture("speech_two_utterances.wav");
+    let (transcriber, client) = fake_client(2.0);
+
+    let stream = client.start_stream(audio.sample_rate).await.unwrap();
+    for chunk in audio.samples.chunks(1600) {
+        stream.send_audio(chunk).unwrap();
+    }
+    let result = stream.finish().await.unwrap();
+
+    // 4s 音频：两个 2s 窗口各确认一次
+    assert_eq!(result.text, "字".repeat(8));
+    assert_eq!(result.segments.len(), 2);
+    assert!((result.segments[1].start - 2.0).abs() < 0.01);
+    assert!(transcriber.calls.load(Ordering::SeqCst) > 2);
+}
+
+#[tokio::test]
+async fn test_chunked_stream_transcripts_are_ordered() {
+    let audio = load_fixture("speech_two_utterances.wav");
+    let (_, client) = fake_client(2.0);
+
+    let mut stream = client.start_stream(audio.sample_rate).await.unwrap();
+    for chunk in audio.samples.chunks(1600) {
+        stream.send_audio(chunk).unwrap();
+    }
+
+    // 收集第一个窗口的结果：中间结果逐步变长，窗口满时得到最终结果
+    let mut transcripts = Vec::new();
+    while let Some(transcript) = stream.next_transcript().await {
+        let is_final = transcript.is_final;
+        transcripts.push(transcript);
+        if is_final {
+            break;
+        }
+    }
+    let result = stream.finish().await.unwrap();
+
+    let (last, partials) = transcripts.split_last().unwrap();
+    assert!(last.is_final);
+    assert_eq!(last.text, "字".repeat(4));
+    assert!(!partials.is_empty());
+    assert!(partials.iter().all(|t| !t.is_final));
+    assert!(partials
+        .windows(2)
+        .all(|w| w[0].text.chars().count() <= w[1].text.chars().count()));
+    assert!(result.text.starts_with(&last.text));
+}
+
+#[tokio::test]
+async fn test_vad_feeds_streaming_asr() {
+    let audio = load_fixture("speech_two_utterances.wav");
+    let mut vad = VoiceActivityDetector::new(VadConfig::default(), audio.sample_rate);
+    let (_, client) = fake_client(10.0);
+
+    // 每段语句启动一次流式识别，只发送语音部分
+    let mut results = Vec::new();
+    let mut stream = None;
+    let mut events = Vec::new();
+    for chunk in audio.samples.chunks(480) {
+        events.extend(vad.push(chunk));
+    }
+    events.extend(vad.flush());
+
+    for event in events {
+        match event {
+            VadEvent::SpeechStart { .. } => {
+                stream = Some(client.start_stream(audio.sample_rate).await.unwrap());
+            }
+            VadEvent::SpeechAudio(samples) => {
+                stream.as_ref().unwrap().send_audio(&samples).unwrap();
+            }
+            VadEvent::SpeechEnd(_) => {
+                results.push(stream.take().unwrap().finish().await.unwrap());
+            }
+        }
+    }
+
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|r| !r.text.is_empty()));
+}
+
+#[tokio::test]
+async fn test_chunked_stream_skips_short_tail() {
+    let (transcriber, client) = fake_client(10.0);
+
+    let stream = client.start_stream(16000).await.unwrap();
+    stream.send_audio(&vec![0i16; 1600]).unwrap();
+    let result = stream.finish().await.unwrap();
+
+    assert!(result.text.is_empty());
+    assert_eq!(transcriber.calls.load(Ordering::SeqCst), 0);
+}
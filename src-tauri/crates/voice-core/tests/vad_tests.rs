@@ -0,0 +1,108 @@
+//! VAD 测试
+//!
+//! 使用 `tests/fixtures` 下的 WAV 音频：
+//! - `speech_two_utterances.wav`: 16kHz 单声道，0.5s-1.5s、2.5s-3.3s 为两段浊音，其余为底噪（共 4s）
+//! - `silence.wav`: 16kHz 单声道，1.5s 底噪
+
+use std::path::PathBuf;
+
+use voice_core::types::AudioData;
+use voice_core::vad::{split_utterances, trim_silence, VadConfig, VadEvent, VoiceActivityDetector};
+
+fn load_fixture(name: &str) -> AudioData {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    let mut reader = hound::WavReader::open(&path).expect("打开 WAV 失败");
+    let spec = reader.spec();
+    let samples: Vec<i16> = reader.samples::<i16>().map(|s| s.unwrap()).collect();
+    AudioData::new(samples, spec.sample_rate, spec.channels)
+}
+
+#[test]
+fn test_split_two_utterances() {
+    let audio = load_fixture("speech_two_utterances.wav");
+    let utterances = split_utterances(&audio, &VadConfig::default());
+
+    assert_eq!(utterances.len(), 2);
+    // 开始时间包含 200ms padding
+    assert!((utterances[0].start_secs - 0.3).abs() < 0.1);
+    assert!((utterances[0].end_secs - 1.7).abs() < 0.1);
+    assert!((utterances[1].start_secs - 2.3).abs() < 0.1);
+    assert!((utterances[1].end_secs - 3.5).abs() < 0.1);
+}
+
+#[test]
+fn test_silence_has_no_utterance() {
+    let audio = load_fixture("silence.wav");
+    assert!(split_utterances(&audio, &VadConfig::default()).is_empty());
+    assert!(trim_silence(&audio, &VadConfig::default()).is_none());
+}
+
+#[test]
+fn test_trim_silence() {
+    let audio = load_fixture("speech_two_utterances.wav");
+    let trimmed = trim_silence(&audio, &VadConfig::default()).unwrap();
+
+    // 两段语音共 1.8s，各加前后 padding
+    assert!(trimmed.duration_secs < audio.duration_secs - 1.0);
+    assert!((trimmed.duration_secs - 2.6).abs() < 0.2);
+}
+
+#[test]
+fn test_streaming_events_match_batch() {
+    let audio = load_fixture("speech_two_utterances.wav");
+    let mut vad = VoiceActivityDetector::new(VadConfig::default(), audio.sample_rate);
+
+    // 模拟录音回调，每次送入 10ms
+    let mut events = Vec::new();
+    for chunk in audio.samples.chunks(160) {
+        events.extend(vad.push(chunk));
+    }
+    events.extend(vad.flush());
+
+    let starts = events
+        .iter()
+        .filter(|e| matches!(e, VadEvent::SpeechStart { .. }))
+        .count();
+    let streamed: usize = events
+        .iter()
+        .map(|e| match e {
+            VadEvent::SpeechAudio(samples) => samples.len(),
+            _ => 0,
+        })
+        .sum();
+    let ends: Vec<_> = events
+        .iter()
+        .filter_map(|e| match e {
+            VadEvent::SpeechEnd(utterance) => Some(utterance),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(starts, 2);
+    assert_eq!(ends.len(), 2);
+    // 流式推送的音频覆盖了每段语句（尾部静音在结束时被裁剪）
+    let total: usize = ends.iter().map(|u| u.audio.samples.len()).sum();
+    assert!(streamed >= total);
+
+    let batch = split_utterances(&audio, &VadConfig::default());
+    for (streamed, batch) in ends.iter().zip(&batch) {
+        assert_eq!(streamed.audio.samples, batch.audio.samples);
+    }
+}
+
+#[test]
+fn test_max_utterance_splits_long_speech() {
+    let audio = load_fixture("speech_two_utterances.wav");
+    let config = VadConfig {
+        max_utterance_ms: 500,
+        ..Default::default()
+    };
+    let utterances = split_utterances(&audio, &config);
+
+    assert!(utterances.len() > 2);
+    assert!(utterances
+        .iter()
+        .all(|u| u.audio.duration_secs <= 0.5 + 0.03));
+}
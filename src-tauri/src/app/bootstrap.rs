@@ -24,7 +24,7 @@ use crate::commands::webview_cmd::{
     ChromeProfileManagerWrapper, WebviewManagerState, WebviewManagerWrapper,
 };
 use crate::config::{GlobalConfigManager, GlobalConfigManagerState};
-use crate::database::{self, DbConnection};
+use crate::database::{self, DbConnection, MigrationRegistry};
 use crate::logger;
 use crate::mcp::McpManagerState;
 use crate::plugin;
@@ -90,6 +90,15 @@ type TelemetryInit = (
     Arc<telemetry::RequestLogger>,
 );
 
+/// 应用使用的全部数据库迁移（core + 调度器 + 终端 + 内容创作工作流）
+fn migration_registry() -> Result<MigrationRegistry, String> {
+    let mut registry = MigrationRegistry::core();
+    registry.register_all(proxycast_scheduler::migrations())?;
+    registry.register_all(proxycast_terminal::persistence::session_store::migrations())?;
+    registry.register_all(proxycast_services::content_creator::progress_store::migrations())?;
+    Ok(registry)
+}
+
 /// 初始化所有应用状态
 pub fn init_states(config: &Config) -> Result<AppStates, String> {
     // 核心状态
@@ -99,7 +108,9 @@ pub fn init_states(config: &Config) -> Result<AppStates, String> {
     )));
 
     // 数据库
-    let db = database::init_database().map_err(|e| format!("数据库初始化失败: {e}"))?;
+    let db_path = database::get_db_path()?;
+    let db = database::init_database_with(&db_path, &migration_registry()?)
+        .map_err(|e| format!("数据库初始化失败: {e}"))?;
 
     // Windows 特定：验证数据库可写性
     #[cfg(target_os = "windows")]
@@ -120,11 +131,6 @@ pub fn init_states(config: &Config) -> Result<AppStates, String> {
         }
     }
 
-    // 初始化批量任务表
-    if let Err(e) = proxycast_scheduler::BatchTaskDao::init_tables(&db) {
-        tracing::warn!("[Bootstrap] 批量任务表初始化失败: {}", e);
-    }
-
     // 服务状态
     let skill_service = SkillService::new().map_err(|e| format!("SkillService 初始化失败: {e}"))?;
     let skill_service_state = SkillServiceState(Arc::new(skill_service));
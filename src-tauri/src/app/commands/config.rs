@@ -4,6 +4,7 @@
 
 use crate::app::types::{AppState, LogState};
 use crate::app::utils::is_valid_bind_host;
+use crate::commands::provider_pool_cmd::TokenRefreshSchedulerState;
 use crate::config::{
     self,
     observer::{ConfigChangeEvent, RoutingChangeEvent},
@@ -21,6 +22,7 @@ pub async fn get_config(state: tauri::State<'_, AppState>) -> Result<config::Con
 #[tauri::command]
 pub async fn save_config(
     state: tauri::State<'_, AppState>,
+    token_refresh: tauri::State<'_, TokenRefreshSchedulerState>,
     config: config::Config,
 ) -> Result<(), String> {
     let host = config.server.host.to_lowercase();
@@ -48,6 +50,9 @@ pub async fn save_config(
 
     let mut s = state.write().await;
     s.config = config.clone();
+    token_refresh
+        .0
+        .update_settings(config.token_refresh.clone());
 
     match config::save_config(&config) {
         Ok(()) => {
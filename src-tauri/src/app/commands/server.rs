@@ -98,27 +98,26 @@ pub async fn get_server_status(
         latencies.get(p95_index).copied()
     };
 
-    // 使用凭证健康状态近似熔断状态：统计当前不健康的上游类型数量
-    status.open_circuit_count = match pool_service.0.get_all_credential_health(db.inner()) {
-        Ok(health_list) => {
-            let unhealthy_provider_count = health_list
-                .into_iter()
-                .filter(|item| !item.is_healthy)
-                .map(|item| item.provider_type)
-                .collect::<HashSet<_>>()
-                .len();
-            u32::try_from(unhealthy_provider_count).unwrap_or(u32::MAX)
-        }
-        Err(err) => {
-            tracing::warn!("[SERVER_STATUS] 获取凭证健康状态失败: {}", err);
-            0
-        }
-    };
-    // 使用 Retry 状态日志作为活跃请求近似值
-    status.active_requests = recent_logs
-        .iter()
-        .filter(|log| matches!(log.status, RequestStatus::Retrying))
-        .count() as u64;
+    // 服务器运行时 status() 已返回熔断器的真实状态；
+    // 未运行时使用凭证健康状态近似：统计当前不健康的上游类型数量
+    if s.circuit_breaker.is_none() {
+        status.open_circuit_count = match pool_service.0.get_all_credential_health(db.inner()) {
+            Ok(health_list) => {
+                let unhealthy_provider_count = health_list
+                    .into_iter()
+                    .filter(|item| !item.is_healthy)
+                    .map(|item| item.provider_type)
+                    .collect::<HashSet<_>>()
+                    .len();
+                u32::try_from(unhealthy_provider_count).unwrap_or(u32::MAX)
+            }
+            Err(err) => {
+                tracing::warn!("[SERVER_STATUS] 获取凭证健康状态失败: {}", err);
+                0
+            }
+        };
+    }
+    // active_requests / queued_requests 由 status() 从并发限制器读取真实值
 
     Ok(status)
 }
@@ -150,9 +150,11 @@ pub fn run() {
         .manage(context_memory_service)
         .manage(tool_hooks_service)
         .manage(recording_service)
+        .manage(crate::voice::recording_service::StreamingTranscriptionState::default())
         .manage(mcp_manager_state)
         .manage(heartbeat_service_state)
         .manage(commands::telegram_remote_cmd::TelegramRemoteState::default())
+        .manage(commands::content_workflow_cmd::ContentWorkflowState::default())
         .on_window_event(move |window, event| {
             // 处理窗口关闭事件
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
@@ -313,6 +315,29 @@ pub fn run() {
                 });
             }
 
+            // 初始化内容创作工作流服务，并恢复上次未完成的工作流
+            {
+                let app_handle = app.handle().clone();
+                let db = db_clone.clone();
+                tauri::async_runtime::spawn(async move {
+                    match crate::commands::content_workflow_cmd::init_content_workflow_service(db)
+                        .await
+                    {
+                        Ok(service) => {
+                            tracing::info!("[启动] 内容创作工作流服务初始化成功");
+                            if let Some(state) = app_handle
+                                .try_state::<crate::commands::content_workflow_cmd::ContentWorkflowState>()
+                            {
+                                *state.0.write().await = Some(Arc::new(service));
+                            }
+                        }
+                        Err(e) => {
+                            tracing::error!("[启动] 内容创作工作流服务初始化失败: {}", e);
+                        }
+                    }
+                });
+            }
+
             // 初始化 Model Registry 服务
             {
                 let app_handle = app.handle().clone();
@@ -890,6 +915,7 @@ pub fn run() {
             commands::provider_pool_cmd::add_claude_oauth_credential,
             commands::provider_pool_cmd::refresh_pool_credential_token,
             commands::provider_pool_cmd::get_pool_credential_oauth_status,
+            commands::provider_pool_cmd::get_token_refresh_status,
             commands::provider_pool_cmd::debug_kiro_credentials,
             commands::provider_pool_cmd::test_user_credentials,
             commands::provider_pool_cmd::migrate_private_config_to_pool,
@@ -958,10 +984,14 @@ pub fn run() {
             commands::resilience_cmd::update_failover_config,
             commands::resilience_cmd::get_switch_log,
             commands::resilience_cmd::clear_switch_log,
+            commands::resilience_cmd::get_circuit_breaker_status,
+            commands::resilience_cmd::reset_circuit_breaker,
+            commands::resilience_cmd::get_concurrency_metrics,
             // Telemetry commands
             commands::telemetry_cmd::get_request_logs,
             commands::telemetry_cmd::get_request_log_detail,
             commands::telemetry_cmd::clear_request_logs,
+            commands::telemetry_cmd::get_circuit_transitions,
             commands::telemetry_cmd::get_stats_summary,
             commands::telemetry_cmd::get_stats_by_provider,
             commands::telemetry_cmd::get_stats_by_model,
@@ -1085,6 +1115,7 @@ pub fn run() {
             commands::aster_agent_cmd::aster_session_delete,
             commands::aster_agent_cmd::aster_agent_confirm,
             commands::aster_agent_cmd::aster_agent_submit_elicitation_response,
+            commands::aster_agent_cmd::aster_lsp_rename_preview,
             // Models config commands
             commands::models_cmd::get_models_config,
             commands::models_cmd::save_models_config,
@@ -1104,6 +1135,8 @@ pub fn run() {
             commands::orchestrator_cmd::get_pool_stats,
             commands::orchestrator_cmd::get_tier_models,
             commands::orchestrator_cmd::get_all_models,
+            commands::orchestrator_cmd::get_model_performance_stats,
+            commands::orchestrator_cmd::reset_model_performance_stats,
             commands::orchestrator_cmd::update_orchestrator_credentials,
             commands::orchestrator_cmd::add_orchestrator_credential,
             commands::orchestrator_cmd::remove_orchestrator_credential,
@@ -1156,6 +1189,19 @@ pub fn run() {
             commands::terminal_cmd::terminal_close,
             commands::terminal_cmd::terminal_list_sessions,
             commands::terminal_cmd::terminal_get_session,
+            commands::terminal_cmd::terminal_search_commands,
+            commands::terminal_cmd::terminal_recent_commands,
+            commands::terminal_cmd::terminal_last_failed_command,
+            commands::terminal_cmd::terminal_start_recording,
+            commands::terminal_cmd::terminal_stop_recording,
+            commands::terminal_cmd::terminal_add_recording_marker,
+            commands::terminal_cmd::terminal_list_recordings,
+            commands::terminal_cmd::terminal_replay_recording,
+            commands::terminal_cmd::terminal_import_recording,
+            commands::terminal_cmd::terminal_export_recording,
+            commands::terminal_cmd::terminal_ssh_add_forward,
+            commands::terminal_cmd::terminal_ssh_remove_forward,
+            commands::terminal_cmd::terminal_ssh_list_forwards,
             // Connection commands
             commands::connection_cmd::connection_list,
             commands::connection_cmd::connection_add,
@@ -1174,11 +1220,15 @@ pub fn run() {
             // File browser commands
             crate::services::file_browser_service::list_dir,
             crate::services::file_browser_service::read_file_preview_cmd,
+            crate::services::file_browser_service::stat_path,
             crate::services::file_browser_service::get_home_dir,
+            crate::services::file_browser_service::get_remote_home_dir,
             crate::services::file_browser_service::create_file,
+            crate::services::file_browser_service::write_file_content,
             crate::services::file_browser_service::create_directory,
             crate::services::file_browser_service::delete_file,
             crate::services::file_browser_service::rename_file,
+            crate::services::file_browser_service::copy_path,
             crate::services::file_browser_service::get_file_name,
             crate::services::file_browser_service::reveal_in_finder,
             crate::services::file_browser_service::open_with_default_app,
@@ -1339,6 +1389,16 @@ pub fn run() {
             commands::content_cmd::content_delete,
             commands::content_cmd::content_reorder,
             commands::content_cmd::content_stats,
+            commands::content_cmd::content_export_manuscript,
+            // Content Workflow commands
+            commands::content_workflow_cmd::list_content_workflows,
+            commands::content_workflow_cmd::get_content_workflow,
+            commands::content_workflow_cmd::create_content_workflow,
+            commands::content_workflow_cmd::complete_content_workflow_step,
+            commands::content_workflow_cmd::skip_content_workflow_step,
+            commands::content_workflow_cmd::rerun_content_workflow_step,
+            commands::content_workflow_cmd::export_content_workflow,
+            commands::content_workflow_cmd::import_content_workflow,
             // Novel Orchestrator commands
             commands::novel_cmd::novel_create_project,
             commands::novel_cmd::novel_update_settings,
@@ -1351,6 +1411,10 @@ pub fn run() {
             commands::novel_cmd::novel_check_consistency,
             commands::novel_cmd::novel_get_project_snapshot,
             commands::novel_cmd::novel_list_runs,
+            commands::novel_cmd::novel_get_canon,
+            commands::novel_cmd::novel_rebuild_canon,
+            commands::novel_cmd::novel_export_manuscript,
+            commands::novel_cmd::novel_import_markdown_bundle,
             // Memory commands (Character, WorldBuilding, StyleGuide, Outline)
             commands::memory_cmd::character_create,
             commands::memory_cmd::character_get,
@@ -1443,6 +1507,8 @@ pub fn run() {
             crate::voice::commands::start_recording,
             crate::voice::commands::stop_recording,
             crate::voice::commands::cancel_recording,
+            crate::voice::commands::start_streaming_transcription,
+            crate::voice::commands::stop_streaming_transcription,
             crate::voice::commands::get_recording_status,
             crate::voice::commands::list_audio_devices,
             // Heartbeat Engine commands
@@ -1468,6 +1534,12 @@ pub fn run() {
             commands::telegram_remote_cmd::stop_telegram_remote,
             commands::telegram_remote_cmd::get_telegram_remote_status,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|_app_handle, event| {
+            // 退出前关闭 Agent 拉起的语言服务器，避免遗留子进程
+            if let tauri::RunEvent::Exit = event {
+                tauri::async_runtime::block_on(proxycast_agent::shutdown_lsp_servers());
+            }
+        });
 }
@@ -3,19 +3,20 @@
 //! 包含应用启动时的初始化逻辑。
 
 use std::sync::Arc;
-use tauri::{App, Manager};
+use tauri::{App, Emitter, Manager};
 
 // use crate::agent::tools::{set_term_scrollback_tool_app_handle, set_terminal_tool_app_handle};
 use crate::agent::AsterAgentState;
+use crate::commands::provider_pool_cmd::TokenRefreshSchedulerState;
 use crate::database;
 use crate::telemetry;
 use crate::tray::{TrayIconStatus, TrayManager, TrayStateSnapshot};
-use proxycast_scheduler::AgentScheduler;
+use proxycast_scheduler::{SchedulerService, SchedulerServiceConfig};
 use proxycast_services::aster_session_store::ProxyCastSessionStore;
 use proxycast_services::provider_pool_service::ProviderPoolService;
 use proxycast_services::token_cache_service::TokenCacheService;
+use proxycast_services::token_refresh_scheduler::TokenRefreshScheduler;
 
-use super::scheduler_service::{SchedulerService, SchedulerServiceConfig};
 use super::types::{AppState, LogState, TrayManagerState};
 
 /// Tauri setup hook
@@ -80,13 +81,6 @@ pub fn setup_app(
             .expect("Failed to initialize default skill repos");
     }
 
-    // 初始化调度器数据库表
-    if let Err(e) = AgentScheduler::init_tables(&db) {
-        tracing::error!("[启动] 调度器表初始化失败: {}", e);
-    } else {
-        tracing::info!("[启动] 调度器表初始化成功");
-    }
-
     // 启动调度器服务
     let scheduler_config = SchedulerServiceConfig::default();
     let scheduler_service = SchedulerService::new(db.clone(), scheduler_config);
@@ -96,6 +90,36 @@ pub fn setup_app(
     // 将调度器服务注册为 Tauri 状态，以便后续访问
     app.manage(Arc::new(scheduler_service));
 
+    // 启动 OAuth Token 后台预刷新（与请求路径共用 Token 缓存及其凭证锁）
+    let token_refresh_settings =
+        tauri::async_runtime::block_on(async { state.read().await.config.token_refresh.clone() });
+    let token_refresh = Arc::new(TokenRefreshScheduler::new(
+        token_cache.clone(),
+        token_refresh_settings,
+    ));
+    {
+        let token_refresh = token_refresh.clone();
+        let db = db.clone();
+        let app_handle = app.handle().clone();
+        tauri::async_runtime::spawn(async move {
+            let mut events = token_refresh.subscribe();
+            token_refresh.start(db);
+            // 将预刷新事件转发给前端
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        if let Err(e) = app_handle.emit("token-refresh", &event) {
+                            tracing::warn!("[启动] 发送 Token 预刷新事件失败: {}", e);
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+    app.manage(TokenRefreshSchedulerState(token_refresh));
+
     // 自动启动服务器
     let app_handle = app.handle().clone();
     tauri::async_runtime::spawn(async move {
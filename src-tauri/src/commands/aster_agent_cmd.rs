@@ -1013,6 +1013,7 @@ fn build_workspace_shell_allow_pattern(
 /// 为指定工作区生成本地 sandbox 权限模板
 async fn apply_workspace_sandbox_permissions(
     state: &AsterAgentState,
+    db: &DbConnection,
     config_manager: &GlobalConfigManagerState,
     heartbeat_state: &HeartbeatServiceState,
     app_handle: &AppHandle,
@@ -1456,6 +1457,7 @@ async fn apply_workspace_sandbox_permissions(
         "ask",
         "three_stage_workflow",
         "heartbeat",
+        "terminal_history",
     ] {
         permissions.push(ToolPermission {
             tool: tool_name.to_string(),
@@ -1536,6 +1538,12 @@ async fn apply_workspace_sandbox_permissions(
     let heartbeat_tool = proxycast_agent::tools::HeartbeatTool::new(Arc::new(heartbeat_adapter));
     registry.register(Box::new(heartbeat_tool));
 
+    // 注册终端命令历史工具
+    let terminal_history_tool = proxycast_agent::tools::TerminalHistoryTool::new(Arc::new(
+        proxycast_terminal::CommandHistoryStore::new(db.clone()),
+    ));
+    registry.register(Box::new(terminal_history_tool));
+
     // 注册浏览器 MCP 工具
     register_browser_mcp_tools_to_registry(&mut registry);
 
@@ -1772,6 +1780,7 @@ pub async fn aster_agent_chat_stream(
 
     let sandbox_outcome = apply_workspace_sandbox_permissions(
         &state,
+        db.inner(),
         config_manager.inner(),
         heartbeat_state.inner(),
         &app,
@@ -2141,6 +2150,25 @@ pub async fn aster_agent_submit_elicitation_response(
     Ok(())
 }
 
+/// 预览重命名符号会产生的编辑（行列从 0 开始，列为 UTF-8 字节偏移），不修改任何文件
+#[tauri::command]
+pub async fn aster_lsp_rename_preview(
+    path: String,
+    line: u32,
+    character: u32,
+    new_name: String,
+) -> Result<proxycast_agent::RenamePreview, String> {
+    if new_name.trim().is_empty() {
+        return Err("new_name 不能为空".to_string());
+    }
+    proxycast_agent::rename_preview(
+        std::path::Path::new(&path),
+        aster::tools::Position::new(line, character),
+        new_name.trim(),
+    )
+    .await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
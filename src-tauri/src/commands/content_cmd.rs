@@ -6,7 +6,13 @@ use crate::content::{
     Content, ContentCreateRequest, ContentListQuery, ContentManager, ContentStatus,
     ContentUpdateRequest,
 };
-use crate::database::DbConnection;
+use crate::database::{lock_db, DbConnection};
+use crate::workspace::WorkspaceManager;
+use proxycast_services::manuscript_export::{
+    self, Manuscript, ManuscriptExportOptions, ManuscriptFormat, ManuscriptMetadata,
+};
+use proxycast_services::material_service::MaterialService;
+use proxycast_services::persona_service::PersonaService;
 use serde::{Deserialize, Serialize};
 use tauri::State;
 
@@ -237,3 +243,76 @@ pub async fn content_stats(
     let manager = ContentManager::new(db.inner().clone());
     manager.get_project_stats(&project_id)
 }
+
+/// 稿件导出请求
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExportManuscriptRequest {
+    pub project_id: String,
+    pub format: ManuscriptFormat,
+    pub output_path: String,
+    #[serde(default)]
+    pub options: Option<ManuscriptExportOptions>,
+    /// 仅导出指定状态的内容（如 `completed`）
+    #[serde(default)]
+    pub status: Option<String>,
+}
+
+/// 稿件导出结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportManuscriptResult {
+    pub output_path: String,
+    pub chapter_count: usize,
+    pub size_bytes: usize,
+}
+
+/// 将项目内容按顺序导出为 EPUB / DOCX / Markdown 包
+///
+/// 书名取自项目名称，作者与简介取自默认人设，封面取自项目图片素材。
+#[tauri::command]
+pub async fn content_export_manuscript(
+    db: State<'_, DbConnection>,
+    request: ExportManuscriptRequest,
+) -> Result<ExportManuscriptResult, String> {
+    let workspace = WorkspaceManager::new(db.inner().clone())
+        .get(&request.project_id)?
+        .ok_or_else(|| format!("项目不存在: {}", request.project_id))?;
+
+    let query = request.status.as_deref().map(|s| ContentListQuery {
+        status: Some(ContentStatus::from_str(s)),
+        ..Default::default()
+    });
+    let contents =
+        ContentManager::new(db.inner().clone()).list_by_project(&request.project_id, query)?;
+
+    let (persona, materials) = {
+        let conn = lock_db(db.inner())?;
+        let persona = PersonaService::get_default_persona(&conn, &request.project_id)
+            .map_err(|e| format!("读取默认人设失败: {e}"))?;
+        let materials = MaterialService::list_materials(&conn, &request.project_id, None)
+            .map_err(|e| format!("读取项目素材失败: {e}"))?;
+        (persona, materials)
+    };
+
+    let mut metadata = ManuscriptMetadata::new(workspace.name);
+    metadata.modified_at = workspace.updated_at.timestamp_millis();
+    if let Some(persona) = &persona {
+        metadata.apply_persona(persona);
+    }
+    let mut manuscript = Manuscript::from_contents(metadata, &contents);
+    manuscript.cover = manuscript_export::cover_from_materials(&materials);
+
+    let options = request.options.unwrap_or_default();
+    let size_bytes = manuscript_export::export_to_file(
+        &manuscript,
+        request.format,
+        &options,
+        std::path::Path::new(&request.output_path),
+    )
+    .map_err(|e| format!("导出稿件失败: {e}"))?;
+
+    Ok(ExportManuscriptResult {
+        output_path: request.output_path,
+        chapter_count: manuscript.chapters.len(),
+        size_bytes,
+    })
+}
@@ -0,0 +1,168 @@
+//! 内容创作工作流命令模块
+//!
+//! 持有带持久化的 `WorkflowService`，启动时从主数据库恢复未完成的工作流。
+//!
+//! ## Tauri 命令
+//!
+//! - `list_content_workflows` - 列出已恢复/活跃的工作流
+//! - `get_content_workflow` - 获取单个工作流（含已完成的历史工作流）
+//! - `create_content_workflow` - 按主题和模式创建工作流
+//! - `complete_content_workflow_step` - 完成当前步骤并进入下一步
+//! - `skip_content_workflow_step` - 跳过当前步骤
+//! - `rerun_content_workflow_step` - 使用新参数单独重跑指定步骤
+//! - `export_content_workflow` - 导出工作流运行记录到 JSON 文件
+//! - `import_content_workflow` - 从 JSON 文件导入工作流
+
+use crate::database::DbConnection;
+use proxycast_services::content_creator::{
+    CreationMode, ProgressStore, StepExecutor, StepResult, ThemeType, WorkflowService,
+    WorkflowState,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::RwLock;
+
+/// 工作流服务状态（延迟初始化，在 setup hook 中完成）
+pub struct ContentWorkflowState(pub Arc<RwLock<Option<Arc<WorkflowService>>>>);
+
+impl ContentWorkflowState {
+    pub fn new() -> Self {
+        Self(Arc::new(RwLock::new(None)))
+    }
+
+    async fn service(&self) -> Result<Arc<WorkflowService>, String> {
+        self.0
+            .read()
+            .await
+            .clone()
+            .ok_or_else(|| "内容创作工作流服务尚未初始化".to_string())
+    }
+}
+
+impl Default for ContentWorkflowState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 初始化工作流服务
+///
+/// 进度表位于主数据库（由迁移注册表创建），创建服务时会调用 `list_incomplete` 恢复未完成的工作流。
+pub async fn init_content_workflow_service(db: DbConnection) -> Result<WorkflowService, String> {
+    let store = ProgressStore::new(db);
+    WorkflowService::with_store(Arc::new(store))
+        .await
+        .map_err(|e| format!("恢复未完成的工作流失败: {e}"))
+}
+
+/// 列出已恢复/活跃的工作流（按更新时间倒序）
+#[tauri::command]
+pub async fn list_content_workflows(
+    state: State<'_, ContentWorkflowState>,
+) -> Result<Vec<WorkflowState>, String> {
+    Ok(state.service().await?.list_workflows().await)
+}
+
+/// 获取单个工作流
+#[tauri::command]
+pub async fn get_content_workflow(
+    state: State<'_, ContentWorkflowState>,
+    workflow_id: String,
+) -> Result<Option<WorkflowState>, String> {
+    Ok(state.service().await?.get_workflow(&workflow_id).await)
+}
+
+/// 创建工作流
+#[tauri::command]
+pub async fn create_content_workflow(
+    state: State<'_, ContentWorkflowState>,
+    theme: ThemeType,
+    mode: CreationMode,
+) -> Result<WorkflowState, String> {
+    state
+        .service()
+        .await?
+        .create_workflow(theme, mode)
+        .await
+        .map_err(|e| format!("创建工作流失败: {e}"))
+}
+
+/// 完成当前步骤（步骤配置了自动前进时进入下一步）
+#[tauri::command]
+pub async fn complete_content_workflow_step(
+    state: State<'_, ContentWorkflowState>,
+    workflow_id: String,
+    result: StepResult,
+) -> Result<WorkflowState, String> {
+    state
+        .service()
+        .await?
+        .complete_step(&workflow_id, result)
+        .await
+        .map_err(|e| format!("完成步骤失败: {e}"))
+}
+
+/// 跳过当前步骤
+#[tauri::command]
+pub async fn skip_content_workflow_step(
+    state: State<'_, ContentWorkflowState>,
+    workflow_id: String,
+) -> Result<WorkflowState, String> {
+    state
+        .service()
+        .await?
+        .skip_step(&workflow_id)
+        .await
+        .map_err(|e| format!("跳过步骤失败: {e}"))
+}
+
+/// 使用新参数单独重跑指定步骤，前置步骤的结果原样复用，后续步骤不受影响
+#[tauri::command]
+pub async fn rerun_content_workflow_step(
+    state: State<'_, ContentWorkflowState>,
+    workflow_id: String,
+    step_index: usize,
+    parameters: Option<HashMap<String, serde_json::Value>>,
+) -> Result<WorkflowState, String> {
+    state
+        .service()
+        .await?
+        .rerun_step(
+            &StepExecutor::new(),
+            &workflow_id,
+            step_index,
+            parameters.unwrap_or_default(),
+        )
+        .await
+        .map_err(|e| format!("重跑步骤失败: {e}"))
+}
+
+/// 导出工作流运行记录到 JSON 文件
+#[tauri::command]
+pub async fn export_content_workflow(
+    state: State<'_, ContentWorkflowState>,
+    workflow_id: String,
+    path: String,
+) -> Result<(), String> {
+    state
+        .service()
+        .await?
+        .export_to_file(&workflow_id, &path)
+        .await
+        .map_err(|e| format!("导出工作流失败: {e}"))
+}
+
+/// 从 JSON 文件导入工作流（分配新的工作流 ID）
+#[tauri::command]
+pub async fn import_content_workflow(
+    state: State<'_, ContentWorkflowState>,
+    path: String,
+) -> Result<WorkflowState, String> {
+    state
+        .service()
+        .await?
+        .import_from_file(&path)
+        .await
+        .map_err(|e| format!("导入工作流失败: {e}"))
+}
@@ -3,11 +3,14 @@
 //! 兼容层：对外保持 tauri command 名称不变，内部转发到主题模块实现。
 
 use crate::database::DbConnection;
+use crate::services::novel_canon::NovelCanonRecord;
 use crate::services::novel_service::{
-    NovelCheckConsistencyRequest, NovelCreateProjectRequest, NovelGenerateChapterRequest,
-    NovelGenerateRequest, NovelGenerateResult, NovelGenerationRun, NovelListRunsRequest,
-    NovelPolishChapterRequest, NovelProject, NovelProjectSnapshot, NovelRewriteChapterRequest,
-    NovelSettingsRecord, NovelUpdateSettingsRequest,
+    NovelCheckConsistencyRequest, NovelCreateProjectRequest, NovelExportManuscriptRequest,
+    NovelExportManuscriptResult, NovelGenerateChapterRequest, NovelGenerateRequest,
+    NovelGenerateResult, NovelGenerationRun, NovelImportMarkdownBundleRequest,
+    NovelListRunsRequest, NovelPolishChapterRequest, NovelProject, NovelProjectSnapshot,
+    NovelRebuildCanonRequest, NovelRewriteChapterRequest, NovelSettingsRecord,
+    NovelUpdateSettingsRequest,
 };
 use tauri::State;
 
@@ -109,3 +112,39 @@ pub async fn novel_list_runs(
 ) -> Result<Vec<NovelGenerationRun>, String> {
     crate::theme::novel::command::novel_list_runs(db, request).await
 }
+
+/// 获取小说设定知识库（Canon）
+#[tauri::command]
+pub async fn novel_get_canon(
+    db: State<'_, DbConnection>,
+    project_id: String,
+) -> Result<Option<NovelCanonRecord>, String> {
+    crate::theme::novel::command::novel_get_canon(db, project_id).await
+}
+
+/// 从设定与章节抽取 Canon（默认增量）
+#[tauri::command]
+pub async fn novel_rebuild_canon(
+    db: State<'_, DbConnection>,
+    request: NovelRebuildCanonRequest,
+) -> Result<NovelCanonRecord, String> {
+    crate::theme::novel::command::novel_rebuild_canon(db, request).await
+}
+
+/// 导出小说为 EPUB / DOCX / Markdown 包
+#[tauri::command]
+pub async fn novel_export_manuscript(
+    db: State<'_, DbConnection>,
+    request: NovelExportManuscriptRequest,
+) -> Result<NovelExportManuscriptResult, String> {
+    crate::theme::novel::command::novel_export_manuscript(db, request).await
+}
+
+/// 从 Markdown 包导入为新的小说项目
+#[tauri::command]
+pub async fn novel_import_markdown_bundle(
+    db: State<'_, DbConnection>,
+    request: NovelImportMarkdownBundleRequest,
+) -> Result<NovelProject, String> {
+    crate::theme::novel::command::novel_import_markdown_bundle(db, request).await
+}
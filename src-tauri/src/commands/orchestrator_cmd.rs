@@ -6,8 +6,8 @@ use crate::database::dao::provider_pool::ProviderPoolDao;
 use crate::database::DbConnection;
 use proxycast_core::orchestrator::{
     get_global_orchestrator, init_global_orchestrator, AvailableModel, CredentialInfo,
-    OrchestratorConfig, PoolStats, ProviderType, SelectionContext, SelectionResult, ServiceTier,
-    StrategyInfo, TaskHint,
+    ModelPerformanceEntry, OrchestratorConfig, PoolStats, ProviderType, SelectionContext,
+    SelectionResult, ServiceTier, StrategyInfo, TaskHint,
 };
 use serde::{Deserialize, Serialize};
 use tauri::State;
@@ -237,6 +237,23 @@ pub async fn get_all_models() -> Result<Vec<AvailableModel>, String> {
     Ok(orchestrator.get_all_models().await)
 }
 
+/// 获取各凭证/模型的实测性能统计
+#[tauri::command]
+pub async fn get_model_performance_stats() -> Result<Vec<ModelPerformanceEntry>, String> {
+    let orchestrator = get_global_orchestrator().ok_or("编排器未初始化")?;
+
+    Ok(orchestrator.get_performance_stats())
+}
+
+/// 清除实测性能统计
+#[tauri::command]
+pub async fn reset_model_performance_stats() -> Result<(), String> {
+    let orchestrator = get_global_orchestrator().ok_or("编排器未初始化")?;
+
+    orchestrator.reset_performance_stats().await;
+    Ok(())
+}
+
 // ============================================================================
 // 凭证管理命令
 // ============================================================================
@@ -11,6 +11,7 @@ use crate::models::provider_pool_model::{
 use chrono::Utc;
 use proxycast_credential::CredentialSyncService;
 use proxycast_services::provider_pool_service::ProviderPoolService;
+use proxycast_services::token_refresh_scheduler::{TokenRefreshScheduler, TokenRefreshStatus};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -22,6 +23,9 @@ pub struct ProviderPoolServiceState(pub Arc<ProviderPoolService>);
 /// 凭证同步服务状态封装
 pub struct CredentialSyncServiceState(pub Option<Arc<CredentialSyncService>>);
 
+/// OAuth Token 预刷新调度器状态封装
+pub struct TokenRefreshSchedulerState(pub Arc<TokenRefreshScheduler>);
+
 /// 展开路径中的 ~ 为用户主目录
 fn expand_tilde(path: &str) -> String {
     if let Some(stripped) = path.strip_prefix("~/") {
@@ -1006,17 +1010,30 @@ pub fn add_claude_oauth_credential(
 pub async fn refresh_pool_credential_token(
     db: State<'_, DbConnection>,
     pool_service: State<'_, ProviderPoolServiceState>,
+    token_refresh: State<'_, TokenRefreshSchedulerState>,
     uuid: String,
 ) -> Result<String, String> {
     tracing::info!("[DEBUG] 开始刷新 Token for uuid: {}", uuid);
     let result = pool_service.0.refresh_credential_token(&db, &uuid).await;
     match &result {
-        Ok(msg) => tracing::info!("[DEBUG] Token 刷新成功: {}", msg),
+        Ok(msg) => {
+            tracing::info!("[DEBUG] Token 刷新成功: {}", msg);
+            // 手动刷新成功后清除后台预刷新的退避与重新授权标记
+            token_refresh.0.reset(&uuid);
+        }
         Err(err) => tracing::error!("[DEBUG] Token 刷新失败: {}", err),
     }
     result
 }
 
+/// 获取 OAuth Token 后台预刷新状态
+#[tauri::command]
+pub fn get_token_refresh_status(
+    token_refresh: State<'_, TokenRefreshSchedulerState>,
+) -> Vec<TokenRefreshStatus> {
+    token_refresh.0.status()
+}
+
 /// 获取凭证的 OAuth 状态
 #[tauri::command]
 pub fn get_pool_credential_oauth_status(
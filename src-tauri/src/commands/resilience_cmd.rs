@@ -1,6 +1,10 @@
 //! 容错配置相关 Tauri 命令
 
-use crate::resilience::{FailoverConfig, RetryConfig};
+use crate::app::types::AppState;
+use crate::resilience::{
+    CircuitKey, CircuitStatus, CircuitTransition, FailoverConfig, RetryConfig,
+};
+use proxycast_services::concurrency_limiter::{global_concurrency_limiter, ConcurrencySnapshot};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -160,6 +164,66 @@ pub async fn clear_switch_log(
     Ok(())
 }
 
+/// 熔断器状态（用于前端显示）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CircuitBreakerStatusDto {
+    /// 服务器是否运行（未运行时无熔断状态）
+    pub running: bool,
+    /// 是否启用熔断器
+    pub enabled: bool,
+    /// 各维度的熔断状态
+    pub circuits: Vec<CircuitStatus>,
+    /// 最近的状态转换事件
+    pub recent_transitions: Vec<CircuitTransition>,
+}
+
+/// 获取熔断器状态
+#[tauri::command]
+pub async fn get_circuit_breaker_status(
+    state: tauri::State<'_, AppState>,
+) -> Result<CircuitBreakerStatusDto, String> {
+    let s = state.read().await;
+    let Some(breaker) = &s.circuit_breaker else {
+        return Ok(CircuitBreakerStatusDto {
+            enabled: s.config.circuit_breaker.enabled,
+            ..Default::default()
+        });
+    };
+
+    Ok(CircuitBreakerStatusDto {
+        running: true,
+        enabled: breaker.config().enabled,
+        circuits: breaker.snapshot(),
+        recent_transitions: breaker.recent_transitions(),
+    })
+}
+
+/// 重置熔断器
+///
+/// 指定 `key` 时只关闭该维度的熔断，否则清除所有熔断状态
+#[tauri::command]
+pub async fn reset_circuit_breaker(
+    state: tauri::State<'_, AppState>,
+    key: Option<CircuitKey>,
+) -> Result<(), String> {
+    let s = state.read().await;
+    let breaker = s
+        .circuit_breaker
+        .as_ref()
+        .ok_or_else(|| "服务器未运行".to_string())?;
+    match key {
+        Some(key) => breaker.reset(&key),
+        None => breaker.reset_all(),
+    }
+    Ok(())
+}
+
+/// 获取并发限制与排队统计
+#[tauri::command]
+pub async fn get_concurrency_metrics() -> Result<ConcurrencySnapshot, String> {
+    Ok(global_concurrency_limiter().snapshot())
+}
+
 /// 添加切换日志条目（内部使用）
 #[allow(dead_code)]
 pub async fn add_switch_log_entry(
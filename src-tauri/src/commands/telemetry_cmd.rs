@@ -2,6 +2,7 @@
 //!
 //! 提供请求日志、统计数据和 Token 追踪的 Tauri 命令
 
+use crate::resilience::CircuitTransition;
 use crate::telemetry::{
     ModelStats, ModelTokenStats, ProviderStats, ProviderTokenStats, RequestLog, RequestLogger,
     RequestStatus, StatsAggregator, StatsSummary, TimeRange, TokenStatsSummary, TokenTracker,
@@ -134,6 +135,17 @@ pub async fn clear_request_logs(state: tauri::State<'_, TelemetryState>) -> Resu
     Ok(())
 }
 
+/// 获取熔断状态转换事件
+#[tauri::command]
+pub async fn get_circuit_transitions(
+    state: tauri::State<'_, TelemetryState>,
+    limit: Option<usize>,
+) -> Result<Vec<CircuitTransition>, String> {
+    let events = state.logger.circuit_transitions();
+    let skip = limit.map_or(0, |limit| events.len().saturating_sub(limit));
+    Ok(events.into_iter().skip(skip).collect())
+}
+
 // ========== 统计命令 ==========
 
 /// 时间范围参数
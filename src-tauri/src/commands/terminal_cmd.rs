@@ -11,14 +11,32 @@
 //! - `terminal_resize` - 调整终端大小
 //! - `terminal_close` - 关闭终端会话
 //! - `terminal_list_sessions` - 获取所有会话列表
+//! - `terminal_search_commands` - 全文搜索命令历史（跨会话）
+//! - `terminal_recent_commands` - 获取最近执行的命令
+//! - `terminal_last_failed_command` - 获取最近一次失败的命令及其输出
+//! - `terminal_start_recording` / `terminal_stop_recording` - 开始/停止 asciicast 录制
+//! - `terminal_add_recording_marker` - 在录制中插入标记
+//! - `terminal_list_recordings` - 获取所有录制
+//! - `terminal_replay_recording` - 回放录制到新的终端块
+//! - `terminal_import_recording` / `terminal_export_recording` - 导入/导出 `.cast` 文件
+//! - `terminal_ssh_add_forward` / `terminal_ssh_remove_forward` - 开启/停止 SSH 端口转发
+//! - `terminal_ssh_list_forwards` - 获取 SSH 连接上的端口转发
 
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use serde::Serialize;
 use tauri::State;
 use tokio::sync::RwLock;
 
-use proxycast_terminal::{SessionMetadata, TerminalSessionManager};
+use proxycast_terminal::connections::{ForwardInfo, ForwardSpec, SSHConnRegistry};
+use proxycast_terminal::persistence::command_store::DEFAULT_SEARCH_LIMIT;
+use proxycast_terminal::{
+    CommandHistoryStore, CommandRecord, CommandSearchHit, RecordingInfo, SessionMetadata,
+    TerminalSessionManager,
+};
+
+use crate::database::DbConnection;
 
 /// 终端会话管理器状态包装
 pub struct TerminalManagerState(pub Arc<RwLock<Option<TerminalSessionManager>>>);
@@ -153,3 +171,271 @@ pub async fn terminal_get_session(
 
     Ok(manager.get_session(&session_id).await)
 }
+
+/// 全文搜索命令历史
+///
+/// # 参数
+/// - `query`: 搜索词（匹配命令行、输出和目录）
+/// - `block_id`: 限定会话（可选，默认跨所有会话）
+/// - `limit`: 最大返回条数（可选）
+#[tauri::command]
+pub async fn terminal_search_commands(
+    db: State<'_, DbConnection>,
+    query: String,
+    block_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<CommandSearchHit>, String> {
+    let store = CommandHistoryStore::new(db.inner().clone());
+    tokio::task::spawn_blocking(move || {
+        store.search(
+            &query,
+            block_id.as_deref(),
+            limit.unwrap_or(DEFAULT_SEARCH_LIMIT),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+/// 获取最近执行的命令
+///
+/// # 参数
+/// - `block_id`: 限定会话（可选）
+/// - `limit`: 最大返回条数（可选）
+#[tauri::command]
+pub async fn terminal_recent_commands(
+    db: State<'_, DbConnection>,
+    block_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<CommandRecord>, String> {
+    let store = CommandHistoryStore::new(db.inner().clone());
+    tokio::task::spawn_blocking(move || {
+        store.recent(block_id.as_deref(), limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+/// 获取最近一次失败（退出码非 0）的命令及其输出
+///
+/// # 参数
+/// - `block_id`: 限定会话（可选）
+#[tauri::command]
+pub async fn terminal_last_failed_command(
+    db: State<'_, DbConnection>,
+    block_id: Option<String>,
+) -> Result<Option<CommandRecord>, String> {
+    let store = CommandHistoryStore::new(db.inner().clone());
+    tokio::task::spawn_blocking(move || store.last_failed(block_id.as_deref()))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// 开始录制终端会话（asciicast v2）
+///
+/// # 参数
+/// - `session_id`: 会话 ID
+/// - `title`: 录制标题（可选）
+///
+/// # 返回
+/// 录制文件路径
+#[tauri::command]
+pub async fn terminal_start_recording(
+    state: State<'_, TerminalManagerState>,
+    session_id: String,
+    title: Option<String>,
+) -> Result<String, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    let path = manager
+        .start_recording(&session_id, title)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// 停止录制终端会话
+///
+/// # 返回
+/// 录制文件路径（会话未在录制时为 None）
+#[tauri::command]
+pub async fn terminal_stop_recording(
+    state: State<'_, TerminalManagerState>,
+    session_id: String,
+) -> Result<Option<String>, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    let path = manager
+        .stop_recording(&session_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(path.map(|p| p.to_string_lossy().into_owned()))
+}
+
+/// 在录制中插入标记
+///
+/// # 参数
+/// - `session_id`: 会话 ID
+/// - `label`: 标记内容
+#[tauri::command]
+pub async fn terminal_add_recording_marker(
+    state: State<'_, TerminalManagerState>,
+    session_id: String,
+    label: String,
+) -> Result<(), String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .add_recording_marker(&session_id, &label)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取所有录制
+#[tauri::command]
+pub async fn terminal_list_recordings(
+    state: State<'_, TerminalManagerState>,
+) -> Result<Vec<RecordingInfo>, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager.list_recordings().map_err(|e| e.to_string())
+}
+
+/// 回放录制到新的终端块
+///
+/// # 参数
+/// - `path`: 录制文件路径
+/// - `speed`: 回放速度倍数（可选，默认 1.0）
+/// - `idle_time_limit`: 最大空闲时间（秒，可选）
+#[tauri::command]
+pub async fn terminal_replay_recording(
+    state: State<'_, TerminalManagerState>,
+    path: String,
+    speed: Option<f64>,
+    idle_time_limit: Option<f64>,
+) -> Result<CreateSessionResponse, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    let session_id = manager
+        .replay_recording(&PathBuf::from(path), speed.unwrap_or(1.0), idle_time_limit)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(CreateSessionResponse { session_id })
+}
+
+/// 导入外部 `.cast` 文件
+///
+/// # 参数
+/// - `path`: 待导入文件路径
+#[tauri::command]
+pub async fn terminal_import_recording(
+    state: State<'_, TerminalManagerState>,
+    path: String,
+) -> Result<RecordingInfo, String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .import_recording(&PathBuf::from(path))
+        .map_err(|e| e.to_string())
+}
+
+/// 导出录制为 `.cast` 文件
+///
+/// # 参数
+/// - `path`: 录制文件路径
+/// - `destination`: 导出目标路径
+#[tauri::command]
+pub async fn terminal_export_recording(
+    state: State<'_, TerminalManagerState>,
+    path: String,
+    destination: String,
+) -> Result<(), String> {
+    let guard = state.inner().0.read().await;
+    let manager = guard
+        .as_ref()
+        .ok_or_else(|| "终端管理器未初始化".to_string())?;
+
+    manager
+        .export_recording(&PathBuf::from(path), &PathBuf::from(destination))
+        .map_err(|e| e.to_string())
+}
+
+/// 在 SSH 连接上开启端口转发
+///
+/// 连接按名称从共享注册表获取，未连接时按配置建立。
+///
+/// # 参数
+/// - `connection`: 连接名称（配置名、Host 别名或 `user@host:port`）
+/// - `spec`: 转发配置（本地 / 远程 / 动态）
+#[tauri::command]
+pub async fn terminal_ssh_add_forward(
+    connection: String,
+    spec: ForwardSpec,
+) -> Result<ForwardInfo, String> {
+    let conn = SSHConnRegistry::global()
+        .get_or_connect(&connection)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // 远程转发需要等待服务端确认监听，放到阻塞线程执行
+    tokio::task::spawn_blocking(move || conn.add_forward(spec))
+        .await
+        .map_err(|e| format!("端口转发任务执行失败: {e}"))?
+        .map_err(|e| e.to_string())
+}
+
+/// 停止 SSH 端口转发
+///
+/// # 参数
+/// - `connection`: 连接名称
+/// - `forward_id`: 转发 ID
+#[tauri::command]
+pub async fn terminal_ssh_remove_forward(
+    connection: String,
+    forward_id: String,
+) -> Result<(), String> {
+    let conn = SSHConnRegistry::global()
+        .get(&connection)
+        .ok_or_else(|| format!("SSH 连接未建立: {connection}"))?;
+
+    tokio::task::spawn_blocking(move || conn.remove_forward(&forward_id))
+        .await
+        .map_err(|e| format!("端口转发任务执行失败: {e}"))?
+        .map_err(|e| e.to_string())
+}
+
+/// 获取 SSH 连接上的端口转发
+///
+/// 连接未建立时返回空列表。
+///
+/// # 参数
+/// - `connection`: 连接名称
+#[tauri::command]
+pub async fn terminal_ssh_list_forwards(connection: String) -> Result<Vec<ForwardInfo>, String> {
+    Ok(SSHConnRegistry::global()
+        .get(&connection)
+        .map(|conn| conn.list_forwards())
+        .unwrap_or_default())
+}
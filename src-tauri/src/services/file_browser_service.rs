@@ -3,8 +3,15 @@
 //! 纯逻辑已迁移到 `proxycast-services` crate，
 //! 本模块仅保留 Tauri 命令封装。
 
+use std::sync::Arc;
+
+use tauri::{AppHandle, Emitter};
+
 pub use proxycast_services::file_browser_service::{list_directory, read_file_preview};
-pub use proxycast_services::file_browser_service::{DirectoryListing, FileEntry, FilePreview};
+pub use proxycast_services::file_browser_service::{
+    DirectoryListing, FileEntry, FilePreview, TransferOptions, TransferProgress,
+    TransferProgressEmitter,
+};
 
 /// Tauri 命令：列出目录
 #[tauri::command]
@@ -21,18 +28,36 @@ pub async fn read_file_preview_cmd(
     proxycast_services::file_browser_service::read_file_preview_cmd(path, max_size).await
 }
 
+/// Tauri 命令：获取文件元信息
+#[tauri::command]
+pub async fn stat_path(path: String) -> Result<FileEntry, String> {
+    proxycast_services::file_browser_service::stat_path(path).await
+}
+
 /// Tauri 命令：获取用户主目录
 #[tauri::command]
 pub async fn get_home_dir() -> Result<String, String> {
     proxycast_services::file_browser_service::get_home_dir().await
 }
 
+/// Tauri 命令：获取远程连接的主目录
+#[tauri::command]
+pub async fn get_remote_home_dir(connection: String) -> Result<String, String> {
+    proxycast_services::file_browser_service::get_remote_home_dir(connection).await
+}
+
 /// Tauri 命令：创建新文件
 #[tauri::command]
 pub async fn create_file(path: String) -> Result<(), String> {
     proxycast_services::file_browser_service::create_file(path).await
 }
 
+/// Tauri 命令：写入文件内容
+#[tauri::command]
+pub async fn write_file_content(path: String, content: String) -> Result<(), String> {
+    proxycast_services::file_browser_service::write_file_content(path, content).await
+}
+
 /// Tauri 命令：创建新目录
 #[tauri::command]
 pub async fn create_directory(path: String) -> Result<(), String> {
@@ -51,6 +76,29 @@ pub async fn rename_file(old_path: String, new_path: String) -> Result<(), Strin
     proxycast_services::file_browser_service::rename_file(old_path, new_path).await
 }
 
+/// Tauri 命令：复制文件或目录（上传 / 下载 / 远程间复制）
+/// 传输过程中向前端发送 file-browser:transfer-progress 事件
+#[tauri::command]
+pub async fn copy_path(
+    app: AppHandle,
+    source: String,
+    destination: String,
+    options: Option<TransferOptions>,
+) -> Result<TransferProgress, String> {
+    let emitter: TransferProgressEmitter = Arc::new(move |progress: &TransferProgress| {
+        app.emit("file-browser:transfer-progress", progress)
+            .map_err(|e| format!("发送传输进度事件失败: {e}"))
+    });
+
+    proxycast_services::file_browser_service::copy_path(
+        source,
+        destination,
+        options.unwrap_or_default(),
+        Some(emitter),
+    )
+    .await
+}
+
 /// Tauri 命令：复制文件名到剪贴板（返回文件名供前端处理）
 #[tauri::command]
 pub async fn get_file_name(path: String) -> Result<String, String> {
@@ -0,0 +1,860 @@
+//! 小说设定知识库（Canon）
+//!
+//! 维护小说项目的“既定事实”：角色及其属性、人物关系、地点、时间线事件与世界规则。
+//! Canon 由创作设定初始化，再由模型逐章抽取增量事实合并而成，
+//! 用于检测新章节中的设定冲突（已死亡角色说话、瞳色改变、时间线错乱等）。
+
+use crate::services::novel_service::{
+    NovelChapterRecord, NovelCharacterRecord, NovelConsistencyIssue, NovelIssueLocation,
+    NovelSettingsEnvelope,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// 单章抽取时送入模型的最大字符数
+const MAX_EXTRACT_CHARS: usize = 12_000;
+/// 一致性检查时送入模型的最大字符数
+const MAX_CHECK_CHARS: usize = 16_000;
+/// 判断“说话”时，姓名之后向后查看的字符数
+const SPEECH_WINDOW_CHARS: usize = 8;
+
+const SPEECH_MARKERS: &[&str] = &[
+    "说", "道", "问", "喊", "叫道", "笑道", "开口", "回答", "低声", "怒道", "吼",
+];
+const RECALL_MARKERS: &[&str] = &[
+    "回忆", "想起", "记得", "梦", "生前", "曾经", "遗言", "幻觉", "幻影", "亡魂", "鬼魂",
+];
+const COLOR_WORDS: &[&str] = &[
+    "琥珀", "黑", "蓝", "绿", "红", "金", "银", "灰", "紫", "棕", "褐", "白", "碧", "赤",
+];
+
+/// 角色生死状态
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CanonCharacterStatus {
+    #[default]
+    Alive,
+    Dead,
+    Missing,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct CanonCharacter {
+    pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// 属性键使用英文蛇形命名（如 `eye_color`、`hair_color`、`age`）
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+    #[serde(default)]
+    pub status: CanonCharacterStatus,
+    /// 状态（如死亡）在哪一章确立
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_since_chapter: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_chapter: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct CanonRelationship {
+    pub from: String,
+    pub to: String,
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since_chapter: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct CanonLocation {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct CanonTimelineEvent {
+    /// 事件发生的章节；抽取结果中可省略，合并时由章节号填充
+    #[serde(default)]
+    pub chapter_no: i32,
+    pub description: String,
+    /// 故事内时间标签（如“第三年冬”）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_label: Option<String>,
+    #[serde(default)]
+    pub characters: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct CanonWorldRule {
+    pub rule: String,
+    #[serde(default)]
+    pub source: String,
+}
+
+/// 小说设定知识库
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct NovelCanon {
+    #[serde(default)]
+    pub characters: Vec<CanonCharacter>,
+    #[serde(default)]
+    pub relationships: Vec<CanonRelationship>,
+    #[serde(default)]
+    pub locations: Vec<CanonLocation>,
+    #[serde(default)]
+    pub timeline: Vec<CanonTimelineEvent>,
+    #[serde(default)]
+    pub world_rules: Vec<CanonWorldRule>,
+    /// 已合并到 Canon 的最后一章章节号（0 表示仅包含设定）
+    #[serde(default)]
+    pub source_chapter_no: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NovelCanonRecord {
+    pub id: String,
+    pub project_id: String,
+    pub canon: NovelCanon,
+    pub source_chapter_no: i32,
+    pub version: i32,
+    pub created_at: i64,
+}
+
+impl NovelCanon {
+    /// 从创作设定与角色卡初始化 Canon（不调用模型）
+    pub fn seed(
+        settings: Option<&NovelSettingsEnvelope>,
+        characters: &[NovelCharacterRecord],
+    ) -> Self {
+        let mut canon = NovelCanon::default();
+
+        if let Some(settings) = settings {
+            let s = &settings.data;
+            if !s.main_character.name.trim().is_empty() {
+                let mut attributes = BTreeMap::new();
+                insert_non_empty(&mut attributes, "gender", &s.main_character.gender);
+                insert_non_empty(&mut attributes, "age", &s.main_character.age);
+                insert_non_empty(
+                    &mut attributes,
+                    "personality",
+                    &s.main_character.personality,
+                );
+                canon.upsert_character(CanonCharacter {
+                    name: s.main_character.name.trim().to_string(),
+                    attributes,
+                    ..Default::default()
+                });
+            }
+
+            for side in &s.side_characters {
+                if side.name.trim().is_empty() {
+                    continue;
+                }
+                let mut attributes = BTreeMap::new();
+                insert_non_empty(&mut attributes, "gender", &side.gender);
+                insert_non_empty(&mut attributes, "age", &side.age);
+                insert_non_empty(&mut attributes, "abilities", &side.abilities);
+                canon.upsert_character(CanonCharacter {
+                    name: side.name.trim().to_string(),
+                    aliases: non_empty_vec(&side.nickname),
+                    attributes,
+                    ..Default::default()
+                });
+                let relationship = if side.relationship_custom.trim().is_empty() {
+                    side.relationship.trim()
+                } else {
+                    side.relationship_custom.trim()
+                };
+                if !relationship.is_empty() && !s.main_character.name.trim().is_empty() {
+                    canon.upsert_relationship(CanonRelationship {
+                        from: side.name.trim().to_string(),
+                        to: s.main_character.name.trim().to_string(),
+                        kind: relationship.to_string(),
+                        since_chapter: None,
+                    });
+                }
+            }
+
+            for antagonist in &s.antagonists {
+                if antagonist.name.trim().is_empty() {
+                    continue;
+                }
+                let mut attributes = BTreeMap::new();
+                insert_non_empty(&mut attributes, "gender", &antagonist.gender);
+                insert_non_empty(&mut attributes, "age", &antagonist.age);
+                insert_non_empty(&mut attributes, "abilities", &antagonist.abilities);
+                insert_non_empty(&mut attributes, "motive", &antagonist.motive);
+                canon.upsert_character(CanonCharacter {
+                    name: antagonist.name.trim().to_string(),
+                    aliases: non_empty_vec(&antagonist.nickname),
+                    attributes,
+                    ..Default::default()
+                });
+            }
+
+            let world = &s.world_details;
+            for (source, text) in [
+                ("power_system", &world.power_system),
+                ("culture_and_taboos", &world.culture_and_taboos),
+            ] {
+                if !text.trim().is_empty() {
+                    canon.world_rules.push(CanonWorldRule {
+                        rule: text.trim().to_string(),
+                        source: format!("settings.{source}"),
+                    });
+                }
+            }
+            for taboo in &s.taboos {
+                if !taboo.content.trim().is_empty() {
+                    canon.world_rules.push(CanonWorldRule {
+                        rule: format!("禁止出现：{}", taboo.content.trim()),
+                        source: "settings.taboos".to_string(),
+                    });
+                }
+            }
+            for name in split_list(&world.important_locations) {
+                canon.upsert_location(CanonLocation {
+                    name,
+                    description: String::new(),
+                });
+            }
+        }
+
+        for record in characters {
+            let name = record.name.trim();
+            if name.is_empty() {
+                continue;
+            }
+            let mut attributes = BTreeMap::new();
+            if let Some(card) = record.card_json.as_object() {
+                for key in ["gender", "age", "personality", "abilities", "appearance"] {
+                    if let Some(value) = card.get(key).and_then(Value::as_str) {
+                        insert_non_empty(&mut attributes, key, value);
+                    }
+                }
+            }
+            canon.upsert_character(CanonCharacter {
+                name: name.to_string(),
+                attributes,
+                ..Default::default()
+            });
+        }
+
+        canon
+    }
+
+    /// 按姓名或别名查找角色
+    pub fn find_character(&self, name: &str) -> Option<&CanonCharacter> {
+        let name = name.trim();
+        self.characters
+            .iter()
+            .find(|c| c.name == name || c.aliases.iter().any(|a| a == name))
+    }
+
+    fn find_character_index(&self, candidate: &CanonCharacter) -> Option<usize> {
+        self.characters.iter().position(|c| {
+            c.name == candidate.name
+                || c.aliases.iter().any(|a| a == &candidate.name)
+                || candidate.aliases.iter().any(|a| a == &c.name)
+        })
+    }
+
+    /// 合并角色：新属性覆盖旧值，死亡等状态变化保留最早确立的章节
+    pub fn upsert_character(&mut self, incoming: CanonCharacter) {
+        if incoming.name.trim().is_empty() {
+            return;
+        }
+        let Some(index) = self.find_character_index(&incoming) else {
+            self.characters.push(incoming);
+            return;
+        };
+
+        let existing = &mut self.characters[index];
+        for alias in incoming.aliases {
+            if alias != existing.name && !existing.aliases.contains(&alias) {
+                existing.aliases.push(alias);
+            }
+        }
+        existing.attributes.extend(incoming.attributes);
+        if incoming.status != CanonCharacterStatus::Alive && incoming.status != existing.status {
+            existing.status = incoming.status;
+            existing.status_since_chapter = incoming.status_since_chapter;
+        }
+        existing.first_chapter = match (existing.first_chapter, incoming.first_chapter) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+    }
+
+    pub fn upsert_relationship(&mut self, incoming: CanonRelationship) {
+        if let Some(existing) = self
+            .relationships
+            .iter_mut()
+            .find(|r| r.from == incoming.from && r.to == incoming.to)
+        {
+            *existing = incoming;
+        } else {
+            self.relationships.push(incoming);
+        }
+    }
+
+    pub fn upsert_location(&mut self, incoming: CanonLocation) {
+        if let Some(existing) = self.locations.iter_mut().find(|l| l.name == incoming.name) {
+            if !incoming.description.is_empty() {
+                existing.description = incoming.description;
+            }
+        } else {
+            self.locations.push(incoming);
+        }
+    }
+
+    /// 合并某一章抽取出的增量事实
+    pub fn merge_chapter(&mut self, chapter_no: i32, delta: NovelCanon) {
+        for mut character in delta.characters {
+            character.first_chapter.get_or_insert(chapter_no);
+            if character.status != CanonCharacterStatus::Alive {
+                character.status_since_chapter.get_or_insert(chapter_no);
+            }
+            self.upsert_character(character);
+        }
+        for mut relationship in delta.relationships {
+            relationship.since_chapter.get_or_insert(chapter_no);
+            self.upsert_relationship(relationship);
+        }
+        for location in delta.locations {
+            self.upsert_location(location);
+        }
+        for mut event in delta.timeline {
+            event.chapter_no = chapter_no;
+            if !self.timeline.contains(&event) {
+                self.timeline.push(event);
+            }
+        }
+        for mut rule in delta.world_rules {
+            if rule.source.is_empty() {
+                rule.source = format!("chapter.{chapter_no}");
+            }
+            if !self.world_rules.iter().any(|r| r.rule == rule.rule) {
+                self.world_rules.push(rule);
+            }
+        }
+        self.source_chapter_no = self.source_chapter_no.max(chapter_no);
+    }
+
+    /// 渲染为提示词中使用的紧凑文本
+    pub fn to_prompt_context(&self) -> String {
+        let mut lines = Vec::new();
+        lines.push("【角色】".to_string());
+        if self.characters.is_empty() {
+            lines.push("暂无".to_string());
+        }
+        for c in &self.characters {
+            let attrs = c
+                .attributes
+                .iter()
+                .map(|(k, v)| format!("{k}={v}"))
+                .collect::<Vec<_>>()
+                .join("，");
+            let status = match (c.status, c.status_since_chapter) {
+                (CanonCharacterStatus::Alive, _) => "存活".to_string(),
+                (CanonCharacterStatus::Dead, Some(ch)) => format!("已于第{ch}章死亡"),
+                (CanonCharacterStatus::Dead, None) => "已死亡".to_string(),
+                (CanonCharacterStatus::Missing, _) => "失踪".to_string(),
+                (CanonCharacterStatus::Unknown, _) => "未知".to_string(),
+            };
+            let aliases = if c.aliases.is_empty() {
+                String::new()
+            } else {
+                format!("（别名：{}）", c.aliases.join("/"))
+            };
+            lines.push(format!("- {}{}：{}；{}", c.name, aliases, status, attrs));
+        }
+
+        if !self.relationships.is_empty() {
+            lines.push("【关系】".to_string());
+            for r in &self.relationships {
+                lines.push(format!("- {} → {}：{}", r.from, r.to, r.kind));
+            }
+        }
+        if !self.locations.is_empty() {
+            lines.push("【地点】".to_string());
+            for l in &self.locations {
+                lines.push(format!("- {}：{}", l.name, l.description));
+            }
+        }
+        if !self.timeline.is_empty() {
+            lines.push("【时间线】".to_string());
+            for e in &self.timeline {
+                let label = e.time_label.as_deref().unwrap_or("");
+                lines.push(format!(
+                    "- 第{}章 {} {}",
+                    e.chapter_no, label, e.description
+                ));
+            }
+        }
+        if !self.world_rules.is_empty() {
+            lines.push("【世界规则】".to_string());
+            for r in &self.world_rules {
+                lines.push(format!("- {}", r.rule));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// 构建单章事实抽取提示词
+pub fn build_extraction_prompt(canon: &NovelCanon, chapter: &NovelChapterRecord) -> String {
+    let content: String = chapter.content.chars().take(MAX_EXTRACT_CHARS).collect();
+    format!(
+        "你是小说设定管理员。请阅读第 {chapter_no} 章，抽取本章新确立或发生变化的设定事实，只输出 JSON 对象，不要解释。\n\n\
+        JSON 结构：\n{{\n  \"characters\": [{{\"name\": \"\", \"aliases\": [], \"attributes\": {{\"eye_color\": \"\"}}, \"status\": \"alive|dead|missing|unknown\"}}],\n  \"relationships\": [{{\"from\": \"\", \"to\": \"\", \"kind\": \"\"}}],\n  \"locations\": [{{\"name\": \"\", \"description\": \"\"}}],\n  \"timeline\": [{{\"description\": \"\", \"time_label\": \"\", \"characters\": []}}],\n  \"world_rules\": [{{\"rule\": \"\"}}]\n}}\n\n\
+        要求：\n1. 属性键使用英文蛇形命名（eye_color、hair_color、age、height、scar 等），值使用中文。\n2. 只记录文本明确写出的事实，不要推测。\n3. 角色在本章死亡时 status 填 dead。\n\n\
+        【已知设定】\n{canon}\n\n【第 {chapter_no} 章 {title}】\n{content}",
+        chapter_no = chapter.chapter_no,
+        title = chapter.title,
+        canon = canon.to_prompt_context(),
+        content = content,
+    )
+}
+
+/// 构建章节一致性检查提示词
+pub fn build_check_prompt(canon: &NovelCanon, chapter: &NovelChapterRecord) -> String {
+    let content: String = chapter.content.chars().take(MAX_CHECK_CHARS).collect();
+    format!(
+        "你是严格的小说连续性审校。请对照【既定设定】检查第 {chapter_no} 章中与设定矛盾的内容，只输出 JSON 数组，不要解释。\n\n\
+        每个元素：{{\"level\": \"error|warn|info\", \"code\": \"dead_character_active|attribute_contradiction|timeline_violation|relationship_contradiction|location_contradiction|world_rule_violation\", \"message\": \"中文说明\", \"quote\": \"从正文中逐字摘录的冲突片段（不超过 40 字）\", \"canon_ref\": \"被违反的设定\"}}\n\n\
+        注意：回忆、梦境、转述中出现已死亡角色不算冲突；没有冲突时输出 []。\n\n\
+        【既定设定】\n{canon}\n\n【第 {chapter_no} 章 {title}】\n{content}",
+        chapter_no = chapter.chapter_no,
+        title = chapter.title,
+        canon = canon.to_prompt_context(),
+        content = content,
+    )
+}
+
+/// 解析模型输出的增量 Canon
+pub fn parse_canon_delta(raw: &str) -> Result<NovelCanon, String> {
+    let json_text = extract_json_block(raw, '{', '}')
+        .ok_or_else(|| "模型输出中未找到 JSON 对象".to_string())?;
+    serde_json::from_str::<NovelCanon>(json_text).map_err(|e| format!("解析 Canon 失败: {e}"))
+}
+
+/// 解析模型输出的一致性问题，并把摘录片段定位为章节字符偏移
+pub fn parse_llm_issues(raw: &str, content: &str) -> Result<Vec<NovelConsistencyIssue>, String> {
+    let json_text = extract_json_block(raw, '[', ']')
+        .ok_or_else(|| "模型输出中未找到 JSON 数组".to_string())?;
+    let items: Vec<Value> =
+        serde_json::from_str(json_text).map_err(|e| format!("解析一致性结果失败: {e}"))?;
+
+    let issues = items
+        .into_iter()
+        .filter_map(|item| {
+            let message = item.get("message").and_then(Value::as_str)?.trim();
+            if message.is_empty() {
+                return None;
+            }
+            let level = item
+                .get("level")
+                .and_then(Value::as_str)
+                .filter(|level| matches!(*level, "error" | "warn" | "info"))
+                .unwrap_or("warn");
+            let code = item
+                .get("code")
+                .and_then(Value::as_str)
+                .filter(|s| !s.trim().is_empty())
+                .unwrap_or("canon_conflict");
+            let quote = item
+                .get("quote")
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .unwrap_or_default();
+            Some(NovelConsistencyIssue {
+                level: level.to_string(),
+                code: code.to_string(),
+                message: message.to_string(),
+                details: Some(json!({
+                    "source": "llm",
+                    "quote": quote,
+                    "canonRef": item.get("canon_ref").cloned().unwrap_or(Value::Null),
+                })),
+                location: locate_quote(content, quote),
+            })
+        })
+        .collect();
+
+    Ok(issues)
+}
+
+/// 基于规则的 Canon 冲突检查（不依赖模型）
+pub fn check_rules(canon: &NovelCanon, chapter: &NovelChapterRecord) -> Vec<NovelConsistencyIssue> {
+    let mut issues = Vec::new();
+    let content = chapter.content.as_str();
+
+    for character in &canon.characters {
+        let names: Vec<&str> = std::iter::once(character.name.as_str())
+            .chain(character.aliases.iter().map(String::as_str))
+            .filter(|n| !n.trim().is_empty())
+            .collect();
+
+        let died_before = character.status == CanonCharacterStatus::Dead
+            && !matches!(character.status_since_chapter, Some(ch) if ch >= chapter.chapter_no);
+        if died_before {
+            for name in &names {
+                if let Some(location) = find_speech(content, name) {
+                    issues.push(NovelConsistencyIssue {
+                        level: "error".to_string(),
+                        code: "dead_character_active".to_string(),
+                        message: format!("已死亡角色“{}”在本章说话", character.name),
+                        details: Some(json!({
+                            "source": "rule",
+                            "character": character.name,
+                            "diedInChapter": character.status_since_chapter,
+                        })),
+                        location: Some(location),
+                    });
+                    break;
+                }
+            }
+        }
+
+        for (attribute, body_parts) in [
+            ("eye_color", &["眼", "瞳", "眸"][..]),
+            ("hair_color", &["发"][..]),
+        ] {
+            let Some(expected) = character.attributes.get(attribute) else {
+                continue;
+            };
+            let Some(expected_color) = color_of(expected) else {
+                continue;
+            };
+            if let Some((location, found)) =
+                find_color_conflict(content, &names, body_parts, expected_color)
+            {
+                issues.push(NovelConsistencyIssue {
+                    level: "error".to_string(),
+                    code: "attribute_contradiction".to_string(),
+                    message: format!(
+                        "角色“{}”的{}应为“{}”，本章写成了“{}”",
+                        character.name,
+                        if attribute == "eye_color" {
+                            "瞳色"
+                        } else {
+                            "发色"
+                        },
+                        expected,
+                        found
+                    ),
+                    details: Some(json!({
+                        "source": "rule",
+                        "character": character.name,
+                        "attribute": attribute,
+                        "expected": expected,
+                        "found": found,
+                    })),
+                    location: Some(location),
+                });
+            }
+        }
+    }
+
+    issues
+}
+
+/// 合并规则与模型的问题：同类且位置重叠的问题只保留一条（规则优先）
+pub fn merge_issues(
+    mut base: Vec<NovelConsistencyIssue>,
+    extra: Vec<NovelConsistencyIssue>,
+) -> Vec<NovelConsistencyIssue> {
+    for issue in extra {
+        let duplicated = base.iter().any(|existing| {
+            existing.code == issue.code
+                && match (&existing.location, &issue.location) {
+                    (Some(a), Some(b)) => a.start < b.end && b.start < a.end,
+                    _ => false,
+                }
+        });
+        if !duplicated {
+            base.push(issue);
+        }
+    }
+    base
+}
+
+/// 在正文中定位摘录片段，返回字符偏移（左闭右开）
+pub fn locate_quote(content: &str, quote: &str) -> Option<NovelIssueLocation> {
+    let quote = quote.trim_matches(|c: char| c.is_whitespace() || c == '“' || c == '”');
+    if quote.is_empty() {
+        return None;
+    }
+    let byte_start = content.find(quote)?;
+    Some(byte_range_to_location(
+        content,
+        byte_start,
+        byte_start + quote.len(),
+    ))
+}
+
+fn byte_range_to_location(content: &str, byte_start: usize, byte_end: usize) -> NovelIssueLocation {
+    let start = content[..byte_start].chars().count();
+    let end = start + content[byte_start..byte_end].chars().count();
+    NovelIssueLocation { start, end }
+}
+
+/// 查找“姓名 + 说话动词”的片段；所在句子包含回忆类词语时忽略
+fn find_speech(content: &str, name: &str) -> Option<NovelIssueLocation> {
+    for (byte_start, _) in content.match_indices(name) {
+        let after_name = byte_start + name.len();
+        let window: String = content[after_name..]
+            .chars()
+            .take(SPEECH_WINDOW_CHARS)
+            .collect();
+        let Some(marker) = SPEECH_MARKERS.iter().find(|m| window.contains(**m)) else {
+            continue;
+        };
+        let (sentence_start, sentence_end) = sentence_bounds(content, byte_start);
+        let sentence = &content[sentence_start..sentence_end];
+        if RECALL_MARKERS.iter().any(|m| sentence.contains(m)) {
+            continue;
+        }
+        let marker_end = after_name + window.find(marker)? + marker.len();
+        return Some(byte_range_to_location(content, byte_start, marker_end));
+    }
+    None
+}
+
+/// 查找句子中“姓名 … X色 … 眼/发”且颜色与设定不符的片段
+fn find_color_conflict(
+    content: &str,
+    names: &[&str],
+    body_parts: &[&str],
+    expected_color: &str,
+) -> Option<(NovelIssueLocation, String)> {
+    for name in names {
+        for (byte_start, _) in content.match_indices(name) {
+            let (_, sentence_end) = sentence_bounds(content, byte_start);
+            let tail = &content[byte_start..sentence_end];
+            for color in COLOR_WORDS {
+                let pattern = format!("{color}色");
+                let Some(color_pos) = tail.find(&pattern) else {
+                    continue;
+                };
+                let after_color = &tail[color_pos + pattern.len()..];
+                let near: String = after_color.chars().take(4).collect();
+                if !body_parts.iter().any(|part| near.contains(part)) {
+                    continue;
+                }
+                if *color == expected_color {
+                    break;
+                }
+                let abs_start = byte_start + color_pos;
+                let abs_end = abs_start + pattern.len() + near.len();
+                return Some((
+                    byte_range_to_location(content, abs_start, abs_end),
+                    format!("{color}色"),
+                ));
+            }
+        }
+    }
+    None
+}
+
+fn sentence_bounds(content: &str, byte_pos: usize) -> (usize, usize) {
+    const TERMINATORS: &[char] = &['。', '！', '？', '\n', '!', '?'];
+    let start = content[..byte_pos]
+        .rfind(TERMINATORS)
+        .map(|i| i + content[i..].chars().next().map_or(1, char::len_utf8))
+        .unwrap_or(0);
+    let end = content[byte_pos..]
+        .find(TERMINATORS)
+        .map(|i| byte_pos + i)
+        .unwrap_or(content.len());
+    (start, end)
+}
+
+fn color_of(value: &str) -> Option<&'static str> {
+    COLOR_WORDS.iter().copied().find(|c| value.contains(c))
+}
+
+fn extract_json_block(raw: &str, open: char, close: char) -> Option<&str> {
+    let start = raw.find(open)?;
+    let end = raw.rfind(close)?;
+    if end > start {
+        Some(&raw[start..=end])
+    } else {
+        None
+    }
+}
+
+fn insert_non_empty(map: &mut BTreeMap<String, String>, key: &str, value: &str) {
+    let value = value.trim();
+    if !value.is_empty() {
+        map.insert(key.to_string(), value.to_string());
+    }
+}
+
+fn non_empty_vec(value: &str) -> Vec<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        Vec::new()
+    } else {
+        vec![value.to_string()]
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(['、', '，', ',', '；', ';', '\n'])
+        .map(|s| s.trim().trim_start_matches('-').trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(no: i32, content: &str) -> NovelChapterRecord {
+        NovelChapterRecord {
+            id: format!("ch-{no}"),
+            project_id: "p".to_string(),
+            chapter_no: no,
+            title: format!("第{no}章"),
+            content: content.to_string(),
+            word_count: 0,
+            status: "draft".to_string(),
+            quality_score: None,
+            metadata_json: None,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    fn canon_with_dead_elder() -> NovelCanon {
+        let mut canon = NovelCanon::default();
+        let mut attributes = BTreeMap::new();
+        attributes.insert("eye_color".to_string(), "碧色".to_string());
+        canon.upsert_character(CanonCharacter {
+            name: "林远".to_string(),
+            attributes,
+            ..Default::default()
+        });
+        canon.merge_chapter(
+            3,
+            NovelCanon {
+                characters: vec![CanonCharacter {
+                    name: "王长老".to_string(),
+                    status: CanonCharacterStatus::Dead,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        );
+        canon
+    }
+
+    #[test]
+    fn test_dead_character_speaking_is_flagged_with_offsets() {
+        let canon = canon_with_dead_elder();
+        let content = "夜色深沉。王长老冷笑道：“你来晚了。”";
+        let issues = check_rules(&canon, &chapter(5, content));
+
+        let issue = issues
+            .iter()
+            .find(|i| i.code == "dead_character_active")
+            .expect("应检测到已死亡角色说话");
+        let location = issue.location.as_ref().unwrap();
+        let flagged: String = content
+            .chars()
+            .skip(location.start)
+            .take(location.end - location.start)
+            .collect();
+        assert!(flagged.starts_with("王长老"));
+        assert_eq!(location.start, 5);
+    }
+
+    #[test]
+    fn test_dead_character_in_recollection_is_ignored() {
+        let canon = canon_with_dead_elder();
+        let content = "林远想起王长老说过的话，心中一痛。";
+        let issues = check_rules(&canon, &chapter(5, content));
+        assert!(issues.iter().all(|i| i.code != "dead_character_active"));
+    }
+
+    #[test]
+    fn test_death_chapter_itself_is_not_flagged() {
+        let canon = canon_with_dead_elder();
+        let issues = check_rules(&canon, &chapter(3, "王长老说：“我不甘心。”随后气绝。"));
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn test_eye_color_contradiction() {
+        let canon = canon_with_dead_elder();
+        let content = "林远抬起头，一双黑色的眼睛里满是怒火。";
+        let issues = check_rules(&canon, &chapter(6, content));
+        let issue = issues
+            .iter()
+            .find(|i| i.code == "attribute_contradiction")
+            .expect("应检测到瞳色冲突");
+        assert!(issue.message.contains("黑色"));
+
+        let consistent = check_rules(&canon, &chapter(6, "林远碧色的眼眸微微一眯。"));
+        assert!(consistent.is_empty());
+    }
+
+    #[test]
+    fn test_parse_llm_issues_locates_quote() {
+        let content = "第三年春天，他回到了青石镇。可那是第一年冬天的事。";
+        let raw = r#"```json
+[{"level":"error","code":"timeline_violation","message":"时间线倒退","quote":"第一年冬天","canon_ref":"第三年春"}]
+```"#;
+        let issues = parse_llm_issues(raw, content).unwrap();
+        assert_eq!(issues.len(), 1);
+        let location = issues[0].location.as_ref().unwrap();
+        assert_eq!(location.start, 17);
+        assert_eq!(location.end, 22);
+    }
+
+    #[test]
+    fn test_merge_chapter_keeps_death_chapter_and_aliases() {
+        let mut canon = NovelCanon::default();
+        canon.merge_chapter(
+            2,
+            serde_json::from_value(json!({
+                "characters": [{"name": "苏晴", "aliases": ["晴儿"], "attributes": {"hair_color": "银色"}}],
+                "timeline": [{"description": "苏晴入宗门"}]
+            }))
+            .unwrap(),
+        );
+        canon.merge_chapter(
+            4,
+            parse_canon_delta(r#"{"characters":[{"name":"晴儿","status":"dead"}]}"#).unwrap(),
+        );
+
+        let character = canon.find_character("苏晴").unwrap();
+        assert_eq!(character.status, CanonCharacterStatus::Dead);
+        assert_eq!(character.status_since_chapter, Some(4));
+        assert_eq!(character.first_chapter, Some(2));
+        assert_eq!(canon.timeline[0].chapter_no, 2);
+        assert_eq!(canon.source_chapter_no, 4);
+    }
+
+    #[test]
+    fn test_merge_issues_dedupes_overlapping() {
+        let rule = NovelConsistencyIssue {
+            level: "error".to_string(),
+            code: "dead_character_active".to_string(),
+            message: "rule".to_string(),
+            details: None,
+            location: Some(NovelIssueLocation { start: 5, end: 10 }),
+        };
+        let llm = NovelConsistencyIssue {
+            message: "llm".to_string(),
+            location: Some(NovelIssueLocation { start: 8, end: 12 }),
+            ..rule.clone()
+        };
+        let merged = merge_issues(vec![rule], vec![llm]);
+        assert_eq!(merged.len(), 1);
+    }
+}
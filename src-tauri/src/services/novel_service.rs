@@ -3,10 +3,17 @@
 //! 提供小说项目、设定、章节生成与一致性检查能力。
 
 use crate::database::{lock_db, DbConnection};
+use crate::services::novel_canon::{self, NovelCanon, NovelCanonRecord};
 use proxycast_services::api_key_provider_service::ApiKeyProviderService;
+use proxycast_services::manuscript_export::{
+    self, Manuscript, ManuscriptAsset, ManuscriptChapter, ManuscriptExportOptions,
+    ManuscriptFormat, ManuscriptMetadata,
+};
+use proxycast_services::material_service::MaterialService;
+use proxycast_services::persona_service::PersonaService;
 use proxycast_services::provider_pool_service::ProviderPoolService;
 use proxycast_skills::{LlmProvider, ProxyCastLlmProvider};
-use rusqlite::{params, OptionalExtension};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::sync::Arc;
@@ -17,6 +24,8 @@ const DEFAULT_TARGET_WORDS: i64 = 100_000;
 const DEFAULT_MODEL: &str = "default";
 const DEFAULT_RECENT_CHAPTERS: usize = 3;
 const NOVEL_SETTINGS_SCHEMA_VERSION: i32 = 1;
+/// 项目元数据中记录导入资源（封面、插图）的字段
+const IMPORTED_ASSETS_KEY: &str = "manuscript_assets";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MainCharacter {
@@ -345,6 +354,13 @@ pub struct NovelGenerationRun {
     pub created_at: i64,
 }
 
+/// 问题在章节正文中的位置（字符偏移，左闭右开）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NovelIssueLocation {
+    pub start: usize,
+    pub end: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NovelConsistencyIssue {
     pub level: String,
@@ -352,6 +368,8 @@ pub struct NovelConsistencyIssue {
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub details: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<NovelIssueLocation>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -470,6 +488,25 @@ pub struct NovelPolishChapterRequest {
 pub struct NovelCheckConsistencyRequest {
     pub project_id: String,
     pub chapter_id: String,
+    /// 是否调用模型做 Canon 冲突检查（默认开启）
+    #[serde(default)]
+    pub use_llm: Option<bool>,
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NovelRebuildCanonRequest {
+    pub project_id: String,
+    /// 为 true 时丢弃已有 Canon，从设定和全部章节重新抽取
+    #[serde(default)]
+    pub full_rebuild: Option<bool>,
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -479,6 +516,34 @@ pub struct NovelListRunsRequest {
     pub limit: Option<i64>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct NovelExportManuscriptRequest {
+    pub project_id: String,
+    pub format: ManuscriptFormat,
+    pub output_path: String,
+    #[serde(default)]
+    pub options: Option<ManuscriptExportOptions>,
+    /// 作者名；为空时使用项目默认人设的名称
+    #[serde(default)]
+    pub author: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NovelExportManuscriptResult {
+    pub output_path: String,
+    pub format: ManuscriptFormat,
+    pub chapter_count: usize,
+    pub size_bytes: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NovelImportMarkdownBundleRequest {
+    pub path: String,
+    /// 覆盖包内书名
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
 #[derive(Clone)]
 pub struct NovelService {
     db: DbConnection,
@@ -602,6 +667,8 @@ impl NovelService {
             params![id, &request.project_id, settings_json, next_version, now],
         )
         .map_err(|e| format!("写入小说设定失败: {e}"))?;
+        // Canon 由设定初始化，设定变更后所有快照都已过期
+        invalidate_canon(&tx, &request.project_id, 0)?;
 
         tx.execute(
             "UPDATE novel_projects SET updated_at = ?1 WHERE id = ?2",
@@ -778,7 +845,7 @@ impl NovelService {
         .await
     }
 
-    pub fn check_consistency(
+    pub async fn check_consistency(
         &self,
         request: NovelCheckConsistencyRequest,
     ) -> Result<NovelConsistencyCheck, String> {
@@ -789,7 +856,88 @@ impl NovelService {
 
         let settings = self.get_latest_settings(&request.project_id)?;
         let characters = self.list_characters(&request.project_id)?;
-        let issues = evaluate_consistency(&chapter, settings.as_ref(), &characters);
+        let use_llm = request.use_llm.unwrap_or(true);
+
+        let mut issues = evaluate_consistency(&chapter, settings.as_ref(), &characters);
+
+        // Canon 只吸收当前章节之前的内容，避免被检查章节“自证”
+        let canon = match self
+            .refresh_canon(
+                &request.project_id,
+                chapter.chapter_no - 1,
+                use_llm,
+                request.provider.as_deref(),
+                request.model.as_deref(),
+            )
+            .await
+        {
+            Ok(canon) => canon,
+            Err(error) => {
+                tracing::warn!("[Novel] Canon 抽取失败，使用已有 Canon 检查: {}", error);
+                issues.push(NovelConsistencyIssue {
+                    level: "info".to_string(),
+                    code: "canon_extract_failed".to_string(),
+                    message: "前文设定抽取失败，Canon 可能不完整".to_string(),
+                    details: Some(json!({ "error": error })),
+                    location: None,
+                });
+                self.refresh_canon(
+                    &request.project_id,
+                    chapter.chapter_no - 1,
+                    false,
+                    None,
+                    None,
+                )
+                .await?
+            }
+        };
+        issues = novel_canon::merge_issues(issues, novel_canon::check_rules(&canon, &chapter));
+
+        if use_llm {
+            let prompt = novel_canon::build_check_prompt(&canon, &chapter);
+            let llm_result = self
+                .call_llm(
+                    &prompt,
+                    request.provider.as_deref(),
+                    request.model.as_deref(),
+                    Some(0.0),
+                    None,
+                )
+                .await
+                .and_then(|(model_used, raw, latency_ms)| {
+                    let parsed = novel_canon::parse_llm_issues(&raw, &chapter.content)?;
+                    Ok((model_used, raw, latency_ms, parsed))
+                });
+
+            match llm_result {
+                Ok((model_used, raw, latency_ms, llm_issues)) => {
+                    self.record_run(InsertRunParams {
+                        run_id: &Uuid::new_v4().to_string(),
+                        project_id: &request.project_id,
+                        mode: "consistency",
+                        input_snapshot: json!({ "chapter_id": chapter.id, "chapter_no": chapter.chapter_no }),
+                        output_snapshot: json!({ "raw": raw }),
+                        model: &model_used,
+                        latency_ms,
+                        status: "success",
+                        error_message: None,
+                        created_at: chrono::Utc::now().timestamp_millis(),
+                    })?;
+                    issues = novel_canon::merge_issues(issues, llm_issues);
+                }
+                Err(error) => {
+                    tracing::warn!("[Novel] 模型一致性检查失败，仅使用规则检查: {}", error);
+                    issues.push(NovelConsistencyIssue {
+                        level: "info".to_string(),
+                        code: "canon_llm_check_unavailable".to_string(),
+                        message: "模型一致性检查不可用，本次结果仅包含规则检查".to_string(),
+                        details: Some(json!({ "error": error })),
+                        location: None,
+                    });
+                }
+            }
+        }
+
         let score = calculate_score(&issues);
         let now = chrono::Utc::now().timestamp_millis();
         let id = Uuid::new_v4().to_string();
@@ -825,6 +973,203 @@ impl NovelService {
         })
     }
 
+    /// 获取最新的 Canon 快照
+    pub fn get_canon(&self, project_id: &str) -> Result<Option<NovelCanonRecord>, String> {
+        self.find_canon(project_id, i32::MAX)
+    }
+
+    /// 获取只吸收到第 `max_chapter_no` 章（含）为止的最新 Canon 快照
+    fn find_canon(
+        &self,
+        project_id: &str,
+        max_chapter_no: i32,
+    ) -> Result<Option<NovelCanonRecord>, String> {
+        let conn = lock_db(&self.db)?;
+        let result = conn.query_row(
+            "SELECT id, project_id, canon_json, source_chapter_no, version, created_at
+             FROM novel_canon_snapshots
+             WHERE project_id = ?1 AND source_chapter_no <= ?2
+             ORDER BY source_chapter_no DESC, version DESC
+             LIMIT 1",
+            params![project_id, max_chapter_no],
+            row_to_canon,
+        );
+
+        match result {
+            Ok(record) => Ok(Some(record)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(format!("读取 Canon 失败: {e}")),
+        }
+    }
+
+    /// 从设定与已有章节抽取 Canon
+    ///
+    /// 默认增量更新：只抽取尚未合并的章节；`full_rebuild` 时从设定重新开始。
+    pub async fn rebuild_canon(
+        &self,
+        request: NovelRebuildCanonRequest,
+    ) -> Result<NovelCanonRecord, String> {
+        self.ensure_project_exists(&request.project_id)?;
+        if request.full_rebuild.unwrap_or(false) {
+            {
+                let conn = lock_db(&self.db)?;
+                invalidate_canon(&conn, &request.project_id, 0)?;
+            }
+            let settings = self.get_latest_settings(&request.project_id)?;
+            let characters = self.list_characters(&request.project_id)?;
+            let seeded = NovelCanon::seed(settings.as_ref().map(|s| &s.settings_json), &characters);
+            self.save_canon(&request.project_id, &seeded)?;
+        }
+
+        self.refresh_canon(
+            &request.project_id,
+            i32::MAX,
+            true,
+            request.provider.as_deref(),
+            request.model.as_deref(),
+        )
+        .await?;
+
+        self.get_canon(&request.project_id)?
+            .ok_or_else(|| "Canon 构建后读取失败".to_string())
+    }
+
+    /// 将 Canon 更新到 `up_to_chapter`（含）为止
+    ///
+    /// 从不超过 `up_to_chapter` 的最新快照出发，之后章节的内容不会混入；
+    /// 尚无快照时从设定初始化；`extract` 为 false 时不调用模型，只返回已有内容。
+    async fn refresh_canon(
+        &self,
+        project_id: &str,
+        up_to_chapter: i32,
+        extract: bool,
+        provider: Option<&str>,
+        model: Option<&str>,
+    ) -> Result<NovelCanon, String> {
+        let (mut canon, mut dirty) = match self.find_canon(project_id, up_to_chapter)? {
+            Some(record) => (record.canon, false),
+            None => {
+                let settings = self.get_latest_settings(project_id)?;
+                let characters = self.list_characters(project_id)?;
+                (
+                    NovelCanon::seed(settings.as_ref().map(|s| &s.settings_json), &characters),
+                    true,
+                )
+            }
+        };
+
+        if extract {
+            let pending: Vec<NovelChapterRecord> = self
+                .list_chapters(project_id)?
+                .into_iter()
+                .filter(|c| c.chapter_no > canon.source_chapter_no && c.chapter_no <= up_to_chapter)
+                .collect();
+
+            for chapter in pending {
+                match self
+                    .extract_chapter_canon(project_id, &canon, &chapter, provider, model)
+                    .await
+                {
+                    Ok(delta) => {
+                        canon.merge_chapter(chapter.chapter_no, delta);
+                        dirty = true;
+                    }
+                    Err(error) => {
+                        // 保留已成功抽取的章节，下次从失败章节继续
+                        if dirty {
+                            self.save_canon(project_id, &canon)?;
+                        }
+                        return Err(error);
+                    }
+                }
+            }
+        }
+
+        if dirty {
+            self.save_canon(project_id, &canon)?;
+        }
+        Ok(canon)
+    }
+
+    async fn extract_chapter_canon(
+        &self,
+        project_id: &str,
+        canon: &NovelCanon,
+        chapter: &NovelChapterRecord,
+        provider: Option<&str>,
+        model: Option<&str>,
+    ) -> Result<NovelCanon, String> {
+        let prompt = novel_canon::build_extraction_prompt(canon, chapter);
+        let (model_used, raw, latency_ms) = self
+            .call_llm(&prompt, provider, model, Some(0.0), None)
+            .await?;
+        let delta = novel_canon::parse_canon_delta(&raw)
+            .map_err(|e| format!("第 {} 章设定抽取失败: {e}", chapter.chapter_no))?;
+
+        self.record_run(InsertRunParams {
+            run_id: &Uuid::new_v4().to_string(),
+            project_id,
+            mode: "canon_extract",
+            input_snapshot: json!({ "chapter_id": chapter.id, "chapter_no": chapter.chapter_no }),
+            output_snapshot: json!({ "raw": raw, "delta": delta }),
+            model: &model_used,
+            latency_ms,
+            status: "success",
+            error_message: None,
+            created_at: chrono::Utc::now().timestamp_millis(),
+        })?;
+
+        Ok(delta)
+    }
+
+    fn save_canon(&self, project_id: &str, canon: &NovelCanon) -> Result<(), String> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let canon_json =
+            serde_json::to_string(canon).map_err(|e| format!("序列化 Canon 失败: {e}"))?;
+        let mut conn = lock_db(&self.db)?;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("开启事务失败: {e}"))?;
+        let next_version = query_next_version(
+            &tx,
+            "SELECT COALESCE(MAX(version), 0) + 1 FROM novel_canon_snapshots WHERE project_id = ?1",
+            project_id,
+        )?;
+
+        // 同一章节进度只保留最新的快照
+        tx.execute(
+            "DELETE FROM novel_canon_snapshots WHERE project_id = ?1 AND source_chapter_no = ?2",
+            params![project_id, canon.source_chapter_no],
+        )
+        .map_err(|e| format!("清理旧 Canon 快照失败: {e}"))?;
+
+        tx.execute(
+            "INSERT INTO novel_canon_snapshots (id, project_id, canon_json, source_chapter_no, version, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                Uuid::new_v4().to_string(),
+                project_id,
+                canon_json,
+                canon.source_chapter_no,
+                next_version,
+                now
+            ],
+        )
+        .map_err(|e| format!("保存 Canon 失败: {e}"))?;
+
+        tx.commit().map_err(|e| format!("提交事务失败: {e}"))?;
+        Ok(())
+    }
+
+    fn record_run(&self, params_data: InsertRunParams<'_>) -> Result<(), String> {
+        let mut conn = lock_db(&self.db)?;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("开启事务失败: {e}"))?;
+        self.insert_run_with_tx(&tx, params_data)?;
+        tx.commit().map_err(|e| format!("提交事务失败: {e}"))
+    }
+
     pub fn get_project_snapshot(&self, project_id: &str) -> Result<NovelProjectSnapshot, String> {
         let project = self
             .get_project(project_id)?
@@ -872,6 +1217,165 @@ impl NovelService {
         Ok(rows)
     }
 
+    /// 由小说项目构建待导出的稿件
+    ///
+    /// 元数据取自项目与最新设定，作者和封面取自同 ID 工作区的默认人设与图片素材；
+    /// 从 Markdown 包导入的封面与插图一并带上（工作区素材中的封面优先）。
+    pub fn build_manuscript(
+        &self,
+        project_id: &str,
+        author: Option<&str>,
+    ) -> Result<Manuscript, String> {
+        let snapshot = self.get_project_snapshot(project_id)?;
+        let project = &snapshot.project;
+
+        let mut metadata = ManuscriptMetadata::new(project.title.clone());
+        metadata.modified_at = project.updated_at;
+        if Uuid::parse_str(&project.id).is_ok() {
+            metadata.identifier = project.id.clone();
+        }
+        if let Some(settings) = &snapshot.latest_settings {
+            let data = &settings.settings_json.data;
+            metadata.subjects = data.genres.clone();
+            if !data.one_line_pitch.trim().is_empty() {
+                metadata.description = Some(data.one_line_pitch.trim().to_string());
+            }
+        }
+        if metadata.description.is_none() {
+            metadata.description = project
+                .theme
+                .as_deref()
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(str::to_string);
+        }
+        if let Some(author) = author.map(str::trim).filter(|a| !a.is_empty()) {
+            metadata.authors.push(author.to_string());
+        }
+
+        let (persona, materials) = {
+            let conn = lock_db(&self.db)?;
+            let persona = PersonaService::get_default_persona(&conn, project_id)
+                .map_err(|e| format!("读取默认人设失败: {e}"))?;
+            let materials = MaterialService::list_materials(&conn, project_id, None)
+                .map_err(|e| format!("读取项目素材失败: {e}"))?;
+            (persona, materials)
+        };
+        if let Some(persona) = &persona {
+            metadata.apply_persona(persona);
+        }
+        let (imported_cover, imported_assets) = load_imported_assets(project)?;
+
+        Ok(Manuscript {
+            metadata,
+            chapters: snapshot
+                .chapters
+                .iter()
+                .map(|chapter| ManuscriptChapter {
+                    title: chapter.title.clone(),
+                    body: chapter.content.clone(),
+                })
+                .collect(),
+            cover: manuscript_export::cover_from_materials(&materials).or(imported_cover),
+            assets: imported_assets,
+        })
+    }
+
+    /// 导出小说为 EPUB / DOCX / Markdown 包
+    pub fn export_manuscript(
+        &self,
+        request: NovelExportManuscriptRequest,
+    ) -> Result<NovelExportManuscriptResult, String> {
+        let manuscript = self.build_manuscript(&request.project_id, request.author.as_deref())?;
+        let options = request.options.unwrap_or_default();
+        let size_bytes = manuscript_export::export_to_file(
+            &manuscript,
+            request.format,
+            &options,
+            std::path::Path::new(&request.output_path),
+        )
+        .map_err(|e| format!("导出稿件失败: {e}"))?;
+
+        Ok(NovelExportManuscriptResult {
+            output_path: request.output_path,
+            format: request.format,
+            chapter_count: manuscript.chapters.len(),
+            size_bytes,
+        })
+    }
+
+    /// 从 Markdown 包创建新的小说项目
+    pub fn import_markdown_bundle(
+        &self,
+        request: NovelImportMarkdownBundleRequest,
+    ) -> Result<NovelProject, String> {
+        let manuscript =
+            manuscript_export::import_markdown_bundle_file(std::path::Path::new(&request.path))
+                .map_err(|e| format!("读取 Markdown 包失败: {e}"))?;
+        let metadata = &manuscript.metadata;
+        let title = request
+            .title
+            .filter(|t| !t.trim().is_empty())
+            .unwrap_or_else(|| metadata.title.clone());
+
+        let project_id = Uuid::new_v4().to_string();
+        if manuscript.cover.is_some() || !manuscript.assets.is_empty() {
+            manuscript_export::save_assets(&manuscript, &imported_assets_dir(&project_id)?)
+                .map_err(|e| format!("保存导入的资源失败: {e}"))?;
+        }
+
+        // 资源数据不入库，只记录路径与类型，导出时从资源目录读回
+        let project = self.create_project(NovelCreateProjectRequest {
+            id: Some(project_id),
+            title,
+            theme: metadata.description.clone(),
+            target_words: None,
+            metadata_json: Some(json!({
+                "imported_from": "markdown_bundle",
+                "authors": metadata.authors,
+                "identifier": metadata.identifier,
+                IMPORTED_ASSETS_KEY: {
+                    "cover": manuscript.cover,
+                    "assets": manuscript.assets,
+                },
+            })),
+            settings_json: Some(json!({
+                "genres": metadata.subjects,
+                "oneLinePitch": metadata.description.clone().unwrap_or_default(),
+            })),
+        })?;
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut conn = lock_db(&self.db)?;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("开启事务失败: {e}"))?;
+        for (index, chapter) in manuscript.chapters.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO novel_chapters
+                 (id, project_id, chapter_no, title, content, word_count, status, quality_score, metadata_json, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'draft', NULL, NULL, ?7, ?8)",
+                params![
+                    Uuid::new_v4().to_string(),
+                    &project.id,
+                    index as i32 + 1,
+                    &chapter.title,
+                    &chapter.body,
+                    count_words(&chapter.body),
+                    now,
+                    now
+                ],
+            )
+            .map_err(|e| format!("写入章节失败: {e}"))?;
+        }
+        self.recalculate_project_word_count_with_tx(&tx, &project.id, now)?;
+        tx.commit().map_err(|e| format!("提交事务失败: {e}"))?;
+        drop(conn);
+
+        self.get_project(&project.id)?
+            .ok_or_else(|| "项目导入成功但读取失败".to_string())
+    }
+
     fn list_characters(&self, project_id: &str) -> Result<Vec<NovelCharacterRecord>, String> {
         let conn = lock_db(&self.db)?;
         let mut stmt = conn
@@ -1119,6 +1623,8 @@ impl NovelService {
             )
             .map_err(|e| format!("写入角色失败: {e}"))?;
         }
+        // 角色卡参与 Canon 初始化，角色变更后所有快照都已过期
+        invalidate_canon(&tx, &request.project_id, 0)?;
 
         self.insert_run_with_tx(
             &tx,
@@ -1226,6 +1732,7 @@ impl NovelService {
             self.fetch_chapter_with_tx(&tx, &chapter_id)?
         };
 
+        invalidate_canon(&tx, project_id, chapter_no)?;
         self.recalculate_project_word_count_with_tx(&tx, project_id, now)?;
         self.insert_run_with_tx(
             &tx,
@@ -1291,6 +1798,7 @@ impl NovelService {
         .map_err(|e| format!("更新章节失败: {e}"))?;
 
         let chapter = self.fetch_chapter_with_tx(&tx, &source.id)?;
+        invalidate_canon(&tx, project_id, source.chapter_no)?;
         self.recalculate_project_word_count_with_tx(&tx, project_id, now)?;
         self.insert_run_with_tx(
             &tx,
@@ -1557,6 +2065,72 @@ fn row_to_run(row: &rusqlite::Row<'_>) -> Result<NovelGenerationRun, rusqlite::E
     })
 }
 
+fn row_to_canon(row: &rusqlite::Row<'_>) -> Result<NovelCanonRecord, rusqlite::Error> {
+    let canon_json: String = row.get(2)?;
+    let canon = serde_json::from_str(&canon_json).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+    Ok(NovelCanonRecord {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        canon,
+        source_chapter_no: row.get(3)?,
+        version: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+/// 章节被改写后，丢弃已吸收该章及之后章节的 Canon 快照
+///
+/// 下次一致性检查或重建时会从更早的快照出发，重新抽取这些章节。
+fn invalidate_canon(
+    conn: &Connection,
+    project_id: &str,
+    from_chapter_no: i32,
+) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM novel_canon_snapshots WHERE project_id = ?1 AND source_chapter_no >= ?2",
+        params![project_id, from_chapter_no],
+    )
+    .map_err(|e| format!("清理过期 Canon 失败: {e}"))?;
+    Ok(())
+}
+
+/// 导入资源的存储目录：`~/.proxycast/novel_assets/<project_id>`
+fn imported_assets_dir(project_id: &str) -> Result<std::path::PathBuf, String> {
+    let home = dirs::home_dir().ok_or_else(|| "无法获取主目录".to_string())?;
+    Ok(home
+        .join(".proxycast")
+        .join("novel_assets")
+        .join(project_id))
+}
+
+/// 读回项目导入时保存的封面与资源
+fn load_imported_assets(
+    project: &NovelProject,
+) -> Result<(Option<ManuscriptAsset>, Vec<ManuscriptAsset>), String> {
+    let Some(entry) = project
+        .metadata_json
+        .as_ref()
+        .and_then(|m| m.get(IMPORTED_ASSETS_KEY))
+    else {
+        return Ok((None, Vec::new()));
+    };
+    let cover: Option<ManuscriptAsset> = entry
+        .get("cover")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok());
+    let assets: Vec<ManuscriptAsset> = entry
+        .get("assets")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+
+    let dir = imported_assets_dir(&project.id)?;
+    let cover = manuscript_export::load_assets(&dir, cover.into_iter().collect()).pop();
+    Ok((cover, manuscript_export::load_assets(&dir, assets)))
+}
+
 fn parse_character_cards(raw: &str) -> Vec<Value> {
     if let Ok(value) = serde_json::from_str::<Value>(raw) {
         if let Some(arr) = value.as_array() {
@@ -2025,6 +2599,7 @@ fn evaluate_consistency(
             code: "chapter_too_short".to_string(),
             message: "章节长度偏短，建议扩展冲突与场景细节".to_string(),
             details: Some(json!({ "current": char_len, "min": 500 })),
+            location: None,
         });
     }
 
@@ -2041,6 +2616,7 @@ fn evaluate_consistency(
                     "current": char_len,
                     "range": { "min": lower, "max": upper }
                 })),
+                location: None,
             });
         }
     }
@@ -2053,6 +2629,7 @@ fn evaluate_consistency(
                 code: "taboo_violation".to_string(),
                 message: format!("命中禁忌词: {}", taboo),
                 details: None,
+                location: novel_canon::locate_quote(&chapter.content, &taboo),
             });
         }
     }
@@ -2080,6 +2657,7 @@ fn evaluate_consistency(
                 code: "main_character_missing".to_string(),
                 message: "本章未出现主要角色姓名，可能存在叙事脱节".to_string(),
                 details: Some(json!({ "mainCharacters": main_names })),
+                location: None,
             });
         }
     }
@@ -2405,3 +2983,78 @@ fn value_as_f64(value: Option<&Value>, fallback: f64) -> f64 {
         .or_else(|| value.as_u64().map(|v| v as f64))
         .unwrap_or(fallback)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::schema::create_tables;
+    use std::sync::Mutex;
+
+    fn setup_service() -> NovelService {
+        let conn = Connection::open_in_memory().expect("创建内存数据库失败");
+        create_tables(&conn).expect("创建数据表失败");
+        NovelService::new(Arc::new(Mutex::new(conn)))
+    }
+
+    fn insert_chapter(service: &NovelService, project_id: &str, content: &str) -> String {
+        let id = Uuid::new_v4().to_string();
+        let conn = lock_db(&service.db).unwrap();
+        conn.execute(
+            "INSERT INTO novel_chapters
+             (id, project_id, chapter_no, title, content, word_count, status, quality_score, metadata_json, created_at, updated_at)
+             VALUES (?1, ?2, 1, '第一章', ?3, 0, 'draft', NULL, NULL, 0, 0)",
+            params![&id, project_id, content],
+        )
+        .unwrap();
+        id
+    }
+
+    fn check_request(project_id: &str, chapter_id: &str) -> NovelCheckConsistencyRequest {
+        NovelCheckConsistencyRequest {
+            project_id: project_id.to_string(),
+            chapter_id: chapter_id.to_string(),
+            use_llm: Some(false),
+            provider: None,
+            model: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_updated_settings_reach_canon_on_next_check() {
+        let service = setup_service();
+        let project = service
+            .create_project(NovelCreateProjectRequest {
+                id: None,
+                title: "测试".to_string(),
+                theme: None,
+                target_words: None,
+                metadata_json: None,
+                settings_json: Some(json!({ "mainCharacter": { "name": "林远" } })),
+            })
+            .unwrap();
+        let chapter_id = insert_chapter(&service, &project.id, "林远推开了山门。");
+
+        service
+            .check_consistency(check_request(&project.id, &chapter_id))
+            .await
+            .unwrap();
+        let canon = service.get_canon(&project.id).unwrap().unwrap().canon;
+        assert!(canon.find_character("林远").is_some());
+
+        service
+            .update_settings(NovelUpdateSettingsRequest {
+                project_id: project.id.clone(),
+                settings_json: json!({ "mainCharacter": { "name": "沈青" } }),
+            })
+            .unwrap();
+        assert!(service.get_canon(&project.id).unwrap().is_none());
+
+        service
+            .check_consistency(check_request(&project.id, &chapter_id))
+            .await
+            .unwrap();
+        let canon = service.get_canon(&project.id).unwrap().unwrap().canon;
+        assert!(canon.find_character("沈青").is_some());
+        assert!(canon.find_character("林远").is_none());
+    }
+}
@@ -3,11 +3,14 @@
 //! 提供 novel tauri commands 的主题化实现入口。
 
 use crate::database::DbConnection;
+use crate::services::novel_canon::NovelCanonRecord;
 use crate::services::novel_service::{
-    NovelCheckConsistencyRequest, NovelCreateProjectRequest, NovelGenerateChapterRequest,
-    NovelGenerateRequest, NovelGenerateResult, NovelGenerationRun, NovelListRunsRequest,
-    NovelPolishChapterRequest, NovelProject, NovelProjectSnapshot, NovelRewriteChapterRequest,
-    NovelSettingsRecord, NovelUpdateSettingsRequest,
+    NovelCheckConsistencyRequest, NovelCreateProjectRequest, NovelExportManuscriptRequest,
+    NovelExportManuscriptResult, NovelGenerateChapterRequest, NovelGenerateRequest,
+    NovelGenerateResult, NovelGenerationRun, NovelImportMarkdownBundleRequest,
+    NovelListRunsRequest, NovelPolishChapterRequest, NovelProject, NovelProjectSnapshot,
+    NovelRebuildCanonRequest, NovelRewriteChapterRequest, NovelSettingsRecord,
+    NovelUpdateSettingsRequest,
 };
 use tauri::State;
 
@@ -75,7 +78,7 @@ pub async fn novel_check_consistency(
     db: State<'_, DbConnection>,
     request: NovelCheckConsistencyRequest,
 ) -> Result<crate::services::novel_service::NovelConsistencyCheck, String> {
-    service(&db).check_consistency(request)
+    service(&db).check_consistency(request).await
 }
 
 pub async fn novel_get_project_snapshot(
@@ -91,3 +94,31 @@ pub async fn novel_list_runs(
 ) -> Result<Vec<NovelGenerationRun>, String> {
     service(&db).list_runs(request)
 }
+
+pub async fn novel_get_canon(
+    db: State<'_, DbConnection>,
+    project_id: String,
+) -> Result<Option<NovelCanonRecord>, String> {
+    service(&db).get_canon(&project_id)
+}
+
+pub async fn novel_rebuild_canon(
+    db: State<'_, DbConnection>,
+    request: NovelRebuildCanonRequest,
+) -> Result<NovelCanonRecord, String> {
+    service(&db).rebuild_canon(request).await
+}
+
+pub async fn novel_export_manuscript(
+    db: State<'_, DbConnection>,
+    request: NovelExportManuscriptRequest,
+) -> Result<NovelExportManuscriptResult, String> {
+    service(&db).export_manuscript(request)
+}
+
+pub async fn novel_import_markdown_bundle(
+    db: State<'_, DbConnection>,
+    request: NovelImportMarkdownBundleRequest,
+) -> Result<NovelProject, String> {
+    service(&db).import_markdown_bundle(request)
+}
@@ -4,12 +4,18 @@
 
 use proxycast_core::config::{VoiceInputConfig, VoiceInstruction};
 use proxycast_services::voice_command_service;
-use tauri::{command, AppHandle};
+use tauri::{command, AppHandle, Emitter};
+use voice_core::VadConfig;
 
 use super::config;
-use super::recording_service::{AudioDeviceInfo, RecordingServiceState};
+use super::recording_service::{
+    AudioDeviceInfo, RecordingServiceState, StreamingTranscriptionState,
+};
 use tauri::State;
 
+/// 流式识别中间结果事件
+const STREAMING_TRANSCRIPT_EVENT: &str = "voice-streaming-transcript";
+
 fn normalize_shortcut(value: Option<String>) -> Option<String> {
     value.and_then(|raw| {
         let trimmed = raw.trim();
@@ -218,11 +224,85 @@ pub async fn stop_recording(
     })
 }
 
+/// 开始录音并边录边识别
+///
+/// 录音启用 VAD，检测到的语音实时送入流式 ASR，中间结果通过
+/// `voice-streaming-transcript` 事件推送；调用 `stop_streaming_transcription` 获取最终结果。
+#[command]
+pub async fn start_streaming_transcription(
+    app: AppHandle,
+    recording_service: State<'_, RecordingServiceState>,
+    streaming: State<'_, StreamingTranscriptionState>,
+    device_id: Option<String>,
+    credential_id: Option<String>,
+) -> Result<(), String> {
+    let (vad_events, sample_rate) = {
+        let mut service = recording_service.0.lock();
+        let events = service.start_with_vad(device_id, VadConfig::default())?;
+        (events, service.sample_rate())
+    };
+    tracing::info!("[录音命令] 开始流式识别，采样率: {}", sample_rate);
+
+    let task = tokio::spawn(async move {
+        voice_command_service::stream_transcription(
+            vad_events,
+            sample_rate,
+            credential_id.as_deref(),
+            |transcript| {
+                let _ = app.emit(STREAMING_TRANSCRIPT_EVENT, transcript);
+            },
+        )
+        .await
+    });
+    if let Some(previous) = streaming.0.lock().await.replace(task) {
+        previous.abort();
+    }
+    Ok(())
+}
+
+/// 停止边录边识别并返回最终结果
+///
+/// 服务不支持流式识别或流式识别失败时，改用录音中检测到的语音批量识别。
+#[command]
+pub async fn stop_streaming_transcription(
+    recording_service: State<'_, RecordingServiceState>,
+    streaming: State<'_, StreamingTranscriptionState>,
+    credential_id: Option<String>,
+) -> Result<TranscribeResult, String> {
+    // 停止录音会关闭 VAD 事件通道，识别任务随之结束送入音频
+    let audio = recording_service.0.lock().stop();
+
+    let streamed = match streaming.0.lock().await.take() {
+        Some(task) => task
+            .await
+            .map_err(|e| format!("流式识别任务失败: {e}"))
+            .and_then(|result| result),
+        None => Ok(None),
+    };
+    match streamed {
+        Ok(Some(result)) if !result.text.trim().is_empty() => return Ok(result),
+        Ok(_) => {}
+        Err(e) => tracing::warn!("[录音命令] {}，改用批量识别", e),
+    }
+
+    let audio = audio?;
+    voice_command_service::transcribe_audio(
+        &audio.to_pcm16le_bytes(),
+        audio.sample_rate,
+        credential_id.as_deref(),
+    )
+    .await
+}
+
 /// 取消录音
 #[command]
 pub async fn cancel_recording(
     recording_service: State<'_, RecordingServiceState>,
+    streaming: State<'_, StreamingTranscriptionState>,
 ) -> Result<(), String> {
+    if let Some(task) = streaming.0.lock().await.take() {
+        task.abort();
+    }
     match recording_service.0.try_lock() {
         Some(mut service) => {
             service.cancel();
@@ -5,5 +5,5 @@
 
 pub use proxycast_services::voice_recording_service::{
     create_recording_service_state, list_audio_devices, AudioDeviceInfo, RecordingCommand,
-    RecordingResponse, RecordingService, RecordingServiceState,
+    RecordingResponse, RecordingService, RecordingServiceState, StreamingTranscriptionState,
 };
@@ -54,6 +54,7 @@ export function AddAsrCredentialModal({
   // 百度配置
   const [baiduApiKey, setBaiduApiKey] = useState("");
   const [baiduSecretKey, setBaiduSecretKey] = useState("");
+  const [baiduAppId, setBaiduAppId] = useState("");
 
   // OpenAI 配置
   const [openaiApiKey, setOpenaiApiKey] = useState("");
@@ -69,6 +70,7 @@ export function AddAsrCredentialModal({
     setXunfeiApiSecret("");
     setBaiduApiKey("");
     setBaiduSecretKey("");
+    setBaiduAppId("");
     setOpenaiApiKey("");
     setOpenaiBaseUrl("");
     setError(null);
@@ -106,7 +108,11 @@ export function AddAsrCredentialModal({
             : undefined,
         baidu_config:
           selectedProvider === "baidu"
-            ? { api_key: baiduApiKey, secret_key: baiduSecretKey }
+            ? {
+                api_key: baiduApiKey,
+                secret_key: baiduSecretKey,
+                app_id: baiduAppId ? Number(baiduAppId) : undefined,
+              }
             : undefined,
         openai_config:
           selectedProvider === "openai"
@@ -311,6 +317,20 @@ export function AddAsrCredentialModal({
                     className="w-full rounded-lg border bg-background px-3 py-2"
                   />
                 </div>
+                <div>
+                  <label className="block text-sm font-medium mb-1">
+                    App ID（可选，实时识别需要）
+                  </label>
+                  <input
+                    type="text"
+                    inputMode="numeric"
+                    value={baiduAppId}
+                    onChange={(e) =>
+                      setBaiduAppId(e.target.value.replace(/\D/g, ""))
+                    }
+                    className="w-full rounded-lg border bg-background px-3 py-2"
+                  />
+                </div>
               </>
             )}
 
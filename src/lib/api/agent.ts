@@ -946,6 +946,38 @@ export async function submitAsterElicitationResponse(
   });
 }
 
+/** 重命名预览中的单处编辑（行列从 0 开始） */
+export interface LspRenameEdit {
+  start_line: number;
+  start_character: number;
+  end_line: number;
+  end_character: number;
+  new_text: string;
+}
+
+/** 重命名预览结果 */
+export interface LspRenamePreview {
+  files: { path: string; edits: LspRenameEdit[] }[];
+  total_edits: number;
+}
+
+/**
+ * 通过语言服务器预览符号重命名（不修改文件）
+ */
+export async function previewLspRename(
+  path: string,
+  line: number,
+  character: number,
+  newName: string,
+): Promise<LspRenamePreview> {
+  return await safeInvoke("aster_lsp_rename_preview", {
+    path,
+    line,
+    character,
+    newName,
+  });
+}
+
 // ============================================================
 // Terminal Tool API (终端命令执行)
 // ============================================================
@@ -31,6 +31,8 @@ export interface XunfeiConfig {
 export interface BaiduConfig {
   api_key: string;
   secret_key: string;
+  /** App ID（实时流式识别需要） */
+  app_id?: number;
 }
 
 /** OpenAI ASR 配置 */
@@ -280,6 +282,39 @@ export async function cancelRecording(): Promise<void> {
   return invoke("cancel_recording");
 }
 
+/** 流式识别中间结果（`voice-streaming-transcript` 事件） */
+export interface StreamingTranscript {
+  /** 当前完整文本（已确认部分 + 识别中的部分） */
+  text: string;
+  /** 是否为最终结果 */
+  is_final: boolean;
+}
+
+/** 流式识别中间结果事件名 */
+export const STREAMING_TRANSCRIPT_EVENT = "voice-streaming-transcript";
+
+/**
+ * 开始录音并边录边识别
+ *
+ * 中间结果通过 `voice-streaming-transcript` 事件推送，
+ * 不支持流式识别的服务会在停止时批量识别。
+ */
+export async function startStreamingTranscription(
+  deviceId?: string,
+  credentialId?: string,
+): Promise<void> {
+  return invoke("start_streaming_transcription", { deviceId, credentialId });
+}
+
+/** 停止边录边识别并返回最终结果 */
+export async function stopStreamingTranscription(
+  credentialId?: string,
+): Promise<TranscribeResult> {
+  return invoke<TranscribeResult>("stop_streaming_transcription", {
+    credentialId,
+  });
+}
+
 /** 获取录音状态 */
 export async function getRecordingStatus(): Promise<RecordingStatus> {
   return invoke<RecordingStatus>("get_recording_status");
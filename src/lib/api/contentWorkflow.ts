@@ -0,0 +1,118 @@
+/**
+ * 内容创作工作流 API
+ *
+ * 对应 src-tauri/src/commands/content_workflow_cmd.rs
+ */
+
+import { safeInvoke } from "@/lib/dev-bridge";
+
+// ============================================================================
+// 类型定义
+// ============================================================================
+
+/** 创作主题 */
+export type WorkflowThemeType =
+  | "general"
+  | "knowledge"
+  | "planning"
+  | "social-media"
+  | "poster"
+  | "document"
+  | "paper"
+  | "novel"
+  | "script"
+  | "music"
+  | "video";
+
+/** 创作模式 */
+export type WorkflowCreationMode = "guided" | "fast" | "hybrid" | "framework";
+
+/** 步骤状态 */
+export type WorkflowStepStatus =
+  | "pending"
+  | "active"
+  | "completed"
+  | "skipped"
+  | "error";
+
+/** 步骤结果 */
+export interface WorkflowStepResult {
+  user_input?: Record<string, unknown>;
+  ai_output?: unknown;
+  artifacts?: Array<Record<string, unknown>>;
+}
+
+/** 工作流步骤（定义字段与运行时状态平铺） */
+export interface WorkflowStep {
+  id: string;
+  type: string;
+  title: string;
+  description?: string;
+  form?: Record<string, unknown>;
+  ai_task?: { task_type: string; prompt?: string; streaming: boolean };
+  behavior: { skippable: boolean; redoable: boolean; auto_advance: boolean };
+  status: WorkflowStepStatus;
+  result?: WorkflowStepResult;
+}
+
+/** 工作流状态 */
+export interface ContentWorkflow {
+  id: string;
+  theme: WorkflowThemeType;
+  mode: WorkflowCreationMode;
+  steps: WorkflowStep[];
+  current_step_index: number;
+  created_at: number;
+  updated_at: number;
+}
+
+// ============================================================================
+// API 函数
+// ============================================================================
+
+export const contentWorkflowApi = {
+  /** 列出已恢复/活跃的工作流 */
+  list: (): Promise<ContentWorkflow[]> => safeInvoke("list_content_workflows"),
+
+  /** 获取单个工作流 */
+  get: (workflowId: string): Promise<ContentWorkflow | null> =>
+    safeInvoke("get_content_workflow", { workflowId }),
+
+  /** 创建工作流 */
+  create: (
+    theme: WorkflowThemeType,
+    mode: WorkflowCreationMode,
+  ): Promise<ContentWorkflow> =>
+    safeInvoke("create_content_workflow", { theme, mode }),
+
+  /** 完成当前步骤并进入下一步 */
+  completeStep: (
+    workflowId: string,
+    result: WorkflowStepResult,
+  ): Promise<ContentWorkflow> =>
+    safeInvoke("complete_content_workflow_step", { workflowId, result }),
+
+  /** 跳过当前步骤 */
+  skipStep: (workflowId: string): Promise<ContentWorkflow> =>
+    safeInvoke("skip_content_workflow_step", { workflowId }),
+
+  /** 使用新参数单独重跑指定步骤 */
+  rerunStep: (
+    workflowId: string,
+    stepIndex: number,
+    parameters?: Record<string, unknown>,
+  ): Promise<ContentWorkflow> =>
+    safeInvoke("rerun_content_workflow_step", {
+      workflowId,
+      stepIndex,
+      parameters,
+    }),
+
+  /** 导出工作流运行记录到 JSON 文件 */
+  exportToFile: (workflowId: string, path: string): Promise<void> =>
+    safeInvoke("export_content_workflow", { workflowId, path }),
+
+  /** 从 JSON 文件导入工作流 */
+  importFromFile: (path: string): Promise<ContentWorkflow> =>
+    safeInvoke("import_content_workflow", { path }),
+};
@@ -80,11 +80,57 @@ export interface NovelGenerationRun {
   created_at: number;
 }
 
+/** 问题在章节正文中的字符偏移（左闭右开） */
+export interface NovelIssueLocation {
+  start: number;
+  end: number;
+}
+
 export interface NovelConsistencyIssue {
   level: "info" | "warn" | "error" | string;
   code: string;
   message: string;
   details?: Record<string, unknown>;
+  location?: NovelIssueLocation;
+}
+
+export type NovelCanonCharacterStatus = "alive" | "dead" | "missing" | "unknown";
+
+export interface NovelCanonCharacter {
+  name: string;
+  aliases: string[];
+  attributes: Record<string, string>;
+  status: NovelCanonCharacterStatus;
+  status_since_chapter?: number;
+  first_chapter?: number;
+}
+
+export interface NovelCanon {
+  characters: NovelCanonCharacter[];
+  relationships: Array<{
+    from: string;
+    to: string;
+    kind: string;
+    since_chapter?: number;
+  }>;
+  locations: Array<{ name: string; description: string }>;
+  timeline: Array<{
+    chapter_no: number;
+    description: string;
+    time_label?: string;
+    characters: string[];
+  }>;
+  world_rules: Array<{ rule: string; source: string }>;
+  source_chapter_no: number;
+}
+
+export interface NovelCanonRecord {
+  id: string;
+  project_id: string;
+  canon: NovelCanon;
+  source_chapter_no: number;
+  version: number;
+  created_at: number;
 }
 
 export interface NovelConsistencyCheck {
@@ -152,6 +198,44 @@ export interface NovelPolishChapterRequest extends NovelGenerateRequest {
 export interface NovelCheckConsistencyRequest {
   project_id: string;
   chapter_id: string;
+  /** 是否调用模型做 Canon 冲突检查，默认 true */
+  use_llm?: boolean;
+  provider?: string;
+  model?: string;
+}
+
+export interface NovelRebuildCanonRequest {
+  project_id: string;
+  full_rebuild?: boolean;
+  provider?: string;
+  model?: string;
+}
+
+export type ManuscriptFormat = "epub" | "docx" | "markdown_bundle";
+
+export interface ManuscriptExportOptions {
+  include_title_page?: boolean;
+  include_toc?: boolean;
+}
+
+export interface NovelExportManuscriptRequest {
+  project_id: string;
+  format: ManuscriptFormat;
+  output_path: string;
+  options?: ManuscriptExportOptions;
+  author?: string;
+}
+
+export interface NovelExportManuscriptResult {
+  output_path: string;
+  format: ManuscriptFormat;
+  chapter_count: number;
+  size_bytes: number;
+}
+
+export interface NovelImportMarkdownBundleRequest {
+  path: string;
+  title?: string;
 }
 
 export interface NovelListRunsRequest {
@@ -236,3 +320,27 @@ export async function listNovelRuns(
 ): Promise<NovelGenerationRun[]> {
   return invoke("novel_list_runs", { request });
 }
+
+export async function getNovelCanon(
+  projectId: string,
+): Promise<NovelCanonRecord | null> {
+  return invoke("novel_get_canon", { projectId });
+}
+
+export async function rebuildNovelCanon(
+  request: NovelRebuildCanonRequest,
+): Promise<NovelCanonRecord> {
+  return invoke("novel_rebuild_canon", { request });
+}
+
+export async function exportNovelManuscript(
+  request: NovelExportManuscriptRequest,
+): Promise<NovelExportManuscriptResult> {
+  return invoke("novel_export_manuscript", { request });
+}
+
+export async function importNovelMarkdownBundle(
+  request: NovelImportMarkdownBundleRequest,
+): Promise<NovelProject> {
+  return invoke("novel_import_markdown_bundle", { request });
+}
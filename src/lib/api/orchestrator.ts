@@ -50,6 +50,34 @@ export interface AvailableModel {
   is_healthy: boolean;
   /** 当前负载 (0-100) */
   current_load?: number;
+  /** 实测性能统计 */
+  performance?: ModelPerformance;
+}
+
+/** 模型实测性能统计 */
+export interface ModelPerformance {
+  /** 累计样本数 */
+  sample_count: number;
+  /** 首 Token 延迟 EWMA（毫秒） */
+  ewma_ttft_ms?: number;
+  /** 输出速度 EWMA（tokens/s） */
+  ewma_tokens_per_sec?: number;
+  /** 非流式请求耗时 EWMA（毫秒） */
+  ewma_latency_ms: number;
+  /** 最近非流式请求的 P95 延迟（毫秒） */
+  p95_latency_ms: number;
+  /** 最近流式请求的 P95 首 Token 延迟（毫秒） */
+  p95_ttft_ms: number;
+  /** 最近请求的错误率 (0-1) */
+  error_rate: number;
+}
+
+/** 凭证/模型的实测性能统计 */
+export interface ModelPerformanceEntry extends ModelPerformance {
+  /** 凭证 ID */
+  credential_id: string;
+  /** 模型 ID */
+  model: string;
 }
 
 /** 模型池统计 */
@@ -192,6 +220,14 @@ export const orchestratorApi = {
   /** 获取所有可用模型 */
   getAllModels: (): Promise<AvailableModel[]> => safeInvoke("get_all_models"),
 
+  /** 获取各凭证/模型的实测性能统计 */
+  getPerformanceStats: (): Promise<ModelPerformanceEntry[]> =>
+    safeInvoke("get_model_performance_stats"),
+
+  /** 清除实测性能统计 */
+  resetPerformanceStats: (): Promise<void> =>
+    safeInvoke("reset_model_performance_stats"),
+
   // ==================== 凭证管理 ====================
 
   /** 更新凭证列表 */
@@ -236,6 +236,29 @@ export interface ListContentQuery {
   limit?: number;
 }
 
+/** 稿件导出格式 */
+export type ManuscriptFormat = "epub" | "docx" | "markdown_bundle";
+
+/** 稿件导出请求 */
+export interface ExportManuscriptRequest {
+  project_id: string;
+  format: ManuscriptFormat;
+  output_path: string;
+  options?: {
+    include_title_page?: boolean;
+    include_toc?: boolean;
+  };
+  /** 仅导出指定状态的内容 */
+  status?: ContentStatus;
+}
+
+/** 稿件导出结果 */
+export interface ExportManuscriptResult {
+  output_path: string;
+  chapter_count: number;
+  size_bytes: number;
+}
+
 // ==================== 项目 API ====================
 
 /** 创建项目 */
@@ -357,6 +380,13 @@ export async function getContentStats(
   return invoke("content_stats", { projectId });
 }
 
+/** 导出项目内容为 EPUB / DOCX / Markdown 包 */
+export async function exportContentManuscript(
+  request: ExportManuscriptRequest,
+): Promise<ExportManuscriptResult> {
+  return invoke("content_export_manuscript", { request });
+}
+
 // ==================== 辅助函数 ====================
 
 /** 规范化项目对象字段 */
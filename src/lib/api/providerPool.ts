@@ -172,6 +172,41 @@ export interface TokenCacheStatus {
   last_refresh_error?: string;
 }
 
+// 后台 Token 预刷新状态
+export interface TokenRefreshStatus {
+  uuid: string;
+  name?: string;
+  provider_type: string;
+  expires_at?: string;
+  next_refresh_at?: string;
+  consecutive_failures: number;
+  needs_relogin: boolean;
+  last_error?: string;
+}
+
+// 后台 Token 预刷新事件（"token-refresh"）
+export type TokenRefreshEvent =
+  | {
+      type: "refreshed";
+      uuid: string;
+      provider_type: string;
+      expires_at?: string;
+    }
+  | {
+      type: "failed";
+      uuid: string;
+      provider_type: string;
+      error: string;
+      consecutive_failures: number;
+      retry_at: string;
+    }
+  | {
+      type: "reauth_required";
+      uuid: string;
+      provider_type: string;
+      error: string;
+    };
+
 // Request types
 export interface AddCredentialRequest {
   provider_type: string;
@@ -499,6 +534,11 @@ export const providerPoolApi = {
     return safeInvoke("get_pool_credential_oauth_status", { uuid });
   },
 
+  // 获取后台 Token 预刷新状态
+  async getTokenRefreshStatus(): Promise<TokenRefreshStatus[]> {
+    return safeInvoke("get_token_refresh_status");
+  },
+
   // Migration API
   async migratePrivateConfig(config: unknown): Promise<MigrationResult> {
     return safeInvoke("migrate_private_config_to_pool", { config });
@@ -22,6 +22,53 @@ export interface SwitchLogEntry {
   timestamp: string;
 }
 
+// Circuit breaker
+export type CircuitState = "closed" | "open" | "half_open";
+
+export interface CircuitKey {
+  scope: "credential" | "provider";
+  id: string;
+}
+
+export interface CircuitStatus {
+  key: CircuitKey;
+  state: CircuitState;
+  request_count: number;
+  failure_rate: number;
+  retry_after_ms: number | null;
+}
+
+export interface CircuitTransition {
+  key: CircuitKey;
+  from: CircuitState;
+  to: CircuitState;
+  reason: string;
+  timestamp: string;
+}
+
+export interface CircuitBreakerStatus {
+  running: boolean;
+  enabled: boolean;
+  circuits: CircuitStatus[];
+  recent_transitions: CircuitTransition[];
+}
+
+export type RequestPriority = "interactive" | "batch";
+
+export interface ConcurrencyMetrics {
+  enabled: boolean;
+  in_flight: number;
+  queue_depth: number;
+  queue_depth_by_priority: Partial<Record<RequestPriority, number>>;
+  queue_depth_by_credential: Record<string, number>;
+  in_flight_by_credential: Record<string, number>;
+  in_flight_by_provider: Record<string, number>;
+  total_queued: number;
+  total_timeouts: number;
+  total_rejected: number;
+  last_wait_ms: number;
+}
+
 export const resilienceApi = {
   // Retry config
   async getRetryConfig(): Promise<RetryConfig> {
@@ -49,4 +96,18 @@ export const resilienceApi = {
   async clearSwitchLog(): Promise<void> {
     return safeInvoke("clear_switch_log");
   },
+
+  // Circuit breaker
+  async getCircuitBreakerStatus(): Promise<CircuitBreakerStatus> {
+    return safeInvoke("get_circuit_breaker_status");
+  },
+
+  async resetCircuitBreaker(key?: CircuitKey): Promise<void> {
+    return safeInvoke("reset_circuit_breaker", { key: key ?? null });
+  },
+
+  // Concurrency limits
+  async getConcurrencyMetrics(): Promise<ConcurrencyMetrics> {
+    return safeInvoke("get_concurrency_metrics");
+  },
 };
@@ -1,4 +1,5 @@
 import { safeInvoke } from "@/lib/dev-bridge";
+import type { CircuitTransition } from "./resilience";
 
 // ========== 类型定义 ==========
 
@@ -145,6 +146,12 @@ export async function clearRequestLogs(): Promise<void> {
   return safeInvoke("clear_request_logs");
 }
 
+export async function getCircuitTransitions(
+  limit?: number,
+): Promise<CircuitTransition[]> {
+  return safeInvoke("get_circuit_transitions", { limit });
+}
+
 // ========== 统计 API ==========
 
 export async function getStatsSummary(
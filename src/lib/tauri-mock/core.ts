@@ -414,6 +414,7 @@ const defaultMocks: Record<string, any> = {
   start_kiro_social_auth_callback_server: () => ({ success: true }),
   refresh_pool_credential_token: () => ({ success: true }),
   get_pool_credential_oauth_status: () => ({ status: "unknown" }),
+  get_token_refresh_status: () => [],
   migrate_private_config_to_pool: () => ({ success: true }),
   get_credential_health: () => ({ healthy: false }),
   get_all_credential_health: () => [],
@@ -466,6 +467,8 @@ const defaultMocks: Record<string, any> = {
   get_pool_stats: () => ({ stats: {} }),
   get_tier_models: () => [],
   get_all_models: () => [],
+  get_model_performance_stats: () => [],
+  reset_model_performance_stats: () => ({ success: true }),
   update_orchestrator_credentials: () => ({ success: true }),
   add_orchestrator_credential: () => ({ success: true }),
   remove_orchestrator_credential: () => ({ success: true }),
@@ -524,6 +527,7 @@ const defaultMocks: Record<string, any> = {
   get_request_logs: () => ({ logs: [] }),
   get_request_log_detail: () => ({ log: null }),
   clear_request_logs: () => ({ success: true }),
+  get_circuit_transitions: () => [],
   get_stats_summary: () => ({ summary: {} }),
   get_stats_by_provider: () => ({ stats: [] }),
   get_stats_by_model: () => ({ stats: [] }),
@@ -570,6 +574,26 @@ const defaultMocks: Record<string, any> = {
   update_failover_config: () => ({ success: true }),
   get_switch_log: () => ({ logs: [] }),
   clear_switch_log: () => ({ success: true }),
+  get_circuit_breaker_status: () => ({
+    running: false,
+    enabled: true,
+    circuits: [],
+    recent_transitions: [],
+  }),
+  reset_circuit_breaker: () => ({ success: true }),
+  get_concurrency_metrics: () => ({
+    enabled: false,
+    in_flight: 0,
+    queue_depth: 0,
+    queue_depth_by_priority: {},
+    queue_depth_by_credential: {},
+    in_flight_by_credential: {},
+    in_flight_by_provider: {},
+    total_queued: 0,
+    total_timeouts: 0,
+    total_rejected: 0,
+    last_wait_ms: 0,
+  }),
 
   // Machine ID 相关
   get_current_machine_id: () => ({ machine_id: "" }),
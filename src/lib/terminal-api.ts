@@ -13,6 +13,7 @@
  * - 发送输入到终端
  * - 调整终端大小
  * - 监听终端输出和状态事件
+ * - 管理 SSH 端口转发
  *
  * ## 使用示例
  * ```typescript
@@ -77,12 +78,51 @@ export interface TerminalStatusEvent {
   error?: string;
 }
 
+/** SSH 端口转发配置 */
+export type ForwardSpec =
+  | {
+      type: "local" | "remote";
+      bind_address: string;
+      bind_port: number;
+      host: string;
+      host_port: number;
+    }
+  | { type: "dynamic"; bind_address: string; bind_port: number };
+
+/** SSH 端口转发状态 */
+export type ForwardStatus = "active" | "error" | "stopped";
+
+/** SSH 端口转发信息 */
+export interface ForwardInfo {
+  /** 转发 ID */
+  id: string;
+  /** 转发配置 */
+  spec: ForwardSpec;
+  /** 转发状态 */
+  status: ForwardStatus;
+  /** 最近一次错误 */
+  error: string | null;
+  /** 实际监听端口 */
+  bound_port: number | null;
+  /** 当前活跃隧道数 */
+  active_channels: number;
+}
+
+/** SSH 端口转发事件 */
+export interface SshForwardEvent {
+  /** 连接名称 */
+  connection: string;
+  /** 转发详情 */
+  forward: ForwardInfo;
+}
+
 // ============================================================================
 // 事件名称
 // ============================================================================
 
 export const TERMINAL_OUTPUT_EVENT = "terminal:output";
 export const TERMINAL_STATUS_EVENT = "terminal:status";
+export const SSH_FORWARD_EVENT = "terminal:ssh-forward";
 
 // ============================================================================
 // API 函数
@@ -194,10 +234,71 @@ export async function getTerminalSession(
   });
 }
 
+/**
+ * 在 SSH 连接上开启端口转发
+ *
+ * @param connection - 连接名称（配置名、Host 别名或 user@host:port）
+ * @param spec - 转发配置
+ * @returns 转发信息
+ */
+export async function addSshForward(
+  connection: string,
+  spec: ForwardSpec,
+): Promise<ForwardInfo> {
+  return safeInvoke<ForwardInfo>("terminal_ssh_add_forward", {
+    connection,
+    spec,
+  });
+}
+
+/**
+ * 停止 SSH 端口转发
+ *
+ * @param connection - 连接名称
+ * @param forwardId - 转发 ID
+ */
+export async function removeSshForward(
+  connection: string,
+  forwardId: string,
+): Promise<void> {
+  await safeInvoke("terminal_ssh_remove_forward", {
+    connection,
+    forwardId,
+  });
+}
+
+/**
+ * 获取 SSH 连接上的端口转发
+ *
+ * @param connection - 连接名称
+ * @returns 转发列表，连接未建立时为空
+ */
+export async function listSshForwards(
+  connection: string,
+): Promise<ForwardInfo[]> {
+  return safeInvoke<ForwardInfo[]>("terminal_ssh_list_forwards", {
+    connection,
+  });
+}
+
 // ============================================================================
 // 事件监听
 // ============================================================================
 
+/**
+ * 监听 SSH 端口转发状态事件
+ *
+ * @param callback - 回调函数，接收转发事件
+ * @returns 取消监听函数
+ */
+export async function onSshForward(
+  callback: (event: SshForwardEvent) => void,
+): Promise<UnlistenFn> {
+  return safeListen<SshForwardEvent>(SSH_FORWARD_EVENT, (event) => {
+    callback(event.payload);
+  });
+}
+
 /**
  * 监听终端输出事件
  *